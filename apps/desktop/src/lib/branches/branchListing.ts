@@ -53,6 +53,18 @@ export type StackReference = {
 	branches: string[];
 	/** Pull Request numbes by branch name associated with the stack */
 	pullRequests: Record<string, number>;
+	/** The description of the stack, set by the user */
+	description?: string | undefined;
+	/** Notes about the commits of the stack, set by the user */
+	commitNotes: CommitNote[];
+};
+
+/** A note about a single commit of a stack */
+export type CommitNote = {
+	/** The id of the commit the note is about */
+	commitId: string;
+	/** The text of the note */
+	text: string;
 };
 
 /** Represents a "commit author" or "signature", based on the data from there git history */
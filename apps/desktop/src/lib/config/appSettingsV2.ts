@@ -65,6 +65,32 @@ export class SettingsService {
 		await this.invokeAndRefresh('update_fetch', { update });
 	}
 
+	async updateDiff(update: Partial<Settings.AppSettings['diff']>) {
+		await this.invokeAndRefresh('update_diff', { update });
+	}
+
+	async updateWhitespaceOnlyHunks(
+		update: Partial<Settings.AppSettings['whitespaceOnlyHunks']>
+	) {
+		await this.invokeAndRefresh('update_whitespace_only_hunks', { update });
+	}
+
+	async updateOplog(update: Partial<Settings.AppSettings['oplog']>) {
+		await this.invokeAndRefresh('update_oplog', { update });
+	}
+
+	async updateMaintenance(update: Partial<Settings.AppSettings['maintenance']>) {
+		await this.invokeAndRefresh('update_maintenance', { update });
+	}
+
+	async updateOperationTrace(update: Partial<Settings.AppSettings['operationTrace']>) {
+		await this.invokeAndRefresh('update_operation_trace', { update });
+	}
+
+	async updateAi(update: Partial<Settings.AppSettings['ai']>) {
+		await this.invokeAndRefresh('update_ai', { update });
+	}
+
 	async updateUi(update: Partial<Settings.AppSettings['ui']>) {
 		await this.invokeAndRefresh('update_ui', { update });
 	}
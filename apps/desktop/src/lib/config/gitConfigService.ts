@@ -93,6 +93,8 @@ export class GbConfig {
 	signCommits?: boolean | undefined;
 	gitbutlerGerritMode?: boolean | undefined;
 	gitbutlerForgeReviewTemplatePath?: string | null;
+	gitbutlerRunHooks?: boolean | undefined;
+	gitbutlerProtectedBranches?: string | null;
 	signingKey?: string | undefined;
 	signingFormat?: string | undefined;
 	gpgProgram?: string | undefined;
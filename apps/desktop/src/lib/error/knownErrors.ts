@@ -8,7 +8,9 @@ export enum Code {
 	ProjectMissing = 'errors.projects.missing',
 	SecretKeychainNotFound = 'errors.secret.keychain_notfound',
 	MissingLoginKeychain = 'errors.secret.missing_login_keychain',
-	GitHubTokenExpired = 'errors.github.expired_token'
+	GitHubTokenExpired = 'errors.github.expired_token',
+	BranchProtected = 'errors.branch.protected',
+	ShallowHistory = 'errors.git.shallow_history'
 }
 
 export const KNOWN_ERRORS: Record<string, string> = {
@@ -31,6 +33,13 @@ This can be done using \`sudo apt install gnome-keyring\` for instance.
 Missing default keychain.
 
 With \`seahorse\` or equivalent, create a \`Login\` password store, right click it and choose \`Set Default\`.
+	`,
+	[Code.ShallowHistory]: `
+This repository is a shallow clone and lacks the history needed for this operation. Fetch more of it using:
+
+    git fetch --deepen=100
+
+or fetch the complete history with \`git fetch --unshallow\`.
 	`,
 	[Code.GitHubTokenExpired]: `
 Your GitHub token appears expired. Please log out and back in to refresh it. (Settings -> Integrations -> Forget) 
@@ -3,6 +3,14 @@ import { InjectionToken } from '@gitbutler/core/context';
 import type { IBackend } from '$lib/backend';
 import type { BackendApi } from '$lib/state/clientState.svelte';
 
+/** A change that a mutating operation made to the workspace. */
+export type WorkspaceEvent =
+	| { kind: 'stackUpdated'; stackId: string }
+	| { kind: 'assignmentsChanged' }
+	| { kind: 'baseMoved'; targetSha: string }
+	| { kind: 'stackDetached'; stackId: string; branchName: string }
+	| { kind: 'snapshotCreated'; snapshotId: string };
+
 export const GIT_SERVICE = new InjectionToken<GitService>('GitService');
 
 export class GitService {
@@ -24,6 +32,40 @@ export class GitService {
 		return this.backend.listen<any>(`project://${projectId}/git/fetch`, callback);
 	}
 
+	/**
+	 * Listens for the backend finding new commits on the target branch when fetching in the background.
+	 */
+	onBaseBranchMoved(projectId: string, callback: (payload: { newCommits: number }) => void) {
+		return this.backend.listen<{ newCommits: number }>(
+			`project://${projectId}/git/base_branch_moved`,
+			(event) => callback(event.payload)
+		);
+	}
+
+	/**
+	 * Listens for commits being made on top of the workspace commit with Git directly, like with `git commit`.
+	 */
+	onExternalCommits(projectId: string, callback: (payload: { commitIds: string[] }) => void) {
+		return this.backend.listen<{ commitIds: string[] }>(
+			`project://${projectId}/git/external_commits`,
+			(event) => callback(event.payload)
+		);
+	}
+
+	/**
+	 * Listens for changes that mutating operations made to the workspace, to invalidate data without polling.
+	 */
+	onWorkspaceEvent(projectId: string, callback: (event: WorkspaceEvent) => void) {
+		return this.backend.listen<WorkspaceEvent>(
+			`project://${projectId}/workspace_event`,
+			(event) => callback(event.payload)
+		);
+	}
+
+	async adoptExternalCommits(projectId: string, stackId?: string): Promise<void> {
+		await this.backend.invoke('adopt_external_commits', { projectId, stackId });
+	}
+
 	async checkSigningSettings(projectId: string): Promise<void> {
 		return await this.backend.invoke('check_signing_settings', { projectId });
 	}
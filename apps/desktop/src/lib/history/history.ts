@@ -4,6 +4,7 @@ import { createEntityAdapter, type EntityState } from '@reduxjs/toolkit';
 import { plainToInstance } from 'class-transformer';
 import { get, writable } from 'svelte/store';
 import type { IBackend } from '$lib/backend';
+import type { SnapshotDelta, StackDiff } from '$lib/history/types';
 import type { TreeChange } from '$lib/hunks/change';
 import type { BackendApi, ClientState } from '$lib/state/clientState.svelte';
 
@@ -78,6 +79,13 @@ type SnapshotDiffParams = {
 	snapshotId: string;
 };
 
+type StackDiffParams = {
+	projectId: string;
+	fromSnapshotId: string;
+	/** Compare with the current workspace if unset. */
+	toSnapshotId?: string;
+};
+
 export class HistoryService {
 	private api: ReturnType<typeof injectEndpoints>;
 
@@ -120,10 +128,22 @@ export class HistoryService {
 		);
 	}
 
+	stackDiff(params: StackDiffParams) {
+		return this.api.endpoints.stackDiff.useQuery(params);
+	}
+
+	snapshotDelta(params: StackDiffParams) {
+		return this.api.endpoints.snapshotDelta.useQuery(params);
+	}
+
 	async restoreSnapshot(projectId: string, sha: string) {
 		await this.api.endpoints.restoreSnapshot.mutate({ projectId, sha });
 	}
 
+	async restoreStack(projectId: string, sha: string, stackId: string) {
+		await this.api.endpoints.restoreStack.mutate({ projectId, sha, stackId });
+	}
+
 	async createSnapshot(projectId: string, message?: string) {
 		await this.backend.invoke<string>('create_snapshot', {
 			projectId,
@@ -132,6 +152,11 @@ export class HistoryService {
 		// Refresh snapshots list after creating
 		this.snapshots(projectId).load();
 	}
+
+	async createNamedSnapshot(projectId: string, label: string) {
+		await this.backend.invoke<string>('create_named_snapshot', { projectId, label: label.trim() });
+		this.snapshots(projectId).load();
+	}
 }
 
 export function createdOnDay(d: Date) {
@@ -149,9 +174,29 @@ function injectEndpoints(api: ClientState['backendApi']) {
 					return snapshotDiffAdapter.addMany(snapshotDiffAdapter.getInitialState(), data);
 				}
 			}),
+			stackDiff: build.query<StackDiff, StackDiffParams>({
+				extraOptions: { command: 'stack_diff' },
+				query: ({ projectId, fromSnapshotId, toSnapshotId }) => ({
+					projectId,
+					fromSha: fromSnapshotId,
+					toSha: toSnapshotId
+				})
+			}),
+			snapshotDelta: build.query<SnapshotDelta, StackDiffParams>({
+				extraOptions: { command: 'snapshot_delta' },
+				query: ({ projectId, fromSnapshotId, toSnapshotId }) => ({
+					projectId,
+					fromSha: fromSnapshotId,
+					toSha: toSnapshotId
+				})
+			}),
 			restoreSnapshot: build.mutation<void, { projectId: string; sha: string }>({
 				extraOptions: { command: 'restore_snapshot' },
 				query: (args) => args
+			}),
+			restoreStack: build.mutation<void, { projectId: string; sha: string; stackId: string }>({
+				extraOptions: { command: 'restore_stack' },
+				query: (args) => args
 			})
 		})
 	});
@@ -1,4 +1,5 @@
 import { Transform, Type } from 'class-transformer';
+import type { TreeChange } from '$lib/hunks/change';
 
 export type Operation =
 	| 'CreateCommit'
@@ -42,7 +43,19 @@ export type Operation =
 	| 'AutoHandleChangesBefore'
 	| 'AutoHandleChangesAfter'
 	| 'SplitBranch'
-	| 'OnDemandSnapshot';
+	| 'OnDemandSnapshot'
+	| 'ApplyReviewSuggestions'
+	| 'AutosquashStack'
+	| 'RevertCommit'
+	| 'UpdateStackIdentity'
+	| 'ExternalChange'
+	| 'RepairWorkspace'
+	| 'AdoptExternalCommits'
+	| 'ArchiveBranch'
+	| 'ApplyPatchSeries';
+
+/** Why a snapshot was taken without GitButler performing an operation. */
+export type SnapshotReason = 'headMoved' | 'workspaceRefMoved';
 
 export class Trailer {
 	key!: string;
@@ -53,6 +66,10 @@ export class SnapshotDetails {
 	title!: string;
 	operation!: Operation;
 	body?: string | undefined;
+	/** The name the user gave to the snapshot, if any. */
+	label?: string | undefined;
+	/** Why the snapshot was taken automatically, if it wasn't for an operation of GitButler. */
+	reason?: SnapshotReason | undefined;
 	@Type(() => Trailer)
 	trailers!: Trailer[];
 }
@@ -64,3 +81,57 @@ export class Snapshot {
 	@Transform((obj) => new Date(obj.value * 1000))
 	createdAt!: Date;
 }
+
+export type StackRef = {
+	stackId: string;
+	name: string;
+};
+
+export type BranchRef = {
+	stackId: string;
+	name: string;
+};
+
+export type RefMove = {
+	branch: BranchRef;
+	from: string;
+	to: string;
+};
+
+export type CommitRef = {
+	stackId: string;
+	branch: string | null;
+	commitId: string;
+	title: string;
+};
+
+export type RewrittenCommit = {
+	old: CommitRef;
+	new: CommitRef;
+	changeId: string;
+};
+
+export type AssignmentMove = {
+	path: string;
+	hunkHeader: string | null;
+	from: string | null;
+	to: string | null;
+};
+
+/** What changed in the workspace structure between two snapshots. */
+export type StackDiff = {
+	stacksAdded: StackRef[];
+	stacksRemoved: StackRef[];
+	branchesAdded: BranchRef[];
+	branchesRemoved: BranchRef[];
+	refsMoved: RefMove[];
+	commitsAdded: CommitRef[];
+	commitsRemoved: CommitRef[];
+	commitsRewritten: RewrittenCommit[];
+	assignmentsMoved: AssignmentMove[];
+};
+
+/** Everything that changed between two snapshots, including the files in the worktree. */
+export type SnapshotDelta = StackDiff & {
+	worktreeChanges: TreeChange[];
+};
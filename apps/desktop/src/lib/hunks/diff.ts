@@ -8,7 +8,33 @@ import type { Patch } from '$lib/hunks/hunk';
 export type UnifiedDiff =
 	| { readonly type: 'Binary' } // A binary file that can't be diffed.
 	| { readonly type: 'TooLarge'; readonly subject: TooLarge }
-	| { readonly type: 'Patch'; readonly subject: Patch };
+	| { readonly type: 'Patch'; readonly subject: Patch }
+	| { readonly type: 'Submodule'; readonly subject: Submodule }
+	| { readonly type: 'LfsPointer'; readonly subject: LfsPointerChange };
+
+/** A submodule whose pointer changed. */
+type Submodule = {
+	/** The commit the submodule pointed to before, or `null` if it was added. */
+	readonly previousId: string | null;
+	/** The commit the submodule points to now, or `null` if it was removed. */
+	readonly id: string | null;
+};
+
+/** A pointer Git stores instead of the content of a file tracked by Git LFS. */
+type LfsPointer = {
+	/** The SHA-256 of the actual content. */
+	readonly oid: string;
+	/** The size of the actual content in bytes. */
+	readonly size: number;
+};
+
+/** A file tracked by Git LFS whose pointer changed. */
+type LfsPointerChange = {
+	/** The pointer before, or `null` if the file was added. */
+	readonly previous: LfsPointer | null;
+	/** The pointer now, or `null` if the file was deleted. */
+	readonly current: LfsPointer | null;
+};
 
 /** The file was too large and couldn't be diffed. */
 type TooLarge = {
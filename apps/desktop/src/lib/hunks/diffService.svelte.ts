@@ -59,7 +59,10 @@ export class DiffService {
 function injectEndpoints(api: ClientState['backendApi']) {
 	return api.injectEndpoints({
 		endpoints: (build) => ({
-			getDiff: build.query<UnifiedDiff | null, { projectId: string; change: TreeChange }>({
+			getDiff: build.query<
+				UnifiedDiff | null,
+				{ projectId: string; change: TreeChange; wordDiff?: boolean }
+			>({
 				extraOptions: { command: 'tree_change_diffs' },
 				query: (args) => args,
 				providesTags: [providesList(ReduxTag.Diff)]
@@ -7,6 +7,7 @@ import {
 	type LineLock
 } from '@gitbutler/ui/utils/diffParsing';
 import type { HunkLock, HunkLocks } from '$lib/dependencies/dependencies';
+import type { CoreUI } from '@gitbutler/core/api';
 import type { Prettify } from '@gitbutler/shared/utils/typeUtils';
 import 'reflect-metadata';
 
@@ -17,6 +18,8 @@ export type DiffSpec = {
 	readonly pathBytes: number[];
 	/** The headers of the hunks to use, or empty if all changes are to be used. */
 	readonly hunkHeaders: HunkHeader[];
+	/** The mode transition to commit along with the hunks, like the executable bit being added. */
+	readonly modeFlags?: CoreUI.ModeFlags | null;
 };
 
 export type HunkHeader = {
@@ -74,6 +77,8 @@ export type HunkAssignment = {
 	readonly lineNumsAdded: number[] | null;
 	/** The line numbers that were removed in this hunk. The "before" or "old" line numbers.*/
 	readonly lineNumsRemoved: number[] | null;
+	/** The mode transition of the file, like the executable bit being added, which is committed along with this hunk. */
+	readonly modeFlags?: CoreUI.ModeFlags | null;
 };
 
 /**
@@ -307,9 +312,25 @@ export type DiffHunk = Prettify<
 		 * Note that the file-portion of the header isn't used here.
 		 */
 		readonly diff: string;
+		/**
+		 * The encoding `diff` was decoded from for display, like `Shift_JIS`, if it wasn't UTF-8.
+		 */
+		readonly encoding?: string;
+		/**
+		 * The parts of each line in `diff` after the header that changed within the line, if requested.
+		 */
+		readonly wordDiff?: WordSpan[][];
 	}
 >;
 
+/**
+ * A part of a line of a `DiffHunk` that changed within the line, with offsets into the line without its `+` or `-` prefix.
+ */
+export type WordSpan = {
+	readonly start: number;
+	readonly end: number;
+};
+
 export function isDiffHunk(something: unknown): something is DiffHunk {
 	return (
 		typeof something === 'object' &&
@@ -91,7 +91,7 @@ export class ModeService {
 function injectEndpoints(api: ClientState['backendApi']) {
 	return api.injectEndpoints({
 		endpoints: (build) => ({
-			enterEditMode: build.mutation<void, { projectId: string; commitId: string; stackId: string }>(
+			enterEditMode: build.mutation<void, { projectId: string; commitId: string; stackId?: string }>(
 				{
 					extraOptions: { command: 'enter_edit_mode' },
 					query: (args) => args,
@@ -84,9 +84,6 @@ export type AddProjectOutcome =
 	| {
 			type: 'bareRepository';
 	  }
-	| {
-			type: 'nonMainWorktree';
-	  }
 	| {
 			type: 'noWorkdir';
 	  }
@@ -151,13 +148,6 @@ export function handleAddProjectOutcome(
 				message: 'The specified path appears to be a bare Git repository and cannot be added.'
 			});
 			return true;
-		case 'nonMainWorktree':
-			showToast({
-				style: 'warning',
-				title: 'Non-main worktree',
-				message: 'The specified path is not the main worktree of the repository.'
-			});
-			return true;
 		case 'noWorkdir':
 			showToast({
 				style: 'warning',
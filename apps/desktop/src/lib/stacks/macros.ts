@@ -65,7 +65,8 @@ export default class StackMacros {
 			stackBranchName: branchName,
 			parentId: undefined,
 			message: message ?? STUB_COMMIT_MESSAGE,
-			worktreeChanges
+			worktreeChanges,
+			runHooks: false
 		});
 
 		if (outcome.pathsToRejectedChanges.length > 0) {
@@ -61,6 +61,8 @@ export type CreateCommitRequest = {
 	parentId: string | undefined;
 	stackBranchName: string;
 	worktreeChanges: DiffSpec[];
+	/** Undefined means that the backend runs the Git hooks unless they are disabled with `gitbutler.runHooks` */
+	runHooks?: boolean;
 };
 
 export type CreateCommitRequestWorktreeChanges = DiffSpec;
@@ -110,7 +112,7 @@ on fetching and pushing for ways to resolve the problem.
 	}
 }
 
-export type SeriesIntegrationStrategy = 'merge' | 'rebase';
+export type SeriesIntegrationStrategy = 'merge' | 'rebase' | 'hardreset';
 
 export interface BranchPushResult {
 	/**
@@ -138,7 +140,9 @@ export const REJECTTION_REASONS = [
 	'pathNotFoundInBaseTree',
 	'unsupportedDirectoryEntry',
 	'unsupportedTreeEntry',
-	'missingDiffSpecAssociation'
+	'missingDiffSpecAssociation',
+	'outsideSparseCheckout',
+	'markedUnchanged'
 ] as const;
 
 export type RejectionReason = (typeof REJECTTION_REASONS)[number];
@@ -1113,6 +1117,7 @@ function injectEndpoints(api: ClientState['backendApi'], uiState: UiState) {
 					branch: string;
 					runHooks: boolean;
 					pushOpts: GerritPushFlag[];
+					pushOptions: string[];
 				}
 			>({
 				extraOptions: {
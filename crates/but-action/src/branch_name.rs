@@ -0,0 +1,139 @@
+//! Suggest branch names for changes before they are committed.
+use std::collections::BTreeMap;
+
+use but_core::DiffSpec;
+use but_ctx::Context;
+use but_hunk_assignment::HunkAssignment;
+use gitbutler_stack::VirtualBranchesHandle;
+
+use crate::{
+    commit_message::{CommitMessageProvider, LlmCommitMessageProvider},
+    grouping::{changed_symbols, name_from_paths, slugify},
+};
+
+/// Branch names longer than this are cut at a word boundary.
+const MAX_NAME_LEN: usize = 50;
+
+/// Suggest a descriptive branch name for the worktree `changes`, which no branch or remote branch has yet.
+///
+/// If `use_ai` is `true`, the provider configured for commit messages derives the name from the subject
+/// it would use to commit the changes. Otherwise, the name is derived from the paths of the changes and
+/// the identifier changed most often.
+/// Either way, the initials of the project's branch name template are kept in front of it.
+pub fn suggest_branch_name(
+    ctx: &mut Context,
+    changes: Vec<DiffSpec>,
+    use_ai: bool,
+) -> anyhow::Result<String> {
+    let hunks = {
+        let guard = ctx.shared_worktree_access();
+        let repo = ctx.repo.get()?.clone();
+        let (_, workspace) = ctx.workspace_and_read_only_meta_from_head(guard.read_permission())?;
+        let (assignments, _assignments_error) = but_hunk_assignment::assignments_with_fallback(
+            ctx,
+            &repo,
+            &workspace,
+            false,
+            None::<Vec<but_core::TreeChange>>,
+            None,
+        )?;
+        assignments
+            .into_iter()
+            .filter(|assignment| is_selected(assignment, &changes))
+            .collect::<Vec<_>>()
+    };
+
+    let mut name = None;
+    if use_ai && !hunks.is_empty() {
+        let git_config = gix::config::File::from_globals()?;
+        let provider = LlmCommitMessageProvider::from_settings(&ctx.settings().ai, &git_config)?;
+        name = name_with_provider(&provider, &hunks)?;
+    }
+    let name = name.or_else(|| name_from_hunks(&hunks));
+
+    let template = gitbutler_stack::canned_branch_name(&*ctx.git2_repo.get()?)?;
+    let name = match name {
+        Some(name) => {
+            let initials = template.strip_suffix("branch-1").unwrap_or_default();
+            gitbutler_reference::normalize_branch_name(&format!("{initials}{name}"))?
+        }
+        None => template,
+    };
+    let state = VirtualBranchesHandle::new(ctx.project_data_dir());
+    let repo = ctx.repo.get()?;
+    gitbutler_stack::Stack::next_available_name(&repo, &state, name, false)
+}
+
+/// Return `true` if `assignment` is one of the hunks of `changes`, or in a file that is selected as a whole.
+fn is_selected(assignment: &HunkAssignment, changes: &[DiffSpec]) -> bool {
+    changes.iter().any(|change| {
+        change.path == assignment.path_bytes
+            && (change.hunk_headers.is_empty()
+                || assignment
+                    .hunk_header
+                    .is_some_and(|header| change.hunk_headers.contains(&header)))
+    })
+}
+
+/// Ask `provider` for a commit message for `hunks` and turn its subject into a branch name.
+fn name_with_provider(
+    provider: &dyn CommitMessageProvider,
+    hunks: &[HunkAssignment],
+) -> anyhow::Result<Option<String>> {
+    let mut diff = String::new();
+    for hunk in hunks {
+        if let Some(hunk_diff) = &hunk.diff {
+            diff.push_str(&format!("--- a/{path}\n+++ b/{path}\n", path = hunk.path));
+            diff.push_str(&hunk_diff.to_string());
+        }
+    }
+    let prompt = format!(
+        "Keep the subject line short, it's also used as branch name.\n\nunified diff:\n```patch\n{diff}```\n"
+    );
+    Ok(provider
+        .suggestions(&prompt, 1)?
+        .first()
+        .and_then(|candidate| slugify(&candidate.subject))
+        .map(|name| shorten(&name)))
+}
+
+/// Derive a branch name from the paths `hunks` are in and the identifier most of them change.
+fn name_from_hunks(hunks: &[HunkAssignment]) -> Option<String> {
+    if hunks.is_empty() {
+        return None;
+    }
+    let mut paths = hunks
+        .iter()
+        .map(|hunk| hunk.path.clone())
+        .collect::<Vec<_>>();
+    paths.dedup();
+    let mut name = name_from_paths(&paths);
+
+    let mut hunks_per_symbol = BTreeMap::<String, usize>::new();
+    for hunk in hunks {
+        for symbol in changed_symbols(hunk) {
+            *hunks_per_symbol.entry(symbol).or_default() += 1;
+        }
+    }
+    if let Some(symbol) = hunks_per_symbol
+        .into_iter()
+        .filter(|(_, count)| *count > 1)
+        .max_by(|(a_symbol, a_count), (b_symbol, b_count)| {
+            a_count.cmp(b_count).then_with(|| b_symbol.cmp(a_symbol))
+        })
+        .and_then(|(symbol, _)| slugify(&symbol))
+        && symbol != name
+    {
+        name = format!("{name}-{symbol}");
+    }
+    Some(shorten(&name))
+}
+
+/// Cut `name` to [`MAX_NAME_LEN`] at the last word boundary before it.
+fn shorten(name: &str) -> String {
+    if name.len() <= MAX_NAME_LEN {
+        return name.to_owned();
+    }
+    let cut = name[..=MAX_NAME_LEN].rfind('-').unwrap_or(MAX_NAME_LEN);
+    name[..cut].to_owned()
+}
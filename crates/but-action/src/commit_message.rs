@@ -0,0 +1,331 @@
+//! Commit message suggestions for the changes assigned to a stack.
+//!
+//! Messages are generated by a [`CommitMessageProvider`], which usually is the LLM configured
+//! in the `ai` section of the application settings.
+use std::fmt::Write as _;
+
+use anyhow::Context as _;
+use but_core::{TreeChange, ref_metadata::StackId};
+use but_ctx::Context;
+use but_hunk_assignment::HunkAssignment;
+use but_llm::{ChatMessage, LLMProvider};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// The model to use if neither the settings nor the provider configure one.
+const DEFAULT_MODEL: &str = "gpt-5-mini";
+
+/// A commit message that could be offered to the user.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+#[schemars(deny_unknown_fields)]
+pub struct CommitMessageCandidate {
+    #[schemars(
+        description = "The first line of the commit message, in the imperative mood and no longer than 50 characters."
+    )]
+    pub subject: String,
+    #[schemars(
+        description = "The paragraphs following the subject, explaining why the change was needed, hard wrapped at 72 characters. May be empty."
+    )]
+    pub body: String,
+}
+
+impl CommitMessageCandidate {
+    /// Return the whole commit message, with the body separated from the subject by an empty line.
+    pub fn message(&self) -> String {
+        let subject = self.subject.trim();
+        let body = self.body.trim();
+        if body.is_empty() {
+            subject.to_owned()
+        } else {
+            format!("{subject}\n\n{body}")
+        }
+    }
+}
+
+/// Something that turns a description of changes into commit messages.
+pub trait CommitMessageProvider {
+    /// Return commit messages for the changes described by `prompt`, ideally `num_candidates` of them.
+    fn suggest(
+        &self,
+        prompt: &str,
+        num_candidates: usize,
+    ) -> anyhow::Result<Vec<CommitMessageCandidate>>;
+
+    /// Like [`suggest()`](Self::suggest), but drop candidates without a subject and return at most `num_candidates`,
+    /// as providers may not follow instructions.
+    fn suggestions(
+        &self,
+        prompt: &str,
+        num_candidates: usize,
+    ) -> anyhow::Result<Vec<CommitMessageCandidate>> {
+        let mut candidates = self.suggest(prompt, num_candidates)?;
+        candidates.retain(|candidate| !candidate.subject.trim().is_empty());
+        candidates.truncate(num_candidates);
+        Ok(candidates)
+    }
+}
+
+/// A [`CommitMessageProvider`] that asks `model` of `llm`.
+#[derive(Debug, Clone)]
+pub struct LlmCommitMessageProvider {
+    /// The LLM to ask.
+    pub llm: LLMProvider,
+    /// The model of `llm` to ask.
+    pub model: String,
+}
+
+impl LlmCommitMessageProvider {
+    /// Create the provider configured in `settings`, using `git_config` for what isn't configured there.
+    ///
+    /// Without a provider in either of them, OpenAI is used as it's available through GitButler.
+    pub fn from_settings(
+        settings: &but_settings::app_settings::Ai,
+        git_config: &gix::config::File<'static>,
+    ) -> anyhow::Result<Self> {
+        let non_empty = |value: &str| (!value.is_empty()).then(|| value.to_owned());
+        let llm = if settings.provider.is_empty() {
+            LLMProvider::from_git_config(git_config)
+                .or_else(LLMProvider::default_openai)
+                .context(
+                    "No AI credentials found. Configure them in GitButler settings or set the OPENAI_API_KEY environment variable.",
+                )?
+        } else {
+            LLMProvider::with_options(
+                &settings.provider,
+                non_empty(&settings.model),
+                non_empty(&settings.endpoint),
+            )
+            .with_context(|| {
+                format!(
+                    "The AI provider '{}' isn't supported or has no credentials",
+                    settings.provider
+                )
+            })?
+        };
+        let model = non_empty(&settings.model)
+            .or_else(|| llm.model())
+            .unwrap_or_else(|| DEFAULT_MODEL.to_owned());
+        Ok(LlmCommitMessageProvider { llm, model })
+    }
+}
+
+impl CommitMessageProvider for LlmCommitMessageProvider {
+    fn suggest(
+        &self,
+        prompt: &str,
+        num_candidates: usize,
+    ) -> anyhow::Result<Vec<CommitMessageCandidate>> {
+        let system_message =
+            "You are a version control assistant that helps with Git branch committing.";
+        let user_message = format!(
+            "Suggest {num_candidates} different commit message(s) for the changes below.\n\n{prompt}"
+        );
+        Ok(self
+            .llm
+            .structured_output::<StructuredOutput>(
+                system_message,
+                vec![ChatMessage::User(user_message)],
+                &self.model,
+            )?
+            .context("Failed to generate structured content for commit messages")?
+            .candidates)
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+#[schemars(deny_unknown_fields)]
+struct StructuredOutput {
+    candidates: Vec<CommitMessageCandidate>,
+}
+
+/// Suggest commit messages for the hunks of `diff` that are assigned to the stack with `stack_id`,
+/// using the provider configured in the settings of `ctx`.
+///
+/// `diff` are the worktree changes to consider, or `None` to compute them.
+/// Returns as many candidates as configured, or none if there is nothing assigned to the stack.
+pub fn commit_message_suggestion(
+    ctx: &mut Context,
+    stack_id: StackId,
+    diff: Option<Vec<TreeChange>>,
+) -> anyhow::Result<Vec<CommitMessageCandidate>> {
+    let git_config = gix::config::File::from_globals()?;
+    let provider = LlmCommitMessageProvider::from_settings(&ctx.settings().ai, &git_config)?;
+    let num_candidates = ctx.settings().ai.commit_message_candidates.max(1) as usize;
+    commit_message_suggestion_with(&provider, ctx, stack_id, diff, num_candidates)
+}
+
+/// Like [`commit_message_suggestion()`], but ask `provider` for `num_candidates` messages.
+pub fn commit_message_suggestion_with(
+    provider: &dyn CommitMessageProvider,
+    ctx: &mut Context,
+    stack_id: StackId,
+    diff: Option<Vec<TreeChange>>,
+    num_candidates: usize,
+) -> anyhow::Result<Vec<CommitMessageCandidate>> {
+    let assignments = {
+        let guard = ctx.shared_worktree_access();
+        let repo = ctx.repo.get()?.clone();
+        let (_, workspace) = ctx.workspace_and_read_only_meta_from_head(guard.read_permission())?;
+        let (assignments, _assignments_error) = but_hunk_assignment::assignments_with_fallback(
+            ctx, &repo, &workspace, false, diff, None,
+        )?;
+        assignments
+    };
+    suggest_from_assignments(provider, &assignments, stack_id, num_candidates)
+}
+
+/// Ask `provider` for `num_candidates` messages for the hunks of `assignments` that are assigned to `stack_id`.
+fn suggest_from_assignments(
+    provider: &dyn CommitMessageProvider,
+    assignments: &[HunkAssignment],
+    stack_id: StackId,
+    num_candidates: usize,
+) -> anyhow::Result<Vec<CommitMessageCandidate>> {
+    match prompt_from_assignments(assignments, stack_id) {
+        Some(prompt) => provider.suggestions(&prompt, num_candidates),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Build the prompt describing the hunks of `assignments` that are assigned to `stack_id`, ignoring all others,
+/// or return `None` if there is no such hunk.
+pub fn prompt_from_assignments(
+    assignments: &[HunkAssignment],
+    stack_id: StackId,
+) -> Option<String> {
+    let mut diff = String::new();
+    let mut previous_path = None;
+    for assignment in assignments
+        .iter()
+        .filter(|assignment| assignment.stack_id == Some(stack_id))
+    {
+        if previous_path != Some(&assignment.path_bytes) {
+            let path = &assignment.path;
+            writeln!(diff, "diff --git a/{path} b/{path}").ok();
+            previous_path = Some(&assignment.path_bytes);
+        }
+        match &assignment.diff {
+            Some(hunk) => {
+                diff.push_str(&hunk.to_string());
+                if !diff.ends_with('\n') {
+                    diff.push('\n');
+                }
+            }
+            None => diff.push_str("Binary files differ\n"),
+        }
+    }
+    if diff.is_empty() {
+        return None;
+    }
+    Some(format!(
+        r#"Each commit message should be a short subject line, followed by a short body explaining WHY the change was needed.
+
+- The subject line should be no more than 50 characters.
+- Use the imperative mood for the subject (e.g. "Add user authentication system" instead of "Adding user authentication system").
+- The lines of the body should be hard wrapped at 72 characters.
+
+unified diff:
+```patch
+{diff}```
+"#
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use super::*;
+
+    /// A provider that returns `candidates` no matter what, and remembers the prompts it was asked with.
+    struct FakeProvider {
+        candidates: Vec<CommitMessageCandidate>,
+        prompts: RefCell<Vec<String>>,
+    }
+
+    impl FakeProvider {
+        fn new(subjects: &[&str]) -> Self {
+            FakeProvider {
+                candidates: subjects
+                    .iter()
+                    .map(|subject| CommitMessageCandidate {
+                        subject: (*subject).to_owned(),
+                        body: "body".into(),
+                    })
+                    .collect(),
+                prompts: Default::default(),
+            }
+        }
+    }
+
+    impl CommitMessageProvider for FakeProvider {
+        fn suggest(
+            &self,
+            prompt: &str,
+            _num_candidates: usize,
+        ) -> anyhow::Result<Vec<CommitMessageCandidate>> {
+            self.prompts.borrow_mut().push(prompt.to_owned());
+            Ok(self.candidates.clone())
+        }
+    }
+
+    fn assignment(path: &str, diff: &str, stack_id: Option<StackId>) -> HunkAssignment {
+        HunkAssignment {
+            id: None,
+            hunk_header: None,
+            path: path.into(),
+            path_bytes: path.into(),
+            stack_id,
+            hunk_locks: None,
+            line_nums_added: None,
+            line_nums_removed: None,
+            diff: Some(diff.into()),
+            mode_flags: None,
+        }
+    }
+
+    #[test]
+    fn prompt_only_describes_hunks_of_the_stack() {
+        let (ours, theirs) = (StackId::generate(), StackId::generate());
+        let assignments = [
+            assignment("ours.txt", "@@ -1 +1 @@\n-old\n+ours\n", Some(ours)),
+            assignment("theirs.txt", "@@ -1 +1 @@\n-old\n+theirs\n", Some(theirs)),
+            assignment("unassigned.txt", "@@ -1 +1 @@\n-old\n+new\n", None),
+        ];
+
+        let prompt = prompt_from_assignments(&assignments, ours).expect("a hunk is assigned");
+        assert!(prompt.contains("diff --git a/ours.txt b/ours.txt\n@@ -1 +1 @@\n-old\n+ours\n"));
+        assert!(!prompt.contains("theirs"));
+        assert!(!prompt.contains("unassigned.txt"));
+
+        assert_eq!(prompt_from_assignments(&assignments[1..], ours), None);
+        assert_eq!(prompt_from_assignments(&[], ours), None);
+    }
+
+    #[test]
+    fn provider_is_not_asked_without_hunks() -> anyhow::Result<()> {
+        let provider = FakeProvider::new(&["Subject"]);
+        let stack_id = StackId::generate();
+        let assignments = [assignment("file", "@@ -1 +1 @@\n-a\n+b\n", None)];
+
+        let candidates = suggest_from_assignments(&provider, &assignments, stack_id, 3)?;
+        assert!(candidates.is_empty());
+        assert!(provider.prompts.borrow().is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn candidates_without_subject_are_dropped_and_limited() -> anyhow::Result<()> {
+        let provider = FakeProvider::new(&["", "First", "  ", "Second", "Third"]);
+        let stack_id = StackId::generate();
+        let assignments = [assignment("file", "@@ -1 +1 @@\n-a\n+b\n", Some(stack_id))];
+
+        let candidates = suggest_from_assignments(&provider, &assignments, stack_id, 2)?;
+        let subjects: Vec<_> = candidates.iter().map(|c| c.subject.as_str()).collect();
+        assert_eq!(subjects, ["First", "Second"]);
+        assert_eq!(provider.prompts.borrow().len(), 1);
+        Ok(())
+    }
+}
@@ -0,0 +1,431 @@
+//! Propose stacks for the unassigned changes in the worktree by clustering related hunks, and create them on request.
+//!
+//! Nothing here runs on its own: the proposals are computed only when asked for, and only turned into
+//! stacks once the user accepted them.
+use std::collections::{BTreeMap, BTreeSet};
+
+use anyhow::Context as _;
+use but_core::ref_metadata::StackId;
+use but_ctx::{Context, access::WorktreeWritePermission};
+use but_hunk_assignment::{HunkAssignment, HunkAssignmentRequest};
+use but_oxidize::OidExt as _;
+use gitbutler_branch::BranchCreateRequest;
+use gitbutler_oplog::{
+    OplogExt,
+    entry::{OperationKind, SnapshotDetails},
+};
+use gitbutler_stack::VirtualBranchesHandle;
+use serde::{Deserialize, Serialize};
+
+use crate::{commit_message::LlmCommitMessageProvider, default_target_setting_if_none};
+
+/// Identifiers that appear in this many hunks or more are too common to relate them.
+const MAX_HUNKS_PER_SYMBOL: usize = 4;
+/// Identifiers shorter than this are too unspecific to relate hunks.
+const MIN_SYMBOL_LEN: usize = 4;
+/// Keywords that are long enough to count as symbol, but say nothing about how hunks relate.
+const KEYWORDS: &[&str] = &[
+    "async",
+    "await",
+    "break",
+    "class",
+    "const",
+    "continue",
+    "default",
+    "else",
+    "enum",
+    "export",
+    "false",
+    "from",
+    "function",
+    "impl",
+    "import",
+    "interface",
+    "match",
+    "null",
+    "public",
+    "private",
+    "return",
+    "self",
+    "Self",
+    "static",
+    "struct",
+    "this",
+    "true",
+    "type",
+    "void",
+    "where",
+    "while",
+];
+
+/// A stack that could be created for a group of related hunks.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProposedStack {
+    /// The name of the branch to create for the stack, which is made unique when the proposal is accepted.
+    pub name: String,
+    /// The paths of the files the hunks are in, for display.
+    pub paths: Vec<String>,
+    /// The identifiers that are changed in more than one of the hunks, for display.
+    pub shared_symbols: Vec<String>,
+    /// The hunks to assign to the stack, all without a stack id.
+    pub hunks: Vec<HunkAssignmentRequest>,
+}
+
+/// What happened when proposed stacks were accepted.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AcceptOutcome {
+    /// The ids of the created stacks, in the order of the accepted proposals.
+    pub stack_ids: Vec<StackId>,
+    /// The hunks that couldn't be assigned as they are locked to commits of other stacks, and stay where they are.
+    pub rejected: Vec<HunkAssignmentRequest>,
+}
+
+/// Group all unassigned hunks of the worktree into stacks of related changes.
+///
+/// Hunks are related if they are in the same file, depend on the same commits, or change the same identifiers.
+/// If `use_ai` is `true`, the LLM configured for commit messages names the stacks, otherwise they are named
+/// after the files they change.
+pub fn propose_stacks(ctx: &mut Context, use_ai: bool) -> anyhow::Result<Vec<ProposedStack>> {
+    let assignments = {
+        let guard = ctx.shared_worktree_access();
+        let repo = ctx.repo.get()?.clone();
+        let (_, workspace) = ctx.workspace_and_read_only_meta_from_head(guard.read_permission())?;
+        let (assignments, _assignments_error) = but_hunk_assignment::assignments_with_fallback(
+            ctx,
+            &repo,
+            &workspace,
+            false,
+            None::<Vec<but_core::TreeChange>>,
+            None,
+        )?;
+        assignments
+    };
+    let unassigned = assignments
+        .into_iter()
+        .filter(|assignment| assignment.stack_id.is_none())
+        .collect::<Vec<_>>();
+    let groups = group_related(&unassigned);
+    let mut proposals = proposals_for_groups(&unassigned, &groups);
+
+    if use_ai && !proposals.is_empty() {
+        let git_config = gix::config::File::from_globals()?;
+        let provider = LlmCommitMessageProvider::from_settings(&ctx.settings().ai, &git_config)?;
+        let mut existing_names = Vec::new();
+        for (proposal, group) in proposals.iter_mut().zip(&groups) {
+            let diffs = group
+                .iter()
+                .filter_map(|idx| unassigned[*idx].diff.as_ref())
+                .map(|diff| diff.to_string())
+                .collect::<Vec<_>>();
+            let name = crate::generate::branch_name(&provider.llm, &[], &diffs, &existing_names)?;
+            proposal.name = slugify(&name).unwrap_or_else(|| proposal.name.clone());
+            existing_names.push(proposal.name.clone());
+        }
+    }
+    Ok(proposals)
+}
+
+/// Create a stack for each of `proposals` and assign their hunks to it.
+///
+/// This happens in one go: if anything fails, the workspace is restored to how it was before.
+pub fn accept_proposed_stacks(
+    ctx: &mut Context,
+    proposals: Vec<ProposedStack>,
+) -> anyhow::Result<AcceptOutcome> {
+    let mut guard = ctx.exclusive_worktree_access();
+    let perm = guard.write_permission();
+    let snapshot = ctx.create_snapshot(SnapshotDetails::new(OperationKind::CreateBranch), perm)?;
+    match accept_proposed_stacks_inner(ctx, proposals, perm) {
+        Ok(outcome) => Ok(outcome),
+        Err(err) => {
+            ctx.restore_snapshot(snapshot, perm).with_context(|| {
+                format!(
+                    "Failed to restore snapshot {} after failing to accept proposed stacks: {err:#}",
+                    snapshot.to_gix()
+                )
+            })?;
+            Err(err)
+        }
+    }
+}
+
+fn accept_proposed_stacks_inner(
+    ctx: &mut Context,
+    proposals: Vec<ProposedStack>,
+    perm: &mut WorktreeWritePermission,
+) -> anyhow::Result<AcceptOutcome> {
+    let vb_state = &VirtualBranchesHandle::new(ctx.project_data_dir());
+    default_target_setting_if_none(ctx, vb_state)?;
+
+    let mut stack_ids = Vec::new();
+    let mut requests = Vec::new();
+    for proposal in proposals {
+        let name = gitbutler_stack::Stack::next_available_name(
+            &*ctx.repo.get()?,
+            vb_state,
+            proposal.name,
+            false,
+        )?;
+        let create_req = BranchCreateRequest {
+            name: Some(name),
+            order: None,
+        };
+        let stack = gitbutler_branch_actions::create_virtual_branch(ctx, &create_req, perm)?;
+        requests.extend(
+            proposal
+                .hunks
+                .into_iter()
+                .map(|hunk| HunkAssignmentRequest {
+                    stack_id: Some(stack.id),
+                    ..hunk
+                }),
+        );
+        stack_ids.push(stack.id);
+    }
+
+    let repo = ctx.repo.get()?.clone();
+    let (_, workspace) = ctx.workspace_and_read_only_meta_from_head(perm.read_permission())?;
+    let rejected = but_hunk_assignment::assign(ctx, &repo, &workspace, requests, None)?
+        .into_iter()
+        .map(|rejection| rejection.request)
+        .collect();
+    Ok(AcceptOutcome {
+        stack_ids,
+        rejected,
+    })
+}
+
+/// Turn `groups` of indices into `unassigned` into one proposal each.
+fn proposals_for_groups(
+    unassigned: &[HunkAssignment],
+    groups: &[Vec<usize>],
+) -> Vec<ProposedStack> {
+    let symbols_per_hunk = unassigned.iter().map(changed_symbols).collect::<Vec<_>>();
+    groups
+        .iter()
+        .map(|group| {
+            let paths = group
+                .iter()
+                .map(|idx| unassigned[*idx].path.clone())
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .collect::<Vec<_>>();
+            let mut symbol_counts = BTreeMap::<&str, usize>::new();
+            for idx in group {
+                for symbol in &symbols_per_hunk[*idx] {
+                    *symbol_counts.entry(symbol).or_default() += 1;
+                }
+            }
+            let shared_symbols = symbol_counts
+                .into_iter()
+                .filter(|(_, count)| *count > 1)
+                .map(|(symbol, _)| symbol.to_owned())
+                .collect();
+            ProposedStack {
+                name: name_from_paths(&paths),
+                paths,
+                shared_symbols,
+                hunks: group
+                    .iter()
+                    .map(|idx| HunkAssignmentRequest {
+                        hunk_header: unassigned[*idx].hunk_header,
+                        path_bytes: unassigned[*idx].path_bytes.clone(),
+                        stack_id: None,
+                    })
+                    .collect(),
+            }
+        })
+        .collect()
+}
+
+/// Return the indices of `hunks` grouped by relatedness, in the order the first hunk of each group appears.
+fn group_related(hunks: &[HunkAssignment]) -> Vec<Vec<usize>> {
+    let mut parents = (0..hunks.len()).collect::<Vec<_>>();
+    fn root(parents: &mut [usize], mut idx: usize) -> usize {
+        while parents[idx] != idx {
+            parents[idx] = parents[parents[idx]];
+            idx = parents[idx];
+        }
+        idx
+    }
+    let mut union = |a: usize, b: usize| {
+        let (a, b) = (root(&mut parents, a), root(&mut parents, b));
+        parents[a.max(b)] = a.min(b);
+    };
+
+    let mut hunks_by_key = BTreeMap::<String, Vec<usize>>::new();
+    for (idx, hunk) in hunks.iter().enumerate() {
+        hunks_by_key
+            .entry(format!("path:{}", hunk.path))
+            .or_default()
+            .push(idx);
+        for lock in hunk.hunk_locks.iter().flatten() {
+            hunks_by_key
+                .entry(format!("lock:{}", lock.commit_id))
+                .or_default()
+                .push(idx);
+        }
+        for symbol in changed_symbols(hunk) {
+            hunks_by_key
+                .entry(format!("symbol:{symbol}"))
+                .or_default()
+                .push(idx);
+        }
+    }
+    for (key, related) in &hunks_by_key {
+        if key.starts_with("symbol:") && related.len() >= MAX_HUNKS_PER_SYMBOL {
+            continue;
+        }
+        for pair in related.windows(2) {
+            union(pair[0], pair[1]);
+        }
+    }
+
+    let mut groups = BTreeMap::<usize, Vec<usize>>::new();
+    for idx in 0..hunks.len() {
+        groups.entry(root(&mut parents, idx)).or_default().push(idx);
+    }
+    groups.into_values().collect()
+}
+
+/// Return the identifiers on the added and removed lines of `hunk`.
+pub(crate) fn changed_symbols(hunk: &HunkAssignment) -> BTreeSet<String> {
+    let Some(diff) = &hunk.diff else {
+        return BTreeSet::new();
+    };
+    diff.to_string()
+        .lines()
+        .filter(|line| line.starts_with('+') || line.starts_with('-'))
+        .flat_map(|line| line[1..].split(|c: char| !(c.is_alphanumeric() || c == '_')))
+        .filter(|word| {
+            word.len() >= MIN_SYMBOL_LEN
+                && word.starts_with(|c: char| c.is_alphabetic() || c == '_')
+                && !KEYWORDS.contains(word)
+        })
+        .map(ToOwned::to_owned)
+        .collect()
+}
+
+/// Name a stack after the directory all `paths` share, or after the stem of the first file if there is none.
+pub(crate) fn name_from_paths(paths: &[String]) -> String {
+    let mut common = paths
+        .first()
+        .map(|path| path.split('/').collect::<Vec<_>>())
+        .unwrap_or_default();
+    // The file name itself is never shared as directory.
+    common.pop();
+    for path in paths.iter().skip(1) {
+        let shared = common
+            .iter()
+            .zip(path.split('/'))
+            .take_while(|(a, b)| *a == b)
+            .count();
+        common.truncate(shared);
+    }
+    let name = match common.last() {
+        Some(dir) => (*dir).to_owned(),
+        None => paths
+            .first()
+            .and_then(|path| path.rsplit('/').next())
+            .map(|file| file.split('.').next().unwrap_or(file).to_owned())
+            .unwrap_or_default(),
+    };
+    slugify(&name).unwrap_or_else(|| "changes".into())
+}
+
+/// Make `name` usable as branch name, or return `None` if nothing usable is left of it.
+pub(crate) fn slugify(name: &str) -> Option<String> {
+    let slug = name
+        .to_lowercase()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+    (!slug.is_empty()).then_some(slug)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hunk(path: &str, diff: &str) -> HunkAssignment {
+        HunkAssignment {
+            id: None,
+            hunk_header: None,
+            path: path.into(),
+            path_bytes: path.into(),
+            stack_id: None,
+            hunk_locks: None,
+            line_nums_added: None,
+            line_nums_removed: None,
+            diff: Some(diff.into()),
+            mode_flags: None,
+        }
+    }
+
+    fn fixture() -> Vec<HunkAssignment> {
+        vec![
+            hunk(
+                "src/auth/login.rs",
+                "@@ -1 +1 @@\n+fn authenticate_user() {}\n",
+            ),
+            hunk("docs/guide.md", "@@ -1 +1 @@\n+Setup instructions\n"),
+            hunk(
+                "src/auth/session.rs",
+                "@@ -1 +1 @@\n+    authenticate_user();\n",
+            ),
+            hunk("src/db/pool.rs", "@@ -1 +1 @@\n+let connection_pool = 1;\n"),
+            hunk("src/auth/login.rs", "@@ -9 +9 @@\n-let stale_value = 2;\n"),
+            hunk("src/db/query.rs", "@@ -1 +1 @@\n+connection_pool.get();\n"),
+        ]
+    }
+
+    /// Return the name, paths and shared symbols of each proposal.
+    fn summarize(proposals: &[ProposedStack]) -> Vec<(String, Vec<String>, Vec<String>)> {
+        proposals
+            .iter()
+            .map(|p| (p.name.clone(), p.paths.clone(), p.shared_symbols.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn related_hunks_are_grouped_deterministically() {
+        let hunks = fixture();
+        let groups = group_related(&hunks);
+        assert_eq!(groups, [vec![0, 2, 4], vec![1], vec![3, 5]]);
+
+        let proposals = proposals_for_groups(&hunks, &groups);
+        let owned = |values: &[&str]| values.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            summarize(&proposals),
+            [
+                (
+                    "auth".to_owned(),
+                    owned(&["src/auth/login.rs", "src/auth/session.rs"]),
+                    owned(&["authenticate_user"])
+                ),
+                ("docs".to_owned(), owned(&["docs/guide.md"]), vec![]),
+                (
+                    "db".to_owned(),
+                    owned(&["src/db/pool.rs", "src/db/query.rs"]),
+                    owned(&["connection_pool"])
+                ),
+            ]
+        );
+        assert_eq!(proposals[0].hunks.len(), 3);
+
+        let mut reversed = hunks.clone();
+        reversed.reverse();
+        let mut reversed_summary =
+            summarize(&proposals_for_groups(&reversed, &group_related(&reversed)));
+        reversed_summary.sort();
+        let mut summary = summarize(&proposals);
+        summary.sort();
+        assert_eq!(
+            reversed_summary, summary,
+            "only the order of the groups follows the order of the hunks"
+        );
+    }
+}
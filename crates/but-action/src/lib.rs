@@ -20,9 +20,12 @@ use serde::{Deserialize, Serialize};
 mod action;
 mod auto_commit;
 mod branch_changes;
+pub mod branch_name;
 pub mod cli;
 pub mod commit_format;
+pub mod commit_message;
 mod generate;
+pub mod grouping;
 pub mod rename_branch;
 pub mod reword;
 mod simple;
@@ -171,7 +171,7 @@ fn handle_changes_simple_inner(
             .and_then(|s| s.heads.first().map(|h| h.name.to_string()))
             .ok_or(anyhow!("Could not find associated reference name"))?;
 
-        let outcome = but_workspace::legacy::commit_engine::create_commit_simple(
+        let outcome = gitbutler_branch_actions::hooks::create_commit_with_hooks(
             ctx,
             stack_id,
             None,
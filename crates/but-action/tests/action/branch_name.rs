@@ -0,0 +1,39 @@
+use but_action::branch_name::suggest_branch_name;
+use but_core::DiffSpec;
+
+use crate::Workspace;
+
+#[test]
+fn names_taken_locally_or_on_the_remote_are_suffixed() -> anyhow::Result<()> {
+    let Workspace { repo, mut ctx, .. } = Workspace::new()?;
+    std::fs::write(repo.path().join("feature.txt"), "content\n")?;
+    let changes = vec![DiffSpec {
+        previous_path: None,
+        path: "feature.txt".into(),
+        hunk_headers: vec![],
+        mode_flags: None,
+    }];
+
+    let name = suggest_branch_name(&mut ctx, changes.clone(), false)?;
+    assert!(name.ends_with("feature"), "named after the file: {name}");
+
+    let head = repo.local_repo.head()?.peel_to_commit()?.id();
+    repo.local_repo
+        .reference(&format!("refs/heads/{name}"), head, false, "taken locally")?;
+    assert_eq!(
+        suggest_branch_name(&mut ctx, changes.clone(), false)?,
+        format!("{name}-1")
+    );
+
+    repo.local_repo.reference(
+        &format!("refs/remotes/origin/{name}-1"),
+        head,
+        false,
+        "taken on the remote",
+    )?;
+    assert_eq!(
+        suggest_branch_name(&mut ctx, changes, false)?,
+        format!("{name}-2")
+    );
+    Ok(())
+}
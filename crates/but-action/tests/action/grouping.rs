@@ -0,0 +1,40 @@
+use but_action::grouping::{accept_proposed_stacks, propose_stacks};
+use gitbutler_stack::VirtualBranchesHandle;
+
+use crate::Workspace;
+
+#[test]
+fn failing_acceptance_leaves_no_stacks_behind() -> anyhow::Result<()> {
+    let Workspace { repo, mut ctx, .. } = Workspace::new()?;
+    let vb_state = VirtualBranchesHandle::new(ctx.project_data_dir());
+    let stacks_before = vb_state.list_stacks_in_workspace()?.len();
+
+    std::fs::write(repo.path().join("file.txt"), "content\n")?;
+    let proposals = propose_stacks(&mut ctx, false)?;
+    assert_eq!(proposals.len(), 1);
+
+    // The first stack can be created, but not the second one.
+    let mut invalid = proposals[0].clone();
+    invalid.name = "refs/heads/invalid".into();
+    accept_proposed_stacks(&mut ctx, vec![proposals[0].clone(), invalid])
+        .expect_err("the name of the second stack is invalid");
+    assert_eq!(
+        vb_state.list_stacks_in_workspace()?.len(),
+        stacks_before,
+        "the first stack was removed again"
+    );
+    assert_eq!(
+        propose_stacks(&mut ctx, false)?.len(),
+        1,
+        "the change is still unassigned"
+    );
+
+    let outcome = accept_proposed_stacks(&mut ctx, proposals)?;
+    assert_eq!(outcome.stack_ids.len(), 1);
+    assert!(outcome.rejected.is_empty());
+    assert_eq!(
+        vb_state.list_stacks_in_workspace()?.len(),
+        stacks_before + 1
+    );
+    Ok(())
+}
@@ -0,0 +1,34 @@
+use but_ctx::Context;
+use but_settings::AppSettings;
+use gitbutler_testsupport::{TestProject, paths};
+use tempfile::TempDir;
+
+mod branch_name;
+mod grouping;
+
+/// A repository with a remote, set up as workspace whose target is `origin/master`.
+struct Workspace {
+    repo: TestProject,
+    ctx: Context,
+    _data_dir: TempDir,
+}
+
+impl Workspace {
+    fn new() -> anyhow::Result<Self> {
+        let data_dir = paths::data_dir();
+        let repo = TestProject::default();
+        let project =
+            gitbutler_project::add_at_app_data_dir(data_dir.path(), repo.path())?.unwrap_project();
+        let ctx = Context::new_from_legacy_project_and_settings(&project, AppSettings::default());
+        gitbutler_branch_actions::set_base_branch(
+            &ctx,
+            &"refs/remotes/origin/master".parse()?,
+            ctx.exclusive_worktree_access().write_permission(),
+        )?;
+        Ok(Workspace {
+            repo,
+            ctx,
+            _data_dir: data_dir,
+        })
+    }
+}
@@ -18,6 +18,11 @@ use syn::{Expr, FnArg, ItemFn, Pat, parse_macro_input};
 /// * `try_from = JSONReturnType`
 ///     - Use it like `but_api(try_from = JSONReturnType)` where `JSONReturnType::try_from(actual_return_type)?` is implemented.
 ///     - Controls how the actual return value is fallibly converted for JSON serialization in `func_json` and `func_cmd`.
+/// * `mutating`
+///     - Use it like `but_api(mutating)` or `but_api(mutating, JSONReturnType)` for functions that change the project.
+///     - Records each call of `func_json` and `func_cmd` in the operation trace of the project, if it's enabled.
+///     - All JSON parameters must be serializable, and the function must return an `anyhow::Result`.
+///     - Only usable for functions whose generated functions need the `legacy` feature anyway.
 ///
 /// # Generated Functions
 ///
@@ -48,15 +53,22 @@ pub fn but_api(attr: TokenStream, item: TokenStream) -> TokenStream {
         syn::ReturnType::Default => panic!("function must return a type"),
     });
 
-    let opts = if attr.is_empty() {
-        Options::default()
-    } else {
-        let meta = syn::parse_macro_input!(attr as syn::Meta);
+    let metas = syn::parse_macro_input!(
+        attr with syn::punctuated::Punctuated::<syn::Meta, syn::Token![,]>::parse_terminated
+    );
+    let mut opts = Options::default();
+    for meta in metas {
+        if meta.path().is_ident("mutating") {
+            opts.mutating = true;
+            continue;
+        }
         match parse_attrs_to_options(meta, is_result_option) {
-            Ok(opts) => opts,
+            Ok(Options {
+                result_conversion, ..
+            }) => opts.result_conversion = result_conversion,
             Err(err) => return err.into_compile_error().into(),
         }
-    };
+    }
 
     let json_ty_by_name = match build_json_type_mapping(input.iter()) {
         Ok(m) => m,
@@ -215,6 +227,51 @@ pub fn but_api(attr: TokenStream, item: TokenStream) -> TokenStream {
         (quote! {}, return_type)
     };
 
+    // Calls of mutating functions are traced, which needs to see the parameters before they are converted.
+    let fn_name_str = fn_name.to_string();
+    let (trace_start_cmd, trace_start_json, call) = if opts.mutating {
+        let has_project_id = param_field_names.iter().any(|name| *name == "project_id");
+        let project_id = if has_project_id {
+            quote! { Some(project_id) }
+        } else {
+            quote! { None }
+        };
+        let input_names = param_field_names
+            .iter()
+            .map(|name| name.to_string().to_case(Case::Camel));
+        (
+            quote! {
+                let traced_operation = crate::operation_trace::Operation::start(
+                    #fn_name_str,
+                    params
+                        .get("projectId")
+                        .and_then(|id| ::serde_json::from_value(id.clone()).ok()),
+                    || params.clone(),
+                );
+            },
+            quote! {
+                let traced_operation = crate::operation_trace::Operation::start(#fn_name_str, #project_id, || {
+                    let mut trace_input = ::serde_json::Map::new();
+                    #(trace_input.insert(#input_names.into(), ::serde_json::to_value(&#param_field_names).unwrap_or_default());)*
+                    ::serde_json::Value::Object(trace_input)
+                });
+            },
+            quote! {
+                let result = #call_fn_args;
+                if let Some(traced_operation) = traced_operation {
+                    traced_operation.finish(&result);
+                }
+                let result = result?;
+            },
+        )
+    } else {
+        (
+            quote! {},
+            quote! {},
+            quote! { let result = #call_fn_args?; },
+        )
+    };
+
     let legacy_cfg_if_json_mapping_is_used = if !json_ty_by_name.is_empty() {
         quote! { #[cfg(feature = "legacy")] }
     } else {
@@ -244,9 +301,10 @@ pub fn but_api(attr: TokenStream, item: TokenStream) -> TokenStream {
         #vis #asyncness fn #fn_cmd_name(
             params: ::serde_json::Value,
         ) -> anyhow::Result<::serde_json::Value> {
+            #trace_start_cmd
             let #param_struct_name { #(#param_field_names),* } = ::serde_json::from_value(params)?;
             #(#param_conversions);*
-            let result = #call_fn_args?;
+            #call
             #convert_to_json_result_type
             Ok(::serde_json::to_value(result)?)
         }
@@ -257,8 +315,9 @@ pub fn but_api(attr: TokenStream, item: TokenStream) -> TokenStream {
         #vis #asyncness fn #fn_json_name(
             #(#json_fn_input_params),*
         ) -> Result<#json_ty, crate::json::Error> {
+            #trace_start_json
             #(#param_conversions);*
-            let result = #call_fn_args?;
+            #call
             #convert_to_json_result_type
             Ok(result)
         }
@@ -426,6 +485,8 @@ struct Options {
     /// It's `None` if the result type converts to JSON naturally.
     /// Otherwise, we convert to it.
     result_conversion: Option<ResultConversion>,
+    /// If `true`, the function changes the project and its calls are traced.
+    mutating: bool,
 }
 
 struct ResultConversion {
@@ -488,7 +549,10 @@ fn parse_attrs_to_options(meta: syn::Meta, is_result_option: bool) -> Result<Opt
             json_ty_rval: rval_ty,
         }
     });
-    Ok(Options { result_conversion })
+    Ok(Options {
+        result_conversion,
+        mutating: false,
+    })
 }
 
 /// Detect `Result<Option<` type
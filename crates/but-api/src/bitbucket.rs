@@ -0,0 +1,31 @@
+// TODO: everything should be fully documented.
+#![allow(missing_docs)]
+use anyhow::Result;
+use but_api_macros::but_api;
+use but_secret::Sensitive;
+use tracing::instrument;
+
+#[but_api]
+#[instrument(err(Debug))]
+pub async fn store_bitbucket_api_token(
+    username: String,
+    api_token: Sensitive<String>,
+) -> Result<but_bitbucket::BitbucketUser> {
+    let storage = but_forge_storage::Controller::from_path(but_path::app_data_dir()?);
+    but_bitbucket::store_api_token(&username, &api_token, &storage).await
+}
+
+#[but_api]
+#[instrument(err(Debug))]
+pub fn forget_bitbucket_account(account: but_bitbucket::BitbucketAccountIdentifier) -> Result<()> {
+    let storage = but_forge_storage::Controller::from_path(but_path::app_data_dir()?);
+    but_bitbucket::forget_bitbucket_account(&account, &storage).ok();
+    Ok(())
+}
+
+#[but_api]
+#[instrument(err(Debug))]
+pub fn list_known_bitbucket_accounts() -> Result<Vec<but_bitbucket::BitbucketAccountIdentifier>> {
+    let storage = but_forge_storage::Controller::from_path(but_path::app_data_dir()?);
+    but_bitbucket::list_known_bitbucket_accounts(&storage)
+}
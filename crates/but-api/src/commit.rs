@@ -1,12 +1,13 @@
-use std::collections::HashSet;
+use std::{borrow::Cow, collections::HashSet};
 
 use crate::json;
 use bstr::{BString, ByteSlice};
 use but_api_macros::but_api;
+use but_core::{RepositoryExt as _, ref_metadata::StackId};
 use but_graph::Graph;
 use but_hunk_assignment::HunkAssignmentRequest;
 use but_oplog::legacy::{OperationKind, SnapshotDetails};
-use but_rebase::graph_rebase::{GraphExt, LookupStep as _, mutate::InsertSide};
+use but_rebase::graph_rebase::{GraphExt, LookupStep as _, MaterializeOutcome, mutate::InsertSide};
 use but_workspace::commit::{move_changes_between_commits, uncommit_changes};
 use tracing::instrument;
 
@@ -21,14 +22,16 @@ pub fn commit_reword_only(
     message: BString,
 ) -> anyhow::Result<gix::ObjectId> {
     let guard = ctx.exclusive_worktree_access();
-    let (_, graph) = ctx.graph_and_read_only_meta_from_head(guard.read_permission())?;
+    let (_, workspace) = ctx.workspace_and_read_only_meta_from_head(guard.read_permission())?;
     let repo = ctx.repo.get()?;
-    let editor = graph.to_editor(&repo)?;
+    let repo = repo_with_stack_identity(ctx, &repo, stack_containing(&workspace, commit_id))?;
+    let editor = workspace.graph.to_editor(&repo)?;
 
     let (outcome, edited_commit_selector) =
         but_workspace::commit::reword(editor, commit_id, message.as_bstr())?;
 
     let outcome = outcome.materialize()?;
+    record_rewritten_commits(ctx, &outcome);
     let id = outcome.lookup_pick(edited_commit_selector)?;
 
     Ok(id)
@@ -108,9 +111,18 @@ pub fn commit_insert_blank_only(
     side: InsertSide,
 ) -> anyhow::Result<gix::ObjectId> {
     let guard = ctx.exclusive_worktree_access();
-    let (_, graph) = ctx.graph_and_read_only_meta_from_head(guard.read_permission())?;
+    let (_, workspace) = ctx.workspace_and_read_only_meta_from_head(guard.read_permission())?;
     let repo = ctx.repo.get()?;
-    commit_insert_blank_only_impl(&graph, &repo, relative_to, side)
+    let stack_id = match &relative_to {
+        ui::RelativeTo::Commit(commit_id) => stack_containing(&workspace, *commit_id),
+        ui::RelativeTo::Reference(name) => workspace
+            .find_segment_and_stack_by_refname(name.as_ref())
+            .and_then(|(stack, _)| stack.id),
+    };
+    let repo = repo_with_stack_identity(ctx, &repo, stack_id)?;
+    let (id, outcome) = insert_blank_commit(&workspace.graph, &repo, relative_to, side)?;
+    record_rewritten_commits(ctx, &outcome);
+    Ok(id)
 }
 
 /// Implementation of inserting a blank commit relative to either a commit or a reference
@@ -122,6 +134,15 @@ pub fn commit_insert_blank_only_impl(
     relative_to: ui::RelativeTo,
     side: InsertSide,
 ) -> anyhow::Result<gix::ObjectId> {
+    insert_blank_commit(graph, repo, relative_to, side).map(|(id, _)| id)
+}
+
+fn insert_blank_commit(
+    graph: &Graph,
+    repo: &gix::Repository,
+    relative_to: ui::RelativeTo,
+    side: InsertSide,
+) -> anyhow::Result<(gix::ObjectId, MaterializeOutcome)> {
     let editor = graph.to_editor(repo)?;
 
     let relative_to = (&relative_to).into();
@@ -132,7 +153,7 @@ pub fn commit_insert_blank_only_impl(
     let outcome = outcome.materialize()?;
     let id = outcome.lookup_pick(blank_commit_selector)?;
 
-    Ok(id)
+    Ok((id, outcome))
 }
 
 /// Inserts a blank commit relative to either a commit or a reference, with oplog support
@@ -174,9 +195,11 @@ pub fn commit_move_changes_between_only(
     changes: Vec<but_core::DiffSpec>,
 ) -> anyhow::Result<json::UIMoveChangesResult> {
     let guard = ctx.exclusive_worktree_access();
-    let (_, graph) = ctx.graph_and_read_only_meta_from_head(guard.read_permission())?;
+    let (_, workspace) = ctx.workspace_and_read_only_meta_from_head(guard.read_permission())?;
     let repo = ctx.repo.get()?;
-    let editor = graph.to_editor(&repo)?;
+    let stack_id = stack_containing(&workspace, destination_commit_id.into());
+    let repo = repo_with_stack_identity(ctx, &repo, stack_id)?;
+    let editor = workspace.graph.to_editor(&repo)?;
 
     let outcome = move_changes_between_commits(
         editor,
@@ -186,6 +209,7 @@ pub fn commit_move_changes_between_only(
         ctx.settings().context_lines,
     )?;
     let materialized = outcome.rebase.materialize()?;
+    record_rewritten_commits(ctx, &materialized);
     let new_source_commit_id = materialized.lookup_pick(outcome.source_selector)?;
     let new_destination_commit_id = materialized.lookup_pick(outcome.destination_selector)?;
 
@@ -239,12 +263,13 @@ pub fn commit_uncommit_changes_only(
     assign_to: Option<but_core::ref_metadata::StackId>,
 ) -> anyhow::Result<json::UIMoveChangesResult> {
     let guard = ctx.exclusive_worktree_access();
-    let (_, graph) = ctx.graph_and_read_only_meta_from_head(guard.read_permission())?;
+    let (_, workspace) = ctx.workspace_and_read_only_meta_from_head(guard.read_permission())?;
     // We need to pass a mutable ctx below, so we can't hold a reference to the repo
     let repo = ctx.repo.get()?.clone();
-    let editor = graph.to_editor(&repo)?;
-
-    let (_, workspace) = ctx.workspace_and_read_only_meta_from_head(guard.read_permission())?;
+    let stack_id = stack_containing(&workspace, commit_id.into());
+    let editor = workspace
+        .graph
+        .to_editor(&repo_with_stack_identity(ctx, &repo, stack_id)?)?;
 
     let before_assignments = if assign_to.is_some() {
         let (assignments, _) = but_hunk_assignment::assignments_with_fallback(
@@ -268,6 +293,7 @@ pub fn commit_uncommit_changes_only(
     )?;
 
     let materialized = outcome.rebase.materialize_without_checkout()?;
+    record_rewritten_commits(ctx, &materialized);
     let new_commit_id = materialized.lookup_pick(outcome.commit_selector)?;
 
     let workspace = ctx
@@ -334,3 +360,64 @@ pub fn commit_uncommit_changes(
 
     res
 }
+
+/// Return the id of the stack in `workspace` that contains `commit_id`.
+fn stack_containing(
+    workspace: &but_graph::projection::Workspace,
+    commit_id: gix::ObjectId,
+) -> Option<StackId> {
+    workspace
+        .find_owner_indexes_by_commit_id(commit_id)
+        .and_then(|(stack_idx, _, _)| workspace.stacks[stack_idx].id)
+}
+
+/// Return `repo` configured to author and commit as set in the identity of the stack with `stack_id`,
+/// or `repo` itself if there is no such override.
+fn repo_with_stack_identity<'repo>(
+    ctx: &but_ctx::Context,
+    repo: &'repo gix::Repository,
+    stack_id: Option<StackId>,
+) -> anyhow::Result<Cow<'repo, gix::Repository>> {
+    let Some(stack_id) = stack_id else {
+        return Ok(Cow::Borrowed(repo));
+    };
+    let meta = but_meta::VirtualBranchesTomlMetadata::from_path(
+        ctx.project_data_dir().join("virtual_branches.toml"),
+    )?;
+    Ok(
+        match meta
+            .data()
+            .branches
+            .get(&stack_id)
+            .and_then(|stack| stack.identity.as_ref())
+        {
+            Some(identity) if !identity.is_empty() => {
+                Cow::Owned(repo.clone().with_stack_identity(identity)?)
+            }
+            _ => Cow::Borrowed(repo),
+        },
+    )
+}
+
+/// Remember which commits were rewritten by `outcome`, so they can be followed to their latest version.
+#[cfg(feature = "legacy")]
+fn record_rewritten_commits(ctx: &but_ctx::Context, outcome: &MaterializeOutcome) {
+    gitbutler_stack::RewrittenCommitsHandle::new(ctx.project_data_dir())
+        .record(outcome.rewritten_commits().iter().copied());
+}
+
+#[cfg(not(feature = "legacy"))]
+fn record_rewritten_commits(_ctx: &but_ctx::Context, _outcome: &MaterializeOutcome) {}
+
+/// Finds commits in the stacks of the workspace by message, author, changed paths or content.
+#[but_api]
+#[instrument(err(Debug))]
+pub fn commit_search(
+    ctx: &but_ctx::Context,
+    query: but_workspace::search::Query,
+) -> anyhow::Result<but_workspace::search::SearchResult> {
+    let guard = ctx.shared_worktree_access();
+    let (_, ws) = ctx.workspace_and_read_only_meta_from_head(guard.read_permission())?;
+    let repo = ctx.repo.get()?;
+    but_workspace::search(&repo, &ws, &query)
+}
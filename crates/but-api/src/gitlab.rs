@@ -0,0 +1,36 @@
+// TODO: everything should be fully documented.
+#![allow(missing_docs)]
+use anyhow::Result;
+use but_api_macros::but_api;
+use but_secret::Sensitive;
+use tracing::instrument;
+
+#[but_api]
+#[instrument(err(Debug))]
+pub async fn store_gitlab_pat(
+    host: String,
+    access_token: Sensitive<String>,
+) -> Result<but_gitlab::GitLabUser> {
+    let storage = but_forge_storage::Controller::from_path(but_path::app_data_dir()?);
+    let settings =
+        but_settings::AppSettings::load_from_default_path_creating_without_customization()?;
+    let api_url = settings
+        .forge_host(&host)
+        .and_then(|forge_host| forge_host.api_url.as_deref());
+    but_gitlab::store_pat(&host, api_url, &access_token, &storage).await
+}
+
+#[but_api]
+#[instrument(err(Debug))]
+pub fn forget_gitlab_account(account: but_gitlab::GitLabAccountIdentifier) -> Result<()> {
+    let storage = but_forge_storage::Controller::from_path(but_path::app_data_dir()?);
+    but_gitlab::forget_gitlab_account(&account, &storage).ok();
+    Ok(())
+}
+
+#[but_api]
+#[instrument(err(Debug))]
+pub fn list_known_gitlab_accounts() -> Result<Vec<but_gitlab::GitLabAccountIdentifier>> {
+    let storage = but_forge_storage::Controller::from_path(but_path::app_data_dir()?);
+    but_gitlab::list_known_gitlab_accounts(&storage)
+}
@@ -136,7 +136,8 @@ mod error {
     //!
     //! `tauri` serializes backend errors and makes these available as JSON objects to the frontend. The format
     //! is an implementation detail, but here it's implemented to turn each `Error` into a dict with `code`
-    //! and `message` fields.
+    //! and `message` fields. If a [`ButError`](but_error::ButError) is part of the error chain, it's also
+    //! provided in the `details` field, along with a `recovery` hint.
     //!
     //! The values in these fields are controlled by attaching context, please [see the `error` docs](gitbutler_error::error))
     //! on how to do this.
@@ -214,8 +215,10 @@ mod error {
             S: serde::Serializer,
         {
             let ctx = self.0.custom_context_or_root_cause();
+            let but_error = self.0.but_error();
 
-            let mut map = serializer.serialize_map(Some(2))?;
+            let mut map =
+                serializer.serialize_map(Some(if but_error.is_some() { 4 } else { 2 }))?;
             map.serialize_entry("code", &ctx.code.to_string())?;
             let message = ctx.message.unwrap_or_else(|| {
                 self.0
@@ -224,6 +227,10 @@ mod error {
                     .unwrap_or_else(|| Cow::Borrowed("An unknown backend error occurred"))
             });
             map.serialize_entry("message", &message)?;
+            if let Some(err) = but_error {
+                map.serialize_entry("details", err)?;
+                map.serialize_entry("recovery", &err.recovery())?;
+            }
             map.end()
         }
     }
@@ -231,7 +238,7 @@ mod error {
     #[cfg(test)]
     mod tests {
         use anyhow::anyhow;
-        use but_error::{Code, Context};
+        use but_error::{ButError, Code, Context};
 
         use super::*;
 
@@ -348,6 +355,30 @@ mod error {
                 "it finds the most recent 'code' (and the same would be true for contexts, of course)"
             );
         }
+
+        #[test]
+        fn find_but_error() {
+            let err = anyhow::Error::from(ButError::StackNotFound {
+                stack_id: "42".into(),
+            })
+            .context("couldn't move commit");
+            assert_eq!(
+                json(err),
+                "{\"code\":\"errors.branch.notfound\",\"message\":\"The stack 42 doesn't exist in the workspace anymore\",\"details\":{\"kind\":\"stackNotFound\",\"stackId\":\"42\"},\"recovery\":\"refresh\"}",
+                "structured errors provide their message, and their fields for the frontend to act on"
+            );
+        }
+
+        #[test]
+        fn context_takes_precedence_over_but_error() {
+            let err = anyhow::Error::from(ButError::MergeConflict)
+                .context(Context::new_static(Code::Validation, "ctx msg"));
+            assert_eq!(
+                json(err),
+                "{\"code\":\"errors.validation\",\"message\":\"ctx msg\",\"details\":{\"kind\":\"mergeConflict\"},\"recovery\":\"resolveConflicts\"}",
+                "an explicit context controls code and message, but the details are still available"
+            );
+        }
     }
 }
 
@@ -165,7 +165,7 @@ pub fn absorption_plan(
         }
     };
 
-    let guard = ctx.exclusive_worktree_access();
+    let guard = ctx.shared_worktree_access();
     let (_, graph) = ctx.graph_and_read_only_meta_from_head(guard.read_permission())?;
     let repo = ctx.repo.get()?;
 
@@ -375,6 +375,7 @@ fn convert_assignments_to_diff_specs(
     let mut diff_specs = Vec::new();
     for (path, hunks) in specs_by_path {
         let mut hunk_headers = Vec::new();
+        let mode_flags = hunks.iter().find_map(|hunk| hunk.mode_flags);
         for hunk in hunks {
             if let Some(header) = hunk.hunk_header {
                 hunk_headers.push(header);
@@ -385,6 +386,7 @@ fn convert_assignments_to_diff_specs(
             previous_path: None, // TODO: Handle renames
             path: path.clone(),
             hunk_headers,
+            mode_flags,
         });
     }
 
@@ -2,6 +2,8 @@ use anyhow::Result;
 use but_api_macros::but_api;
 use but_core::{RepositoryExt, settings::git::ui::GitConfigSettings};
 use but_serde::bstring_opt_lossy;
+use but_workspace::legacy::WorkspaceMergeOptions;
+use gitbutler_branch_actions::MessageTemplate;
 use gitbutler_project::ProjectId;
 use gix::bstr::BString;
 use serde::Serialize;
@@ -24,6 +26,33 @@ pub fn set_gb_config(project_id: ProjectId, config: GitConfigSettings) -> Result
         .set_git_settings(&config.into())
 }
 
+#[but_api]
+#[instrument(err(Debug))]
+pub fn get_commit_template(project_id: ProjectId) -> Result<MessageTemplate> {
+    MessageTemplate::from_repo(&gitbutler_project::get(project_id)?.open_repo()?)
+}
+
+#[but_api]
+#[instrument(err(Debug))]
+pub fn set_commit_template(project_id: ProjectId, template: MessageTemplate) -> Result<()> {
+    template.persist_to_local_config(&gitbutler_project::get(project_id)?.open_repo()?)
+}
+
+#[but_api]
+#[instrument(err(Debug))]
+pub fn get_workspace_merge_options(project_id: ProjectId) -> Result<WorkspaceMergeOptions> {
+    WorkspaceMergeOptions::from_repo(&gitbutler_project::get(project_id)?.open_repo()?)
+}
+
+#[but_api]
+#[instrument(err(Debug))]
+pub fn set_workspace_merge_options(
+    project_id: ProjectId,
+    options: WorkspaceMergeOptions,
+) -> Result<()> {
+    options.persist_to_local_config(&gitbutler_project::get(project_id)?.open_repo()?)
+}
+
 #[but_api]
 #[instrument(err(Debug))]
 pub fn store_author_globally_if_unset(
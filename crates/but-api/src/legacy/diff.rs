@@ -10,15 +10,23 @@ use tracing::instrument;
 
 /// Provide a unified diff for `change`, but fail if `change` is a [type-change](but_core::ModeFlags::TypeChange)
 /// or if it involves a change to a [submodule](gix::object::Kind::Commit).
+///
+/// If `word_diff` is `true`, the hunks also contain the [changes within lines](but_core::unified_diff::DiffHunk::word_diff).
 #[but_api]
 #[instrument(err(Debug))]
 pub fn tree_change_diffs(
     ctx: &Context,
     change: TreeChange,
+    word_diff: Option<bool>,
 ) -> anyhow::Result<Option<but_core::UnifiedPatch>> {
     let change: but_core::TreeChange = change.into();
     let repo = ctx.repo.get()?;
-    change.unified_patch(&repo, ctx.settings.context_lines)
+    let patch = change.unified_patch(&repo, ctx.settings.context_lines)?;
+    Ok(if word_diff.unwrap_or_default() {
+        patch.map(but_core::UnifiedPatch::with_word_diffs)
+    } else {
+        patch
+    })
 }
 
 /// This UI-version of [`but_core::diff::worktree_changes()`] simplifies the `git status` information for display in
@@ -86,8 +94,31 @@ pub fn changes_in_worktree(ctx: &mut Context) -> anyhow::Result<WorktreeChanges>
     })
 }
 
+/// Explain if changes to the worktree-relative `path` are ignored, and which pattern of the `.gitignore` files,
+/// `.git/info/exclude` or `core.excludesFile` decided it, to find out why a change doesn't show up,
+/// or why untracked files do.
 #[but_api]
 #[instrument(err(Debug))]
+pub fn explain_ignore(
+    ctx: &Context,
+    path: String,
+) -> anyhow::Result<but_core::ignore::IgnoreExplanation> {
+    let repo = ctx.repo.get()?;
+    but_core::ignore::explain_ignore(&repo, path.as_str().into())
+}
+
+/// Set the `skip-worktree` bit of the tracked file at `path` if `skip` is `true`, or clear it otherwise.
+/// Changes to files with the bit set are hidden from the worktree changes, and thus can't be assigned or committed.
+#[but_api(mutating)]
+#[instrument(err(Debug))]
+pub fn set_skip_worktree(ctx: &mut Context, path: String, skip: bool) -> anyhow::Result<()> {
+    let _guard = ctx.exclusive_worktree_access();
+    let repo = ctx.repo.get()?;
+    but_core::unchanged::set_skip_worktree(&repo, path.as_str().into(), skip)
+}
+
+#[but_api(mutating)]
+#[instrument(err(Debug))]
 pub fn assign_hunk(
     ctx: &mut Context,
     assignments: Vec<HunkAssignmentRequest>,
@@ -98,3 +129,37 @@ pub fn assign_hunk(
     let rejections = but_hunk_assignment::assign(ctx, &repo, &workspace, assignments, None)?;
     Ok(rejections)
 }
+
+/// Attribute each line of the worktree file at `path` to the commit and stack that own it,
+/// with uncommitted lines attributed to the stack their hunk is assigned to.
+#[but_api]
+#[instrument(err(Debug))]
+pub fn blame(
+    ctx: &mut Context,
+    path: String,
+) -> anyhow::Result<Vec<but_workspace::blame::LineBlame>> {
+    let guard = ctx.shared_worktree_access();
+    let repo = ctx.repo.get()?.clone();
+    let (_, workspace) = ctx.workspace_and_read_only_meta_from_head(guard.read_permission())?;
+    let changes = but_core::diff::worktree_changes(&repo)?;
+    let (assignments, _) = but_hunk_assignment::assignments_with_fallback(
+        ctx,
+        &repo,
+        &workspace,
+        false,
+        Some(changes.changes),
+        None,
+    )?;
+    let uncommitted_hunks: Vec<_> = assignments
+        .into_iter()
+        .filter(|assignment| assignment.path == path)
+        .filter_map(|assignment| {
+            let header = assignment.hunk_header?;
+            Some(but_workspace::blame::UncommittedHunk {
+                new_lines: header.new_start..header.new_start + header.new_lines,
+                stack_id: assignment.stack_id,
+            })
+        })
+        .collect();
+    but_workspace::blame(&repo, &workspace, path.as_str().into(), &uncommitted_hunks)
+}
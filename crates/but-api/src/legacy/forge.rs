@@ -6,8 +6,14 @@ use but_ctx::Context;
 use but_forge::{
     ForgeName, ReviewTemplateFunctions, available_review_templates, get_review_template_functions,
 };
+use but_hunk_dependency::ui::HunkLockTarget;
+use gitbutler_oplog::{
+    OplogExt,
+    entry::{OperationKind, SnapshotDetails},
+};
 use gitbutler_project::ProjectId;
 use gitbutler_repo::RepoCommands;
+use gitbutler_stack::{StackId, VirtualBranchesHandle};
 use tracing::instrument;
 
 /// (Deprecated) Get the list of PR template paths for the given project and forge.
@@ -74,6 +80,85 @@ mod json {
         /// The content of the review template.
         pub content: String,
     }
+
+    /// The outcome of importing review suggestions into the worktree.
+    #[derive(Debug, Clone, serde::Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct ImportedReviewSuggestions {
+        /// The suggestions that were written to the worktree.
+        pub applied: Vec<but_forge::ReviewSuggestion>,
+        /// The suggestions that weren't written to the worktree, along with the reason.
+        pub skipped: Vec<SkippedReviewSuggestion>,
+        /// Hunks that couldn't be assigned to the stack owning the branch as they depend on other stacks.
+        pub rejections: Vec<but_hunk_assignment::AssignmentRejection>,
+    }
+
+    /// A review suggestion that wasn't written to the worktree.
+    #[derive(Debug, Clone, serde::Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct SkippedReviewSuggestion {
+        /// The suggestion that was skipped.
+        pub suggestion: but_forge::ReviewSuggestion,
+        /// Why the suggestion was skipped.
+        pub reason: SkipReason,
+    }
+
+    /// The reason for a review suggestion to be skipped.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub enum SkipReason {
+        /// Its lines overlap with the lines of another suggestion that was applied.
+        Overlapping,
+        /// The file in the worktree, or the file in the commit the review was made on, couldn't be read.
+        Unreadable,
+        /// The lines to replace differ from the lines the reviewer saw, or don't exist anymore.
+        Outdated,
+    }
+
+    /// How to create a pull request for a branch of a stack.
+    #[derive(Debug, Clone, Default, serde::Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct CreatePrOptions {
+        /// The title of the pull request, or the name of the branch if unset.
+        pub title: Option<String>,
+        /// The description of the pull request.
+        pub body: Option<String>,
+        /// Whether to create the pull request as draft.
+        #[serde(default)]
+        pub draft: bool,
+    }
+
+    /// A review comment on a branch of a stack, along with where its lines are now.
+    #[derive(Debug, Clone, serde::Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct StackReviewComment {
+        /// The name of the branch whose review the comment was made in.
+        pub branch: String,
+        /// The number of the review the comment was made in.
+        pub review_number: usize,
+        pub comment: but_forge::ForgeReviewComment,
+        /// Where the commented lines are now, or `None` if they were changed or removed since the comment was made,
+        /// or if the commit the comment was made on isn't available locally.
+        pub location: Option<ReviewCommentLocation>,
+        /// The local commit that the commit the comment was made on was rewritten into since, if any.
+        #[serde(with = "but_serde::object_id_opt")]
+        pub rewritten_commit_id: Option<gix::ObjectId>,
+    }
+
+    /// The current location of the lines a review comment refers to.
+    #[derive(Debug, Clone, serde::Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct ReviewCommentLocation {
+        /// The commit of the stack that last changed the commented lines, or `None` if they
+        /// weren't changed in the stack.
+        #[serde(with = "but_serde::object_id_opt")]
+        pub commit_id: Option<gix::ObjectId>,
+        /// The first and last commented line in the file as of `commit_id`, or as of the workspace commit
+        /// if `commit_id` is `None`.
+        pub commit_lines: (u32, u32),
+        /// The first and last commented line in the worktree file, or `None` if they were changed in the worktree.
+        pub worktree_lines: Option<(u32, u32)>,
+    }
 }
 
 /// Get the review template content for the given project and relative path.
@@ -214,6 +299,32 @@ pub fn list_ci_checks_and_update_cache(
     )
 }
 
+/// Get the checks of the head of `branch` along with their overall state, fetching them from the forge
+/// if they aren't cached or if `refresh` is `true`.
+#[but_api]
+#[instrument(err(Debug))]
+pub fn checks_for_branch(
+    project_id: ProjectId,
+    branch: String,
+    refresh: bool,
+) -> Result<but_forge::BranchChecks> {
+    let mut ctx = Context::new_from_legacy_project_id(project_id)?;
+    let base_branch = gitbutler_branch_actions::base::get_base_branch_data(&ctx)?;
+    let storage = but_forge_storage::Controller::from_path(but_path::app_data_dir()?);
+    let preferred_forge_user = ctx.legacy_project.preferred_forge_user.clone();
+    let db = &mut *ctx.db.get_mut()?;
+    but_forge::checks_for_branch(
+        preferred_forge_user,
+        &base_branch
+            .forge_repo_info
+            .context("No forge could be determined for this repository branch")?,
+        &storage,
+        &branch,
+        db,
+        refresh,
+    )
+}
+
 #[but_api]
 #[instrument(err(Debug))]
 pub async fn publish_review(
@@ -320,3 +431,786 @@ pub fn warm_ci_checks_cache(project_id: ProjectId) -> Result<()> {
 
     Ok(())
 }
+
+/// Write all suggestions made in review comments of the review with `review_number` into the worktree,
+/// and assign the resulting hunks to the stack that owns `branch`.
+///
+/// That way the suggestions can be committed like any other change, or amended into the commit of choice.
+/// Suggestions whose lines changed since the commit they were made on are skipped, as are suggestions
+/// overlapping with another one.
+#[but_api]
+#[instrument(err(Debug))]
+pub async fn import_review_suggestions(
+    project_id: ProjectId,
+    branch: String,
+    review_number: usize,
+) -> Result<json::ImportedReviewSuggestions> {
+    let (storage, base_branch, project) = {
+        let ctx = Context::new_from_legacy_project_id(project_id)?;
+        let base_branch = gitbutler_branch_actions::base::get_base_branch_data(&ctx)?;
+        (
+            but_forge_storage::Controller::from_path(but_path::app_data_dir()?),
+            base_branch,
+            ctx.legacy_project,
+        )
+    };
+    let suggestions = but_forge::list_forge_review_suggestions(
+        &project.preferred_forge_user,
+        &base_branch
+            .forge_repo_info
+            .context("No forge could be determined for this repository branch")?,
+        review_number,
+        &storage,
+    )
+    .await?;
+
+    let mut ctx = Context::new_from_legacy_project_id(project_id)?;
+    apply_review_suggestions(&mut ctx, &branch, suggestions)
+}
+
+/// Write `suggestions` into the worktree and assign the resulting hunks to the stack that owns `branch`.
+fn apply_review_suggestions(
+    ctx: &mut Context,
+    branch: &str,
+    mut suggestions: Vec<but_forge::ReviewSuggestion>,
+) -> Result<json::ImportedReviewSuggestions> {
+    let mut guard = ctx.exclusive_worktree_access();
+    let repo = ctx.repo.get()?.clone();
+    let (_, workspace) = ctx.workspace_and_read_only_meta_from_head(guard.read_permission())?;
+    let branch_ref: gix::refs::FullName = format!("refs/heads/{branch}").try_into()?;
+    let stack_id = workspace
+        .try_find_segment_and_stack_by_refname(branch_ref.as_ref())?
+        .0
+        .id
+        .with_context(|| format!("The stack owning '{branch}' has no id"))?;
+    let workdir = repo
+        .workdir()
+        .context("Cannot import suggestions into a bare repository")?
+        .to_owned();
+
+    let _ = ctx.create_snapshot(
+        SnapshotDetails::new(OperationKind::ApplyReviewSuggestions),
+        guard.write_permission(),
+    );
+
+    // Apply bottom-up so the line numbers of suggestions further up in the same file remain valid.
+    suggestions.sort_by(|a, b| a.path.cmp(&b.path).then(b.start_line.cmp(&a.start_line)));
+    let (mut applied, mut skipped) = (Vec::new(), Vec::new());
+    for suggestion in suggestions {
+        let path = workdir.join(&suggestion.path);
+        let overlaps_applied = applied.iter().any(|s: &but_forge::ReviewSuggestion| {
+            s.path == suggestion.path && s.start_line <= suggestion.end_line
+        });
+        let outcome = if overlaps_applied {
+            Err(json::SkipReason::Overlapping)
+        } else {
+            match (
+                std::fs::read_to_string(&path).ok(),
+                reviewed_file_content(&repo, &suggestion),
+            ) {
+                (Some(content), Some(original))
+                    if suggestion.matches_original(&content, &original) =>
+                {
+                    suggestion
+                        .apply_to(&content)
+                        .ok_or(json::SkipReason::Outdated)
+                }
+                (Some(_), Some(_)) => Err(json::SkipReason::Outdated),
+                _ => Err(json::SkipReason::Unreadable),
+            }
+        };
+        match outcome {
+            Ok(content) => {
+                std::fs::write(&path, content)?;
+                applied.push(suggestion);
+            }
+            Err(reason) => skipped.push(json::SkippedReviewSuggestion { suggestion, reason }),
+        }
+    }
+
+    let requests = suggestion_assignment_requests(&repo, ctx, &applied, stack_id)?;
+    let rejections = but_hunk_assignment::assign(ctx, &repo, &workspace, requests, None)?;
+    Ok(json::ImportedReviewSuggestions {
+        applied,
+        skipped,
+        rejections,
+    })
+}
+
+/// Return the content of the file `suggestion` was made on, as the reviewer saw it in its commit,
+/// or `None` if it couldn't be found or isn't valid UTF-8.
+fn reviewed_file_content(
+    repo: &gix::Repository,
+    suggestion: &but_forge::ReviewSuggestion,
+) -> Option<String> {
+    let spec = format!("{}:{}", suggestion.commit_id, suggestion.path);
+    let blob = repo.rev_parse_single(spec.as_str()).ok()?.object().ok()?;
+    String::from_utf8(blob.detach().data).ok()
+}
+
+/// Produce requests to assign all worktree hunks that overlap with lines changed by `applied` suggestions to `stack_id`.
+fn suggestion_assignment_requests(
+    repo: &gix::Repository,
+    ctx: &Context,
+    applied: &[but_forge::ReviewSuggestion],
+    stack_id: but_core::ref_metadata::StackId,
+) -> Result<Vec<but_hunk_assignment::HunkAssignmentRequest>> {
+    let mut requests = Vec::new();
+    let changes = but_core::diff::worktree_changes(repo)?.changes;
+    for change in changes {
+        let mut in_file: Vec<_> = applied
+            .iter()
+            .filter(|s| change.path == s.path.as_str())
+            .collect();
+        if in_file.is_empty() {
+            continue;
+        }
+        // Translate the line ranges of the suggestions into the coordinates of the new file,
+        // accounting for suggestions above them that changed the amount of lines.
+        in_file.sort_by_key(|s| s.start_line);
+        let mut offset = 0i64;
+        let mut new_ranges = Vec::new();
+        for s in in_file {
+            let new_lines = s.replacement.lines().count() as i64;
+            let start = (s.start_line as i64 + offset) as u32;
+            new_ranges.push(if new_lines == 0 {
+                // A deletion leaves no lines behind, so match the hunks touching the lines around it.
+                but_core::HunkRange {
+                    start: start.saturating_sub(1).max(1),
+                    lines: 2,
+                }
+            } else {
+                but_core::HunkRange {
+                    start,
+                    lines: new_lines as u32,
+                }
+            });
+            offset += new_lines - (s.end_line as i64 - s.start_line as i64 + 1);
+        }
+
+        match change.unified_patch(repo, ctx.settings.context_lines)? {
+            Some(but_core::UnifiedPatch::Patch { hunks, .. }) => {
+                for hunk in hunks {
+                    let header = but_core::HunkHeader::from(hunk);
+                    if new_ranges
+                        .iter()
+                        .any(|range| header.new_range().intersects(*range))
+                    {
+                        requests.push(but_hunk_assignment::HunkAssignmentRequest {
+                            hunk_header: Some(header),
+                            path_bytes: change.path.clone(),
+                            stack_id: Some(stack_id),
+                        });
+                    }
+                }
+            }
+            _ => requests.push(but_hunk_assignment::HunkAssignmentRequest {
+                hunk_header: None,
+                path_bytes: change.path.clone(),
+                stack_id: Some(stack_id),
+            }),
+        }
+    }
+    Ok(requests)
+}
+
+/// Create a pull request for `branch_name` in the stack with `stack_id`, and remember its number with the branch.
+///
+/// The pull request targets the branch below it in the stack, or the target branch if it is at the bottom,
+/// so stacked branches are reviewed one at a time.
+#[but_api]
+#[instrument(err(Debug))]
+pub async fn create_pr_for_branch(
+    project_id: ProjectId,
+    stack_id: StackId,
+    branch_name: String,
+    opts: json::CreatePrOptions,
+) -> Result<but_forge::ForgeReview> {
+    let (storage, forge_repo_info, project, bases) = forge_and_review_bases(project_id, stack_id)?;
+    let target_branch = bases
+        .into_iter()
+        .find_map(|review_base| (review_base.branch == branch_name).then_some(review_base.base))
+        .with_context(|| format!("Branch '{branch_name}' isn't part of the stack"))?;
+    let params = but_forge::CreateForgeReviewParams {
+        title: opts.title.unwrap_or_else(|| branch_name.clone()),
+        body: opts.body.unwrap_or_default(),
+        source_branch: branch_name.clone(),
+        target_branch,
+        draft: opts.draft,
+    };
+    let review = but_forge::create_forge_review(
+        &project.preferred_forge_user,
+        &forge_repo_info,
+        &params,
+        &storage,
+    )
+    .await?;
+
+    let ctx = Context::new_from_legacy_project_id(project_id)?;
+    gitbutler_branch_actions::stack::update_branch_pr_number(
+        &ctx,
+        stack_id,
+        branch_name,
+        Some(review.number.try_into()?),
+    )?;
+    drop(ctx);
+    if let Err(err) = update_stack_footers(project_id, stack_id).await {
+        tracing::warn!(?err, "Failed to update the stack footers of reviews");
+    }
+    Ok(review)
+}
+
+/// Make the pull requests of all branches in the stack with `stack_id` target the branch below them again,
+/// as needed after the branches of the stack were reordered, and return the reviews that were changed.
+#[but_api]
+#[instrument(err(Debug))]
+pub async fn update_review_bases(
+    project_id: ProjectId,
+    stack_id: StackId,
+) -> Result<Vec<but_forge::ForgeReview>> {
+    let (storage, forge_repo_info, project, bases) = forge_and_review_bases(project_id, stack_id)?;
+    let mut updated = Vec::new();
+    for ReviewBase {
+        pr_number, base, ..
+    } in bases
+    {
+        let Some(pr_number) = pr_number else {
+            continue;
+        };
+        let review = but_forge::get_forge_review(
+            &project.preferred_forge_user,
+            &forge_repo_info,
+            pr_number,
+            &storage,
+        )
+        .await?;
+        if !review.is_open() || review.target_branch == base {
+            continue;
+        }
+        updated.push(
+            but_forge::update_forge_review_base(
+                &project.preferred_forge_user,
+                &forge_repo_info,
+                pr_number,
+                &base,
+                &storage,
+            )
+            .await?,
+        );
+    }
+    if let Err(err) = update_stack_footers(project_id, stack_id).await {
+        tracing::warn!(?err, "Failed to update the stack footers of reviews");
+    }
+    Ok(updated)
+}
+
+/// Add or refresh the footer listing all reviews of the stack with `stack_id` in the description of each
+/// of its open reviews, and return the reviews that were changed.
+///
+/// Stacks with a single review have their footer removed. Nothing happens if `gitbutler.forgeStackFooter`
+/// is disabled.
+#[but_api]
+#[instrument(err(Debug))]
+pub async fn update_stack_footers(
+    project_id: ProjectId,
+    stack_id: StackId,
+) -> Result<Vec<but_forge::ForgeReview>> {
+    let enabled = Context::new_from_legacy_project_id(project_id)?
+        .repo
+        .get()?
+        .git_settings()?
+        .gitbutler_forge_stack_footer
+        .unwrap_or(true);
+    if !enabled {
+        return Ok(Vec::new());
+    }
+    let (storage, forge_repo_info, project, bases) = forge_and_review_bases(project_id, stack_id)?;
+    let review_numbers: Vec<usize> = bases.iter().filter_map(|base| base.pr_number).collect();
+    let mut updated = Vec::new();
+    for &review_number in &review_numbers {
+        let review = but_forge::get_forge_review(
+            &project.preferred_forge_user,
+            &forge_repo_info,
+            review_number,
+            &storage,
+        )
+        .await?;
+        if !review.is_open() {
+            continue;
+        }
+        let body = review.body.unwrap_or_default();
+        let new_body = if review_numbers.len() > 1 {
+            let footer =
+                but_forge::stack_footer(review_number, &review_numbers, &review.unit_symbol);
+            but_forge::with_stack_footer(&body, &footer)
+        } else {
+            but_forge::without_stack_footer(&body)
+        };
+        if new_body == body {
+            continue;
+        }
+        updated.push(
+            but_forge::update_forge_review_body(
+                &project.preferred_forge_user,
+                &forge_repo_info,
+                review_number,
+                &new_body,
+                &storage,
+            )
+            .await?,
+        );
+    }
+    Ok(updated)
+}
+
+/// Fetch the review comments of the reviews of all branches in the stack with `stack_id`, and locate the lines
+/// they refer to in the commits of the stack and in the worktree, so they can be shown inline.
+///
+/// Comments are made on the commits that were pushed at the time, so their lines are followed through all
+/// changes made since, including rebases, by diffing against the workspace commit and the worktree.
+/// The commit that last changed the lines is determined with the same ranges that assign worktree hunks to commits.
+#[but_api]
+#[instrument(err(Debug))]
+pub async fn review_comments_for_stack(
+    project_id: ProjectId,
+    stack_id: StackId,
+) -> Result<Vec<json::StackReviewComment>> {
+    let (storage, forge_repo_info, project, bases) = forge_and_review_bases(project_id, stack_id)?;
+    let mut comments = Vec::new();
+    for ReviewBase {
+        branch, pr_number, ..
+    } in bases
+    {
+        let Some(review_number) = pr_number else {
+            continue;
+        };
+        let review_comments = but_forge::list_forge_review_comments(
+            &project.preferred_forge_user,
+            &forge_repo_info,
+            review_number,
+            &storage,
+        )
+        .await?;
+        comments.extend(
+            review_comments
+                .into_iter()
+                .map(|comment| (branch.clone(), review_number, comment)),
+        );
+    }
+
+    let ctx = Context::new_from_legacy_project_id(project_id)?;
+    let guard = ctx.shared_worktree_access();
+    let repo = ctx.repo.get()?.clone().for_tree_diffing()?;
+    let (_, workspace) = ctx.workspace_and_read_only_meta_from_head(guard.read_permission())?;
+    let ranges = but_hunk_dependency::WorkspaceRanges::try_from_stacks(
+        but_hunk_dependency::new_stacks_to_input_stacks(&repo, &workspace)?,
+    )?;
+    let head_tree = repo.head_tree_id_or_empty()?.object()?.into_tree();
+    let worktree_changes = but_core::diff::worktree_changes(&repo)?.changes;
+    let rewritten_commits =
+        gitbutler_stack::RewrittenCommitsHandle::new(ctx.project_data_dir()).load()?;
+
+    comments
+        .into_iter()
+        .map(|(branch, review_number, comment)| {
+            let location = locate_review_comment(
+                &repo,
+                &head_tree,
+                &ranges,
+                &worktree_changes,
+                stack_id,
+                &comment,
+            )?;
+            let rewritten_commit_id = gix::ObjectId::from_hex(comment.commit_id.as_bytes())
+                .ok()
+                .and_then(|commit_id| rewritten_commits.resolve(commit_id));
+            Ok(json::StackReviewComment {
+                branch,
+                review_number,
+                comment,
+                location,
+                rewritten_commit_id,
+            })
+        })
+        .collect()
+}
+
+/// Find where the lines of `comment` are in the workspace commit with `head_tree` and in the worktree.
+fn locate_review_comment(
+    repo: &gix::Repository,
+    head_tree: &gix::Tree<'_>,
+    ranges: &but_hunk_dependency::WorkspaceRanges,
+    worktree_changes: &[but_core::TreeChange],
+    stack_id: StackId,
+    comment: &but_forge::ForgeReviewComment,
+) -> Result<Option<json::ReviewCommentLocation>> {
+    let (Some(start_line), Some(end_line)) = (comment.start_line, comment.end_line) else {
+        return Ok(None);
+    };
+    let Ok(commented_commit) = gix::ObjectId::from_hex(comment.commit_id.as_bytes())
+        .map_err(anyhow::Error::from)
+        .and_then(|id| Ok(repo.find_commit(id)?))
+    else {
+        return Ok(None);
+    };
+    let path = comment.path.as_str();
+    let commented_state = change_state_at(&commented_commit.tree()?, path)?;
+    let head_state = change_state_at(head_tree, path)?;
+    let Some(head_lines) = map_lines_between(
+        repo,
+        path,
+        commented_state,
+        head_state,
+        start_line,
+        end_line,
+    )?
+    else {
+        return Ok(None);
+    };
+
+    let in_stack = ranges
+        .intersection(&path.into(), head_lines.0, head_lines.1 - head_lines.0 + 1)
+        .and_then(|intersection| {
+            intersection
+                .into_iter()
+                .rfind(|range| range.target == HunkLockTarget::Stack(stack_id))
+        });
+    let (commit_id, commit_lines) = match in_stack {
+        Some(range) => {
+            let shift = |line: u32| u32::try_from(line as i64 - range.line_shift as i64).ok();
+            let lines = shift(head_lines.0).zip(shift(head_lines.1));
+            (Some(range.commit_id), lines.unwrap_or(head_lines))
+        }
+        None => (None, head_lines),
+    };
+
+    let worktree_lines = match worktree_changes.iter().find(|change| change.path == path) {
+        None => Some(head_lines),
+        Some(change) => match change.unified_patch(repo, 0)? {
+            Some(but_core::UnifiedPatch::Patch { hunks, .. }) => {
+                map_line_through_hunks(&hunks, head_lines.0)
+                    .zip(map_line_through_hunks(&hunks, head_lines.1))
+            }
+            _ => None,
+        },
+    };
+    Ok(Some(json::ReviewCommentLocation {
+        commit_id,
+        commit_lines,
+        worktree_lines,
+    }))
+}
+
+/// Return the state of the file at `path` in `tree`, if there is one.
+fn change_state_at(tree: &gix::Tree<'_>, path: &str) -> Result<Option<but_core::ChangeState>> {
+    Ok(tree
+        .lookup_entry_by_path(path)?
+        .map(|entry| but_core::ChangeState {
+            id: entry.object_id(),
+            kind: entry.mode().kind(),
+        }))
+}
+
+/// Return where the lines `start_line..=end_line` of the file at `path` with `previous` state are when it has
+/// the `current` state, or `None` if any of them changed.
+fn map_lines_between(
+    repo: &gix::Repository,
+    path: &str,
+    previous: Option<but_core::ChangeState>,
+    current: Option<but_core::ChangeState>,
+    start_line: u32,
+    end_line: u32,
+) -> Result<Option<(u32, u32)>> {
+    let (Some(previous), Some(current)) = (previous, current) else {
+        return Ok(None);
+    };
+    if previous.id == current.id {
+        return Ok(Some((start_line, end_line)));
+    }
+    let hunks =
+        match but_core::UnifiedPatch::compute(repo, path.into(), None, current, previous, 0)? {
+            Some(but_core::UnifiedPatch::Patch { hunks, .. }) => hunks,
+            _ => return Ok(None),
+        };
+    if hunks.iter().any(|hunk| {
+        hunk.old_lines > 0
+            && hunk.old_start <= end_line
+            && start_line < hunk.old_start + hunk.old_lines
+    }) {
+        return Ok(None);
+    }
+    Ok(map_line_through_hunks(&hunks, start_line).zip(map_line_through_hunks(&hunks, end_line)))
+}
+
+/// Return where `line` of the old version of a file is in the new version according to the `hunks` of a diff
+/// without context lines, or `None` if the line was changed or removed.
+fn map_line_through_hunks(hunks: &[but_core::unified_diff::DiffHunk], line: u32) -> Option<u32> {
+    let mut shift = 0i64;
+    for hunk in hunks {
+        if hunk.old_lines == 0 {
+            // Lines were inserted after `old_start`.
+            if line <= hunk.old_start {
+                break;
+            }
+        } else if line < hunk.old_start {
+            break;
+        } else if line < hunk.old_start + hunk.old_lines {
+            return None;
+        }
+        shift += hunk.new_lines as i64 - hunk.old_lines as i64;
+    }
+    u32::try_from(line as i64 + shift).ok()
+}
+
+/// The branch a review of `branch` should be merged into.
+struct ReviewBase {
+    branch: String,
+    pr_number: Option<usize>,
+    base: String,
+}
+
+/// Return everything needed to talk to the forge, along with the review bases of the branches of
+/// the stack with `stack_id`.
+fn forge_and_review_bases(
+    project_id: ProjectId,
+    stack_id: StackId,
+) -> Result<(
+    but_forge_storage::Controller,
+    but_forge::ForgeRepoInfo,
+    gitbutler_project::Project,
+    Vec<ReviewBase>,
+)> {
+    let ctx = Context::new_from_legacy_project_id(project_id)?;
+    let base_branch = gitbutler_branch_actions::base::get_base_branch_data(&ctx)?;
+    let forge_repo_info = base_branch
+        .forge_repo_info
+        .clone()
+        .context("No forge could be determined for this repository branch")?;
+    let stack = VirtualBranchesHandle::new(ctx.project_data_dir()).get_stack(stack_id)?;
+
+    // Heads are ordered bottom to top, and archived branches were integrated already.
+    let mut base = base_branch.short_name().to_owned();
+    let mut bases = Vec::new();
+    for head in stack.heads.iter().filter(|head| !head.archived) {
+        bases.push(ReviewBase {
+            branch: head.name().to_owned(),
+            pr_number: head.pr_number,
+            base: base.clone(),
+        });
+        base = head.name().to_owned();
+    }
+    Ok((
+        but_forge_storage::Controller::from_path(but_path::app_data_dir()?),
+        forge_repo_info,
+        ctx.legacy_project,
+        bases,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use but_core::unified_diff::DiffHunk;
+
+    use super::map_line_through_hunks;
+
+    fn hunk(old_start: u32, old_lines: u32, new_start: u32, new_lines: u32) -> DiffHunk {
+        DiffHunk {
+            old_start,
+            old_lines,
+            new_start,
+            new_lines,
+            diff: Default::default(),
+            encoding: None,
+            word_diff: None,
+        }
+    }
+
+    #[test]
+    fn lines_follow_insertions_and_removals_above_them() {
+        // Two lines inserted after line 2, line 10 removed.
+        let hunks = [hunk(2, 0, 3, 2), hunk(10, 1, 11, 0)];
+        assert_eq!(map_line_through_hunks(&hunks, 1), Some(1));
+        assert_eq!(map_line_through_hunks(&hunks, 2), Some(2));
+        assert_eq!(map_line_through_hunks(&hunks, 3), Some(5));
+        assert_eq!(map_line_through_hunks(&hunks, 10), None, "removed");
+        assert_eq!(map_line_through_hunks(&hunks, 11), Some(12));
+    }
+
+    #[test]
+    fn changed_lines_are_not_mapped() {
+        let hunks = [hunk(4, 2, 4, 3)];
+        assert_eq!(map_line_through_hunks(&hunks, 3), Some(3));
+        assert_eq!(map_line_through_hunks(&hunks, 4), None);
+        assert_eq!(map_line_through_hunks(&hunks, 5), None);
+        assert_eq!(map_line_through_hunks(&hunks, 6), Some(7));
+    }
+
+    mod import_review_suggestions {
+        use but_ctx::Context;
+        use but_forge::ReviewSuggestion;
+
+        use crate::legacy::forge::{apply_review_suggestions, json::SkipReason};
+
+        fn suggestion(
+            comment_id: i64,
+            path: &str,
+            commit_id: gix::ObjectId,
+            start_line: u32,
+            end_line: u32,
+            replacement: &str,
+        ) -> ReviewSuggestion {
+            ReviewSuggestion {
+                comment_id,
+                html_url: String::new(),
+                author: None,
+                path: path.into(),
+                commit_id: commit_id.to_string(),
+                start_line,
+                end_line,
+                replacement: replacement.into(),
+            }
+        }
+
+        fn numbered_lines(edit: impl FnOnce(&mut Vec<String>)) -> String {
+            let mut lines: Vec<String> = (1..=20).map(|n| n.to_string()).collect();
+            edit(&mut lines);
+            lines.join("\n") + "\n"
+        }
+
+        /// Return a context for the `review-suggestions` scenario, along with the id of the commit under review
+        /// and the path to the file suggestions are made on.
+        fn scenario() -> anyhow::Result<(
+            Context,
+            gix::ObjectId,
+            std::path::PathBuf,
+            but_testsupport::gix_testtools::tempfile::TempDir,
+        )> {
+            let (repo, tmp) = but_testsupport::writable_scenario("review-suggestions");
+            let reviewed = repo.rev_parse_single("feature")?.detach();
+            let file = repo.workdir().expect("non-bare").join("file");
+            let ctx = Context::from_repo(repo)?;
+            {
+                let guard = ctx.shared_worktree_access();
+                let meta = ctx.legacy_meta(guard.read_permission())?;
+                meta.write_reconciled(&*ctx.repo.get()?)?;
+            }
+            Ok((ctx, reviewed, file, tmp))
+        }
+
+        /// Return the line ranges of all hunks in `file` that are assigned to a stack.
+        fn assigned_hunk_ranges(ctx: &mut Context) -> anyhow::Result<Vec<(u32, u32)>> {
+            let repo = ctx.repo.get()?.clone();
+            let guard = ctx.shared_worktree_access();
+            let (_, workspace) =
+                ctx.workspace_and_read_only_meta_from_head(guard.read_permission())?;
+            let (assignments, _) = but_hunk_assignment::assignments_with_fallback(
+                ctx,
+                &repo,
+                &workspace,
+                false,
+                None::<Vec<but_core::TreeChange>>,
+                None,
+            )?;
+            let stack_id = workspace.stacks[0].id;
+            Ok(assignments
+                .into_iter()
+                .filter(|a| a.path == "file" && a.stack_id.is_some())
+                .inspect(|a| assert_eq!(a.stack_id, stack_id))
+                .filter_map(|a| a.hunk_header)
+                .map(|h| (h.new_start, h.new_lines))
+                .collect())
+        }
+
+        #[test]
+        fn outdated_overlapping_and_unreadable_suggestions_are_skipped() -> anyhow::Result<()> {
+            let (mut ctx, reviewed, file, _tmp) = scenario()?;
+            // The author changed line 3 after the review.
+            std::fs::write(&file, numbered_lines(|l| l[2] = "three".into()))?;
+
+            let out = apply_review_suggestions(
+                &mut ctx,
+                "feature",
+                vec![
+                    suggestion(1, "file", reviewed, 3, 3, "THREE"),
+                    suggestion(2, "file", reviewed, 10, 11, "TEN AND ELEVEN"),
+                    suggestion(3, "file", reviewed, 11, 12, "eleven\ntwelve"),
+                    suggestion(4, "missing", reviewed, 1, 1, "one"),
+                    suggestion(5, "file", reviewed, 18, 18, "EIGHTEEN"),
+                ],
+            )?;
+
+            let applied: Vec<_> = out.applied.iter().map(|s| s.comment_id).collect();
+            assert_eq!(applied, [5, 3], "applied bottom-up");
+            let skipped: Vec<_> = out
+                .skipped
+                .iter()
+                .map(|s| (s.suggestion.comment_id, s.reason))
+                .collect();
+            assert_eq!(
+                skipped,
+                [
+                    (2, SkipReason::Overlapping),
+                    (1, SkipReason::Outdated),
+                    (4, SkipReason::Unreadable)
+                ]
+            );
+            assert!(out.rejections.is_empty());
+
+            assert_eq!(
+                std::fs::read_to_string(&file)?,
+                numbered_lines(|l| {
+                    l[2] = "three".into();
+                    l[10] = "eleven".into();
+                    l[11] = "twelve".into();
+                    l[17] = "EIGHTEEN".into();
+                }),
+                "the line changed by the author is kept"
+            );
+            let assigned = assigned_hunk_ranges(&mut ctx)?;
+            assert!(
+                assigned
+                    .iter()
+                    .any(|(start, lines)| *start <= 11 && 18 < start + lines),
+                "the hunk with the applied suggestions is assigned to the stack: {assigned:?}"
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn deletions_shift_the_hunks_of_suggestions_below_them() -> anyhow::Result<()> {
+            let (mut ctx, reviewed, file, _tmp) = scenario()?;
+            // Without context lines, each suggestion becomes its own hunk, and an off-by-one misses it.
+            ctx.settings.context_lines = 0;
+
+            let out = apply_review_suggestions(
+                &mut ctx,
+                "feature",
+                vec![
+                    suggestion(1, "file", reviewed, 5, 6, ""),
+                    suggestion(2, "file", reviewed, 15, 15, "FIFTEEN"),
+                ],
+            )?;
+            assert_eq!(out.applied.len(), 2);
+            assert!(out.skipped.is_empty());
+            assert_eq!(
+                std::fs::read_to_string(&file)?,
+                numbered_lines(|l| {
+                    l[14] = "FIFTEEN".into();
+                    l.remove(5);
+                    l.remove(4);
+                })
+            );
+
+            let assigned = assigned_hunk_ranges(&mut ctx)?;
+            assert!(
+                assigned.contains(&(13, 1)),
+                "line 15 moved up by the two deleted lines: {assigned:?}"
+            );
+            assert!(
+                assigned.iter().any(|(_, lines)| *lines == 0),
+                "the deletion is assigned as well: {assigned:?}"
+            );
+            Ok(())
+        }
+    }
+}
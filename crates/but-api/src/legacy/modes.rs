@@ -3,7 +3,7 @@ use anyhow::{Context as _, Result};
 use but_api_macros::but_api;
 use but_core::{ref_metadata::StackId, ui::TreeChange};
 use but_ctx::Context;
-use gitbutler_edit_mode::ConflictEntryPresence;
+use gitbutler_edit_mode::{ConflictEntryPresence, MergeToolOutcome};
 use gitbutler_operating_modes::{EditModeMetadata, OperatingMode};
 use gitbutler_project::ProjectId;
 use tracing::instrument;
@@ -52,12 +52,12 @@ pub fn head_sha(project_id: ProjectId) -> Result<HeadSha, Error> {
     Ok(HeadSha { head_sha })
 }
 
-#[but_api]
+#[but_api(mutating)]
 #[instrument(err(Debug))]
 pub fn enter_edit_mode(
     project_id: ProjectId,
     commit_id: String,
-    stack_id: StackId,
+    stack_id: Option<StackId>,
 ) -> Result<EditModeMetadata> {
     let ctx = Context::new_from_legacy_project_id(project_id)?;
     let commit = git2::Oid::from_str(&commit_id).context("Failed to parse commit oid")?;
@@ -65,7 +65,7 @@ pub fn enter_edit_mode(
     gitbutler_edit_mode::commands::enter_edit_mode(&ctx, commit, stack_id)
 }
 
-#[but_api]
+#[but_api(mutating)]
 #[instrument(err(Debug))]
 pub fn abort_edit_and_return_to_workspace(project_id: ProjectId) -> Result<()> {
     let ctx = Context::new_from_legacy_project_id(project_id)?;
@@ -76,7 +76,7 @@ pub fn abort_edit_and_return_to_workspace(project_id: ProjectId) -> Result<()> {
 }
 
 // GUI-facing API that returns () for serialization compatibility
-#[but_api]
+#[but_api(mutating)]
 #[instrument(err(Debug))]
 pub fn save_edit_and_return_to_workspace(project_id: ProjectId) -> Result<()> {
     let ctx = Context::new_from_legacy_project_id(project_id)?;
@@ -94,6 +94,20 @@ pub fn save_edit_and_return_to_workspace_with_output(project_id: ProjectId) -> R
     Ok(())
 }
 
+/// Resolve the conflicts of the file at `path` in the commit in edit mode with the merge tool configured for `git mergetool`.
+#[but_api(mutating)]
+#[instrument(err(Debug))]
+pub fn resolve_with_mergetool(
+    project_id: ProjectId,
+    commit_id: String,
+    path: std::path::PathBuf,
+) -> Result<MergeToolOutcome> {
+    let ctx = Context::new_from_legacy_project_id(project_id)?;
+    let commit = git2::Oid::from_str(&commit_id).context("Failed to parse commit oid")?;
+
+    gitbutler_edit_mode::commands::resolve_with_mergetool(&ctx, commit, &path)
+}
+
 #[but_api]
 #[instrument(err(Debug))]
 pub fn edit_initial_index_state(
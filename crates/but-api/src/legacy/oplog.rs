@@ -18,13 +18,21 @@
 //!
 use anyhow::{Context as _, Result};
 use but_api_macros::but_api;
-use but_ctx::Context;
+use but_ctx::{
+    Context,
+    access::{OperationCategory, Priority},
+};
 use but_oxidize::OidExt;
+use gitbutler_branch_actions::update_workspace_commit;
 use gitbutler_oplog::{
     OplogExt,
     entry::{OperationKind, Snapshot, SnapshotDetails},
+    file_history::FileHistoryEntry,
+    prune::PruneOutcome,
+    stack_diff::{SnapshotDelta, StackDiff},
 };
 use gitbutler_project::ProjectId;
+use gitbutler_stack::{StackId, VirtualBranchesHandle};
 use tracing::instrument;
 
 /// List snapshots in the oplog.
@@ -85,7 +93,7 @@ pub fn get_snapshot(project_id: ProjectId, sha: String) -> Result<Snapshot> {
 ///
 /// # Errors
 /// Returns an error if the project cannot be found or if there is an issue creating the snapshot.
-#[but_api]
+#[but_api(mutating)]
 #[instrument(err(Debug))]
 pub fn create_snapshot(project_id: ProjectId, message: Option<String>) -> Result<gix::ObjectId> {
     let project = gitbutler_project::get(project_id).context("failed to get project")?;
@@ -97,6 +105,62 @@ pub fn create_snapshot(project_id: ProjectId, message: Option<String>) -> Result
     Ok(oid.to_gix())
 }
 
+/// Creates a new, on-demand snapshot named `label` in the oplog, which can later be found and restored by its name.
+///
+/// - `project_id`: The ID of the project to create a snapshot for.
+/// - `label`: The name of the snapshot, like `before risky rebase`.
+///
+/// Returns the OID of the created snapshot.
+///
+/// # Errors
+/// Returns an error if the project cannot be found, if `label` is empty, or if there is an issue creating the snapshot.
+#[but_api]
+#[instrument(err(Debug))]
+pub fn create_named_snapshot(project_id: ProjectId, label: String) -> Result<gix::ObjectId> {
+    let project = gitbutler_project::get(project_id).context("failed to get project")?;
+    let ctx = Context::new_from_legacy_project(project.clone())?;
+    let mut guard = ctx.exclusive_worktree_access();
+    let oid = ctx.create_named_snapshot(&label, guard.write_permission())?;
+    Ok(oid.to_gix())
+}
+
+/// List the named snapshots in the oplog, the most recent first.
+///
+/// - `project_id`: The ID of the project to list snapshots for.
+/// - `label`: If set, only the snapshots with this name are returned.
+///
+/// # Errors
+/// Returns an error if the project cannot be found or if there is an issue accessing the oplog.
+#[but_api]
+#[instrument(err(Debug))]
+pub fn list_named_snapshots(project_id: ProjectId, label: Option<String>) -> Result<Vec<Snapshot>> {
+    let ctx = Context::new_from_legacy_project_id(project_id)?;
+    ctx.list_named_snapshots(label.as_deref())
+}
+
+/// Restores the project to the most recent snapshot named `label`. This operation also creates a new snapshot in the oplog.
+///
+/// - `project_id`: The ID of the project to restore.
+/// - `label`: The name of the snapshot to restore to.
+///
+/// # Errors
+/// Returns an error if the project cannot be found, if there is no snapshot named `label`,
+/// or if there is an issue during the restore operation.
+#[but_api(mutating)]
+#[instrument(err(Debug))]
+pub fn restore_named_snapshot(project_id: ProjectId, label: String) -> Result<()> {
+    let project = gitbutler_project::get(project_id).context("failed to get project")?;
+    let ctx = Context::new_from_legacy_project(project.clone())?;
+    let snapshot = ctx
+        .list_named_snapshots(Some(&label))?
+        .into_iter()
+        .next()
+        .with_context(|| format!("No snapshot named '{label}'"))?;
+    let mut guard = ctx.exclusive_worktree_access();
+    ctx.restore_snapshot(snapshot.commit_id, guard.write_permission())?;
+    Ok(())
+}
+
 /// Restores the project to a specific snapshot. This operation also creates a new snapshot in the oplog.
 ///
 /// - `project_id`: The ID of the project to restore.
@@ -109,7 +173,7 @@ pub fn create_snapshot(project_id: ProjectId, message: Option<String>) -> Result
 /// This operation modifies the repository state, reverting it to the specified snapshot.
 /// This includes the state of the working directory as well as commit history and references.
 /// Additionally, a new snapshot is created in the oplog to record the restore action.
-#[but_api]
+#[but_api(mutating)]
 #[instrument(err(Debug))]
 pub fn restore_snapshot(project_id: ProjectId, sha: String) -> Result<()> {
     let project = gitbutler_project::get(project_id).context("failed to get project")?;
@@ -122,6 +186,36 @@ pub fn restore_snapshot(project_id: ProjectId, sha: String) -> Result<()> {
     Ok(())
 }
 
+/// Restores only the stack with `stack_id` to its state in a specific snapshot, leaving all other stacks untouched.
+/// This operation also creates a new snapshot in the oplog.
+///
+/// - `project_id`: The ID of the project to restore the stack in.
+/// - `sha`: The SHA of the snapshot to restore the stack from.
+/// - `stack_id`: The ID of the stack to restore, which must have been in the workspace at the time of the snapshot.
+///
+/// # Errors
+/// Returns an error if the project cannot be found, if the snapshot SHA is invalid, if the stack isn't part of the snapshot,
+/// or if there is an issue during the restore operation.
+///
+/// # Side Effects
+/// The commits, branch references and hunk assignments of the stack are reverted, and the workspace commit is updated.
+/// The working directory is left as is, so changes that were committed to the stack since become uncommitted changes.
+#[but_api(mutating)]
+#[instrument(err(Debug))]
+pub fn restore_stack(project_id: ProjectId, sha: String, stack_id: StackId) -> Result<()> {
+    let project = gitbutler_project::get(project_id).context("failed to get project")?;
+    let ctx = Context::new_from_legacy_project(project.clone())?;
+    let mut guard = ctx.exclusive_worktree_access();
+    ctx.restore_stack(
+        sha.parse().map_err(anyhow::Error::from)?,
+        stack_id,
+        guard.write_permission(),
+    )?;
+    let vb_state = VirtualBranchesHandle::new(ctx.project_data_dir());
+    update_workspace_commit(&vb_state, &ctx, false)?;
+    Ok(())
+}
+
 /// Computes the file tree difference between the the state of the project at a specific snapshot and the current state.
 /// Not all snapshots may have a meaningful file tree difference, in which case the result may be empty.
 /// An example of a snapshot that does have file tree diffs is a `CreateCommit` snapshot where the commit introduced changes to files.
@@ -142,3 +236,124 @@ pub fn snapshot_diff(project_id: ProjectId, sha: String) -> Result<Vec<but_core:
     let diff: Vec<but_core::ui::TreeChange> = diff.into_iter().map(Into::into).collect();
     Ok(diff)
 }
+
+/// Compares the structure of the workspace between two snapshots, i.e. which stacks and branches were added or removed,
+/// which commits were added, removed or rewritten, and which uncommitted hunks were assigned to another stack.
+///
+/// - `project_id`: The ID of the project to compare snapshots for.
+/// - `from_sha`: The SHA of the older snapshot.
+/// - `to_sha`: The SHA of the newer snapshot, or `None` to compare with the current state of the workspace.
+///
+/// Returns a `StackDiff` describing what changed between both points in time.
+///
+/// # Errors
+/// Returns an error if the project cannot be found, if a snapshot SHA is invalid, or if a snapshot can't be read.
+#[but_api]
+#[instrument(err(Debug))]
+pub fn stack_diff(
+    project_id: ProjectId,
+    from_sha: String,
+    to_sha: Option<String>,
+) -> Result<StackDiff> {
+    let project = gitbutler_project::get(project_id).context("failed to get project")?;
+    let ctx = Context::new_from_legacy_project(project.clone())?;
+    let guard = ctx.shared_worktree_access();
+    ctx.stack_diff(
+        from_sha.parse().map_err(anyhow::Error::from)?,
+        to_sha
+            .map(|hex| hex.parse().map_err(anyhow::Error::from))
+            .transpose()?,
+        guard.read_permission(),
+    )
+}
+
+/// Compares everything that changed between two snapshots, i.e. the structure of the workspace like [`stack_diff()`],
+/// along with the files that changed in the worktree. This allows to preview what restoring a snapshot would do.
+///
+/// - `project_id`: The ID of the project to compare snapshots for.
+/// - `from_sha`: The SHA of the older snapshot.
+/// - `to_sha`: The SHA of the newer snapshot, or `None` to compare with the current state of the workspace.
+///
+/// Returns a `SnapshotDelta` describing what changed between both points in time.
+///
+/// # Errors
+/// Returns an error if the project cannot be found, if a snapshot SHA is invalid, or if a snapshot can't be read.
+#[but_api]
+#[instrument(err(Debug))]
+pub fn snapshot_delta(
+    project_id: ProjectId,
+    from_sha: String,
+    to_sha: Option<String>,
+) -> Result<SnapshotDelta> {
+    let project = gitbutler_project::get(project_id).context("failed to get project")?;
+    let ctx = Context::new_from_legacy_project(project.clone())?;
+    let guard = ctx.shared_worktree_access();
+    ctx.snapshot_delta(
+        from_sha.parse().map_err(anyhow::Error::from)?,
+        to_sha
+            .map(|hex| hex.parse().map_err(anyhow::Error::from))
+            .transpose()?,
+        guard.read_permission(),
+    )
+}
+
+/// Lists all changes to the file at `path` made by commits of stacks, including the versions of these commits
+/// from before they were amended or rebased, as recorded in snapshots.
+///
+/// - `project_id`: The ID of the project the file belongs to.
+/// - `path`: The path of the file, relative to the root of the worktree.
+///
+/// Returns the changes to the file, the most recent first, each with all versions of the commit that made it.
+///
+/// # Errors
+/// Returns an error if the project cannot be found or if a snapshot can't be read.
+#[but_api]
+#[instrument(err(Debug))]
+pub fn file_history(project_id: ProjectId, path: String) -> Result<Vec<FileHistoryEntry>> {
+    let project = gitbutler_project::get(project_id).context("failed to get project")?;
+    let ctx = Context::new_from_legacy_project(project.clone())?;
+    let guard = ctx.shared_worktree_access();
+    ctx.file_history(path.as_str().into(), guard.read_permission())
+}
+
+/// Removes the oldest snapshots of the oplog that are too old, too many or take too much space according to the
+/// oplog settings. The most recent snapshot and all named snapshots are always kept.
+///
+/// - `project_id`: The ID of the project to prune snapshots of.
+///
+/// Returns a `PruneOutcome` with the amount of snapshots that were kept and pruned.
+///
+/// # Errors
+/// Returns an error if the project cannot be found or if the snapshots can't be rewritten.
+///
+/// # Side Effects
+/// The ids of all remaining snapshots change, as the chain of snapshots is cut off after the oldest one that is kept,
+/// so the pruned snapshots can be deleted by maintenance. This also applies to snapshots that were hidden by automatic pruning.
+#[but_api]
+#[instrument(err(Debug))]
+pub fn prune_snapshots(project_id: ProjectId) -> Result<PruneOutcome> {
+    let project = gitbutler_project::get(project_id).context("failed to get project")?;
+    let ctx = Context::new_from_legacy_project(project.clone())?;
+    let mut guard = ctx.exclusive_worktree_access();
+    ctx.prune_snapshots(guard.write_permission())
+}
+
+/// Maintains the object database of the project according to the maintenance settings: packs loose objects,
+/// deletes unreachable objects like those of pruned snapshots once they expired, and writes the commit-graph file.
+///
+/// - `project_id`: The ID of the project to maintain.
+///
+/// # Errors
+/// Returns an error if the project cannot be found, maintenance is misconfigured or Git fails.
+///
+/// # Side Effects
+/// This runs `git repack`, `git prune` and `git commit-graph`, which can take a while in large repositories.
+#[but_api]
+#[instrument(err(Debug))]
+pub fn run_maintenance(project_id: ProjectId) -> Result<()> {
+    let project = gitbutler_project::get(project_id).context("failed to get project")?;
+    let ctx = Context::new_from_legacy_project(project.clone())?;
+    let mut guard =
+        ctx.queued_exclusive_worktree_access(OperationCategory::Other, Priority::Normal)?;
+    ctx.run_maintenance(guard.write_permission())
+}
@@ -12,7 +12,7 @@ pub fn list_remotes(project_id: ProjectId) -> Result<Vec<GitRemote>> {
     project.remotes()
 }
 
-#[but_api]
+#[but_api(mutating)]
 #[instrument(err(Debug))]
 pub fn add_remote(project_id: ProjectId, name: String, url: String) -> Result<()> {
     let project = gitbutler_project::get(project_id)?;
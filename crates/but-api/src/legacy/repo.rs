@@ -5,7 +5,10 @@ use but_api_macros::but_api;
 use but_core::DiffSpec;
 use but_ctx::Context;
 use but_oxidize::ObjectIdExt;
-use gitbutler_branch_actions::hooks;
+use gitbutler_branch_actions::{
+    commit_lint::{self, LintFinding},
+    hooks,
+};
 use gitbutler_project::ProjectId;
 use gitbutler_repo::{
     FileInfo, RepoCommands,
@@ -85,6 +88,8 @@ pub fn get_blob_file(
     Ok(FileInfo::from_content(&relative_path, &blob.data))
 }
 
+/// Run the `pre-commit` hook on the tree that would be committed with `changes`,
+/// which is [not configured](HookResult::NotConfigured) if `gitbutler.runHooks` is `false`.
 #[but_api]
 #[instrument(err(Debug))]
 pub fn pre_commit_hook_diffspecs(
@@ -92,6 +97,9 @@ pub fn pre_commit_hook_diffspecs(
     changes: Vec<DiffSpec>,
 ) -> Result<HookResult> {
     let ctx = Context::new_from_legacy_project_id(project_id)?;
+    if !hooks::hooks_enabled(&ctx)? {
+        return Ok(HookResult::NotConfigured);
+    }
 
     let repository = ctx.repo.get()?;
     let head = repository
@@ -112,20 +120,45 @@ pub fn pre_commit_hook_diffspecs(
     hooks::pre_commit_with_tree(&ctx, new_tree.to_git2())
 }
 
+/// Run the `post-commit` hook, unless `gitbutler.runHooks` is `false`.
 #[but_api]
 #[instrument(err(Debug))]
 pub fn post_commit_hook(project_id: ProjectId) -> Result<HookResult> {
     let ctx = Context::new_from_legacy_project_id(project_id)?;
+    if !hooks::hooks_enabled(&ctx)? {
+        return Ok(HookResult::NotConfigured);
+    }
     gitbutler_repo::hooks::post_commit(&ctx)
 }
 
+/// Run the `commit-msg` hook on `message`, unless `gitbutler.runHooks` is `false`.
 #[but_api]
 #[instrument(err(Debug))]
 pub fn message_hook(project_id: ProjectId, message: String) -> Result<MessageHookResult> {
     let ctx = Context::new_from_legacy_project_id(project_id)?;
+    if !hooks::hooks_enabled(&ctx)? {
+        return Ok(MessageHookResult::NotConfigured);
+    }
     gitbutler_repo::hooks::commit_msg(&ctx, message)
 }
 
+/// Check `message` against the commit message rules of the project, and return all violations.
+#[but_api]
+#[instrument(err(Debug))]
+pub fn validate_commit_message(project_id: ProjectId, message: String) -> Result<Vec<LintFinding>> {
+    let ctx = Context::new_from_legacy_project_id(project_id)?;
+    commit_lint::validate_commit_message(&ctx, &message)
+}
+
+/// Return `message` with all fixable violations of the commit message rules of the project fixed,
+/// which rewraps its body.
+#[but_api]
+#[instrument(err(Debug))]
+pub fn fix_commit_message(project_id: ProjectId, message: String) -> Result<String> {
+    let ctx = Context::new_from_legacy_project_id(project_id)?;
+    commit_lint::fix_commit_message(&ctx, &message)
+}
+
 #[but_api]
 #[instrument(err(Debug))]
 pub fn find_files(
@@ -137,3 +170,23 @@ pub fn find_files(
     let limit = limit.unwrap_or(10);
     project.find_files(&query, limit)
 }
+
+/// Return the resolutions of conflicts that were recorded to resolve the same conflicts automatically
+/// when they appear again, the most recent one first.
+#[but_api]
+#[instrument(err(Debug))]
+pub fn list_recorded_resolutions(
+    project_id: ProjectId,
+) -> Result<Vec<but_rebase::rerere::RecordedResolution>> {
+    let ctx = Context::new_from_legacy_project_id(project_id)?;
+    but_rebase::rerere::list(&ctx.repo.get()?)
+}
+
+/// Forget the recorded resolution of the conflict with `fingerprint`, so the conflict has to be resolved again
+/// the next time it appears. Return `true` if the resolution was recorded.
+#[but_api]
+#[instrument(err(Debug))]
+pub fn forget_recorded_resolution(project_id: ProjectId, fingerprint: String) -> Result<bool> {
+    let ctx = Context::new_from_legacy_project_id(project_id)?;
+    but_rebase::rerere::forget(&ctx.repo.get()?, &fingerprint)
+}
@@ -3,8 +3,11 @@ use anyhow::Result;
 use but_settings::{
     AppSettingsWithDiskSync,
     api::{
-        ClaudeUpdate, FeatureFlagsUpdate, FetchUpdate, ReviewsUpdate, TelemetryUpdate, UiUpdate,
+        AiUpdate, ClaudeUpdate, DiffUpdate, FeatureFlagsUpdate, FetchUpdate, MaintenanceUpdate,
+        OperationTraceUpdate, OplogUpdate, ReviewsUpdate, TelemetryUpdate, UiUpdate,
+        WhitespaceOnlyHunksUpdate,
     },
+    app_settings::ForgeHost,
 };
 use serde::Deserialize;
 
@@ -86,6 +89,19 @@ pub fn update_reviews(
     app_settings_sync.update_reviews(params.update)
 }
 
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateForgeHostsParams {
+    pub forge_hosts: Vec<ForgeHost>,
+}
+
+pub fn update_forge_hosts(
+    app_settings_sync: &AppSettingsWithDiskSync,
+    params: UpdateForgeHostsParams,
+) -> Result<()> {
+    app_settings_sync.update_forge_hosts(params.forge_hosts)
+}
+
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct UpdateFetchParams {
@@ -99,6 +115,84 @@ pub fn update_fetch(
     app_settings_sync.update_fetch(params.update)
 }
 
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateDiffParams {
+    pub update: DiffUpdate,
+}
+
+pub fn update_diff(
+    app_settings_sync: &AppSettingsWithDiskSync,
+    params: UpdateDiffParams,
+) -> Result<()> {
+    app_settings_sync.update_diff(params.update)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateWhitespaceOnlyHunksParams {
+    pub update: WhitespaceOnlyHunksUpdate,
+}
+
+pub fn update_whitespace_only_hunks(
+    app_settings_sync: &AppSettingsWithDiskSync,
+    params: UpdateWhitespaceOnlyHunksParams,
+) -> Result<()> {
+    app_settings_sync.update_whitespace_only_hunks(params.update)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateOplogParams {
+    pub update: OplogUpdate,
+}
+
+pub fn update_oplog(
+    app_settings_sync: &AppSettingsWithDiskSync,
+    params: UpdateOplogParams,
+) -> Result<()> {
+    app_settings_sync.update_oplog(params.update)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateMaintenanceParams {
+    pub update: MaintenanceUpdate,
+}
+
+pub fn update_maintenance(
+    app_settings_sync: &AppSettingsWithDiskSync,
+    params: UpdateMaintenanceParams,
+) -> Result<()> {
+    app_settings_sync.update_maintenance(params.update)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateOperationTraceParams {
+    pub update: OperationTraceUpdate,
+}
+
+pub fn update_operation_trace(
+    app_settings_sync: &AppSettingsWithDiskSync,
+    params: UpdateOperationTraceParams,
+) -> Result<()> {
+    app_settings_sync.update_operation_trace(params.update)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateAiParams {
+    pub update: AiUpdate,
+}
+
+pub fn update_ai(
+    app_settings_sync: &AppSettingsWithDiskSync,
+    params: UpdateAiParams,
+) -> Result<()> {
+    app_settings_sync.update_ai(params.update)
+}
+
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct UpdateUiParams {
@@ -2,15 +2,21 @@ use std::borrow::Cow;
 
 use anyhow::{Context as _, Result, anyhow};
 use but_api_macros::but_api;
+use but_core::ref_metadata::{StackIdentity, StackNotes};
 use but_ctx::Context;
-use gitbutler_branch_actions::{internal::PushResult, stack::CreateSeriesRequest};
+use gitbutler_branch_actions::{
+    internal::PushResult,
+    stack::{CreateSeriesRequest, StackPushOutcome},
+};
 use gitbutler_oplog::SnapshotExt;
 use gitbutler_project::ProjectId;
 use gitbutler_reference::normalize_branch_name;
-use gitbutler_stack::StackId;
+use gitbutler_stack::{StackId, VirtualBranchesHandle};
 use gix::refs::Category;
 use tracing::instrument;
 
+use crate::json::HexHash;
+
 pub mod create_reference {
     use serde::{Deserialize, Serialize};
 
@@ -155,7 +161,7 @@ pub fn create_branch(
     Ok(())
 }
 
-#[but_api]
+#[but_api(mutating)]
 #[instrument(err(Debug))]
 pub fn remove_branch(project_id: ProjectId, stack_id: StackId, branch_name: String) -> Result<()> {
     let ctx = Context::new_from_legacy_project_id(project_id)?;
@@ -183,7 +189,7 @@ pub fn remove_branch(project_id: ProjectId, stack_id: StackId, branch_name: Stri
     Ok(())
 }
 
-#[but_api]
+#[but_api(mutating)]
 #[instrument(err(Debug))]
 pub fn update_branch_name(
     project_id: ProjectId,
@@ -196,7 +202,7 @@ pub fn update_branch_name(
     Ok(())
 }
 
-#[but_api]
+#[but_api(mutating)]
 #[instrument(err(Debug))]
 pub fn update_branch_pr_number(
     project_id: ProjectId,
@@ -214,6 +220,56 @@ pub fn update_branch_pr_number(
     Ok(())
 }
 
+#[but_api]
+#[instrument(err(Debug))]
+pub fn stack_identity(project_id: ProjectId, stack_id: StackId) -> Result<Option<StackIdentity>> {
+    let ctx = Context::new_from_legacy_project_id(project_id)?;
+    let vb_state = VirtualBranchesHandle::new(ctx.project_data_dir());
+    Ok(vb_state.get_stack(stack_id)?.identity)
+}
+
+#[but_api]
+#[instrument(err(Debug))]
+pub fn set_stack_identity(
+    project_id: ProjectId,
+    stack_id: StackId,
+    identity: Option<StackIdentity>,
+) -> Result<()> {
+    let ctx = Context::new_from_legacy_project_id(project_id)?;
+    gitbutler_branch_actions::stack::set_stack_identity(&ctx, stack_id, identity)
+}
+
+/// Return the description of the stack with `stack_id` and the notes about its commits.
+#[but_api]
+#[instrument(err(Debug))]
+pub fn stack_notes(project_id: ProjectId, stack_id: StackId) -> Result<StackNotes> {
+    let ctx = Context::new_from_legacy_project_id(project_id)?;
+    let vb_state = VirtualBranchesHandle::new(ctx.project_data_dir());
+    Ok(vb_state.get_stack(stack_id)?.stack_notes)
+}
+
+#[but_api]
+#[instrument(err(Debug))]
+pub fn set_stack_description(
+    project_id: ProjectId,
+    stack_id: StackId,
+    description: Option<String>,
+) -> Result<()> {
+    let ctx = Context::new_from_legacy_project_id(project_id)?;
+    gitbutler_branch_actions::stack::set_stack_description(&ctx, stack_id, description)
+}
+
+#[but_api]
+#[instrument(err(Debug))]
+pub fn set_commit_note(
+    project_id: ProjectId,
+    commit_id: HexHash,
+    text: Option<String>,
+) -> Result<()> {
+    let ctx = Context::new_from_legacy_project_id(project_id)?;
+    gitbutler_branch_actions::stack::set_commit_note(&ctx, commit_id.into(), text)
+}
+
 #[but_api]
 #[instrument(err(Debug))]
 pub fn push_stack(
@@ -224,6 +280,7 @@ pub fn push_stack(
     branch: String,
     run_hooks: bool,
     push_opts: Vec<but_gerrit::PushFlag>,
+    push_options: Vec<String>,
 ) -> Result<PushResult> {
     let project = gitbutler_project::get(project_id)?;
     let mut ctx = Context::new_from_legacy_project(project.clone())?;
@@ -235,5 +292,19 @@ pub fn push_stack(
         branch,
         run_hooks,
         push_opts,
+        push_options,
     )
 }
+
+/// Push all branches of the stack with `stack_id` together, atomically if the remote supports it,
+/// and report the result for each of them.
+#[but_api(mutating)]
+#[instrument(err(Debug))]
+pub fn push_stack_all(
+    project_id: ProjectId,
+    stack_id: StackId,
+    with_force: bool,
+) -> Result<StackPushOutcome> {
+    let mut ctx = Context::new_from_legacy_project_id(project_id)?;
+    gitbutler_branch_actions::stack::push_stack_all(&mut ctx, stack_id, with_force)
+}
@@ -3,12 +3,15 @@ use std::collections::HashMap;
 use anyhow::{Context as _, Result, anyhow};
 use but_api_macros::but_api;
 use but_core::DiffSpec;
-use but_ctx::Context;
+use but_ctx::{
+    Context,
+    access::{OperationCategory, Priority},
+};
 use but_workspace::legacy::ui::{StackEntryNoOpt, StackHeadInfo};
 use gitbutler_branch::{BranchCreateRequest, BranchUpdateRequest};
 use gitbutler_branch_actions::{
-    BaseBranch, BranchListing, BranchListingDetails, BranchListingFilter, MoveBranchResult,
-    MoveCommitIllegalAction, StackOrder,
+    BaseBranch, BranchListing, BranchListingDetails, BranchListingFilter, CherryPickedCommit,
+    MoveBranchResult, MoveCommitIllegalAction, PatchApplyMode, RevertMode, StackOrder,
     branch_upstream_integration::IntegrationStrategy,
     upstream_integration::{
         BaseBranchResolution, BaseBranchResolutionApproach, IntegrationOutcome, Resolution,
@@ -21,7 +24,10 @@ use gitbutler_stack::StackId;
 use gix::reference::Category;
 use tracing::instrument;
 
-use crate::{json::Error, legacy::workspace::canned_branch_name};
+use crate::{
+    json::{Error, HexHash},
+    legacy::workspace::canned_branch_name,
+};
 // Parameter structs for all functions
 
 #[but_api]
@@ -129,6 +135,20 @@ pub fn integrate_upstream_commits(
     Ok(())
 }
 
+/// Integrate the upstream commits of all branches of the stack with `stack_id` using `strategy`,
+/// leaving the target and all other stacks as they are.
+#[but_api]
+#[instrument(err(Debug))]
+pub fn integrate_upstream_for_stack(
+    project_id: ProjectId,
+    stack_id: StackId,
+    strategy: IntegrationStrategy,
+) -> Result<()> {
+    let ctx = Context::new_from_legacy_project_id(project_id)?;
+    gitbutler_branch_actions::integrate_upstream_for_stack(&ctx, stack_id, strategy)?;
+    Ok(())
+}
+
 #[but_api]
 #[instrument(err(Debug))]
 pub fn get_initial_integration_steps_for_branch(
@@ -200,7 +220,7 @@ pub fn get_base_branch_data(project_id: ProjectId) -> Result<Option<BaseBranch>>
     }
 }
 
-#[but_api]
+#[but_api(mutating)]
 #[instrument(err(Debug))]
 pub fn set_base_branch(
     project_id: ProjectId,
@@ -234,7 +254,34 @@ pub fn set_base_branch(
     Ok(base_branch)
 }
 
-#[but_api]
+/// Make `branch`, like `origin/release/1.2`, the new target branch and rebase all applied stacks onto it.
+/// Stacks that would conflict with it are unapplied, unless `abort_on_incompatible` is `true`, which leaves
+/// everything as is instead. The workspace is restored if the switch fails midway.
+#[but_api(mutating)]
+#[instrument(err(Debug))]
+pub fn switch_base_branch(
+    project_id: ProjectId,
+    branch: String,
+    abort_on_incompatible: bool,
+) -> Result<gitbutler_branch_actions::base_switch::BaseBranchSwitch> {
+    let mut ctx = Context::new_from_legacy_project_id(project_id)?;
+    let branch_name = format!("refs/remotes/{branch}")
+        .parse()
+        .context("Invalid branch name")?;
+    let outcome =
+        gitbutler_branch_actions::switch_base_branch(&ctx, &branch_name, abort_on_incompatible)?;
+    {
+        let mut guard = ctx.exclusive_worktree_access();
+        crate::legacy::meta::reconcile_in_workspace_state_of_vb_toml(
+            &mut ctx,
+            guard.write_permission(),
+        )
+        .ok();
+    }
+    Ok(outcome)
+}
+
+#[but_api(mutating)]
 #[instrument(err(Debug))]
 pub fn push_base_branch(project_id: ProjectId, with_force: bool) -> Result<()> {
     let ctx = Context::new_from_legacy_project_id(project_id)?;
@@ -250,7 +297,7 @@ pub fn update_stack_order(project_id: ProjectId, stacks: Vec<BranchUpdateRequest
     Ok(())
 }
 
-#[but_api]
+#[but_api(mutating)]
 #[instrument(err(Debug))]
 pub fn unapply_stack(project_id: ProjectId, stack_id: StackId) -> Result<()> {
     let project = gitbutler_project::get(project_id)?;
@@ -285,7 +332,7 @@ pub fn unapply_stack(project_id: ProjectId, stack_id: StackId) -> Result<()> {
     Ok(())
 }
 
-#[but_api]
+#[but_api(mutating)]
 #[instrument(err(Debug))]
 pub fn amend_virtual_branch(
     project_id: ProjectId,
@@ -299,7 +346,7 @@ pub fn amend_virtual_branch(
     Ok(oid.to_string())
 }
 
-#[but_api]
+#[but_api(mutating)]
 #[instrument(err(Debug))]
 pub fn undo_commit(project_id: ProjectId, stack_id: StackId, commit_id: String) -> Result<()> {
     let ctx = Context::new_from_legacy_project_id(project_id)?;
@@ -342,7 +389,7 @@ pub fn get_branch_listing_details(
     Ok(branches)
 }
 
-#[but_api]
+#[but_api(mutating)]
 #[instrument(err(Debug))]
 pub fn squash_commits(
     project_id: ProjectId,
@@ -366,6 +413,217 @@ pub fn squash_commits(
     Ok(())
 }
 
+#[but_api(mutating)]
+#[instrument(err(Debug))]
+pub fn cherry_pick_into_stack(
+    project_id: ProjectId,
+    stack_id: StackId,
+    commit_ids: Vec<HexHash>,
+    mainline: Option<usize>,
+) -> Result<Vec<CherryPickedCommit>> {
+    let ctx = Context::new_from_legacy_project_id(project_id)?;
+    gitbutler_branch_actions::cherry_pick_into_stack(
+        &ctx,
+        stack_id,
+        commit_ids.into_iter().map(Into::into).collect(),
+        mainline,
+    )
+}
+
+/// The outcome of reverting a commit.
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RevertedCommit {
+    /// The commit that reverts the original one, if the revert was committed.
+    #[serde(with = "but_serde::object_id_opt")]
+    pub new_commit: Option<gix::ObjectId>,
+    /// If `true`, the new commit couldn't be created cleanly and is now conflicted.
+    pub conflicted: bool,
+    /// Reverted hunks in the worktree that couldn't be assigned to the stack, as they depend on other stacks.
+    pub rejections: Vec<but_hunk_assignment::AssignmentRejection>,
+}
+
+/// Revert the commit with `commit_id` in the stack with `stack_id`.
+///
+/// By default, a new commit with the inverse changes is placed on top of the stack.
+/// With `mode` set to `worktree`, the inverse changes are applied to the worktree instead,
+/// and assigned to the stack so they can be committed or amended as needed.
+#[but_api]
+#[instrument(err(Debug))]
+pub fn revert_commit(
+    project_id: ProjectId,
+    stack_id: StackId,
+    commit_id: HexHash,
+    mode: Option<RevertMode>,
+) -> Result<RevertedCommit> {
+    let mut ctx = Context::new_from_legacy_project_id(project_id)?;
+    let mut guard =
+        ctx.queued_exclusive_worktree_access(OperationCategory::Rebase, Priority::Normal)?;
+    let outcome = gitbutler_branch_actions::revert_commit(
+        &ctx,
+        stack_id,
+        commit_id.into(),
+        mode.unwrap_or_default(),
+        guard.write_permission(),
+    )?;
+    // Assign under the same guard so nothing can change the worktree in between.
+    let rejections = if outcome.worktree_changes.is_empty() {
+        Vec::new()
+    } else {
+        let repo = ctx.repo.get()?.clone();
+        let (_, workspace) = ctx.workspace_and_read_only_meta_from_head(guard.read_permission())?;
+        let requests = worktree_assignment_requests(
+            &repo,
+            &outcome.worktree_changes,
+            stack_id,
+            ctx.settings().context_lines,
+        )?;
+        but_hunk_assignment::assign(&mut ctx, &repo, &workspace, requests, None)?
+    };
+    Ok(RevertedCommit {
+        new_commit: outcome.new_commit,
+        conflicted: outcome.conflicted,
+        rejections,
+    })
+}
+
+/// Produce requests to assign all worktree hunks that overlap with the `applied` changes to `stack_id`.
+fn worktree_assignment_requests(
+    repo: &gix::Repository,
+    applied: &[but_core::TreeChange],
+    stack_id: StackId,
+    context_lines: u32,
+) -> Result<Vec<but_hunk_assignment::HunkAssignmentRequest>> {
+    let mut requests = Vec::new();
+    for change in but_core::diff::worktree_changes(repo)?.changes {
+        let Some(applied) = applied.iter().find(|c| c.path == change.path) else {
+            continue;
+        };
+        let applied_ranges: Vec<_> = match applied.unified_patch(repo, 0)? {
+            Some(but_core::UnifiedPatch::Patch { hunks, .. }) => hunks
+                .into_iter()
+                .map(|hunk| but_core::HunkHeader::from(hunk).new_range())
+                .collect(),
+            _ => Vec::new(),
+        };
+        match change.unified_patch(repo, context_lines)? {
+            Some(but_core::UnifiedPatch::Patch { hunks, .. }) => {
+                for hunk in hunks {
+                    let header = but_core::HunkHeader::from(hunk);
+                    if applied_ranges
+                        .iter()
+                        .any(|range| header.new_range().intersects(*range))
+                    {
+                        requests.push(but_hunk_assignment::HunkAssignmentRequest {
+                            hunk_header: Some(header),
+                            path_bytes: change.path.clone(),
+                            stack_id: Some(stack_id),
+                        });
+                    }
+                }
+            }
+            _ => requests.push(but_hunk_assignment::HunkAssignmentRequest {
+                hunk_header: None,
+                path_bytes: change.path.clone(),
+                stack_id: Some(stack_id),
+            }),
+        }
+    }
+    Ok(requests)
+}
+
+/// The outcome of applying a patch series.
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppliedPatchSeries {
+    /// All applied patches, in order, along with the commits created for them.
+    pub patches: Vec<gitbutler_branch_actions::AppliedPatch>,
+    /// Applied hunks in the worktree that couldn't be assigned to the stack, as they depend on other stacks.
+    pub rejections: Vec<but_hunk_assignment::AssignmentRejection>,
+}
+
+/// Apply the patches in `files`, which are mbox files as written by `git format-patch` or plain diffs,
+/// to the stack with `stack_id`.
+///
+/// By default, a commit is created for each patch on top of the stack, keeping the author and date of the mail.
+/// With `mode` set to `worktree`, the changes are applied to the worktree instead to review them first,
+/// and assigned to the stack so they can be committed as needed.
+#[but_api]
+#[instrument(err(Debug))]
+pub fn apply_patch_series(
+    project_id: ProjectId,
+    stack_id: StackId,
+    files: Vec<std::path::PathBuf>,
+    mode: Option<PatchApplyMode>,
+) -> Result<AppliedPatchSeries> {
+    let mut ctx = Context::new_from_legacy_project_id(project_id)?;
+    let outcome = gitbutler_branch_actions::apply_patch_series(
+        &ctx,
+        stack_id,
+        files,
+        mode.unwrap_or_default(),
+    )?;
+    let rejections = if outcome.worktree_changes.is_empty() {
+        Vec::new()
+    } else {
+        let guard = ctx.shared_worktree_access();
+        let repo = ctx.repo.get()?.clone();
+        let (_, workspace) = ctx.workspace_and_read_only_meta_from_head(guard.read_permission())?;
+        let requests = worktree_assignment_requests(
+            &repo,
+            &outcome.worktree_changes,
+            stack_id,
+            ctx.settings().context_lines,
+        )?;
+        drop(guard);
+        but_hunk_assignment::assign(&mut ctx, &repo, &workspace, requests, None)?
+    };
+    Ok(AppliedPatchSeries {
+        patches: outcome.patches,
+        rejections,
+    })
+}
+
+/// Return the commit that `commit_id` was most recently rewritten into by rebases and amends,
+/// or `None` if it wasn't rewritten, to find out where a commit went.
+#[but_api]
+#[instrument(err(Debug))]
+pub fn resolve_rewritten(project_id: ProjectId, commit_id: HexHash) -> Result<Option<HexHash>> {
+    let ctx = Context::new_from_legacy_project_id(project_id)?;
+    Ok(gitbutler_branch_actions::resolve_rewritten(&ctx, commit_id.into())?.map(Into::into))
+}
+
+/// Return the commits that were made on top of the workspace commit with Git directly, the oldest one first.
+#[but_api]
+#[instrument(err(Debug))]
+pub fn external_commits(project_id: ProjectId) -> Result<Vec<crate::json::HexHash>> {
+    let ctx = Context::new_from_legacy_project_id(project_id)?;
+    let _guard = ctx.shared_worktree_access();
+    Ok(gitbutler_branch_actions::external_commits(&ctx)?
+        .into_iter()
+        .map(Into::into)
+        .collect())
+}
+
+/// Move the commits that were made on top of the workspace commit with Git directly onto the stack with `stack_id`,
+/// or onto a new stack if it's not set.
+#[but_api(mutating)]
+#[instrument(err(Debug))]
+pub fn adopt_external_commits(
+    project_id: ProjectId,
+    stack_id: Option<StackId>,
+) -> Result<gitbutler_branch_actions::AdoptedCommits> {
+    let ctx = Context::new_from_legacy_project_id(project_id)?;
+    gitbutler_branch_actions::adopt_external_commits(&ctx, stack_id)
+}
+
+#[but_api(mutating)]
+#[instrument(err(Debug))]
+pub fn autosquash_stack(project_id: ProjectId, stack_id: StackId) -> Result<usize> {
+    let ctx = Context::new_from_legacy_project_id(project_id)?;
+    gitbutler_branch_actions::autosquash_stack(&ctx, stack_id)
+}
+
 #[but_api]
 #[instrument(err(Debug))]
 pub fn fetch_from_remotes(project_id: ProjectId, action: Option<String>) -> Result<BaseBranch> {
@@ -393,7 +651,7 @@ pub fn fetch_from_remotes(project_id: ProjectId, action: Option<String>) -> Resu
     Ok(base_branch)
 }
 
-#[but_api]
+#[but_api(mutating)]
 #[instrument(err(Debug))]
 pub fn move_commit(
     project_id: ProjectId,
@@ -406,7 +664,7 @@ pub fn move_commit(
     gitbutler_branch_actions::move_commit(&ctx, target_stack_id, commit_id, source_stack_id)
 }
 
-#[but_api]
+#[but_api(mutating)]
 #[instrument(err(Debug))]
 pub fn move_branch(
     project_id: ProjectId,
@@ -425,7 +683,7 @@ pub fn move_branch(
     )
 }
 
-#[but_api]
+#[but_api(mutating)]
 #[instrument(err(Debug))]
 pub fn tear_off_branch(
     project_id: ProjectId,
@@ -436,7 +694,7 @@ pub fn tear_off_branch(
     gitbutler_branch_actions::tear_off_branch(&ctx, source_stack_id, subject_branch_name.as_str())
 }
 
-#[but_api]
+#[but_api(mutating)]
 #[instrument(err(Debug))]
 pub fn update_commit_message(
     project_id: ProjectId,
@@ -526,6 +784,166 @@ pub async fn resolve_upstream_integration(
     Ok(commit_id)
 }
 
+/// For each branch of the stack with `stack_id`, list the commits that only exist locally or only on
+/// its remote tracking branch, top-most branch first.
+#[but_api]
+#[instrument(err(Debug))]
+pub fn branch_divergence(
+    project_id: ProjectId,
+    stack_id: StackId,
+) -> Result<Vec<gitbutler_branch_actions::branch_divergence::BranchDivergence>> {
+    let ctx = Context::new_from_legacy_project_id(project_id)?;
+    gitbutler_branch_actions::branch_divergence(&ctx, stack_id)
+}
+
+/// Return the base, our and their version of each file the commit with `commit_id` conflicts in, split into hunks,
+/// along with the commits and branches the sides come from, or `None` if the commit isn't conflicted.
+#[but_api]
+#[instrument(err(Debug))]
+pub fn conflict_details(
+    project_id: ProjectId,
+    commit_id: HexHash,
+) -> Result<Option<gitbutler_branch_actions::conflict_details::ConflictDetails>> {
+    let ctx = Context::new_from_legacy_project_id(project_id)?;
+    gitbutler_branch_actions::conflict_details(&ctx, commit_id.into())
+}
+
+/// Write the commits of each branch of the stack with `stack_id` as patch series with a cover letter into `out_dir`,
+/// ready to be sent to a mailing list.
+#[but_api]
+#[instrument(err(Debug))]
+pub fn export_patches(
+    project_id: ProjectId,
+    stack_id: StackId,
+    out_dir: std::path::PathBuf,
+) -> Result<Vec<gitbutler_branch_actions::patch_export::PatchSeries>> {
+    let ctx = Context::new_from_legacy_project_id(project_id)?;
+    gitbutler_branch_actions::export_patches(&ctx, stack_id, &out_dir)
+}
+
+/// Write the stack with `stack_id` to a Git bundle at `path` for sharing it without a remote,
+/// along with its metadata, including the uncommitted changes assigned to it, next to it as `<path>.json`.
+#[but_api]
+#[instrument(err(Debug))]
+pub fn export_stack_bundle(
+    project_id: ProjectId,
+    stack_id: StackId,
+    path: std::path::PathBuf,
+) -> Result<()> {
+    let project = gitbutler_project::get(project_id)?;
+    let ctx = &mut Context::new_from_legacy_project(project.clone())?;
+    let assigned_diffspec = {
+        let guard = ctx.shared_worktree_access();
+        let repo = ctx.repo.get()?.clone();
+        let (_, workspace) = ctx.workspace_and_read_only_meta_from_head(guard.read_permission())?;
+        let (assignments, _) = but_hunk_assignment::assignments_with_fallback(
+            ctx,
+            &repo,
+            &workspace,
+            false,
+            Some(
+                but_core::diff::ui::worktree_changes_by_worktree_dir(
+                    project.worktree_dir()?.into(),
+                )?
+                .changes,
+            ),
+            None,
+        )?;
+        assignments
+            .into_iter()
+            .filter(|a| a.stack_id == Some(stack_id))
+            .map(|a| a.into())
+            .collect::<Vec<DiffSpec>>()
+    };
+    let metadata =
+        gitbutler_branch_actions::export_stack_bundle(ctx, stack_id, &path, assigned_diffspec)?;
+    let metadata_path = gitbutler_branch_actions::stack_bundle::metadata_path(&path);
+    std::fs::write(&metadata_path, serde_json::to_string_pretty(&metadata)?).with_context(
+        || {
+            format!(
+                "Could not write stack bundle metadata to '{}'",
+                metadata_path.display()
+            )
+        },
+    )?;
+    Ok(())
+}
+
+/// The outcome of importing a stack bundle.
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportedStackBundle {
+    /// The id of the stack that was created from the bundle.
+    pub stack_id: StackId,
+    /// Uncommitted changes of the bundle that couldn't be assigned to the stack, as they depend on other stacks.
+    pub rejections: Vec<but_hunk_assignment::AssignmentRejection>,
+}
+
+/// Recreate the stack stored in the Git bundle at `path` by [`export_stack_bundle()`], with its metadata read from
+/// `<path>.json`, and apply it to the workspace along with its uncommitted changes.
+#[but_api(mutating)]
+#[instrument(err(Debug))]
+pub fn import_stack_bundle(
+    project_id: ProjectId,
+    path: std::path::PathBuf,
+) -> Result<ImportedStackBundle> {
+    let mut ctx = Context::new_from_legacy_project_id(project_id)?;
+    let metadata_path = gitbutler_branch_actions::stack_bundle::metadata_path(&path);
+    let metadata = std::fs::read(&metadata_path).with_context(|| {
+        format!(
+            "Could not read stack bundle metadata from '{}'",
+            metadata_path.display()
+        )
+    })?;
+    let metadata = serde_json::from_slice(&metadata)?;
+    let outcome = gitbutler_branch_actions::import_stack_bundle(&ctx, &path, &metadata)?;
+    let rejections = if outcome.worktree_changes.is_empty() {
+        Vec::new()
+    } else {
+        let guard = ctx.shared_worktree_access();
+        let repo = ctx.repo.get()?.clone();
+        let (_, workspace) = ctx.workspace_and_read_only_meta_from_head(guard.read_permission())?;
+        let requests = worktree_assignment_requests(
+            &repo,
+            &outcome.worktree_changes,
+            outcome.stack_id,
+            ctx.settings().context_lines,
+        )?;
+        drop(guard);
+        but_hunk_assignment::assign(&mut ctx, &repo, &workspace, requests, None)?
+    };
+    Ok(ImportedStackBundle {
+        stack_id: outcome.stack_id,
+        rejections,
+    })
+}
+
+/// List the applied stacks whose changes are already part of the remote target branch, be it by merge,
+/// squash-merge or a merged review, so they can be archived.
+#[but_api]
+#[instrument(err(Debug))]
+pub async fn detect_integrated_stacks(
+    project_id: ProjectId,
+) -> Result<Vec<gitbutler_branch_actions::integrated_stacks::IntegratedStack>> {
+    let project = gitbutler_project::get(project_id)?;
+    let (base_branch, sync_ctx) = {
+        let ctx = Context::new_from_legacy_project(project.clone())?;
+        let base_branch = gitbutler_branch_actions::base::get_base_branch_data(&ctx)?;
+        (base_branch, ctx.into_sync())
+    };
+    let resolved_reviews = resolve_review_map(project, &base_branch).await?;
+    let ctx = sync_ctx.into_thread_local();
+    gitbutler_branch_actions::detect_integrated_stacks(&ctx, &resolved_reviews)
+}
+
+/// Unapply the stack with `stack_id` and delete its local branches, as is done for integrated stacks.
+#[but_api(mutating)]
+#[instrument(err(Debug))]
+pub fn archive_stack(project_id: ProjectId, stack_id: StackId) -> Result<()> {
+    let ctx = Context::new_from_legacy_project_id(project_id)?;
+    gitbutler_branch_actions::archive_stack(&ctx, stack_id)
+}
+
 /// Resolve all actively applied reviews for the given project and command context
 async fn resolve_review_map(
     project: gitbutler_project::Project,
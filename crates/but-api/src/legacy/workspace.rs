@@ -3,7 +3,10 @@ use std::{collections::HashSet, str::FromStr};
 use anyhow::{Context as _, Result};
 use but_api_macros::but_api;
 use but_core::{RepositoryExt, sync::WorkspaceWriteGuard};
-use but_ctx::Context;
+use but_ctx::{
+    Context,
+    access::{OperationCategory, Priority},
+};
 use but_hunk_assignment::HunkAssignmentRequest;
 use but_meta::VirtualBranchesTomlMetadata;
 use but_settings::AppSettings;
@@ -45,6 +48,84 @@ pub fn head_info(project_id: ProjectId) -> Result<but_workspace::ui::RefInfo> {
     })
 }
 
+/// Return the commits of all applied stacks and their base, laid out in lanes for drawing.
+/// Commits only on the remote tracking branches are included if `upstream_commits` is `true`.
+#[but_api]
+#[instrument(err(Debug))]
+pub fn workspace_graph(
+    project_id: ProjectId,
+    upstream_commits: Option<bool>,
+) -> Result<but_workspace::WorkspaceGraph> {
+    let ctx = Context::new_from_legacy_project_id(project_id)?;
+    let repo = ctx.clone_repo_for_merging_non_persisting()?;
+    let meta = ref_metadata_toml(&ctx.legacy_project)?;
+    but_workspace::graph(
+        &repo,
+        &meta,
+        but_workspace::graph::Options {
+            ref_info: but_workspace::ref_info::Options {
+                traversal: but_graph::init::Options::limited(),
+                expensive_commit_info: false,
+            },
+            upstream_commits: upstream_commits.unwrap_or(true),
+        },
+    )
+}
+
+/// Compute figures about all stacks of the workspace, like their size, their age and signs of upcoming conflicts,
+/// along with the amount of uncommitted hunks that aren't assigned to any stack.
+#[but_api]
+#[instrument(err(Debug))]
+pub fn workspace_stats(project_id: ProjectId) -> Result<but_workspace::WorkspaceStats> {
+    let mut ctx = Context::new_from_legacy_project_id(project_id)?;
+    let repo = ctx.clone_repo_for_merging_non_persisting()?;
+    let meta = ref_metadata_toml(&ctx.legacy_project)?;
+    let info = but_workspace::head_info(
+        &repo,
+        &meta,
+        but_workspace::ref_info::Options {
+            traversal: but_graph::init::Options::limited(),
+            expensive_commit_info: true,
+        },
+    )?;
+
+    let guard = ctx.shared_worktree_access();
+    let (_, workspace) = ctx.workspace_and_read_only_meta_from_head(guard.read_permission())?;
+    let (assignments, _) = but_hunk_assignment::assignments_with_fallback(
+        &mut ctx,
+        &repo,
+        &workspace,
+        false,
+        Some(but_core::diff::worktree_changes(&repo)?.changes),
+        None,
+    )?;
+    but_workspace::WorkspaceStats::from_ref_info(
+        &repo,
+        &info,
+        assignments
+            .into_iter()
+            .map(|assignment| assignment.stack_id),
+        gix::date::Time::now_local_or_utc().seconds,
+    )
+}
+
+/// Check the workspace for consistency without changing anything.
+#[but_api]
+#[instrument(err(Debug))]
+pub fn fsck(project_id: ProjectId) -> Result<Vec<but_workspace::legacy::IntegrityIssue>> {
+    let ctx = Context::new_from_legacy_project_id(project_id)?;
+    let _guard = ctx.shared_worktree_access();
+    but_workspace::legacy::fsck(&ctx)
+}
+
+/// Check the workspace for consistency and fix the issues that can be fixed automatically.
+#[but_api]
+#[instrument(err(Debug))]
+pub fn repair_workspace(project_id: ProjectId) -> Result<gitbutler_branch_actions::RepairOutcome> {
+    let ctx = Context::new_from_legacy_project_id(project_id)?;
+    gitbutler_branch_actions::repair_workspace(&ctx)
+}
+
 #[but_api]
 #[instrument(err(Debug))]
 pub fn stacks(
@@ -244,7 +325,9 @@ pub fn branch_details(
 /// hunks would fail.
 /// `stack_branch_name` is the short name of the reference that the UI knows is present in a given segment.
 /// It is necessary to insert the new commit into the right bucket.
-#[but_api]
+/// The Git hooks are run around the commit unless `gitbutler.runHooks` is `false`, or `run_hooks` is `Some(false)`
+/// for callers that run them on their own, for instance to report on their progress.
+#[but_api(mutating)]
 #[instrument(err(Debug))]
 pub fn create_commit_from_worktree_changes(
     project_id: ProjectId,
@@ -253,12 +336,25 @@ pub fn create_commit_from_worktree_changes(
     worktree_changes: Vec<but_core::DiffSpec>,
     message: String,
     stack_branch_name: String,
+    run_hooks: Option<bool>,
 ) -> Result<commit_engine::ui::CreateCommitOutcome> {
     let ctx = Context::new_from_legacy_project_id(project_id)?;
-    let mut guard = ctx.exclusive_worktree_access();
+    let mut guard =
+        ctx.queued_exclusive_worktree_access(OperationCategory::Commit, Priority::Normal)?;
+    let message = gitbutler_branch_actions::message_template::apply_message_template(
+        &ctx,
+        stack_id,
+        &stack_branch_name,
+        &message,
+    )?;
     let snapshot_tree = ctx.prepare_snapshot(guard.read_permission());
 
-    let outcome = but_workspace::legacy::commit_engine::create_commit_simple(
+    let create_commit = if run_hooks.unwrap_or(true) {
+        gitbutler_branch_actions::hooks::create_commit_with_hooks
+    } else {
+        but_workspace::legacy::commit_engine::create_commit_simple
+    };
+    let outcome = create_commit(
         &ctx,
         stack_id,
         parent_id.map(|id| id.into()),
@@ -287,7 +383,7 @@ pub fn create_commit_from_worktree_changes(
 /// All `changes` are meant to be relative to the worktree.
 /// Note that submodules *must* be provided as diffspec without hunks, as attempting to generate
 /// hunks would fail.
-#[but_api]
+#[but_api(mutating)]
 #[instrument(err(Debug))]
 pub fn amend_commit_from_worktree_changes(
     ctx: &Context,
@@ -342,7 +438,7 @@ pub fn amend_commit_and_count_failures(
 /// If whole files should be discarded, be sure to not pass any hunks
 ///
 /// Returns the `worktree_changes` that couldn't be applied,
-#[but_api]
+#[but_api(mutating)]
 #[instrument(err(Debug))]
 pub fn discard_worktree_changes(
     project_id: ProjectId,
@@ -386,7 +482,7 @@ mod json {
     }
 }
 
-#[but_api]
+#[but_api(mutating)]
 #[instrument(err(Debug))]
 pub fn move_changes_between_commits(
     project_id: ProjectId,
@@ -419,7 +515,7 @@ pub fn move_changes_between_commits(
     Ok(result.into())
 }
 
-#[but_api]
+#[but_api(mutating)]
 #[instrument(err(Debug))]
 pub fn split_branch(
     project_id: ProjectId,
@@ -460,7 +556,7 @@ pub fn split_branch(
     Ok(move_changes_result.into())
 }
 
-#[but_api]
+#[but_api(mutating)]
 #[instrument(err(Debug))]
 pub fn split_branch_into_dependent_branch(
     project_id: ProjectId,
@@ -497,7 +593,7 @@ pub fn split_branch_into_dependent_branch(
 /// If `assign_to` is provided, the changes will be assigned to the stack
 /// specified.
 /// If `assign_to` is not provided, the changes will be unassigned.
-#[but_api]
+#[but_api(mutating)]
 #[instrument(err(Debug))]
 pub fn uncommit_changes(
     project_id: ProjectId,
@@ -585,7 +681,7 @@ pub fn uncommit_changes(
 /// Unlike the regular stash, the user specifies a new branch where those changes will be 'saved'/committed.
 /// Immediately after the changes are committed, the branch is unapplied from the workspace, and the "stash" branch can be re-applied at a later time
 /// In theory it should be possible to specify an existing "dumping" branch for this, but currently this endpoint expects a new branch.
-#[but_api]
+#[but_api(mutating)]
 #[instrument(err(Debug))]
 pub fn stash_into_branch(
     project_id: ProjectId,
@@ -659,6 +755,20 @@ pub fn canned_branch_name(project_id: ProjectId) -> Result<String> {
     gitbutler_stack::Stack::next_available_name(&repo, &state, template, false)
 }
 
+/// Returns a new available branch name describing `changes`, keeping the initials of the template
+/// used by [`canned_branch_name()`]. If `use_ai` is `true`, the AI provider configured for commit messages
+/// is asked for it.
+#[but_api]
+#[instrument(err(Debug))]
+pub fn suggest_branch_name(
+    project_id: ProjectId,
+    changes: Vec<but_core::DiffSpec>,
+    use_ai: bool,
+) -> Result<String> {
+    let mut ctx = Context::new_from_legacy_project_id(project_id)?;
+    but_action::branch_name::suggest_branch_name(&mut ctx, changes, use_ai)
+}
+
 #[but_api]
 #[instrument(err(Debug))]
 pub fn target_commits(
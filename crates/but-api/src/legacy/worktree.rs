@@ -28,7 +28,7 @@ pub fn worktree_new(
 pub fn worktree_list(project_id: ProjectId) -> Result<ListWorktreeOutcome> {
     let project = gitbutler_project::get(project_id)?;
     let mut ctx = Context::new_from_legacy_project(project.clone())?;
-    let guard = ctx.exclusive_worktree_access();
+    let guard = ctx.shared_worktree_access();
 
     but_worktrees::list::worktree_list(&mut ctx, guard.read_permission())
 }
@@ -42,7 +42,7 @@ pub fn worktree_integration_status(
 ) -> Result<WorktreeIntegrationStatus> {
     let project = gitbutler_project::get(project_id)?;
     let mut ctx = Context::new_from_legacy_project(project.clone())?;
-    let guard = ctx.exclusive_worktree_access();
+    let guard = ctx.shared_worktree_access();
 
     but_worktrees::integrate::worktree_integration_status(
         &mut ctx,
@@ -11,14 +11,25 @@
 #[cfg(feature = "legacy")]
 pub mod legacy;
 
+/// An opt-in, local trace of the mutating operations of a project.
+#[cfg(feature = "legacy")]
+pub mod operation_trace;
+
 pub mod github;
 
+pub mod gitlab;
+
+pub mod bitbucket;
+
 /// Functions that take a branch as input.
 pub mod branch;
 
 /// Functions that operate commits
 pub mod commit;
 
+/// Functions to inspect and cancel the queued operations of a project.
+pub mod operations;
+
 /// Functions that show what changed in various Git entities, like trees, commits and the worktree.
 pub mod diff;
 
@@ -0,0 +1,185 @@
+//! An opt-in trace of the mutating operations of a project, to help debugging without telemetry.
+//!
+//! If [enabled](but_settings::app_settings::OperationTrace), each call of a function marked with
+//! `#[but_api(mutating)]` appends one JSON line to [`TRACE_FILE`] in the GitButler directory of its project.
+//! The trace never leaves the machine unless the user attaches it to a bug report, which is why only
+//! the shape of the input is recorded, but never its values.
+use std::{
+    io::Write as _,
+    path::{Path, PathBuf},
+    time::{Instant, SystemTime},
+};
+
+use but_error::AnyhowContextExt as _;
+use but_settings::{AppSettings, app_settings::OperationTrace};
+use gitbutler_project::ProjectId;
+use serde::{Deserialize, Serialize};
+
+/// The name of the trace file in the GitButler directory of a project.
+pub const TRACE_FILE: &str = "operations.jsonl";
+
+/// A line of the trace, describing one call of a mutating operation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TraceEntry {
+    /// When the operation started, in milliseconds since the Unix epoch.
+    pub started_at_ms: u64,
+    /// The name of the API function that was called.
+    pub operation: String,
+    /// How long the operation took, in milliseconds.
+    pub duration_ms: u64,
+    /// The shape of the parameters the operation was called with, see [`summarize()`].
+    pub input: serde_json::Value,
+    /// `ok`, or the code of the error the operation failed with, like `errors.unknown`.
+    pub result: String,
+}
+
+/// A mutating operation in progress, which is written to the trace once it's finished.
+pub struct Operation {
+    name: &'static str,
+    trace_path: PathBuf,
+    settings: OperationTrace,
+    input: serde_json::Value,
+    started_at: SystemTime,
+    start: Instant,
+}
+
+impl Operation {
+    /// Start tracing the operation `name` of the project with `project_id`, with `input` producing its parameters.
+    ///
+    /// Returns `None` if there is no project, or if the trace isn't enabled for it, without calling `input`.
+    pub fn start(
+        name: &'static str,
+        project_id: Option<ProjectId>,
+        input: impl FnOnce() -> serde_json::Value,
+    ) -> Option<Self> {
+        let project = gitbutler_project::get(project_id?).ok()?;
+        let settings = AppSettings::load_from_default_path_creating_without_customization()
+            .ok()?
+            .with_project_overrides_or_unchanged(project.git_dir())
+            .operation_trace;
+        if !settings.enabled {
+            return None;
+        }
+        Some(Operation {
+            name,
+            trace_path: project.gb_dir().join(TRACE_FILE),
+            settings,
+            input: summarize(&input()),
+            started_at: SystemTime::now(),
+            start: Instant::now(),
+        })
+    }
+
+    /// Append the operation along with the outcome of `result` to the trace.
+    ///
+    /// Failing to do so is logged, but never affects the operation itself.
+    pub fn finish<T>(self, result: &anyhow::Result<T>) {
+        let entry = TraceEntry {
+            started_at_ms: self
+                .started_at
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .map(|duration| duration.as_millis() as u64)
+                .unwrap_or_default(),
+            operation: self.name.to_owned(),
+            duration_ms: self.start.elapsed().as_millis() as u64,
+            input: self.input,
+            result: match result {
+                Ok(_) => "ok".into(),
+                Err(err) => err.custom_context_or_root_cause().code.to_string(),
+            },
+        };
+        if let Err(err) = append(&self.trace_path, &entry, &self.settings) {
+            tracing::warn!(
+                "Could not write operation trace to '{}': {err:#}",
+                self.trace_path.display()
+            );
+        }
+    }
+}
+
+/// Return the shape of `value`, with strings and arrays replaced by their length.
+///
+/// Objects keep their keys, and numbers, booleans and `null` are kept as they are.
+pub fn summarize(value: &serde_json::Value) -> serde_json::Value {
+    use serde_json::Value;
+    match value {
+        Value::Null | Value::Bool(_) | Value::Number(_) => value.clone(),
+        Value::String(string) => format!("string({})", string.chars().count()).into(),
+        Value::Array(items) => format!("array({})", items.len()).into(),
+        Value::Object(fields) => Value::Object(
+            fields
+                .iter()
+                .map(|(key, value)| (key.clone(), summarize(value)))
+                .collect(),
+        ),
+    }
+}
+
+/// Return the trace files in the GitButler directory `project_data_dir`, the oldest one first.
+pub fn trace_files(project_data_dir: &Path) -> Vec<PathBuf> {
+    let mut rotated = std::fs::read_dir(project_data_dir)
+        .into_iter()
+        .flatten()
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let file_name = entry.file_name();
+            let number = file_name
+                .to_str()?
+                .strip_prefix("operations.")?
+                .strip_suffix(".jsonl")?
+                .parse::<u32>()
+                .ok()?;
+            Some((number, entry.path()))
+        })
+        .collect::<Vec<_>>();
+    rotated.sort_by(|(a, _), (b, _)| b.cmp(a));
+    let mut files = rotated
+        .into_iter()
+        .map(|(_, path)| path)
+        .collect::<Vec<_>>();
+    let current = project_data_dir.join(TRACE_FILE);
+    if current.is_file() {
+        files.push(current);
+    }
+    files
+}
+
+/// Append `entry` to the trace at `trace_path`, after rotating it if it got too large as per `settings`.
+fn append(trace_path: &Path, entry: &TraceEntry, settings: &OperationTrace) -> anyhow::Result<()> {
+    let size = std::fs::metadata(trace_path).map_or(0, |metadata| metadata.len());
+    if size > u64::from(settings.max_file_size_kb) * 1024 {
+        rotate(trace_path, settings.max_rotated_files)?;
+    }
+    if let Some(dir) = trace_path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mut line = serde_json::to_string(entry)?;
+    line.push('\n');
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(trace_path)?
+        .write_all(line.as_bytes())?;
+    Ok(())
+}
+
+/// Move the trace at `trace_path` to `operations.1.jsonl`, shifting previously rotated files up by one
+/// and deleting those beyond `max_rotated_files`.
+fn rotate(trace_path: &Path, max_rotated_files: u32) -> std::io::Result<()> {
+    let rotated = |number: u32| trace_path.with_file_name(format!("operations.{number}.jsonl"));
+    if max_rotated_files == 0 {
+        return std::fs::remove_file(trace_path);
+    }
+    match std::fs::remove_file(rotated(max_rotated_files)) {
+        Err(err) if err.kind() != std::io::ErrorKind::NotFound => return Err(err),
+        _ => {}
+    }
+    for number in (1..max_rotated_files).rev() {
+        let from = rotated(number);
+        if from.is_file() {
+            std::fs::rename(from, rotated(number + 1))?;
+        }
+    }
+    std::fs::rename(trace_path, rotated(1))
+}
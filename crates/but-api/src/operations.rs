@@ -0,0 +1,20 @@
+use but_api_macros::but_api;
+use but_ctx::access::{OperationId, OperationInfo};
+use tracing::instrument;
+
+/// List the running and queued mutating operations of the project, the running one first,
+/// followed by the queued ones in the order in which they will run.
+#[but_api]
+#[instrument(err(Debug))]
+pub fn list_operations(ctx: &but_ctx::Context) -> anyhow::Result<Vec<OperationInfo>> {
+    Ok(ctx.queued_operations())
+}
+
+/// Cancel the operation with `id`, so it won't run if it's queued, or stops at the next safe point if it's running.
+///
+/// Returns `false` if the operation doesn't exist anymore.
+#[but_api]
+#[instrument(err(Debug))]
+pub fn cancel_operation(ctx: &but_ctx::Context, id: OperationId) -> anyhow::Result<bool> {
+    ctx.cancel_queued_operation(id)
+}
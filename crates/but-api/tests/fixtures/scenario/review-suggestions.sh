@@ -0,0 +1,37 @@
+#!/usr/bin/env bash
+set -eu -o pipefail
+
+# A workspace with a single stack, `feature`, whose commit adds a file to which review suggestions can be made.
+git init
+echo "initial content" > shared.txt
+git add . && git commit -m "init"
+
+mkdir -p .git/refs/remotes/origin
+cp .git/refs/heads/main .git/refs/remotes/origin/main
+
+cat <<EOF2 >>.git/config
+[remote "origin"]
+  url = ./fake/local/path/which-is-fine-as-we-dont-fetch-or-push
+  fetch = +refs/heads/*:refs/remotes/origin/*
+EOF2
+
+mkdir .git/gitbutler
+cat <<EOF2 >>.git/gitbutler/virtual_branches.toml
+[default_target]
+   branchName = "main"
+   remoteName = "origin"
+   remoteUrl = "."
+   sha = "$(git rev-parse main)"
+   pushRemoteName = "origin"
+
+[branch_targets]
+
+[branches]
+EOF2
+
+git checkout -b feature
+seq 1 20 > file
+git add . && git commit -m "add file"
+
+git checkout -b gitbutler/workspace
+git commit --allow-empty -m "GitButler Workspace Commit"
@@ -0,0 +1,294 @@
+use anyhow::{Context as _, Result, bail};
+use but_secret::Sensitive;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+
+const API_URL: &str = "https://api.bitbucket.org/2.0";
+
+/// A client for the REST API of Bitbucket Cloud.
+pub struct BitbucketClient {
+    http: reqwest::Client,
+    username: String,
+    api_token: Sensitive<String>,
+}
+
+impl BitbucketClient {
+    /// Create a client which authenticates as `username` with `api_token`.
+    pub fn new(username: &str, api_token: &Sensitive<String>) -> Self {
+        BitbucketClient {
+            http: reqwest::Client::new(),
+            username: username.to_owned(),
+            api_token: api_token.clone(),
+        }
+    }
+
+    pub async fn get_authenticated(&self) -> Result<BitbucketUser> {
+        self.get(&format!("{API_URL}/user"), &[]).await
+    }
+
+    /// List the open pull requests of `repository`, like `workspace/repo`.
+    pub async fn list_open_pull_requests(&self, repository: &str) -> Result<Vec<PullRequest>> {
+        self.get_all_pages(
+            &format!("{API_URL}/repositories/{repository}/pullrequests"),
+            &[("state", "OPEN"), ("pagelen", "50")],
+        )
+        .await
+    }
+
+    /// List the pull requests of `repository` in any state whose changes come from `source_branch`.
+    pub async fn list_pull_requests_for_source_branch(
+        &self,
+        repository: &str,
+        source_branch: &str,
+    ) -> Result<Vec<PullRequest>> {
+        let query = format!(
+            "source.branch.name=\"{}\"",
+            source_branch.replace('\\', "\\\\").replace('"', "\\\"")
+        );
+        self.get_all_pages(
+            &format!("{API_URL}/repositories/{repository}/pullrequests"),
+            &[
+                ("q", query.as_str()),
+                // Without any state, only open pull requests would be returned.
+                ("state", "OPEN"),
+                ("state", "MERGED"),
+                ("state", "DECLINED"),
+                ("state", "SUPERSEDED"),
+                ("pagelen", "50"),
+            ],
+        )
+        .await
+    }
+
+    pub async fn get_pull_request(&self, repository: &str, id: i64) -> Result<PullRequest> {
+        self.get(
+            &format!("{API_URL}/repositories/{repository}/pullrequests/{id}"),
+            &[],
+        )
+        .await
+    }
+
+    pub async fn create_pull_request(
+        &self,
+        params: &CreatePullRequestParams<'_>,
+    ) -> Result<PullRequest> {
+        #[derive(Serialize)]
+        struct Branch<'a> {
+            name: &'a str,
+        }
+        #[derive(Serialize)]
+        struct Endpoint<'a> {
+            branch: Branch<'a>,
+        }
+        #[derive(Serialize)]
+        struct Body<'a> {
+            title: &'a str,
+            description: &'a str,
+            source: Endpoint<'a>,
+            destination: Endpoint<'a>,
+            draft: bool,
+            close_source_branch: bool,
+        }
+        let body = Body {
+            title: params.title,
+            description: params.description,
+            source: Endpoint {
+                branch: Branch {
+                    name: params.source_branch,
+                },
+            },
+            destination: Endpoint {
+                branch: Branch {
+                    name: params.destination_branch,
+                },
+            },
+            draft: params.draft,
+            close_source_branch: false,
+        };
+        self.send(
+            self.http.post(format!(
+                "{API_URL}/repositories/{}/pullrequests",
+                params.repository
+            )),
+            Some(&body),
+        )
+        .await
+    }
+
+    /// Return the commit `branch` of `repository` points to.
+    pub async fn branch_commit(&self, repository: &str, branch: &str) -> Result<String> {
+        #[derive(Deserialize)]
+        struct Branch {
+            target: Commit,
+        }
+        let branch: Branch = self
+            .get(
+                &format!("{API_URL}/repositories/{repository}/refs/branches/{branch}"),
+                &[],
+            )
+            .await?;
+        Ok(branch.target.hash)
+    }
+
+    /// List the statuses that CI systems reported for `commit` of `repository`.
+    pub async fn list_commit_statuses(
+        &self,
+        repository: &str,
+        commit: &str,
+    ) -> Result<Vec<CommitStatus>> {
+        self.get_all_pages(
+            &format!("{API_URL}/repositories/{repository}/commit/{commit}/statuses"),
+            &[("pagelen", "100")],
+        )
+        .await
+    }
+
+    async fn get<T: DeserializeOwned>(&self, url: &str, query: &[(&str, &str)]) -> Result<T> {
+        self.send::<T, ()>(self.http.get(url).query(query), None)
+            .await
+    }
+
+    /// Fetch `url` and all pages that follow it.
+    async fn get_all_pages<T: DeserializeOwned>(
+        &self,
+        url: &str,
+        query: &[(&str, &str)],
+    ) -> Result<Vec<T>> {
+        let mut page: Page<T> = self.get(url, query).await?;
+        let mut values = std::mem::take(&mut page.values);
+        // Links to the next page already contain the query.
+        while let Some(next) = page.next.take() {
+            page = self.get(&next, &[]).await?;
+            values.append(&mut page.values);
+        }
+        Ok(values)
+    }
+
+    async fn send<T: DeserializeOwned, B: Serialize>(
+        &self,
+        request: reqwest::RequestBuilder,
+        body: Option<&B>,
+    ) -> Result<T> {
+        let mut request = request.basic_auth(&self.username, Some(self.api_token.as_str()));
+        if let Some(body) = body {
+            request = request.json(body);
+        }
+        let response = request
+            .send()
+            .await
+            .context("Failed to send request to Bitbucket")?;
+        let status = response.status();
+        if !status.is_success() {
+            let message = response.text().await.unwrap_or_default();
+            bail!("Bitbucket responded with {status}: {message}");
+        }
+        response
+            .json()
+            .await
+            .context("Failed to parse the response of Bitbucket")
+    }
+}
+
+#[derive(Deserialize)]
+struct Page<T> {
+    values: Vec<T>,
+    next: Option<String>,
+}
+
+pub struct CreatePullRequestParams<'a> {
+    /// The full name of the repository, like `workspace/repo`.
+    pub repository: &'a str,
+    pub title: &'a str,
+    pub description: &'a str,
+    pub source_branch: &'a str,
+    pub destination_branch: &'a str,
+    pub draft: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BitbucketUser {
+    /// The ID of the user, like `{c1b8f0a4-...}`.
+    pub uuid: String,
+    pub display_name: String,
+    /// The name the user is mentioned with, which isn't set for all accounts.
+    pub nickname: Option<String>,
+    #[serde(default)]
+    pub links: Links,
+    /// `user` for people and `app_user` for bots.
+    #[serde(rename = "type", default)]
+    pub kind: String,
+}
+
+impl BitbucketUser {
+    pub fn avatar_url(&self) -> Option<&str> {
+        self.links.avatar.as_ref().map(|link| link.href.as_str())
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Links {
+    pub html: Option<Link>,
+    pub avatar: Option<Link>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Link {
+    pub href: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PullRequest {
+    pub id: i64,
+    pub title: String,
+    pub description: Option<String>,
+    /// One of `OPEN`, `MERGED`, `DECLINED` or `SUPERSEDED`.
+    pub state: String,
+    pub author: Option<BitbucketUser>,
+    pub source: Endpoint,
+    pub destination: Endpoint,
+    #[serde(default)]
+    pub draft: bool,
+    pub created_on: Option<String>,
+    pub updated_on: Option<String>,
+    #[serde(default)]
+    pub links: Links,
+    /// Only returned when getting a single pull request.
+    #[serde(default)]
+    pub reviewers: Vec<BitbucketUser>,
+}
+
+/// The branch a pull request is coming from or going to.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Endpoint {
+    pub branch: BranchName,
+    pub commit: Option<Commit>,
+    pub repository: Option<Repository>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BranchName {
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Commit {
+    pub hash: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Repository {
+    /// The name of the repository including its workspace, like `workspace/repo`.
+    pub full_name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommitStatus {
+    /// Identifies the status among those of the commit, like the name of a build plan.
+    pub key: String,
+    pub name: Option<String>,
+    /// One of `INPROGRESS`, `SUCCESSFUL`, `FAILED` or `STOPPED`.
+    pub state: String,
+    pub url: String,
+    pub description: Option<String>,
+    pub created_on: Option<String>,
+    pub updated_on: Option<String>,
+}
@@ -0,0 +1,43 @@
+//! Talk to the REST API of Bitbucket Cloud, authenticated with the username and API token of an account.
+use anyhow::{Context as _, Result};
+use but_secret::Sensitive;
+
+mod client;
+pub mod pr;
+pub use client::{
+    BitbucketClient, BitbucketUser, CommitStatus, CreatePullRequestParams, PullRequest,
+};
+mod token;
+pub use token::{BitbucketAccountIdentifier, list_known_bitbucket_accounts};
+
+/// Verify that `username` can authenticate with `api_token`, and persist the token along with the account.
+///
+/// `username` is what Bitbucket expects for HTTP basic authentication, the email address for API tokens
+/// or the username for app passwords.
+pub async fn store_api_token(
+    username: &str,
+    api_token: &Sensitive<String>,
+    storage: &but_forge_storage::Controller,
+) -> Result<BitbucketUser> {
+    let user = BitbucketClient::new(username, api_token)
+        .get_authenticated()
+        .await
+        .context("Failed to get authenticated user")?;
+    token::persist_bitbucket_api_token(
+        &BitbucketAccountIdentifier {
+            username: username.to_owned(),
+        },
+        api_token,
+        storage,
+    )
+    .context("Failed to persist API token")?;
+    Ok(user)
+}
+
+/// Forget the Bitbucket account with `account` and its API token.
+pub fn forget_bitbucket_account(
+    account: &BitbucketAccountIdentifier,
+    storage: &but_forge_storage::Controller,
+) -> Result<()> {
+    token::delete_bitbucket_api_token(account, storage)
+}
@@ -0,0 +1,56 @@
+use anyhow::{Context as _, Result};
+
+use crate::{BitbucketAccountIdentifier, client::BitbucketClient};
+
+pub async fn list(
+    preferred_account: Option<&BitbucketAccountIdentifier>,
+    repository: &str,
+    storage: &but_forge_storage::Controller,
+) -> Result<Vec<crate::PullRequest>> {
+    if let Ok(bb) = BitbucketClient::from_storage(storage, preferred_account) {
+        bb.list_open_pull_requests(repository)
+            .await
+            .context("Failed to list open pull requests")
+    } else {
+        Ok(vec![])
+    }
+}
+
+pub async fn list_all_for_branch(
+    preferred_account: Option<&BitbucketAccountIdentifier>,
+    repository: &str,
+    branch: &str,
+    storage: &but_forge_storage::Controller,
+) -> Result<Vec<crate::PullRequest>> {
+    if let Ok(bb) = BitbucketClient::from_storage(storage, preferred_account) {
+        bb.list_pull_requests_for_source_branch(repository, branch)
+            .await
+            .context("Failed to list pull requests for branch")
+    } else {
+        Ok(vec![])
+    }
+}
+
+pub async fn create(
+    preferred_account: Option<&BitbucketAccountIdentifier>,
+    params: crate::CreatePullRequestParams<'_>,
+    storage: &but_forge_storage::Controller,
+) -> Result<crate::PullRequest> {
+    BitbucketClient::from_storage(storage, preferred_account)?
+        .create_pull_request(&params)
+        .await
+        .context("Failed to create pull request")
+}
+
+pub async fn get(
+    preferred_account: Option<&BitbucketAccountIdentifier>,
+    repository: &str,
+    id: usize,
+    storage: &but_forge_storage::Controller,
+) -> Result<crate::PullRequest> {
+    let id = id.try_into().context("PR number is too large")?;
+    BitbucketClient::from_storage(storage, preferred_account)?
+        .get_pull_request(repository, id)
+        .await
+        .context("Failed to get pull request")
+}
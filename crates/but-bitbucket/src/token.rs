@@ -0,0 +1,118 @@
+use std::sync::Mutex;
+
+use anyhow::{Result, bail};
+use but_secret::{Sensitive, secret};
+use serde::{Deserialize, Serialize};
+
+use crate::client::BitbucketClient;
+
+/// Identifies a Bitbucket Cloud account by the name it authenticates with.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BitbucketAccountIdentifier {
+    pub username: String,
+}
+
+impl std::fmt::Display for BitbucketAccountIdentifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.username)
+    }
+}
+
+impl From<&but_forge_storage::settings::BitbucketAccount> for BitbucketAccountIdentifier {
+    fn from(account: &but_forge_storage::settings::BitbucketAccount) -> Self {
+        BitbucketAccountIdentifier {
+            username: account.username.clone(),
+        }
+    }
+}
+
+impl BitbucketAccountIdentifier {
+    fn secret_key(&self) -> String {
+        format!("bitbucket_api_token_{}", self.username)
+    }
+
+    fn storage_account(&self) -> but_forge_storage::settings::BitbucketAccount {
+        but_forge_storage::settings::BitbucketAccount {
+            username: self.username.clone(),
+            api_token_key: self.secret_key(),
+        }
+    }
+}
+
+static FAIR_QUEUE: Mutex<()> = Mutex::new(());
+
+/// Persist the API token of the Bitbucket account with `account_id` securely.
+pub fn persist_bitbucket_api_token(
+    account_id: &BitbucketAccountIdentifier,
+    api_token: &Sensitive<String>,
+    storage: &but_forge_storage::Controller,
+) -> Result<()> {
+    storage.add_bitbucket_account(&account_id.storage_account())?;
+    let _one_at_a_time_to_prevent_races = FAIR_QUEUE.lock().unwrap();
+    secret::persist(
+        &account_id.secret_key(),
+        api_token,
+        secret::Namespace::BuildKind,
+    )
+}
+
+/// Delete the Bitbucket account with `account_id` along with its API token.
+pub fn delete_bitbucket_api_token(
+    account_id: &BitbucketAccountIdentifier,
+    storage: &but_forge_storage::Controller,
+) -> Result<()> {
+    storage.remove_bitbucket_account(&account_id.storage_account())?;
+    let _one_at_a_time_to_prevent_races = FAIR_QUEUE.lock().unwrap();
+    secret::delete(&account_id.secret_key(), secret::Namespace::BuildKind)
+}
+
+/// Retrieve the API token of the Bitbucket account with `account_id`.
+pub fn get_bitbucket_api_token(
+    account_id: &BitbucketAccountIdentifier,
+    storage: &but_forge_storage::Controller,
+) -> Result<Option<Sensitive<String>>> {
+    let Some(account) = storage
+        .bitbucket_accounts()?
+        .into_iter()
+        .find(|account| BitbucketAccountIdentifier::from(account) == *account_id)
+    else {
+        return Ok(None);
+    };
+    let _one_at_a_time_to_prevent_races = FAIR_QUEUE.lock().unwrap();
+    secret::retrieve(&account.api_token_key, secret::Namespace::BuildKind)
+}
+
+pub fn list_known_bitbucket_accounts(
+    storage: &but_forge_storage::Controller,
+) -> Result<Vec<BitbucketAccountIdentifier>> {
+    Ok(storage
+        .bitbucket_accounts()?
+        .iter()
+        .map(Into::into)
+        .collect())
+}
+
+impl BitbucketClient {
+    /// Create a client authenticated as `preferred_account` if it is known, or as the first known account.
+    pub fn from_storage(
+        storage: &but_forge_storage::Controller,
+        preferred_account: Option<&BitbucketAccountIdentifier>,
+    ) -> Result<Self> {
+        let accounts = list_known_bitbucket_accounts(storage)?;
+        let account = preferred_account
+            .filter(|account| accounts.contains(account))
+            .or_else(|| accounts.first());
+        let Some(account) = account else {
+            bail!(
+                "No authenticated Bitbucket account found. Please authenticate with Bitbucket first."
+            );
+        };
+        match get_bitbucket_api_token(account, storage)? {
+            Some(api_token) => Ok(BitbucketClient::new(&account.username, &api_token)),
+            None => bail!(
+                "No Bitbucket API token found for account '{account}'.\nPlease, try to re-authenticate with this account."
+            ),
+        }
+    }
+}
@@ -140,7 +140,7 @@ pub fn cherry_apply(
     rebase.steps(steps)?;
     rebase.rebase_noops(false);
     let output = rebase.rebase()?;
-    stack.set_heads_from_rebase_output(ctx, output.references)?;
+    stack.set_heads_from_rebase_output(ctx, &output)?;
 
     {
         let new_workspace = WorkspaceState::create(ctx, perm.read_permission())?;
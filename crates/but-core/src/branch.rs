@@ -1,5 +1,10 @@
 use std::{collections::BTreeMap, path::PathBuf};
 
+use bstr::{BStr, BString, ByteSlice};
+use but_error::ButError;
+
+use crate::RepositoryExt;
+
 type WorktreePathByRef = BTreeMap<gix::refs::FullName, Vec<PathBuf>>;
 
 /// State for reuse when [safely deleting references](SafeDelete::delete_reference).
@@ -51,6 +56,95 @@ impl SafeDelete {
     }
 }
 
+/// The branches that GitButler must not commit to, amend, force-push or delete,
+/// as configured with the glob patterns in `gitbutler.protectedBranches`.
+#[derive(Debug, Clone, Default)]
+pub struct ProtectedBranches {
+    /// Glob patterns to match against short branch names, like `release/*`.
+    patterns: Vec<BString>,
+}
+
+/// Lifecycle
+impl ProtectedBranches {
+    /// Read the protected branch patterns from the configuration of `repo`.
+    pub fn from_repo(repo: &gix::Repository) -> anyhow::Result<Self> {
+        let patterns = repo
+            .git_settings()?
+            .gitbutler_protected_branches
+            .map(|patterns| {
+                patterns
+                    .fields_with(|c| c.is_whitespace() || c == ',')
+                    .map(Into::into)
+                    .collect()
+            })
+            .unwrap_or_default();
+        Ok(ProtectedBranches { patterns })
+    }
+}
+
+impl ProtectedBranches {
+    /// Return `true` if no branch is protected.
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    /// Return the first pattern that matches `branch_name`, which may be a short name like `main`
+    /// or a full name like `refs/heads/main`.
+    pub fn matching_pattern(&self, branch_name: &BStr) -> Option<&BStr> {
+        let short_name = branch_name
+            .strip_prefix(b"refs/heads/")
+            .unwrap_or(branch_name)
+            .as_bstr();
+        self.patterns
+            .iter()
+            .find(|pattern| {
+                gix::glob::wildmatch(
+                    pattern.as_bstr(),
+                    short_name,
+                    gix::glob::wildmatch::Mode::NO_MATCH_SLASH_LITERAL,
+                )
+            })
+            .map(|pattern| pattern.as_bstr())
+    }
+
+    /// Fail with [`ButError::BranchProtected`] if `branch_name` is protected.
+    pub fn ensure_unprotected(&self, branch_name: &BStr) -> anyhow::Result<()> {
+        match self.matching_pattern(branch_name) {
+            Some(pattern) => Err(ButError::BranchProtected {
+                name: branch_name.to_str_lossy().into_owned(),
+                pattern: pattern.to_str_lossy().into_owned(),
+            }
+            .into()),
+            None => Ok(()),
+        }
+    }
+
+    /// Fail with [`ButError::BranchProtected`] if `branch_name` is protected and pushing `local_tip` to it would
+    /// rewrite its history, which is the case if its tip on the remote, `remote_tip`, isn't an ancestor of `local_tip`.
+    ///
+    /// Fast-forwards, and pushes of branches that don't exist on the remote yet, are always allowed.
+    pub fn ensure_push_unprotected(
+        &self,
+        repo: &gix::Repository,
+        branch_name: &BStr,
+        remote_tip: Option<gix::ObjectId>,
+        local_tip: gix::ObjectId,
+    ) -> anyhow::Result<()> {
+        let Some(remote_tip) = remote_tip.filter(|_| self.matching_pattern(branch_name).is_some())
+        else {
+            return Ok(());
+        };
+        let is_fast_forward = remote_tip == local_tip
+            || repo
+                .merge_base(remote_tip, local_tip)
+                .is_ok_and(|merge_base| merge_base == remote_tip);
+        if is_fast_forward {
+            return Ok(());
+        }
+        self.ensure_unprotected(branch_name)
+    }
+}
+
 /// This code was copied from `gix` and it should rather be exposed there.
 /// Everyone needs it for safe-deletion.
 // TODO(gix): expose this in gix (but find this code, it already exists there)
@@ -2,20 +2,78 @@ use bstr::{BStr, ByteSlice};
 
 use crate::{
     ChangeState, IgnoredWorktreeChange, ModeFlags, TreeChange, TreeStatus, TreeStatusKind,
+    UnifiedPatch,
 };
 
 pub(crate) mod tree_changes;
 pub use tree_changes::{TreeChanges, tree_changes, tree_changes_with_line_stats};
 
 mod worktree;
-pub use worktree::{worktree_changes, worktree_changes_no_renames};
+pub use worktree::{worktree_changes, worktree_changes_incremental, worktree_changes_no_renames};
+
+mod stream;
+pub use stream::{StreamedHunk, prefix_patch, stream_hunks};
 
 mod commit_details;
 pub use commit_details::{CommitDetails, LineStats};
 
+mod patch_id;
+pub use patch_id::patch_id;
+
 /// conversion functions for use in the UI
 pub mod ui;
 
+/// Compute the [unified patch](TreeChange::unified_patch()) of each of `changes` with `context_lines`, on up to `threads`
+/// threads, or as many threads as there are cores if `None`, returning the outcome of each change in the order of `changes`.
+/// `source` is what `changes` were computed from, to know which of their blobs can be fetched in a partial clone.
+///
+/// This is useful when diffing many files, like after a big refactor.
+pub fn unified_patches(
+    repo: &gix::Repository,
+    changes: &[TreeChange],
+    source: crate::shallow::ChangesSource,
+    context_lines: u32,
+    threads: Option<usize>,
+) -> Vec<anyhow::Result<Option<UnifiedPatch>>> {
+    // Fetch what's missing in a partial clone all at once, instead of one blob at a time.
+    crate::shallow::prefetch_blobs_of_changes(repo, changes, source);
+    let threads = threads
+        .filter(|threads| *threads > 0)
+        .or_else(|| std::thread::available_parallelism().ok().map(Into::into))
+        .unwrap_or(1)
+        .min(changes.len());
+    if threads <= 1 {
+        return changes
+            .iter()
+            .map(|change| change.unified_patch(repo, context_lines))
+            .collect();
+    }
+
+    let chunk_size = changes.len().div_ceil(threads);
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = changes
+            .chunks(chunk_size)
+            .map(|chunk| {
+                let repo = repo.clone();
+                scope.spawn(move || {
+                    chunk
+                        .iter()
+                        .map(|change| change.unified_patch(&repo, context_lines))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|err| std::panic::resume_unwind(err))
+            })
+            .collect()
+    })
+}
+
 impl TreeStatus {
     /// Learn what kind of status this is, useful if only this information is needed.
     pub fn kind(&self) -> TreeStatusKind {
@@ -37,6 +95,14 @@ impl TreeStatus {
         }
     }
 
+    /// Return the mode transition between the previous and the current state, if there is one.
+    pub fn flags(&self) -> Option<ModeFlags> {
+        match self {
+            TreeStatus::Addition { .. } | TreeStatus::Deletion { .. } => None,
+            TreeStatus::Modification { flags, .. } | TreeStatus::Rename { flags, .. } => *flags,
+        }
+    }
+
     /// Return the previous state that the change originated from. May be `None` if there is no previous state, for instance after an addition.
     /// Also provide the path from which the state was possibly obtained.
     pub fn previous_state_and_path(&self) -> Option<(ChangeState, Option<&BStr>)> {
@@ -0,0 +1,65 @@
+use bstr::ByteSlice;
+
+use crate::{ChangeState, TreeChange, UnifiedPatch};
+
+/// Compute an ID for the changes between the `lhs` and `rhs` trees, or `None` if they are the same.
+///
+/// Like `git patch-id --stable`, it only hashes the paths and the added and removed lines with
+/// their whitespace removed, so the same changes applied on top of different bases have the same ID.
+/// This makes it suitable to find out if the changes of a branch were squash-merged elsewhere.
+/// Changes that can't be diffed line by line, like binary files, are identified by their object ids instead.
+pub fn patch_id(
+    repo: &gix::Repository,
+    lhs: Option<gix::ObjectId>,
+    rhs: gix::ObjectId,
+) -> anyhow::Result<Option<gix::ObjectId>> {
+    let mut changes = super::tree_changes(repo, lhs, rhs)?;
+    if changes.is_empty() {
+        return Ok(None);
+    }
+    changes.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let mut hash = gix::hash::hasher(gix::hash::Kind::Sha1);
+    for change in &changes {
+        for path in change
+            .previous_path()
+            .into_iter()
+            .chain(Some(change.path.as_bstr()))
+        {
+            hash.update(path);
+            hash.update(&[0]);
+        }
+        match change.unified_patch(repo, 0)? {
+            Some(UnifiedPatch::Patch { hunks, .. }) => {
+                for line in hunks.iter().flat_map(|hunk| hunk.diff.lines()) {
+                    let (Some(sign @ (b'+' | b'-')), Some(line)) = (line.first(), line.get(1..))
+                    else {
+                        continue;
+                    };
+                    hash.update(&[*sign]);
+                    for word in line.fields() {
+                        hash.update(word);
+                    }
+                    hash.update(b"\n");
+                }
+            }
+            _ => hash_object_ids(&mut hash, change),
+        }
+    }
+    Ok(Some(hash.try_finalize()?))
+}
+
+fn hash_object_ids(hash: &mut gix::hash::Hasher, change: &TreeChange) {
+    for state in [
+        change
+            .status
+            .previous_state_and_path()
+            .map(|(state, _)| state),
+        change.status.state(),
+    ] {
+        match state {
+            Some(ChangeState { id, .. }) => hash.update(id.as_bytes()),
+            None => hash.update(b"-"),
+        }
+    }
+}
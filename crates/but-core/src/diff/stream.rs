@@ -0,0 +1,260 @@
+use std::{io::Read, ops::ControlFlow};
+
+use bstr::ByteSlice;
+use gix::diff::blob::unified_diff::{ConsumeBinaryHunk, ContextSize, HunkHeader};
+
+use crate::{ChangeState, TreeChange, UnifiedPatch, unified_diff::DiffHunk};
+
+/// An item produced by [`stream_hunks()`].
+#[derive(Debug, Clone)]
+pub enum StreamedHunk {
+    /// A hunk as it would also be part of a [`UnifiedPatch`](crate::UnifiedPatch).
+    Hunk(DiffHunk),
+    /// The byte budget was exhausted, so the changes at and after the given 1-based lines are unknown.
+    ///
+    /// This is always the last item.
+    Truncated {
+        /// The first line in the previous version of the file that may be part of an unknown hunk.
+        old_start: u32,
+        /// The first line in the new version of the file that may be part of an unknown hunk.
+        new_start: u32,
+    },
+}
+
+/// Diff `change` with `context_lines` by looking at no more than `byte_budget` bytes of each of its versions,
+/// and pass each hunk to `on_hunk` as soon as it's known, which may stop the diff early by breaking.
+///
+/// This allows to work with files that are too large to be diffed as a whole, as it's still possible to
+/// assign the hunks at the beginning of a huge generated file, for instance.
+/// If the budget is exhausted, hunks that may be affected by the missing content aren't produced, and
+/// [`StreamedHunk::Truncated`] is passed instead.
+///
+/// Note that unlike [`TreeChange::unified_patch()`], the content is diffed as stored, without applying filters or
+/// binary-to-text conversions, and that changes involving submodules or binary content don't produce hunks.
+pub fn stream_hunks(
+    repo: &gix::Repository,
+    change: &TreeChange,
+    context_lines: u32,
+    byte_budget: usize,
+    mut on_hunk: impl FnMut(StreamedHunk) -> ControlFlow<()>,
+) -> anyhow::Result<()> {
+    let previous_path = change.previous_path().unwrap_or(change.path.as_ref());
+    let (Some(old), Some(new)) = (
+        read_prefix(
+            repo,
+            previous_path,
+            change
+                .status
+                .previous_state_and_path()
+                .map(|(state, _)| state),
+            byte_budget,
+        )?,
+        read_prefix(
+            repo,
+            change.path.as_ref(),
+            change.status.state(),
+            byte_budget,
+        )?,
+    ) else {
+        return Ok(());
+    };
+
+    let input = gix::diff::blob::InternedInput::new(
+        gix::diff::blob::sources::byte_lines_with_terminator(&old.data),
+        gix::diff::blob::sources::byte_lines_with_terminator(&new.data),
+    );
+    let delegate = StreamDelegate {
+        encoding: crate::diff::ui::DisplayEncodings::from_repo(repo)
+            .encoding_for(change.path.as_ref(), &[&new.data, &old.data]),
+        old_lines_if_truncated: old.is_truncated.then_some(old.lines),
+        new_lines_if_truncated: new.is_truncated.then_some(new.lines),
+        on_hunk: &mut on_hunk,
+    };
+    let uni_diff = gix::diff::blob::UnifiedDiff::new(
+        &input,
+        ConsumeBinaryHunk::new(delegate, "\n"),
+        ContextSize::symmetrical(context_lines),
+    );
+    match gix::diff::blob::diff(repo.diff_algorithm()?, &input, uni_diff) {
+        Ok(_) => {
+            if old.is_truncated || new.is_truncated {
+                let _ = on_hunk(StreamedHunk::Truncated {
+                    old_start: old.lines + 1,
+                    new_start: new.lines + 1,
+                });
+            }
+            Ok(())
+        }
+        // The delegate stopped the diff early, after informing about the truncation if needed.
+        Err(err) if err.get_ref().is_some_and(|inner| inner.is::<Stopped>()) => Ok(()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Collect the hunks of [`stream_hunks()`] into a patch, leaving out everything after the truncation, if there was one.
+/// Return `None` if there are no hunks to collect, for instance because there are no changes in the first `byte_budget` bytes.
+///
+/// This is useful to obtain hunks for a file that is [too large](UnifiedPatch::TooLarge) to be diffed as a whole.
+pub fn prefix_patch(
+    repo: &gix::Repository,
+    change: &TreeChange,
+    context_lines: u32,
+    byte_budget: usize,
+) -> anyhow::Result<Option<UnifiedPatch>> {
+    let mut hunks = Vec::new();
+    stream_hunks(repo, change, context_lines, byte_budget, |hunk| {
+        if let StreamedHunk::Hunk(hunk) = hunk {
+            hunks.push(hunk);
+        }
+        ControlFlow::Continue(())
+    })?;
+    if hunks.is_empty() {
+        return Ok(None);
+    }
+    let (lines_added, lines_removed) = crate::unified_diff::compute_line_changes(&hunks);
+    Ok(Some(UnifiedPatch::Patch {
+        hunks,
+        is_result_of_binary_to_text_conversion: false,
+        lines_added,
+        lines_removed,
+    }))
+}
+
+/// The error used by [`StreamDelegate`] to stop the diff.
+#[derive(Debug)]
+struct Stopped;
+
+impl std::fmt::Display for Stopped {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("stopped by consumer")
+    }
+}
+
+impl std::error::Error for Stopped {}
+
+fn stopped() -> std::io::Error {
+    std::io::Error::other(Stopped)
+}
+
+struct StreamDelegate<'a, F> {
+    encoding: Option<&'static encoding_rs::Encoding>,
+    old_lines_if_truncated: Option<u32>,
+    new_lines_if_truncated: Option<u32>,
+    on_hunk: &'a mut F,
+}
+
+impl<F> gix::diff::blob::unified_diff::ConsumeBinaryHunkDelegate for StreamDelegate<'_, F>
+where
+    F: FnMut(StreamedHunk) -> ControlFlow<()>,
+{
+    fn consume_binary_hunk(
+        &mut self,
+        header: HunkHeader,
+        header_str: &str,
+        hunk: &[u8],
+    ) -> std::io::Result<()> {
+        let reaches_end = |start: u32, len: u32, truncated_lines: Option<u32>| {
+            truncated_lines.is_some_and(|lines| start + len > lines)
+        };
+        if reaches_end(
+            header.before_hunk_start,
+            header.before_hunk_len,
+            self.old_lines_if_truncated,
+        ) || reaches_end(
+            header.after_hunk_start,
+            header.after_hunk_len,
+            self.new_lines_if_truncated,
+        ) {
+            // This hunk may change once the rest of the file is known.
+            let _ = (self.on_hunk)(StreamedHunk::Truncated {
+                old_start: header.before_hunk_start,
+                new_start: header.after_hunk_start,
+            });
+            return Err(stopped());
+        }
+
+        let hunk = DiffHunk {
+            old_start: header.before_hunk_start,
+            old_lines: header.before_hunk_len,
+            new_start: header.after_hunk_start,
+            new_lines: header.after_hunk_len,
+            diff: {
+                let mut buf = Vec::with_capacity(header_str.len() + hunk.len());
+                buf.extend_from_slice(header_str.as_bytes());
+                buf.extend_from_slice(hunk);
+                crate::diff::ui::decode_for_display(buf.into(), self.encoding)
+            },
+            encoding: self.encoding.map(|encoding| encoding.name().to_owned()),
+            word_diff: None,
+        };
+        match (self.on_hunk)(StreamedHunk::Hunk(hunk)) {
+            ControlFlow::Continue(()) => Ok(()),
+            ControlFlow::Break(()) => Err(stopped()),
+        }
+    }
+}
+
+/// The beginning of a version of a file.
+struct Prefix {
+    /// Data that ends with a complete line.
+    data: Vec<u8>,
+    /// The amount of lines in `data`.
+    lines: u32,
+    /// If `true`, there is more data after `data`.
+    is_truncated: bool,
+}
+
+/// Read no more than `byte_budget` bytes of `state` at `path`, which refers to the worktree if its id is null.
+/// Return `None` if there is nothing to diff, like with submodules or binary files.
+fn read_prefix(
+    repo: &gix::Repository,
+    path: &bstr::BStr,
+    state: Option<ChangeState>,
+    byte_budget: usize,
+) -> anyhow::Result<Option<Prefix>> {
+    use gix::object::tree::EntryKind;
+    let mut data = match state {
+        None => Vec::new(),
+        Some(ChangeState {
+            kind: EntryKind::Commit | EntryKind::Tree,
+            ..
+        }) => return Ok(None),
+        Some(ChangeState { id, .. }) if !id.is_null() => repo.find_blob(id)?.detach().data,
+        Some(ChangeState { kind, .. }) => {
+            let path = repo.workdir_path(path).ok_or_else(|| {
+                anyhow::anyhow!("need non-bare repository to read worktree files")
+            })?;
+            if kind == EntryKind::Link {
+                gix::path::into_bstr(std::fs::read_link(path)?)
+                    .into_owned()
+                    .into()
+            } else {
+                let mut buf = Vec::new();
+                std::fs::File::open(path)?
+                    .take(byte_budget as u64 + 1)
+                    .read_to_end(&mut buf)?;
+                buf
+            }
+        }
+    };
+    // Like Git, consider content with a NUL byte at its beginning to be binary.
+    if data[..data.len().min(8000)].contains(&0) {
+        return Ok(None);
+    }
+    let is_truncated = data.len() > byte_budget;
+    if is_truncated {
+        data.truncate(byte_budget);
+        let complete_lines = data.rfind_byte(b'\n').map_or(0, |pos| pos + 1);
+        data.truncate(complete_lines);
+    }
+    let lines = if is_truncated {
+        data.find_iter(b"\n").count()
+    } else {
+        data.lines_with_terminator().count()
+    };
+    Ok(Some(Prefix {
+        lines: lines.try_into()?,
+        data,
+        is_truncated,
+    }))
+}
@@ -1,6 +1,8 @@
 // TODO: all of these should go away.
 use std::path::PathBuf;
 
+use bstr::{BStr, BString, ByteSlice};
+use encoding_rs::Encoding;
 use gix::prelude::ObjectIdExt;
 
 use crate::{
@@ -52,3 +54,113 @@ pub fn changes_with_line_stats_in_range(
         .map(|(c, s)| (c.into_iter().map(Into::into).collect(), s.into()))?;
     Ok(TreeChanges { changes, stats })
 }
+
+/// The configuration key whose values control how the content of diffs is decoded for display, see [`DisplayEncodings`].
+pub const DISPLAY_ENCODING_KEY: &str = "gitbutler.diffEncoding";
+
+/// How the content of files is decoded to show their diffs, configured per path pattern with `gitbutler.diffEncoding`.
+///
+/// Each value is a comma-separated list of rules like `*.txt=Shift_JIS, legacy/**=windows-1252`,
+/// and the last matching pattern wins, like in `.gitattributes`.
+/// Patterns without a slash match the file name in any directory. The encoding is any label of the
+/// [Encoding Standard](https://encoding.spec.whatwg.org/#names-and-labels), or `auto` to detect it, which is also
+/// what happens with paths that match no pattern. Use `utf-8` to show the content as is.
+///
+/// Only the displayed diff is decoded, commits are always created from the original bytes.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct DisplayEncodings {
+    /// Patterns along with their encoding, which is `None` if it should be detected.
+    rules: Vec<(BString, Option<&'static Encoding>)>,
+}
+
+impl DisplayEncodings {
+    /// Read the rules from the configuration of `repo`, ignoring invalid values.
+    pub fn from_repo(repo: &gix::Repository) -> Self {
+        let rules = repo
+            .config_snapshot()
+            .strings(DISPLAY_ENCODING_KEY)
+            .unwrap_or_default()
+            .iter()
+            .flat_map(|value| value.split_str(","))
+            .map(|value| value.trim().as_bstr())
+            .filter(|value| !value.is_empty())
+            .filter_map(|value| {
+                let Some((pattern, label)) = value.rsplit_once_str("=") else {
+                    tracing::warn!(rule = %value, key = DISPLAY_ENCODING_KEY, "ignoring rule without '='");
+                    return None;
+                };
+                let label = label.trim();
+                let encoding = if label.eq_ignore_ascii_case(b"auto") {
+                    None
+                } else {
+                    let Some(encoding) = Encoding::for_label(label) else {
+                        tracing::warn!(rule = %value, key = DISPLAY_ENCODING_KEY, "ignoring unknown encoding");
+                        return None;
+                    };
+                    Some(encoding)
+                };
+                Some((pattern.trim().into(), encoding))
+            })
+            .collect();
+        DisplayEncodings { rules }
+    }
+
+    /// Return the encoding configured for `path`, or `None` if it should be detected.
+    pub fn configured(&self, path: &BStr) -> Option<&'static Encoding> {
+        let file_name = path.rsplit_str("/").next().unwrap_or_default().as_bstr();
+        self.rules
+            .iter()
+            .rev()
+            .find(|(pattern, _)| {
+                gix::glob::wildmatch(
+                    pattern.as_bstr(),
+                    if pattern.contains(&b'/') {
+                        path
+                    } else {
+                        file_name
+                    },
+                    gix::glob::wildmatch::Mode::NO_MATCH_SLASH_LITERAL,
+                )
+            })
+            .and_then(|(_, encoding)| *encoding)
+    }
+
+    /// Return the encoding to decode the content of `path` with for display, detecting it in `contents`,
+    /// the versions of the file, unless it's configured.
+    /// Return `None` if the content is UTF-8 and can be shown as is.
+    pub fn encoding_for(&self, path: &BStr, contents: &[&[u8]]) -> Option<&'static Encoding> {
+        let encoding = match self.configured(path) {
+            Some(encoding) => encoding,
+            None => detect_encoding(contents)?,
+        };
+        (encoding != encoding_rs::UTF_8).then_some(encoding)
+    }
+}
+
+/// Detect the encoding of `contents`, which are versions of the same file, and return it if the detection is confident.
+///
+/// Content that is valid UTF-8 is always considered to be UTF-8.
+pub fn detect_encoding(contents: &[&[u8]]) -> Option<&'static Encoding> {
+    if contents.iter().all(|content| content.is_utf8()) {
+        return Some(encoding_rs::UTF_8);
+    }
+    let mut detect = chardetng::EncodingDetector::new();
+    for content in contents {
+        detect.feed(content, false);
+    }
+    detect.feed(&[], true);
+    let (encoding, high_confidence) = detect.guess_assess(None, true);
+    high_confidence.then_some(encoding)
+}
+
+/// Decode `content` with `encoding` to show it, or return it as is if there is no `encoding`.
+pub fn decode_for_display(content: BString, encoding: Option<&'static Encoding>) -> BString {
+    match encoding {
+        None => content,
+        Some(encoding) => encoding
+            .decode_without_bom_handling(&content)
+            .0
+            .into_owned()
+            .into(),
+    }
+}
@@ -1,4 +1,9 @@
-use std::{cmp::Ordering, io::Read, path::PathBuf};
+use std::{
+    cmp::Ordering,
+    collections::HashMap,
+    io::Read,
+    path::{Path, PathBuf},
+};
 
 use anyhow::{Context as _, bail};
 use bstr::{BStr, BString, ByteSlice, ByteVec};
@@ -36,13 +41,94 @@ enum Origin {
 /// to get a commit with a tree equal to the current worktree.
 #[instrument(skip(repo), err(Debug))]
 pub fn worktree_changes(repo: &gix::Repository) -> anyhow::Result<WorktreeChanges> {
-    worktree_changes_inner(repo, RenameTracking::Always)
+    worktree_changes_inner(repo, RenameTracking::Always, &[])
 }
 
 /// Just like [`worktree_changes()`], but don't do any rename tracking for performance.
 #[instrument(skip(repo), err(Debug))]
 pub fn worktree_changes_no_renames(repo: &gix::Repository) -> anyhow::Result<WorktreeChanges> {
-    worktree_changes_inner(repo, RenameTracking::Disabled)
+    worktree_changes_inner(repo, RenameTracking::Disabled, &[])
+}
+
+/// Like [`worktree_changes()`], but instead of walking the whole worktree, only re-diff the worktree-relative
+/// `dirty_paths`, as reported by a filesystem monitor, and merge the result into the `previous` changes
+/// that were obtained with [`worktree_changes()`] as well.
+///
+/// A dirty path that is a directory also marks everything inside of it as dirty.
+/// To keep rename tracking intact, all paths of the previous changes that are related to a dirty path,
+/// along with all previously added or deleted paths, are re-diffed as well.
+/// Note that this is only correct if the index and `HEAD` didn't change since `previous` was obtained,
+/// otherwise [`worktree_changes()`] must be used.
+#[instrument(skip(repo, previous, dirty_paths), fields(dirty_paths = dirty_paths.len()), err(Debug))]
+pub fn worktree_changes_incremental(
+    repo: &gix::Repository,
+    previous: WorktreeChanges,
+    dirty_paths: &[BString],
+) -> anyhow::Result<WorktreeChanges> {
+    if dirty_paths.is_empty() {
+        return Ok(previous);
+    }
+    let mut paths = dirty_paths.to_vec();
+    for change in &previous.changes {
+        let is_rename_candidate = matches!(
+            change.status,
+            TreeStatus::Addition { .. } | TreeStatus::Deletion { .. }
+        );
+        let previous_path = change.previous_path();
+        if is_rename_candidate
+            || is_selected(change.path.as_ref(), dirty_paths, false)
+            || previous_path.is_some_and(|path| is_selected(path, dirty_paths, false))
+        {
+            paths.push(change.path.clone());
+            paths.extend(previous_path.map(ToOwned::to_owned));
+        }
+    }
+    paths.sort();
+    paths.dedup();
+
+    let WorktreeChanges {
+        mut changes,
+        mut ignored_changes,
+        mut index_changes,
+        mut index_conflicts,
+    } = previous;
+    changes.retain(|change| {
+        !is_selected(change.path.as_ref(), &paths, false)
+            && !change
+                .previous_path()
+                .is_some_and(|path| is_selected(path, &paths, false))
+    });
+    ignored_changes.retain(|change| !is_selected(change.path.as_ref(), &paths, false));
+    index_changes.retain(|change| !is_selected(change.location(), &paths, false));
+    index_conflicts.retain(|(path, _)| !is_selected(path.as_ref(), &paths, false));
+
+    let updated = worktree_changes_inner(repo, RenameTracking::Always, &paths)?;
+    changes.extend(updated.changes);
+    changes.sort_by(|a, b| a.path.cmp(&b.path));
+    ignored_changes.extend(updated.ignored_changes);
+    index_changes.extend(updated.index_changes);
+    index_conflicts.extend(updated.index_conflicts);
+    index_conflicts.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(WorktreeChanges {
+        changes,
+        ignored_changes,
+        index_changes,
+        index_conflicts,
+    })
+}
+
+/// Return `true` if `path` is one of `paths` or is contained in one of them, comparing ASCII characters
+/// case-insensitively if `ignore_case` is `true`.
+fn is_selected(path: &BStr, paths: &[BString], ignore_case: bool) -> bool {
+    paths.iter().any(|selected| {
+        path.len() >= selected.len()
+            && (path.len() == selected.len() || path[selected.len()] == b'/')
+            && if ignore_case {
+                path[..selected.len()].eq_ignore_ascii_case(selected)
+            } else {
+                path[..selected.len()] == selected[..]
+            }
+    })
 }
 
 enum RenameTracking {
@@ -50,9 +136,11 @@ enum RenameTracking {
     Disabled,
 }
 
+/// Only consider changes in `paths` and their children, or all changes if `paths` is empty.
 fn worktree_changes_inner(
     repo: &gix::Repository,
     renames: RenameTracking,
+    paths: &[BString],
 ) -> anyhow::Result<WorktreeChanges> {
     let (tree_index_rewrites, worktree_rewrites) = match renames {
         RenameTracking::Always => {
@@ -65,6 +153,11 @@ fn worktree_changes_inner(
         }
         RenameTracking::Disabled => (TrackRenames::Disabled, None),
     };
+    if repo.index_or_empty()?.is_sparse() {
+        bail!(
+            "Sparse indices aren't supported yet - run `git sparse-checkout reapply --no-sparse-index` to keep the sparse checkout without it"
+        );
+    }
     let has_submodule_ignore_configuration = repo.modules()?.is_some_and(|modules| {
         modules
             .names()
@@ -95,7 +188,11 @@ fn worktree_changes_inner(
                     .set_emit_collapsed(None);
             }
         })
-        .into_iter(None)?;
+        .into_iter(paths.iter().map(|path| {
+            let mut pattern = BString::from(":(literal)");
+            pattern.push_str(path);
+            pattern
+        }))?;
 
     let work_dir = repo.workdir().context("need non-bare repository")?;
     let mut tmp = Vec::new();
@@ -555,6 +652,53 @@ fn worktree_changes_inner(
         last_change = changes.last();
     }
 
+    if repo
+        .config_snapshot()
+        .boolean("core.ignoreCase")
+        .unwrap_or_default()
+    {
+        fold_case_only_renames(
+            repo,
+            work_dir,
+            &mut changes,
+            paths,
+            matches!(renames, RenameTracking::Always),
+        )?;
+    }
+
+    if let Some(sparse) = crate::sparse::SparseCheckout::from_repo(repo)? {
+        changes.retain(|change| {
+            let is_excluded = sparse.is_excluded(change.path.as_bstr())
+                || change
+                    .previous_path()
+                    .is_some_and(|path| sparse.is_excluded(path));
+            if is_excluded {
+                ignored_changes.push(IgnoredWorktreeChange {
+                    path: change.path.clone(),
+                    status: IgnoredWorktreeTreeChangeStatus::OutsideSparseCheckout,
+                });
+            }
+            !is_excluded
+        });
+    }
+
+    let unchanged = crate::unchanged::MarkedUnchanged::from_repo(repo)?;
+    if !unchanged.is_empty() {
+        changes.retain(|change| {
+            let is_marked = unchanged.contains(change.path.as_bstr())
+                || change
+                    .previous_path()
+                    .is_some_and(|path| unchanged.contains(path));
+            if is_marked {
+                ignored_changes.push(IgnoredWorktreeChange {
+                    path: change.path.clone(),
+                    status: IgnoredWorktreeTreeChangeStatus::MarkedUnchanged,
+                });
+            }
+            !is_marked
+        });
+    }
+
     Ok(WorktreeChanges {
         changes,
         ignored_changes,
@@ -563,6 +707,154 @@ fn worktree_changes_inner(
     })
 }
 
+/// On case-insensitive filesystems, renaming `Foo.rs` to `foo.rs` isn't visible to a status as `Foo.rs` can still be
+/// found on disk, so at best it compares the content of `foo.rs` to the one of `Foo.rs`, or shows `foo.rs` as untracked.
+/// Thus, compare the names of tracked files to the names that are actually stored in their directories, and represent
+/// each case-only mismatch as single rename from the tracked name, replacing all other `changes` to either path.
+/// If `track_renames` is `false`, the rename is represented as deletion and addition instead.
+/// If `paths` isn't empty, only tracked files within them are considered.
+///
+/// Note that only file names are compared, so case-only renames of directories remain invisible.
+fn fold_case_only_renames(
+    repo: &gix::Repository,
+    work_dir: &Path,
+    changes: &mut Vec<TreeChange>,
+    paths: &[BString],
+    track_renames: bool,
+) -> anyhow::Result<()> {
+    let index = repo.index_or_empty()?;
+    let mut names_by_dir = HashMap::<&BStr, Option<Vec<BString>>>::new();
+    let mut folded = false;
+    for entry in index.entries() {
+        if entry.stage() != gix::index::entry::Stage::Unconflicted
+            || entry
+                .flags
+                .contains(gix::index::entry::Flags::SKIP_WORKTREE)
+        {
+            continue;
+        }
+        let path = entry.path(&index);
+        if !paths.is_empty() && !is_selected(path, paths, true) {
+            continue;
+        }
+        let (dir, name) = match path.rfind_byte(b'/') {
+            Some(pos) => (path[..pos].as_bstr(), path[pos + 1..].as_bstr()),
+            None => ("".into(), path),
+        };
+        let Some(names) = names_by_dir.entry(dir).or_insert_with(|| {
+            let entries = std::fs::read_dir(work_dir.join(gix::path::from_bstr(dir))).ok()?;
+            Some(
+                entries
+                    .filter_map(|entry| {
+                        gix::path::os_string_into_bstring(entry.ok()?.file_name()).ok()
+                    })
+                    .collect(),
+            )
+        }) else {
+            continue;
+        };
+        if names.iter().any(|disk_name| disk_name == name) {
+            continue;
+        }
+        let Some(disk_name) = names
+            .iter()
+            .find(|disk_name| disk_name.eq_ignore_ascii_case(name))
+        else {
+            continue;
+        };
+        let mut new_path = BString::from(dir);
+        if !dir.is_empty() {
+            new_path.push(b'/');
+        }
+        new_path.push_str(disk_name);
+        // Both names are tracked, so there is no rename, even though only one of them can be on disk.
+        if index.entry_by_path(new_path.as_bstr()).is_some() {
+            continue;
+        }
+
+        let md = work_dir
+            .join(gix::path::from_bstr(new_path.as_bstr()))
+            .symlink_metadata()?;
+        let kind = if md.is_symlink() {
+            EntryKind::Link
+        } else if !md.is_file() {
+            continue;
+        } else if gix::fs::is_executable(&md) {
+            EntryKind::BlobExecutable
+        } else {
+            EntryKind::Blob
+        };
+        let state = ChangeState {
+            id: repo.object_hash().null(),
+            kind,
+        };
+        let previous_change = changes.iter().find(|change| change.path == path);
+        // The previous state is the one in `HEAD^{tree}`, which may differ from the one in the index.
+        let previous = match previous_change.map(|change| &change.status) {
+            None => Some((
+                path.to_owned(),
+                ChangeState {
+                    id: entry.id,
+                    kind: into_tree_entry_kind(entry.mode)?,
+                },
+            )),
+            Some(TreeStatus::Modification { previous_state, .. }) => {
+                Some((path.to_owned(), *previous_state))
+            }
+            Some(TreeStatus::Rename {
+                previous_path,
+                previous_state,
+                ..
+            }) => Some((previous_path.clone(), *previous_state)),
+            Some(TreeStatus::Addition { .. }) => None,
+            Some(TreeStatus::Deletion { previous_state }) => {
+                Some((path.to_owned(), *previous_state))
+            }
+        };
+        changes.retain(|change| change.path != path && change.path != new_path);
+        match previous {
+            Some((previous_path, previous_state)) if track_renames => {
+                changes.push(TreeChange {
+                    path: new_path,
+                    status: TreeStatus::Rename {
+                        previous_path,
+                        previous_state,
+                        state,
+                        flags: ModeFlags::calculate(&previous_state, &state),
+                    },
+                });
+            }
+            Some((previous_path, previous_state)) => {
+                changes.push(TreeChange {
+                    path: previous_path,
+                    status: TreeStatus::Deletion { previous_state },
+                });
+                changes.push(TreeChange {
+                    path: new_path,
+                    status: TreeStatus::Addition {
+                        state,
+                        is_untracked: true,
+                    },
+                });
+            }
+            None => {
+                changes.push(TreeChange {
+                    path: new_path,
+                    status: TreeStatus::Addition {
+                        state,
+                        is_untracked: false,
+                    },
+                });
+            }
+        }
+        folded = true;
+    }
+    if folded {
+        changes.sort_by(|a, b| a.path.cmp(&b.path));
+    }
+    Ok(())
+}
+
 fn cmp_prefer_overlapping(a: &TreeChange, b: &TreeChange) -> Ordering {
     if a.path == b.path
         || a.previous_path() == Some(b.path.as_bstr())
@@ -932,6 +1224,20 @@ impl TreeChange {
                     out.push_str(&hunk.diff);
                 }
             }
+            // Like Git, show submodules as the commits they point to.
+            Some(UnifiedPatch::Submodule { previous_id, id }) => {
+                out.push_str(match (previous_id, id) {
+                    (Some(_), Some(_)) => "@@ -1 +1 @@\n",
+                    (None, _) => "@@ -0,0 +1 @@\n",
+                    (_, None) => "@@ -1 +0,0 @@\n",
+                });
+                if let Some(previous_id) = previous_id {
+                    out.push_str(format!("-Subproject commit {previous_id}\n"));
+                }
+                if let Some(id) = id {
+                    out.push_str(format!("+Subproject commit {id}\n"));
+                }
+            }
             None => {}
             _ => return Ok(None),
         }
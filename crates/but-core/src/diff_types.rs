@@ -20,6 +20,13 @@ pub struct DiffSpec {
     /// If empty, the whole file is taken as is if this seems to be an addition.
     /// Otherwise, the whole file is being deleted.
     pub hunk_headers: Vec<HunkHeader>,
+    /// The mode transition of the entry that is committed along with its `hunk_headers`, like the executable bit being
+    /// added or removed, so it isn't lost when specs are flattened or moved between commits.
+    ///
+    /// Hunks from the worktree are always committed with the mode of the worktree entry, so clients that don't know
+    /// about mode changes can leave this `None`.
+    #[serde(default)]
+    pub mode_flags: Option<ModeFlags>,
 }
 
 impl From<&TreeChange> for DiffSpec {
@@ -28,6 +35,7 @@ impl From<&TreeChange> for DiffSpec {
             previous_path: change.previous_path().map(ToOwned::to_owned),
             path: change.path.to_owned(),
             hunk_headers: vec![],
+            mode_flags: change.status.flags(),
         }
     }
 }
@@ -38,6 +46,7 @@ impl From<TreeChange> for DiffSpec {
             previous_path: change.previous_path().map(ToOwned::to_owned),
             path: change.path.to_owned(),
             hunk_headers: vec![],
+            mode_flags: change.status.flags(),
         }
     }
 }
@@ -66,6 +75,8 @@ impl From<&crate::unified_diff::DiffHunk> for HunkHeader {
             new_start,
             new_lines,
             diff: _,
+            encoding: _,
+            word_diff: _,
         }: &crate::unified_diff::DiffHunk,
     ) -> Self {
         Self {
@@ -85,6 +96,8 @@ impl From<crate::unified_diff::DiffHunk> for HunkHeader {
             new_start,
             new_lines,
             diff: _,
+            encoding: _,
+            word_diff: _,
         }: crate::unified_diff::DiffHunk,
     ) -> Self {
         Self {
@@ -123,7 +136,7 @@ impl std::fmt::Debug for HunkHeader {
 /// Computed using the file kinds/modes of two [`crate::ChangeState`] instances to represent
 /// the *dominant* change to display. Note that it can stack with a content change,
 /// but *should not only in case of a `TypeChange*`*.
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[expect(missing_docs)]
 pub enum ModeFlags {
     ExecutableBitAdded,
@@ -0,0 +1,111 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Context as _;
+use bstr::{BStr, ByteSlice};
+use serde::Serialize;
+
+/// Where the pattern that decided if a path is ignored is from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum IgnoreSource {
+    /// A `.gitignore` file in the worktree.
+    Gitignore,
+    /// The `.git/info/exclude` file of the repository.
+    InfoExclude,
+    /// The file configured with `core.excludesFile`, or `$XDG_CONFIG_HOME/git/ignore` by default.
+    ExcludesFile,
+}
+
+/// The last pattern of all ignore files that matched a path, which is the one deciding if it's ignored.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IgnorePattern {
+    /// The pattern as written in its file, like `target/` or `!keep.log`.
+    pub pattern: String,
+    /// `true` if the pattern starts with `!`, and thus re-includes a path that was ignored by a previous pattern.
+    pub negated: bool,
+    /// What kind of file the pattern is from.
+    pub source: IgnoreSource,
+    /// The file the pattern is from, relative to the worktree if it's inside of it.
+    pub file: Option<PathBuf>,
+    /// The number of the line of the pattern in `file`, starting at 1.
+    pub line_number: usize,
+}
+
+/// Why a path in the worktree is ignored, or not, as returned by [`explain_ignore()`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IgnoreExplanation {
+    /// The worktree-relative path that was explained.
+    pub path: String,
+    /// `true` if changes to the path are hidden from worktree changes.
+    pub is_ignored: bool,
+    /// `true` if the path is tracked, which means it isn't ignored no matter which pattern matches it.
+    pub is_tracked: bool,
+    /// The pattern that decided if the path is excluded, or `None` if there is no such pattern.
+    pub pattern: Option<IgnorePattern>,
+}
+
+/// Explain if changes to the worktree-relative `rela_path` of `repo` are ignored, and which pattern
+/// of `.gitignore` files, `.git/info/exclude` or the file configured in `core.excludesFile` is responsible.
+///
+/// This uses the same rules as the computation of [worktree changes](crate::diff::worktree_changes()),
+/// negations and patterns matching a parent directory included. Note that `core.excludesFile` is only
+/// honored if `repo` was opened with the global configuration.
+pub fn explain_ignore(
+    repo: &gix::Repository,
+    rela_path: &BStr,
+) -> anyhow::Result<IgnoreExplanation> {
+    let workdir = repo
+        .workdir()
+        .context("Cannot explain ignored paths in bare repositories")?;
+    let rela_path = rela_path.trim_end_with(|c| c == '/').as_bstr();
+    let path = gix::path::try_from_bstr(rela_path)?;
+    let is_dir = workdir.join(&path).is_dir();
+
+    let index = repo.index_or_empty()?;
+    let is_tracked = if is_dir {
+        index.path_is_directory(rela_path)
+    } else {
+        index.entry_by_path(rela_path).is_some()
+    };
+    let mut excludes = repo.excludes(
+        &index,
+        None,
+        gix::worktree::stack::state::ignore::Source::WorktreeThenIdMappingIfNotSkipped,
+    )?;
+    let platform = excludes.at_path(
+        path.as_ref(),
+        is_dir.then_some(gix::index::entry::Mode::DIR),
+    )?;
+    let pattern = platform.matching_exclude_pattern().map(|m| IgnorePattern {
+        pattern: m.pattern.to_string(),
+        negated: m.pattern.is_negative(),
+        source: source_of(m.source, workdir),
+        file: m
+            .source
+            .map(|source| source.strip_prefix(workdir).unwrap_or(source).to_owned()),
+        line_number: m.sequence_number,
+    });
+    Ok(IgnoreExplanation {
+        path: rela_path.to_str_lossy().into_owned(),
+        is_ignored: !is_tracked && pattern.as_ref().is_some_and(|pattern| !pattern.negated),
+        is_tracked,
+        pattern,
+    })
+}
+
+/// Classify the ignore `file` by its location relative to `workdir`.
+fn source_of(file: Option<&Path>, workdir: &Path) -> IgnoreSource {
+    match file {
+        Some(file) if file.ends_with(Path::new("info").join("exclude")) => {
+            IgnoreSource::InfoExclude
+        }
+        Some(file)
+            if file.starts_with(workdir) && file.file_name() == Some(".gitignore".as_ref()) =>
+        {
+            IgnoreSource::Gitignore
+        }
+        _ => IgnoreSource::ExcludesFile,
+    }
+}
@@ -0,0 +1,122 @@
+use bstr::{BStr, ByteSlice};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+const VERSION_LINE: &str = "version https://git-lfs.github.com/spec/v1";
+/// Pointer files are tiny, anything larger can't be one.
+const MAX_POINTER_SIZE: usize = 1024;
+
+/// The content of a pointer file that Git stores instead of the content of a file tracked by Git LFS.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LfsPointer {
+    /// The SHA-256 of the actual content as hex string.
+    pub oid: String,
+    /// The size of the actual content in bytes.
+    pub size: u64,
+}
+
+impl LfsPointer {
+    /// Parse `data` as pointer file, or return `None` if it isn't one.
+    pub fn from_bytes(data: &[u8]) -> Option<Self> {
+        if data.len() > MAX_POINTER_SIZE {
+            return None;
+        }
+        let mut lines = data.lines();
+        if lines.next()? != VERSION_LINE.as_bytes() {
+            return None;
+        }
+        let (mut oid, mut size) = (None, None);
+        for line in lines {
+            if let Some(hex) = line.strip_prefix(b"oid sha256:") {
+                oid = Some(hex.to_str().ok()?.to_owned());
+            } else if let Some(bytes) = line.strip_prefix(b"size ") {
+                size = Some(bytes.to_str().ok()?.parse().ok()?);
+            }
+        }
+        Some(LfsPointer {
+            oid: oid?,
+            size: size?,
+        })
+    }
+
+    /// Compute the pointer for the actual `content` of a file.
+    pub fn for_content(content: &[u8]) -> Self {
+        let oid = Sha256::digest(content)
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect();
+        LfsPointer {
+            oid,
+            size: content.len() as u64,
+        }
+    }
+
+    /// Serialize this instance into a pointer file.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        format!(
+            "{VERSION_LINE}\noid sha256:{oid}\nsize {size}\n",
+            oid = self.oid,
+            size = self.size
+        )
+        .into_bytes()
+    }
+}
+
+/// Find the paths that are tracked by Git LFS through the `filter=lfs` attribute.
+pub struct LfsAttributes<'repo> {
+    stack: gix::AttributeStack<'repo>,
+    outcome: gix::attrs::search::Outcome,
+}
+
+impl<'repo> LfsAttributes<'repo> {
+    /// Read attributes from the worktree of `repo`, and from `index` for files that aren't checked out.
+    pub fn new(repo: &'repo gix::Repository, index: &gix::index::State) -> anyhow::Result<Self> {
+        let stack = repo.attributes_only(
+            index,
+            gix::worktree::stack::state::attributes::Source::WorktreeThenIdMapping,
+        )?;
+        let outcome = stack.selected_attribute_matches(Some("filter"));
+        Ok(LfsAttributes { stack, outcome })
+    }
+
+    /// Return `true` if the worktree-relative `rela_path` is tracked by Git LFS.
+    pub fn is_lfs(&mut self, rela_path: &BStr) -> anyhow::Result<bool> {
+        let platform = self.stack.at_entry(rela_path, None)?;
+        Ok(platform.matching_attributes(&mut self.outcome)
+            && self
+                .outcome
+                .iter()
+                .any(|m| m.assignment.state.as_bstr() == Some("lfs".into())))
+    }
+}
+
+/// Assure the blob `id` of a file tracked by Git LFS is a pointer, and return the id of the pointer blob.
+///
+/// If the `lfs` clean filter didn't run, for instance because Git LFS isn't configured, the blob has the actual content.
+/// Then the content is moved into the LFS object storage of `repo` like the clean filter would,
+/// so it can be pushed with `git lfs push`.
+pub fn ensure_pointer_blob(
+    repo: &gix::Repository,
+    id: gix::ObjectId,
+) -> anyhow::Result<gix::ObjectId> {
+    let blob = repo.find_blob(id)?;
+    if LfsPointer::from_bytes(&blob.data).is_some() {
+        return Ok(id);
+    }
+    let pointer = LfsPointer::for_content(&blob.data);
+    let object_path = repo
+        .common_dir()
+        .join("lfs")
+        .join("objects")
+        .join(&pointer.oid[..2])
+        .join(&pointer.oid[2..4])
+        .join(&pointer.oid);
+    if !object_path.is_file() {
+        if let Some(dir) = object_path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(&object_path, &blob.data)?;
+    }
+    Ok(repo.write_blob(pointer.to_bytes())?.detach())
+}
@@ -91,11 +91,28 @@ pub mod cmd;
 pub mod settings;
 pub use settings::git::types::GitConfigSettings;
 
+pub mod signing;
+
 pub mod snapshot;
 
 /// Utilities to deal with git worktrees.
 pub mod worktree;
 
+/// Explain why paths in the worktree are ignored.
+pub mod ignore;
+
+/// Utilities to respect sparse checkouts.
+pub mod sparse;
+
+/// Utilities to respect files marked as unchanged in the index.
+pub mod unchanged;
+
+/// Support for files tracked by Git LFS.
+pub mod lfs;
+
+/// Support for shallow clones and partial clones, whose object database lacks objects.
+pub mod shallow;
+
 /// Utilities to create Git trees.
 pub mod tree;
 
@@ -259,6 +276,28 @@ pub enum UnifiedPatch {
         /// The total amount of lines removed.
         lines_removed: u32,
     },
+    /// The resource is a submodule whose pointer changed, which is shown as the commits it pointed to before and after.
+    ///
+    /// This deliberately stands in for a dedicated `TreeChange::Submodule { old, new }`: the [`TreeChange`] of a submodule
+    /// stays an ordinary [`TreeStatus`] with states of kind [`Commit`](gix::object::tree::EntryKind::Commit),
+    /// so everything that handles whole-file changes, like assignments and commits, handles submodules as well.
+    #[serde(rename_all = "camelCase")]
+    Submodule {
+        /// The commit the submodule pointed to before, or `None` if it was added.
+        #[serde(with = "but_serde::object_id_opt")]
+        previous_id: Option<gix::ObjectId>,
+        /// The commit the submodule points to now, or `None` if it was removed.
+        #[serde(with = "but_serde::object_id_opt")]
+        id: Option<gix::ObjectId>,
+    },
+    /// The resource is tracked by Git LFS, which is shown as the pointers Git stores instead of the actual content.
+    #[serde(rename_all = "camelCase")]
+    LfsPointer {
+        /// The pointer before, or `None` if the file was added.
+        previous: Option<lfs::LfsPointer>,
+        /// The pointer now, or `None` if the file was deleted.
+        current: Option<lfs::LfsPointer>,
+    },
 }
 
 /// Either git reference or a virtual reference (i.e. a reference not visible in Git).
@@ -393,6 +432,11 @@ pub enum IgnoredWorktreeTreeChangeStatus {
     /// A tree-index change was effectively undone by an index-worktree change. Thus, the version in the worktree
     /// is the same as what Git is currently tracking.
     TreeIndexWorktreeChangeIneffective,
+    /// The path isn't part of the sparse checkout, so changes to it can't be committed.
+    OutsideSparseCheckout,
+    /// The path has the `skip-worktree` or `assume-unchanged` bit set in the index, so its changes are hidden
+    /// until the bit is cleared.
+    MarkedUnchanged,
 }
 
 /// A way to indicate that a path in the index isn't suitable for committing and needs to be dealt with.
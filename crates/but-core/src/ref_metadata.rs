@@ -424,6 +424,119 @@ impl StackId {
     }
 }
 
+/// The identities to use when creating commits in a particular stack, overriding what's configured in Git.
+///
+/// Each unset identity falls back to the Git configuration, as if there was no override.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct StackIdentity {
+    /// The author of new commits, instead of `author.name|email` or `user.name|email`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<Identity>,
+    /// The committer of new and rewritten commits, instead of `committer.name|email` or `user.name|email`.
+    ///
+    /// Note that this also overrides `gitbutler.gitbutlerCommitter`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub committer: Option<Identity>,
+}
+
+/// A name and an email, like the ones in a commit signature.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Identity {
+    /// The name of the person, like `user.name`.
+    pub name: String,
+    /// The email of the person, like `user.email`.
+    pub email: String,
+}
+
+/// Access
+impl StackIdentity {
+    /// Return `true` if nothing is overridden.
+    pub fn is_empty(&self) -> bool {
+        self.author.is_none() && self.committer.is_none()
+    }
+}
+
+/// Free-form text about a stack and its commits, for instance to generate review descriptions from.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct StackNotes {
+    /// What the stack as a whole is about.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Notes about individual commits of the stack, which follow the commits as they are rewritten.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub commits: Vec<CommitNote>,
+}
+
+/// A note about a single commit.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitNote {
+    /// The commit the note is about.
+    #[serde(with = "but_serde::object_id")]
+    pub commit_id: gix::ObjectId,
+    /// The text of the note.
+    pub text: String,
+}
+
+/// Access
+impl StackNotes {
+    /// Return `true` if there is neither a description nor a note about any commit.
+    pub fn is_empty(&self) -> bool {
+        self.description.is_none() && self.commits.is_empty()
+    }
+
+    /// Return the note about the commit with `commit_id`, if there is one.
+    pub fn commit_note(&self, commit_id: &gix::oid) -> Option<&str> {
+        self.commits
+            .iter()
+            .find(|note| note.commit_id == commit_id)
+            .map(|note| note.text.as_str())
+    }
+}
+
+/// Mutation
+impl StackNotes {
+    /// Set the note about the commit with `commit_id` to `text`, or remove it if `text` is `None` or empty.
+    pub fn set_commit_note(&mut self, commit_id: gix::ObjectId, text: Option<String>) {
+        self.commits.retain(|note| note.commit_id != commit_id);
+        if let Some(text) = text.filter(|text| !text.trim().is_empty()) {
+            self.commits.push(CommitNote { commit_id, text });
+        }
+    }
+
+    /// Let notes follow their commits after a rebase, with `mapping` yielding `(old, new)` ids of each rewritten commit.
+    ///
+    /// If multiple commits were rewritten into the same commit, like when squashing, their notes are combined.
+    pub fn rewrite_commits(
+        &mut self,
+        mapping: impl IntoIterator<Item = (gix::ObjectId, gix::ObjectId)>,
+    ) {
+        let mapping: std::collections::HashMap<_, _> = mapping.into_iter().collect();
+        if mapping.is_empty() {
+            return;
+        }
+        let mut rewritten: Vec<CommitNote> = Vec::with_capacity(self.commits.len());
+        for mut note in std::mem::take(&mut self.commits) {
+            if let Some(new_id) = mapping.get(&note.commit_id) {
+                note.commit_id = *new_id;
+            }
+            match rewritten
+                .iter_mut()
+                .find(|existing| existing.commit_id == note.commit_id)
+            {
+                Some(existing) => {
+                    existing.text.push_str("\n\n");
+                    existing.text.push_str(&note.text);
+                }
+                None => rewritten.push(note),
+            }
+        }
+        self.commits = rewritten;
+    }
+}
+
 /// A stack that was, at some point in time, applied to the workspace, i.e. a parent of the *workspace commit*.
 /// Note that if `in_workspace` is `false`, it's not considered unapplied.
 #[derive(Debug, Clone, PartialEq, Eq)]
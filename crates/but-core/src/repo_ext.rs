@@ -5,7 +5,7 @@ use gix::{
     prelude::ObjectIdExt,
 };
 
-use crate::{GitConfigSettings, commit::TreeKind};
+use crate::{GitConfigSettings, commit::TreeKind, ref_metadata::StackIdentity};
 
 /// Easy access of settings relevant to GitButler for retrieval and storage in Git settings.
 pub trait RepositoryExt: Sized {
@@ -39,6 +39,11 @@ pub trait RepositoryExt: Sized {
     /// This means it needs an object cache relative to the amount of files in the repository.
     fn for_tree_diffing(self) -> anyhow::Result<Self>;
 
+    /// Configure the repository so that new commits are authored and committed as set in `identity`,
+    /// which affects [`Self::commit_signatures()`] and everything else that queries the author or committer.
+    /// Unset identities are left as configured in Git.
+    fn with_stack_identity(self, identity: &StackIdentity) -> anyhow::Result<Self>;
+
     /// Just like the above, but with `gix` types.
     fn merges_cleanly(
         &self,
@@ -184,6 +189,23 @@ impl RepositoryExt for gix::Repository {
         Ok(self)
     }
 
+    fn with_stack_identity(mut self, identity: &StackIdentity) -> anyhow::Result<Self> {
+        use gix::config::tree::{Author, Committer};
+        {
+            let mut config = self.config_snapshot_mut();
+            if let Some(author) = &identity.author {
+                config.set_value(&Author::NAME, author.name.as_str())?;
+                config.set_value(&Author::EMAIL, author.email.as_str())?;
+            }
+            if let Some(committer) = &identity.committer {
+                config.set_value(&Committer::NAME, committer.name.as_str())?;
+                config.set_value(&Committer::EMAIL, committer.email.as_str())?;
+                config.set_raw_value(&"gitbutler.gitbutlerCommitter", "false")?;
+            }
+        }
+        Ok(self)
+    }
+
     fn merges_cleanly(
         &self,
         ancestor_tree: gix::ObjectId,
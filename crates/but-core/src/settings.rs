@@ -9,6 +9,9 @@ pub mod git {
     const GITBUTLER_SIGN_COMMITS: &str = "gitbutler.signCommits";
     const GITBUTLER_GERRIT_MODE: &str = "gitbutler.gerritMode";
     const GITBUTLER_FORGE_TEMPLATE_PATH: &str = "gitbutler.forgeReviewTemplatePath";
+    const GITBUTLER_RUN_HOOKS: &str = "gitbutler.runHooks";
+    const GITBUTLER_FORGE_STACK_FOOTER: &str = "gitbutler.forgeStackFooter";
+    const GITBUTLER_PROTECTED_BRANCHES: &str = "gitbutler.protectedBranches";
     const SIGNING_KEY: &str = "user.signingKey";
     const SIGNING_FORMAT: &str = "gpg.format";
     const GPG_PROGRAM: &str = "gpg.program";
@@ -27,6 +30,9 @@ pub mod git {
             pub gitbutler_sign_commits: Option<bool>,
             pub gitbutler_gerrit_mode: Option<bool>,
             pub gitbutler_forge_review_template_path: Option<BStringForFrontend>,
+            pub gitbutler_run_hooks: Option<bool>,
+            pub gitbutler_forge_stack_footer: Option<bool>,
+            pub gitbutler_protected_branches: Option<BStringForFrontend>,
             pub signing_key: Option<BStringForFrontend>,
             pub signing_format: Option<BStringForFrontend>,
             pub gpg_program: Option<BStringForFrontend>,
@@ -39,6 +45,9 @@ pub mod git {
                     gitbutler_sign_commits,
                     gitbutler_gerrit_mode,
                     gitbutler_forge_review_template_path,
+                    gitbutler_run_hooks,
+                    gitbutler_forge_stack_footer,
+                    gitbutler_protected_branches,
                     signing_key,
                     signing_format,
                     gpg_program,
@@ -50,6 +59,9 @@ pub mod git {
                     gitbutler_gerrit_mode,
                     gitbutler_forge_review_template_path: gitbutler_forge_review_template_path
                         .map(Into::into),
+                    gitbutler_run_hooks,
+                    gitbutler_forge_stack_footer,
+                    gitbutler_protected_branches: gitbutler_protected_branches.map(Into::into),
                     signing_key: signing_key.map(Into::into),
                     signing_format: signing_format.map(Into::into),
                     gpg_program: gpg_program
@@ -66,6 +78,9 @@ pub mod git {
                     gitbutler_sign_commits,
                     gitbutler_gerrit_mode,
                     gitbutler_forge_review_template_path,
+                    gitbutler_run_hooks,
+                    gitbutler_forge_stack_footer,
+                    gitbutler_protected_branches,
                     signing_key,
                     signing_format,
                     gpg_program,
@@ -77,6 +92,9 @@ pub mod git {
                     gitbutler_gerrit_mode,
                     gitbutler_forge_review_template_path: gitbutler_forge_review_template_path
                         .map(Into::into),
+                    gitbutler_run_hooks,
+                    gitbutler_forge_stack_footer,
+                    gitbutler_protected_branches: gitbutler_protected_branches.map(Into::into),
                     signing_key: signing_key.map(Into::into),
                     signing_format: signing_format.map(Into::into),
                     gpg_program: gpg_program.map(Into::into),
@@ -106,6 +124,17 @@ pub mod git {
             pub gitbutler_gerrit_mode: Option<bool>,
             /// The path to the review description template to be used for this repository.
             pub gitbutler_forge_review_template_path: Option<BString>,
+            /// If `true`, the Git hooks of the repository are run when GitButler creates commits.
+            /// This value is always set when querying it, and defaults to `true` just like Git would.
+            pub gitbutler_run_hooks: Option<bool>,
+            /// If `true`, the reviews of stacks with more than one review get a footer listing all reviews of the stack,
+            /// which is refreshed when branches are pushed or reordered.
+            /// This value is always set when querying it, and defaults to `true`.
+            pub gitbutler_forge_stack_footer: Option<bool>,
+            /// Whitespace-separated glob patterns of the short names of branches that GitButler must not commit to,
+            /// amend, force-push or delete, like `main release/*`.
+            /// See [`ProtectedBranches`](crate::branch::ProtectedBranches) for how they are enforced.
+            pub gitbutler_protected_branches: Option<BString>,
             /// `user.signingKey`.
             pub signing_key: Option<BString>,
             /// `gpg.format`
@@ -131,6 +160,12 @@ pub mod git {
             let gitbutler_forge_review_template_path = config
                 .string(GITBUTLER_FORGE_TEMPLATE_PATH)
                 .map(Cow::into_owned);
+            let gitbutler_run_hooks = config.boolean(GITBUTLER_RUN_HOOKS).or(Some(true));
+            let gitbutler_forge_stack_footer =
+                config.boolean(GITBUTLER_FORGE_STACK_FOOTER).or(Some(true));
+            let gitbutler_protected_branches = config
+                .string(GITBUTLER_PROTECTED_BRANCHES)
+                .map(Cow::into_owned);
             let signing_key = config.string(SIGNING_KEY).map(Cow::into_owned);
             let signing_format = config.string(SIGNING_FORMAT).map(Cow::into_owned);
             let gpg_program = config.trusted_program(GPG_PROGRAM).map(Cow::into_owned);
@@ -139,6 +174,9 @@ pub mod git {
                 gitbutler_sign_commits,
                 gitbutler_gerrit_mode,
                 gitbutler_forge_review_template_path,
+                gitbutler_run_hooks,
+                gitbutler_forge_stack_footer,
+                gitbutler_protected_branches,
                 signing_key,
                 signing_format,
                 gpg_program,
@@ -169,6 +207,22 @@ pub mod git {
                     forge_template_path.as_bstr(),
                 )?;
             };
+            if let Some(run_hooks) = self.gitbutler_run_hooks {
+                config.set_raw_value(
+                    &GITBUTLER_RUN_HOOKS,
+                    if run_hooks { "true" } else { "false" },
+                )?;
+            };
+            if let Some(stack_footer) = self.gitbutler_forge_stack_footer {
+                config.set_raw_value(
+                    &GITBUTLER_FORGE_STACK_FOOTER,
+                    if stack_footer { "true" } else { "false" },
+                )?;
+            };
+            if let Some(protected_branches) = &self.gitbutler_protected_branches {
+                config
+                    .set_raw_value(&GITBUTLER_PROTECTED_BRANCHES, protected_branches.as_bstr())?;
+            };
             if let Some(signing_key) = &self.signing_key {
                 config.set_raw_value(&SIGNING_KEY, signing_key.as_bstr())?;
             };
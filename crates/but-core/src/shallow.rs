@@ -0,0 +1,224 @@
+use std::{collections::BTreeMap, io::Write as _, process::Stdio};
+
+use anyhow::{Result, bail};
+use bstr::ByteSlice;
+use but_error::ButError;
+use gix::{object::tree::EntryKind, prelude::ObjectIdExt as _};
+
+use crate::TreeChange;
+
+/// Return the names of the remotes that objects missing in a partial clone of `repo` can be fetched from.
+///
+/// These are the remotes with `remote.<name>.promisor` set, or with a `remote.<name>.partialCloneFilter`,
+/// and the remote named in `extensions.partialClone`.
+pub fn promisor_remotes(repo: &gix::Repository) -> Vec<String> {
+    let config = repo.config_snapshot();
+    let mut remotes: Vec<String> = repo
+        .remote_names()
+        .into_iter()
+        .filter(|name| {
+            let name = name.to_str_lossy();
+            config
+                .boolean(format!("remote.{name}.promisor").as_str())
+                .unwrap_or(false)
+                || config
+                    .string(format!("remote.{name}.partialCloneFilter").as_str())
+                    .is_some()
+        })
+        .map(|name| name.to_str_lossy().into_owned())
+        .collect();
+    if let Some(name) = config
+        .string("extensions.partialClone")
+        .map(|name| name.to_str_lossy().into_owned())
+        .filter(|name| !name.is_empty() && !remotes.contains(name))
+    {
+        remotes.push(name);
+    }
+    remotes
+}
+
+/// Return `true` if `repo` is a partial clone, whose objects may be missing until they are fetched
+/// from one of its [promisor remotes](promisor_remotes()).
+pub fn is_partial_clone(repo: &gix::Repository) -> bool {
+    !promisor_remotes(repo).is_empty()
+}
+
+/// Fetch those of `ids` that aren't in the object database of the partial clone `repo` from its promisor remote,
+/// all at once, and return the amount of objects that were missing.
+///
+/// Null ids are ignored, and nothing happens if `repo` isn't a partial clone, which is when missing objects are an error
+/// that's reported by the operation that needs them.
+pub fn fetch_missing_objects(
+    repo: &gix::Repository,
+    ids: impl IntoIterator<Item = gix::ObjectId>,
+) -> Result<usize> {
+    let mut missing: Vec<_> = ids
+        .into_iter()
+        .filter(|id| !id.is_null() && !repo.has_object(id))
+        .collect();
+    if missing.is_empty() {
+        return Ok(0);
+    }
+    let Some(remote) = promisor_remotes(repo).into_iter().next() else {
+        return Ok(0);
+    };
+    missing.sort();
+    missing.dedup();
+
+    // This is what Git does itself when it lazily fetches objects of a partial clone.
+    let mut child =
+        std::process::Command::from(gix::command::prepare(gix::path::env::exe_invocation()))
+            .current_dir(repo.workdir().unwrap_or(repo.git_dir()))
+            .args([
+                "-c",
+                "fetch.negotiationAlgorithm=noop",
+                "fetch",
+                remote.as_str(),
+                "--no-tags",
+                "--no-write-fetch-head",
+                "--recurse-submodules=no",
+                "--filter=blob:none",
+                "--stdin",
+            ])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()?;
+    {
+        let mut stdin = child.stdin.take().expect("configured");
+        for id in &missing {
+            writeln!(stdin, "{id}")?;
+        }
+    }
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        bail!(
+            "Could not fetch {count} missing object(s) from promisor remote '{remote}'\n\n{stderr}",
+            count = missing.len(),
+            stderr = output.stderr.to_str_lossy()
+        );
+    }
+    Ok(missing.len())
+}
+
+/// What a list of [changes](TreeChange) was computed from, which determines which of their blobs can be fetched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangesSource {
+    /// The changes are between two trees, so the blobs of both of their sides are in the object database
+    /// or can be fetched from a promisor remote.
+    Trees,
+    /// The changes are between a tree and the worktree, so their current state was hashed from files
+    /// and is never in the object database.
+    Worktree,
+}
+
+/// Fetch the blobs that are needed to diff `changes`, which were computed from `source`, all at once if `repo`
+/// is a partial clone, so computing their diffs doesn't run into missing objects one at a time.
+///
+/// Failures are only logged, as the diff reports the missing objects it actually needs.
+pub fn prefetch_blobs_of_changes<'a>(
+    repo: &gix::Repository,
+    changes: impl IntoIterator<Item = &'a TreeChange>,
+    source: ChangesSource,
+) {
+    if !is_partial_clone(repo) {
+        return;
+    }
+    let ids = changes.into_iter().flat_map(|change| {
+        let current_state = match source {
+            ChangesSource::Trees => change.status.state(),
+            ChangesSource::Worktree => None,
+        };
+        current_state
+            .into_iter()
+            .chain(
+                change
+                    .status
+                    .previous_state_and_path()
+                    .map(|(state, _)| state),
+            )
+            .filter(|state| matches!(state.kind, EntryKind::Blob | EntryKind::BlobExecutable))
+            .map(|state| state.id)
+    });
+    if let Err(err) = fetch_missing_objects(repo, ids) {
+        tracing::warn!(?err, "could not prefetch blobs for diffing");
+    }
+}
+
+/// Fetch the blobs changed between `base` and `ours`, and between `base` and `theirs`, if `repo` is a partial clone,
+/// so the trees can be merged without running into missing objects.
+///
+/// The blobs of paths changed on only one side aren't needed as the merge picks them without looking at their content.
+pub fn fetch_blobs_for_merge(
+    repo: &gix::Repository,
+    base: gix::ObjectId,
+    ours: gix::ObjectId,
+    theirs: gix::ObjectId,
+) -> Result<()> {
+    if !is_partial_clone(repo) {
+        return Ok(());
+    }
+    let ours_changes = changed_blobs(repo, base, ours)?;
+    let theirs_changes = changed_blobs(repo, base, theirs)?;
+    let ids = ours_changes
+        .iter()
+        .filter_map(|(path, ours_ids)| {
+            let theirs_ids = theirs_changes.get(path)?;
+            Some(ours_ids.iter().chain(theirs_ids).copied())
+        })
+        .flatten();
+    fetch_missing_objects(repo, ids)?;
+    Ok(())
+}
+
+/// Return the paths of all blobs that changed between the trees `lhs` and `rhs`, along with their ids on either side.
+fn changed_blobs(
+    repo: &gix::Repository,
+    lhs: gix::ObjectId,
+    rhs: gix::ObjectId,
+) -> Result<BTreeMap<bstr::BString, Vec<gix::ObjectId>>> {
+    let lhs = lhs.attach(repo).object()?.peel_to_tree()?;
+    let rhs = rhs.attach(repo).object()?.peel_to_tree()?;
+    // Rename tracking would need the content of the blobs that are about to be fetched.
+    let changes = repo.diff_tree_to_tree(
+        Some(&lhs),
+        Some(&rhs),
+        gix::diff::Options::default().with_rewrites(None),
+    )?;
+    Ok(changes
+        .into_iter()
+        .filter(|change| change.entry_mode().is_blob())
+        .map(|change| {
+            use gix::object::tree::diff::ChangeDetached as Change;
+            let ids = match &change {
+                Change::Addition { id, .. } | Change::Deletion { id, .. } => vec![*id],
+                Change::Modification {
+                    previous_id, id, ..
+                }
+                | Change::Rewrite {
+                    source_id: previous_id,
+                    id,
+                    ..
+                } => vec![*previous_id, *id],
+            };
+            (change.location().to_owned(), ids)
+        })
+        .collect())
+}
+
+/// Turn `err`, which happened while looking at the history of `commit_id`, into a [`ButError::ShallowHistory`]
+/// if `repo` is a shallow clone, as the history beyond its shallow boundary is the likely cause.
+/// Otherwise `err` is returned unchanged.
+pub fn into_shallow_history_error(
+    repo: &gix::Repository,
+    commit_id: gix::ObjectId,
+    err: anyhow::Error,
+) -> anyhow::Error {
+    if repo.is_shallow() {
+        err.context(ButError::ShallowHistory {
+            commit_id: commit_id.to_string(),
+        })
+    } else {
+        err
+    }
+}
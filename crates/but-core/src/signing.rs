@@ -0,0 +1,273 @@
+//! Signing of commits with OpenPGP, X.509 or SSH keys, configured just like Git does it.
+//!
+//! Whether commits are signed at all is controlled by [`GitConfigSettings::gitbutler_sign_commits`](crate::GitConfigSettings),
+//! which, along with the signing key, format and programs, can be overridden per project in its local Git configuration.
+use std::{borrow::Cow, io::Write, path::Path, process::Stdio};
+
+use anyhow::{Context as _, anyhow, bail};
+use bstr::{BStr, BString, ByteSlice};
+use but_error::Code;
+use gix::objs::WriteTo;
+
+use crate::{GitConfigSettings, RepositoryExt, cmd::prepare_with_shell_on_windows};
+
+/// Remove any existing signature from `commit`, and sign it if signing is enabled in `repo`.
+///
+/// Signatures are removed even if signing is disabled, to prevent rewritten commits from keeping
+/// a signature that doesn't match their content anymore.
+///
+/// If signing fails, signing is disabled in the repository-local configuration to avoid failing over and over,
+/// unless it was explicitly enabled outside the repository, which implies user intervention.
+pub fn sign_commit(repo: &gix::Repository, commit: &mut gix::objs::Commit) -> anyhow::Result<()> {
+    if !remove_signature_and_check_signing(repo, commit)? {
+        return Ok(());
+    }
+
+    let mut buf = Vec::new();
+    commit.write_to(&mut buf)?;
+    match sign_buffer(repo, &buf) {
+        Ok(signature) => {
+            commit
+                .extra_headers
+                .push((gix::objs::commit::SIGNATURE_FIELD_NAME.into(), signature));
+            Ok(())
+        }
+        Err(err) => {
+            if repo
+                .config_snapshot()
+                .boolean_filter("gitbutler.signCommits", |md| {
+                    md.source != gix::config::Source::Local
+                })
+                .is_none()
+            {
+                repo.set_git_settings(&GitConfigSettings {
+                    gitbutler_sign_commits: Some(false),
+                    ..GitConfigSettings::default()
+                })?;
+                Err(anyhow!("Failed to sign commit: {}", err).context(Code::CommitSigningFailed))
+            } else {
+                tracing::warn!(
+                    "Commit signing failed but remains enabled as gitbutler.signCommits is explicitly enabled globally"
+                );
+                Err(err)
+            }
+        }
+    }
+}
+
+/// Like [`sign_commit()`], but for commits that are managed by GitButler, like workspace commits, which remain
+/// unsigned if signing fails. This way, unrelated operations don't fail, and signing isn't disabled either.
+pub fn try_sign_commit(
+    repo: &gix::Repository,
+    commit: &mut gix::objs::Commit,
+) -> anyhow::Result<()> {
+    if !remove_signature_and_check_signing(repo, commit)? {
+        return Ok(());
+    }
+
+    let mut buf = Vec::new();
+    commit.write_to(&mut buf)?;
+    match sign_buffer(repo, &buf) {
+        Ok(signature) => commit
+            .extra_headers
+            .push((gix::objs::commit::SIGNATURE_FIELD_NAME.into(), signature)),
+        Err(err) => tracing::warn!(?err, "Commit signing failed, leaving the commit unsigned"),
+    }
+    Ok(())
+}
+
+/// Remove any existing signature from `commit`, and return `true` if signing is enabled in `repo`.
+fn remove_signature_and_check_signing(
+    repo: &gix::Repository,
+    commit: &mut gix::objs::Commit,
+) -> anyhow::Result<bool> {
+    if let Some(pos) = commit
+        .extra_headers()
+        .find_pos(gix::objs::commit::SIGNATURE_FIELD_NAME)
+    {
+        commit.extra_headers.remove(pos);
+    }
+    Ok(repo.git_settings()?.gitbutler_sign_commits.unwrap_or(false))
+}
+
+/// The kind of key to sign with, as configured with `gpg.format`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    OpenPgp,
+    X509,
+    Ssh,
+}
+
+impl Format {
+    fn from_config(config: &gix::config::Snapshot<'_>) -> anyhow::Result<Self> {
+        Ok(
+            match config.string("gpg.format").as_deref().map(|f| f.as_bytes()) {
+                None | Some(b"openpgp") => Format::OpenPgp,
+                Some(b"x509") => Format::X509,
+                Some(b"ssh") => Format::Ssh,
+                Some(other) => bail!(
+                    "Unsupported signing format in gpg.format: {}",
+                    other.as_bstr()
+                ),
+            },
+        )
+    }
+}
+
+/// Sign the given `buffer` using configuration from `repo`, just like Git would.
+pub fn sign_buffer(repo: &gix::Repository, buffer: &[u8]) -> anyhow::Result<BString> {
+    // TODO: support gpg.ssh.defaultKeyCommand to get the signing key if this value doesn't exist
+    let config = repo.config_snapshot();
+    let signing_key = signing_key(repo)?;
+    let format = Format::from_config(&config)?;
+
+    if format == Format::Ssh {
+        // write commit data to a temp file so we can sign it
+        let mut signature_storage = tempfile::NamedTempFile::new()?;
+        signature_storage.write_all(buffer)?;
+        let buffer_file_to_sign_path = signature_storage.into_temp_path();
+
+        let gpg_program = config
+            .trusted_program("gpg.ssh.program")
+            .filter(|program| !program.is_empty())
+            .map_or_else(
+                || Path::new("ssh-keygen").into(),
+                |program| Cow::Owned(program.into_owned().into()),
+            );
+
+        let mut signing_cmd = prepare_with_shell_on_windows(gpg_program.into_owned())
+            .args(["-Y", "sign", "-n", "git", "-f"]);
+
+        // Write the key to a temp file. This is needs to be created in the
+        // same scope where its used; IE: in the command, otherwise the
+        // tmpfile will get removed too early.
+        let _key_storage;
+        signing_cmd = if let Some(signing_key) = as_literal_key(signing_key.as_bstr()) {
+            let mut keyfile = tempfile::NamedTempFile::new()?;
+            keyfile.write_all(signing_key.as_bytes())?;
+
+            // if on unix
+            #[cfg(unix)]
+            {
+                use std::os::unix::prelude::PermissionsExt;
+                // make sure the tempfile permissions are acceptable for a private ssh key
+                let mut permissions = keyfile.as_file().metadata()?.permissions();
+                permissions.set_mode(0o600);
+                keyfile.as_file().set_permissions(permissions)?;
+            }
+
+            let keyfile_path = keyfile.path().to_owned();
+            _key_storage = keyfile.into_temp_path();
+            signing_cmd
+                .arg(keyfile_path)
+                .arg("-U")
+                .arg(buffer_file_to_sign_path.to_path_buf())
+        } else {
+            let signing_key = config
+                .trusted_path("user.signingkey")
+                .transpose()?
+                .with_context(|| format!("Didn't trust 'ssh.signingKey': {signing_key}"))?;
+            signing_cmd
+                .arg(signing_key.into_owned())
+                .arg(buffer_file_to_sign_path.to_path_buf())
+        };
+        let output = into_command(signing_cmd)
+            .stderr(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stdin(Stdio::null())
+            .output()?;
+
+        if output.status.success() {
+            // read signed_storage path plus .sig
+            let signature_path = buffer_file_to_sign_path.with_extension("sig");
+            let sig_data = std::fs::read(signature_path)?;
+            let signature = BString::new(sig_data);
+            Ok(signature)
+        } else {
+            let stderr = BString::new(output.stderr);
+            let stdout = BString::new(output.stdout);
+            let std_both = format!("{stdout} {stderr}");
+            bail!("Failed to sign SSH: {}", std_both);
+        }
+    } else {
+        let (program_key, default_program) = match format {
+            Format::X509 => ("gpg.x509.program", "gpgsm"),
+            Format::OpenPgp | Format::Ssh => ("gpg.program", "gpg"),
+        };
+        let gpg_program = config
+            .trusted_program(program_key)
+            .filter(|program| !program.is_empty())
+            .map_or_else(
+                || Path::new(default_program).into(),
+                |program| Cow::Owned(program.into_owned().into()),
+            );
+
+        let mut cmd = into_command(
+            prepare_with_shell_on_windows(gpg_program.as_ref())
+                .args(["--status-fd=2", "-bsau"])
+                .arg(gix::path::from_bstring(signing_key))
+                .arg("-"),
+        );
+        cmd.stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .stdin(Stdio::piped());
+
+        let mut child = match cmd.spawn() {
+            Ok(child) => child,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                bail!(
+                    "Could not find '{}'. Please make sure it is in your `PATH` or configure the full path using `{program_key}` in the Git configuration",
+                    gpg_program.display()
+                )
+            }
+            Err(err) => {
+                return Err(err).context(format!("Could not execute GPG program using {cmd:?}"));
+            }
+        };
+        child.stdin.take().expect("configured").write_all(buffer)?;
+
+        let output = child.wait_with_output()?;
+        if output.status.success() {
+            // read stdout
+            let signature = BString::new(output.stdout);
+            Ok(signature)
+        } else {
+            let stderr = BString::new(output.stderr);
+            let stdout = BString::new(output.stdout);
+            let std_both = format!("{stdout} {stderr}");
+            bail!("Failed to sign GPG: {}", std_both);
+        }
+    }
+}
+
+fn into_command(prepare: gix::command::Prepare) -> std::process::Command {
+    let cmd: std::process::Command = prepare.into();
+    tracing::debug!(?cmd, "command to produce commit signature");
+    cmd
+}
+
+fn as_literal_key(maybe_key: &BStr) -> Option<&BStr> {
+    if let Some(key) = maybe_key.strip_prefix(b"key::") {
+        return Some(key.into());
+    }
+    if maybe_key.starts_with(b"ssh-") {
+        return Some(maybe_key);
+    }
+    None
+}
+
+/// Fail if there is no usable signing key.
+fn signing_key(repo: &gix::Repository) -> anyhow::Result<BString> {
+    if let Some(key) = repo.config_snapshot().string("user.signingkey") {
+        return Ok(key.into_owned());
+    }
+    tracing::info!("Falling back to committer identity as user.signingKey isn't configured.");
+    let mut buf = Vec::<u8>::new();
+    repo.committer()
+        .transpose()?
+        .context("user.signingKey isn't configured and no committer is available either")?
+        .actor()
+        .trim()
+        .write_to(&mut buf)?;
+    Ok(buf.into())
+}
@@ -0,0 +1,137 @@
+use std::collections::BTreeSet;
+
+use bstr::{BStr, BString, ByteSlice};
+
+/// The paths that a sparse checkout leaves out of the worktree.
+///
+/// Paths are excluded if they are tracked with the `skip-worktree` bit, which works for all sparse checkouts,
+/// or if they are outside the cone of a sparse checkout in cone mode, which also covers untracked files.
+#[derive(Debug, Clone, Default)]
+pub struct SparseCheckout {
+    /// Tracked files that aren't checked out.
+    skip_worktree: BTreeSet<BString>,
+    /// Directories of a sparse index that aren't checked out, without trailing slash.
+    skip_worktree_dirs: Vec<BString>,
+    /// The cone if the sparse checkout is in cone mode.
+    cone: Option<Cone>,
+}
+
+/// The directories that are part of a sparse checkout in cone mode.
+#[derive(Debug, Clone, Default)]
+struct Cone {
+    /// Directories whose whole content is checked out.
+    recursive: Vec<BString>,
+    /// Directories whose files, but not their subdirectories, are checked out.
+    parents: BTreeSet<BString>,
+}
+
+impl SparseCheckout {
+    /// Read the sparse checkout configuration of `repo`, or return `None` if `core.sparseCheckout` isn't enabled.
+    pub fn from_repo(repo: &gix::Repository) -> anyhow::Result<Option<Self>> {
+        let config = repo.config_snapshot();
+        if !config.boolean("core.sparseCheckout").unwrap_or_default() {
+            return Ok(None);
+        }
+
+        let mut sparse = SparseCheckout::default();
+        let index = repo.index_or_empty()?;
+        for entry in index.entries() {
+            if !entry
+                .flags
+                .contains(gix::index::entry::Flags::SKIP_WORKTREE)
+            {
+                continue;
+            }
+            let path = entry.path(&index);
+            if entry.mode == gix::index::entry::Mode::DIR {
+                sparse
+                    .skip_worktree_dirs
+                    .push(path.trim_end_with(|c| c == '/').into());
+            } else {
+                sparse.skip_worktree.insert(path.to_owned());
+            }
+        }
+
+        // Git defaults to cone mode since v2.37.
+        if config.boolean("core.sparseCheckoutCone").unwrap_or(true) {
+            let patterns_path = repo.git_dir().join("info").join("sparse-checkout");
+            match std::fs::read(&patterns_path) {
+                Ok(patterns) => sparse.cone = Some(Cone::from_patterns(patterns.as_bstr())),
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+                Err(err) => return Err(err.into()),
+            }
+        }
+        Ok(Some(sparse))
+    }
+
+    /// Return `true` if the worktree-relative `rela_path` isn't part of the sparse checkout,
+    /// so it must neither be read from nor written to the worktree.
+    pub fn is_excluded(&self, rela_path: &BStr) -> bool {
+        self.skip_worktree.contains(rela_path)
+            || self
+                .skip_worktree_dirs
+                .iter()
+                .any(|dir| is_in_dir(rela_path, dir.as_bstr()))
+            || self
+                .cone
+                .as_ref()
+                .is_some_and(|cone| !cone.contains(rela_path))
+    }
+}
+
+impl Cone {
+    /// Parse the `patterns` written by `git sparse-checkout set` in cone mode, like
+    ///
+    /// ```text
+    /// /*
+    /// !/*/
+    /// /c1/
+    /// !/c1/*/
+    /// /c1/c2/
+    /// ```
+    fn from_patterns(patterns: &BStr) -> Self {
+        let mut dirs = Vec::new();
+        let mut parents = BTreeSet::new();
+        for line in patterns.lines().map(|line| line.trim()) {
+            if line.is_empty() || line.starts_with(b"#") {
+                continue;
+            }
+            if let Some(negated) = line.strip_prefix(b"!") {
+                if let Some(dir) = negated
+                    .strip_prefix(b"/")
+                    .and_then(|dir| dir.strip_suffix(b"/*/"))
+                {
+                    parents.insert(BString::from(dir));
+                }
+            } else if let Some(dir) = line
+                .strip_prefix(b"/")
+                .and_then(|dir| dir.strip_suffix(b"/"))
+            {
+                dirs.push(BString::from(dir));
+            }
+        }
+        let recursive = dirs
+            .into_iter()
+            .filter(|dir| !parents.contains(dir))
+            .collect();
+        Cone { recursive, parents }
+    }
+
+    fn contains(&self, rela_path: &BStr) -> bool {
+        // Files at the root are always checked out.
+        let Some((dir, _file_name)) = rela_path.rsplit_once_str(b"/") else {
+            return true;
+        };
+        self.parents.contains(dir.as_bstr())
+            || self
+                .recursive
+                .iter()
+                .any(|recursive| is_in_dir(rela_path, recursive.as_bstr()))
+    }
+}
+
+fn is_in_dir(rela_path: &BStr, dir: &BStr) -> bool {
+    rela_path
+        .strip_prefix(dir.as_bytes())
+        .is_some_and(|rest| rest.starts_with(b"/"))
+}
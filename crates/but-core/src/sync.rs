@@ -5,7 +5,14 @@ use std::{
 };
 
 use anyhow::{Context as _, bail};
-use parking_lot::{ArcRwLockReadGuard, ArcRwLockWriteGuard, RawRwLock};
+use parking_lot::{
+    ArcRwLockReadGuard, ArcRwLockUpgradableReadGuard, ArcRwLockWriteGuard, RawRwLock,
+};
+
+pub mod events;
+
+pub mod queue;
+
 /// The scope of a lock. It can be either on the entire project or on specific operations.
 #[derive(Debug, Clone, Copy, Default)]
 pub enum LockScope {
@@ -95,6 +102,22 @@ pub fn shared_worktree_access(git_dir: impl Into<PathBuf>) -> WorkspaceReadGuard
     WorkspaceReadGuard(Some(map.entry(git_dir).or_default().read_arc()))
 }
 
+/// Return a guard for shared (read) worktree access for the project at `git_dir` which can be
+/// [upgraded](WorkspaceUpgradableGuard::upgrade()) to exclusive access, blocking while waiting for writers to disappear.
+///
+/// It can be held alongside [shared guards](shared_worktree_access()), but there can only be one upgradable guard at a time.
+/// Use it for operations that only need to write in some cases, so they don't block readers otherwise.
+pub fn upgradable_worktree_access(git_dir: impl Into<PathBuf>) -> WorkspaceUpgradableGuard {
+    let mut map = WORKTREE_LOCKS.lock();
+    let git_dir = git_dir.into();
+    WorkspaceUpgradableGuard {
+        inner: Some(UpgradableLock::Read(
+            map.entry(git_dir).or_default().upgradable_read_arc(),
+        )),
+        perm: WorktreeWritePermission(()),
+    }
+}
+
 /// A utility that drops an exclusive lock on drop.
 pub struct WorkspaceWriteGuard {
     inner: Option<parking_lot::ArcRwLockWriteGuard<RawRwLock, ()>>,
@@ -135,6 +158,49 @@ impl WorkspaceWriteGuard {
     }
 }
 
+/// A utility that holds a shared lock which can be upgraded to an exclusive one, and drops it on drop.
+pub struct WorkspaceUpgradableGuard {
+    inner: Option<UpgradableLock>,
+    perm: WorktreeWritePermission,
+}
+
+enum UpgradableLock {
+    Read(ArcRwLockUpgradableReadGuard<RawRwLock, ()>),
+    Write(ArcRwLockWriteGuard<RawRwLock, ()>),
+}
+
+impl WorkspaceUpgradableGuard {
+    /// Signal that a read-permission is available - useful as API-marker to assure these
+    /// can only be called when the respective protection/permission is present.
+    pub fn read_permission(&self) -> &WorktreeReadPermission {
+        self.perm.read_permission()
+    }
+
+    /// Block until all readers are gone to obtain exclusive access, which is held until this guard is dropped,
+    /// and return the write-permission that signals it.
+    pub fn upgrade(&mut self) -> &mut WorktreeWritePermission {
+        let lock = match self.inner.take().expect("always set unless dropped") {
+            UpgradableLock::Read(lock) => ArcRwLockUpgradableReadGuard::upgrade(lock),
+            UpgradableLock::Write(lock) => lock,
+        };
+        self.inner = Some(UpgradableLock::Write(lock));
+        &mut self.perm
+    }
+}
+
+impl Drop for WorkspaceUpgradableGuard {
+    fn drop(&mut self) {
+        match self
+            .inner
+            .take()
+            .expect("it's always set, and only taken once when dropping")
+        {
+            UpgradableLock::Read(lock) => ArcRwLockUpgradableReadGuard::unlock_fair(lock),
+            UpgradableLock::Write(lock) => ArcRwLockWriteGuard::unlock_fair(lock),
+        }
+    }
+}
+
 /// A utility that drops a shared lock on drop.
 pub struct WorkspaceReadGuard(Option<parking_lot::ArcRwLockReadGuard<RawRwLock, ()>>);
 
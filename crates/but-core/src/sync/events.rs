@@ -0,0 +1,82 @@
+//! An in-process bus on which mutating operations publish what they changed in a workspace,
+//! so observers like the file watcher or the UI can invalidate their data without polling.
+//!
+//! Events are published per project, identified by the directory in which GitButler stores its application data.
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    sync::mpsc,
+};
+
+use serde::Serialize;
+
+use crate::ref_metadata::StackId;
+
+/// Something that changed in the workspace.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(
+    tag = "kind",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum WorkspaceEvent {
+    /// The stack with `stack_id` was created, changed or removed.
+    StackUpdated {
+        /// The id of the stack.
+        stack_id: StackId,
+    },
+    /// Uncommitted hunks were assigned to different stacks.
+    AssignmentsChanged,
+    /// The target branch the workspace is based on was changed or moved to `target_sha`.
+    BaseMoved {
+        /// The commit the target branch now points to.
+        #[serde(with = "but_serde::object_id")]
+        target_sha: gix::ObjectId,
+    },
+    /// The reference of the branch `branch_name` of the stack with `stack_id` was deleted outside of GitButler,
+    /// like with `git branch -D`, detaching the stack from it.
+    ///
+    /// The branch stays marked as detached in the workspace metadata until its reference exists again,
+    /// which can be recreated from the metadata by repairing the workspace.
+    StackDetached {
+        /// The id of the stack.
+        stack_id: StackId,
+        /// The short name of the deleted branch, like `feature`.
+        branch_name: String,
+    },
+    /// An oplog snapshot was created.
+    SnapshotCreated {
+        /// The id of the snapshot commit.
+        #[serde(with = "but_serde::object_id")]
+        snapshot_id: gix::ObjectId,
+    },
+}
+
+static SUBSCRIBERS: parking_lot::Mutex<BTreeMap<PathBuf, Vec<mpsc::Sender<WorkspaceEvent>>>> =
+    parking_lot::Mutex::new(BTreeMap::new());
+
+/// Send `event` to all current subscribers of the project whose application data is stored in `project_data_dir`.
+///
+/// Subscribers that were dropped are forgotten, and publishing without subscribers does nothing.
+pub fn publish(project_data_dir: &Path, event: WorkspaceEvent) {
+    let mut map = SUBSCRIBERS.lock();
+    let Some(senders) = map.get_mut(project_data_dir) else {
+        return;
+    };
+    senders.retain(|tx| tx.send(event.clone()).is_ok());
+    if senders.is_empty() {
+        map.remove(project_data_dir);
+    }
+}
+
+/// Receive all events published for the project whose application data is stored in `project_data_dir`
+/// from now on, until the returned receiver is dropped.
+pub fn subscribe(project_data_dir: impl Into<PathBuf>) -> mpsc::Receiver<WorkspaceEvent> {
+    let (tx, rx) = mpsc::channel();
+    SUBSCRIBERS
+        .lock()
+        .entry(project_data_dir.into())
+        .or_default()
+        .push(tx);
+    rx
+}
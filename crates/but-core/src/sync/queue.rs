@@ -0,0 +1,429 @@
+//! An in-process queue for mutating operations, so they run one after another in order of priority,
+//! and can be listed and cancelled while they wait.
+//!
+//! Running operations report their [progress](ProgressHandle) and stop at the next safe point once they are cancelled.
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+    },
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::bail;
+use serde::{Deserialize, Serialize};
+
+use crate::sync::{
+    WorkspaceWriteGuard, WorktreeReadPermission, WorktreeWritePermission, exclusive_worktree_access,
+};
+
+/// The identifier of an operation in the queue, unique for the lifetime of the process.
+pub type OperationId = u64;
+
+/// What kind of work an operation does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OperationCategory {
+    /// Create or amend commits.
+    Commit,
+    /// Rewrite existing commits, like reordering, moving or squashing them.
+    Rebase,
+    /// Integrate upstream changes into the workspace or a branch.
+    Integrate,
+    /// Fetch from remotes.
+    Fetch,
+    /// Push branches to a remote.
+    Push,
+    /// Anything else that changes the repository.
+    Other,
+}
+
+/// How urgently an operation should run, compared to the other queued operations.
+/// Operations of the same priority run in the order in which they were enqueued.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Priority {
+    /// Work that may wait, like background maintenance.
+    Low,
+    /// The default for user-initiated operations.
+    #[default]
+    Normal,
+    /// Work that should run before everything else that is queued.
+    High,
+}
+
+/// The state of an operation in the queue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OperationState {
+    /// The operation waits for its turn.
+    Queued,
+    /// The operation is running.
+    Running,
+}
+
+/// The progress reported by a running operation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Progress {
+    /// The amount of completed steps.
+    pub completed: usize,
+    /// The amount of steps in total, if known.
+    pub total: Option<usize>,
+    /// A description of the current step.
+    pub message: Option<String>,
+    /// `completed` in percent of `total`, if it's known.
+    pub percentage: Option<u8>,
+}
+
+/// Information about an operation that is queued or running.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OperationInfo {
+    /// The id of the operation, for use with [`cancel()`].
+    pub id: OperationId,
+    /// What the operation does.
+    pub category: OperationCategory,
+    /// How urgent the operation is.
+    pub priority: Priority,
+    /// Whether the operation is waiting or running.
+    pub state: OperationState,
+    /// The last progress reported by the operation, if it's running.
+    pub progress: Option<Progress>,
+    /// The time at which the operation was enqueued, in milliseconds since the UNIX epoch.
+    pub enqueued_at_ms: u64,
+}
+
+#[derive(Default)]
+struct State {
+    /// All operations, running or queued, in order of insertion.
+    operations: Vec<Entry>,
+}
+
+struct Entry {
+    info: OperationInfo,
+    /// Set if the operation was cancelled while it was queued, so it won't ever run.
+    cancelled: bool,
+    /// Cancelled if the operation was cancelled while it was running.
+    token: CancellationToken,
+}
+
+impl State {
+    fn is_running(&self) -> bool {
+        self.operations
+            .iter()
+            .any(|op| op.info.state == OperationState::Running)
+    }
+
+    /// Return the id of the operation to run next, which is the oldest one with the highest priority.
+    fn next(&self) -> Option<OperationId> {
+        self.operations
+            .iter()
+            .filter(|op| op.info.state == OperationState::Queued && !op.cancelled)
+            // `max_by_key` returns the last maximum, but we want the oldest.
+            .rev()
+            .max_by_key(|op| op.info.priority)
+            .map(|op| op.info.id)
+    }
+
+    fn remove(&mut self, id: OperationId) {
+        self.operations.retain(|op| op.info.id != id);
+    }
+
+    fn get_mut(&mut self, id: OperationId) -> Option<&mut Entry> {
+        self.operations.iter_mut().find(|op| op.info.id == id)
+    }
+}
+
+/// A function to call with an operation whenever it reports progress.
+type ProgressListener = Arc<dyn Fn(&OperationInfo) + Send + Sync>;
+
+#[derive(Default)]
+struct Queue {
+    state: parking_lot::Mutex<State>,
+    changed: parking_lot::Condvar,
+    listener: parking_lot::Mutex<Option<ProgressListener>>,
+}
+
+static QUEUES: parking_lot::Mutex<BTreeMap<PathBuf, Arc<Queue>>> =
+    parking_lot::Mutex::new(BTreeMap::new());
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+fn queue_at(git_dir: impl Into<PathBuf>) -> Arc<Queue> {
+    QUEUES.lock().entry(git_dir.into()).or_default().clone()
+}
+
+/// Enqueue an operation of `category` with `priority` for the project at `git_dir`.
+/// Call [`QueuedOperation::wait()`] to block until it's its turn to run.
+pub fn enqueue(
+    git_dir: impl Into<PathBuf>,
+    category: OperationCategory,
+    priority: Priority,
+) -> QueuedOperation {
+    let queue = queue_at(git_dir);
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    let enqueued_at_ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default();
+    queue.state.lock().operations.push(Entry {
+        info: OperationInfo {
+            id,
+            category,
+            priority,
+            state: OperationState::Queued,
+            progress: None,
+            enqueued_at_ms,
+        },
+        cancelled: false,
+        token: CancellationToken::default(),
+    });
+    QueuedOperation {
+        queue: Some(queue),
+        id,
+    }
+}
+
+/// List all operations for the project at `git_dir`, the running one first, followed by the queued ones
+/// in the order in which they will run.
+pub fn list(git_dir: impl AsRef<Path>) -> Vec<OperationInfo> {
+    let Some(queue) = QUEUES.lock().get(git_dir.as_ref()).cloned() else {
+        return Vec::new();
+    };
+    let state = queue.state.lock();
+    let mut out: Vec<_> = state
+        .operations
+        .iter()
+        .filter(|op| !op.cancelled)
+        .map(|op| op.info.clone())
+        .collect();
+    out.sort_by_key(|op| {
+        (
+            op.state != OperationState::Running,
+            std::cmp::Reverse(op.priority),
+            op.id,
+        )
+    });
+    out
+}
+
+/// Cancel the operation with `id` for the project at `git_dir`.
+/// Return `false` if there is no such operation, e.g. because it finished already.
+///
+/// Queued operations fail once they wake up, while running operations fail at the next point where
+/// they can stop safely. Operations that don't check their [`CancellationToken`] run to completion.
+pub fn cancel(git_dir: impl AsRef<Path>, id: OperationId) -> anyhow::Result<bool> {
+    let Some(queue) = QUEUES.lock().get(git_dir.as_ref()).cloned() else {
+        return Ok(false);
+    };
+    let mut state = queue.state.lock();
+    let Some(entry) = state.get_mut(id) else {
+        return Ok(false);
+    };
+    if entry.info.state == OperationState::Running {
+        entry.token.cancel();
+        return Ok(true);
+    }
+    entry.cancelled = true;
+    drop(state);
+    queue.changed.notify_all();
+    Ok(true)
+}
+
+/// Call `listener` with the operation whenever one of the operations of the project at `git_dir` reports progress,
+/// replacing the previous listener.
+pub fn set_progress_listener(
+    git_dir: impl Into<PathBuf>,
+    listener: impl Fn(&OperationInfo) + Send + Sync + 'static,
+) {
+    *queue_at(git_dir).listener.lock() = Some(Arc::new(listener));
+}
+
+/// Enqueue an operation like [`enqueue()`], wait for it to run, and obtain exclusive worktree access for it.
+pub fn queued_exclusive_worktree_access(
+    git_dir: impl Into<PathBuf>,
+    category: OperationCategory,
+    priority: Priority,
+) -> anyhow::Result<QueuedWorkspaceWriteGuard> {
+    let git_dir = git_dir.into();
+    let operation = enqueue(git_dir.clone(), category, priority).wait()?;
+    Ok(QueuedWorkspaceWriteGuard {
+        guard: exclusive_worktree_access(git_dir),
+        operation,
+    })
+}
+
+/// An operation that waits for its turn. It's removed from the queue when dropped.
+pub struct QueuedOperation {
+    queue: Option<Arc<Queue>>,
+    id: OperationId,
+}
+
+impl QueuedOperation {
+    /// The id of the operation.
+    pub fn id(&self) -> OperationId {
+        self.id
+    }
+
+    /// Block until no other operation runs and this one is next, and return a handle to the running operation.
+    /// Fail if the operation was [cancelled](cancel()) while waiting.
+    pub fn wait(mut self) -> anyhow::Result<RunningOperation> {
+        let queue = self.queue.take().expect("only taken here");
+        let mut state = queue.state.lock();
+        loop {
+            let cancelled = state.get_mut(self.id).is_none_or(|op| op.cancelled);
+            if cancelled {
+                state.remove(self.id);
+                drop(state);
+                queue.changed.notify_all();
+                bail!("Operation {} was cancelled before it started", self.id);
+            }
+            if !state.is_running() && state.next() == Some(self.id) {
+                let entry = state.get_mut(self.id).expect("checked above");
+                entry.info.state = OperationState::Running;
+                let token = entry.token.clone();
+                drop(state);
+                return Ok(RunningOperation {
+                    progress: ProgressHandle {
+                        queue,
+                        id: self.id,
+                        token,
+                    },
+                });
+            }
+            queue.changed.wait(&mut state);
+        }
+    }
+}
+
+impl Drop for QueuedOperation {
+    fn drop(&mut self) {
+        if let Some(queue) = self.queue.take() {
+            queue.state.lock().remove(self.id);
+            queue.changed.notify_all();
+        }
+    }
+}
+
+/// An operation that is running. The next operation can run once it's dropped.
+pub struct RunningOperation {
+    progress: ProgressHandle,
+}
+
+impl RunningOperation {
+    /// The id of the operation.
+    pub fn id(&self) -> OperationId {
+        self.progress.id
+    }
+
+    /// Report that `completed` out of `total` steps are done, with `message` describing the current step.
+    pub fn set_progress(&self, completed: usize, total: Option<usize>, message: Option<String>) {
+        self.progress.set_progress(completed, total, message);
+    }
+
+    /// Return a handle to report progress with and to learn if the operation was cancelled,
+    /// for passing it to where the work is done.
+    pub fn progress(&self) -> ProgressHandle {
+        self.progress.clone()
+    }
+}
+
+impl Drop for RunningOperation {
+    fn drop(&mut self) {
+        let queue = &self.progress.queue;
+        queue.state.lock().remove(self.progress.id);
+        queue.changed.notify_all();
+    }
+}
+
+/// A flag to tell a running operation that it should stop.
+#[derive(Debug, Default, Clone)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    /// Ask the operation holding this token to stop.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Return `true` if the operation should stop.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// A handle to report the progress of a running operation with, and to learn if it was cancelled.
+///
+/// It's cheap to clone and can outlive the operation, in which case reporting progress does nothing.
+#[derive(Clone)]
+pub struct ProgressHandle {
+    queue: Arc<Queue>,
+    id: OperationId,
+    token: CancellationToken,
+}
+
+impl ProgressHandle {
+    /// Report that `completed` out of `total` steps are done, with `message` describing the current phase.
+    pub fn set_progress(&self, completed: usize, total: Option<usize>, message: Option<String>) {
+        let info = {
+            let mut state = self.queue.state.lock();
+            let Some(entry) = state.get_mut(self.id) else {
+                return;
+            };
+            entry.info.progress = Some(Progress {
+                completed,
+                total,
+                message,
+                percentage: total
+                    .filter(|total| *total > 0)
+                    .map(|total| (completed.min(total) * 100 / total) as u8),
+            });
+            entry.info.clone()
+        };
+        let listener = self.queue.listener.lock().clone();
+        if let Some(listener) = listener {
+            listener(&info);
+        }
+    }
+
+    /// The token through which the operation is cancelled.
+    pub fn cancellation_token(&self) -> &CancellationToken {
+        &self.token
+    }
+
+    /// Fail if the operation was cancelled. Call this where the operation can stop without leaving anything behind.
+    pub fn check_cancelled(&self) -> anyhow::Result<()> {
+        if self.token.is_cancelled() {
+            bail!("Operation {} was cancelled", self.id);
+        }
+        Ok(())
+    }
+}
+
+/// A [`WorkspaceWriteGuard`] which is held by a running operation, see [`queued_exclusive_worktree_access()`].
+pub struct QueuedWorkspaceWriteGuard {
+    // NOTE: dropped before `operation`, so the next operation doesn't have to wait for the worktree lock.
+    guard: WorkspaceWriteGuard,
+    operation: RunningOperation,
+}
+
+impl QueuedWorkspaceWriteGuard {
+    /// Signal that a write-permission is available - useful as API-marker to assure these
+    /// can only be called when the respective protection/permission is present.
+    pub fn write_permission(&mut self) -> &mut WorktreeWritePermission {
+        self.guard.write_permission()
+    }
+
+    /// Signal that a read-permission is available - useful as API-marker to assure these
+    /// can only be called when the respective protection/permission is present.
+    pub fn read_permission(&self) -> &WorktreeReadPermission {
+        self.guard.read_permission()
+    }
+
+    /// The running operation, to report progress with.
+    pub fn operation(&self) -> &RunningOperation {
+        &self.operation
+    }
+}
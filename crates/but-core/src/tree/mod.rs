@@ -45,6 +45,10 @@ pub mod create_tree {
         /// The DiffSpec points to an actual change, or a subset of that change using a file path and optionally hunks into that file.
         /// However, at least one hunk was not fully contained.
         MissingDiffSpecAssociation,
+        /// The path isn't part of the sparse checkout, so its worktree version can't be read.
+        OutsideSparseCheckout,
+        /// The path has the `skip-worktree` or `assume-unchanged` bit set in the index, so its worktree version must not be committed.
+        MarkedUnchanged,
     }
 }
 use create_tree::RejectionReason;
@@ -170,6 +174,27 @@ fn into_err_spec(input: &mut PossibleChange, reason: RejectionReason) {
     };
 }
 
+/// Like [`crate::TreeChange::unified_patch_with_filter()`], but for files that are too large to be diffed as a whole,
+/// diff their beginning so the hunks that were selected from it can still be committed.
+fn unified_patch_or_prefix(
+    repo: &gix::Repository,
+    change: &crate::TreeChange,
+    context_lines: u32,
+    diff_filter: &mut gix::diff::blob::Platform,
+) -> anyhow::Result<Option<UnifiedPatch>> {
+    Ok(
+        match change.unified_patch_with_filter(repo, context_lines, diff_filter)? {
+            Some(UnifiedPatch::TooLarge { .. }) => crate::diff::prefix_patch(
+                repo,
+                change,
+                context_lines,
+                repo.big_file_threshold()?.try_into()?,
+            )?,
+            patch => patch,
+        },
+    )
+}
+
 /// A utility type to keep track of `Ok` specs to use, or `Err` specs that have been rejected.
 pub type PossibleChange = Result<DiffSpec, (RejectionReason, DiffSpec)>;
 
@@ -178,6 +203,9 @@ pub type PossibleChange = Result<DiffSpec, (RejectionReason, DiffSpec)>;
 ///
 /// Note that the returned `new_tree` may be the same as `actual_base_tree`, if no change was successfully applied
 /// as recorded in `changes`.
+///
+/// Like `git add`, worktree content passes through the clean filters and line-ending conversions configured
+/// in `.gitattributes` and the Git configuration before it's written.
 pub fn apply_worktree_changes<'repo>(
     actual_base_tree: gix::ObjectId,
     repo: &'repo gix::Repository,
@@ -195,6 +223,9 @@ pub fn apply_worktree_changes<'repo>(
         .then(|| crate::diff::worktree_changes(repo).map(|wtc| wtc.changes))
         .transpose()?;
     let mut current_worktree = Vec::new();
+    let sparse = crate::sparse::SparseCheckout::from_repo(repo)?;
+    let unchanged = crate::unchanged::MarkedUnchanged::from_repo(repo)?;
+    let mut lfs = crate::lfs::LfsAttributes::new(repo, &index)?;
 
     let work_dir = repo.workdir().expect("non-bare repo");
     'each_change: for possible_change in changes.iter_mut() {
@@ -202,6 +233,26 @@ pub fn apply_worktree_changes<'repo>(
             Ok(change) => change,
             Err(_) => continue,
         };
+        // Files outside a sparse checkout are missing in the worktree, which must not be mistaken for a deletion.
+        if sparse.as_ref().is_some_and(|sparse| {
+            sparse.is_excluded(change_request.path.as_bstr())
+                || change_request
+                    .previous_path
+                    .as_ref()
+                    .is_some_and(|path| sparse.is_excluded(path.as_bstr()))
+        }) {
+            into_err_spec(possible_change, RejectionReason::OutsideSparseCheckout);
+            continue;
+        }
+        if unchanged.contains(change_request.path.as_bstr())
+            || change_request
+                .previous_path
+                .as_ref()
+                .is_some_and(|path| unchanged.contains(path.as_bstr()))
+        {
+            into_err_spec(possible_change, RejectionReason::MarkedUnchanged);
+            continue;
+        }
         let path = work_dir.join(gix::path::from_bstr(change_request.path.as_bstr()));
         let md = match gix::index::fs::Metadata::from_path_no_follow(&path) {
             Ok(md) => md,
@@ -217,8 +268,31 @@ pub fn apply_worktree_changes<'repo>(
         }
         if change_request.hunk_headers.is_empty() {
             let rela_path = change_request.path.as_bstr();
+            if md.is_dir() {
+                // A submodule is committed as the commit its `HEAD` points to.
+                match gix::open_opts(&path, gix::open::Options::isolated())
+                    .ok()
+                    .and_then(|submodule| submodule.head_id().ok().map(|id| id.detach()))
+                {
+                    Some(id) => {
+                        base_tree_editor.upsert(rela_path, EntryKind::Commit, id)?;
+                    }
+                    None => {
+                        into_err_spec(possible_change, RejectionReason::UnsupportedDirectoryEntry)
+                    }
+                }
+                continue;
+            }
             match pipeline.worktree_file_to_object(rela_path, &index)? {
                 Some((id, kind, _fs_metadata)) => {
+                    // Without Git LFS configured its clean filter won't run, but the commit must still store the pointer.
+                    let id = if matches!(kind, EntryKind::Blob | EntryKind::BlobExecutable)
+                        && lfs.is_lfs(rela_path)?
+                    {
+                        crate::lfs::ensure_pointer_blob(repo, id)?
+                    } else {
+                        id
+                    };
                     base_tree_editor.upsert(rela_path, kind, id)?;
                 }
                 None => into_err_spec(
@@ -247,7 +321,7 @@ pub fn apply_worktree_changes<'repo>(
             );
             // TODO(perf): avoid computing the unified diff here, we only need hunks with, usually with zero context.
             let Some(UnifiedPatch::Patch { hunks, .. }) =
-                worktree_change.unified_patch_with_filter(repo, context_lines, &mut diff_filter)?
+                unified_patch_or_prefix(repo, worktree_change, context_lines, &mut diff_filter)?
             else {
                 into_err_spec(possible_change, RejectionReason::FileToLargeOrBinary);
                 continue;
@@ -262,7 +336,7 @@ pub fn apply_worktree_changes<'repo>(
                 let Some(UnifiedPatch::Patch {
                     hunks: hunks_no_context,
                     ..
-                }) = worktree_change.unified_patch_with_filter(repo, 0, &mut diff_filter)?
+                }) = unified_patch_or_prefix(repo, worktree_change, 0, &mut diff_filter)?
                 else {
                     into_err_spec(possible_change, RejectionReason::FileToLargeOrBinary);
                     continue;
@@ -281,12 +355,10 @@ pub fn apply_worktree_changes<'repo>(
                 into_err_spec(possible_change, RejectionReason::MissingDiffSpecAssociation);
                 continue 'each_change;
             }
-            let (previous_state, previous_path) = worktree_change
+            let previous_state = worktree_change
                 .status
                 .previous_state_and_path()
-                .map(|(state, maybe_path)| (Some(state), maybe_path))
-                .unwrap_or_default();
-            let base_rela_path = previous_path.unwrap_or(change_request.path.as_bstr());
+                .map(|(state, _maybe_path)| state);
             let current_entry_kind = if md.is_symlink() {
                 EntryKind::Link
             } else if md.is_file() {
@@ -317,10 +389,12 @@ pub fn apply_worktree_changes<'repo>(
                 }
             };
 
+            // Like `git add`, the attributes of the current path drive the conversion,
+            // which also keeps it in sync with the conversion used to produce the hunks.
             worktree_file_to_git_in_buf(
                 &mut current_worktree,
                 &md,
-                base_rela_path,
+                change_request.path.as_bstr(),
                 &path,
                 &mut pipeline,
                 &index,
@@ -0,0 +1,78 @@
+use std::collections::BTreeSet;
+
+use anyhow::{Context as _, bail};
+use bstr::{BStr, BString};
+use gix::index::entry::{Flags, Stage};
+
+/// Tracked files that the index claims are unchanged, so their changes in the worktree are hidden.
+///
+/// These are files with the `skip-worktree` bit, as set by `git update-index --skip-worktree`, or
+/// the `assume-unchanged` bit, as set by `git update-index --assume-unchanged`.
+/// In a sparse checkout, `skip-worktree` is used to leave files out of the worktree, which
+/// is handled by [`SparseCheckout`](crate::sparse::SparseCheckout) instead.
+#[derive(Debug, Clone, Default)]
+pub struct MarkedUnchanged {
+    paths: BTreeSet<BString>,
+}
+
+impl MarkedUnchanged {
+    /// Collect all files of the index of `repo` that have the `skip-worktree` or `assume-unchanged` bit set.
+    pub fn from_repo(repo: &gix::Repository) -> anyhow::Result<Self> {
+        let index = repo.index_or_empty()?;
+        let paths = index
+            .entries()
+            .iter()
+            .filter(|entry| {
+                entry.mode != gix::index::entry::Mode::DIR
+                    && entry
+                        .flags
+                        .intersects(Flags::SKIP_WORKTREE | Flags::ASSUME_VALID)
+            })
+            .map(|entry| entry.path(&index).to_owned())
+            .collect();
+        Ok(MarkedUnchanged { paths })
+    }
+
+    /// Return `true` if no file is marked as unchanged.
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+
+    /// Return `true` if changes to the worktree-relative `rela_path` must be hidden.
+    pub fn contains(&self, rela_path: &BStr) -> bool {
+        self.paths.contains(rela_path)
+    }
+}
+
+/// Set the `skip-worktree` bit of the tracked file at the worktree-relative `rela_path` if `skip` is `true`,
+/// or clear it otherwise, like `git update-index --[no-]skip-worktree` would.
+///
+/// Changes to files with the bit set are neither listed nor committed until it's cleared again.
+/// This fails in sparse checkouts, where the bit decides which files are checked out.
+pub fn set_skip_worktree(
+    repo: &gix::Repository,
+    rela_path: &BStr,
+    skip: bool,
+) -> anyhow::Result<()> {
+    if repo
+        .config_snapshot()
+        .boolean("core.sparseCheckout")
+        .unwrap_or_default()
+    {
+        bail!(
+            "Can't change the skip-worktree bit of '{rela_path}' in a sparse checkout, use `git sparse-checkout` instead"
+        );
+    }
+    let mut index = repo.open_index()?;
+    let entry_idx = index
+        .entry_index_by_path_and_stage(rela_path, Stage::Unconflicted)
+        .with_context(|| format!("'{rela_path}' isn't a tracked file without conflicts"))?;
+    #[expect(clippy::indexing_slicing)]
+    let entry = &mut index.entries_mut()[entry_idx];
+    if entry.flags.contains(Flags::SKIP_WORKTREE) == skip {
+        return Ok(());
+    }
+    entry.flags.set(Flags::SKIP_WORKTREE, skip);
+    index.write(Default::default())?;
+    Ok(())
+}
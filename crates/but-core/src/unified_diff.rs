@@ -6,7 +6,8 @@ use gix::diff::blob::{
 };
 use serde::{Deserialize, Serialize};
 
-use super::{ChangeState, UnifiedPatch};
+use super::{ChangeState, UnifiedPatch, lfs::LfsPointer};
+use crate::diff::ui::{DisplayEncodings, decode_for_display};
 
 /// A hunk as used in a [UnifiedPatch], which also contains all added and removed lines.
 #[derive(Clone, Serialize, Deserialize)]
@@ -38,6 +39,160 @@ pub struct DiffHunk {
     /// replacing invalid codepoints with markers.
     #[serde(serialize_with = "but_serde::bstring_lossy::serialize")]
     pub diff: BString,
+    /// The name of the encoding that `diff` was decoded from for display, like `Shift_JIS`, or `None` if it's shown as is.
+    /// See [`DisplayEncodings`](crate::diff::ui::DisplayEncodings) for how it's chosen.
+    ///
+    /// Line numbers still refer to the original content, which is what commits are created from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<String>,
+    /// The parts of each line in `diff` that changed within the line, for highlighting them,
+    /// or `None` if they weren't [computed](UnifiedPatch::with_word_diffs()).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub word_diff: Option<Vec<Vec<WordSpan>>>,
+}
+
+/// A part of a line of a [`DiffHunk`] that changed within the line.
+///
+/// The offsets are in UTF-16 code units of the line without its `+` or `-` prefix, as used by JavaScript strings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WordSpan {
+    /// The offset at which the changed part starts.
+    pub start: u32,
+    /// The offset past the end of the changed part.
+    pub end: u32,
+}
+
+impl DiffHunk {
+    /// Compute the changed parts of each line in `diff` after its header, as would be stored in [`Self::word_diff`].
+    ///
+    /// The words of each block of removed lines are compared with the words of the added lines that follow it, and the
+    /// words that don't have a counterpart are the changed parts. Context lines, and removed or added lines without
+    /// a counterpart, have no changed parts as the whole line changed.
+    pub fn compute_word_diff(&self) -> Vec<Vec<WordSpan>> {
+        let lines: Vec<_> = self
+            .diff
+            .lines()
+            .skip_while(|line| line.starts_with(b"@@"))
+            .collect();
+        let mut spans = vec![Vec::new(); lines.len()];
+        let mut idx = 0;
+        while idx < lines.len() {
+            let is =
+                |idx: usize, prefix: u8| lines.get(idx).is_some_and(|l| l.first() == Some(&prefix));
+            let removed_start = idx;
+            while is(idx, b'-') {
+                idx += 1;
+            }
+            let added_start = idx;
+            while removed_start != added_start && is(idx, b'+') {
+                idx += 1;
+            }
+            if removed_start == added_start || added_start == idx {
+                idx = idx.max(removed_start + 1);
+                continue;
+            }
+            let removed = Words::from_lines(&lines[removed_start..added_start], removed_start);
+            let added = Words::from_lines(&lines[added_start..idx], added_start);
+            let mut interner =
+                gix::diff::blob::intern::Interner::new(removed.words.len() + added.words.len());
+            let removed_tokens: Vec<_> = removed
+                .words
+                .iter()
+                .map(|w| interner.intern(w.text.as_str()))
+                .collect();
+            let added_tokens: Vec<_> = added
+                .words
+                .iter()
+                .map(|w| interner.intern(w.text.as_str()))
+                .collect();
+            let mut changed = Vec::new();
+            gix::diff::blob::diff_with_tokens(
+                gix::diff::blob::Algorithm::Histogram,
+                &removed_tokens,
+                &added_tokens,
+                interner.num_tokens(),
+                |before: std::ops::Range<u32>, after: std::ops::Range<u32>| {
+                    changed.push((before, after))
+                },
+            );
+            for (before, after) in changed {
+                removed.mark(before, &mut spans);
+                added.mark(after, &mut spans);
+            }
+        }
+        spans
+    }
+}
+
+/// The words of a block of lines in a [`DiffHunk`].
+struct Words {
+    words: Vec<Word>,
+}
+
+struct Word {
+    /// The index of the line in the hunk the word is on.
+    line: usize,
+    span: WordSpan,
+    text: String,
+}
+
+impl Words {
+    /// Split `lines`, the first of which is the line with index `first_line` in the hunk, into words,
+    /// which are runs of alphanumeric characters, runs of whitespace, or any other single character.
+    fn from_lines(lines: &[&[u8]], first_line: usize) -> Self {
+        #[derive(PartialEq)]
+        enum Class {
+            Word,
+            Space,
+            Other,
+        }
+        let class = |c: char| {
+            if c.is_alphanumeric() || c == '_' {
+                Class::Word
+            } else if c.is_whitespace() {
+                Class::Space
+            } else {
+                Class::Other
+            }
+        };
+        let mut words = Vec::new();
+        for (line_idx, line) in lines.iter().enumerate() {
+            let line = line[1..].to_str_lossy();
+            let mut offset = 0;
+            let mut chars = line.chars().peekable();
+            while let Some(first) = chars.next() {
+                let mut text = String::from(first);
+                let first_class = class(first);
+                if first_class != Class::Other {
+                    while let Some(c) = chars.next_if(|c| class(*c) == first_class) {
+                        text.push(c);
+                    }
+                }
+                let len = text.encode_utf16().count() as u32;
+                words.push(Word {
+                    line: first_line + line_idx,
+                    span: WordSpan {
+                        start: offset,
+                        end: offset + len,
+                    },
+                    text,
+                });
+                offset += len;
+            }
+        }
+        Words { words }
+    }
+
+    /// Add the spans of the words in `range` to `spans`, extending the last span of a line if it ends where the word starts.
+    fn mark(&self, range: std::ops::Range<u32>, spans: &mut [Vec<WordSpan>]) {
+        for word in &self.words[range.start as usize..range.end as usize] {
+            let line_spans = &mut spans[word.line];
+            match line_spans.last_mut() {
+                Some(last) if last.end == word.span.start => last.end = word.span.end,
+                _ => line_spans.push(word.span),
+            }
+        }
+    }
 }
 
 impl std::fmt::Debug for DiffHunk {
@@ -50,6 +205,88 @@ impl std::fmt::Debug for DiffHunk {
     }
 }
 
+/// How whitespace differences between lines are treated when computing a [`UnifiedPatch`].
+///
+/// Lines that only differ in ignored whitespace are considered unchanged, and show with their current version
+/// as context, similar to what `git diff` does.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum IgnoreWhitespace {
+    /// Whitespace is significant.
+    #[default]
+    None,
+    /// Ignore whitespace at the end of lines, like `git diff --ignore-space-at-eol`.
+    AtEol,
+    /// Ignore changes in the amount of whitespace, like `git diff --ignore-space-change`.
+    Change,
+    /// Ignore all whitespace, like `git diff --ignore-all-space`.
+    All,
+}
+
+impl IgnoreWhitespace {
+    /// The configuration key that holds the mode, with values as produced by [`Self::as_config_value()`].
+    pub const CONFIG_KEY: &str = "gitbutler.diffIgnoreWhitespace";
+
+    /// Read the mode from the configuration of `repo`, defaulting to [`IgnoreWhitespace::None`] if unset or invalid.
+    pub fn from_repo(repo: &gix::Repository) -> Self {
+        let Some(value) = repo.config_snapshot().string(Self::CONFIG_KEY) else {
+            return IgnoreWhitespace::None;
+        };
+        Self::from_config_value(value.as_ref()).unwrap_or_else(|| {
+            tracing::warn!(%value, key = Self::CONFIG_KEY, "ignoring invalid value");
+            IgnoreWhitespace::None
+        })
+    }
+
+    /// Parse `value` as one of `none`, `atEol`, `change` or `all`, ignoring case.
+    pub fn from_config_value(value: &BStr) -> Option<Self> {
+        Some(match value.to_ascii_lowercase().as_slice() {
+            b"none" | b"" => IgnoreWhitespace::None,
+            b"ateol" => IgnoreWhitespace::AtEol,
+            b"change" => IgnoreWhitespace::Change,
+            b"all" => IgnoreWhitespace::All,
+            _ => return None,
+        })
+    }
+
+    /// The value to store in the configuration to obtain this mode.
+    pub fn as_config_value(&self) -> &'static str {
+        match self {
+            IgnoreWhitespace::None => "none",
+            IgnoreWhitespace::AtEol => "atEol",
+            IgnoreWhitespace::Change => "change",
+            IgnoreWhitespace::All => "all",
+        }
+    }
+
+    /// Return `line` in a form that compares equal to all lines that only differ in ignored whitespace.
+    fn normalize(self, line: &[u8]) -> std::borrow::Cow<'_, [u8]> {
+        match self {
+            IgnoreWhitespace::None => line.into(),
+            IgnoreWhitespace::AtEol => line.trim_end().into(),
+            IgnoreWhitespace::Change => {
+                let mut out = Vec::with_capacity(line.len());
+                let mut words = line
+                    .trim_end()
+                    .split(|b| b.is_ascii_whitespace())
+                    .peekable();
+                while let Some(word) = words.next() {
+                    out.extend_from_slice(word);
+                    if words.peek().is_some_and(|next| !next.is_empty()) {
+                        out.push(b' ');
+                    }
+                }
+                out.into()
+            }
+            IgnoreWhitespace::All => line
+                .iter()
+                .copied()
+                .filter(|b| !b.is_ascii_whitespace())
+                .collect::<Vec<_>>()
+                .into(),
+        }
+    }
+}
+
 impl UnifiedPatch {
     /// Determine how resources are converted to their form used for diffing.
     ///
@@ -68,14 +305,20 @@ impl UnifiedPatch {
     /// `current_state` is either the state we know the resource currently has, or is `None`, if there is no current state.
     /// `previous_state`, if `None`, indicates the file is new so there is nothing to compare to.
     /// Otherwise, it's the state of the resource as previously known.
-    /// Return `None` if the given states cannot produce a diff, typically because a submodule is involved.
+    /// Return `None` if the given states cannot produce a diff, typically because of a type-change involving a submodule.
     ///
     /// ### Special Types
     ///
-    /// Note that *Submodules* won't render as patches, they have to be caught in the UI to render their previous hash
-    /// and current hash directly.
+    /// Note that *Submodules* won't render as patches, but as [`UnifiedPatch::Submodule`] with their previous hash
+    /// and current hash.
+    /// Files tracked by *Git LFS* render as [`UnifiedPatch::LfsPointer`] with their previous and current pointer
+    /// as long as their content in Git is a pointer.
     /// Type-changes, from file to submodule or vice-versa for instance, should be shown as typechange only, probably showing
     /// the old and the new type, without diff preview for now.
+    ///
+    /// ### Configuration
+    ///
+    /// The diff algorithm is read from `diff.algorithm`, and whitespace is ignored according to [`IgnoreWhitespace::from_repo()`].
     pub fn compute(
         repo: &gix::Repository,
         path: &BStr,
@@ -110,9 +353,75 @@ impl UnifiedPatch {
         context_lines: u32,
         diff_filter: &mut gix::diff::blob::Platform,
     ) -> anyhow::Result<Option<Self>> {
-        use gix::diff::blob;
         let current_state = current_state.into();
         let previous_state = previous_state.into();
+        let is_submodule_or_missing = |state: Option<ChangeState>| {
+            state.is_none_or(|state| state.kind == gix::object::tree::EntryKind::Commit)
+        };
+        if is_submodule_or_missing(current_state) && is_submodule_or_missing(previous_state) {
+            return Ok(Some(UnifiedPatch::Submodule {
+                previous_id: previous_state.map(|state| state.id),
+                id: current_state.map(|state| state.id),
+            }));
+        }
+        let ignore_whitespace = IgnoreWhitespace::from_repo(repo);
+        let key = PatchKey::new(
+            repo,
+            path,
+            previous_path,
+            current_state,
+            previous_state,
+            context_lines,
+            ignore_whitespace,
+        )?;
+        if let Some(patch) = key.as_ref().and_then(|key| PATCH_CACHE.lock().get(key)) {
+            return Ok(patch);
+        }
+        let patch = Self::compute_uncached(
+            repo,
+            path,
+            previous_path,
+            current_state,
+            previous_state,
+            context_lines,
+            ignore_whitespace,
+            diff_filter,
+        )?;
+        if let Some(key) = key {
+            PATCH_CACHE.lock().insert(key, patch.clone());
+        }
+        Ok(patch)
+    }
+
+    /// Compute the [word diff](DiffHunk::word_diff) of all hunks if this is a [`UnifiedPatch::Patch`].
+    ///
+    /// This is a separate step as it's only needed to highlight changes within lines.
+    pub fn with_word_diffs(mut self) -> Self {
+        if let UnifiedPatch::Patch { hunks, .. } = &mut self {
+            for hunk in hunks {
+                hunk.word_diff = Some(hunk.compute_word_diff());
+            }
+        }
+        self
+    }
+
+    /// Forget all patches that were cached by [`Self::compute_with_filter()`], which is needed if `.gitattributes`
+    /// or the diff drivers changed, as these affect the patch of the same pair of objects.
+    pub fn clear_cache() {
+        PATCH_CACHE.lock().clear();
+    }
+
+    fn compute_uncached(
+        repo: &gix::Repository,
+        path: &BStr,
+        previous_path: Option<&BStr>,
+        current_state: Option<ChangeState>,
+        previous_state: Option<ChangeState>,
+        context_lines: u32,
+        ignore_whitespace: IgnoreWhitespace,
+        diff_filter: &mut gix::diff::blob::Platform,
+    ) -> anyhow::Result<Option<Self>> {
+        use gix::diff::blob;
         match diff_filter.set_resource(
             current_state.map_or(repo.object_hash().null(), |state| state.id),
             current_state.map_or_else(
@@ -163,12 +472,45 @@ impl UnifiedPatch {
             Err(err) => return Err(err.into()),
         };
 
+        if let Some((previous, current)) = lfs_pointers(diff_filter) {
+            return Ok(Some(UnifiedPatch::LfsPointer { previous, current }));
+        }
+        let encoding = display_encoding(repo, path, diff_filter);
+
         let prep = diff_filter.prepare_diff()?;
         Ok(Some(match prep.operation {
+            Operation::InternalDiff { algorithm }
+                if ignore_whitespace != IgnoreWhitespace::None =>
+            {
+                use gix::diff::blob::platform::resource::Data;
+                let is_result_of_binary_to_text_conversion = prep.old_or_new_is_derived;
+                let (old, new) = diff_filter
+                    .resources()
+                    .expect("prepare would have failed if a resource is missing");
+                let buf = |data: Data<'_>| match data {
+                    Data::Buffer { buf, .. } => buf,
+                    Data::Missing | Data::Binary { .. } => &[],
+                };
+                let hunks = whitespace_insensitive_hunks(
+                    algorithm,
+                    buf(old.data),
+                    buf(new.data),
+                    context_lines,
+                    ignore_whitespace,
+                    encoding,
+                );
+                let (lines_added, lines_removed) = compute_line_changes(&hunks);
+                UnifiedPatch::Patch {
+                    is_result_of_binary_to_text_conversion,
+                    hunks,
+                    lines_added,
+                    lines_removed,
+                }
+            }
             Operation::InternalDiff { algorithm } => {
-                #[derive(Default)]
                 struct ProduceDiffHunk {
                     hunks: Vec<DiffHunk>,
+                    encoding: Option<&'static encoding_rs::Encoding>,
                 }
                 impl gix::diff::blob::unified_diff::ConsumeBinaryHunkDelegate for ProduceDiffHunk {
                     fn consume_binary_hunk(
@@ -186,8 +528,10 @@ impl UnifiedPatch {
                                 let mut buf = Vec::with_capacity(header_str.len() + hunk.len());
                                 buf.extend_from_slice(header_str.as_bytes());
                                 buf.extend_from_slice(hunk);
-                                detect_and_convert_to_utf8(buf.into())
+                                decode_for_display(buf.into(), self.encoding)
                             },
+                            encoding: self.encoding.map(|encoding| encoding.name().to_owned()),
+                            word_diff: None,
                         });
                         Ok(())
                     }
@@ -195,7 +539,13 @@ impl UnifiedPatch {
                 let input = prep.interned_input();
                 let uni_diff = gix::diff::blob::UnifiedDiff::new(
                     &input,
-                    ConsumeBinaryHunk::new(ProduceDiffHunk::default(), "\n"),
+                    ConsumeBinaryHunk::new(
+                        ProduceDiffHunk {
+                            hunks: Vec::new(),
+                            encoding,
+                        },
+                        "\n",
+                    ),
                     ContextSize::symmetrical(context_lines),
                 );
                 let hunks = gix::diff::blob::diff(algorithm, &input, uni_diff)?.hunks;
@@ -239,23 +589,125 @@ impl UnifiedPatch {
     }
 }
 
-/// Detect the encoding of the given byte content and convert it to UTF-8, after attempting to guess its encoding.
-/// Even if decoding failed, we always return the original `content` in the wirst case.
-fn detect_and_convert_to_utf8(content: BString) -> BString {
-    // Use chardet to detect the encoding
-    let mut detect = chardetng::EncodingDetector::new();
-    detect.feed(&content, true);
-    let (encoding, high_confidence) = detect.guess_assess(None, true);
-    if encoding.name() == "UTF-8" || !high_confidence {
-        return content;
+/// The amount of patches to keep in the [`PATCH_CACHE`].
+const PATCH_CACHE_CAPACITY: usize = 2048;
+
+/// Patches between objects that are stored in the object database, which don't change unless the configuration does,
+/// and which would otherwise be recomputed on each refresh of the worktree changes, of hunk assignments or of hunk dependencies.
+static PATCH_CACHE: parking_lot::Mutex<PatchCache> = parking_lot::Mutex::new(PatchCache::new());
+
+/// Everything that affects the outcome of a diff between two objects.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct PatchKey {
+    /// The repository, as its configuration and attributes affect the diff.
+    common_dir: std::path::PathBuf,
+    /// The path used to look up attributes.
+    path: BString,
+    previous_path: Option<BString>,
+    current: Option<(gix::ObjectId, gix::object::tree::EntryKind)>,
+    previous: Option<(gix::ObjectId, gix::object::tree::EntryKind)>,
+    context_lines: u32,
+    /// The configured diff algorithm.
+    algorithm: u8,
+    ignore_whitespace: IgnoreWhitespace,
+    /// The encoding configured for `path`, or `auto`.
+    display_encoding: &'static str,
+}
+
+impl PatchKey {
+    /// Return `None` if one of the states refers to the worktree, whose content isn't known by id.
+    fn new(
+        repo: &gix::Repository,
+        path: &BStr,
+        previous_path: Option<&BStr>,
+        current_state: Option<ChangeState>,
+        previous_state: Option<ChangeState>,
+        context_lines: u32,
+        ignore_whitespace: IgnoreWhitespace,
+    ) -> anyhow::Result<Option<Self>> {
+        let stored = |state: Option<ChangeState>| match state {
+            None => Some(None),
+            Some(state) => (!state.id.is_null()).then_some(Some((state.id, state.kind))),
+        };
+        let (Some(current), Some(previous)) = (stored(current_state), stored(previous_state))
+        else {
+            return Ok(None);
+        };
+        Ok(Some(PatchKey {
+            common_dir: repo.common_dir().to_owned(),
+            path: path.to_owned(),
+            previous_path: previous_path.map(ToOwned::to_owned),
+            current,
+            previous,
+            context_lines,
+            algorithm: repo.diff_algorithm()? as u8,
+            ignore_whitespace,
+            display_encoding: DisplayEncodings::from_repo(repo)
+                .configured(path)
+                .map_or("auto", |encoding| encoding.name()),
+        }))
     }
+}
 
-    // Convert to UTF-8
-    let (decoded, _actual_encoding, _used_replacement_chars) = encoding.decode(&content);
-    decoded.into_owned().into()
+/// A cache of patches that drops the least recently used patch once it's full.
+struct PatchCache {
+    entries: std::collections::BTreeMap<PatchKey, (Option<UnifiedPatch>, u64)>,
+    /// Incremented on each access to know which entry was used least recently.
+    tick: u64,
 }
 
-fn compute_line_changes(hunks: &Vec<DiffHunk>) -> (u32, u32) {
+impl PatchCache {
+    const fn new() -> Self {
+        PatchCache {
+            entries: std::collections::BTreeMap::new(),
+            tick: 0,
+        }
+    }
+
+    fn get(&mut self, key: &PatchKey) -> Option<Option<UnifiedPatch>> {
+        self.tick += 1;
+        let (patch, last_used) = self.entries.get_mut(key)?;
+        *last_used = self.tick;
+        Some(patch.clone())
+    }
+
+    fn insert(&mut self, key: PatchKey, patch: Option<UnifiedPatch>) {
+        self.tick += 1;
+        if self.entries.len() >= PATCH_CACHE_CAPACITY
+            && !self.entries.contains_key(&key)
+            && let Some(least_recently_used) = self
+                .entries
+                .iter()
+                .min_by_key(|(_, (_, last_used))| *last_used)
+                .map(|(key, _)| key.clone())
+        {
+            self.entries.remove(&least_recently_used);
+        }
+        self.entries.insert(key, (patch, self.tick));
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+/// Return the encoding to decode the diff of the resources set on `diff_filter` with for display,
+/// detected from both versions of the file at `path` as a whole, or `None` if it can be shown as is.
+fn display_encoding(
+    repo: &gix::Repository,
+    path: &BStr,
+    diff_filter: &gix::diff::blob::Platform,
+) -> Option<&'static encoding_rs::Encoding> {
+    use gix::diff::blob::platform::resource::Data;
+    let (old, new) = diff_filter.resources()?;
+    let buf = |data: Data<'_>| match data {
+        Data::Buffer { buf, .. } => buf,
+        Data::Missing | Data::Binary { .. } => &[],
+    };
+    DisplayEncodings::from_repo(repo).encoding_for(path, &[buf(new.data), buf(old.data)])
+}
+
+pub(crate) fn compute_line_changes(hunks: &Vec<DiffHunk>) -> (u32, u32) {
     let mut lines_added = 0;
     let mut lines_removed = 0;
     for hunk in hunks {
@@ -270,6 +722,102 @@ fn compute_line_changes(hunks: &Vec<DiffHunk>) -> (u32, u32) {
     (lines_added, lines_removed)
 }
 
+/// Diff the lines of `old` and `new` with `algorithm` while ignoring whitespace according to `ignore_whitespace`,
+/// and produce hunks with `context_lines` that show the current version of lines that are considered unchanged.
+fn whitespace_insensitive_hunks(
+    algorithm: gix::diff::blob::Algorithm,
+    old: &[u8],
+    new: &[u8],
+    context_lines: u32,
+    ignore_whitespace: IgnoreWhitespace,
+    encoding: Option<&'static encoding_rs::Encoding>,
+) -> Vec<DiffHunk> {
+    use std::ops::Range;
+    let old_lines: Vec<&[u8]> = old.lines_with_terminator().collect();
+    let new_lines: Vec<&[u8]> = new.lines_with_terminator().collect();
+    let mut interner = gix::diff::blob::intern::Interner::new(old_lines.len() + new_lines.len());
+    let old_tokens: Vec<_> = old_lines
+        .iter()
+        .map(|line| interner.intern(ignore_whitespace.normalize(line)))
+        .collect();
+    let new_tokens: Vec<_> = new_lines
+        .iter()
+        .map(|line| interner.intern(ignore_whitespace.normalize(line)))
+        .collect();
+    let mut changes = Vec::<(Range<u32>, Range<u32>)>::new();
+    gix::diff::blob::diff_with_tokens(
+        algorithm,
+        &old_tokens,
+        &new_tokens,
+        interner.num_tokens(),
+        |before: Range<u32>, after: Range<u32>| changes.push((before, after)),
+    );
+
+    /// Append `lines[range]` to `out`, each starting with `prefix`.
+    fn push_lines(out: &mut Vec<u8>, prefix: u8, lines: &[&[u8]], range: Range<u32>) {
+        for line in &lines[range.start as usize..range.end as usize] {
+            out.push(prefix);
+            out.extend_from_slice(line);
+            if !line.ends_with(b"\n") {
+                out.push(b'\n');
+            }
+        }
+    }
+
+    let num_old_lines = old_lines.len() as u32;
+    let mut hunks = Vec::new();
+    let mut changes = changes.into_iter().peekable();
+    while let Some((before, after)) = changes.next() {
+        // The lines between changes are unchanged, so there are as many of them on both sides.
+        let leading_context = context_lines.min(before.start);
+        let (old_start, new_start) = (
+            before.start - leading_context,
+            after.start - leading_context,
+        );
+        let mut body = Vec::new();
+        push_lines(&mut body, b' ', &new_lines, new_start..after.start);
+        push_lines(&mut body, b'-', &old_lines, before.clone());
+        push_lines(&mut body, b'+', &new_lines, after.clone());
+        let (mut old_end, mut new_end) = (before.end, after.end);
+        while let Some((before, after)) =
+            changes.next_if(|(before, _)| before.start - old_end <= 2 * context_lines)
+        {
+            push_lines(&mut body, b' ', &new_lines, new_end..after.start);
+            push_lines(&mut body, b'-', &old_lines, before.clone());
+            push_lines(&mut body, b'+', &new_lines, after.clone());
+            (old_end, new_end) = (before.end, after.end);
+        }
+        let trailing_context = context_lines.min(num_old_lines - old_end);
+        push_lines(
+            &mut body,
+            b' ',
+            &new_lines,
+            new_end..new_end + trailing_context,
+        );
+        let (old_end, new_end) = (old_end + trailing_context, new_end + trailing_context);
+
+        let (old_start, old_lines, new_start, new_lines) = (
+            old_start + 1,
+            old_end - old_start,
+            new_start + 1,
+            new_end - new_start,
+        );
+        let mut diff =
+            format!("@@ -{old_start},{old_lines} +{new_start},{new_lines} @@\n").into_bytes();
+        diff.extend_from_slice(&body);
+        hunks.push(DiffHunk {
+            old_start,
+            old_lines,
+            new_start,
+            new_lines,
+            diff: decode_for_display(diff.into(), encoding),
+            encoding: encoding.map(|encoding| encoding.name().to_owned()),
+            word_diff: None,
+        });
+    }
+    hunks
+}
+
 /// Produce a filter from `repo` and `state` using `mode` that is able to perform diffs of `state`.
 pub fn filter_from_state(
     repo: &gix::Repository,
@@ -286,3 +834,67 @@ pub fn filter_from_state(
         },
     )?)
 }
+
+/// Return the previous and current LFS pointer if the resources set on `diff_filter` are pointers or missing,
+/// with at least one of them being a pointer.
+fn lfs_pointers(
+    diff_filter: &gix::diff::blob::Platform,
+) -> Option<(Option<LfsPointer>, Option<LfsPointer>)> {
+    use gix::diff::blob::platform::resource::Data;
+    let (old, new) = diff_filter.resources()?;
+    let parse = |data: Data<'_>| match data {
+        Data::Missing => Some(None),
+        Data::Buffer { buf, .. } => LfsPointer::from_bytes(buf).map(Some),
+        Data::Binary { .. } => None,
+    };
+    let (previous, current) = (parse(old.data)?, parse(new.data)?);
+    (previous.is_some() || current.is_some()).then_some((previous, current))
+}
+
+#[cfg(test)]
+mod tests {
+    mod patch_cache {
+        use crate::{
+            UnifiedPatch,
+            unified_diff::{IgnoreWhitespace, PATCH_CACHE_CAPACITY, PatchCache, PatchKey},
+        };
+
+        fn key(context_lines: usize) -> PatchKey {
+            PatchKey {
+                common_dir: ".git".into(),
+                path: "file".into(),
+                previous_path: None,
+                current: None,
+                previous: None,
+                context_lines: context_lines as u32,
+                algorithm: 0,
+                ignore_whitespace: IgnoreWhitespace::None,
+                display_encoding: "auto",
+            }
+        }
+
+        #[test]
+        fn least_recently_used_is_dropped_when_full() {
+            let mut cache = PatchCache::new();
+            for context_lines in 0..PATCH_CACHE_CAPACITY {
+                cache.insert(key(context_lines), Some(UnifiedPatch::Binary));
+            }
+            assert!(
+                cache.get(&key(0)).is_some(),
+                "the oldest entry is used, so it's not the least recently used one anymore"
+            );
+
+            cache.insert(key(PATCH_CACHE_CAPACITY), None);
+            assert_eq!(cache.entries.len(), PATCH_CACHE_CAPACITY);
+            assert!(cache.get(&key(1)).is_none(), "this one was dropped");
+            assert!(cache.get(&key(0)).is_some());
+            assert!(
+                matches!(cache.get(&key(PATCH_CACHE_CAPACITY)), Some(None)),
+                "patches that couldn't be produced are cached as well"
+            );
+
+            cache.clear();
+            assert!(cache.get(&key(0)).is_none());
+        }
+    }
+}
@@ -40,3 +40,83 @@ fn delete_reference_journey() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn protected_branches_match_short_and_full_names() -> anyhow::Result<()> {
+    let tmp = gix_testtools::tempfile::TempDir::new()?;
+    gix::init(tmp.path())?;
+    let mut repo = gix::open_opts(tmp.path(), gix::open::Options::isolated())?;
+    assert!(branch::ProtectedBranches::from_repo(&repo)?.is_empty());
+
+    repo.config_snapshot_mut()
+        .set_raw_value(&"gitbutler.protectedBranches", "main release/*")?;
+    let protected = branch::ProtectedBranches::from_repo(&repo)?;
+
+    assert_eq!(
+        protected.matching_pattern("main".into()),
+        Some("main".into())
+    );
+    assert_eq!(
+        protected.matching_pattern("refs/heads/release/1.0".into()),
+        Some("release/*".into())
+    );
+    assert_eq!(
+        protected.matching_pattern("release/1.0/hotfix".into()),
+        None,
+        "a single star doesn't match across slashes"
+    );
+    assert_eq!(protected.matching_pattern("feature".into()), None);
+
+    let err = protected
+        .ensure_unprotected("release/2.0".into())
+        .unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<but_error::ButError>(),
+        Some(&but_error::ButError::BranchProtected {
+            name: "release/2.0".into(),
+            pattern: "release/*".into(),
+        })
+    );
+    Ok(())
+}
+
+#[test]
+fn protected_branches_can_only_be_fast_forwarded() -> anyhow::Result<()> {
+    let tmp = gix_testtools::tempfile::TempDir::new()?;
+    gix::init(tmp.path())?;
+    let mut repo = gix::open_opts(tmp.path(), gix::open::Options::isolated())?;
+    repo.config_snapshot_mut()
+        .set_raw_value(&"gitbutler.protectedBranches", "main")?;
+    let protected = branch::ProtectedBranches::from_repo(&repo)?;
+
+    let empty_tree = repo.empty_tree().id;
+    let base = repo
+        .new_commit("base", empty_tree, None::<gix::ObjectId>)?
+        .id;
+    let ahead = repo.new_commit("ahead", empty_tree, Some(base))?.id;
+    let rewritten = repo.new_commit("rewritten", empty_tree, Some(base))?.id;
+
+    protected.ensure_push_unprotected(&repo, "main".into(), Some(base), ahead)?;
+    protected.ensure_push_unprotected(&repo, "main".into(), Some(ahead), ahead)?;
+    protected.ensure_push_unprotected(&repo, "main".into(), None, rewritten)?;
+    protected.ensure_push_unprotected(&repo, "feature".into(), Some(ahead), rewritten)?;
+
+    let err = protected
+        .ensure_push_unprotected(&repo, "main".into(), Some(ahead), rewritten)
+        .unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<but_error::ButError>(),
+        Some(&but_error::ButError::BranchProtected {
+            name: "main".into(),
+            pattern: "main".into(),
+        }),
+        "rewriting the history of a protected branch is refused"
+    );
+    assert!(
+        protected
+            .ensure_push_unprotected(&repo, "main".into(), Some(ahead), base)
+            .is_err(),
+        "moving it back also loses commits"
+    );
+    Ok(())
+}
@@ -0,0 +1,34 @@
+use but_core::diff::patch_id;
+use but_testsupport::read_only_in_memory_scenario;
+
+#[test]
+fn squash_merge_has_the_patch_id_of_the_merged_branch() -> anyhow::Result<()> {
+    let repo = read_only_in_memory_scenario("squash-merged")?;
+    let id =
+        |spec: &str| -> anyhow::Result<gix::ObjectId> { Ok(repo.rev_parse_single(spec)?.detach()) };
+    let merge_base = repo.merge_base(id("feature")?, id("main")?)?.detach();
+
+    let branch = patch_id(&repo, Some(merge_base), id("feature")?)?;
+    assert!(branch.is_some());
+    assert_eq!(
+        patch_id(&repo, Some(id("main~1")?), id("main")?)?,
+        branch,
+        "the same changes on top of a different base have the same id"
+    );
+    assert_ne!(
+        patch_id(&repo, Some(merge_base), id("feature~1")?)?,
+        branch,
+        "only part of the changes"
+    );
+    assert_ne!(
+        patch_id(&repo, Some(merge_base), id("other")?)?,
+        branch,
+        "different changes to the same file"
+    );
+    assert_eq!(
+        patch_id(&repo, Some(id("feature")?), id("feature")?)?,
+        None,
+        "no changes, no id"
+    );
+    Ok(())
+}
@@ -1,5 +1,5 @@
 use anyhow::Result;
-use but_core::{UnifiedPatch, WorktreeChanges, diff};
+use but_core::{UnifiedPatch, WorktreeChanges, diff, shallow::ChangesSource};
 use but_testsupport::gix_testtools;
 
 #[test]
@@ -263,6 +263,30 @@ fn sparse() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn sparse_cone_excludes_paths_outside_of_it() -> Result<()> {
+    let repo = repo_in("sparse", "cone")?;
+    let actual = diff::worktree_changes(&repo)?;
+    assert_eq!(
+        actual
+            .changes
+            .iter()
+            .map(|change| change.path.to_string())
+            .collect::<Vec<_>>(),
+        ["c1/c2/a"],
+        "the untracked file outside of the cone isn't a change that can be committed"
+    );
+    assert_eq!(
+        actual
+            .ignored_changes
+            .iter()
+            .map(|change| change.path.to_string())
+            .collect::<Vec<_>>(),
+        ["d/untracked"]
+    );
+    Ok(())
+}
+
 #[test]
 fn submodule_added_in_unborn() -> Result<()> {
     let repo = repo("submodule-added-unborn")?;
@@ -294,9 +318,15 @@ fn submodule_added_in_unborn() -> Result<()> {
         ignored_changes: [],
     }
     "#);
-    assert_eq!(
-        unified_patches(actual, &repo).unwrap_err().to_string(),
-        "Can only diff blobs and links, not Commit"
+    assert!(
+        matches!(
+            unified_patches(actual, &repo)?[1],
+            UnifiedPatch::Submodule {
+                previous_id: None,
+                id: Some(_)
+            }
+        ),
+        "submodules are shown as the commit they point to"
     );
     Ok(())
 }
@@ -367,10 +397,18 @@ fn submodule_changed_head() -> Result<()> {
         ignored_changes: [],
     }
     "#);
-    assert_eq!(
-        unified_patches(actual, &repo).unwrap_err().to_string(),
-        "Can only diff blobs and links, not Commit"
-    );
+    insta::assert_debug_snapshot!(unified_patches(actual, &repo)?, @r"
+    [
+        Submodule {
+            previous_id: Some(
+                Sha1(e95516bd2f49a83a6cdb98cfec40b2717fbc2c1b),
+            ),
+            id: Some(
+                Sha1(800a5398d76f28db44bc976b561d8885687fd1b6),
+            ),
+        },
+    ]
+    ");
     Ok(())
 }
 
@@ -469,6 +507,37 @@ fn case_folding_worktree_and_index_changes() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn case_only_rename() -> Result<()> {
+    let repo = repo("case-only-rename")?;
+    let actual = diff::worktree_changes(&repo)?;
+    // With `core.ignoreCase`, the file is found under its new name on disk, which is shown as rename,
+    // independently of whether the filesystem is actually case-insensitive.
+    insta::assert_debug_snapshot!(actual, @r#"
+    WorktreeChanges {
+        changes: [
+            TreeChange {
+                path: "foo.rs",
+                status: Rename {
+                    previous_path: "Foo.rs",
+                    previous_state: ChangeState {
+                        id: Sha1(d95f3ad14dee633a758d2e331151e950dd13e4ed),
+                        kind: Blob,
+                    },
+                    state: ChangeState {
+                        id: Sha1(0000000000000000000000000000000000000000),
+                        kind: Blob,
+                    },
+                    flags: None,
+                },
+            },
+        ],
+        ignored_changes: [],
+    }
+    "#);
+    Ok(())
+}
+
 #[test]
 fn file_to_dir_in_worktree() -> Result<()> {
     let repo = repo("file-to-dir-in-worktree")?;
@@ -966,6 +1035,110 @@ fn added_modified_in_worktree() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn unified_patches_in_parallel_keep_their_order() -> Result<()> {
+    let repo = repo("added-modified-in-worktree")?;
+    let changes = diff::worktree_changes(&repo)?.changes;
+    let expected = format!("{:?}", super::unified_patches(&changes, &repo)?);
+    for threads in [None, Some(1), Some(2), Some(changes.len() + 1)] {
+        let actual = diff::unified_patches(&repo, &changes, ChangesSource::Worktree, 3, threads)
+            .into_iter()
+            .map(|patch| patch.map(|patch| patch.expect("all blobs")))
+            .collect::<Result<Vec<_>>>()?;
+        assert_eq!(
+            format!("{actual:?}"),
+            expected,
+            "the outcome doesn't depend on the amount of {threads:?} threads"
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn stream_hunks_with_byte_budget() -> Result<()> {
+    let repo = repo("added-modified-in-worktree")?;
+    let changes = diff::worktree_changes(&repo)?.changes;
+    let modified = changes
+        .iter()
+        .find(|change| change.path == "modified")
+        .expect("present");
+
+    let mut hunks = Vec::new();
+    diff::stream_hunks(&repo, modified, 3, 1024, |hunk| {
+        hunks.push(hunk);
+        std::ops::ControlFlow::Continue(())
+    })?;
+    insta::assert_debug_snapshot!(hunks, @r#"
+    [
+        Hunk(
+            DiffHunk("@@ -1,1 +1,1 @@
+            -something
+            +change
+            "),
+        ),
+    ]
+    "#);
+
+    let mut hunks = Vec::new();
+    diff::stream_hunks(&repo, modified, 3, 5, |hunk| {
+        hunks.push(hunk);
+        std::ops::ControlFlow::Continue(())
+    })?;
+    insta::assert_debug_snapshot!(hunks, @r"
+    [
+        Truncated {
+            old_start: 1,
+            new_start: 1,
+        },
+    ]
+    ");
+    assert!(
+        diff::prefix_patch(&repo, modified, 3, 5)?.is_none(),
+        "nothing is known about the first line, so there is no patch"
+    );
+    Ok(())
+}
+
+#[test]
+fn incremental_matches_full_diff() -> Result<()> {
+    let tmp = gix_testtools::scripted_fixture_writable("worktree-changes.sh")
+        .map_err(anyhow::Error::from_boxed)?;
+    let repo = gix::open_opts(
+        tmp.path().join("added-modified-in-worktree"),
+        gix::open::Options::isolated(),
+    )?;
+    let workdir = repo.workdir().expect("non-bare");
+    let previous = diff::worktree_changes(&repo)?;
+
+    std::fs::write(workdir.join("untracked"), "new\n")?;
+    std::fs::rename(workdir.join("modified"), workdir.join("renamed"))?;
+    let actual = diff::worktree_changes_incremental(
+        &repo,
+        previous.clone(),
+        &["untracked".into(), "modified".into(), "renamed".into()],
+    )?;
+    assert_eq!(
+        format!("{actual:?}"),
+        format!("{:?}", diff::worktree_changes(&repo)?),
+        "only the dirty paths are re-diffed, but the outcome is the same"
+    );
+
+    let actual = diff::worktree_changes_incremental(&repo, actual, &["renamed".into()])?;
+    assert_eq!(
+        format!("{actual:?}"),
+        format!("{:?}", diff::worktree_changes(&repo)?),
+        "the rename is kept even though its source wasn't marked as dirty"
+    );
+
+    let actual = diff::worktree_changes_incremental(&repo, previous, &[])?;
+    assert_eq!(
+        actual.changes.len(),
+        3,
+        "without dirty paths, the previous changes are returned"
+    );
+    Ok(())
+}
+
 #[test]
 fn non_utf8_decoding() -> Result<()> {
     let repo = repo("non-utf8-encodings")?;
@@ -0,0 +1,57 @@
+use but_core::ignore::{IgnoreSource, explain_ignore};
+use but_testsupport::read_only_in_memory_scenario;
+
+#[test]
+fn patterns_of_all_sources_are_explained() -> anyhow::Result<()> {
+    let repo = read_only_in_memory_scenario("ignored-files")?;
+
+    let explanation = explain_ignore(&repo, "target/debug/app".into())?;
+    assert!(explanation.is_ignored, "ignored by a parent directory");
+    let pattern = explanation.pattern.expect("matched");
+    assert_eq!(pattern.source, IgnoreSource::Gitignore);
+    assert_eq!(pattern.file.as_deref(), Some(".gitignore".as_ref()));
+    assert_eq!(pattern.line_number, 1);
+
+    let explanation = explain_ignore(&repo, "debug.log".into())?;
+    assert!(explanation.is_ignored);
+    assert_eq!(explanation.pattern.expect("matched").pattern, "*.log");
+
+    let explanation = explain_ignore(&repo, "scratch".into())?;
+    assert!(explanation.is_ignored);
+    assert_eq!(
+        explanation.pattern.expect("matched").source,
+        IgnoreSource::InfoExclude
+    );
+
+    let explanation = explain_ignore(&repo, "file.tmp".into())?;
+    assert!(explanation.is_ignored, "core.excludesFile is honored");
+    assert_eq!(
+        explanation.pattern.expect("matched").source,
+        IgnoreSource::ExcludesFile
+    );
+    Ok(())
+}
+
+#[test]
+fn negated_tracked_and_unmatched_paths_are_not_ignored() -> anyhow::Result<()> {
+    let repo = read_only_in_memory_scenario("ignored-files")?;
+
+    let explanation = explain_ignore(&repo, "keep.log".into())?;
+    assert!(!explanation.is_ignored);
+    let pattern = explanation.pattern.expect("the negation matched last");
+    assert!(pattern.negated);
+    assert_eq!(pattern.line_number, 3);
+
+    let explanation = explain_ignore(&repo, "tracked.log".into())?;
+    assert!(explanation.is_tracked);
+    assert!(
+        !explanation.is_ignored,
+        "tracked files are never ignored, even though a pattern matches"
+    );
+    assert!(explanation.pattern.is_some());
+
+    let explanation = explain_ignore(&repo, "untracked".into())?;
+    assert!(!explanation.is_ignored);
+    assert_eq!(explanation.pattern, None);
+    Ok(())
+}
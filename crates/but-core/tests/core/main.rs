@@ -3,10 +3,14 @@ mod change_id;
 mod cmd;
 mod commit;
 mod diff;
+mod ignore;
 mod json_samples;
 mod ref_metadata;
 mod settings;
+mod shallow;
+mod signing;
 mod snapshot;
 mod sync;
+mod unchanged;
 mod unified_diff;
 mod worktree;
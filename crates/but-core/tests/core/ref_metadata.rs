@@ -148,3 +148,79 @@ mod workspace {
         StackId::generate()
     }
 }
+
+mod stack_identity {
+    use but_core::{
+        RepositoryExt,
+        ref_metadata::{Identity, StackIdentity},
+    };
+    use but_testsupport::gix_testtools;
+
+    #[test]
+    fn overrides_only_what_is_set() -> anyhow::Result<()> {
+        let tmp = gix_testtools::tempfile::TempDir::new()?;
+        gix::init(tmp.path())?;
+        let mut repo = gix::open_opts(tmp.path(), gix::open::Options::isolated())?;
+        {
+            let mut config = repo.config_snapshot_mut();
+            config.set_raw_value(&"user.name", "user")?;
+            config.set_raw_value(&"user.email", "user@example.com")?;
+        }
+
+        let repo = repo.with_stack_identity(&StackIdentity::default())?;
+        let (author, committer) = repo.commit_signatures()?;
+        assert_eq!(
+            (author.name, committer.name),
+            ("user".into(), "user".into()),
+            "nothing changes without overrides"
+        );
+
+        let repo = repo.with_stack_identity(&StackIdentity {
+            author: Some(Identity {
+                name: "other".into(),
+                email: "other@example.com".into(),
+            }),
+            committer: None,
+        })?;
+        let (author, committer) = repo.commit_signatures()?;
+        assert_eq!(author.name, "other");
+        assert_eq!(author.email, "other@example.com");
+        assert_eq!(
+            committer.name, "user",
+            "the committer still comes from `user.*`"
+        );
+        Ok(())
+    }
+}
+
+mod stack_notes {
+    use but_core::ref_metadata::StackNotes;
+
+    #[test]
+    fn notes_follow_rewritten_commits() {
+        let (a, b, c) = (id(1), id(2), id(3));
+        let mut notes = StackNotes::default();
+        notes.set_commit_note(a, Some("first".into()));
+        notes.set_commit_note(b, Some("second".into()));
+        notes.set_commit_note(c, Some("   ".into()));
+        assert_eq!(notes.commits.len(), 2, "blank notes aren't stored");
+
+        let (new_a, new_b) = (id(4), id(5));
+        notes.rewrite_commits([(a, new_a), (b, new_b)]);
+        assert_eq!(notes.commit_note(&a), None);
+        assert_eq!(notes.commit_note(&new_a), Some("first"));
+        assert_eq!(notes.commit_note(&new_b), Some("second"));
+
+        let squashed = id(6);
+        notes.rewrite_commits([(new_a, squashed), (new_b, squashed)]);
+        assert_eq!(notes.commits.len(), 1);
+        assert_eq!(notes.commit_note(&squashed), Some("first\n\nsecond"));
+
+        notes.set_commit_note(squashed, None);
+        assert!(notes.is_empty());
+    }
+
+    fn id(byte: u8) -> gix::ObjectId {
+        gix::ObjectId::from_bytes_or_panic(&[byte; 20])
+    }
+}
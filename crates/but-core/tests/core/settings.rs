@@ -14,6 +14,8 @@ mod git {
                 gitbutler_sign_commits: Some(false),
                 ..GitConfigSettings {
                     gitbutler_gerrit_mode: Some(false),
+                    gitbutler_run_hooks: Some(true),
+                    gitbutler_forge_stack_footer: Some(true),
                     ..Default::default()
                 }
             },
@@ -23,6 +25,9 @@ mod git {
             gitbutler_sign_commits: Some(true),
             gitbutler_gerrit_mode: Some(false),
             gitbutler_forge_review_template_path: None,
+            gitbutler_run_hooks: Some(false),
+            gitbutler_forge_stack_footer: Some(false),
+            gitbutler_protected_branches: Some("main release/*".into()),
             signing_key: Some("signing key".into()),
             signing_format: Some("signing format".into()),
             gpg_program: Some("gpg program".into()),
@@ -54,6 +59,8 @@ mod git {
             gitbutler_sign_commits: Some(true),
             ..GitConfigSettings {
                 gitbutler_gerrit_mode: Some(false),
+                gitbutler_run_hooks: Some(true),
+                gitbutler_forge_stack_footer: Some(true),
                 ..Default::default()
             }
         };
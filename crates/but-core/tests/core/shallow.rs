@@ -0,0 +1,118 @@
+use but_core::shallow;
+use but_testsupport::read_only_in_memory_scenario;
+
+#[test]
+fn promisor_remotes_of_partial_clone() -> anyhow::Result<()> {
+    let repo = read_only_in_memory_scenario("shallow-partial-clone")?;
+    assert_eq!(shallow::promisor_remotes(&repo), ["origin"]);
+    assert!(shallow::is_partial_clone(&repo));
+
+    let repo = read_only_in_memory_scenario("marked-unchanged")?;
+    assert!(shallow::promisor_remotes(&repo).is_empty());
+    assert!(!shallow::is_partial_clone(&repo));
+    Ok(())
+}
+
+#[test]
+fn missing_history_of_shallow_clone_is_typed_error() -> anyhow::Result<()> {
+    let repo = read_only_in_memory_scenario("shallow-partial-clone")?;
+    assert!(repo.is_shallow());
+    let head = repo.head_id()?.detach();
+    let err = shallow::into_shallow_history_error(&repo, head, anyhow::anyhow!("not found"));
+    assert_eq!(
+        err.downcast_ref::<but_error::ButError>(),
+        Some(&but_error::ButError::ShallowHistory {
+            commit_id: head.to_string(),
+        })
+    );
+
+    let repo = read_only_in_memory_scenario("marked-unchanged")?;
+    let err = shallow::into_shallow_history_error(&repo, head, anyhow::anyhow!("not found"));
+    assert_eq!(
+        err.downcast_ref::<but_error::ButError>(),
+        None,
+        "errors of complete repositories are left as they are"
+    );
+    Ok(())
+}
+
+mod prefetch_blobs_of_changes {
+    use but_core::{
+        ChangeState, TreeChange, TreeStatus,
+        shallow::{self, ChangesSource},
+    };
+    use but_testsupport::writable_scenario_slow;
+    use gix::object::tree::EntryKind;
+
+    fn blob(id: gix::ObjectId) -> ChangeState {
+        ChangeState {
+            id,
+            kind: EntryKind::Blob,
+        }
+    }
+
+    fn modification(previous_id: gix::ObjectId, id: gix::ObjectId) -> TreeChange {
+        TreeChange {
+            path: "file".into(),
+            status: TreeStatus::Modification {
+                previous_state: blob(previous_id),
+                state: blob(id),
+                flags: None,
+            },
+        }
+    }
+
+    fn hash(data: &[u8]) -> anyhow::Result<gix::ObjectId> {
+        Ok(gix::objs::compute_hash(
+            gix::hash::Kind::Sha1,
+            gix::object::Kind::Blob,
+            data,
+        )?)
+    }
+
+    #[test]
+    fn worktree_states_are_not_fetched() -> anyhow::Result<()> {
+        let (repo, _tmp) = writable_scenario_slow("shallow-partial-clone");
+        let head_blob = repo.rev_parse_single("HEAD:file")?.detach();
+        assert!(
+            !repo.has_object(head_blob),
+            "blobs are missing in the partial clone"
+        );
+
+        // This id was hashed from the worktree and is unknown to the remote, so fetching it would fail.
+        let worktree_blob = hash(b"changed in worktree\n")?;
+        shallow::prefetch_blobs_of_changes(
+            &repo,
+            [&modification(head_blob, worktree_blob)],
+            ChangesSource::Worktree,
+        );
+        assert!(
+            repo.has_object(head_blob),
+            "the blob on the tree side was fetched"
+        );
+        assert!(!repo.has_object(worktree_blob));
+        Ok(())
+    }
+
+    #[test]
+    fn both_sides_of_tree_changes_are_fetched_at_once() -> anyhow::Result<()> {
+        let (repo, _tmp) = writable_scenario_slow("shallow-partial-clone");
+        let head_blob = repo.rev_parse_single("HEAD:file")?.detach();
+        // The blob of the commit before the shallow boundary, which the remote still has.
+        let first_blob = hash(b"first\n")?;
+        assert!(!repo.has_object(head_blob) && !repo.has_object(first_blob));
+
+        shallow::prefetch_blobs_of_changes(
+            &repo,
+            [&modification(first_blob, head_blob)],
+            ChangesSource::Trees,
+        );
+        assert!(repo.has_object(head_blob) && repo.has_object(first_blob));
+        assert_eq!(
+            shallow::fetch_missing_objects(&repo, [head_blob, first_blob])?,
+            0,
+            "nothing is left to fetch"
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,113 @@
+use bstr::ByteSlice;
+use but_core::{
+    RepositoryExt,
+    signing::{sign_buffer, sign_commit, try_sign_commit},
+};
+use gix::objs::commit::SIGNATURE_FIELD_NAME;
+
+const SSH_SIGNATURE_START: &[u8] = b"-----BEGIN SSH SIGNATURE-----";
+
+fn head_commit(repo: &gix::Repository) -> anyhow::Result<gix::objs::Commit> {
+    Ok(repo.head_commit()?.decode()?.try_into()?)
+}
+
+fn signatures(commit: &gix::objs::Commit) -> Vec<&bstr::BStr> {
+    commit
+        .extra_headers
+        .iter()
+        .filter(|(name, _)| name == SIGNATURE_FIELD_NAME)
+        .map(|(_, value)| value.as_bstr())
+        .collect()
+}
+
+#[test]
+fn sign_buffer_with_ssh_key() -> anyhow::Result<()> {
+    let (repo, _tmp) = but_testsupport::writable_scenario_with_ssh_key("signing");
+    let signature = sign_buffer(&repo, b"content to sign")?;
+    assert!(
+        signature.starts_with(SSH_SIGNATURE_START),
+        "the format is configured in gpg.format: {signature}"
+    );
+    Ok(())
+}
+
+#[test]
+fn sign_commit_replaces_existing_signature() -> anyhow::Result<()> {
+    let (repo, _tmp) = but_testsupport::writable_scenario_with_ssh_key("signing");
+    let mut commit = head_commit(&repo)?;
+    commit
+        .extra_headers
+        .push((SIGNATURE_FIELD_NAME.into(), "outdated".into()));
+
+    sign_commit(&repo, &mut commit)?;
+    let signatures = signatures(&commit);
+    assert_eq!(signatures.len(), 1, "the outdated signature was replaced");
+    assert!(signatures[0].starts_with(SSH_SIGNATURE_START));
+    Ok(())
+}
+
+#[test]
+fn sign_commit_removes_signature_if_signing_is_disabled() -> anyhow::Result<()> {
+    let (mut repo, _tmp) = but_testsupport::writable_scenario_with_ssh_key("signing");
+    repo.config_snapshot_mut()
+        .set_raw_value(&"gitbutler.signCommits", "false")?;
+    let mut commit = head_commit(&repo)?;
+    commit
+        .extra_headers
+        .push((SIGNATURE_FIELD_NAME.into(), "outdated".into()));
+
+    sign_commit(&repo, &mut commit)?;
+    assert!(
+        signatures(&commit).is_empty(),
+        "a signature that doesn't match the content anymore is removed"
+    );
+    Ok(())
+}
+
+#[test]
+fn sign_commit_failure_disables_signing_in_the_repository() -> anyhow::Result<()> {
+    let (mut repo, _tmp) = but_testsupport::writable_scenario_with_ssh_key("signing");
+    repo.config_snapshot_mut()
+        .set_raw_value(&"user.signingKey", "/path/to/nonexisting.key")?;
+    let mut commit = head_commit(&repo)?;
+
+    let err = sign_commit(&repo, &mut commit).unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<but_error::Code>(),
+        Some(&but_error::Code::CommitSigningFailed)
+    );
+    assert!(signatures(&commit).is_empty());
+
+    let repo = gix::open(repo.git_dir())?;
+    assert_eq!(
+        repo.git_settings()?.gitbutler_sign_commits,
+        Some(false),
+        "signing is disabled locally to not fail over and over"
+    );
+    Ok(())
+}
+
+#[test]
+fn try_sign_commit_failure_leaves_the_commit_unsigned_and_signing_enabled() -> anyhow::Result<()> {
+    let (mut repo, _tmp) = but_testsupport::writable_scenario_with_ssh_key("signing");
+    repo.config_snapshot_mut()
+        .set_raw_value(&"user.signingKey", "/path/to/nonexisting.key")?;
+    let mut commit = head_commit(&repo)?;
+    commit
+        .extra_headers
+        .push((SIGNATURE_FIELD_NAME.into(), "outdated".into()));
+
+    try_sign_commit(&repo, &mut commit)?;
+    assert!(
+        signatures(&commit).is_empty(),
+        "the outdated signature is removed, and no new one could be added"
+    );
+
+    let repo = gix::open(repo.git_dir())?;
+    assert_eq!(
+        repo.git_settings()?.gitbutler_sign_commits,
+        Some(true),
+        "signing remains enabled for user commits"
+    );
+    Ok(())
+}
@@ -105,3 +105,209 @@ fn default_lock_scope_is_all_operations() {
     let default_scope = LockScope::default();
     assert!(matches!(default_scope, LockScope::AllOperations));
 }
+
+mod worktree_access {
+    use std::{sync::mpsc, time::Duration};
+
+    use but_core::sync::{shared_worktree_access, upgradable_worktree_access};
+    use but_testsupport::gix_testtools;
+
+    #[test]
+    fn upgradable_guards_share_access_with_readers_until_upgraded() -> anyhow::Result<()> {
+        let tmp = gix_testtools::tempfile::TempDir::new()?;
+        {
+            let _upgradable = upgradable_worktree_access(tmp.path());
+            let _reader = shared_worktree_access(tmp.path());
+            let _other_reader = shared_worktree_access(tmp.path());
+        }
+
+        let reader = shared_worktree_access(tmp.path());
+        let (upgraded_tx, upgraded_rx) = mpsc::channel();
+        let path = tmp.path().to_owned();
+        let upgrader = std::thread::spawn(move || {
+            let mut guard = upgradable_worktree_access(path);
+            let _perm = guard.upgrade();
+            upgraded_tx.send(()).expect("receiver is alive");
+        });
+        assert!(
+            upgraded_rx
+                .recv_timeout(Duration::from_millis(100))
+                .is_err(),
+            "upgrading waits for all readers to disappear"
+        );
+        drop(reader);
+        upgraded_rx.recv()?;
+        upgrader.join().expect("no panic");
+
+        let _reader = shared_worktree_access(tmp.path());
+        Ok(())
+    }
+}
+
+mod events {
+    use but_core::{
+        ref_metadata::StackId,
+        sync::events::{WorkspaceEvent, publish, subscribe},
+    };
+    use but_testsupport::gix_testtools;
+
+    #[test]
+    fn events_reach_all_subscribers_of_the_same_project() -> anyhow::Result<()> {
+        let tmp = gix_testtools::tempfile::TempDir::new()?;
+        let other = gix_testtools::tempfile::TempDir::new()?;
+        let event = WorkspaceEvent::StackUpdated {
+            stack_id: StackId::generate(),
+        };
+        publish(tmp.path(), WorkspaceEvent::AssignmentsChanged);
+
+        let first = subscribe(tmp.path());
+        let second = subscribe(tmp.path());
+        let unrelated = subscribe(other.path());
+        publish(tmp.path(), event.clone());
+        assert_eq!(
+            first.try_recv()?,
+            event,
+            "events before subscribing aren't seen"
+        );
+        assert_eq!(second.try_recv()?, event);
+        assert!(first.try_recv().is_err(), "each event is received once");
+        assert!(
+            unrelated.try_recv().is_err(),
+            "events are scoped by project"
+        );
+
+        drop(first);
+        publish(tmp.path(), WorkspaceEvent::AssignmentsChanged);
+        assert_eq!(
+            second.try_recv()?,
+            WorkspaceEvent::AssignmentsChanged,
+            "dropped subscribers don't affect the remaining ones"
+        );
+        Ok(())
+    }
+}
+
+mod queue {
+    use std::sync::{Arc, Mutex};
+
+    use but_core::sync::queue::{
+        OperationCategory, OperationInfo, OperationState, Priority, Progress, cancel, enqueue,
+        list, queued_exclusive_worktree_access, set_progress_listener,
+    };
+    use but_testsupport::gix_testtools;
+
+    #[test]
+    fn operations_run_by_priority_then_in_order() -> anyhow::Result<()> {
+        let tmp = gix_testtools::tempfile::TempDir::new()?;
+        let running = enqueue(tmp.path(), OperationCategory::Commit, Priority::Normal).wait()?;
+        let low = enqueue(tmp.path(), OperationCategory::Other, Priority::Low);
+        let first = enqueue(tmp.path(), OperationCategory::Rebase, Priority::Normal);
+        let second = enqueue(tmp.path(), OperationCategory::Push, Priority::Normal);
+        let high = enqueue(tmp.path(), OperationCategory::Integrate, Priority::High);
+
+        let ops = list(tmp.path());
+        assert_eq!(
+            ops.iter().map(|op| op.id).collect::<Vec<_>>(),
+            [running.id(), high.id(), first.id(), second.id(), low.id()]
+        );
+        assert_eq!(ops[0].state, OperationState::Running);
+        assert!(ops[1..].iter().all(|op| op.state == OperationState::Queued));
+
+        running.set_progress(1, Some(2), Some("halfway".into()));
+        let progress = list(tmp.path())[0].progress.clone().expect("set");
+        assert_eq!(
+            (progress.completed, progress.total, progress.percentage),
+            (1, Some(2), Some(50))
+        );
+
+        drop(running);
+        let high = high.wait()?;
+        assert_eq!(list(tmp.path())[0].id, high.id());
+        Ok(())
+    }
+
+    #[test]
+    fn queued_operations_can_be_cancelled() -> anyhow::Result<()> {
+        let tmp = gix_testtools::tempfile::TempDir::new()?;
+        let running = enqueue(tmp.path(), OperationCategory::Commit, Priority::Normal).wait()?;
+        let queued = enqueue(tmp.path(), OperationCategory::Push, Priority::Normal);
+
+        let queued_id = queued.id();
+        let waiter = std::thread::spawn(move || queued.wait().map(|_| ()));
+        assert!(cancel(tmp.path(), queued_id)?);
+        let err = waiter.join().expect("no panic").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!("Operation {queued_id} was cancelled before it started")
+        );
+        assert!(!cancel(tmp.path(), queued_id)?, "it's gone now");
+        assert_eq!(list(tmp.path()).len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn running_operations_are_cancelled_at_safe_points() -> anyhow::Result<()> {
+        let tmp = gix_testtools::tempfile::TempDir::new()?;
+        let running = enqueue(tmp.path(), OperationCategory::Fetch, Priority::Normal).wait()?;
+        let progress = running.progress();
+        progress.check_cancelled()?;
+
+        let reported = Arc::new(Mutex::new(Vec::new()));
+        set_progress_listener(tmp.path(), {
+            let reported = reported.clone();
+            move |op: &OperationInfo| reported.lock().unwrap().push(op.progress.clone())
+        });
+        progress.set_progress(1, Some(4), Some("fetching origin".into()));
+        assert_eq!(
+            reported.lock().unwrap().as_slice(),
+            [Some(Progress {
+                completed: 1,
+                total: Some(4),
+                message: Some("fetching origin".into()),
+                percentage: Some(25),
+            })]
+        );
+
+        assert!(cancel(tmp.path(), running.id())?);
+        assert!(progress.cancellation_token().is_cancelled());
+        assert_eq!(
+            progress.check_cancelled().unwrap_err().to_string(),
+            format!("Operation {} was cancelled", running.id())
+        );
+        assert_eq!(
+            list(tmp.path()).len(),
+            1,
+            "the operation keeps running until it stops by itself"
+        );
+        drop(running);
+        assert!(list(tmp.path()).is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn waiting_operations_run_once_the_previous_one_is_done() -> anyhow::Result<()> {
+        let tmp = gix_testtools::tempfile::TempDir::new()?;
+        let mut guard = queued_exclusive_worktree_access(
+            tmp.path(),
+            OperationCategory::Rebase,
+            Priority::Normal,
+        )?;
+        let _perm = guard.write_permission();
+
+        let path = tmp.path().to_owned();
+        let waiter = std::thread::spawn(move || {
+            queued_exclusive_worktree_access(path, OperationCategory::Commit, Priority::Normal)
+                .map(|_| ())
+        });
+        while list(tmp.path()).len() != 2 {
+            std::thread::yield_now();
+        }
+        drop(guard);
+        waiter.join().expect("no panic")?;
+        assert!(
+            list(tmp.path()).is_empty(),
+            "finished operations are removed"
+        );
+        Ok(())
+    }
+}
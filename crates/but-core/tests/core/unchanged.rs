@@ -0,0 +1,43 @@
+use but_core::{diff::worktree_changes, unchanged::set_skip_worktree};
+use but_testsupport::writable_scenario;
+
+#[test]
+fn changes_to_marked_files_are_hidden_until_unmarked() -> anyhow::Result<()> {
+    let (repo, _tmp) = writable_scenario("marked-unchanged");
+
+    let actual = worktree_changes(&repo)?;
+    assert_eq!(
+        actual
+            .changes
+            .iter()
+            .map(|change| change.path.to_string())
+            .collect::<Vec<_>>(),
+        ["regular"],
+        "changes to files with the skip-worktree or assume-unchanged bit are hidden"
+    );
+    set_skip_worktree(&repo, "local.conf".into(), false)?;
+    let actual = worktree_changes(&repo)?;
+    assert_eq!(
+        actual
+            .changes
+            .iter()
+            .map(|change| change.path.to_string())
+            .collect::<Vec<_>>(),
+        ["local.conf", "regular"]
+    );
+
+    set_skip_worktree(&repo, "regular".into(), true)?;
+    let actual = worktree_changes(&repo)?;
+    assert_eq!(
+        actual
+            .changes
+            .iter()
+            .map(|change| change.path.to_string())
+            .collect::<Vec<_>>(),
+        ["local.conf"]
+    );
+
+    let err = set_skip_worktree(&repo, "untracked".into(), true).unwrap_err();
+    assert!(err.to_string().contains("isn't a tracked file"));
+    Ok(())
+}
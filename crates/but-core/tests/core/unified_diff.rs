@@ -1,4 +1,8 @@
-use but_core::{ChangeState, UnifiedPatch, unified_diff};
+use but_core::{
+    ChangeState, UnifiedPatch,
+    diff::ui::{DISPLAY_ENCODING_KEY, DisplayEncodings},
+    unified_diff,
+};
 use gix::object::tree::EntryKind;
 
 #[test]
@@ -149,7 +153,10 @@ fn big_file_20_in_worktree() -> anyhow::Result<()> {
     )?
     .expect("present");
     match actual {
-        UnifiedPatch::Binary | UnifiedPatch::Patch { .. } => {
+        UnifiedPatch::Binary
+        | UnifiedPatch::Patch { .. }
+        | UnifiedPatch::Submodule { .. }
+        | UnifiedPatch::LfsPointer { .. } => {
             unreachable!("Should be considered too large")
         }
         UnifiedPatch::TooLarge { size_in_bytes } => {
@@ -178,7 +185,10 @@ fn binary_file_in_worktree() -> anyhow::Result<()> {
     )?
     .expect("present");
     match actual {
-        UnifiedPatch::TooLarge { .. } | UnifiedPatch::Patch { .. } => {
+        UnifiedPatch::TooLarge { .. }
+        | UnifiedPatch::Patch { .. }
+        | UnifiedPatch::Submodule { .. }
+        | UnifiedPatch::LfsPointer { .. } => {
             unreachable!("Should be considered binary, but was {actual:?}");
         }
         UnifiedPatch::Binary => {
@@ -188,6 +198,51 @@ fn binary_file_in_worktree() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test]
+fn whitespace_changes_are_ignored_if_configured() -> anyhow::Result<()> {
+    let mut repo = crate::diff::worktree_changes::repo("whitespace-modified-in-worktree")?;
+    let compute = |repo: &gix::Repository| {
+        UnifiedPatch::compute(
+            repo,
+            "file.rs".into(),
+            None,
+            ChangeState {
+                id: repo.object_hash().null(),
+                kind: EntryKind::Blob,
+            },
+            ChangeState {
+                id: repo.rev_parse_single("@:file.rs")?.into(),
+                kind: EntryKind::Blob,
+            },
+            1,
+        )
+    };
+    let actual = extract_patch(compute(&repo)?);
+    assert_eq!(actual.len(), 1);
+    assert_eq!(
+        (actual[0].old_lines, actual[0].new_lines),
+        (5, 5),
+        "by default, re-indented lines are changed"
+    );
+
+    repo.config_snapshot_mut().set_raw_value(
+        &unified_diff::IgnoreWhitespace::CONFIG_KEY,
+        unified_diff::IgnoreWhitespace::Change.as_config_value(),
+    )?;
+    let actual = extract_patch(compute(&repo)?);
+    insta::assert_debug_snapshot!(actual, @r#"
+    [
+        DiffHunk("@@ -3,3 +3,3 @@
+             let  b = 2;
+        -    println!("{a}");
+        +    println!("{b}");
+         }
+        "),
+    ]
+    "#);
+    Ok(())
+}
+
 #[test]
 #[cfg(unix)]
 fn symlink_modified_in_worktree() -> anyhow::Result<()> {
@@ -246,16 +301,177 @@ fn submodule_added() -> anyhow::Result<()> {
         },
     ]
     "#);
-    assert!(
-        changes[1].unified_patch(&repo, 3)?.is_none(),
-        "submodules produce no diffs"
+    insta::assert_debug_snapshot!(changes[1].unified_patch(&repo, 3)?, @r"
+    Some(
+        Submodule {
+            previous_id: None,
+            id: Some(
+                Sha1(e95516bd2f49a83a6cdb98cfec40b2717fbc2c1b),
+            ),
+        },
+    )
+    ");
+    insta::assert_snapshot!(changes[1].unified_diff(&repo, 3)?.expect("submodules have a diff"), @r"
+    --- /dev/null
+    +++ b/submodule
+    @@ -0,0 +1 @@
+    +Subproject commit e95516bd2f49a83a6cdb98cfec40b2717fbc2c1b
+    ");
+    Ok(())
+}
+
+#[test]
+fn lfs_pointer_modified() -> anyhow::Result<()> {
+    let repo = crate::diff::worktree_changes::repo("lfs-pointer-modified")?;
+    let changes = but_core::diff::worktree_changes(&repo)?.changes;
+    assert_eq!(changes.len(), 1, "only the pointer file changed");
+    insta::assert_debug_snapshot!(changes[0].unified_patch(&repo, 3)?, @r#"
+    Some(
+        LfsPointer {
+            previous: Some(
+                LfsPointer {
+                    oid: "4d7a214614ab2935c943f9e0ff69d22eadbb8f32b1258daaa5e2ca24d17e2393",
+                    size: 12345,
+                },
+            ),
+            current: Some(
+                LfsPointer {
+                    oid: "d2a84f4b8b650937ec8f73cd8be2c74add5a911ba64df27458ed8229da804a26",
+                    size: 54321,
+                },
+            ),
+        },
+    )
+    "#);
+    Ok(())
+}
+
+#[test]
+fn lfs_pointer_roundtrip() {
+    let pointer = but_core::lfs::LfsPointer::for_content(b"hello");
+    assert_eq!(pointer.size, 5);
+    assert_eq!(
+        pointer.oid,
+        "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
+    );
+    assert_eq!(
+        but_core::lfs::LfsPointer::from_bytes(&pointer.to_bytes()),
+        Some(pointer)
+    );
+    assert_eq!(
+        but_core::lfs::LfsPointer::from_bytes(b"hello"),
+        None,
+        "content isn't a pointer"
+    );
+}
+
+#[test]
+fn word_diff() {
+    let hunk = unified_diff::DiffHunk {
+        old_start: 1,
+        old_lines: 3,
+        new_start: 1,
+        new_lines: 3,
+        diff: "@@ -1,3 +1,3 @@\n context\n-let value = foo(1);\n+let value = bar(1, 2);\n-removed\n end\n".into(),
+        encoding: None,
+        word_diff: None,
+    };
+    let span = |start, end| unified_diff::WordSpan { start, end };
+    assert_eq!(
+        hunk.compute_word_diff(),
+        [
+            vec![],
+            vec![span(12, 15)],
+            vec![span(12, 15), span(17, 20)],
+            vec![],
+            vec![],
+        ],
+        "only changed words are marked, and lines without counterpart or context have no spans"
+    );
+}
+
+#[test]
+fn content_is_decoded_for_display_as_configured() -> anyhow::Result<()> {
+    let mut repo =
+        crate::diff::worktree_changes::repo("added-modified-in-worktree")?.with_object_memory();
+    // "café" and "crème" in latin-1, which is too little to detect the encoding reliably.
+    let previous = repo.write_blob(b"caf\xe9\n")?.detach();
+    let current = repo.write_blob(b"cr\xe8me\n")?.detach();
+    let compute = |repo: &gix::Repository| {
+        UnifiedPatch::compute(
+            repo,
+            "menu.txt".into(),
+            None,
+            ChangeState {
+                id: current,
+                kind: EntryKind::Blob,
+            },
+            ChangeState {
+                id: previous,
+                kind: EntryKind::Blob,
+            },
+            3,
+        )
+    };
+
+    repo.config_snapshot_mut()
+        .set_raw_value(&DISPLAY_ENCODING_KEY, "*.txt=latin1")?;
+    let actual = extract_patch(compute(&repo)?);
+    assert_eq!(actual.len(), 1);
+    assert_eq!(actual[0].diff, "@@ -1,1 +1,1 @@\n-café\n+crème\n");
+    assert_eq!(
+        actual[0].encoding.as_deref(),
+        Some("windows-1252"),
+        "hunks are tagged with the encoding they were decoded from"
+    );
+    assert_eq!(
+        (actual[0].old_lines, actual[0].new_lines),
+        (1, 1),
+        "line numbers refer to the original content"
+    );
+
+    repo.config_snapshot_mut()
+        .set_raw_value(&DISPLAY_ENCODING_KEY, "*.txt=latin1, menu.txt=utf-8")?;
+    let actual = extract_patch(compute(&repo)?);
+    assert_eq!(
+        actual[0].encoding, None,
+        "the last matching pattern wins, and UTF-8 is shown as is"
+    );
+    Ok(())
+}
+
+#[test]
+fn display_encodings_match_paths_like_gitattributes() -> anyhow::Result<()> {
+    let mut repo = crate::diff::worktree_changes::repo("added-modified-in-worktree")?;
+    repo.config_snapshot_mut().set_raw_value(
+        &DISPLAY_ENCODING_KEY,
+        "*.txt=Shift_JIS, legacy/**=auto, invalid",
+    )?;
+    let encodings = DisplayEncodings::from_repo(&repo);
+    assert_eq!(
+        encodings.configured("dir/notes.txt".into()),
+        Some(encoding_rs::SHIFT_JIS),
+        "patterns without slash match the file name"
+    );
+    assert_eq!(encodings.configured("legacy/notes.txt".into()), None);
+    assert_eq!(encodings.configured("other.rs".into()), None);
+    assert_eq!(
+        encodings.encoding_for("other.rs".into(), &[b"plain ascii\n"]),
+        None,
+        "UTF-8 content is never decoded"
     );
     Ok(())
 }
 
 fn extract_patch(diff: Option<UnifiedPatch>) -> Vec<unified_diff::DiffHunk> {
     match diff {
-        None | Some(UnifiedPatch::Binary | UnifiedPatch::TooLarge { .. }) => {
+        None
+        | Some(
+            UnifiedPatch::Binary
+            | UnifiedPatch::TooLarge { .. }
+            | UnifiedPatch::Submodule { .. }
+            | UnifiedPatch::LfsPointer { .. },
+        ) => {
             unreachable!("should have patches")
         }
         Some(UnifiedPatch::Patch { hunks, .. }) => hunks,
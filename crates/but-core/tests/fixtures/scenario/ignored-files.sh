@@ -0,0 +1,27 @@
+#!/usr/bin/env bash
+
+### Description
+# Untracked files that are ignored by `.gitignore`, `.git/info/exclude` and `core.excludesFile`,
+# along with a negation and an ignored file that is tracked.
+set -eu -o pipefail
+
+git init
+cat >.gitignore <<'EOF2'
+target/
+*.log
+!keep.log
+EOF2
+echo "tracked" >tracked.log
+git add .gitignore && git add -f tracked.log && git commit -m "init"
+
+echo "scratch" >>.git/info/exclude
+echo "*.tmp" >../global-ignore
+git config core.excludesFile "$PWD/../global-ignore"
+
+mkdir -p target/debug
+echo "artifact" >target/debug/app
+echo "log" >debug.log
+echo "log" >keep.log
+echo "notes" >scratch
+echo "temp" >file.tmp
+echo "new" >untracked
@@ -0,0 +1,19 @@
+#!/usr/bin/env bash
+
+### Description
+# Modified tracked files with the `skip-worktree` and `assume-unchanged` bits set, along with one
+# modified file without any of them.
+set -eu -o pipefail
+
+git init
+echo "local" >local.conf
+echo "assumed" >assumed
+echo "regular" >regular
+git add . && git commit -m "init"
+
+git update-index --skip-worktree local.conf
+git update-index --assume-unchanged assumed
+
+echo "changed" >>local.conf
+echo "changed" >>assumed
+echo "changed" >>regular
@@ -0,0 +1,22 @@
+#!/usr/bin/env bash
+
+### Description
+# A repository that fetched only the latest commit of `remote`, without any blobs, like
+# `git clone --depth 1 --filter=blob:none` would, so it's a shallow and a partial clone.
+set -eu -o pipefail
+
+git init remote
+(cd remote
+  git config uploadpack.allowFilter true
+  echo "first" >file && git add . && git commit -m "first"
+  echo "second" >file && git commit -am "second"
+)
+
+git init
+git remote add origin "file://$PWD/remote"
+git config core.repositoryformatversion 1
+git config extensions.partialClone origin
+git config remote.origin.promisor true
+git config remote.origin.partialCloneFilter blob:none
+git fetch --depth 1 --filter=blob:none origin "$(git -C remote rev-parse HEAD)"
+git reset --soft FETCH_HEAD
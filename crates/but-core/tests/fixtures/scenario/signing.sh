@@ -0,0 +1,13 @@
+#!/usr/bin/env bash
+set -eu -o pipefail
+
+### Description
+# A repository with a single commit, along with an SSH key to sign commits with and signing enabled.
+ssh-keygen -t rsa -b 2048 -C "test@example.com" -N "" -f signature.key
+
+git init
+git config gpg.format ssh
+git config user.signingKey "$PWD/signature.key"
+git config gitbutler.signCommits true
+echo "*.key*" >.gitignore
+git add . && git commit -m "init"
@@ -0,0 +1,23 @@
+#!/usr/bin/env bash
+
+### Description
+# A `feature` branch with two commits that was squash-merged into `main` after `main` moved on,
+# with `other` changing the same file differently.
+set -eu -o pipefail
+
+git init
+seq 10 >file
+echo "unrelated" >unrelated
+git add . && git commit -m "init"
+
+git branch other
+git checkout -b feature
+seq 0 10 >file && git commit -am "prepend 0"
+echo "new" >new && git add new && git commit -m "add new"
+
+git checkout main
+echo "changed" >>unrelated && git commit -am "main moves on"
+seq 0 10 >file && echo "new" >new && git add . && git commit -m "squash-merge feature"
+
+git checkout other
+seq 11 >file && git commit -am "append 11"
@@ -17,3 +17,19 @@ git init -q non-cone
   git sparse-checkout set c1/c2 --sparse-index
 )
 
+
+git init -q cone
+(cd cone
+  touch a b
+  mkdir c1
+  (cd c1 && touch a b && mkdir c2 && cd c2 && touch a b)
+  mkdir d
+  (cd d && touch a b)
+
+  git add .
+  git commit -m "init"
+
+  git sparse-checkout set c1/c2 --cone --no-sparse-index
+  echo change >c1/c2/a
+  mkdir d && echo untracked >d/untracked
+)
@@ -260,6 +260,13 @@ cp -Rv case-folding-worktree-changes case-folding-worktree-and-index-changes
 EOF
 )
 
+git init case-only-rename
+(cd case-only-rename
+  git config core.ignorecase true
+  echo content >Foo.rs && git add . && git commit -m "init"
+  mv Foo.rs foo.rs
+)
+
 git init conflicting
 (cd conflicting
   touch unrelated && git add . && git commit -m "init"
@@ -326,3 +333,18 @@ git init non-utf8-encodings
 (cd non-utf8-encodings
   printf '\x80\xc4\xc0' > windows1252
 )
+
+git init lfs-pointer-modified
+(cd lfs-pointer-modified
+  echo "*.bin filter=lfs diff=lfs merge=lfs -text" >.gitattributes
+  printf 'version https://git-lfs.github.com/spec/v1\noid sha256:4d7a214614ab2935c943f9e0ff69d22eadbb8f32b1258daaa5e2ca24d17e2393\nsize 12345\n' >file.bin
+  git add . && git commit -m "init"
+  printf 'version https://git-lfs.github.com/spec/v1\noid sha256:d2a84f4b8b650937ec8f73cd8be2c74add5a911ba64df27458ed8229da804a26\nsize 54321\n' >file.bin
+)
+
+git init whitespace-modified-in-worktree
+(cd whitespace-modified-in-worktree
+  printf 'fn main() {\n    let a = 1;\n    let b = 2;\n    println!("{a}");\n}\n' >file.rs
+  git add . && git commit -m "init"
+  printf 'fn main() {\n\tlet a = 1;\n    let  b = 2;\n    println!("{b}");\n}\n' >file.rs
+)
@@ -1,8 +1,12 @@
 use crate::Context;
 use but_core::sync::LockScope::AllOperations;
 pub use but_core::sync::{
-    LockFile, WorkspaceReadGuard, WorkspaceWriteGuard, WorktreeReadPermission,
-    WorktreeWritePermission,
+    LockFile, WorkspaceReadGuard, WorkspaceUpgradableGuard, WorkspaceWriteGuard,
+    WorktreeReadPermission, WorktreeWritePermission,
+    queue::{
+        CancellationToken, OperationCategory, OperationId, OperationInfo, Priority, ProgressHandle,
+        QueuedOperation, QueuedWorkspaceWriteGuard, RunningOperation,
+    },
 };
 
 /// Locking utilities to protect against concurrency on the same repo.
@@ -32,4 +36,55 @@ impl Context {
     pub fn shared_worktree_access(&self) -> but_core::sync::WorkspaceReadGuard {
         but_core::sync::shared_worktree_access(&self.gitdir)
     }
+
+    /// Return a guard for shared (read) worktree access that can be upgraded to exclusive access
+    /// once it turns out that a write is needed, and block while waiting for writers to disappear.
+    ///
+    /// Prefer it over [`Self::exclusive_worktree_access()`] for operations that only write sometimes,
+    /// so readers can proceed in parallel otherwise.
+    pub fn upgradable_worktree_access(&self) -> but_core::sync::WorkspaceUpgradableGuard {
+        but_core::sync::upgradable_worktree_access(&self.gitdir)
+    }
+
+    /// Enqueue an operation of `category` with `priority`, block until it's its turn, and return a guard
+    /// for exclusive worktree access which also keeps the operation running until it's dropped.
+    ///
+    /// Use this instead of [`Self::exclusive_worktree_access()`] for user-visible mutations so they show up
+    /// in [`Self::queued_operations()`] and can be cancelled while they wait.
+    pub fn queued_exclusive_worktree_access(
+        &self,
+        category: OperationCategory,
+        priority: Priority,
+    ) -> anyhow::Result<QueuedWorkspaceWriteGuard> {
+        but_core::sync::queue::queued_exclusive_worktree_access(&self.gitdir, category, priority)
+    }
+
+    /// Enqueue an operation of `category` with `priority` without taking any worktree lock once it runs.
+    /// This is useful for operations that mostly talk to remotes, like pushing.
+    pub fn enqueue_operation(
+        &self,
+        category: OperationCategory,
+        priority: Priority,
+    ) -> QueuedOperation {
+        but_core::sync::queue::enqueue(&self.gitdir, category, priority)
+    }
+
+    /// List the running and queued operations of this project, the running one first.
+    pub fn queued_operations(&self) -> Vec<OperationInfo> {
+        but_core::sync::queue::list(&self.gitdir)
+    }
+
+    /// Cancel the queued or running operation with `id`, returning `false` if it doesn't exist (anymore).
+    /// Running operations stop at the next point where it's safe to do so.
+    pub fn cancel_queued_operation(&self, id: OperationId) -> anyhow::Result<bool> {
+        but_core::sync::queue::cancel(&self.gitdir, id)
+    }
+
+    /// Call `listener` with the operation whenever one of the operations of this project reports progress.
+    pub fn set_operation_progress_listener(
+        &self,
+        listener: impl Fn(&OperationInfo) + Send + Sync + 'static,
+    ) {
+        but_core::sync::queue::set_progress_listener(&self.gitdir, listener)
+    }
 }
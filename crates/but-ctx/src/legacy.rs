@@ -20,17 +20,20 @@ pub(crate) mod types {
 /// Legacy Lifecycle
 impl Context {
     /// Open the repository identified by `legacy_project` and `settings`.
+    ///
+    /// The overrides of the repository are merged into `settings`, unless they are invalid.
     pub fn new_from_legacy_project_and_settings(
         legacy_project: &gitbutler_project::Project,
         settings: AppSettings,
     ) -> Self {
         let gitdir = legacy_project.git_dir().to_owned();
+        let settings = settings.with_project_overrides_or_unchanged(&gitdir);
         let app_cache_dir = but_path::app_cache_dir().ok();
         Context {
-            settings,
             gitdir: gitdir.clone(),
             legacy_project: legacy_project.clone(),
-            repo: new_ondemand_repo(gitdir.clone()),
+            repo: new_ondemand_repo(gitdir.clone(), &settings),
+            settings,
             git2_repo: new_ondemand_git2_repo(gitdir.clone()),
             db: new_ondemand_db(gitdir),
             app_cache: new_ondemand_app_cache(app_cache_dir.clone()),
@@ -43,12 +46,13 @@ impl Context {
         legacy_project: gitbutler_project::Project,
     ) -> anyhow::Result<Self> {
         let gitdir = legacy_project.git_dir().to_owned();
+        let settings = app_settings(but_path::app_config_dir()?, &gitdir)?;
         let app_cache_dir = but_path::app_cache_dir().ok();
         Ok(Context {
-            settings: app_settings(but_path::app_config_dir()?)?,
             gitdir: gitdir.clone(),
             legacy_project,
-            repo: new_ondemand_repo(gitdir.clone()),
+            repo: new_ondemand_repo(gitdir.clone(), &settings),
+            settings,
             git2_repo: new_ondemand_git2_repo(gitdir.clone()),
             db: new_ondemand_db(gitdir),
             app_cache: new_ondemand_app_cache(app_cache_dir.clone()),
@@ -61,12 +65,13 @@ impl Context {
     pub fn new_from_legacy_project_id(project_id: LegacyProjectId) -> anyhow::Result<Self> {
         let legacy_project = gitbutler_project::get(project_id)?;
         let gitdir = legacy_project.git_dir().to_owned();
+        let settings = app_settings(but_path::app_config_dir()?, &gitdir)?;
         let app_cache_dir = but_path::app_cache_dir().ok();
         Ok(Context {
-            settings: app_settings(but_path::app_config_dir()?)?,
             gitdir: gitdir.clone(),
             legacy_project,
-            repo: new_ondemand_repo(gitdir.clone()),
+            repo: new_ondemand_repo(gitdir.clone(), &settings),
+            settings,
             git2_repo: new_ondemand_git2_repo(gitdir.clone()),
             db: new_ondemand_db(gitdir),
             app_cache: new_ondemand_app_cache(app_cache_dir.clone()),
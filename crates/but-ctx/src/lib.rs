@@ -100,7 +100,7 @@ impl From<ThreadSafeContext> for Context {
             #[cfg(feature = "legacy")]
             legacy_project,
         } = value;
-        let mut ondemand = new_ondemand_repo(gitdir.clone());
+        let mut ondemand = new_ondemand_repo(gitdir.clone(), &settings);
         if let Some(repo) = repo {
             ondemand.assign(repo.to_thread_local());
         }
@@ -145,13 +145,13 @@ impl Context {
         app_cache_dir: Option<PathBuf>,
     ) -> anyhow::Result<Context> {
         let gitdir = gitdir.into();
-        let settings = app_settings(app_config_dir)?;
+        let settings = app_settings(app_config_dir, &gitdir)?;
         #[cfg(not(feature = "legacy"))]
         {
             Ok(Context {
                 gitdir: gitdir.clone(),
+                repo: new_ondemand_repo(gitdir.clone(), &settings),
                 settings,
-                repo: new_ondemand_repo(gitdir.clone()),
                 git2_repo: new_ondemand_git2_repo(gitdir.clone()),
                 db: new_ondemand_db(gitdir),
                 app_cache: new_ondemand_app_cache(app_cache_dir.clone()),
@@ -168,10 +168,10 @@ impl Context {
             let legacy_project = LegacyProject::find_by_worktree_dir(worktree_dir)
                 .unwrap_or_else(|_| default_legacy_project_at_repo(&repo));
             Ok(Context {
-                settings,
                 gitdir: gitdir.clone(),
                 legacy_project,
-                repo: new_ondemand_repo(gitdir.clone()),
+                repo: new_ondemand_repo(gitdir.clone(), &settings),
+                settings,
                 git2_repo: new_ondemand_git2_repo(gitdir.clone()),
                 db: new_ondemand_db(gitdir),
                 app_cache: new_ondemand_app_cache(app_cache_dir.clone()),
@@ -185,6 +185,7 @@ impl Context {
     pub fn discover(directory: impl AsRef<Path>) -> anyhow::Result<Context> {
         let directory = directory.as_ref();
         let repo = gix::discover(directory)?;
+        let settings = app_settings(but_path::app_config_dir()?, repo.git_dir())?;
         let app_cache_dir = but_path::app_cache_dir().ok();
         #[cfg(feature = "legacy")]
         {
@@ -196,10 +197,10 @@ impl Context {
                 .unwrap_or_else(|_| default_legacy_project_at_repo(&repo));
             let gitdir = repo.git_dir().to_owned();
             Ok(Context {
-                settings: app_settings(but_path::app_config_dir()?)?,
                 gitdir: gitdir.clone(),
                 legacy_project,
-                repo: new_ondemand_repo(gitdir.clone()),
+                repo: new_ondemand_repo(gitdir.clone(), &settings),
+                settings,
                 git2_repo: new_ondemand_git2_repo(gitdir.clone()),
                 db: new_ondemand_db(gitdir),
                 app_cache: new_ondemand_app_cache(app_cache_dir.clone()),
@@ -213,8 +214,8 @@ impl Context {
             let gitdir = repo.git_dir().to_owned();
             Ok(crate::Context {
                 gitdir: gitdir.clone(),
-                settings: app_settings(but_path::app_config_dir()?)?,
-                repo: new_ondemand_repo(gitdir.clone()),
+                repo: new_ondemand_repo(gitdir.clone(), &settings),
+                settings,
                 git2_repo: new_ondemand_git2_repo(gitdir.clone()),
                 db: new_ondemand_db(gitdir),
                 app_cache: new_ondemand_app_cache(app_cache_dir.clone()),
@@ -229,15 +230,15 @@ impl Context {
     /// **Note that it does not have support for legacy projects to encourage single-branch compatible code.**
     pub fn from_repo(repo: gix::Repository) -> anyhow::Result<Context> {
         let gitdir = repo.git_dir().to_owned();
-        let settings = app_settings(but_path::app_config_dir()?)?;
+        let settings = app_settings(but_path::app_config_dir()?, &gitdir)?;
         let app_cache_dir = but_path::app_cache_dir().ok();
 
         Ok(Context {
             #[cfg(feature = "legacy")]
             legacy_project: default_legacy_project_at_repo(&repo),
             gitdir: gitdir.clone(),
+            repo: new_ondemand_repo(gitdir.clone(), &settings),
             settings,
-            repo: new_ondemand_repo(gitdir.clone()),
             git2_repo: new_ondemand_git2_repo(gitdir.clone()),
             db: new_ondemand_db(gitdir),
             app_cache: new_ondemand_app_cache(app_cache_dir.clone()),
@@ -253,7 +254,10 @@ impl Context {
     }
 
     /// Use `repo` instead of the default repository that would be opened on first query.
-    pub fn with_repo(mut self, repo: gix::Repository) -> Self {
+    ///
+    /// Note that the diff settings are applied to its configuration.
+    pub fn with_repo(mut self, mut repo: gix::Repository) -> Self {
+        apply_diff_settings(&mut repo, &self.settings.diff);
         self.repo.assign(repo);
         self
     }
@@ -444,8 +448,29 @@ fn project_data_dir(gitdir: &Path) -> PathBuf {
     gitdir.join("gitbutler")
 }
 
-fn new_ondemand_repo(gitdir: PathBuf) -> OnDemand<gix::Repository> {
-    OnDemand::new(move || gix::open(&gitdir).map_err(Into::into))
+fn new_ondemand_repo(gitdir: PathBuf, settings: &AppSettings) -> OnDemand<gix::Repository> {
+    let diff = settings.diff.clone();
+    OnDemand::new(move || {
+        let mut repo = gix::open(&gitdir)?;
+        apply_diff_settings(&mut repo, &diff);
+        Ok(repo)
+    })
+}
+
+/// Make the diff `settings` part of the in-memory configuration of `repo`, so every diff performed with it uses them.
+fn apply_diff_settings(repo: &mut gix::Repository, settings: &but_settings::app_settings::Diff) {
+    let mut config = repo.config_snapshot_mut();
+    if !settings.algorithm.is_empty() {
+        config
+            .set_raw_value(&"diff.algorithm", settings.algorithm.as_str())
+            .expect("BUG: the key is valid");
+    }
+    config
+        .set_raw_value(
+            &but_core::unified_diff::IgnoreWhitespace::CONFIG_KEY,
+            settings.ignore_whitespace.as_str(),
+        )
+        .expect("BUG: the key is valid");
 }
 
 fn new_ondemand_git2_repo(gitdir: PathBuf) -> OnDemand<git2::Repository> {
@@ -463,11 +488,14 @@ fn new_ondemand_app_cache(cache_dir: Option<PathBuf>) -> OnDemandCache<but_db::A
     OnDemandCache::new(move || but_db::AppCacheHandle::new_in_directory(cache_dir.clone()))
 }
 
-fn app_settings(config_dir: impl AsRef<Path>) -> anyhow::Result<AppSettings> {
-    AppSettings::load(
+/// Load the application settings from `config_dir`, with the overrides of the repository at `gitdir` merged in
+/// unless they are invalid.
+fn app_settings(config_dir: impl AsRef<Path>, gitdir: &Path) -> anyhow::Result<AppSettings> {
+    Ok(AppSettings::load(
         &AppSettings::default_settings_path(config_dir.as_ref()),
         None,
-    )
+    )?
+    .with_project_overrides_or_unchanged(gitdir))
 }
 
 #[cfg(feature = "legacy")]
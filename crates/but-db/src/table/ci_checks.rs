@@ -3,9 +3,10 @@ use serde::{Deserialize, Serialize};
 use crate::Transaction;
 use crate::{DbHandle, M};
 
-pub(crate) const M: &[M<'static>] = &[M::up(
-    20260105095934,
-    "CREATE TABLE `ci_checks`(
+pub(crate) const M: &[M<'static>] = &[
+    M::up(
+        20260105095934,
+        "CREATE TABLE `ci_checks`(
 	`id` BIGINT NOT NULL PRIMARY KEY,
 	`name` TEXT NOT NULL,
 	`output_summary` TEXT NOT NULL,
@@ -26,7 +27,15 @@ pub(crate) const M: &[M<'static>] = &[M::up(
 );
 
 CREATE INDEX `idx_ci_checks_reference` ON `ci_checks`(`reference`);",
-)];
+    ),
+    M::up(
+        20261016093512,
+        "CREATE TABLE `ci_check_etags`(
+	`reference` TEXT NOT NULL PRIMARY KEY,
+	`etag` TEXT NOT NULL
+);",
+    ),
+];
 
 /// Tests are in `but-db/tests/db/table/ci_check.rs`.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -129,6 +138,15 @@ impl CiChecksHandle<'_> {
 
         results.collect::<Result<Vec<_>, _>>()
     }
+
+    /// Returns the `ETag` of the response the checks of `ref_name` were last obtained from, if known.
+    pub fn etag_for_reference(&self, ref_name: &str) -> rusqlite::Result<Option<String>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT etag FROM ci_check_etags WHERE reference = ?1")?;
+        let mut rows = stmt.query_map([ref_name], |row| row.get(0))?;
+        rows.next().transpose()
+    }
 }
 
 impl CiChecksHandleMut<'_> {
@@ -185,10 +203,38 @@ impl CiChecksHandleMut<'_> {
         Ok(())
     }
 
-    /// Deletes all CI check entries for a specific reference.
+    /// Sets the `ETag` of the response the checks of `ref_name` were obtained from, or forgets it if `None`.
+    ///
+    /// Consumes this handle because it commits the internal savepoint/transaction.
+    pub fn set_etag_for_reference(
+        self,
+        ref_name: &str,
+        etag: Option<&str>,
+    ) -> rusqlite::Result<()> {
+        let sp = self.sp;
+        match etag {
+            Some(etag) => sp.execute(
+                "INSERT INTO ci_check_etags (reference, etag) VALUES (?1, ?2)
+                 ON CONFLICT(reference) DO UPDATE SET etag = excluded.etag",
+                [ref_name, etag],
+            )?,
+            None => sp.execute(
+                "DELETE FROM ci_check_etags WHERE reference = ?1",
+                [ref_name],
+            )?,
+        };
+        sp.commit()?;
+        Ok(())
+    }
+
+    /// Deletes all CI check entries and the `ETag` for a specific reference.
     pub fn delete_for_reference(self, ref_name: &str) -> rusqlite::Result<()> {
         self.sp
             .execute("DELETE FROM ci_checks WHERE reference = ?1", [ref_name])?;
+        self.sp.execute(
+            "DELETE FROM ci_check_etags WHERE reference = ?1",
+            [ref_name],
+        )?;
         self.sp.commit()?;
         Ok(())
     }
@@ -150,6 +150,12 @@ mod run {
         	`error` TEXT
         , `external_summary` TEXT NOT NULL, `external_prompt` TEXT, `source` TEXT);
 
+        -- table ci_check_etags
+        CREATE TABLE `ci_check_etags`(
+        	`reference` TEXT NOT NULL PRIMARY KEY,
+        	`etag` TEXT NOT NULL
+        );
+
         -- table ci_checks
         CREATE TABLE `ci_checks`(
         	`id` BIGINT NOT NULL PRIMARY KEY,
@@ -315,6 +321,7 @@ mod run {
         Text("20251110103940")
         Text("20260101223932")
         Text("20260105095934")
+        Text("20261016093512")
 
         Table: hunk_assignments
         hunk_header | path | path_bytes | stack_id | id
@@ -348,6 +355,9 @@ mod run {
 
         Table: ci_checks
         id | name | output_summary | output_text | output_title | started_at | status_type | status_conclusion | status_completed_at | head_sha | url | html_url | details_url | pull_requests | reference | last_sync_at | struct_version
+
+        Table: ci_check_etags
+        reference | etag
         "#);
 
         let count = migration::run(&mut db, but_db::migration::ours())?;
@@ -204,6 +204,39 @@ fn delete_for_reference() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test]
+fn etags() -> anyhow::Result<()> {
+    let mut db = in_memory_db();
+    assert_eq!(db.ci_checks().etag_for_reference("refs/heads/main")?, None);
+
+    db.ci_checks_mut()?
+        .set_etag_for_reference("refs/heads/main", Some("\"first\""))?;
+    db.ci_checks_mut()?
+        .set_etag_for_reference("refs/heads/main", Some("\"second\""))?;
+    db.ci_checks_mut()?
+        .set_etag_for_reference("refs/heads/feature", Some("\"feature\""))?;
+    assert_eq!(
+        db.ci_checks()
+            .etag_for_reference("refs/heads/main")?
+            .as_deref(),
+        Some("\"second\""),
+        "setting an ETag replaces the previous one"
+    );
+
+    db.ci_checks_mut()?
+        .set_etag_for_reference("refs/heads/main", None)?;
+    assert_eq!(db.ci_checks().etag_for_reference("refs/heads/main")?, None);
+
+    db.ci_checks_mut()?
+        .delete_for_reference("refs/heads/feature")?;
+    assert_eq!(
+        db.ci_checks().etag_for_reference("refs/heads/feature")?,
+        None,
+        "deleting the checks of a reference also forgets its ETag"
+    );
+    Ok(())
+}
+
 fn ci_check(id: i64, ref_name: &str) -> CiCheck {
     CiCheck {
         id,
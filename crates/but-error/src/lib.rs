@@ -96,6 +96,28 @@
 //!            "now the added context just looks like an error, even though it also contains a `Code` which can be queried");
 //! ```
 //!
+//! #### Structured errors
+//!
+//! When the frontend has to do more than showing a message, like offering to resolve conflicts or to
+//! refresh the view, a [`ButError`] can be returned or attached as context instead. It provides a [`Code`]
+//! along with the facts the frontend needs, and a [hint](Recovery) on how the user may recover from it.
+//!
+//! ```rust
+//!# use anyhow::{Result, Context};
+//!# use but_error::{AnyhowContextExt, ButError, Code, Recovery};
+//!
+//! fn find_stack(id: &str) -> Result<()> {
+//!     Err(ButError::StackNotFound { stack_id: id.into() }.into())
+//! }
+//!
+//! let err = find_stack("42").context("couldn't move commit").unwrap_err();
+//! let but_err = err.but_error().expect("found anywhere in the chain");
+//! assert_eq!(but_err.code(), Code::BranchNotFound);
+//! assert_eq!(but_err.recovery(), Recovery::Refresh);
+//! assert_eq!(err.custom_context().and_then(|ctx| ctx.message).as_deref(),
+//!            Some("The stack 42 doesn't exist in the workspace anymore"));
+//! ```
+//!
 //! ### Backtraces and `anyhow`
 //!
 //! Backtraces are automatically collected when `anyhow` errors are instantiated, as long as the
@@ -109,6 +131,8 @@
 //! By default, `thiserror` instances have no context.
 use std::{borrow::Cow, fmt::Debug};
 
+use serde::Serialize;
+
 /// A unique code that consumers of the API may rely on to identify errors.
 ///
 /// ### Important
@@ -136,6 +160,8 @@ pub enum Code {
     MissingLoginKeychain,
     GitForcePushProtection,
     NetworkError,
+    BranchProtected,
+    ShallowHistory,
 }
 
 impl std::fmt::Display for Code {
@@ -155,6 +181,8 @@ impl std::fmt::Display for Code {
             Code::MissingLoginKeychain => "errors.secret.missing_login_keychain",
             Code::GitForcePushProtection => "errors.git.force_push_protection",
             Code::NetworkError => "errors.network",
+            Code::BranchProtected => "errors.branch.protected",
+            Code::ShallowHistory => "errors.git.shallow_history",
         };
         f.write_str(code)
     }
@@ -211,6 +239,166 @@ impl Context {
     }
 }
 
+/// An error with a well-known cause, carrying the facts the frontend needs to handle it.
+///
+/// Its [`Display`](std::fmt::Display) implementation is the message shown to the user. It can be returned as error,
+/// or attached as [`anyhow context`](anyhow::Context), and is found by [`AnyhowContextExt`] in either case.
+///
+/// When serialized, the variant name is stored in the `kind` field, next to the fields of the variant.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(
+    tag = "kind",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum ButError {
+    /// The branch with the given name doesn't exist.
+    BranchNotFound {
+        /// The name of the branch, as provided by the caller.
+        name: String,
+    },
+    /// The stack with the given id isn't part of the workspace.
+    StackNotFound {
+        /// The id of the stack.
+        stack_id: String,
+    },
+    /// There is no target branch configured for the project.
+    DefaultTargetNotFound,
+    /// The operation can't be performed on the given commit as it's conflicted.
+    CommitConflicted {
+        /// The hex-id of the conflicted commit.
+        commit_id: String,
+    },
+    /// Changes couldn't be applied without causing conflicts.
+    MergeConflict,
+    /// A force-push was refused as it would overwrite commits on the remote that weren't seen before.
+    ForcePushProtection {
+        /// The remote reference, or a description of it if it isn't known.
+        remote_ref: String,
+    },
+    /// None of the available credentials were accepted by the remote.
+    Authentication {
+        /// The name of the remote.
+        remote: String,
+    },
+    /// The branch matches a pattern in `gitbutler.protectedBranches`, so it can't be committed to,
+    /// amended, force-pushed or deleted.
+    BranchProtected {
+        /// The name of the protected branch.
+        name: String,
+        /// The pattern that matched the branch name.
+        pattern: String,
+    },
+    /// The operation needs commits beyond the shallow boundary of a shallow clone, which the user has to fetch first.
+    ShallowHistory {
+        /// The hex-id of the commit whose history is needed.
+        commit_id: String,
+    },
+}
+
+/// A hint on what the user may do to recover from a [`ButError`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Recovery {
+    /// The state the operation was based on is outdated, and it may succeed once the data is refreshed.
+    Refresh,
+    /// The project needs to be configured first, like by choosing a target branch.
+    Configure,
+    /// Conflicts have to be resolved first.
+    ResolveConflicts,
+    /// The remote has to be integrated first, or the user has to explicitly agree to overwrite it.
+    IntegrateUpstream,
+    /// The user has to provide working credentials for the remote.
+    Authenticate,
+    /// More history has to be fetched, like with `git fetch --deepen` or `git fetch --unshallow`.
+    DeepenHistory,
+}
+
+impl ButError {
+    /// Return the code to classify this error with, which is shared with less structured errors.
+    pub fn code(&self) -> Code {
+        match self {
+            ButError::BranchNotFound { .. } | ButError::StackNotFound { .. } => {
+                Code::BranchNotFound
+            }
+            ButError::DefaultTargetNotFound => Code::DefaultTargetNotFound,
+            ButError::CommitConflicted { .. } => Code::Validation,
+            ButError::MergeConflict => Code::CommitMergeConflictFailure,
+            ButError::ForcePushProtection { .. } => Code::GitForcePushProtection,
+            ButError::Authentication { .. } => Code::ProjectGitAuth,
+            ButError::BranchProtected { .. } => Code::BranchProtected,
+            ButError::ShallowHistory { .. } => Code::ShallowHistory,
+        }
+    }
+
+    /// Return a hint on how the user may recover from this error.
+    pub fn recovery(&self) -> Recovery {
+        match self {
+            ButError::BranchNotFound { .. } | ButError::StackNotFound { .. } => Recovery::Refresh,
+            ButError::DefaultTargetNotFound => Recovery::Configure,
+            ButError::CommitConflicted { .. } | ButError::MergeConflict => {
+                Recovery::ResolveConflicts
+            }
+            ButError::ForcePushProtection { .. } => Recovery::IntegrateUpstream,
+            ButError::Authentication { .. } => Recovery::Authenticate,
+            ButError::BranchProtected { .. } => Recovery::Configure,
+            ButError::ShallowHistory { .. } => Recovery::DeepenHistory,
+        }
+    }
+}
+
+impl std::fmt::Display for ButError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ButError::BranchNotFound { name } => write!(f, "The branch {name} doesn't exist"),
+            ButError::StackNotFound { stack_id } => {
+                write!(
+                    f,
+                    "The stack {stack_id} doesn't exist in the workspace anymore"
+                )
+            }
+            ButError::DefaultTargetNotFound => {
+                f.write_str("There is no target branch configured for this project")
+            }
+            ButError::CommitConflicted { commit_id } => write!(
+                f,
+                "The commit {} is conflicted and has to be resolved first",
+                commit_id.get(..7).unwrap_or(commit_id)
+            ),
+            ButError::MergeConflict => {
+                f.write_str("The changes can't be applied without causing conflicts")
+            }
+            ButError::ForcePushProtection { remote_ref } => write!(
+                f,
+                "The force push was blocked because {remote_ref} contains commits that would be overwritten"
+            ),
+            ButError::Authentication { remote } => {
+                write!(f, "Couldn't authenticate with the remote {remote}")
+            }
+            ButError::BranchProtected { name, pattern } => write!(
+                f,
+                "The branch {name} is protected by the pattern '{pattern}' in gitbutler.protectedBranches"
+            ),
+            ButError::ShallowHistory { commit_id } => write!(
+                f,
+                "The history of commit {} is missing in this shallow clone, fetch more of it with `git fetch --deepen=<depth>` or `git fetch --unshallow`",
+                commit_id.get(..7).unwrap_or(commit_id)
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ButError {}
+
+impl From<&ButError> for Context {
+    fn from(err: &ButError) -> Self {
+        Context {
+            code: err.code(),
+            message: Some(err.to_string().into()),
+        }
+    }
+}
+
 mod private {
     pub trait Sealed {}
 }
@@ -225,6 +413,9 @@ pub trait AnyhowContextExt: private::Sealed {
 
     /// Return our custom context or default it to the root-cause of the error.
     fn custom_context_or_root_cause(&self) -> Context;
+
+    /// Return the structured error that might be part of the error chain.
+    fn but_error(&self) -> Option<&ButError>;
 }
 
 impl private::Sealed for anyhow::Error {}
@@ -232,6 +423,8 @@ impl AnyhowContextExt for anyhow::Error {
     fn custom_context(&self) -> Option<Context> {
         if let Some(ctx) = self.downcast_ref::<Context>() {
             Some(ctx.clone())
+        } else if let Some(err) = self.but_error() {
+            Some(err.into())
         } else {
             self.downcast_ref::<Code>().map(|code| (*code).into())
         }
@@ -243,6 +436,10 @@ impl AnyhowContextExt for anyhow::Error {
             message: Some(self.root_cause().to_string().into()),
         })
     }
+
+    fn but_error(&self) -> Option<&ButError> {
+        self.downcast_ref::<ButError>()
+    }
 }
 
 /// A way to mark errors using `[anyhow::Context::context]` for later retrieval, e.g. to know
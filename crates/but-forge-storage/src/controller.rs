@@ -64,6 +64,70 @@ impl Controller {
         self.save_settings(&settings)
     }
 
+    /// Get all known GitLab accounts.
+    pub fn gitlab_accounts(&self) -> anyhow::Result<Vec<crate::settings::GitLabAccount>> {
+        let settings = self.read_settings()?;
+        Ok(settings.gitlab.known_accounts)
+    }
+
+    /// Add a GitLab account, replacing the one for the same user on the same host.
+    pub fn add_gitlab_account(
+        &self,
+        account: &crate::settings::GitLabAccount,
+    ) -> anyhow::Result<()> {
+        let mut settings = self.read_settings()?;
+        settings
+            .gitlab
+            .known_accounts
+            .retain(|a| !(a.host == account.host && a.username == account.username));
+        settings.gitlab.known_accounts.push(account.to_owned());
+        self.save_settings(&settings)
+    }
+
+    /// Remove a GitLab account.
+    pub fn remove_gitlab_account(
+        &self,
+        account: &crate::settings::GitLabAccount,
+    ) -> anyhow::Result<()> {
+        let mut settings = self.read_settings()?;
+
+        settings.gitlab.known_accounts.retain(|a| a != account);
+
+        self.save_settings(&settings)
+    }
+
+    /// Get all known Bitbucket accounts.
+    pub fn bitbucket_accounts(&self) -> anyhow::Result<Vec<crate::settings::BitbucketAccount>> {
+        let settings = self.read_settings()?;
+        Ok(settings.bitbucket.known_accounts)
+    }
+
+    /// Add a Bitbucket account, replacing the one with the same username.
+    pub fn add_bitbucket_account(
+        &self,
+        account: &crate::settings::BitbucketAccount,
+    ) -> anyhow::Result<()> {
+        let mut settings = self.read_settings()?;
+        settings
+            .bitbucket
+            .known_accounts
+            .retain(|a| a.username != account.username);
+        settings.bitbucket.known_accounts.push(account.to_owned());
+        self.save_settings(&settings)
+    }
+
+    /// Remove a Bitbucket account.
+    pub fn remove_bitbucket_account(
+        &self,
+        account: &crate::settings::BitbucketAccount,
+    ) -> anyhow::Result<()> {
+        let mut settings = self.read_settings()?;
+
+        settings.bitbucket.known_accounts.retain(|a| a != account);
+
+        self.save_settings(&settings)
+    }
+
     fn read_settings(&self) -> anyhow::Result<crate::settings::ForgeSettings> {
         self.settings_storage.read()
     }
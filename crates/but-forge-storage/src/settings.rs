@@ -5,6 +5,46 @@ use serde::{Deserialize, Serialize};
 pub struct ForgeSettings {
     /// GitHub-specific settings.
     pub github: GitHubSettings,
+    /// GitLab-specific settings.
+    #[serde(default)]
+    pub gitlab: GitLabSettings,
+    /// Bitbucket-specific settings.
+    #[serde(default)]
+    pub bitbucket: BitbucketSettings,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct BitbucketSettings {
+    /// The Bitbucket Cloud accounts that were authenticated.
+    pub known_accounts: Vec<BitbucketAccount>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BitbucketAccount {
+    /// The name used to authenticate, the email address for API tokens.
+    pub username: String,
+    /// Key to retrieve the API token from secure storage.
+    pub api_token_key: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct GitLabSettings {
+    /// The GitLab accounts that were authenticated, on gitlab.com or self-managed instances.
+    pub known_accounts: Vec<GitLabAccount>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitLabAccount {
+    /// Hostname of the GitLab instance, like `gitlab.com`.
+    pub host: String,
+    /// Username associated with the personal access token.
+    pub username: String,
+    /// Key to retrieve the access token from secure storage.
+    pub access_token_key: String,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, Default)]
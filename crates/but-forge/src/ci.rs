@@ -21,35 +21,178 @@ pub fn ci_checks_for_ref_with_cache(
                     return Ok(cached);
                 }
             }
-            let checks =
-                ci_checks_for_ref(preferred_forge_user, forge_repo_info, storage, reference)?;
-            crate::db::cache_ci_checks(db, reference, &checks).ok();
-            checks
-        }
-        crate::CacheConfig::NoCache => {
-            let checks =
-                ci_checks_for_ref(preferred_forge_user, forge_repo_info, storage, reference)?;
-            crate::db::cache_ci_checks(db, reference, &checks).ok();
-            checks
+            fetch_and_cache_ci_checks(
+                preferred_forge_user,
+                forge_repo_info,
+                storage,
+                reference,
+                db,
+            )?
         }
+        crate::CacheConfig::NoCache => fetch_and_cache_ci_checks(
+            preferred_forge_user,
+            forge_repo_info,
+            storage,
+            reference,
+            db,
+        )?,
     };
     Ok(checks)
 }
 
-fn ci_checks_for_ref(
+/// How long the checks of a branch are shown from the cache before they are fetched again.
+const BRANCH_CHECKS_MAX_AGE_SECONDS: u64 = 60;
+
+/// The checks of a branch along with their overall state.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BranchChecks {
+    pub summary: CiSummary,
+    pub checks: Vec<CiCheck>,
+}
+
+/// Get the checks that ran for the head of `branch`, the short name of a branch on the forge,
+/// so its state can be shown in the list of stacks.
+///
+/// Cached checks are used unless they are older than a minute, or unless `refresh` is `true`.
+/// Fetching is cheap if nothing changed as the forge is asked for changes since the last response.
+pub fn checks_for_branch(
+    preferred_forge_user: Option<crate::ForgeUser>,
+    forge_repo_info: &crate::forge::ForgeRepoInfo,
+    storage: &but_forge_storage::Controller,
+    branch: &str,
+    db: &mut but_db::DbHandle,
+    refresh: bool,
+) -> anyhow::Result<BranchChecks> {
+    let cache_config = if refresh {
+        crate::CacheConfig::NoCache
+    } else {
+        crate::CacheConfig::CacheWithFallback {
+            max_age_seconds: BRANCH_CHECKS_MAX_AGE_SECONDS,
+        }
+    };
+    let checks = ci_checks_for_ref_with_cache(
+        preferred_forge_user,
+        forge_repo_info,
+        storage,
+        branch,
+        db,
+        Some(cache_config),
+    )?;
+    Ok(BranchChecks {
+        summary: CiSummary::from_checks(&checks),
+        checks,
+    })
+}
+
+/// The overall state of all checks of a reference, red or green in the simplest case.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CiSummary {
+    /// There are no checks.
+    None,
+    /// No check failed, but some didn't complete yet.
+    Pending,
+    /// All checks completed and none of them failed.
+    Success,
+    /// At least one check failed.
+    Failure,
+}
+
+impl CiSummary {
+    pub fn from_checks(checks: &[CiCheck]) -> Self {
+        if checks.is_empty() {
+            return CiSummary::None;
+        }
+        let mut pending = false;
+        for check in checks {
+            match &check.status {
+                CiStatus::Complete { conclusion, .. } => match conclusion {
+                    CiConclusion::Failure
+                    | CiConclusion::TimedOut
+                    | CiConclusion::Cancelled
+                    | CiConclusion::ActionRequired => return CiSummary::Failure,
+                    CiConclusion::Neutral
+                    | CiConclusion::Skipped
+                    | CiConclusion::Success
+                    | CiConclusion::Unknown => {}
+                },
+                CiStatus::InProgress | CiStatus::Queued | CiStatus::Unknown => pending = true,
+            }
+        }
+        if pending {
+            CiSummary::Pending
+        } else {
+            CiSummary::Success
+        }
+    }
+}
+
+/// Fetch the checks of `reference` and cache them in `db`, passing the `ETag` of the previous response
+/// so the cached checks can be reused if nothing changed.
+fn fetch_and_cache_ci_checks(
     preferred_forge_user: Option<crate::ForgeUser>,
     forge_repo_info: &crate::forge::ForgeRepoInfo,
     storage: &but_forge_storage::Controller,
     reference: &str,
+    db: &mut but_db::DbHandle,
 ) -> anyhow::Result<Vec<CiCheck>> {
+    let etag = db.ci_checks().etag_for_reference(reference)?;
+    match ci_checks_for_ref(
+        preferred_forge_user,
+        forge_repo_info,
+        storage,
+        reference,
+        etag,
+    )? {
+        FetchedChecks::NotModified => {
+            let now = chrono::Local::now().naive_local();
+            let checks: Vec<_> = crate::db::ci_checks_from_cache(db, reference)?
+                .into_iter()
+                .map(|check| CiCheck {
+                    last_sync_at: now,
+                    ..check
+                })
+                .collect();
+            crate::db::cache_ci_checks(db, reference, &checks).ok();
+            Ok(checks)
+        }
+        FetchedChecks::Modified { checks, etag } => {
+            // Only remember the ETag if the checks it belongs to are cached.
+            if crate::db::cache_ci_checks(db, reference, &checks).is_ok() {
+                db.ci_checks_mut()?
+                    .set_etag_for_reference(reference, etag.as_deref())
+                    .ok();
+            }
+            Ok(checks)
+        }
+    }
+}
+
+/// The checks of a reference as obtained from the forge.
+enum FetchedChecks {
+    /// The checks didn't change since the response with the `ETag` that was passed.
+    NotModified,
+    Modified {
+        checks: Vec<CiCheck>,
+        etag: Option<String>,
+    },
+}
+
+fn ci_checks_for_ref(
+    preferred_forge_user: Option<crate::ForgeUser>,
+    forge_repo_info: &crate::forge::ForgeRepoInfo,
+    storage: &but_forge_storage::Controller,
+    reference: &str,
+    etag: Option<String>,
+) -> anyhow::Result<FetchedChecks> {
     let crate::forge::ForgeRepoInfo {
         forge, owner, repo, ..
     } = forge_repo_info;
     match forge {
         ForgeName::GitHub => {
-            let preferred_account = preferred_forge_user
-                .as_ref()
-                .and_then(|user| user.github().cloned());
+            let preferred_account =
+                crate::forge::github_account(&preferred_forge_user, forge_repo_info, storage);
             let gh = but_github::GitHubClient::from_storage(storage, preferred_account.as_ref())?;
 
             // Clone owned data for thread
@@ -59,21 +202,95 @@ fn ci_checks_for_ref(
             let reference_for_checks = reference.clone();
 
             let checks = std::thread::spawn(move || {
-                tokio::runtime::Runtime::new()
-                    .unwrap()
-                    .block_on(gh.list_checks_for_ref(&owner, &repo, &reference))
+                tokio::runtime::Runtime::new().unwrap().block_on(
+                    gh.list_checks_for_ref_if_modified(&owner, &repo, &reference, etag.as_deref()),
+                )
             })
             .join()
             .map_err(|e| anyhow::anyhow!("Failed to join thread: {:?}", e))?;
-            checks.map(|c| {
-                c.into_iter()
-                    .map(|check| {
-                        let mut ci_check = CiCheck::from(check);
-                        ci_check.reference = reference_for_checks.to_string();
-                        ci_check
+            checks.map(|checks| match checks {
+                but_github::Conditional::NotModified => FetchedChecks::NotModified,
+                but_github::Conditional::Modified { value, etag } => FetchedChecks::Modified {
+                    checks: value
+                        .into_iter()
+                        .map(|check| {
+                            let mut ci_check = CiCheck::from(check);
+                            ci_check.reference = reference_for_checks.to_string();
+                            ci_check
+                        })
+                        .collect(),
+                    etag,
+                },
+            })
+        }
+        ForgeName::GitLab => {
+            let preferred_account = preferred_forge_user
+                .as_ref()
+                .and_then(|user| user.gitlab().cloned());
+            let host = forge_repo_info.host.clone();
+            let gl = but_gitlab::GitLabClient::from_storage(
+                storage,
+                &host,
+                forge_repo_info.api_url.as_deref(),
+                preferred_account.as_ref(),
+            )?;
+
+            // Clone owned data for thread
+            let project = format!("{owner}/{repo}");
+            let reference = reference.to_string();
+
+            // GitLab doesn't offer ETags for pipelines through its REST API, so they are always fetched.
+            std::thread::spawn(move || {
+                tokio::runtime::Runtime::new().unwrap().block_on(async {
+                    let Some(pipeline) = gl.latest_pipeline_for_ref(&project, &reference).await?
+                    else {
+                        return Ok(FetchedChecks::Modified {
+                            checks: vec![],
+                            etag: None,
+                        });
+                    };
+                    let jobs = gl.list_pipeline_jobs(&project, pipeline.id).await?;
+                    Ok(FetchedChecks::Modified {
+                        checks: jobs
+                            .into_iter()
+                            .map(|job| ci_check_from_gitlab_job(job, &pipeline, &reference))
+                            .collect(),
+                        etag: None,
                     })
-                    .collect()
+                })
             })
+            .join()
+            .map_err(|e| anyhow::anyhow!("Failed to join thread: {:?}", e))?
+        }
+        ForgeName::Bitbucket => {
+            let preferred_account = preferred_forge_user
+                .as_ref()
+                .and_then(|user| user.bitbucket().cloned());
+            let bb =
+                but_bitbucket::BitbucketClient::from_storage(storage, preferred_account.as_ref())?;
+
+            // Clone owned data for thread
+            let repository = format!("{owner}/{repo}");
+            let reference = reference.to_string();
+
+            // Bitbucket doesn't offer ETags for commit statuses, so they are always fetched.
+            std::thread::spawn(move || {
+                tokio::runtime::Runtime::new().unwrap().block_on(async {
+                    let commit = bb.branch_commit(&repository, &reference).await?;
+                    let statuses = bb.list_commit_statuses(&repository, &commit).await?;
+                    Ok(FetchedChecks::Modified {
+                        checks: statuses
+                            .into_iter()
+                            .map(|status| {
+                                ci_check_from_bitbucket_status(status, &commit, &reference)
+                            })
+                            .collect(),
+                        etag: None,
+                    })
+                })
+            })
+            .join()
+            .map_err(|e| anyhow::anyhow!("Failed to join thread: {:?}", e))?
         }
         _ => Err(anyhow::anyhow!(
             "Listing ci checks for forge {:?} is not implemented yet.",
@@ -233,3 +450,196 @@ impl From<octorust::types::CheckRun> for CiCheck {
         }
     }
 }
+
+/// Turn a `job` of the GitLab `pipeline` that ran for `reference` into a check, mapping GitLab job states
+/// to their closest GitHub check run equivalent.
+fn ci_check_from_gitlab_job(
+    job: but_gitlab::PipelineJob,
+    pipeline: &but_gitlab::Pipeline,
+    reference: &str,
+) -> CiCheck {
+    let parse_time = |time: Option<&str>| {
+        time.and_then(|time| time.parse::<chrono::DateTime<chrono::Utc>>().ok())
+    };
+    let finished_at = parse_time(job.finished_at.as_deref());
+    let conclusion = match job.status.as_str() {
+        "success" => Some(CiConclusion::Success),
+        "failed" if job.allow_failure => Some(CiConclusion::Neutral),
+        "failed" => Some(CiConclusion::Failure),
+        "canceled" => Some(CiConclusion::Cancelled),
+        "skipped" => Some(CiConclusion::Skipped),
+        _ => None,
+    };
+    let status = match (conclusion, finished_at) {
+        (Some(conclusion), Some(completed_at)) => CiStatus::Complete {
+            conclusion,
+            completed_at,
+        },
+        (Some(_), None) => CiStatus::Unknown,
+        (None, _) => match job.status.as_str() {
+            "running" => CiStatus::InProgress,
+            "created"
+            | "waiting_for_resource"
+            | "preparing"
+            | "pending"
+            | "scheduled"
+            | "manual" => CiStatus::Queued,
+            _ => CiStatus::Unknown,
+        },
+    };
+    CiCheck {
+        id: job.id,
+        name: job.name,
+        output: CiOutput {
+            summary: String::new(),
+            text: String::new(),
+            title: job.stage,
+        },
+        started_at: parse_time(job.started_at.as_deref()),
+        status,
+        head_sha: pipeline.sha.clone(),
+        url: job.web_url.clone(),
+        html_url: job.web_url.clone(),
+        details_url: job.web_url,
+        pull_requests: Vec::new(),
+        reference: reference.to_string(),
+        last_sync_at: chrono::Local::now().naive_local(),
+    }
+}
+
+/// Turn a commit `status` reported for `commit`, the tip of `reference`, into a check.
+fn ci_check_from_bitbucket_status(
+    status: but_bitbucket::CommitStatus,
+    commit: &str,
+    reference: &str,
+) -> CiCheck {
+    let parse_time = |time: Option<&str>| {
+        time.and_then(|time| time.parse::<chrono::DateTime<chrono::Utc>>().ok())
+    };
+    let conclusion = match status.state.as_str() {
+        "SUCCESSFUL" => Some(CiConclusion::Success),
+        "FAILED" => Some(CiConclusion::Failure),
+        "STOPPED" => Some(CiConclusion::Cancelled),
+        _ => None,
+    };
+    let status_of_check = match (conclusion, parse_time(status.updated_on.as_deref())) {
+        (Some(conclusion), Some(completed_at)) => CiStatus::Complete {
+            conclusion,
+            completed_at,
+        },
+        (Some(_), None) => CiStatus::Unknown,
+        (None, _) if status.state == "INPROGRESS" => CiStatus::InProgress,
+        (None, _) => CiStatus::Unknown,
+    };
+    CiCheck {
+        // Statuses are identified by their key, which is unique per commit.
+        id: crate::review::numeric_id(&status.key),
+        name: status.name.unwrap_or_else(|| status.key.clone()),
+        output: CiOutput {
+            summary: status.description.unwrap_or_default(),
+            text: String::new(),
+            title: status.key,
+        },
+        started_at: parse_time(status.created_on.as_deref()),
+        status: status_of_check,
+        head_sha: commit.to_owned(),
+        url: status.url.clone(),
+        html_url: status.url.clone(),
+        details_url: status.url,
+        pull_requests: Vec::new(),
+        reference: reference.to_string(),
+        last_sync_at: chrono::Local::now().naive_local(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(status: CiStatus) -> CiCheck {
+        CiCheck {
+            id: 1,
+            name: "test".into(),
+            output: CiOutput {
+                summary: String::new(),
+                text: String::new(),
+                title: String::new(),
+            },
+            started_at: None,
+            status,
+            head_sha: String::new(),
+            url: String::new(),
+            html_url: String::new(),
+            details_url: String::new(),
+            pull_requests: vec![],
+            reference: "main".into(),
+            last_sync_at: chrono::Local::now().naive_local(),
+        }
+    }
+
+    fn complete(conclusion: CiConclusion) -> CiStatus {
+        CiStatus::Complete {
+            conclusion,
+            completed_at: chrono::Utc::now(),
+        }
+    }
+
+    #[test]
+    fn summary_of_checks() {
+        assert_eq!(CiSummary::from_checks(&[]), CiSummary::None);
+        assert_eq!(
+            CiSummary::from_checks(&[
+                check(complete(CiConclusion::Success)),
+                check(complete(CiConclusion::Skipped))
+            ]),
+            CiSummary::Success
+        );
+        assert_eq!(
+            CiSummary::from_checks(&[
+                check(complete(CiConclusion::Success)),
+                check(CiStatus::InProgress)
+            ]),
+            CiSummary::Pending
+        );
+        assert_eq!(
+            CiSummary::from_checks(&[
+                check(CiStatus::Queued),
+                check(complete(CiConclusion::Failure))
+            ]),
+            CiSummary::Failure,
+            "a failure is known even while other checks are still running"
+        );
+    }
+
+    #[test]
+    fn bitbucket_statuses_become_checks() {
+        let status = |state: &str| but_bitbucket::CommitStatus {
+            key: "build".into(),
+            name: None,
+            state: state.into(),
+            url: "https://ci.example.com/1".into(),
+            description: None,
+            created_on: Some("2026-01-01T10:00:00.000000+00:00".into()),
+            updated_on: Some("2026-01-01T10:05:00.000000+00:00".into()),
+        };
+        let check = ci_check_from_bitbucket_status(status("SUCCESSFUL"), "abc", "main");
+        assert_eq!(check.name, "build", "the key is the name if there is none");
+        assert_eq!(check.head_sha, "abc");
+        assert!(matches!(
+            check.status,
+            CiStatus::Complete {
+                conclusion: CiConclusion::Success,
+                ..
+            }
+        ));
+        assert!(matches!(
+            ci_check_from_bitbucket_status(status("INPROGRESS"), "abc", "main").status,
+            CiStatus::InProgress
+        ));
+        assert_eq!(
+            ci_check_from_bitbucket_status(status("FAILED"), "abc", "main").id,
+            check.id,
+            "the ID is stable for the same key"
+        );
+    }
+}
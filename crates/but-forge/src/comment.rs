@@ -0,0 +1,81 @@
+use anyhow::{Error, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{forge::ForgeName, review::ForgeUser};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+/// A comment made on lines of a file in a review, as opposed to a comment on the review as a whole.
+pub struct ForgeReviewComment {
+    /// The id of the comment on the forge.
+    pub id: i64,
+    /// The URL to view the comment in a web browser.
+    pub html_url: String,
+    /// The user who made the comment.
+    pub author: Option<ForgeUser>,
+    /// The markdown text of the comment.
+    pub body: String,
+    /// The path of the file the comment refers to, relative to the repository root.
+    pub path: String,
+    /// The sha of the commit that `start_line` and `end_line` refer to.
+    pub commit_id: String,
+    /// The first line the comment refers to, 1-based.
+    pub start_line: Option<u32>,
+    /// The last line the comment refers to, 1-based and inclusive, or `None` if the lines don't exist
+    /// in the reviewed changes anymore.
+    pub end_line: Option<u32>,
+    /// The id of the comment this comment replies to, which starts the thread it's part of.
+    pub in_reply_to_id: Option<i64>,
+}
+
+impl From<but_github::PullRequestReviewComment> for ForgeReviewComment {
+    fn from(comment: but_github::PullRequestReviewComment) -> Self {
+        let end_line = comment.line.and_then(|line| u32::try_from(line).ok());
+        ForgeReviewComment {
+            id: comment.id,
+            html_url: comment.html_url,
+            author: comment.author.map(ForgeUser::from),
+            body: comment.body,
+            path: comment.path,
+            commit_id: comment.commit_id,
+            start_line: comment
+                .start_line
+                .and_then(|line| u32::try_from(line).ok())
+                .or(end_line),
+            end_line,
+            in_reply_to_id: comment.in_reply_to_id,
+        }
+    }
+}
+
+/// List all comments made on lines of files in the review with `review_number`, in the order they were made.
+pub async fn list_forge_review_comments(
+    preferred_forge_user: &Option<crate::ForgeUser>,
+    forge_repo_info: &crate::forge::ForgeRepoInfo,
+    review_number: usize,
+    storage: &but_forge_storage::Controller,
+) -> Result<Vec<ForgeReviewComment>> {
+    let crate::forge::ForgeRepoInfo {
+        forge, owner, repo, ..
+    } = forge_repo_info;
+    match forge {
+        ForgeName::GitHub => {
+            let account =
+                crate::forge::github_account(preferred_forge_user, forge_repo_info, storage);
+            let preferred_account = account.as_ref();
+            let comments = but_github::pr::list_review_comments(
+                preferred_account,
+                owner,
+                repo,
+                review_number,
+                storage,
+            )
+            .await?;
+            Ok(comments.into_iter().map(ForgeReviewComment::from).collect())
+        }
+        _ => Err(Error::msg(format!(
+            "Listing review comments for forge {:?} is not implemented yet.",
+            forge,
+        ))),
+    }
+}
@@ -0,0 +1,99 @@
+//! The footer that lists all reviews of a stack in the description of each of them.
+//!
+//! It's delimited by the same markers the desktop app uses, so either can refresh what the other wrote.
+
+const BOUNDARY_TOP: &str = "<!-- GitButler Footer Boundary Top -->";
+const BOUNDARY_BOTTOM: &str = "<!-- GitButler Footer Boundary Bottom -->";
+
+/// Generate the footer for the review with `review_number`, given the `review_numbers` of all reviews of
+/// its stack ordered from the bottom of the stack to its top.
+///
+/// `unit_symbol` is the prefix of review numbers on the forge, like `#` on GitHub.
+pub fn stack_footer(review_number: usize, review_numbers: &[usize], unit_symbol: &str) -> String {
+    let count = review_numbers.len();
+    let part = review_numbers
+        .iter()
+        .position(|number| *number == review_number)
+        .map_or(0, |idx| idx + 1);
+    let mut footer = format!(
+        "{BOUNDARY_TOP}\n---\nThis is **part {part} of {count} in a stack** made with GitButler:\n"
+    );
+    // The top of the stack is listed first, just like stacks are shown.
+    for (idx, number) in review_numbers.iter().enumerate().rev() {
+        let marker = if *number == review_number {
+            "👈 "
+        } else {
+            ""
+        };
+        footer.push_str(&format!(
+            "- <kbd>&nbsp;{}&nbsp;</kbd> {unit_symbol}{number} {marker}\n",
+            idx + 1
+        ));
+    }
+    footer.push_str(BOUNDARY_BOTTOM);
+    footer
+}
+
+/// Return `body` with its stack footer replaced by `footer`, or with `footer` appended if it has none.
+pub fn with_stack_footer(body: &str, footer: &str) -> String {
+    let (head, tail) = split_at_footer(body);
+    format!("{head}\n\n{footer}\n\n{tail}")
+}
+
+/// Return `body` without its stack footer, or `body` itself if it has none.
+pub fn without_stack_footer(body: &str) -> String {
+    if !(body.contains(BOUNDARY_TOP) && body.contains(BOUNDARY_BOTTOM)) {
+        return body.to_owned();
+    }
+    let (head, tail) = split_at_footer(body);
+    format!("{head}\n\n{tail}")
+}
+
+/// Return what's above and below the footer in `body`, trimmed.
+fn split_at_footer(body: &str) -> (&str, &str) {
+    let head = body.split(BOUNDARY_TOP).next().unwrap_or_default().trim();
+    let tail = body
+        .split(BOUNDARY_BOTTOM)
+        .nth(1)
+        .unwrap_or_default()
+        .trim();
+    (head, tail)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn footer_lists_the_top_first_and_marks_the_current_review() {
+        let footer = stack_footer(2, &[1, 2, 3], "#");
+        assert_eq!(
+            footer,
+            "<!-- GitButler Footer Boundary Top -->
+---
+This is **part 2 of 3 in a stack** made with GitButler:
+- <kbd>&nbsp;3&nbsp;</kbd> #3 
+- <kbd>&nbsp;2&nbsp;</kbd> #2 👈 
+- <kbd>&nbsp;1&nbsp;</kbd> #1 
+<!-- GitButler Footer Boundary Bottom -->"
+        );
+    }
+
+    #[test]
+    fn footers_are_replaced_and_removed() {
+        let footer = stack_footer(1, &[1, 2], "!");
+        let body = with_stack_footer("Description", &footer);
+        assert_eq!(body, format!("Description\n\n{footer}\n\n"));
+
+        let new_footer = stack_footer(1, &[1, 2, 3], "!");
+        let body = with_stack_footer(&format!("{body}Below"), &new_footer);
+        assert_eq!(body, format!("Description\n\n{new_footer}\n\nBelow"));
+
+        assert_eq!(without_stack_footer(&body), "Description\n\nBelow");
+        assert_eq!(
+            without_stack_footer("Without footer"),
+            "Without footer",
+            "bodies without footer are left alone"
+        );
+    }
+}
@@ -10,6 +10,20 @@ pub enum ForgeName {
     Azure,
 }
 
+impl std::str::FromStr for ForgeName {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.to_ascii_lowercase().as_str() {
+            "github" => ForgeName::GitHub,
+            "gitlab" => ForgeName::GitLab,
+            "bitbucket" => ForgeName::Bitbucket,
+            "azure" => ForgeName::Azure,
+            _ => anyhow::bail!("Unknown forge '{s}'"),
+        })
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct ForgeRepoInfo {
@@ -17,6 +31,51 @@ pub struct ForgeRepoInfo {
     pub owner: String,
     pub repo: String,
     pub protocol: String,
+    /// The host of the remote, like `gitlab.com`, which is where self-managed instances are reached.
+    #[serde(default)]
+    pub host: String,
+    /// The base URL of the API of a self-hosted forge if it was configured, or `None` to use its default location.
+    #[serde(default)]
+    pub api_url: Option<String>,
+}
+
+impl ForgeRepoInfo {
+    /// Return the base URL of the API if the repository is on a GitHub Enterprise instance.
+    pub fn github_enterprise_api_url(&self) -> Option<String> {
+        if self.forge != ForgeName::GitHub
+            || self.host.is_empty()
+            || self.host.eq_ignore_ascii_case("github.com")
+        {
+            return None;
+        }
+        Some(
+            self.api_url
+                .clone()
+                .unwrap_or_else(|| format!("https://{}/api/v3", self.host)),
+        )
+    }
+}
+
+/// Return the GitHub account to use with the repository of `forge_repo_info`.
+///
+/// That's the account of `preferred_forge_user`, unless the repository is on a GitHub Enterprise instance
+/// the account doesn't belong to, in which case a known account of that instance is used.
+pub(crate) fn github_account(
+    preferred_forge_user: &Option<ForgeUser>,
+    forge_repo_info: &ForgeRepoInfo,
+    storage: &but_forge_storage::Controller,
+) -> Option<but_github::GithubAccountIdentifier> {
+    let preferred_account = preferred_forge_user.as_ref().and_then(|user| user.github());
+    let Some(api_url) = forge_repo_info.github_enterprise_api_url() else {
+        return preferred_account.cloned();
+    };
+    match preferred_account {
+        Some(account) if account.is_for_api_url(&api_url) => Some(account.clone()),
+        _ => but_github::enterprise_account_for_api_url(&api_url, storage)
+            .ok()
+            .flatten()
+            .or_else(|| preferred_account.cloned()),
+    }
 }
 
 impl PartialEq for ForgeRepoInfo {
@@ -29,12 +88,29 @@ impl PartialEq for ForgeRepoInfo {
 #[serde(tag = "provider", rename_all = "lowercase", content = "details")]
 pub enum ForgeUser {
     GitHub(but_github::GithubAccountIdentifier),
+    GitLab(but_gitlab::GitLabAccountIdentifier),
+    Bitbucket(but_bitbucket::BitbucketAccountIdentifier),
 }
 
 impl ForgeUser {
     pub fn github(&self) -> Option<&but_github::GithubAccountIdentifier> {
         match self {
             ForgeUser::GitHub(id) => Some(id),
+            ForgeUser::GitLab(_) | ForgeUser::Bitbucket(_) => None,
+        }
+    }
+
+    pub fn gitlab(&self) -> Option<&but_gitlab::GitLabAccountIdentifier> {
+        match self {
+            ForgeUser::GitLab(id) => Some(id),
+            ForgeUser::GitHub(_) | ForgeUser::Bitbucket(_) => None,
+        }
+    }
+
+    pub fn bitbucket(&self) -> Option<&but_bitbucket::BitbucketAccountIdentifier> {
+        match self {
+            ForgeUser::Bitbucket(id) => Some(id),
+            ForgeUser::GitHub(_) | ForgeUser::GitLab(_) => None,
         }
     }
 }
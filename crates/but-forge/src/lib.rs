@@ -1,17 +1,28 @@
+use but_settings::app_settings::ForgeHost;
 use git_url_parse::{GitUrl, types::provider::GenericProvider};
 
 mod forge;
 pub use crate::forge::{ForgeName, ForgeRepoInfo, ForgeUser, deserialize_preferred_forge_user_opt};
 
 mod ci;
+mod comment;
+pub use comment::{ForgeReviewComment, list_forge_review_comments};
 mod db;
+mod footer;
+pub use footer::{stack_footer, with_stack_footer, without_stack_footer};
 mod review;
-pub use ci::{CiCheck, CiConclusion, CiOutput, CiStatus, ci_checks_for_ref_with_cache};
+pub use ci::{
+    BranchChecks, CiCheck, CiConclusion, CiOutput, CiStatus, CiSummary, checks_for_branch,
+    ci_checks_for_ref_with_cache,
+};
 pub use review::{
     CacheConfig, CreateForgeReviewParams, ForgeReview, ForgeReviewFilter, ReviewTemplateFunctions,
     available_review_templates, create_forge_review, get_forge_review,
     get_review_template_functions, list_forge_reviews_for_branch, list_forge_reviews_with_cache,
+    update_forge_review_base, update_forge_review_body,
 };
+mod suggestion;
+pub use suggestion::{ReviewSuggestion, list_forge_review_suggestions, parse_suggestion_blocks};
 
 fn determine_forge_from_host(host: &str) -> Option<ForgeName> {
     if host.contains("github.com") || host.starts_with("github.") {
@@ -29,16 +40,107 @@ fn determine_forge_from_host(host: &str) -> Option<ForgeName> {
 
 /// Derive the forge repository information from a remote URL.
 pub fn derive_forge_repo_info(url: &str) -> Option<ForgeRepoInfo> {
+    derive_forge_repo_info_with_forge(url, None)
+}
+
+/// Like [`derive_forge_repo_info()`], but use `forge` if set instead of determining it from the host of `url`,
+/// for hosts whose name doesn't give away the forge.
+pub fn derive_forge_repo_info_with_forge(
+    url: &str,
+    forge: Option<ForgeName>,
+) -> Option<ForgeRepoInfo> {
+    derive_forge_repo_info_with_hosts(url, forge, &[])
+}
+
+/// Like [`derive_forge_repo_info_with_forge()`], but look up the host of `url` in the self-hosted `forge_hosts`
+/// to learn which forge it is, if `forge` isn't set, and where its API is.
+pub fn derive_forge_repo_info_with_hosts(
+    url: &str,
+    forge: Option<ForgeName>,
+    forge_hosts: &[ForgeHost],
+) -> Option<ForgeRepoInfo> {
     let git_url = GitUrl::parse(url).ok()?;
     let host = git_url.host()?;
     let protocol = git_url.scheme()?;
 
     let provider_info: GenericProvider = git_url.provider_info().ok()?;
+    let forge_host = forge_hosts
+        .iter()
+        .find(|forge_host| forge_host.host.eq_ignore_ascii_case(host));
+    let configured_forge = forge_host.and_then(|forge_host| match forge_host.forge.parse() {
+        Ok(forge) => Some(forge),
+        Err(err) => {
+            tracing::warn!(?err, host, "ignoring forge host with invalid forge");
+            None
+        }
+    });
 
     Some(ForgeRepoInfo {
-        forge: determine_forge_from_host(host)?,
+        forge: forge
+            .or(configured_forge)
+            .or_else(|| determine_forge_from_host(host))?,
         owner: provider_info.owner().to_string(),
         repo: provider_info.repo().to_string(),
         protocol: protocol.to_string(),
+        host: host.to_string(),
+        api_url: forge_host.and_then(|forge_host| forge_host.api_url.clone()),
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn forge_of_self_managed_hosts_can_be_overridden() {
+        let url = "git@git.example.com:group/repo.git";
+        assert_eq!(derive_forge_repo_info(url), None);
+
+        let info = derive_forge_repo_info_with_forge(url, Some(ForgeName::GitLab)).unwrap();
+        assert_eq!(info.forge, ForgeName::GitLab);
+        assert_eq!(info.owner, "group");
+        assert_eq!(info.repo, "repo");
+        assert_eq!(info.host, "git.example.com");
+    }
+
+    #[test]
+    fn self_hosted_forges_are_configured_by_host() {
+        let forge_hosts = [ForgeHost {
+            host: "GHE.example.com".into(),
+            forge: "github".into(),
+            api_url: Some("https://ghe.example.com/custom/api".into()),
+        }];
+        let info = derive_forge_repo_info_with_hosts(
+            "https://ghe.example.com/org/repo.git",
+            None,
+            &forge_hosts,
+        )
+        .unwrap();
+        assert_eq!(info.forge, ForgeName::GitHub);
+        assert_eq!(
+            info.github_enterprise_api_url().as_deref(),
+            Some("https://ghe.example.com/custom/api")
+        );
+
+        let info = derive_forge_repo_info_with_hosts(
+            "https://ghe.example.com/org/repo.git",
+            Some(ForgeName::GitLab),
+            &forge_hosts,
+        )
+        .unwrap();
+        assert_eq!(
+            info.forge,
+            ForgeName::GitLab,
+            "an explicit forge overrides the configuration"
+        );
+
+        let info = derive_forge_repo_info_with_hosts(
+            "https://github.com/org/repo.git",
+            None,
+            &forge_hosts,
+        )
+        .unwrap();
+        assert_eq!(info.api_url, None);
+        assert_eq!(info.github_enterprise_api_url(), None);
+    }
+}
@@ -238,6 +238,45 @@ impl From<but_github::GitHubUser> for ForgeUser {
     }
 }
 
+impl From<but_gitlab::GitLabUser> for ForgeUser {
+    fn from(user: but_gitlab::GitLabUser) -> Self {
+        ForgeUser {
+            id: user.id,
+            login: user.username,
+            name: user.name,
+            email: user.public_email.filter(|email| !email.is_empty()),
+            avatar_url: user.avatar_url,
+            is_bot: user.bot,
+        }
+    }
+}
+
+impl From<but_bitbucket::BitbucketUser> for ForgeUser {
+    fn from(user: but_bitbucket::BitbucketUser) -> Self {
+        ForgeUser {
+            id: numeric_id(&user.uuid),
+            login: user
+                .nickname
+                .clone()
+                .unwrap_or_else(|| user.display_name.clone()),
+            avatar_url: user.avatar_url().map(ToOwned::to_owned),
+            name: Some(user.display_name),
+            // Bitbucket never reveals email addresses of users.
+            email: None,
+            is_bot: user.kind == "app_user",
+        }
+    }
+}
+
+/// Derive a numeric ID from `uuid` for forges that identify objects by UUID only.
+pub(crate) fn numeric_id(uuid: &str) -> i64 {
+    // FNV-1a, which is stable across runs unlike the hasher of the standard library.
+    let hash = uuid.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    });
+    (hash >> 1) as i64
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 /// Represents a review (pull request/merge request) from a forge platform (GitHub, GitLab, etc.).
@@ -361,6 +400,86 @@ impl From<but_github::PullRequest> for ForgeReview {
         }
     }
 }
+
+impl From<but_gitlab::MergeRequest> for ForgeReview {
+    fn from(mr: but_gitlab::MergeRequest) -> Self {
+        ForgeReview {
+            html_url: mr.web_url,
+            number: mr.iid,
+            title: mr.title,
+            body: mr.description,
+            author: mr.author.map(ForgeUser::from),
+            labels: mr
+                .labels
+                .into_iter()
+                .map(|name| ForgeReviewLabel {
+                    name,
+                    description: None,
+                    // GitLab only returns label colors when asked for detailed labels.
+                    color: String::new(),
+                })
+                .collect(),
+            draft: mr.draft,
+            source_branch: mr.source_branch,
+            target_branch: mr.target_branch,
+            sha: mr.sha.unwrap_or_default(),
+            created_at: mr.created_at,
+            modified_at: mr.updated_at,
+            merged_at: mr.merged_at,
+            closed_at: mr.closed_at,
+            repository_ssh_url: None,
+            repository_https_url: None,
+            repo_owner: None,
+            reviewers: mr.reviewers.into_iter().map(ForgeUser::from).collect(),
+            unit_symbol: "!".to_string(),
+            last_sync_at: chrono::Local::now().naive_local(),
+        }
+    }
+}
+
+impl From<but_bitbucket::PullRequest> for ForgeReview {
+    fn from(pr: but_bitbucket::PullRequest) -> Self {
+        // Bitbucket doesn't say when pull requests were merged or closed, but it's their last update.
+        let (merged_at, closed_at) = match pr.state.as_str() {
+            "MERGED" => (pr.updated_on.clone(), None),
+            "DECLINED" | "SUPERSEDED" => (None, pr.updated_on.clone()),
+            _ => (None, None),
+        };
+        let repo_owner = pr
+            .source
+            .repository
+            .as_ref()
+            .and_then(|repo| repo.full_name.split_once('/'))
+            .map(|(owner, _)| owner.to_owned());
+        ForgeReview {
+            html_url: pr.links.html.map(|link| link.href).unwrap_or_default(),
+            number: pr.id,
+            title: pr.title,
+            body: pr.description.filter(|description| !description.is_empty()),
+            author: pr.author.map(ForgeUser::from),
+            // Bitbucket has no labels.
+            labels: Vec::new(),
+            draft: pr.draft,
+            source_branch: pr.source.branch.name,
+            target_branch: pr.destination.branch.name,
+            sha: pr
+                .source
+                .commit
+                .map(|commit| commit.hash)
+                .unwrap_or_default(),
+            created_at: pr.created_on,
+            modified_at: pr.updated_on,
+            merged_at,
+            closed_at,
+            repository_ssh_url: None,
+            repository_https_url: None,
+            repo_owner,
+            reviewers: pr.reviewers.into_iter().map(ForgeUser::from).collect(),
+            unit_symbol: "#".to_string(),
+            last_sync_at: chrono::Local::now().naive_local(),
+        }
+    }
+}
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 #[derive(Default)]
@@ -415,9 +534,8 @@ fn list_forge_reviews(
     } = forge_repo_info;
     let reviews = match forge {
         ForgeName::GitHub => {
-            let preferred_account = preferred_forge_user
-                .as_ref()
-                .and_then(|user| user.github().cloned());
+            let preferred_account =
+                crate::forge::github_account(&preferred_forge_user, forge_repo_info, storage);
 
             // Clone owned data for thread
             let owner = owner.clone();
@@ -442,6 +560,62 @@ fn list_forge_reviews(
                 .map(ForgeReview::from)
                 .collect::<Vec<ForgeReview>>()
         }
+        ForgeName::GitLab => {
+            let preferred_account = preferred_forge_user
+                .as_ref()
+                .and_then(|user| user.gitlab().cloned());
+
+            // Clone owned data for thread
+            let host = forge_repo_info.host.clone();
+            let api_url = forge_repo_info.api_url.clone();
+            let project = format!("{owner}/{repo}");
+            let storage = storage.clone();
+
+            let merge_requests = std::thread::spawn(move || {
+                tokio::runtime::Runtime::new()
+                    .unwrap()
+                    .block_on(but_gitlab::mr::list(
+                        preferred_account.as_ref(),
+                        &host,
+                        api_url.as_deref(),
+                        &project,
+                        &storage,
+                    ))
+            })
+            .join()
+            .map_err(|e| anyhow::anyhow!("Failed to join thread: {:?}", e))??;
+
+            merge_requests
+                .into_iter()
+                .map(ForgeReview::from)
+                .collect::<Vec<ForgeReview>>()
+        }
+        ForgeName::Bitbucket => {
+            let preferred_account = preferred_forge_user
+                .as_ref()
+                .and_then(|user| user.bitbucket().cloned());
+
+            // Clone owned data for thread
+            let repository = format!("{owner}/{repo}");
+            let storage = storage.clone();
+
+            let pulls = std::thread::spawn(move || {
+                tokio::runtime::Runtime::new()
+                    .unwrap()
+                    .block_on(but_bitbucket::pr::list(
+                        preferred_account.as_ref(),
+                        &repository,
+                        &storage,
+                    ))
+            })
+            .join()
+            .map_err(|e| anyhow::anyhow!("Failed to join thread: {:?}", e))??;
+
+            pulls
+                .into_iter()
+                .map(ForgeReview::from)
+                .collect::<Vec<ForgeReview>>()
+        }
         _ => {
             return Err(Error::msg(format!(
                 "Listing reviews for forge {:?} is not implemented yet.",
@@ -475,9 +649,8 @@ pub async fn list_forge_reviews_for_branch(
     } = forge_repo_info;
     match forge {
         ForgeName::GitHub => {
-            let preferred_account = preferred_forge_user
-                .as_ref()
-                .and_then(|user| user.github().cloned());
+            let preferred_account =
+                crate::forge::github_account(&preferred_forge_user, forge_repo_info, storage);
             let prs = but_github::pr::list_all_for_branch(
                 preferred_account.as_ref(),
                 owner,
@@ -487,9 +660,40 @@ pub async fn list_forge_reviews_for_branch(
             )
             .await?;
 
-            let prs = filter_prs(prs, &filter);
+            let reviews = prs.into_iter().map(ForgeReview::from).collect();
+            Ok(filter_reviews(reviews, &filter))
+        }
+        ForgeName::GitLab => {
+            let preferred_account = preferred_forge_user
+                .as_ref()
+                .and_then(|user| user.gitlab().cloned());
+            let merge_requests = but_gitlab::mr::list_all_for_branch(
+                preferred_account.as_ref(),
+                &forge_repo_info.host,
+                forge_repo_info.api_url.as_deref(),
+                &format!("{owner}/{repo}"),
+                branch,
+                storage,
+            )
+            .await?;
 
-            Ok(prs.into_iter().map(ForgeReview::from).collect())
+            let reviews = merge_requests.into_iter().map(ForgeReview::from).collect();
+            Ok(filter_reviews(reviews, &filter))
+        }
+        ForgeName::Bitbucket => {
+            let preferred_account = preferred_forge_user
+                .as_ref()
+                .and_then(|user| user.bitbucket().cloned());
+            let prs = but_bitbucket::pr::list_all_for_branch(
+                preferred_account.as_ref(),
+                &format!("{owner}/{repo}"),
+                branch,
+                storage,
+            )
+            .await?;
+
+            let reviews = prs.into_iter().map(ForgeReview::from).collect();
+            Ok(filter_reviews(reviews, &filter))
         }
         _ => Err(Error::msg(format!(
             "Listing reviews for forge {:?} is not implemented yet.",
@@ -498,19 +702,17 @@ pub async fn list_forge_reviews_for_branch(
     }
 }
 
-fn filter_prs(
-    prs: Vec<but_github::PullRequest>,
-    filter: &ForgeReviewFilter,
-) -> Vec<but_github::PullRequest> {
+fn filter_reviews(reviews: Vec<ForgeReview>, filter: &ForgeReviewFilter) -> Vec<ForgeReview> {
     let now = chrono::Utc::now();
-    prs.into_iter()
-        .filter(|pr| {
-            if pr.merged_at.is_none() {
+    reviews
+        .into_iter()
+        .filter(|review| {
+            if review.merged_at.is_none() {
                 return false;
             }
             match filter {
                 ForgeReviewFilter::Today => {
-                    if let Some(merged_at_str) = &pr.merged_at
+                    if let Some(merged_at_str) = &review.merged_at
                         && let Ok(merged_at) = chrono::DateTime::parse_from_rfc3339(merged_at_str)
                     {
                         return merged_at.date_naive() == now.date_naive();
@@ -518,7 +720,7 @@ fn filter_prs(
                     false
                 }
                 ForgeReviewFilter::ThisWeek => {
-                    if let Some(merged_at_str) = &pr.merged_at
+                    if let Some(merged_at_str) = &review.merged_at
                         && let Ok(merged_at) = chrono::DateTime::parse_from_rfc3339(merged_at_str)
                     {
                         let week_start = now
@@ -528,7 +730,7 @@ fn filter_prs(
                     false
                 }
                 ForgeReviewFilter::ThisMonth => {
-                    if let Some(merged_at_str) = &pr.merged_at
+                    if let Some(merged_at_str) = &review.merged_at
                         && let Ok(merged_at) = chrono::DateTime::parse_from_rfc3339(merged_at_str)
                     {
                         return merged_at.year() == now.year() && merged_at.month() == now.month();
@@ -553,11 +755,39 @@ pub async fn get_forge_review(
     } = forge_repo_info;
     match forge {
         ForgeName::GitHub => {
-            let preferred_account = preferred_forge_user.as_ref().and_then(|user| user.github());
+            let account =
+                crate::forge::github_account(preferred_forge_user, forge_repo_info, storage);
+            let preferred_account = account.as_ref();
             let pr =
                 but_github::pr::get(preferred_account, owner, repo, pr_number, storage).await?;
             Ok(ForgeReview::from(pr))
         }
+        ForgeName::GitLab => {
+            let preferred_account = preferred_forge_user.as_ref().and_then(|user| user.gitlab());
+            let mr = but_gitlab::mr::get(
+                preferred_account,
+                &forge_repo_info.host,
+                forge_repo_info.api_url.as_deref(),
+                &format!("{owner}/{repo}"),
+                pr_number,
+                storage,
+            )
+            .await?;
+            Ok(ForgeReview::from(mr))
+        }
+        ForgeName::Bitbucket => {
+            let preferred_account = preferred_forge_user
+                .as_ref()
+                .and_then(|user| user.bitbucket());
+            let pr = but_bitbucket::pr::get(
+                preferred_account,
+                &format!("{owner}/{repo}"),
+                pr_number,
+                storage,
+            )
+            .await?;
+            Ok(ForgeReview::from(pr))
+        }
         _ => Err(Error::msg(format!(
             "Getting reviews for forge {:?} is not implemented yet.",
             forge,
@@ -598,10 +828,49 @@ pub async fn create_forge_review(
                 base: &params.target_branch,
                 draft: params.draft,
             };
-            let preferred_account = preferred_forge_user.as_ref().and_then(|user| user.github());
+            let account =
+                crate::forge::github_account(preferred_forge_user, forge_repo_info, storage);
+            let preferred_account = account.as_ref();
             let pr = but_github::pr::create(preferred_account, pr_params, storage).await?;
             Ok(ForgeReview::from(pr))
         }
+        ForgeName::GitLab => {
+            let project = format!("{owner}/{repo}");
+            let mr_params = but_gitlab::CreateMergeRequestParams {
+                project: &project,
+                title: &params.title,
+                description: &params.body,
+                source_branch: &params.source_branch,
+                target_branch: &params.target_branch,
+                draft: params.draft,
+            };
+            let preferred_account = preferred_forge_user.as_ref().and_then(|user| user.gitlab());
+            let mr = but_gitlab::mr::create(
+                preferred_account,
+                &forge_repo_info.host,
+                forge_repo_info.api_url.as_deref(),
+                mr_params,
+                storage,
+            )
+            .await?;
+            Ok(ForgeReview::from(mr))
+        }
+        ForgeName::Bitbucket => {
+            let repository = format!("{owner}/{repo}");
+            let pr_params = but_bitbucket::CreatePullRequestParams {
+                repository: &repository,
+                title: &params.title,
+                description: &params.body,
+                source_branch: &params.source_branch,
+                destination_branch: &params.target_branch,
+                draft: params.draft,
+            };
+            let preferred_account = preferred_forge_user
+                .as_ref()
+                .and_then(|user| user.bitbucket());
+            let pr = but_bitbucket::pr::create(preferred_account, pr_params, storage).await?;
+            Ok(ForgeReview::from(pr))
+        }
         _ => Err(Error::msg(format!(
             "Creating reviews for forge {:?} is not implemented yet.",
             forge,
@@ -609,6 +878,103 @@ pub async fn create_forge_review(
     }
 }
 
+/// Change the branch the review with `review_number` is to be merged into to `target_branch`,
+/// as needed when the branch below it in a stack changed.
+pub async fn update_forge_review_base(
+    preferred_forge_user: &Option<crate::ForgeUser>,
+    forge_repo_info: &crate::forge::ForgeRepoInfo,
+    review_number: usize,
+    target_branch: &str,
+    storage: &but_forge_storage::Controller,
+) -> Result<ForgeReview> {
+    let crate::forge::ForgeRepoInfo {
+        forge, owner, repo, ..
+    } = forge_repo_info;
+    match forge {
+        ForgeName::GitHub => {
+            let account =
+                crate::forge::github_account(preferred_forge_user, forge_repo_info, storage);
+            let preferred_account = account.as_ref();
+            let pr = but_github::pr::update_base(
+                preferred_account,
+                owner,
+                repo,
+                review_number,
+                target_branch,
+                storage,
+            )
+            .await?;
+            Ok(ForgeReview::from(pr))
+        }
+        ForgeName::GitLab => {
+            let preferred_account = preferred_forge_user.as_ref().and_then(|user| user.gitlab());
+            let mr = but_gitlab::mr::update_target_branch(
+                preferred_account,
+                &forge_repo_info.host,
+                forge_repo_info.api_url.as_deref(),
+                &format!("{owner}/{repo}"),
+                review_number,
+                target_branch,
+                storage,
+            )
+            .await?;
+            Ok(ForgeReview::from(mr))
+        }
+        _ => Err(Error::msg(format!(
+            "Updating the base of reviews for forge {:?} is not implemented yet.",
+            forge,
+        ))),
+    }
+}
+
+/// Replace the description of the review with `review_number` with `body`.
+pub async fn update_forge_review_body(
+    preferred_forge_user: &Option<crate::ForgeUser>,
+    forge_repo_info: &crate::forge::ForgeRepoInfo,
+    review_number: usize,
+    body: &str,
+    storage: &but_forge_storage::Controller,
+) -> Result<ForgeReview> {
+    let crate::forge::ForgeRepoInfo {
+        forge, owner, repo, ..
+    } = forge_repo_info;
+    match forge {
+        ForgeName::GitHub => {
+            let account =
+                crate::forge::github_account(preferred_forge_user, forge_repo_info, storage);
+            let preferred_account = account.as_ref();
+            let pr = but_github::pr::update_body(
+                preferred_account,
+                owner,
+                repo,
+                review_number,
+                body,
+                storage,
+            )
+            .await?;
+            Ok(ForgeReview::from(pr))
+        }
+        ForgeName::GitLab => {
+            let preferred_account = preferred_forge_user.as_ref().and_then(|user| user.gitlab());
+            let mr = but_gitlab::mr::update_description(
+                preferred_account,
+                &forge_repo_info.host,
+                forge_repo_info.api_url.as_deref(),
+                &format!("{owner}/{repo}"),
+                review_number,
+                body,
+                storage,
+            )
+            .await?;
+            Ok(ForgeReview::from(mr))
+        }
+        _ => Err(Error::msg(format!(
+            "Updating the description of reviews for forge {:?} is not implemented yet.",
+            forge,
+        ))),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::path::Path;
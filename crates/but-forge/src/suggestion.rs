@@ -0,0 +1,278 @@
+use anyhow::{Error, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{forge::ForgeName, review::ForgeUser};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+/// A change proposed by a reviewer as part of a review comment, e.g. a GitHub ```` ```suggestion ```` block.
+///
+/// Applying it means replacing the lines `start_line..=end_line` of the file at `path` with `replacement`.
+pub struct ReviewSuggestion {
+    /// The id of the review comment the suggestion was taken from.
+    pub comment_id: i64,
+    /// The URL to view the review comment in a web browser.
+    pub html_url: String,
+    /// The reviewer who made the suggestion.
+    pub author: Option<ForgeUser>,
+    /// The path of the file to change, relative to the repository root.
+    pub path: String,
+    /// The sha of the commit the suggestion was made on.
+    pub commit_id: String,
+    /// The first line to replace, 1-based.
+    pub start_line: u32,
+    /// The last line to replace, 1-based and inclusive.
+    pub end_line: u32,
+    /// The lines to put in place of `start_line..=end_line`, without trailing newline.
+    /// It's empty if the reviewer suggested to remove the lines.
+    pub replacement: String,
+}
+
+impl ReviewSuggestion {
+    /// Return `true` if the lines `start_line..=end_line` of `content`, the current content of the file at [`Self::path`],
+    /// are the same as in `original`, the content of the file at [`Self::commit_id`] which the reviewer saw.
+    ///
+    /// Line endings are ignored. If this is `false`, the lines were changed or moved since the review,
+    /// and applying the suggestion would overwrite the wrong lines.
+    pub fn matches_original(&self, content: &str, original: &str) -> bool {
+        let (start, end) = (self.start_line as usize, self.end_line as usize);
+        if start == 0 || start > end {
+            return false;
+        }
+        let target_lines = |text: &str| -> Option<Vec<&str>> {
+            let lines: Vec<&str> = text.lines().collect();
+            lines.get(start - 1..end).map(<[&str]>::to_vec)
+        };
+        match (target_lines(content), target_lines(original)) {
+            (Some(current), Some(original)) => current == original,
+            _ => false,
+        }
+    }
+
+    /// Apply this suggestion to `content`, the current content of the file at [`Self::path`], and return the new content.
+    /// Return `None` if the line range of this suggestion is out of bounds.
+    ///
+    /// The line endings used by `content` are also used for the replaced lines.
+    pub fn apply_to(&self, content: &str) -> Option<String> {
+        let lines: Vec<&str> = content.split_inclusive('\n').collect();
+        let (start, end) = (self.start_line as usize, self.end_line as usize);
+        if start == 0 || start > end || end > lines.len() {
+            return None;
+        }
+        let newline = if content.contains("\r\n") {
+            "\r\n"
+        } else {
+            "\n"
+        };
+        let last_replaced_line_has_newline = lines[end - 1].ends_with('\n');
+
+        let mut out = String::with_capacity(content.len() + self.replacement.len());
+        for line in &lines[..start - 1] {
+            out.push_str(line);
+        }
+        let mut replacement_lines = self.replacement.lines().peekable();
+        while let Some(line) = replacement_lines.next() {
+            out.push_str(line);
+            if replacement_lines.peek().is_some() || last_replaced_line_has_newline {
+                out.push_str(newline);
+            }
+        }
+        for line in &lines[end..] {
+            out.push_str(line);
+        }
+        Some(out)
+    }
+}
+
+/// Extract the content of all suggestion blocks in the markdown `body` of a review comment.
+///
+/// Each block is returned without its fences, and without trailing newline.
+pub fn parse_suggestion_blocks(body: &str) -> Vec<String> {
+    let mut blocks = Vec::new();
+    let mut current: Option<(usize, Vec<&str>)> = None;
+    for line in body.lines() {
+        let trimmed = line.trim();
+        match current.as_mut() {
+            None => {
+                let fence_len = trimmed.chars().take_while(|c| *c == '`').count();
+                if fence_len >= 3 && trimmed[fence_len..].trim() == "suggestion" {
+                    current = Some((fence_len, Vec::new()));
+                }
+            }
+            Some((fence_len, lines)) => {
+                let is_closing_fence = trimmed.len() >= *fence_len
+                    && trimmed.chars().all(|c| c == '`')
+                    && !trimmed.is_empty();
+                if is_closing_fence {
+                    blocks.push(lines.join("\n"));
+                    current = None;
+                } else {
+                    lines.push(line);
+                }
+            }
+        }
+    }
+    blocks
+}
+
+impl TryFrom<but_github::PullRequestReviewComment> for ReviewSuggestion {
+    type Error = Error;
+
+    /// Convert a comment with exactly one suggestion block, and which isn't outdated.
+    fn try_from(comment: but_github::PullRequestReviewComment) -> Result<Self> {
+        let end_line = comment
+            .line
+            .ok_or_else(|| Error::msg("Review comment is outdated"))?;
+        let start_line = comment.start_line.unwrap_or(end_line);
+        let replacement = match parse_suggestion_blocks(&comment.body).as_slice() {
+            [] => return Err(Error::msg("Review comment doesn't contain a suggestion")),
+            [suggestion] => suggestion.clone(),
+            _ => {
+                return Err(Error::msg(
+                    "Review comment contains more than one suggestion",
+                ));
+            }
+        };
+        Ok(ReviewSuggestion {
+            comment_id: comment.id,
+            html_url: comment.html_url,
+            author: comment.author.map(ForgeUser::from),
+            path: comment.path,
+            commit_id: comment.commit_id,
+            start_line: start_line.try_into()?,
+            end_line: end_line.try_into()?,
+            replacement,
+        })
+    }
+}
+
+/// List all applicable suggestions made in review comments of the review with `review_number`.
+///
+/// Comments without suggestions, outdated comments and comments with more than one suggestion are skipped.
+pub async fn list_forge_review_suggestions(
+    preferred_forge_user: &Option<crate::ForgeUser>,
+    forge_repo_info: &crate::forge::ForgeRepoInfo,
+    review_number: usize,
+    storage: &but_forge_storage::Controller,
+) -> Result<Vec<ReviewSuggestion>> {
+    let crate::forge::ForgeRepoInfo {
+        forge, owner, repo, ..
+    } = forge_repo_info;
+    match forge {
+        ForgeName::GitHub => {
+            let account =
+                crate::forge::github_account(preferred_forge_user, forge_repo_info, storage);
+            let preferred_account = account.as_ref();
+            let comments = but_github::pr::list_review_comments(
+                preferred_account,
+                owner,
+                repo,
+                review_number,
+                storage,
+            )
+            .await?;
+            Ok(comments
+                .into_iter()
+                .filter_map(|comment| ReviewSuggestion::try_from(comment).ok())
+                .collect())
+        }
+        _ => Err(Error::msg(format!(
+            "Listing review suggestions for forge {:?} is not implemented yet.",
+            forge,
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn suggestion(start_line: u32, end_line: u32, replacement: &str) -> ReviewSuggestion {
+        ReviewSuggestion {
+            comment_id: 1,
+            html_url: String::new(),
+            author: None,
+            path: "file".into(),
+            commit_id: String::new(),
+            start_line,
+            end_line,
+            replacement: replacement.into(),
+        }
+    }
+
+    #[test]
+    fn parse_single_block() {
+        let body = "Consider this:\n```suggestion\nlet a = 1;\nlet b = 2;\n```\nThanks!";
+        assert_eq!(
+            parse_suggestion_blocks(body),
+            vec!["let a = 1;\nlet b = 2;"]
+        );
+    }
+
+    #[test]
+    fn parse_empty_block_and_long_fences() {
+        let body = "````suggestion\n```\nnested\n```\n````\n```suggestion\n```";
+        assert_eq!(parse_suggestion_blocks(body), vec!["```\nnested\n```", ""]);
+    }
+
+    #[test]
+    fn parse_ignores_other_code_blocks() {
+        let body = "```rust\nfn main() {}\n```";
+        assert!(parse_suggestion_blocks(body).is_empty());
+    }
+
+    #[test]
+    fn apply_replaces_line_range() {
+        let content = "one\ntwo\nthree\nfour\n";
+        assert_eq!(
+            suggestion(2, 3, "TWO\nTHREE\nTHREE-AND-A-HALF").apply_to(content),
+            Some("one\nTWO\nTHREE\nTHREE-AND-A-HALF\nfour\n".into())
+        );
+    }
+
+    #[test]
+    fn apply_removes_lines_with_empty_replacement() {
+        let content = "one\ntwo\nthree\n";
+        assert_eq!(
+            suggestion(2, 2, "").apply_to(content),
+            Some("one\nthree\n".into())
+        );
+    }
+
+    #[test]
+    fn apply_keeps_line_endings() {
+        assert_eq!(
+            suggestion(1, 1, "ONE").apply_to("one\r\ntwo"),
+            Some("ONE\r\ntwo".into())
+        );
+        assert_eq!(
+            suggestion(2, 2, "TWO").apply_to("one\ntwo"),
+            Some("one\nTWO".into()),
+            "no newline is added if there was none"
+        );
+    }
+
+    #[test]
+    fn matches_original_compares_target_lines_only() {
+        let original = "one\ntwo\nthree\n";
+        let s = suggestion(2, 3, "x");
+        assert!(s.matches_original("ONE\r\ntwo\r\nthree", original));
+        assert!(
+            !s.matches_original("one\nTWO\nthree\n", original),
+            "a target line was changed"
+        );
+        assert!(
+            !s.matches_original("zero\none\ntwo\nthree\n", original),
+            "the target lines moved"
+        );
+        assert!(!s.matches_original("one\ntwo\n", original), "out of bounds");
+        assert!(!suggestion(0, 1, "x").matches_original(original, original));
+    }
+
+    #[test]
+    fn apply_out_of_bounds() {
+        assert_eq!(suggestion(3, 3, "x").apply_to("one\ntwo\n"), None);
+        assert_eq!(suggestion(0, 1, "x").apply_to("one\n"), None);
+        assert_eq!(suggestion(2, 1, "x").apply_to("one\ntwo\n"), None);
+    }
+}
@@ -50,12 +50,32 @@ impl Display for GerritChangeId {
     }
 }
 
+/// Add a `Change-Id` trailer to the message of `commit` that is derived from its change-id header,
+/// unless it already has one, which is kept so rewritten commits keep updating the same Gerrit change.
+///
+/// Commits without change-id header, like those created with plain Git, get a new one first.
 pub fn set_trailers(commit: &mut gix::objs::Commit) {
-    if let Some(headers) = Headers::try_from_commit(commit)
-        && let Some(change_id) = headers.change_id
-    {
-        commit.message = with_change_id_trailer(commit.message.clone(), change_id);
+    if has_change_id_trailer(commit.message.as_bstr()) {
+        return;
     }
+    let mut headers = Headers::try_from_commit(commit).unwrap_or(Headers {
+        change_id: None,
+        conflicted: None,
+    });
+    let change_id = match headers.change_id.clone() {
+        Some(change_id) => change_id,
+        None => {
+            let change_id = ChangeId::generate();
+            headers.change_id = Some(change_id.clone());
+            headers.set_in_commit(commit);
+            change_id
+        }
+    };
+    commit.message = with_change_id_trailer(commit.message.clone(), change_id);
+}
+
+fn has_change_id_trailer(msg: &bstr::BStr) -> bool {
+    msg.lines().any(|line| line.starts_with(b"Change-Id: I"))
 }
 
 fn with_change_id_trailer(msg: BString, change_id: ChangeId) -> BString {
@@ -260,6 +280,71 @@ fn gerrit_host(repo: &gix::Repository) -> Option<String> {
 mod tests {
     use super::*;
 
+    fn commit(message: &str, headers: Option<&Headers>) -> gix::objs::Commit {
+        let signature = gix::actor::Signature::default();
+        let mut commit = gix::objs::Commit {
+            tree: gix::ObjectId::empty_tree(gix::hash::Kind::Sha1),
+            parents: Default::default(),
+            author: signature.clone(),
+            committer: signature,
+            encoding: None,
+            message: message.into(),
+            extra_headers: vec![],
+        };
+        if let Some(headers) = headers {
+            headers.set_in_commit(&mut commit);
+        }
+        commit
+    }
+
+    #[test]
+    fn trailers_are_derived_from_change_id_headers() {
+        let change_id = ChangeId::generate();
+        let mut commit = commit(
+            "title\n",
+            Some(&Headers {
+                change_id: Some(change_id.clone()),
+                conflicted: None,
+            }),
+        );
+        set_trailers(&mut commit);
+        assert_eq!(
+            commit.message,
+            format!("title\n\nChange-Id: {}\n", GerritChangeId::from(&change_id))
+        );
+    }
+
+    #[test]
+    fn existing_trailers_are_preserved() {
+        let message = "title\n\nChange-Id: I0123456789012345678901234567890123456789\n";
+        let mut commit = commit(
+            message,
+            Some(&Headers {
+                change_id: Some(ChangeId::generate()),
+                conflicted: None,
+            }),
+        );
+        set_trailers(&mut commit);
+        assert_eq!(
+            commit.message, message,
+            "the change on Gerrit is identified by the trailer it was pushed with"
+        );
+    }
+
+    #[test]
+    fn commits_without_change_id_get_one() {
+        let mut commit = commit("title\n", None);
+        set_trailers(&mut commit);
+        let change_id = Headers::try_from_commit(&commit)
+            .and_then(|headers| headers.change_id)
+            .expect("a change-id header was added");
+        assert!(
+            commit
+                .message
+                .ends_with(format!("Change-Id: {}\n", GerritChangeId::from(&change_id)).as_bytes())
+        );
+    }
+
     #[test]
     fn output_is_41_characters_long() {
         let commit_change_id = ChangeId::generate();
@@ -5,29 +5,69 @@ use octorust::{
     auth::Credentials,
     types::{ActionsListJobsWorkflowRunFilter, JobStatus, UsersGetByUsernameResponseOneOf},
 };
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+
+/// The URL to ask Git credential helpers for a GitHub token with.
+const GITHUB_URL: &str = "https://github.com";
+/// The URL of the REST API of github.com.
+const GITHUB_API_URL: &str = "https://api.github.com";
+const USER_AGENT: &str = "gb-github-integration";
 
 pub struct GitHubClient {
     github: Client,
+    /// The client and base URL for requests that `octorust` can't make, like conditional ones.
+    http: reqwest::Client,
+    api_url: String,
+    access_token: Sensitive<String>,
+}
+
+/// The outcome of a request made with the `ETag` of a previous response.
+#[derive(Debug, Clone)]
+pub enum Conditional<T> {
+    /// Nothing changed since the response with the given `ETag`.
+    NotModified,
+    /// The resource changed, or there was no `ETag` to compare with.
+    Modified {
+        value: T,
+        /// The `ETag` to pass with the next request for the same resource.
+        etag: Option<String>,
+    },
 }
 
 impl GitHubClient {
     pub fn new(access_token: &Sensitive<String>) -> Result<Self> {
         let github = Client::new(
-            String::from("gb-github-integration"),
+            String::from(USER_AGENT),
             Credentials::Token(access_token.to_string()),
         )?;
 
-        Ok(Self { github })
+        Ok(Self {
+            github,
+            http: reqwest::Client::new(),
+            api_url: GITHUB_API_URL.to_owned(),
+            access_token: access_token.clone(),
+        })
     }
 
+    /// Create a client for `preferred_account` or the first known account in `storage`.
+    ///
+    /// Without any known account, the token is obtained from the Git credential helpers for `github.com`,
+    /// which includes `gh auth git-credential` if the GitHub CLI is set up for Git.
     pub fn from_storage(
         storage: &but_forge_storage::Controller,
         preferred_account: Option<&crate::GithubAccountIdentifier>,
     ) -> anyhow::Result<Self> {
+        if preferred_account.is_none()
+            && crate::token::list_known_github_accounts(storage)?.is_empty()
+            && let Some(credential) =
+                but_secret::git_credentials::fill(GITHUB_URL, None).unwrap_or_default()
+        {
+            return GitHubClient::new(&credential.password);
+        }
         let account_id = resolve_account(preferred_account, storage)?;
         if let Some(access_token) = crate::token::get_gh_access_token(&account_id, storage)? {
-            Ok(GitHubClient::new(&access_token)?)
+            // Enterprise accounts talk to the API of their instance.
+            account_id.client(&access_token)
         } else {
             Err(anyhow::anyhow!(
                 "No GitHub access token found for account '{}'.\nPlease, try to re-authenticate with this account.",
@@ -38,13 +78,18 @@ impl GitHubClient {
 
     pub fn new_with_host_override(access_token: &Sensitive<String>, host: &str) -> Result<Self> {
         let github = Client::new(
-            String::from("gb-github-integration"),
+            String::from(USER_AGENT),
             Credentials::Token(access_token.to_string()),
         )?
         .with_host_override(host)
         .to_owned();
 
-        Ok(Self { github })
+        Ok(Self {
+            github,
+            http: reqwest::Client::new(),
+            api_url: host.trim_end_matches('/').to_owned(),
+            access_token: access_token.clone(),
+        })
     }
 
     pub async fn get_authenticated(&self) -> Result<AuthenticatedUser, octorust::ClientError> {
@@ -105,6 +150,58 @@ impl GitHubClient {
             .map(|r| r.check_runs)
     }
 
+    /// Like [`Self::list_checks_for_ref()`], but return [`Conditional::NotModified`] if the checks didn't
+    /// change since the response with `etag`.
+    ///
+    /// Requests answered with *not modified* don't count against the rate limit.
+    pub async fn list_checks_for_ref_if_modified(
+        &self,
+        owner: &str,
+        repo: &str,
+        reference: &str,
+        etag: Option<&str>,
+    ) -> Result<Conditional<Vec<octorust::types::CheckRun>>> {
+        #[derive(Deserialize)]
+        struct CheckRuns {
+            check_runs: Vec<octorust::types::CheckRun>,
+        }
+
+        let mut url = reqwest::Url::parse(&self.api_url)?;
+        url.path_segments_mut()
+            .map_err(|()| anyhow::anyhow!("Invalid GitHub API URL: {}", self.api_url))?
+            .pop_if_empty()
+            .extend(["repos", owner, repo, "commits", reference, "check-runs"]);
+        let mut request = self
+            .http
+            .get(url)
+            .query(&[("filter", "latest"), ("per_page", "100")])
+            .bearer_auth(self.access_token.as_str())
+            .header(reqwest::header::USER_AGENT, USER_AGENT)
+            .header(reqwest::header::ACCEPT, "application/vnd.github+json");
+        if let Some(etag) = etag {
+            request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+        }
+        let response = request.send().await?;
+        if response.status() == reqwest::StatusCode::NOT_MODIFIED {
+            return Ok(Conditional::NotModified);
+        }
+        let status = response.status();
+        if !status.is_success() {
+            let message = response.text().await.unwrap_or_default();
+            bail!("Failed to list checks for ref: {status}: {message}");
+        }
+        let etag = response
+            .headers()
+            .get(reqwest::header::ETAG)
+            .and_then(|etag| etag.to_str().ok())
+            .map(ToOwned::to_owned);
+        let CheckRuns { check_runs } = response.json().await?;
+        Ok(Conditional::Modified {
+            value: check_runs,
+            etag,
+        })
+    }
+
     pub async fn list_open_pulls(&self, owner: &str, repo: &str) -> Result<Vec<PullRequest>> {
         let pulls = self
             .github
@@ -200,6 +297,92 @@ impl GitHubClient {
 
         Ok(pr.into())
     }
+
+    /// Change the branch the pull request with `pr_number` is to be merged into to `base`.
+    pub async fn update_pull_request_base(
+        &self,
+        owner: &str,
+        repo: &str,
+        pr_number: i64,
+        base: &str,
+    ) -> Result<PullRequest> {
+        let pr = self
+            .github
+            .pulls()
+            .update(
+                owner,
+                repo,
+                pr_number,
+                &octorust::types::PullsUpdateRequest {
+                    base: base.to_string(),
+                    body: String::new(),
+                    maintainer_can_modify: None,
+                    state: None,
+                    title: String::new(),
+                },
+            )
+            .await
+            .map(|response| response.body)
+            .map_err(anyhow::Error::from)?;
+
+        Ok(pr.into())
+    }
+
+    /// Replace the description of the pull request with `pr_number` with `body`.
+    pub async fn update_pull_request_body(
+        &self,
+        owner: &str,
+        repo: &str,
+        pr_number: i64,
+        body: &str,
+    ) -> Result<PullRequest> {
+        let pr = self
+            .github
+            .pulls()
+            .update(
+                owner,
+                repo,
+                pr_number,
+                &octorust::types::PullsUpdateRequest {
+                    base: String::new(),
+                    body: body.to_string(),
+                    maintainer_can_modify: None,
+                    state: None,
+                    title: String::new(),
+                },
+            )
+            .await
+            .map(|response| response.body)
+            .map_err(anyhow::Error::from)?;
+
+        Ok(pr.into())
+    }
+
+    pub async fn list_review_comments(
+        &self,
+        owner: &str,
+        repo: &str,
+        pr_number: i64,
+    ) -> Result<Vec<PullRequestReviewComment>> {
+        let comments = self
+            .github
+            .pulls()
+            .list_all_review_comments(
+                owner,
+                repo,
+                pr_number,
+                octorust::types::Sort::Created,
+                octorust::types::Order::Asc,
+                None,
+            )
+            .await
+            .map(|response| response.body)?
+            .into_iter()
+            .map(Into::into)
+            .collect();
+
+        Ok(comments)
+    }
 }
 
 pub struct CreatePullRequestParams<'a> {
@@ -243,6 +426,40 @@ impl From<octorust::types::SimpleUser> for GitHubUser {
     }
 }
 
+#[derive(Debug, Serialize)]
+pub struct PullRequestReviewComment {
+    pub id: i64,
+    pub html_url: String,
+    pub author: Option<GitHubUser>,
+    pub body: String,
+    /// The path of the file the comment refers to, relative to the repository root.
+    pub path: String,
+    /// The sha of the commit the comment was made on.
+    pub commit_id: String,
+    /// The first line of a multi-line comment, or `None` if the comment is on a single line.
+    pub start_line: Option<i64>,
+    /// The last line the comment refers to, or `None` if the comment is outdated.
+    pub line: Option<i64>,
+    /// The comment this comment is a reply to, if any.
+    pub in_reply_to_id: Option<i64>,
+}
+
+impl From<octorust::types::PullRequestReviewComment> for PullRequestReviewComment {
+    fn from(comment: octorust::types::PullRequestReviewComment) -> Self {
+        PullRequestReviewComment {
+            id: comment.id,
+            html_url: comment.html_url,
+            author: comment.user.map(Into::into),
+            body: comment.body,
+            path: comment.path,
+            commit_id: comment.commit_id,
+            start_line: (comment.start_line > 0).then_some(comment.start_line),
+            line: (comment.line > 0).then_some(comment.line),
+            in_reply_to_id: (comment.in_reply_to_id > 0).then_some(comment.in_reply_to_id),
+        }
+    }
+}
+
 #[derive(Debug, Serialize)]
 pub struct GitHubPrLabel {
     pub id: i64,
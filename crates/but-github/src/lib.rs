@@ -7,9 +7,12 @@ use serde::{Deserialize, Serialize};
 
 mod client;
 pub mod pr;
-pub use client::{CreatePullRequestParams, GitHubClient, GitHubPrLabel, GitHubUser, PullRequest};
+pub use client::{
+    Conditional, CreatePullRequestParams, GitHubClient, GitHubPrLabel, GitHubUser, PullRequest,
+    PullRequestReviewComment,
+};
 mod token;
-pub use token::GithubAccountIdentifier;
+pub use token::{GithubAccountIdentifier, enterprise_account_for_api_url};
 
 #[derive(Debug, Deserialize, Serialize, Clone, Default)]
 pub struct Verification {
@@ -58,3 +58,51 @@ pub async fn get(
         .context("Failed to get pull request")?;
     Ok(pr)
 }
+
+/// Change the base branch of the pull request with `pr_number` to `base`.
+pub async fn update_base(
+    preferred_account: Option<&crate::GithubAccountIdentifier>,
+    owner: &str,
+    repo: &str,
+    pr_number: usize,
+    base: &str,
+    storage: &but_forge_storage::Controller,
+) -> Result<crate::client::PullRequest> {
+    let pr_number = pr_number.try_into().context("PR number is too large")?;
+    let pr = GitHubClient::from_storage(storage, preferred_account)?
+        .update_pull_request_base(owner, repo, pr_number, base)
+        .await
+        .context("Failed to update the base of the pull request")?;
+    Ok(pr)
+}
+
+pub async fn update_body(
+    preferred_account: Option<&crate::GithubAccountIdentifier>,
+    owner: &str,
+    repo: &str,
+    pr_number: usize,
+    body: &str,
+    storage: &but_forge_storage::Controller,
+) -> Result<crate::client::PullRequest> {
+    let pr_number = pr_number.try_into().context("PR number is too large")?;
+    let pr = GitHubClient::from_storage(storage, preferred_account)?
+        .update_pull_request_body(owner, repo, pr_number, body)
+        .await
+        .context("Failed to update the description of the pull request")?;
+    Ok(pr)
+}
+
+pub async fn list_review_comments(
+    preferred_account: Option<&crate::GithubAccountIdentifier>,
+    owner: &str,
+    repo: &str,
+    pr_number: usize,
+    storage: &but_forge_storage::Controller,
+) -> Result<Vec<crate::client::PullRequestReviewComment>> {
+    let pr_number = pr_number.try_into().context("PR number is too large")?;
+    let comments = GitHubClient::from_storage(storage, preferred_account)?
+        .list_review_comments(owner, repo, pr_number)
+        .await
+        .context("Failed to list review comments")?;
+    Ok(comments)
+}
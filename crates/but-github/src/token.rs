@@ -96,6 +96,27 @@ impl GithubAccountIdentifier {
             }
         }
     }
+
+    /// Return `true` if this is an account of the GitHub Enterprise instance whose API is at `api_url`.
+    pub fn is_for_api_url(&self, api_url: &str) -> bool {
+        match self {
+            GithubAccountIdentifier::Enterprise { host, .. } => host
+                .trim_end_matches('/')
+                .eq_ignore_ascii_case(api_url.trim_end_matches('/')),
+            GithubAccountIdentifier::OAuthUsername { .. }
+            | GithubAccountIdentifier::PatUsername { .. } => false,
+        }
+    }
+}
+
+/// Return the first known account of the GitHub Enterprise instance whose API is at `api_url`.
+pub fn enterprise_account_for_api_url(
+    api_url: &str,
+    storage: &but_forge_storage::Controller,
+) -> Result<Option<GithubAccountIdentifier>> {
+    Ok(list_known_github_accounts(storage)?
+        .into_iter()
+        .find(|account| account.is_for_api_url(api_url)))
 }
 
 impl std::fmt::Display for GithubAccountIdentifier {
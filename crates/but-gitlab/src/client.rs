@@ -0,0 +1,305 @@
+use anyhow::{Context as _, Result, bail};
+use but_secret::Sensitive;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+
+/// A client for the REST API of gitlab.com or a self-managed GitLab instance.
+pub struct GitLabClient {
+    http: reqwest::Client,
+    /// The base URL of the API, like `https://gitlab.com/api/v4`.
+    api_url: String,
+    access_token: Sensitive<String>,
+}
+
+impl GitLabClient {
+    /// Create a client for the GitLab instance at `host`, like `gitlab.com`, which authenticates with `access_token`.
+    pub fn new(host: &str, access_token: &Sensitive<String>) -> Self {
+        GitLabClient {
+            http: reqwest::Client::new(),
+            api_url: format!("https://{host}/api/v4"),
+            access_token: access_token.clone(),
+        }
+    }
+
+    /// Use the API at `api_url`, like `https://git.example.com/gitlab/api/v4`, instead of its default location.
+    pub fn with_api_url(mut self, api_url: &str) -> Self {
+        self.api_url = api_url.trim_end_matches('/').to_owned();
+        self
+    }
+
+    pub async fn get_authenticated(&self) -> Result<GitLabUser> {
+        self.get("/user", &[]).await
+    }
+
+    pub async fn list_open_merge_requests(&self, project: &str) -> Result<Vec<MergeRequest>> {
+        self.get(
+            &format!("/projects/{}/merge_requests", encode_project(project)),
+            &[("state", "opened"), ("per_page", "100")],
+        )
+        .await
+    }
+
+    pub async fn list_merge_requests_for_source_branch(
+        &self,
+        project: &str,
+        source_branch: &str,
+    ) -> Result<Vec<MergeRequest>> {
+        self.get(
+            &format!("/projects/{}/merge_requests", encode_project(project)),
+            &[
+                ("source_branch", source_branch),
+                ("state", "all"),
+                ("per_page", "100"),
+            ],
+        )
+        .await
+    }
+
+    pub async fn get_merge_request(&self, project: &str, iid: i64) -> Result<MergeRequest> {
+        self.get(
+            &format!("/projects/{}/merge_requests/{iid}", encode_project(project)),
+            &[],
+        )
+        .await
+    }
+
+    pub async fn create_merge_request(
+        &self,
+        params: &CreateMergeRequestParams<'_>,
+    ) -> Result<MergeRequest> {
+        #[derive(Serialize)]
+        struct Body<'a> {
+            source_branch: &'a str,
+            target_branch: &'a str,
+            title: String,
+            description: &'a str,
+            remove_source_branch: bool,
+        }
+        // Drafts are marked by their title, which works with all versions of GitLab.
+        let title = if params.draft {
+            format!("Draft: {}", params.title)
+        } else {
+            params.title.to_owned()
+        };
+        let body = Body {
+            source_branch: params.source_branch,
+            target_branch: params.target_branch,
+            title,
+            description: params.description,
+            remove_source_branch: false,
+        };
+        self.send(
+            self.http.post(format!(
+                "{}/projects/{}/merge_requests",
+                self.api_url,
+                encode_project(params.project)
+            )),
+            Some(&body),
+        )
+        .await
+    }
+
+    /// Change the branch the merge request with `iid` is to be merged into to `target_branch`.
+    pub async fn update_merge_request_target_branch(
+        &self,
+        project: &str,
+        iid: i64,
+        target_branch: &str,
+    ) -> Result<MergeRequest> {
+        #[derive(Serialize)]
+        struct Body<'a> {
+            target_branch: &'a str,
+        }
+        self.send(
+            self.http.put(format!(
+                "{}/projects/{}/merge_requests/{iid}",
+                self.api_url,
+                encode_project(project)
+            )),
+            Some(&Body { target_branch }),
+        )
+        .await
+    }
+
+    /// Replace the description of the merge request with `iid` with `description`.
+    pub async fn update_merge_request_description(
+        &self,
+        project: &str,
+        iid: i64,
+        description: &str,
+    ) -> Result<MergeRequest> {
+        #[derive(Serialize)]
+        struct Body<'a> {
+            description: &'a str,
+        }
+        self.send(
+            self.http.put(format!(
+                "{}/projects/{}/merge_requests/{iid}",
+                self.api_url,
+                encode_project(project)
+            )),
+            Some(&Body { description }),
+        )
+        .await
+    }
+
+    /// Return the most recent pipeline that ran for `reference`, a branch name or commit.
+    pub async fn latest_pipeline_for_ref(
+        &self,
+        project: &str,
+        reference: &str,
+    ) -> Result<Option<Pipeline>> {
+        let pipelines: Vec<Pipeline> = self
+            .get(
+                &format!("/projects/{}/pipelines", encode_project(project)),
+                &[("ref", reference), ("per_page", "1")],
+            )
+            .await?;
+        Ok(pipelines.into_iter().next())
+    }
+
+    pub async fn list_pipeline_jobs(
+        &self,
+        project: &str,
+        pipeline_id: i64,
+    ) -> Result<Vec<PipelineJob>> {
+        self.get(
+            &format!(
+                "/projects/{}/pipelines/{pipeline_id}/jobs",
+                encode_project(project)
+            ),
+            &[("per_page", "100")],
+        )
+        .await
+    }
+
+    async fn get<T: DeserializeOwned>(&self, path: &str, query: &[(&str, &str)]) -> Result<T> {
+        self.send::<T, ()>(
+            self.http
+                .get(format!("{}{path}", self.api_url))
+                .query(query),
+            None,
+        )
+        .await
+    }
+
+    async fn send<T: DeserializeOwned, B: Serialize>(
+        &self,
+        request: reqwest::RequestBuilder,
+        body: Option<&B>,
+    ) -> Result<T> {
+        let mut request = request.header("PRIVATE-TOKEN", self.access_token.as_str());
+        if let Some(body) = body {
+            request = request.json(body);
+        }
+        let response = request
+            .send()
+            .await
+            .context("Failed to send request to GitLab")?;
+        let status = response.status();
+        if !status.is_success() {
+            let message = response.text().await.unwrap_or_default();
+            bail!("GitLab responded with {status}: {message}");
+        }
+        response
+            .json()
+            .await
+            .context("Failed to parse the response of GitLab")
+    }
+}
+
+/// Encode the full path of a project, like `group/subgroup/repo`, to be used as its ID in API paths.
+fn encode_project(project: &str) -> String {
+    let mut out = String::with_capacity(project.len());
+    for byte in project.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                out.push(byte as char)
+            }
+            _ => out.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    out
+}
+
+pub struct CreateMergeRequestParams<'a> {
+    /// The full path of the project, like `group/repo`.
+    pub project: &'a str,
+    pub title: &'a str,
+    pub description: &'a str,
+    pub source_branch: &'a str,
+    pub target_branch: &'a str,
+    pub draft: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitLabUser {
+    pub id: i64,
+    pub username: String,
+    pub name: Option<String>,
+    #[serde(default)]
+    pub public_email: Option<String>,
+    pub avatar_url: Option<String>,
+    #[serde(default)]
+    pub bot: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MergeRequest {
+    /// The number of the merge request within its project.
+    pub iid: i64,
+    pub web_url: String,
+    pub title: String,
+    pub description: Option<String>,
+    pub author: Option<GitLabUser>,
+    #[serde(default)]
+    pub labels: Vec<String>,
+    #[serde(default)]
+    pub draft: bool,
+    pub source_branch: String,
+    pub target_branch: String,
+    pub sha: Option<String>,
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
+    pub merged_at: Option<String>,
+    pub closed_at: Option<String>,
+    #[serde(default)]
+    pub reviewers: Vec<GitLabUser>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Pipeline {
+    pub id: i64,
+    /// One of `created`, `waiting_for_resource`, `preparing`, `pending`, `running`, `success`, `failed`,
+    /// `canceled`, `skipped`, `manual` or `scheduled`.
+    pub status: String,
+    pub sha: String,
+    pub web_url: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PipelineJob {
+    pub id: i64,
+    pub name: String,
+    pub stage: String,
+    /// Uses the same values as [`Pipeline::status`].
+    pub status: String,
+    pub web_url: String,
+    pub started_at: Option<String>,
+    pub finished_at: Option<String>,
+    #[serde(default)]
+    pub allow_failure: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn project_paths_are_encoded_as_ids() {
+        assert_eq!(encode_project("group/repo"), "group%2Frepo");
+        assert_eq!(
+            encode_project("group/sub group/my.repo"),
+            "group%2Fsub%20group%2Fmy.repo"
+        );
+    }
+}
@@ -0,0 +1,49 @@
+//! Talk to the REST API of gitlab.com and self-managed GitLab instances, authenticated with personal access tokens.
+use anyhow::{Context as _, Result};
+use but_secret::Sensitive;
+
+mod client;
+pub mod mr;
+pub use client::{
+    CreateMergeRequestParams, GitLabClient, GitLabUser, MergeRequest, Pipeline, PipelineJob,
+};
+mod token;
+pub use token::{GitLabAccountIdentifier, list_known_gitlab_accounts};
+
+/// Verify `access_token` with the GitLab instance at `host`, and persist it along with the account it belongs to.
+///
+/// `api_url` is the base URL of the API if it isn't at its default location on `host`.
+pub async fn store_pat(
+    host: &str,
+    api_url: Option<&str>,
+    access_token: &Sensitive<String>,
+    storage: &but_forge_storage::Controller,
+) -> Result<GitLabUser> {
+    let client = GitLabClient::new(host, access_token);
+    let client = match api_url {
+        Some(api_url) => client.with_api_url(api_url),
+        None => client,
+    };
+    let user = client
+        .get_authenticated()
+        .await
+        .context("Failed to get authenticated user")?;
+    token::persist_gitlab_access_token(
+        &GitLabAccountIdentifier {
+            host: host.to_owned(),
+            username: user.username.clone(),
+        },
+        access_token,
+        storage,
+    )
+    .context("Failed to persist access token")?;
+    Ok(user)
+}
+
+/// Forget the GitLab account with `account` and its access token.
+pub fn forget_gitlab_account(
+    account: &GitLabAccountIdentifier,
+    storage: &but_forge_storage::Controller,
+) -> Result<()> {
+    token::delete_gitlab_access_token(account, storage)
+}
@@ -0,0 +1,98 @@
+use anyhow::{Context as _, Result};
+
+use crate::{GitLabAccountIdentifier, client::GitLabClient};
+
+pub async fn list(
+    preferred_account: Option<&GitLabAccountIdentifier>,
+    host: &str,
+    api_url: Option<&str>,
+    project: &str,
+    storage: &but_forge_storage::Controller,
+) -> Result<Vec<crate::MergeRequest>> {
+    if let Ok(gl) = GitLabClient::from_storage(storage, host, api_url, preferred_account) {
+        gl.list_open_merge_requests(project)
+            .await
+            .context("Failed to list open merge requests")
+    } else {
+        Ok(vec![])
+    }
+}
+
+pub async fn list_all_for_branch(
+    preferred_account: Option<&GitLabAccountIdentifier>,
+    host: &str,
+    api_url: Option<&str>,
+    project: &str,
+    branch: &str,
+    storage: &but_forge_storage::Controller,
+) -> Result<Vec<crate::MergeRequest>> {
+    if let Ok(gl) = GitLabClient::from_storage(storage, host, api_url, preferred_account) {
+        gl.list_merge_requests_for_source_branch(project, branch)
+            .await
+            .context("Failed to list merge requests for branch")
+    } else {
+        Ok(vec![])
+    }
+}
+
+pub async fn create(
+    preferred_account: Option<&GitLabAccountIdentifier>,
+    host: &str,
+    api_url: Option<&str>,
+    params: crate::CreateMergeRequestParams<'_>,
+    storage: &but_forge_storage::Controller,
+) -> Result<crate::MergeRequest> {
+    GitLabClient::from_storage(storage, host, api_url, preferred_account)?
+        .create_merge_request(&params)
+        .await
+        .context("Failed to create merge request")
+}
+
+pub async fn get(
+    preferred_account: Option<&GitLabAccountIdentifier>,
+    host: &str,
+    api_url: Option<&str>,
+    project: &str,
+    iid: usize,
+    storage: &but_forge_storage::Controller,
+) -> Result<crate::MergeRequest> {
+    let iid = iid.try_into().context("MR number is too large")?;
+    GitLabClient::from_storage(storage, host, api_url, preferred_account)?
+        .get_merge_request(project, iid)
+        .await
+        .context("Failed to get merge request")
+}
+
+/// Change the target branch of the merge request with `iid` to `target_branch`.
+pub async fn update_target_branch(
+    preferred_account: Option<&GitLabAccountIdentifier>,
+    host: &str,
+    api_url: Option<&str>,
+    project: &str,
+    iid: usize,
+    target_branch: &str,
+    storage: &but_forge_storage::Controller,
+) -> Result<crate::MergeRequest> {
+    let iid = iid.try_into().context("MR number is too large")?;
+    GitLabClient::from_storage(storage, host, api_url, preferred_account)?
+        .update_merge_request_target_branch(project, iid, target_branch)
+        .await
+        .context("Failed to update the target branch of the merge request")
+}
+
+/// Replace the description of the merge request with `iid` with `description`.
+pub async fn update_description(
+    preferred_account: Option<&GitLabAccountIdentifier>,
+    host: &str,
+    api_url: Option<&str>,
+    project: &str,
+    iid: usize,
+    description: &str,
+    storage: &but_forge_storage::Controller,
+) -> Result<crate::MergeRequest> {
+    let iid = iid.try_into().context("MR number is too large")?;
+    GitLabClient::from_storage(storage, host, api_url, preferred_account)?
+        .update_merge_request_description(project, iid, description)
+        .await
+        .context("Failed to update the description of the merge request")
+}
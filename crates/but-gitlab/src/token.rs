@@ -0,0 +1,129 @@
+use std::sync::Mutex;
+
+use anyhow::{Result, bail};
+use but_secret::{Sensitive, secret};
+use serde::{Deserialize, Serialize};
+
+use crate::client::GitLabClient;
+
+/// Identifies a GitLab account by the instance it lives on and its username.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitLabAccountIdentifier {
+    /// The hostname of the GitLab instance, like `gitlab.com`.
+    pub host: String,
+    pub username: String,
+}
+
+impl std::fmt::Display for GitLabAccountIdentifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}@{}", self.username, self.host)
+    }
+}
+
+impl From<&but_forge_storage::settings::GitLabAccount> for GitLabAccountIdentifier {
+    fn from(account: &but_forge_storage::settings::GitLabAccount) -> Self {
+        GitLabAccountIdentifier {
+            host: account.host.clone(),
+            username: account.username.clone(),
+        }
+    }
+}
+
+impl GitLabAccountIdentifier {
+    fn secret_key(&self) -> String {
+        format!("gitlab_pat_{}_{}", self.host, self.username)
+    }
+
+    fn storage_account(&self) -> but_forge_storage::settings::GitLabAccount {
+        but_forge_storage::settings::GitLabAccount {
+            host: self.host.clone(),
+            username: self.username.clone(),
+            access_token_key: self.secret_key(),
+        }
+    }
+}
+
+static FAIR_QUEUE: Mutex<()> = Mutex::new(());
+
+/// Persist the access token of the GitLab account with `account_id` securely.
+pub fn persist_gitlab_access_token(
+    account_id: &GitLabAccountIdentifier,
+    access_token: &Sensitive<String>,
+    storage: &but_forge_storage::Controller,
+) -> Result<()> {
+    storage.add_gitlab_account(&account_id.storage_account())?;
+    let _one_at_a_time_to_prevent_races = FAIR_QUEUE.lock().unwrap();
+    secret::persist(
+        &account_id.secret_key(),
+        access_token,
+        secret::Namespace::BuildKind,
+    )
+}
+
+/// Delete the GitLab account with `account_id` along with its access token.
+pub fn delete_gitlab_access_token(
+    account_id: &GitLabAccountIdentifier,
+    storage: &but_forge_storage::Controller,
+) -> Result<()> {
+    storage.remove_gitlab_account(&account_id.storage_account())?;
+    let _one_at_a_time_to_prevent_races = FAIR_QUEUE.lock().unwrap();
+    secret::delete(&account_id.secret_key(), secret::Namespace::BuildKind)
+}
+
+/// Retrieve the access token of the GitLab account with `account_id`.
+pub fn get_gitlab_access_token(
+    account_id: &GitLabAccountIdentifier,
+    storage: &but_forge_storage::Controller,
+) -> Result<Option<Sensitive<String>>> {
+    let Some(account) = storage
+        .gitlab_accounts()?
+        .into_iter()
+        .find(|account| GitLabAccountIdentifier::from(account) == *account_id)
+    else {
+        return Ok(None);
+    };
+    let _one_at_a_time_to_prevent_races = FAIR_QUEUE.lock().unwrap();
+    secret::retrieve(&account.access_token_key, secret::Namespace::BuildKind)
+}
+
+pub fn list_known_gitlab_accounts(
+    storage: &but_forge_storage::Controller,
+) -> Result<Vec<GitLabAccountIdentifier>> {
+    Ok(storage.gitlab_accounts()?.iter().map(Into::into).collect())
+}
+
+impl GitLabClient {
+    /// Create a client for the GitLab instance at `host`, authenticated as `preferred_account` if it is on
+    /// that host, or as the first known account on it.
+    ///
+    /// `api_url` is the base URL of the API if it isn't at its default location on `host`.
+    pub fn from_storage(
+        storage: &but_forge_storage::Controller,
+        host: &str,
+        api_url: Option<&str>,
+        preferred_account: Option<&GitLabAccountIdentifier>,
+    ) -> Result<Self> {
+        let accounts = list_known_gitlab_accounts(storage)?;
+        let account = preferred_account
+            .filter(|account| account.host == host && accounts.contains(account))
+            .or_else(|| accounts.iter().find(|account| account.host == host));
+        let Some(account) = account else {
+            bail!(
+                "No authenticated GitLab account found for '{host}'. Please authenticate with GitLab first."
+            );
+        };
+        match get_gitlab_access_token(account, storage)? {
+            Some(access_token) => {
+                let client = GitLabClient::new(host, &access_token);
+                Ok(match api_url {
+                    Some(api_url) => client.with_api_url(api_url),
+                    None => client,
+                })
+            }
+            None => bail!(
+                "No GitLab access token found for account '{account}'.\nPlease, try to re-authenticate with this account."
+            ),
+        }
+    }
+}
@@ -11,10 +11,11 @@
 
 mod reconcile;
 mod state;
+mod whitespace;
 
-use anyhow::Result;
-use bstr::{BString, ByteSlice};
-use but_core::{HunkHeader, TreeChange, UnifiedPatch, ref_metadata::StackId};
+use anyhow::{Result, bail};
+use bstr::{BStr, BString, ByteSlice};
+use but_core::{HunkHeader, ModeFlags, TreeChange, UnifiedPatch, ref_metadata::StackId};
 use but_ctx::Context;
 use but_hunk_dependency::ui::{
     HunkDependencies, HunkLock, hunk_dependencies_for_workspace_changes_by_worktree_dir,
@@ -24,6 +25,7 @@ use reconcile::MultipleOverlapping;
 use serde::{Deserialize, Serialize};
 use tracing::instrument;
 use uuid::Uuid;
+use whitespace::WhitespaceOnlyHunks;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[cfg_attr(feature = "export-ts", derive(ts_rs::TS))]
@@ -61,14 +63,177 @@ pub struct HunkAssignment {
     /// The hunk diff for internal usage. This is not to be persisted or sent over the API.
     #[serde(skip)]
     pub diff: Option<BString>,
+    /// The mode transition of the file, like the executable bit being added, which is committed along with this assignment.
+    /// This is not persisted, but sent over the API so clients can pass it back with the diff-specs to commit.
+    #[serde(default)]
+    #[cfg_attr(
+        feature = "export-ts",
+        ts(
+            type = "'ExecutableBitAdded' | 'ExecutableBitRemoved' | 'TypeChangeFileToLink' | 'TypeChangeLinkToFile' | 'TypeChange' | null"
+        )
+    )]
+    pub mode_flags: Option<ModeFlags>,
 }
 
 impl HunkAssignment {
     pub fn from_tree_change(change: &TreeChange, patch: Option<UnifiedPatch>) -> Vec<Self> {
-        diff_to_assignments(patch, change.path.clone())
+        diff_to_assignments(patch, change.path.clone(), change.status.flags())
+    }
+
+    /// Split this hunk into one selection for each of its added and removed lines, in the order they appear in the diff.
+    ///
+    /// Each selection marks the side it doesn't change as `0,0`, so `-0,0 +2,1` selects the added line 2, which is how
+    /// sub-hunk selections are assigned and committed. Returns nothing if the hunk has no header or no diff.
+    pub fn split_into_line_selections(&self) -> Vec<LineSelection> {
+        let (Some(header), Some(diff)) = (self.hunk_header, self.diff.as_ref()) else {
+            return Vec::new();
+        };
+        if header.old_range().is_null() || header.new_range().is_null() {
+            // Already a selection of added or removed lines, which are numbered on the side that isn't null.
+            let is_addition = header.old_range().is_null();
+            let mut line_num = if is_addition {
+                header.new_start
+            } else {
+                header.old_start
+            };
+            return diff
+                .lines()
+                .filter(|line| line.first().is_some_and(|b| matches!(b, b'+' | b'-')))
+                .map(|line| {
+                    let selection = LineSelection {
+                        hunk_header: line_selection(is_addition, line_num),
+                        line: line.into(),
+                    };
+                    line_num += 1;
+                    selection
+                })
+                .collect();
+        }
+        let mut selections = vec![];
+        let mut old_line_num = header.old_start;
+        let mut new_line_num = header.new_start;
+        for line in diff.lines() {
+            match line.first() {
+                Some(b'+') => {
+                    selections.push(LineSelection {
+                        hunk_header: line_selection(true, new_line_num),
+                        line: line.into(),
+                    });
+                    new_line_num += 1;
+                }
+                Some(b'-') => {
+                    selections.push(LineSelection {
+                        hunk_header: line_selection(false, old_line_num),
+                        line: line.into(),
+                    });
+                    old_line_num += 1;
+                }
+                Some(b'@') | Some(b'\\') | None => {}
+                Some(_) => {
+                    old_line_num += 1;
+                    new_line_num += 1;
+                }
+            }
+        }
+        selections
+    }
+
+    /// Split this assignment into one assignment per added or removed line, or return `None` if it can't be split.
+    pub(crate) fn split_into_lines(&self) -> Option<Vec<HunkAssignment>> {
+        let selections = self.split_into_line_selections();
+        if selections.len() < 2 {
+            return None;
+        }
+        Some(
+            selections
+                .into_iter()
+                .map(|selection| {
+                    let header = selection.hunk_header;
+                    let is_addition = header.old_range().is_null();
+                    HunkAssignment {
+                        hunk_header: Some(header),
+                        line_nums_added: Some(if is_addition {
+                            vec![header.new_start as usize]
+                        } else {
+                            vec![]
+                        }),
+                        line_nums_removed: Some(if is_addition {
+                            vec![]
+                        } else {
+                            vec![header.old_start as usize]
+                        }),
+                        diff: Some(hunk_diff(header, [selection.line.as_bstr()])),
+                        ..self.clone()
+                    }
+                })
+                .collect(),
+        )
+    }
+
+    /// Return `true` if this assignment selects some of the added or removed lines of `other`, but not all of them.
+    pub(crate) fn selects_lines_of(&self, other: &HunkAssignment) -> bool {
+        let (Some(header), Some(other_header)) = (self.hunk_header, other.hunk_header) else {
+            return false;
+        };
+        if self.path_bytes != other.path_bytes || header == other_header {
+            return false;
+        }
+        if header.old_range().is_null() {
+            !other_header.new_range().is_null()
+                && other_header.new_range().contains(header.new_range())
+        } else if header.new_range().is_null() {
+            !other_header.old_range().is_null()
+                && other_header.old_range().contains(header.old_range())
+        } else {
+            false
+        }
+    }
+}
+
+/// Return the header that selects the added line `line_num` if `is_addition`, or the removed line `line_num` otherwise.
+fn line_selection(is_addition: bool, line_num: u32) -> HunkHeader {
+    if is_addition {
+        HunkHeader {
+            old_start: 0,
+            old_lines: 0,
+            new_start: line_num,
+            new_lines: 1,
+        }
+    } else {
+        HunkHeader {
+            old_start: line_num,
+            old_lines: 1,
+            new_start: 0,
+            new_lines: 0,
+        }
     }
 }
 
+/// Return the diff of a hunk with `header` and `lines`, which include their `+` or `-` prefix.
+pub(crate) fn hunk_diff<'a>(
+    header: HunkHeader,
+    lines: impl IntoIterator<Item = &'a BStr>,
+) -> BString {
+    let mut diff = BString::from(format!(
+        "@@ -{},{} +{},{} @@\n",
+        header.old_start, header.old_lines, header.new_start, header.new_lines
+    ));
+    for line in lines {
+        diff.extend_from_slice(line);
+        diff.push(b'\n');
+    }
+    diff
+}
+
+/// A single added or removed line of a hunk, as returned by [`HunkAssignment::split_into_line_selections()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineSelection {
+    /// The header that selects only this line from the hunk.
+    pub hunk_header: HunkHeader,
+    /// The line as it appears in the diff, including its `+` or `-` prefix.
+    pub line: BString,
+}
+
 impl TryFrom<but_db::HunkAssignment> for HunkAssignment {
     type Error = anyhow::Error;
     fn try_from(value: but_db::HunkAssignment) -> Result<Self, Self::Error> {
@@ -91,6 +256,7 @@ impl TryFrom<but_db::HunkAssignment> for HunkAssignment {
             line_nums_added: None,   // derived data (not persisted)
             line_nums_removed: None, // derived data (not persisted)
             diff: None,              // derived data (not persisted)
+            mode_flags: None,        // derived data (not persisted)
         })
     }
 }
@@ -131,6 +297,7 @@ impl From<HunkAssignment> for but_core::DiffSpec {
             previous_path: None, // TODO
             path: value.path_bytes.clone(),
             hunk_headers,
+            mode_flags: value.mode_flags,
         }
     }
 }
@@ -247,9 +414,9 @@ pub struct JsonAbsorbOutput {
 /// Indicates that the assignment request was rejected due to locking - the hunk depends on a commit in the stack it is currently in.
 pub struct AssignmentRejection {
     /// The request that was rejected.
-    request: HunkAssignmentRequest,
+    pub request: HunkAssignmentRequest,
     /// The locks that caused the rejection.
-    locks: Vec<HunkLock>,
+    pub locks: Vec<HunkLock>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -300,8 +467,25 @@ impl From<but_core::WorktreeChanges> for WorktreeChanges {
 }
 
 impl HunkAssignmentRequest {
+    /// Return `true` if this request is for `assignment`, or for some of the lines that were joined into it.
     pub fn matches_assignment(&self, assignment: &HunkAssignment) -> bool {
-        self.path_bytes == assignment.path_bytes && self.hunk_header == assignment.hunk_header
+        if self.path_bytes != assignment.path_bytes {
+            return false;
+        }
+        match (self.hunk_header, assignment.hunk_header) {
+            (Some(header), Some(assigned)) if header != assigned => {
+                if header.old_range().is_null() {
+                    assigned.old_range().is_null()
+                        && assigned.new_range().contains(header.new_range())
+                } else if header.new_range().is_null() {
+                    assigned.new_range().is_null()
+                        && assigned.old_range().contains(header.old_range())
+                } else {
+                    false
+                }
+            }
+            (header, assigned) => header == assigned,
+        }
     }
 }
 
@@ -338,10 +522,21 @@ impl HunkAssignment {
             return true;
         }
 
-        // Both have hunk headers - check if the ranges overlap
+        // Both have hunk headers - check if the ranges overlap, ignoring the side that selections of
+        // added or removed lines leave empty.
         if let (Some(header), Some(other_header)) = (self.hunk_header, other.hunk_header) {
-            return header.old_range().intersects(other_header.old_range())
-                && header.new_range().intersects(other_header.new_range());
+            let (old, other_old) = (header.old_range(), other_header.old_range());
+            let (new, other_new) = (header.new_range(), other_header.new_range());
+            return match (
+                old.is_null() || other_old.is_null(),
+                new.is_null() || other_new.is_null(),
+            ) {
+                // Added lines never overlap with removed lines.
+                (true, true) => false,
+                (true, false) => new.intersects(other_new),
+                (false, true) => old.intersects(other_old),
+                (false, false) => old.intersects(other_old) && new.intersects(other_new),
+            };
         }
 
         false
@@ -363,6 +558,17 @@ pub fn assign(
     requests: Vec<HunkAssignmentRequest>,
     deps: Option<&HunkDependencies>,
 ) -> Result<Vec<AssignmentRejection>> {
+    if let Some(sparse) = but_core::sparse::SparseCheckout::from_repo(repo)?
+        && let Some(request) = requests
+            .iter()
+            .find(|request| sparse.is_excluded(request.path_bytes.as_bstr()))
+    {
+        bail!(
+            "Cannot assign changes to '{}' as it is outside of the sparse checkout",
+            request.path_bytes
+        );
+    }
+
     let identifiable_stacks = workspace
         .stacks
         .iter()
@@ -379,18 +585,14 @@ pub fn assign(
     };
     let worktree_changes: Vec<but_core::TreeChange> =
         but_core::diff::worktree_changes(repo)?.changes;
-    let mut worktree_assignments = vec![];
-    for change in &worktree_changes {
-        let diff = change.unified_patch(repo, ctx.settings().context_lines);
-        worktree_assignments.extend(HunkAssignment::from_tree_change(
-            change,
-            diff.ok().flatten(),
-        ));
-    }
+    let mut worktree_assignments =
+        worktree_assignments(repo, &worktree_changes, ctx.settings().context_lines);
+    WhitespaceOnlyHunks::from_settings(ctx, workspace).apply(&mut worktree_assignments);
 
     // Reconcile worktree with the persisted assignments
     let db = &mut *ctx.db.get_mut()?;
-    let persisted_assignments = state::assignments(db)?;
+    let mut persisted_assignments = state::assignments(db)?;
+    reconcile::follow_case_only_renames(&mut persisted_assignments, &worktree_changes);
     let with_worktree = reconcile::assignments(
         &worktree_assignments,
         &persisted_assignments,
@@ -419,6 +621,10 @@ pub fn assign(
     );
 
     state::set_assignments(db, with_locks.clone())?;
+    but_core::sync::events::publish(
+        &ctx.project_data_dir(),
+        but_core::sync::events::WorkspaceEvent::AssignmentsChanged,
+    );
 
     // Request where the stack_id is different from the outcome are considered rejections - this is due to locking
     // Collect all the rejected requests together with the locks that caused the rejection
@@ -490,18 +696,14 @@ fn reconcile_worktree_changes_with_worktree_and_locks(
     if worktree_changes.is_empty() {
         return Ok(vec![]);
     }
-    let mut worktree_assignments = vec![];
-    for change in &worktree_changes {
-        let diff = change.unified_patch(repo, ctx.settings().context_lines);
-        worktree_assignments.extend(HunkAssignment::from_tree_change(
-            change,
-            diff.ok().flatten(),
-        ));
-    }
+    let mut worktree_assignments =
+        worktree_assignments(repo, &worktree_changes, ctx.settings().context_lines);
+    WhitespaceOnlyHunks::from_settings(ctx, workspace).apply(&mut worktree_assignments);
     let reconciled = reconcile_with_worktree_and_locks(
         ctx,
         workspace,
         set_assignment_from_locks,
+        &worktree_changes,
         &worktree_assignments,
         deps,
     )?;
@@ -535,6 +737,7 @@ fn reconcile_with_worktree_and_locks(
     ctx: &mut Context,
     workspace: &but_graph::projection::Workspace,
     set_assignment_from_locks: bool,
+    worktree_changes: &[but_core::TreeChange],
     worktree_assignments: &[HunkAssignment],
     deps: &HunkDependencies,
 ) -> Result<Vec<HunkAssignment>> {
@@ -545,7 +748,8 @@ fn reconcile_with_worktree_and_locks(
         .collect::<Vec<_>>();
 
     let db = &*ctx.db.get()?;
-    let persisted_assignments = state::assignments(db)?;
+    let mut persisted_assignments = state::assignments(db)?;
+    reconcile::follow_case_only_renames(&mut persisted_assignments, worktree_changes);
     let with_worktree = reconcile::assignments(
         worktree_assignments,
         &persisted_assignments,
@@ -566,6 +770,42 @@ fn reconcile_with_worktree_and_locks(
     Ok(with_locks)
 }
 
+/// Produce unassigned assignments for all hunks of `worktree_changes`.
+///
+/// Files that are too large to be diffed as a whole are diffed up to the big-file threshold, so that at least
+/// the hunks at their beginning can be assigned.
+fn worktree_assignments(
+    repo: &gix::Repository,
+    worktree_changes: &[TreeChange],
+    context_lines: u32,
+) -> Vec<HunkAssignment> {
+    let diffs = but_core::diff::unified_patches(
+        repo,
+        worktree_changes,
+        but_core::shallow::ChangesSource::Worktree,
+        context_lines,
+        None,
+    );
+    let mut assignments = vec![];
+    for (change, diff) in worktree_changes.iter().zip(diffs) {
+        let diff = match diff.ok().flatten() {
+            Some(UnifiedPatch::TooLarge { size_in_bytes }) => repo
+                .big_file_threshold()
+                .ok()
+                .and_then(|threshold| threshold.try_into().ok())
+                .and_then(|byte_budget| {
+                    but_core::diff::prefix_patch(repo, change, context_lines, byte_budget)
+                        .ok()
+                        .flatten()
+                })
+                .or(Some(UnifiedPatch::TooLarge { size_in_bytes })),
+            diff => diff,
+        };
+        assignments.extend(HunkAssignment::from_tree_change(change, diff));
+    }
+    assignments
+}
+
 fn hunk_dependency_assignments(deps: &HunkDependencies) -> Vec<HunkAssignment> {
     let mut assignments = vec![];
     for (path, hunk, locks) in &deps.diffs {
@@ -590,6 +830,7 @@ fn hunk_dependency_assignments(deps: &HunkDependencies) -> Vec<HunkAssignment> {
             line_nums_added: None,   // derived data (not persisted)
             line_nums_removed: None, // derived data (not persisted)
             diff: None,              // derived data (not persisted)
+            mode_flags: None,        // derived data (not persisted)
         };
         assignments.push(assignment);
     }
@@ -597,7 +838,12 @@ fn hunk_dependency_assignments(deps: &HunkDependencies) -> Vec<HunkAssignment> {
 }
 
 /// This also generates a UUID for the assignment
-fn diff_to_assignments(diff: Option<UnifiedPatch>, path: BString) -> Vec<HunkAssignment> {
+/// Each assignment carries the `mode_flags` of the file, so the mode transition goes wherever its content goes.
+fn diff_to_assignments(
+    diff: Option<UnifiedPatch>,
+    path: BString,
+    mode_flags: Option<ModeFlags>,
+) -> Vec<HunkAssignment> {
     let path_str = path.to_str_lossy();
     if let Some(diff) = diff {
         match diff {
@@ -611,18 +857,25 @@ fn diff_to_assignments(diff: Option<UnifiedPatch>, path: BString) -> Vec<HunkAss
                 line_nums_added: None,
                 line_nums_removed: None,
                 diff: None,
+                mode_flags,
             }],
-            but_core::UnifiedPatch::TooLarge { .. } => vec![HunkAssignment {
-                id: Some(Uuid::new_v4()),
-                hunk_header: None,
-                path: path_str.into(),
-                path_bytes: path,
-                stack_id: None,
-                hunk_locks: None,
-                line_nums_added: None,
-                line_nums_removed: None,
-                diff: None,
-            }],
+            // Submodules and files tracked by Git LFS can only be assigned as a whole.
+            but_core::UnifiedPatch::TooLarge { .. }
+            | but_core::UnifiedPatch::Submodule { .. }
+            | but_core::UnifiedPatch::LfsPointer { .. } => {
+                vec![HunkAssignment {
+                    id: Some(Uuid::new_v4()),
+                    hunk_header: None,
+                    path: path_str.into(),
+                    path_bytes: path,
+                    stack_id: None,
+                    hunk_locks: None,
+                    line_nums_added: None,
+                    line_nums_removed: None,
+                    diff: None,
+                    mode_flags,
+                }]
+            }
             but_core::UnifiedPatch::Patch {
                 hunks,
                 is_result_of_binary_to_text_conversion,
@@ -640,6 +893,7 @@ fn diff_to_assignments(diff: Option<UnifiedPatch>, path: BString) -> Vec<HunkAss
                         line_nums_added: None,
                         line_nums_removed: None,
                         diff: None,
+                        mode_flags,
                     }]
                 } else {
                     hunks
@@ -657,6 +911,7 @@ fn diff_to_assignments(diff: Option<UnifiedPatch>, path: BString) -> Vec<HunkAss
                                 line_nums_added: Some(line_nums_added_new),
                                 line_nums_removed: Some(line_nums_removed_old),
                                 diff: Some(hunk.diff.clone()),
+                                mode_flags,
                             }
                         })
                         .collect()
@@ -674,6 +929,7 @@ fn diff_to_assignments(diff: Option<UnifiedPatch>, path: BString) -> Vec<HunkAss
             line_nums_added: None,
             line_nums_removed: None,
             diff: None,
+            mode_flags,
         }]
     }
 }
@@ -734,6 +990,7 @@ fn requests_to_assignments(request: Vec<HunkAssignmentRequest>) -> Vec<HunkAssig
             line_nums_added: None,
             line_nums_removed: None,
             diff: None,
+            mode_flags: None,
         };
         assignments.push(assignment);
     }
@@ -782,6 +1039,7 @@ mod tests {
                 line_nums_added: None,
                 line_nums_removed: None,
                 diff: None,
+                mode_flags: None,
             }
         }
     }
@@ -830,6 +1088,164 @@ mod tests {
         );
     }
 
+    #[test]
+    fn mode_flags_round_trip_through_the_api_into_diff_specs() -> anyhow::Result<()> {
+        let mut assignment = HunkAssignment::new("script.sh", 1, 4, Some(1), Some(1));
+        assignment.mode_flags = Some(ModeFlags::ExecutableBitAdded);
+
+        let json = serde_json::to_value(&assignment)?;
+        assert_eq!(json["modeFlags"], "ExecutableBitAdded");
+        let assignment: HunkAssignment = serde_json::from_value(json)?;
+        let spec = but_core::DiffSpec::from(assignment);
+        assert_eq!(spec.mode_flags, Some(ModeFlags::ExecutableBitAdded));
+
+        let mut json = serde_json::to_value(HunkAssignment::new("file", 1, 4, None, None))?;
+        json.as_object_mut().unwrap().remove("modeFlags");
+        let assignment: HunkAssignment = serde_json::from_value(json)?;
+        assert_eq!(
+            assignment.mode_flags, None,
+            "clients that don't know about modes can leave it out"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_split_into_line_selections() {
+        let mut hunk = HunkAssignment::new("foo.rs", 1, 4, None, None);
+        assert!(
+            hunk.split_into_line_selections().is_empty(),
+            "nothing to split without a diff"
+        );
+
+        hunk.hunk_header = Some(HunkHeader {
+            old_start: 1,
+            old_lines: 3,
+            new_start: 1,
+            new_lines: 4,
+        });
+        hunk.diff = Some("@@ -1,3 +1,4 @@\n a\n-b\n+B\n+C\n d\n".into());
+        let selections = hunk.split_into_line_selections();
+        let headers = selections
+            .iter()
+            .map(|s| (s.line.to_string(), s.hunk_header))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            headers,
+            [
+                ("-b".to_string(), header(2, 1, 0, 0)),
+                ("+B".to_string(), header(0, 0, 2, 1)),
+                ("+C".to_string(), header(0, 0, 3, 1)),
+            ],
+            "each line is selected on the side it changes, like the commit engine expects it"
+        );
+    }
+
+    #[test]
+    fn lines_of_a_hunk_are_assigned_to_different_stacks() {
+        let mut hunk = HunkAssignment::new("foo.rs", 1, 4, None, Some(1));
+        hunk.hunk_header = Some(header(1, 3, 1, 5));
+        hunk.diff = Some("@@ -1,3 +1,5 @@\n a\n-b\n+B\n+C\n+D\n d\n".into());
+        let request = |header: HunkHeader, stack_id: Option<usize>| HunkAssignmentRequest {
+            hunk_header: Some(header),
+            path_bytes: "foo.rs".into(),
+            stack_id: stack_id.map(stack_id_seq),
+        };
+        let stacks = [stack_id_seq(1), stack_id_seq(2)];
+        // `-b` and `+B` go to stack 1, `+C` is skipped and `+D` goes to stack 2.
+        let requests = requests_to_assignments(vec![
+            request(header(2, 1, 0, 0), Some(1)),
+            request(header(0, 0, 2, 1), Some(1)),
+            request(header(0, 0, 4, 1), Some(2)),
+        ]);
+        let assigned = reconcile::assignments(
+            std::slice::from_ref(&hunk),
+            &requests,
+            &stacks,
+            MultipleOverlapping::SetMostLines,
+            true,
+        );
+        let summary = |assignments: &[HunkAssignment]| {
+            assignments
+                .iter()
+                .map(|a| (a.hunk_header.unwrap(), a.stack_id))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            summary(&assigned),
+            [
+                (header(2, 1, 0, 0), Some(stack_id_seq(1))),
+                (header(0, 0, 2, 1), Some(stack_id_seq(1))),
+                (header(0, 0, 3, 1), None),
+                (header(0, 0, 4, 1), Some(stack_id_seq(2))),
+            ],
+            "skipped lines stay unassigned"
+        );
+        assert_eq!(
+            assigned[1].diff.as_ref().unwrap(),
+            "@@ -0,0 +2,1 @@\n+B\n",
+            "each selection shows its own lines"
+        );
+        assert_eq!(
+            assigned.iter().filter_map(|a| a.id).unique().count(),
+            assigned.len(),
+            "each selection has its own id"
+        );
+
+        let reconciled = reconcile::assignments(
+            std::slice::from_ref(&hunk),
+            &assigned,
+            &stacks,
+            MultipleOverlapping::SetMostLines,
+            true,
+        );
+        assert_eq!(
+            summary(&reconciled),
+            summary(&assigned),
+            "the split survives the next reconciliation with the worktree"
+        );
+
+        let all_to_one_stack = requests_to_assignments(vec![
+            request(header(0, 0, 3, 1), Some(1)),
+            request(header(0, 0, 4, 1), Some(1)),
+        ]);
+        let reconciled = reconcile::assignments(
+            &reconciled,
+            &all_to_one_stack,
+            &stacks,
+            MultipleOverlapping::SetMostLines,
+            true,
+        );
+        assert_eq!(
+            summary(&reconciled),
+            [
+                (header(2, 1, 0, 0), Some(stack_id_seq(1))),
+                (header(0, 0, 2, 3), Some(stack_id_seq(1))),
+            ],
+            "consecutive lines of the same stack are joined"
+        );
+        let reconciled = reconcile::assignments(
+            std::slice::from_ref(&hunk),
+            &reconciled,
+            &stacks,
+            MultipleOverlapping::SetMostLines,
+            true,
+        );
+        assert_eq!(
+            summary(&reconciled),
+            [(header(1, 3, 1, 5), Some(stack_id_seq(1)))],
+            "a hunk whose lines all go to the same stack is whole again"
+        );
+    }
+
+    fn header(old_start: u32, old_lines: u32, new_start: u32, new_lines: u32) -> HunkHeader {
+        HunkHeader {
+            old_start,
+            old_lines,
+            new_start,
+            new_lines,
+        }
+    }
+
     #[test]
     fn test_intersection() {
         assert!(
@@ -967,6 +1383,38 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_reconcile_follows_case_only_rename() {
+        let state = but_core::ChangeState {
+            id: gix::hash::Kind::Sha1.null(),
+            kind: gix::object::tree::EntryKind::Blob,
+        };
+        let worktree_changes = vec![but_core::TreeChange {
+            path: "foo.rs".into(),
+            status: but_core::TreeStatus::Rename {
+                previous_path: "Foo.rs".into(),
+                previous_state: state,
+                state,
+                flags: None,
+            },
+        }];
+        let mut previous_assignments = vec![HunkAssignment::new("Foo.rs", 10, 5, Some(1), Some(1))];
+        reconcile::follow_case_only_renames(&mut previous_assignments, &worktree_changes);
+        let worktree_assignments = vec![HunkAssignment::new("foo.rs", 10, 5, None, None)];
+        let applied_stacks = vec![stack_id_seq(1)];
+        let result = reconcile::assignments(
+            &worktree_assignments,
+            &previous_assignments,
+            &applied_stacks,
+            MultipleOverlapping::SetMostLines,
+            true,
+        );
+        assert_eq(
+            result,
+            vec![HunkAssignment::new("foo.rs", 10, 5, Some(1), Some(1))],
+        );
+    }
+
     #[test]
     fn test_hunk_assignment_partial_eq() {
         let hunk1 = HunkAssignment::new("foo.rs", 10, 15, Some(1), Some(3));
@@ -994,6 +1442,7 @@ mod tests {
             line_nums_added: None,
             line_nums_removed: None,
             diff: None,
+            mode_flags: None,
         };
 
         let binary2 = HunkAssignment {
@@ -1006,6 +1455,7 @@ mod tests {
             line_nums_added: None,
             line_nums_removed: None,
             diff: None,
+            mode_flags: None,
         };
 
         assert!(
@@ -1033,6 +1483,7 @@ mod tests {
             line_nums_added: None,
             line_nums_removed: None,
             diff: None,
+            mode_flags: None,
         };
 
         assert!(
@@ -1058,6 +1509,7 @@ mod tests {
             line_nums_added: None,
             line_nums_removed: None,
             diff: None,
+            mode_flags: None,
         };
 
         let binary2 = HunkAssignment {
@@ -1070,6 +1522,7 @@ mod tests {
             line_nums_added: None,
             line_nums_removed: None,
             diff: None,
+            mode_flags: None,
         };
 
         assert!(
@@ -1095,6 +1548,7 @@ mod tests {
                 line_nums_added: None,
                 line_nums_removed: None,
                 diff: None,
+                mode_flags: None,
             },
             // Text file assigned to stack 1
             HunkAssignment::new("code.rs", 10, 5, Some(1), Some(2)),
@@ -1112,6 +1566,7 @@ mod tests {
                 line_nums_added: None,
                 line_nums_removed: None,
                 diff: None,
+                mode_flags: None,
             },
             // Same text file, modified
             HunkAssignment::new("code.rs", 10, 7, None, Some(4)),
@@ -1159,6 +1614,7 @@ mod tests {
             line_nums_added: None,
             line_nums_removed: None,
             diff: None,
+            mode_flags: None,
         }];
 
         let result = reconcile::assignments(
@@ -1,6 +1,7 @@
-use std::cmp::Ordering;
+use std::{cmp::Ordering, collections::HashSet};
 
-use but_core::ref_metadata::StackId;
+use bstr::{BString, ByteSlice};
+use but_core::{TreeChange, ref_metadata::StackId};
 use itertools::Itertools;
 
 use crate::HunkAssignment;
@@ -50,6 +51,33 @@ impl HunkAssignment {
     }
 }
 
+/// Make `assignments` to the previous path of a case-only rename in `worktree_changes` apply to its new path,
+/// as on case-insensitive filesystems, the renamed file is still the one that was assigned.
+pub(crate) fn follow_case_only_renames(
+    assignments: &mut [HunkAssignment],
+    worktree_changes: &[TreeChange],
+) {
+    for change in worktree_changes {
+        let Some(previous_path) = change.previous_path() else {
+            continue;
+        };
+        if previous_path == change.path || !previous_path.eq_ignore_ascii_case(&change.path) {
+            continue;
+        }
+        for assignment in assignments
+            .iter_mut()
+            .filter(|assignment| assignment.path_bytes == previous_path)
+        {
+            assignment.path = change.path.to_str_lossy().into();
+            assignment.path_bytes = change.path.clone();
+        }
+    }
+}
+
+/// Reconcile each of the `new` assignments with the `old` ones it overlaps with.
+///
+/// If some lines of a `new` hunk are assigned separately in `old`, the hunk is split into its lines so each of them
+/// can be reconciled on its own, and lines that end up in the same stack are joined again.
 pub(crate) fn assignments(
     new: &[HunkAssignment],
     old: &[HunkAssignment],
@@ -57,47 +85,151 @@ pub(crate) fn assignments(
     multiple_overlapping_resolution: MultipleOverlapping,
     update_unassigned: bool,
 ) -> Vec<HunkAssignment> {
+    let reconcile = |assignment: &HunkAssignment| {
+        reconcile_one(
+            assignment,
+            old,
+            applied_stack_ids,
+            multiple_overlapping_resolution,
+            update_unassigned,
+        )
+    };
     let mut reconciled = vec![];
     for new_assignment in new {
-        let mut new_assignment = new_assignment.clone();
-        let intersecting = old
+        let lines = old
             .iter()
-            .filter(|current_entry| current_entry.intersects(new_assignment.clone()))
-            .collect::<Vec<_>>();
-
-        match intersecting.len().cmp(&1) {
-            Ordering::Less => {
-                // No intersection - do nothing, the None assignment is kept
-                let matching_file = old
-                    .iter()
-                    .filter(|current_entry| current_entry.path == new_assignment.path)
-                    .collect::<Vec<_>>();
-                if let Some(matching_file) = matching_file.first() {
-                    new_assignment.hunk_locks = matching_file.hunk_locks.clone();
+            .any(|old_assignment| old_assignment.selects_lines_of(new_assignment))
+            .then(|| new_assignment.split_into_lines())
+            .flatten();
+        let Some(lines) = lines else {
+            reconciled.push(reconcile(new_assignment));
+            continue;
+        };
+        let mut lines = lines.iter().map(reconcile).collect::<Vec<_>>();
+        if lines.iter().map(|line| line.stack_id).all_equal() {
+            let mut whole = reconcile(new_assignment);
+            whole.stack_id = lines[0].stack_id;
+            reconciled.push(whole);
+        } else {
+            // Each line needs its own identity, even if it was reconciled with the same assignment as another line.
+            let mut seen_ids = HashSet::new();
+            for line in &mut lines {
+                if let Some(id) = line.id
+                    && !seen_ids.insert(id)
+                {
+                    line.id = Some(uuid::Uuid::new_v4());
                 }
             }
-            Ordering::Equal => {
-                new_assignment.set_from(intersecting[0], applied_stack_ids, update_unassigned);
+            reconciled.extend(lines);
+        }
+    }
+    join_lines(reconciled)
+}
+
+fn reconcile_one(
+    new_assignment: &HunkAssignment,
+    old: &[HunkAssignment],
+    applied_stack_ids: &[StackId],
+    multiple_overlapping_resolution: MultipleOverlapping,
+    update_unassigned: bool,
+) -> HunkAssignment {
+    let mut new_assignment = new_assignment.clone();
+    let intersecting = old
+        .iter()
+        .filter(|current_entry| current_entry.intersects(new_assignment.clone()))
+        .collect::<Vec<_>>();
+
+    match intersecting.len().cmp(&1) {
+        Ordering::Less => {
+            // No intersection - do nothing, the None assignment is kept
+            let matching_file = old
+                .iter()
+                .filter(|current_entry| current_entry.path == new_assignment.path)
+                .collect::<Vec<_>>();
+            if let Some(matching_file) = matching_file.first() {
+                new_assignment.hunk_locks = matching_file.hunk_locks.clone();
+            }
+        }
+        Ordering::Equal => {
+            new_assignment.set_from(intersecting[0], applied_stack_ids, update_unassigned);
+        }
+        Ordering::Greater => {
+            // Pick the hunk with the most lines to adopt the assignment info from.
+            let biggest_hunk = intersecting
+                .iter()
+                .max_by_key(|h| h.hunk_header.as_ref().map(|h| h.new_lines));
+            if let Some(other) = biggest_hunk {
+                new_assignment.set_from(other, applied_stack_ids, update_unassigned);
             }
-            Ordering::Greater => {
-                // Pick the hunk with the most lines to adopt the assignment info from.
-                let biggest_hunk = intersecting
-                    .iter()
-                    .max_by_key(|h| h.hunk_header.as_ref().map(|h| h.new_lines));
-                if let Some(other) = biggest_hunk {
-                    new_assignment.set_from(other, applied_stack_ids, update_unassigned);
-                }
 
-                // If requested, reset stack_id to none on multiple overlapping
-                let unique_stack_ids = intersecting.iter().filter_map(|a| a.stack_id).unique();
-                if multiple_overlapping_resolution == MultipleOverlapping::SetNone
-                    && unique_stack_ids.count() > 1
-                {
-                    new_assignment.stack_id = None;
-                }
+            // If requested, reset stack_id to none on multiple overlapping
+            let unique_stack_ids = intersecting.iter().filter_map(|a| a.stack_id).unique();
+            if multiple_overlapping_resolution == MultipleOverlapping::SetNone
+                && unique_stack_ids.count() > 1
+            {
+                new_assignment.stack_id = None;
+            }
+        }
+    }
+    new_assignment
+}
+
+/// Join consecutive selections of added or removed lines in `assignments` that are assigned to the same stack
+/// into one selection.
+fn join_lines(assignments: Vec<HunkAssignment>) -> Vec<HunkAssignment> {
+    let mut joined: Vec<HunkAssignment> = Vec::new();
+    for assignment in assignments {
+        let Some(header) = assignment.hunk_header else {
+            joined.push(assignment);
+            continue;
+        };
+        let previous = joined.last_mut().filter(|previous| {
+            previous.path_bytes == assignment.path_bytes
+                && previous.stack_id == assignment.stack_id
+                && previous.hunk_header.is_some_and(|previous| {
+                    if header.old_range().is_null() {
+                        previous.old_range().is_null()
+                            && previous.new_range().end() == header.new_start
+                    } else if header.new_range().is_null() {
+                        previous.new_range().is_null()
+                            && previous.old_range().end() == header.old_start
+                    } else {
+                        false
+                    }
+                })
+        });
+        let Some(previous) = previous else {
+            joined.push(assignment);
+            continue;
+        };
+        let previous_header = previous.hunk_header.as_mut().expect("checked above");
+        previous_header.old_lines += header.old_lines;
+        previous_header.new_lines += header.new_lines;
+        let header = *previous_header;
+        let diff_lines = |diff: &Option<BString>| {
+            diff.iter()
+                .flat_map(|diff| diff.lines())
+                .filter(|line| !line.starts_with(b"@@"))
+                .map(|line| line.as_bstr().to_owned())
+                .collect::<Vec<_>>()
+        };
+        let mut lines = diff_lines(&previous.diff);
+        lines.extend(diff_lines(&assignment.diff));
+        previous.diff = Some(crate::hunk_diff(
+            header,
+            lines.iter().map(|line| line.as_bstr()),
+        ));
+        for (nums, line_nums) in [
+            (&mut previous.line_nums_added, assignment.line_nums_added),
+            (
+                &mut previous.line_nums_removed,
+                assignment.line_nums_removed,
+            ),
+        ] {
+            if let (Some(nums), Some(line_nums)) = (nums.as_mut(), line_nums) {
+                nums.extend(line_nums);
             }
         }
-        reconciled.push(new_assignment);
     }
-    reconciled
+    joined
 }
@@ -0,0 +1,128 @@
+use bstr::{BStr, ByteSlice};
+use but_core::ref_metadata::StackId;
+use but_ctx::Context;
+
+use crate::HunkAssignment;
+
+/// What to do with hunks that only change whitespace, like re-indented or re-wrapped lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum WhitespaceOnlyHunks {
+    /// List them like any other hunk.
+    Show,
+    /// Leave them out of the assignments, so they don't show as unassigned changes.
+    Hide,
+    /// Assign them to the given stack unless they were assigned before, so formatting changes collect in one place.
+    Assign(StackId),
+}
+
+impl WhitespaceOnlyHunks {
+    /// Read the handling from the settings of `ctx`, and find the stack of the formatting branch in `workspace`.
+    ///
+    /// If the formatting branch isn't applied, whitespace-only hunks are shown as usual.
+    pub(crate) fn from_settings(
+        ctx: &Context,
+        workspace: &but_graph::projection::Workspace,
+    ) -> Self {
+        let settings = &ctx.settings().whitespace_only_hunks;
+        match settings.handling.as_str() {
+            "show" => WhitespaceOnlyHunks::Show,
+            "hide" => WhitespaceOnlyHunks::Hide,
+            "assign" => workspace
+                .stacks
+                .iter()
+                .find(|stack| {
+                    stack.segments.iter().any(|segment| {
+                        segment.ref_name().is_some_and(|name| {
+                            name.shorten() == settings.formatting_branch.as_str()
+                        })
+                    })
+                })
+                .and_then(|stack| stack.id)
+                .map_or(WhitespaceOnlyHunks::Show, WhitespaceOnlyHunks::Assign),
+            unknown => {
+                tracing::warn!(
+                    handling = unknown,
+                    "Unknown handling of whitespace-only hunks, showing them"
+                );
+                WhitespaceOnlyHunks::Show
+            }
+        }
+    }
+
+    /// Apply this handling to `worktree_assignments`, before they are reconciled with persisted assignments.
+    ///
+    /// As reconciliation keeps previous assignments, hunks that were assigned or unassigned by the user
+    /// stay where they are.
+    pub(crate) fn apply(self, worktree_assignments: &mut Vec<HunkAssignment>) {
+        match self {
+            WhitespaceOnlyHunks::Show => {}
+            WhitespaceOnlyHunks::Hide => {
+                worktree_assignments.retain(|assignment| !is_whitespace_only(assignment));
+            }
+            WhitespaceOnlyHunks::Assign(stack_id) => {
+                for assignment in worktree_assignments
+                    .iter_mut()
+                    .filter(|assignment| is_whitespace_only(assignment))
+                {
+                    assignment.stack_id = Some(stack_id);
+                }
+            }
+        }
+    }
+}
+
+/// Return `true` if `assignment` has a hunk whose removed and added lines are the same when ignoring all whitespace.
+fn is_whitespace_only(assignment: &HunkAssignment) -> bool {
+    assignment.hunk_header.is_some()
+        && assignment.mode_flags.is_none()
+        && assignment
+            .diff
+            .as_ref()
+            .is_some_and(|diff| diff_is_whitespace_only(diff.as_ref()))
+}
+
+fn diff_is_whitespace_only(diff: &BStr) -> bool {
+    let (mut removed, mut added) = (Vec::new(), Vec::new());
+    let mut has_changes = false;
+    for line in diff.lines().skip_while(|line| line.starts_with(b"@@")) {
+        let (out, content) = match line.split_first() {
+            Some((b'-', content)) => (&mut removed, content),
+            Some((b'+', content)) => (&mut added, content),
+            _ => continue,
+        };
+        has_changes = true;
+        out.extend(content.iter().filter(|b| !b.is_ascii_whitespace()));
+    }
+    has_changes && removed == added
+}
+
+#[cfg(test)]
+mod tests {
+    use super::diff_is_whitespace_only;
+
+    #[test]
+    fn whitespace_only_diffs() {
+        assert!(diff_is_whitespace_only(
+            "@@ -1,2 +1,2 @@\n-fn a() {\n-  b();\n+fn a() {\n+\tb();\n".into()
+        ));
+        assert!(
+            diff_is_whitespace_only("@@ -1,1 +1,2 @@\n-a, b\n+a,\n+    b\n".into()),
+            "re-wrapped lines only change whitespace"
+        );
+        assert!(
+            diff_is_whitespace_only("@@ -1,2 +1,1 @@\n a\n-\n".into()),
+            "removing an empty line only changes whitespace"
+        );
+    }
+
+    #[test]
+    fn diffs_with_content_changes() {
+        assert!(!diff_is_whitespace_only(
+            "@@ -1,1 +1,1 @@\n-a = 1\n+a = 2\n".into()
+        ));
+        assert!(
+            !diff_is_whitespace_only("@@ -1,1 +1,1 @@\n a\n".into()),
+            "context alone isn't a change"
+        );
+    }
+}
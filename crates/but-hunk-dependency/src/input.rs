@@ -70,6 +70,8 @@ impl InputDiffHunk {
             new_start,
             new_lines,
             diff: _,
+            encoding: _,
+            word_diff: _,
         }: &but_core::unified_diff::DiffHunk,
     ) -> Self {
         InputDiffHunk {
@@ -187,8 +187,15 @@ pub fn tree_changes_to_input_files(
     changes: Vec<TreeChange>,
 ) -> anyhow::Result<Vec<InputFile>> {
     let mut files = Vec::new();
-    for change in changes {
-        let diff = change.unified_patch(repo, 0)?;
+    let diffs = but_core::diff::unified_patches(
+        repo,
+        &changes,
+        but_core::shallow::ChangesSource::Trees,
+        0,
+        None,
+    );
+    for (change, diff) in changes.into_iter().zip(diffs) {
+        let diff = diff?;
         let Some(UnifiedPatch::Patch { hunks, .. }) = diff else {
             trace::warn!(
                 "Skipping change at '{}' as it doesn't have hunks to calculate dependencies for (binary/too large)",
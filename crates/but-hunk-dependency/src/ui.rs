@@ -50,8 +50,15 @@ impl HunkDependencies {
         worktree_changes: Vec<but_core::TreeChange>,
     ) -> anyhow::Result<HunkDependencies> {
         let mut diffs = Vec::<(String, DiffHunk, Vec<HunkLock>)>::new();
-        for change in worktree_changes {
-            let unidiff = change.unified_patch(repo, 0 /* zero context lines */)?;
+        let unidiffs = but_core::diff::unified_patches(
+            repo,
+            &worktree_changes,
+            but_core::shallow::ChangesSource::Worktree,
+            0, /* zero context lines */
+            None,
+        );
+        for (change, unidiff) in worktree_changes.iter().zip(unidiffs) {
+            let unidiff = unidiff?;
             let Some(UnifiedPatch::Patch { hunks, .. }) = unidiff else {
                 continue;
             };
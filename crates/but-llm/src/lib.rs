@@ -145,6 +145,39 @@ impl LLMProvider {
         }
     }
 
+    /// Creates a new LLM provider by the name of its kind, like `openai`, `anthropic` or `ollama`.
+    ///
+    /// Credentials are discovered just like with [`Self::new()`], while `model` and `endpoint`
+    /// override what the provider would use by default. The `endpoint` is the base URL of an
+    /// OpenAI-compatible API, or the `host:port` of an ollama server. Anthropic ignores it.
+    ///
+    /// # Returns
+    ///
+    /// Returns `None` if `provider` isn't known, isn't supported (e.g., LMStudio),
+    /// or if the provider couldn't be initialized.
+    pub fn with_options(
+        provider: &str,
+        model: Option<String>,
+        endpoint: Option<String>,
+    ) -> Option<Self> {
+        let client = match LLMProviderKind::from_str(provider)? {
+            LLMProviderKind::OpenAi => LLMClientType::OpenAi(Arc::new(
+                openai::OpenAiProvider::with(None, model, endpoint)?,
+            )),
+            LLMProviderKind::Anthropic => {
+                LLMClientType::Anthropic(Arc::new(anthropic::AnthropicProvider::with(None, model)?))
+            }
+            LLMProviderKind::Ollama => {
+                let config = ollama::OllamaConfig {
+                    host_config: endpoint.map(Into::into),
+                };
+                LLMClientType::Ollama(Arc::new(ollama::OllamaProvider::new(config, model)))
+            }
+            LLMProviderKind::LMStudio => return None,
+        };
+        Some(Self { client })
+    }
+
     /// Returns the model identifier configured for this LLM provider.
     ///
     /// This method retrieves the model name that was configured for the provider
@@ -213,6 +213,8 @@ impl Snapshot {
                     pr_number: None,
                     archived: false,
                     review_id: None,
+                    remote_head: None,
+                    detached: false,
                 });
             }
 
@@ -789,9 +791,15 @@ fn branch_from_ref_name(ref_name: &FullNameRef) -> anyhow::Result<RemoteRefname>
     let (category, short_name) = ref_name
         .category_and_short_name()
         .context("couldn't classify supposed remote tracking branch")?;
+    if category == Category::LocalBranch {
+        return Ok(RemoteRefname::new(
+            RemoteRefname::LOCAL_REMOTE,
+            short_name.to_str_lossy().as_ref(),
+        ));
+    }
     if category != Category::RemoteBranch {
         bail!(
-            "Cannot set target branches to a branch that isn't a remote tracking branch: '{short_name}'"
+            "Cannot set target branches to a branch that isn't a local or remote tracking branch: '{short_name}'"
         );
     }
 
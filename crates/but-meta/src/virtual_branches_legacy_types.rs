@@ -27,7 +27,7 @@ mod stack {
     use std::{fmt, fmt::Display, path, str::FromStr};
 
     use anyhow::{Context as _, anyhow};
-    use but_core::ref_metadata::StackId;
+    use but_core::ref_metadata::{StackId, StackIdentity, StackNotes};
     use gitbutler_reference::{Refname, RemoteRefname};
     use serde::{Deserialize, Serialize, Serializer};
 
@@ -54,6 +54,12 @@ mod stack {
         /// Do **NOT** edit this directly, instead use the `Stack` trait in gitbutler_stack.
         #[serde(default)]
         pub heads: Vec<StackBranch>,
+        /// Who to author and commit as in this stack, if not as configured in Git.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub identity: Option<StackIdentity>,
+        /// The description of the stack and notes about its commits.
+        #[serde(default, skip_serializing_if = "StackNotes::is_empty")]
+        pub stack_notes: StackNotes,
 
         // For serialization backwards compatibility
         // These should not be read, it's just to satisfy past versions of the app
@@ -149,6 +155,8 @@ mod stack {
                 // Don't keep redundant information
                 source_refname: None,
                 upstream: None,
+                identity: None,
+                stack_notes: StackNotes::default(),
 
                 // Unused - everything is defined by the top-most branch name.
                 // unclear, obsolete
@@ -199,6 +207,16 @@ mod stack {
 
         #[serde(default)]
         pub review_id: Option<String>,
+        /// The commit the remote branch was last seen at when pushing, which is the lease for force-pushes.
+        #[serde(
+            with = "but_serde::object_id_opt",
+            default,
+            skip_serializing_if = "Option::is_none"
+        )]
+        pub remote_head: Option<gix::ObjectId>,
+        /// The reference of the branch was deleted outside of GitButler, like with `git branch -D`.
+        #[serde(default)]
+        pub detached: bool,
     }
 
     impl StackBranch {
@@ -214,6 +232,8 @@ mod stack {
                 archived,
                 review_id,
                 head: gix::hash::Kind::Sha1.null(),
+                remote_head: None,
+                detached: false,
             }
         }
     }
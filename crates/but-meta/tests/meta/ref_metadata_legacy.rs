@@ -936,6 +936,17 @@ fn target_journey() -> anyhow::Result<()> {
         "can change the name as well"
     );
 
+    let local_target: gix::refs::FullName = "refs/heads/main".try_into()?;
+    ws.target_ref = Some(local_target.clone());
+    store.set_workspace(&ws)?;
+
+    let mut ws = store.workspace(ws_name)?;
+    assert_eq!(
+        ws.target_ref,
+        Some(local_target),
+        "local branches can be targets, too"
+    );
+
     ws.target_ref = None;
     store.set_workspace(&ws)?;
 
@@ -74,6 +74,15 @@ pub(crate) mod function {
 
         let conflict_kind = gix::merge::tree::TreatAsUnresolved::forced_resolution();
         if cherry_pick.has_unresolved_conflicts(conflict_kind) {
+            if let Some(resolved_tree_id) = resolve_conflicts(&base, &commit_to_rebase) {
+                let resolved_tree_id = resolved_tree_id.attach(base.id.repo);
+                return commit_from_unconflicted_tree(
+                    base,
+                    commit_to_rebase,
+                    resolved_tree_id,
+                    empty_commit,
+                );
+            }
             commit_from_conflicted_tree(base, commit_to_rebase, tree_id, cherry_pick, conflict_kind)
         } else {
             commit_from_unconflicted_tree(base, commit_to_rebase, tree_id, empty_commit)
@@ -103,6 +112,12 @@ pub(crate) mod function {
     ) -> anyhow::Result<gix::merge::tree::Outcome<'repo>> {
         let repo = to_rebase.id.repo;
         let (base, ours, theirs) = find_cherry_pick_trees(new_base, to_rebase)?;
+        but_core::shallow::fetch_blobs_for_merge(
+            repo,
+            base.detach(),
+            ours.detach(),
+            theirs.detach(),
+        )?;
         use but_core::RepositoryExt;
         repo.merge_trees(
             base,
@@ -114,6 +129,32 @@ pub(crate) mod function {
         .context("failed to merge trees for cherry pick")
     }
 
+    /// Return the tree of the cherry-pick of `to_rebase` onto `new_base` with all of its conflicts resolved
+    /// by merge drivers or like they were resolved before, or `None` if not all of them could be resolved.
+    fn resolve_conflicts(
+        new_base: &but_core::Commit<'_>,
+        to_rebase: &but_core::Commit<'_>,
+    ) -> Option<gix::ObjectId> {
+        let repo = to_rebase.id.repo;
+        let resolved =
+            find_cherry_pick_trees(new_base, to_rebase).and_then(|(base, ours, theirs)| {
+                crate::merge_driver::resolve_conflicts(
+                    repo,
+                    base.detach(),
+                    ours.detach(),
+                    theirs.detach(),
+                )
+            });
+        match resolved {
+            Ok(resolved) => resolved,
+            Err(err) => {
+                // The conflicts are kept for resolving them by hand instead.
+                tracing::warn!(?err, commit_id = %to_rebase.id, "could not resolve conflicts automatically");
+                None
+            }
+        }
+    }
+
     /// Return `(base, ours, theirs)` suitable for cherry-pick merges from the `new_base` for `to_rebase`.
     fn find_cherry_pick_trees<'repo>(
         new_base: &but_core::Commit<'repo>,
@@ -128,7 +169,10 @@ pub(crate) mod function {
         } else {
             let base_commit_id = to_rebase.parents.first().context("no parent")?;
             // Use the parent's auto-resolution
-            let base_commit = but_core::Commit::from_id(base_commit_id.attach(repo))?;
+            let base_commit =
+                but_core::Commit::from_id(base_commit_id.attach(repo)).map_err(|err| {
+                    but_core::shallow::into_shallow_history_error(repo, to_rebase.id.detach(), err)
+                })?;
             find_real_tree(&base_commit, TreeKind::AutoResolution)?
         };
         // Get the auto-resolution
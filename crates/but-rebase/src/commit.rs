@@ -1,10 +1,7 @@
-use std::{borrow::Cow, io::Write, path::Path, process::Stdio};
-
-use anyhow::{Context as _, anyhow, bail};
-use bstr::{BStr, BString, ByteSlice};
-use but_core::{GitConfigSettings, RepositoryExt, cmd::prepare_with_shell_on_windows};
-use but_error::Code;
-use gix::{config::Source, objs::WriteTo};
+use anyhow::{Context as _, bail};
+use bstr::BStr;
+use but_core::RepositoryExt;
+use gix::config::Source;
 
 /// What to do with the committer (actor) and the commit time when [creating a new commit](create()).
 #[derive(Debug, Copy, Clone)]
@@ -101,49 +98,17 @@ pub fn create(
             update_author_time(repo, &mut commit)?;
         }
     }
-    if let Some(pos) = commit
-        .extra_headers()
-        .find_pos(gix::objs::commit::SIGNATURE_FIELD_NAME)
-    {
-        commit.extra_headers.remove(pos);
-    }
     let settings = repo.git_settings()?;
     if settings.gitbutler_gerrit_mode.unwrap_or(false) {
         but_gerrit::set_trailers(&mut commit);
     }
-    if sign_if_configured && settings.gitbutler_sign_commits.unwrap_or(false) {
-        let mut buf = Vec::new();
-        commit.write_to(&mut buf)?;
-        match sign_buffer(repo, &buf) {
-            Ok(signature) => {
-                commit
-                    .extra_headers
-                    .push((gix::objs::commit::SIGNATURE_FIELD_NAME.into(), signature));
-            }
-            Err(err) => {
-                // If signing fails, turn off signing automatically and let everyone know,
-                // but only if it's not already configured globally (which implies user intervention).
-                if repo
-                    .config_snapshot()
-                    .boolean_filter("gitbutler.signCommits", |md| {
-                        md.source != gix::config::Source::Local
-                    })
-                    .is_none()
-                {
-                    repo.set_git_settings(&GitConfigSettings {
-                        gitbutler_sign_commits: Some(false),
-                        ..GitConfigSettings::default()
-                    })?;
-                    return Err(anyhow!("Failed to sign commit: {}", err)
-                        .context(Code::CommitSigningFailed));
-                } else {
-                    tracing::warn!(
-                        "Commit signing failed but remains enabled as gitbutler.signCommits is explicitly enabled globally"
-                    );
-                    return Err(err);
-                }
-            }
-        }
+    if sign_if_configured {
+        but_core::signing::sign_commit(repo, &mut commit)?;
+    } else if let Some(pos) = commit
+        .extra_headers()
+        .find_pos(gix::objs::commit::SIGNATURE_FIELD_NAME)
+    {
+        commit.extra_headers.remove(pos);
     }
 
     Ok(repo.write_object(&commit)?.detach())
@@ -176,160 +141,4 @@ pub(crate) fn update_author_time(
 }
 
 /// Sign the given `buffer` using configuration from `repo`, just like Git would.
-pub fn sign_buffer(repo: &gix::Repository, buffer: &[u8]) -> anyhow::Result<BString> {
-    // TODO: support gpg.ssh.defaultKeyCommand to get the signing key if this value doesn't exist
-    let config = repo.config_snapshot();
-    let signing_key = signing_key(repo)?;
-    let sign_format = config.string("gpg.format");
-    let is_ssh = if let Some(sign_format) = sign_format {
-        sign_format.as_ref() == "ssh"
-    } else {
-        false
-    };
-
-    if is_ssh {
-        // write commit data to a temp file so we can sign it
-        let mut signature_storage = tempfile::NamedTempFile::new()?;
-        signature_storage.write_all(buffer)?;
-        let buffer_file_to_sign_path = signature_storage.into_temp_path();
-
-        let gpg_program = config
-            .trusted_program("gpg.ssh.program")
-            .filter(|program| !program.is_empty())
-            .map_or_else(
-                || Path::new("ssh-keygen").into(),
-                |program| Cow::Owned(program.into_owned().into()),
-            );
-
-        let mut signing_cmd = prepare_with_shell_on_windows(gpg_program.into_owned())
-            .args(["-Y", "sign", "-n", "git", "-f"]);
-
-        // Write the key to a temp file. This is needs to be created in the
-        // same scope where its used; IE: in the command, otherwise the
-        // tmpfile will get removed too early.
-        let _key_storage;
-        signing_cmd = if let Some(signing_key) = as_literal_key(signing_key.as_bstr()) {
-            let mut keyfile = tempfile::NamedTempFile::new()?;
-            keyfile.write_all(signing_key.as_bytes())?;
-
-            // if on unix
-            #[cfg(unix)]
-            {
-                use std::os::unix::prelude::PermissionsExt;
-                // make sure the tempfile permissions are acceptable for a private ssh key
-                let mut permissions = keyfile.as_file().metadata()?.permissions();
-                permissions.set_mode(0o600);
-                keyfile.as_file().set_permissions(permissions)?;
-            }
-
-            let keyfile_path = keyfile.path().to_owned();
-            _key_storage = keyfile.into_temp_path();
-            signing_cmd
-                .arg(keyfile_path)
-                .arg("-U")
-                .arg(buffer_file_to_sign_path.to_path_buf())
-        } else {
-            let signing_key = config
-                .trusted_path("user.signingkey")
-                .transpose()?
-                .with_context(|| format!("Didn't trust 'ssh.signingKey': {signing_key}"))?;
-            signing_cmd
-                .arg(signing_key.into_owned())
-                .arg(buffer_file_to_sign_path.to_path_buf())
-        };
-        let output = into_command(signing_cmd)
-            .stderr(Stdio::piped())
-            .stdout(Stdio::piped())
-            .stdin(Stdio::null())
-            .output()?;
-
-        if output.status.success() {
-            // read signed_storage path plus .sig
-            let signature_path = buffer_file_to_sign_path.with_extension("sig");
-            let sig_data = std::fs::read(signature_path)?;
-            let signature = BString::new(sig_data);
-            Ok(signature)
-        } else {
-            let stderr = BString::new(output.stderr);
-            let stdout = BString::new(output.stdout);
-            let std_both = format!("{stdout} {stderr}");
-            bail!("Failed to sign SSH: {}", std_both);
-        }
-    } else {
-        let gpg_program = config
-            .trusted_program("gpg.program")
-            .filter(|program| !program.is_empty())
-            .map_or_else(
-                || Path::new("gpg").into(),
-                |program| Cow::Owned(program.into_owned().into()),
-            );
-
-        let mut cmd = into_command(
-            prepare_with_shell_on_windows(gpg_program.as_ref())
-                .args(["--status-fd=2", "-bsau"])
-                .arg(gix::path::from_bstring(signing_key))
-                .arg("-"),
-        );
-        cmd.stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .stdin(Stdio::piped());
-
-        let mut child = match cmd.spawn() {
-            Ok(child) => child,
-            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
-                bail!(
-                    "Could not find '{}'. Please make sure it is in your `PATH` or configure the full path using `gpg.program` in the Git configuration",
-                    gpg_program.display()
-                )
-            }
-            Err(err) => {
-                return Err(err).context(format!("Could not execute GPG program using {cmd:?}"));
-            }
-        };
-        child.stdin.take().expect("configured").write_all(buffer)?;
-
-        let output = child.wait_with_output()?;
-        if output.status.success() {
-            // read stdout
-            let signature = BString::new(output.stdout);
-            Ok(signature)
-        } else {
-            let stderr = BString::new(output.stderr);
-            let stdout = BString::new(output.stdout);
-            let std_both = format!("{stdout} {stderr}");
-            bail!("Failed to sign GPG: {}", std_both);
-        }
-    }
-}
-
-fn into_command(prepare: gix::command::Prepare) -> std::process::Command {
-    let cmd: std::process::Command = prepare.into();
-    tracing::debug!(?cmd, "command to produce commit signature");
-    cmd
-}
-
-fn as_literal_key(maybe_key: &BStr) -> Option<&BStr> {
-    if let Some(key) = maybe_key.strip_prefix(b"key::") {
-        return Some(key.into());
-    }
-    if maybe_key.starts_with(b"ssh-") {
-        return Some(maybe_key);
-    }
-    None
-}
-
-/// Fail if there is no usable signing key.
-fn signing_key(repo: &gix::Repository) -> anyhow::Result<BString> {
-    if let Some(key) = repo.config_snapshot().string("user.signingkey") {
-        return Ok(key.into_owned());
-    }
-    tracing::info!("Falling back to committer identity as user.signingKey isn't configured.");
-    let mut buf = Vec::<u8>::new();
-    repo.committer()
-        .transpose()?
-        .context("user.signingKey isn't configured and no committer is available either")?
-        .actor()
-        .trim()
-        .write_to(&mut buf)?;
-    Ok(buf.into())
-}
+pub use but_core::signing::sign_buffer;
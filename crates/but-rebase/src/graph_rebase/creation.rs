@@ -154,6 +154,7 @@ impl GraphExt for Graph {
             checkouts: head_selectors.into_iter().map(Checkout::Head).collect(),
             repo: repo.clone().with_object_memory(),
             history: RevisionHistory::new(),
+            rewritten_commits: Vec::new(),
         })
     }
 }
@@ -167,6 +168,7 @@ impl SuccessfulRebase {
             checkouts: self.checkouts,
             repo: self.repo,
             history: self.history,
+            rewritten_commits: self.rewritten_commits,
         }
     }
 }
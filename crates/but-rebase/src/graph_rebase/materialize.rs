@@ -59,6 +59,7 @@ impl SuccessfulRebase {
         Ok(MaterializeOutcome {
             graph: self.graph,
             history: self.history,
+            rewritten_commits: self.rewritten_commits,
         })
     }
 
@@ -88,6 +89,7 @@ impl SuccessfulRebase {
         Ok(MaterializeOutcome {
             graph: self.graph,
             history: self.history,
+            rewritten_commits: self.rewritten_commits,
         })
     }
 }
@@ -128,6 +128,8 @@ pub struct Editor {
     /// The in-memory repository that the rebase engine works with.
     repo: gix::Repository,
     history: RevisionHistory,
+    /// Commits that were rewritten so far as `(old, new)` pairs, in the order in which they were rewritten.
+    rewritten_commits: Vec<(gix::ObjectId, gix::ObjectId)>,
 }
 
 /// Represents a successful rebase, and any valid, but potentially conflicting scenarios it had.
@@ -143,6 +145,7 @@ pub struct SuccessfulRebase {
     pub(crate) graph: StepGraph,
     pub(crate) checkouts: Vec<Checkout>,
     pub(crate) history: RevisionHistory,
+    pub(crate) rewritten_commits: Vec<(gix::ObjectId, gix::ObjectId)>,
 }
 
 /// The outcome of a materialize
@@ -150,6 +153,17 @@ pub struct SuccessfulRebase {
 pub struct MaterializeOutcome {
     pub(crate) graph: StepGraph,
     pub(crate) history: RevisionHistory,
+    pub(crate) rewritten_commits: Vec<(gix::ObjectId, gix::ObjectId)>,
+}
+
+impl MaterializeOutcome {
+    /// Return the commits that were rewritten as `(old, new)` pairs, in the order in which they were rewritten.
+    ///
+    /// A commit that was rewritten more than once, like one that was reworded and then rebased, appears in
+    /// a chain of pairs, and commits that were newly created don't appear at all.
+    pub fn rewritten_commits(&self) -> &[(gix::ObjectId, gix::ObjectId)] {
+        &self.rewritten_commits
+    }
 }
 
 /// Provides lookup for different steps that a selector might point to.
@@ -71,12 +71,18 @@ impl Editor {
     }
 
     /// Replaces the node that the function was pointing to.
+    /// Replacing a pick with a pick of another commit counts as rewriting the commit.
     ///
     /// Returns the replaced step.
     pub fn replace(&mut self, target: Selector, step: Step) -> Result<Step> {
         let target = self.history.normalize_selector(target)?;
-        let old = self.graph[target.id].clone();
-        self.graph[target.id] = step;
+        let old = std::mem::replace(&mut self.graph[target.id], step);
+        if let (Step::Pick(Pick { id: old_id, .. }), Step::Pick(Pick { id: new_id, .. })) =
+            (&old, &self.graph[target.id])
+            && old_id != new_id
+        {
+            self.rewritten_commits.push((*old_id, *new_id));
+        }
         Ok(old)
     }
 
@@ -27,6 +27,7 @@ impl Editor {
         validate_reference_constraints(&self.graph)?;
 
         let mut ref_edits = vec![];
+        let mut rewritten_commits = self.rewritten_commits;
         let steps_to_pick = order_steps_picking(
             &self.graph,
             &self
@@ -80,6 +81,9 @@ impl Editor {
                         CherryPickOutcome::Commit(new_id)
                         | CherryPickOutcome::ConflictedCommit(new_id)
                         | CherryPickOutcome::Identity(new_id) => {
+                            if new_id != pick.id {
+                                rewritten_commits.push((pick.id, new_id));
+                            }
                             let mut new_pick = pick.clone();
                             new_pick.id = new_id;
                             let new_idx = output_graph.add_node(Step::Pick(new_pick));
@@ -199,6 +203,7 @@ impl Editor {
             graph: output_graph,
             checkouts: self.checkouts.to_owned(),
             history,
+            rewritten_commits,
         })
     }
 }
@@ -24,6 +24,15 @@ pub mod commit;
 /// Utilities around merging
 pub mod merge;
 
+/// Recorded resolutions of conflicts, reused when the same conflicts appear again
+pub mod rerere;
+
+/// Merge drivers from `.gitattributes` that resolve conflicts
+pub mod merge_driver;
+
+/// A builder for interactive rebases, lowered into [`RebaseStep`]s
+pub mod plan;
+
 /// An instruction for [`Rebase::rebase()`].
 #[derive(Debug, Clone)]
 pub enum RebaseStep {
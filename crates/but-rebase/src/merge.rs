@@ -3,8 +3,6 @@ use bstr::{BString, ByteSlice};
 use but_core::{RepositoryExt, commit::TreeKind};
 use gix::prelude::ObjectIdExt;
 
-use crate::commit::DateMode;
-
 /// Perform a three-base merge for each of the parents in `target_merge_commit` which serves as template for the merge.
 /// This means that after merging, we will use it unchanged to create a new, possibly signed commit, after adjusting its
 /// tree to point to the merge result of its parents.
@@ -20,10 +18,10 @@ use crate::commit::DateMode;
 ///
 /// ### About Signing
 ///
-/// Merges are special, and we will *not* sign it if it wasn't yet signed. That way workspace commits will naturally
-/// remain unsigned.
-/// However, if we re-merge a commit that was signed before it's likely a user-commit that should be treated accordingly.
-/// Thanks to this logic, the caller shouldn't have to steer signing.
+/// Merges that were signed before are likely user commits, and are signed like any other commit if `gitbutler.signCommits`
+/// is enabled. All other merges, like workspace commits, are signed if possible so they don't trip up repositories that
+/// enforce signatures, but remain unsigned if signing fails. Otherwise, a previous signature is removed.
+/// Unlike other commits, merges don't receive a Gerrit `Change-Id` as they aren't meant to be reviewed.
 pub fn octopus(
     repo: &gix::Repository,
     mut target_merge_commit: gix::objs::Commit,
@@ -65,6 +63,14 @@ pub fn octopus(
             repo.default_merge_labels(),
             merge_options.clone(),
         )?;
+        if merge.has_unresolved_conflicts(unresolved)
+            && let Some(resolved_tree) =
+                crate::merge_driver::resolve_conflicts(repo, merge_base, ours, tree_to_merge)?
+        {
+            successfully_merged.push(tree_to_merge);
+            ours = resolved_tree;
+            continue;
+        }
         if merge.has_unresolved_conflicts(unresolved) {
             return Err(anyhow!(
                 "Encountered conflict when merging tree {tree_to_merge}{details}",
@@ -97,21 +103,17 @@ pub fn octopus(
         but_core::commit::Headers::from_config(&repo.config_snapshot())
             .set_in_commit(&mut target_merge_commit);
     }
-    if target_merge_commit
+    let was_signed = target_merge_commit
         .extra_headers()
         .pgp_signature()
-        .is_some()
-    {
-        crate::commit::create(
-            repo,
-            target_merge_commit,
-            DateMode::CommitterUpdateAuthorKeep,
-            true,
-        )
+        .is_some();
+    crate::commit::update_committer(repo, &mut target_merge_commit)?;
+    if was_signed {
+        but_core::signing::sign_commit(repo, &mut target_merge_commit)?;
     } else {
-        crate::commit::update_committer(repo, &mut target_merge_commit)?;
-        Ok(repo.write_object(target_merge_commit)?.detach())
+        but_core::signing::try_sign_commit(repo, &mut target_merge_commit)?;
     }
+    Ok(repo.write_object(target_merge_commit)?.detach())
 }
 
 /// A type that can be retrieved as an `anyhow` context to see if the rebase failed due to merge conflicts.
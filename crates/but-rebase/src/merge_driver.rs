@@ -0,0 +1,249 @@
+//! Resolve conflicts with the merge drivers assigned to files by the `merge` attribute in `.gitattributes`,
+//! which is what makes conflicts in changelogs or lockfiles go away.
+//!
+//! `merge=union` keeps the lines of both sides, and `merge=<name>` runs the command configured
+//! in `merge.<name>.driver` like Git does. Conflicts that drivers can't resolve are looked up
+//! in the [recorded resolutions](crate::rerere).
+use std::path::PathBuf;
+
+use anyhow::Result;
+use bstr::{BStr, ByteSlice};
+use gix::prelude::ObjectIdExt as _;
+
+use crate::rerere::{self, ConflictedFile};
+
+/// Merge `ours` and `theirs` with `base` and resolve all conflicts with merge drivers or recorded resolutions,
+/// returning the merged tree, or `None` if there were no conflicts or if not all of them could be resolved.
+pub fn resolve_conflicts(
+    repo: &gix::Repository,
+    base: gix::ObjectId,
+    ours: gix::ObjectId,
+    theirs: gix::ObjectId,
+) -> Result<Option<gix::ObjectId>> {
+    let Some((merged_tree, conflicted_files)) = rerere::conflicted_files(repo, base, ours, theirs)?
+    else {
+        return Ok(None);
+    };
+    let mut drivers = MergeDrivers::new(repo)?;
+    let mut editor = merged_tree.attach(repo).object()?.into_tree().edit()?;
+    for file in conflicted_files {
+        let resolved = match drivers.resolve(&file)? {
+            Some(resolved) => resolved,
+            None => match rerere::resolve(repo, &file.data)? {
+                Some(resolved) => resolved,
+                None => return Ok(None),
+            },
+        };
+        editor.upsert(
+            file.path.as_bstr(),
+            file.mode.kind(),
+            repo.write_blob(resolved)?,
+        )?;
+    }
+    Ok(Some(editor.write()?.detach()))
+}
+
+/// A merge driver that resolves conflicts.
+enum Driver {
+    /// Keep the lines of both sides, ours first.
+    Union,
+    /// Run the command of the driver with `name`.
+    External { name: String, command: String },
+}
+
+/// The merge drivers configured for the files of a repository.
+struct MergeDrivers<'repo> {
+    repo: &'repo gix::Repository,
+    stack: gix::AttributeStack<'repo>,
+    outcome: gix::attrs::search::Outcome,
+}
+
+impl<'repo> MergeDrivers<'repo> {
+    /// Read attributes from the worktree of `repo`, and from its index for files that aren't checked out.
+    fn new(repo: &'repo gix::Repository) -> Result<Self> {
+        let index = repo.index_or_empty()?;
+        let stack = repo.attributes_only(
+            &index,
+            gix::worktree::stack::state::attributes::Source::WorktreeThenIdMapping,
+        )?;
+        let outcome = stack.selected_attribute_matches(Some("merge"));
+        Ok(MergeDrivers {
+            repo,
+            stack,
+            outcome,
+        })
+    }
+
+    /// Return the driver that resolves conflicts in the file at `rela_path`, or `None` if it's merged line by line.
+    fn driver(&mut self, rela_path: &BStr) -> Result<Option<Driver>> {
+        let platform = self.stack.at_entry(rela_path, None)?;
+        if !platform.matching_attributes(&mut self.outcome) {
+            return Ok(None);
+        }
+        let Some(name) = self
+            .outcome
+            .iter()
+            .find_map(|m| m.assignment.state.as_bstr())
+            .map(|name| name.to_str_lossy().into_owned())
+        else {
+            return Ok(None);
+        };
+        Ok(match name.as_str() {
+            "union" => Some(Driver::Union),
+            "text" | "binary" => None,
+            _ => self
+                .repo
+                .config_snapshot()
+                .string(format!("merge.{name}.driver").as_str())
+                .map(|command| Driver::External {
+                    command: command.to_str_lossy().into_owned(),
+                    name,
+                }),
+        })
+    }
+
+    /// Resolve the conflicts in `file` with its driver, or return `None` if it has none or the driver failed.
+    fn resolve(&mut self, file: &ConflictedFile) -> Result<Option<Vec<u8>>> {
+        match self.driver(file.path.as_bstr())? {
+            None => Ok(None),
+            Some(Driver::Union) => Ok(union(&file.data)),
+            Some(Driver::External { name, command }) => {
+                run_external_driver(self.repo, &name, &command, file)
+            }
+        }
+    }
+}
+
+/// Replace each conflict in `conflicted`, the content of a file with conflict markers, with the lines of both of its sides,
+/// or return `None` if there are no conflicts.
+fn union(conflicted: &[u8]) -> Option<Vec<u8>> {
+    let lines: Vec<&[u8]> = conflicted.lines_with_terminator().collect();
+    let regions = rerere::conflict_regions(&lines);
+    if regions.is_empty() {
+        return None;
+    }
+    let mut out = Vec::with_capacity(conflicted.len());
+    let mut next_line = 0;
+    for region in regions {
+        out.extend(lines[next_line..region.lines.start as usize].concat());
+        out.extend_from_slice(&region.ours);
+        out.extend_from_slice(&region.theirs);
+        next_line = region.lines.end as usize;
+    }
+    out.extend(lines[next_line..].concat());
+    Some(out)
+}
+
+/// Run `command` of the merge driver `name` like Git does, with `%O`, `%A` and `%B` replaced by files with the base,
+/// our and their version of `file`, `%L` by the conflict marker size and `%P` by its path.
+///
+/// The driver leaves the result in the file of our version and exits with a non-zero code if there are conflicts left,
+/// in which case `None` is returned.
+fn run_external_driver(
+    repo: &gix::Repository,
+    name: &str,
+    command: &str,
+    file: &ConflictedFile,
+) -> Result<Option<Vec<u8>>> {
+    let tmp = tempfile::tempdir()?;
+    let write_version = |version: &str, id: Option<gix::ObjectId>| -> Result<PathBuf> {
+        let path = tmp.path().join(version);
+        let data = match id {
+            Some(id) => repo.find_blob(id)?.take_data(),
+            None => Vec::new(),
+        };
+        std::fs::write(&path, data)?;
+        Ok(path)
+    };
+    let base = write_version("base", file.base_id)?;
+    let ours = write_version("ours", file.ours_id)?;
+    let theirs = write_version("theirs", file.theirs_id)?;
+    let command = expand_placeholders(
+        command,
+        &[
+            ('O', quote(&base.to_string_lossy())),
+            ('A', quote(&ours.to_string_lossy())),
+            ('B', quote(&theirs.to_string_lossy())),
+            ('L', "7".into()),
+            ('P', quote(&file.path.to_str_lossy())),
+        ],
+    );
+
+    let output = std::process::Command::from(gix::command::prepare(command).with_shell())
+        .current_dir(repo.workdir().unwrap_or(repo.git_dir()))
+        .stdin(std::process::Stdio::null())
+        .output()?;
+    if !output.status.success() {
+        tracing::debug!(
+            driver = name,
+            path = %file.path,
+            stderr = %output.stderr.as_bstr(),
+            "merge driver left conflicts"
+        );
+        return Ok(None);
+    }
+    Ok(Some(std::fs::read(&ours)?))
+}
+
+/// Replace `%<char>` in `command` with the value of its placeholder, and `%%` with `%`.
+fn expand_placeholders(command: &str, placeholders: &[(char, String)]) -> String {
+    let mut out = String::with_capacity(command.len());
+    let mut chars = command.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('%') => out.push('%'),
+            Some(placeholder) => match placeholders.iter().find(|(name, _)| *name == placeholder) {
+                Some((_, value)) => out.push_str(value),
+                None => {
+                    out.push('%');
+                    out.push(placeholder);
+                }
+            },
+            None => out.push('%'),
+        }
+    }
+    out
+}
+
+/// Quote `value` for use as a single argument in a shell command.
+fn quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn union_keeps_both_sides_of_each_conflict() {
+        let conflicted =
+            b"# Changelog\n<<<<<<< ours\n- a\n||||||| base\n=======\n- b\n>>>>>>> theirs\nend\n";
+        assert_eq!(
+            union(conflicted).as_deref(),
+            Some(&b"# Changelog\n- a\n- b\nend\n"[..])
+        );
+        assert_eq!(union(b"no conflicts\n"), None);
+    }
+
+    #[test]
+    fn placeholders_are_expanded_once() {
+        let command = expand_placeholders(
+            "merge-json %O %A %B -L %L --path=%P 100%% %X",
+            &[
+                ('O', quote("/tmp/base")),
+                ('A', quote("/tmp/%B")),
+                ('B', quote("/tmp/theirs")),
+                ('L', "7".into()),
+                ('P', quote("it's.json")),
+            ],
+        );
+        assert_eq!(
+            command,
+            r"merge-json '/tmp/base' '/tmp/%B' '/tmp/theirs' -L 7 --path='it'\''s.json' 100% %X"
+        );
+    }
+}
@@ -0,0 +1,388 @@
+//! A higher-level, `git rebase -i`-like way of describing a rebase, which is lowered into [`RebaseStep`]s for execution.
+use std::{path::Path, process::Stdio};
+
+use anyhow::{Context as _, Result, bail};
+use bstr::{BString, ByteSlice};
+use gix::prelude::ObjectIdExt;
+
+use crate::{Rebase, RebaseOutput, RebaseStep};
+
+/// A single instruction of a [`RebasePlan`], modelled after the commands of `git rebase -i`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PlanStep {
+    /// Use the commit as is.
+    Pick(gix::ObjectId),
+    /// Use the commit, but replace its message with `message`.
+    Reword {
+        /// The commit to pick.
+        commit_id: gix::ObjectId,
+        /// The message to use instead of the original one.
+        message: BString,
+    },
+    /// Meld the commit into the previous one, and combine both messages unless `message` is set.
+    Squash {
+        /// The commit to squash into its predecessor.
+        commit_id: gix::ObjectId,
+        /// The message of the squashed commit, or `None` to concatenate both messages.
+        message: Option<BString>,
+    },
+    /// Meld the commit into the previous one, keeping only the message of the previous one.
+    Fixup(gix::ObjectId),
+    /// Remove the commit.
+    Drop(gix::ObjectId),
+    /// Run `command` with a shell in a temporary checkout of the previously rewritten commit,
+    /// with its id available in the `GITBUTLER_REBASE_COMMIT` environment variable.
+    /// The rebase is aborted if the command fails.
+    ///
+    /// As the checkout is a plain directory, changes made by the command are discarded.
+    Exec(String),
+    /// Place a reference on the previous commit.
+    Reference(but_core::Reference),
+}
+
+impl PlanStep {
+    /// Get the commit id associated with this step, if there is one.
+    pub fn commit_id(&self) -> Option<gix::ObjectId> {
+        match self {
+            PlanStep::Pick(commit_id)
+            | PlanStep::Reword { commit_id, .. }
+            | PlanStep::Squash { commit_id, .. }
+            | PlanStep::Fixup(commit_id)
+            | PlanStep::Drop(commit_id) => Some(*commit_id),
+            PlanStep::Exec(_) | PlanStep::Reference(_) => None,
+        }
+    }
+}
+
+/// A builder for a list of [steps](PlanStep) to perform on top of a `base` commit.
+///
+/// Unlike [`Rebase`], it knows about squashes with combined messages, fixups, dropped commits and commands to execute,
+/// which makes it possible to express arbitrary interactive rebases without having to translate each of them
+/// into [`RebaseStep`]s by hand.
+#[derive(Debug, Clone)]
+pub struct RebasePlan {
+    base: Option<gix::ObjectId>,
+    base_substitute: Option<gix::ObjectId>,
+    steps: Vec<PlanStep>,
+    rebase_noops: bool,
+}
+
+/// The result of [executing](RebasePlan::execute()) a plan.
+#[derive(Debug, Clone)]
+pub struct PlanOutcome {
+    /// The output of the underlying rebase, as if it was performed in one go.
+    pub output: RebaseOutput,
+    /// The rewritten commits that ended up conflicted, in order of the plan, as `(old, new)` pairs.
+    pub conflicted_commits: Vec<(gix::ObjectId, gix::ObjectId)>,
+    /// The commands that were executed along with the commit they were executed for.
+    pub executed_commands: Vec<(String, gix::ObjectId)>,
+}
+
+impl PlanOutcome {
+    /// Return `true` if at least one rewritten commit is conflicted.
+    pub fn has_conflicts(&self) -> bool {
+        !self.conflicted_commits.is_empty()
+    }
+}
+
+/// Lifecycle
+impl RebasePlan {
+    /// Create a new plan whose first picked commit will be placed on top of `base`.
+    /// See [`Rebase::new()`] for details on `base` and `base_substitute`.
+    pub fn new(
+        base: impl Into<Option<gix::ObjectId>>,
+        base_substitute: Option<gix::ObjectId>,
+    ) -> Self {
+        RebasePlan {
+            base: base.into(),
+            base_substitute,
+            steps: Vec::new(),
+            rebase_noops: true,
+        }
+    }
+
+    /// Create a plan from existing rebase `steps`, for instance as produced for a stack, so it can be altered further.
+    pub fn from_rebase_steps(
+        base: impl Into<Option<gix::ObjectId>>,
+        steps: impl IntoIterator<Item = RebaseStep>,
+    ) -> Self {
+        let mut plan = Self::new(base, None);
+        for step in steps {
+            plan.steps.push(match step {
+                RebaseStep::Pick {
+                    commit_id,
+                    new_message: None,
+                } => PlanStep::Pick(commit_id),
+                RebaseStep::Pick {
+                    commit_id,
+                    new_message: Some(message),
+                } => PlanStep::Reword { commit_id, message },
+                RebaseStep::SquashIntoPreceding {
+                    commit_id,
+                    new_message,
+                } => PlanStep::Squash {
+                    commit_id,
+                    message: new_message,
+                },
+                RebaseStep::Reference(reference) => PlanStep::Reference(reference),
+            });
+        }
+        plan
+    }
+}
+
+/// Building
+impl RebasePlan {
+    /// Add `step` to the end of the plan.
+    pub fn step(&mut self, step: PlanStep) -> &mut Self {
+        self.steps.push(step);
+        self
+    }
+
+    /// Pick `commit_id`.
+    pub fn pick(&mut self, commit_id: gix::ObjectId) -> &mut Self {
+        self.step(PlanStep::Pick(commit_id))
+    }
+
+    /// Pick `commit_id` and change its message to `message`.
+    pub fn reword(&mut self, commit_id: gix::ObjectId, message: impl Into<BString>) -> &mut Self {
+        self.step(PlanStep::Reword {
+            commit_id,
+            message: message.into(),
+        })
+    }
+
+    /// Squash `commit_id` into the previous commit, combining both messages.
+    pub fn squash(&mut self, commit_id: gix::ObjectId) -> &mut Self {
+        self.step(PlanStep::Squash {
+            commit_id,
+            message: None,
+        })
+    }
+
+    /// Fixup `commit_id` into the previous commit, discarding its message.
+    pub fn fixup(&mut self, commit_id: gix::ObjectId) -> &mut Self {
+        self.step(PlanStep::Fixup(commit_id))
+    }
+
+    /// Drop `commit_id`.
+    pub fn drop_commit(&mut self, commit_id: gix::ObjectId) -> &mut Self {
+        self.step(PlanStep::Drop(commit_id))
+    }
+
+    /// Execute `command` in a checkout of the commit produced by the previous step.
+    pub fn exec(&mut self, command: impl Into<String>) -> &mut Self {
+        self.step(PlanStep::Exec(command.into()))
+    }
+
+    /// Place `reference` onto the previous commit.
+    pub fn reference(&mut self, reference: but_core::Reference) -> &mut Self {
+        self.step(PlanStep::Reference(reference))
+    }
+
+    /// Configures whether the noop steps should be rebased regardless, see [`Rebase::rebase_noops()`].
+    pub fn rebase_noops(&mut self, value: bool) -> &mut Self {
+        self.rebase_noops = value;
+        self
+    }
+
+    /// Return all steps of the plan.
+    pub fn steps(&self) -> &[PlanStep] {
+        &self.steps
+    }
+}
+
+/// Execution
+impl RebasePlan {
+    /// Lower the plan into chunks of [`RebaseStep`]s, separated by the commands to execute after each of them.
+    fn lower(&self, repo: &gix::Repository) -> Result<Vec<(Vec<RebaseStep>, Option<String>)>> {
+        let mut chunks = Vec::new();
+        let mut current = Vec::new();
+        // The message of the most recent commit, which squashes and fixups will use.
+        let mut message_of_previous: Option<BString> = None;
+        for step in &self.steps {
+            match step {
+                PlanStep::Pick(commit_id) => {
+                    message_of_previous = Some(message_of(repo, *commit_id)?);
+                    current.push(RebaseStep::Pick {
+                        commit_id: *commit_id,
+                        new_message: None,
+                    });
+                }
+                PlanStep::Reword { commit_id, message } => {
+                    message_of_previous = Some(message.clone());
+                    current.push(RebaseStep::Pick {
+                        commit_id: *commit_id,
+                        new_message: Some(message.clone()),
+                    });
+                }
+                PlanStep::Squash { commit_id, message } => {
+                    let previous = message_of_previous
+                        .take()
+                        .context("Squash needs a commit to squash into")?;
+                    ensure_squashable_after_exec(&current, *commit_id)?;
+                    let message = match message {
+                        Some(message) => message.clone(),
+                        None => combine_messages(previous, message_of(repo, *commit_id)?),
+                    };
+                    message_of_previous = Some(message.clone());
+                    current.push(RebaseStep::SquashIntoPreceding {
+                        commit_id: *commit_id,
+                        new_message: Some(message),
+                    });
+                }
+                PlanStep::Fixup(commit_id) => {
+                    let previous = message_of_previous
+                        .clone()
+                        .context("Fixup needs a commit to squash into")?;
+                    ensure_squashable_after_exec(&current, *commit_id)?;
+                    current.push(RebaseStep::SquashIntoPreceding {
+                        commit_id: *commit_id,
+                        new_message: Some(previous),
+                    });
+                }
+                PlanStep::Drop(_) => {}
+                PlanStep::Exec(command) => {
+                    if current.is_empty() {
+                        bail!("Exec '{command}' must be preceded by a step that produces a commit");
+                    }
+                    chunks.push((std::mem::take(&mut current), Some(command.clone())));
+                }
+                PlanStep::Reference(reference) => {
+                    current.push(RebaseStep::Reference(reference.clone()));
+                }
+            }
+        }
+        if !current.is_empty() {
+            chunks.push((current, None));
+        }
+        Ok(chunks)
+    }
+
+    /// Perform the plan in `repo`, running commands in a temporary checkout of the commit they follow.
+    ///
+    /// Like [`Rebase::rebase()`], this only creates new commits and never updates references or the worktree.
+    /// Commits that can't be rebased cleanly are marked as conflicted and listed in the outcome.
+    pub fn execute(&self, repo: &gix::Repository) -> Result<PlanOutcome> {
+        let chunks = self.lower(repo)?;
+        if chunks.is_empty() {
+            bail!("The rebase plan doesn't produce any commit");
+        }
+
+        let mut base = self.base;
+        let mut base_substitute = self.base_substitute;
+        let mut output: Option<RebaseOutput> = None;
+        let mut executed_commands = Vec::new();
+        for (steps, command) in chunks {
+            let mut rebase = Rebase::new(repo, base, base_substitute.take())?;
+            rebase.rebase_noops(self.rebase_noops);
+            rebase.steps(steps)?;
+            let chunk_output = rebase.rebase()?;
+            base = Some(chunk_output.top_commit);
+
+            if let Some(command) = command {
+                let checkout = checkout_commit(repo, chunk_output.top_commit)?;
+                run_command(&command, chunk_output.top_commit, checkout.path())?;
+                executed_commands.push((command, chunk_output.top_commit));
+            }
+
+            output = Some(match output {
+                None => chunk_output,
+                Some(mut previous) => {
+                    previous.top_commit = chunk_output.top_commit;
+                    previous.references.extend(chunk_output.references);
+                    previous.commit_mapping.extend(chunk_output.commit_mapping);
+                    previous
+                }
+            });
+        }
+
+        let output = output.expect("at least one chunk");
+        let mut conflicted_commits = Vec::new();
+        for (_base, old, new) in &output.commit_mapping {
+            if conflicted_commits.iter().any(|(_, seen)| seen == new) {
+                continue;
+            }
+            if but_core::Commit::from_id(new.attach(repo))?.is_conflicted() {
+                conflicted_commits.push((*old, *new));
+            }
+        }
+        Ok(PlanOutcome {
+            output,
+            conflicted_commits,
+            executed_commands,
+        })
+    }
+}
+
+fn message_of(repo: &gix::Repository, commit_id: gix::ObjectId) -> Result<BString> {
+    Ok(repo.find_commit(commit_id)?.message_raw()?.to_owned())
+}
+
+/// Combine messages like `git rebase -i` does when squashing, but without the comments.
+fn combine_messages(first: BString, second: BString) -> BString {
+    let mut out = first.trim_end().to_owned();
+    out.extend_from_slice(b"\n\n");
+    out.extend_from_slice(second.trim_end());
+    out.push(b'\n');
+    out.into()
+}
+
+/// Fail if a squash or fixup of `commit_id` would be the first commit of the `current` chunk,
+/// which happens right after an exec step, as there would be nothing to squash into.
+fn ensure_squashable_after_exec(current: &[RebaseStep], commit_id: gix::ObjectId) -> Result<()> {
+    if !current
+        .iter()
+        .any(|step| !matches!(step, RebaseStep::Reference(_)))
+    {
+        bail!(
+            "Cannot squash {commit_id} right after an exec step, as the commit before it was already finalized"
+        );
+    }
+    Ok(())
+}
+
+/// Check out the tree of `commit_id` into a new temporary directory, the resolved one if it's conflicted,
+/// so commands can operate on its files.
+fn checkout_commit(repo: &gix::Repository, commit_id: gix::ObjectId) -> Result<tempfile::TempDir> {
+    let tree_id =
+        but_core::Commit::from_id(commit_id.attach(repo))?.tree_id_or_auto_resolution()?;
+    let mut index = repo.index_from_tree(&tree_id)?;
+    let mut opts =
+        repo.checkout_options(gix::worktree::stack::state::attributes::Source::IdMapping)?;
+    opts.destination_is_initially_empty = true;
+    let dir = tempfile::tempdir()?;
+    gix::worktree::state::checkout(
+        &mut index,
+        dir.path(),
+        repo.clone().objects.into_arc()?,
+        &gix::progress::Discard,
+        &gix::progress::Discard,
+        &gix::interrupt::IS_INTERRUPTED,
+        opts,
+    )?;
+    Ok(dir)
+}
+
+fn run_command(command: &str, commit_id: gix::ObjectId, cwd: &Path) -> Result<()> {
+    let mut cmd: std::process::Command = gix::command::prepare(command)
+        .command_may_be_shell_script()
+        .with_shell()
+        .env("GITBUTLER_REBASE_COMMIT", commit_id.to_string())
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .into();
+    cmd.current_dir(cwd);
+    let output = cmd
+        .output()
+        .with_context(|| format!("Could not execute '{command}'"))?;
+    if !output.status.success() {
+        bail!(
+            "Command '{command}' failed for commit {commit_id} with {status}: {stderr}",
+            status = output.status,
+            stderr = output.stderr.as_bstr()
+        );
+    }
+    Ok(())
+}
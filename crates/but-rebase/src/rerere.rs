@@ -0,0 +1,470 @@
+//! Like `git rerere`, remember how conflicts were resolved to resolve them the same way when they appear again,
+//! which is common as stacks are rebased over and over.
+//!
+//! Each region of a file between conflict markers is identified by a fingerprint of its two sides, so its
+//! resolution applies no matter where the conflict reappears. Resolutions are stored in `gitbutler/rr-cache`
+//! in the common `.git` directory, one file per fingerprint.
+use std::{
+    ops::Range,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Result, bail};
+use bstr::{BStr, BString, ByteSlice};
+use gix::{merge::tree::TreatAsUnresolved, prelude::ObjectIdExt as _};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest as _, Sha256};
+
+/// The directory in the common `.git` directory that holds recorded resolutions.
+const CACHE_DIR: &str = "gitbutler/rr-cache";
+
+/// How a conflict was resolved.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordedResolution {
+    /// The fingerprint of the conflict, computed from both of its sides.
+    pub fingerprint: String,
+    /// The path of the file the conflict was resolved in, relative to the worktree.
+    pub path: String,
+    /// The time at which the resolution was recorded, in seconds since the Unix epoch.
+    pub recorded_at: u64,
+    /// The lines of one side of the conflict.
+    pub ours: String,
+    /// The lines of the other side of the conflict.
+    pub theirs: String,
+    /// The lines that replace the conflict and its markers.
+    pub resolution: String,
+}
+
+/// A recorded resolution as it's stored in its file, which is named after the fingerprint.
+#[derive(Serialize, Deserialize)]
+struct Record {
+    path: String,
+    recorded_at: u64,
+    ours: String,
+    theirs: String,
+    resolution: String,
+}
+
+/// Return all resolutions recorded in `repo`, the most recent one first.
+pub fn list(repo: &gix::Repository) -> Result<Vec<RecordedResolution>> {
+    let entries = match std::fs::read_dir(cache_dir(repo)) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err.into()),
+    };
+    let mut out = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        let Some(fingerprint) = entry
+            .file_name()
+            .to_str()
+            .filter(|name| is_fingerprint(name))
+            .map(ToOwned::to_owned)
+        else {
+            continue;
+        };
+        let record: Record = toml::from_str(&std::fs::read_to_string(entry.path())?)?;
+        out.push(RecordedResolution {
+            fingerprint,
+            path: record.path,
+            recorded_at: record.recorded_at,
+            ours: record.ours,
+            theirs: record.theirs,
+            resolution: record.resolution,
+        });
+    }
+    out.sort_by(|a, b| {
+        b.recorded_at
+            .cmp(&a.recorded_at)
+            .then_with(|| a.fingerprint.cmp(&b.fingerprint))
+    });
+    Ok(out)
+}
+
+/// Forget the resolution of the conflict with `fingerprint` so it's no longer applied,
+/// and return `true` if it was recorded.
+pub fn forget(repo: &gix::Repository, fingerprint: &str) -> Result<bool> {
+    if !is_fingerprint(fingerprint) {
+        bail!("Invalid conflict fingerprint '{fingerprint}'");
+    }
+    match std::fs::remove_file(cache_dir(repo).join(fingerprint)) {
+        Ok(()) => Ok(true),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(false),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Record how the conflicts of merging `ours` and `theirs` with `base` were resolved in `resolved_tree`,
+/// and return the amount of recorded resolutions.
+///
+/// Conflicts whose resolution can't be told apart from other changes made in the same file are skipped.
+pub fn record_merge_resolutions(
+    repo: &gix::Repository,
+    base: gix::ObjectId,
+    ours: gix::ObjectId,
+    theirs: gix::ObjectId,
+    resolved_tree: gix::ObjectId,
+) -> Result<usize> {
+    let Some((_, conflicted_files)) = conflicted_files(repo, base, ours, theirs)? else {
+        return Ok(0);
+    };
+    let resolved_tree = resolved_tree.attach(repo).object()?.into_tree();
+    let mut recorded = 0;
+    for file in conflicted_files {
+        let Some(entry) = resolved_tree.lookup_entry_by_path(gix::path::from_bstr(&file.path))?
+        else {
+            continue;
+        };
+        if !entry.mode().is_blob() {
+            continue;
+        }
+        let resolved = entry.object()?.into_blob().take_data();
+        recorded += record(repo, file.path.as_bstr(), &file.data, &resolved)?;
+    }
+    Ok(recorded)
+}
+
+/// Record how each conflict in `conflicted`, the content of the file at `path` with conflict markers, was resolved
+/// in `resolved`, and return the amount of recorded resolutions.
+pub fn record(
+    repo: &gix::Repository,
+    path: &BStr,
+    conflicted: &[u8],
+    resolved: &[u8],
+) -> Result<usize> {
+    let conflicted_lines: Vec<&[u8]> = conflicted.lines_with_terminator().collect();
+    let resolved_lines: Vec<&[u8]> = resolved.lines_with_terminator().collect();
+    let mut interner =
+        gix::diff::blob::intern::Interner::new(conflicted_lines.len() + resolved_lines.len());
+    let conflicted_tokens: Vec<_> = conflicted_lines
+        .iter()
+        .map(|line| interner.intern(*line))
+        .collect();
+    let resolved_tokens: Vec<_> = resolved_lines
+        .iter()
+        .map(|line| interner.intern(*line))
+        .collect();
+    let mut changes = Vec::<(Range<u32>, Range<u32>)>::new();
+    gix::diff::blob::diff_with_tokens(
+        gix::diff::blob::Algorithm::Histogram,
+        &conflicted_tokens,
+        &resolved_tokens,
+        interner.num_tokens(),
+        |before: Range<u32>, after: Range<u32>| changes.push((before, after)),
+    );
+
+    let recorded_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_secs())
+        .unwrap_or_default();
+    let mut recorded = 0;
+    for region in conflict_regions(&conflicted_lines) {
+        let Some(resolution) = resolved_lines_of(&region.lines, &changes) else {
+            tracing::debug!(%path, ?region.lines, "conflict resolution is mixed with other changes");
+            continue;
+        };
+        let resolution =
+            resolved_lines[resolution.start as usize..resolution.end as usize].concat();
+        let (Ok(ours), Ok(theirs), Ok(resolution)) = (
+            String::from_utf8(region.ours.clone()),
+            String::from_utf8(region.theirs.clone()),
+            String::from_utf8(resolution),
+        ) else {
+            continue;
+        };
+        let record = Record {
+            path: path.to_str_lossy().into_owned(),
+            recorded_at,
+            ours,
+            theirs,
+            resolution,
+        };
+        but_fs::create_dirs_then_write(
+            cache_dir(repo).join(region.fingerprint()),
+            toml::to_string(&record)?,
+        )?;
+        recorded += 1;
+    }
+    Ok(recorded)
+}
+
+/// Replace each conflict in `conflicted`, the content of a file with conflict markers, with its recorded resolution,
+/// or return `None` if there are no conflicts or not all of them have a recorded resolution.
+pub fn resolve(repo: &gix::Repository, conflicted: &[u8]) -> Result<Option<Vec<u8>>> {
+    let lines: Vec<&[u8]> = conflicted.lines_with_terminator().collect();
+    let regions = conflict_regions(&lines);
+    if regions.is_empty() {
+        return Ok(None);
+    }
+    let mut out = Vec::with_capacity(conflicted.len());
+    let mut next_line = 0;
+    for region in regions {
+        let record = match std::fs::read_to_string(cache_dir(repo).join(region.fingerprint())) {
+            Ok(record) => record,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+        let record: Record = toml::from_str(&record)?;
+        out.extend(lines[next_line..region.lines.start as usize].concat());
+        out.extend_from_slice(record.resolution.as_bytes());
+        next_line = region.lines.end as usize;
+    }
+    out.extend(lines[next_line..].concat());
+    Ok(Some(out))
+}
+
+/// The directory with the recorded resolutions of `repo`.
+fn cache_dir(repo: &gix::Repository) -> PathBuf {
+    repo.common_dir().join(CACHE_DIR)
+}
+
+fn is_fingerprint(name: &str) -> bool {
+    name.len() == 64 && name.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// A file that conflicts when merging.
+pub(crate) struct ConflictedFile {
+    pub(crate) path: BString,
+    pub(crate) mode: gix::object::tree::EntryMode,
+    /// The merged content, with conflict markers.
+    pub(crate) data: Vec<u8>,
+    /// The blobs of the file in the merge-base and on each side, or `None` if it doesn't exist there.
+    pub(crate) base_id: Option<gix::ObjectId>,
+    pub(crate) ours_id: Option<gix::ObjectId>,
+    pub(crate) theirs_id: Option<gix::ObjectId>,
+}
+
+/// Merge `ours` and `theirs` with `base` without favoring a side, and return the merged tree along with the files
+/// that contain conflict markers, or `None` if there are no conflicts or conflicts that aren't about the content of files.
+pub(crate) fn conflicted_files(
+    repo: &gix::Repository,
+    base: gix::ObjectId,
+    ours: gix::ObjectId,
+    theirs: gix::ObjectId,
+) -> Result<Option<(gix::ObjectId, Vec<ConflictedFile>)>> {
+    use but_core::RepositoryExt as _;
+    let mut merge = repo.merge_trees(
+        base,
+        ours,
+        theirs,
+        repo.default_merge_labels(),
+        repo.tree_merge_options()?,
+    )?;
+    // Match what cherry-picks consider to be a conflict.
+    let conflict_kind = TreatAsUnresolved::forced_resolution();
+    if !merge.has_unresolved_conflicts(conflict_kind) {
+        return Ok(None);
+    }
+    let merged_tree = merge.tree.write()?.detach();
+    let tree = merged_tree.attach(repo).object()?.into_tree();
+    let blob_id = |tree_id: gix::ObjectId, path: &BStr| -> Result<Option<gix::ObjectId>> {
+        Ok(tree_id
+            .attach(repo)
+            .object()?
+            .into_tree()
+            .lookup_entry_by_path(gix::path::from_bstr(path))?
+            .filter(|entry| entry.mode().is_blob())
+            .map(|entry| entry.object_id()))
+    };
+    let mut files = Vec::<ConflictedFile>::new();
+    for conflict in merge
+        .conflicts
+        .iter()
+        .filter(|conflict| conflict.is_unresolved(conflict_kind))
+    {
+        let (our_change, their_change) = conflict.changes_in_resolution();
+        if our_change.location() != their_change.location() {
+            return Ok(None);
+        }
+        let path = our_change.location();
+        if files.iter().any(|file| file.path == path) {
+            continue;
+        }
+        let Some(entry) = tree.lookup_entry_by_path(gix::path::from_bstr(path))? else {
+            return Ok(None);
+        };
+        if !entry.mode().is_blob() {
+            return Ok(None);
+        }
+        files.push(ConflictedFile {
+            path: path.to_owned(),
+            mode: entry.mode(),
+            data: entry.object()?.into_blob().take_data(),
+            base_id: blob_id(base, path)?,
+            ours_id: blob_id(ours, path)?,
+            theirs_id: blob_id(theirs, path)?,
+        });
+    }
+    Ok(Some((merged_tree, files)))
+}
+
+/// A conflict between conflict markers.
+pub(crate) struct ConflictRegion {
+    /// The lines of the conflict, including its markers.
+    pub(crate) lines: Range<u32>,
+    pub(crate) ours: Vec<u8>,
+    pub(crate) theirs: Vec<u8>,
+}
+
+impl ConflictRegion {
+    /// Identify the conflict by its sides, in any order, as a conflict may appear with swapped sides.
+    fn fingerprint(&self) -> String {
+        let (first, second) = if self.ours <= self.theirs {
+            (&self.ours, &self.theirs)
+        } else {
+            (&self.theirs, &self.ours)
+        };
+        let mut hasher = Sha256::new();
+        hasher.update(first);
+        hasher.update([0]);
+        hasher.update(second);
+        hasher.update([0]);
+        hasher
+            .finalize()
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect()
+    }
+}
+
+/// Find the conflicts in `lines`, in order. The base section of `diff3` style conflicts is ignored.
+pub(crate) fn conflict_regions(lines: &[&[u8]]) -> Vec<ConflictRegion> {
+    fn is_marker(line: &[u8], marker: &[u8]) -> bool {
+        line.strip_prefix(marker)
+            .is_some_and(|rest| matches!(rest.first(), None | Some(b' ' | b'\n' | b'\r')))
+    }
+    enum State {
+        Outside,
+        Ours(usize),
+        Base(usize),
+        Theirs(usize),
+    }
+    let mut out = Vec::new();
+    let mut state = State::Outside;
+    let (mut ours, mut theirs) = (Vec::new(), Vec::new());
+    for (idx, line) in lines.iter().enumerate() {
+        state = match state {
+            State::Outside if is_marker(line, b"<<<<<<<") => {
+                ours.clear();
+                theirs.clear();
+                State::Ours(idx)
+            }
+            State::Outside => State::Outside,
+            State::Ours(start) if is_marker(line, b"|||||||") => State::Base(start),
+            State::Ours(start) | State::Base(start) if is_marker(line, b"=======") => {
+                State::Theirs(start)
+            }
+            State::Ours(start) => {
+                ours.extend_from_slice(line);
+                State::Ours(start)
+            }
+            State::Base(start) => State::Base(start),
+            State::Theirs(start) if is_marker(line, b">>>>>>>") => {
+                out.push(ConflictRegion {
+                    lines: start as u32..idx as u32 + 1,
+                    ours: std::mem::take(&mut ours),
+                    theirs: std::mem::take(&mut theirs),
+                });
+                State::Outside
+            }
+            State::Theirs(start) => {
+                theirs.extend_from_slice(line);
+                State::Theirs(start)
+            }
+        };
+    }
+    out
+}
+
+/// Return the lines that replaced `region` according to `changes`, or `None` if a change also affected lines around it.
+fn resolved_lines_of(
+    region: &Range<u32>,
+    changes: &[(Range<u32>, Range<u32>)],
+) -> Option<Range<u32>> {
+    let (mut offset_before, mut offset_inside) = (0i64, 0i64);
+    for (before, after) in changes {
+        let delta = i64::from(after.end - after.start) - i64::from(before.end - before.start);
+        let is_inside = if before.is_empty() {
+            before.start >= region.start && before.start < region.end
+        } else {
+            before.start < region.end && before.end > region.start
+        };
+        if is_inside {
+            if before.start < region.start || before.end > region.end {
+                return None;
+            }
+            offset_inside += delta;
+        } else if before.start < region.start {
+            offset_before += delta;
+        }
+    }
+    let start = i64::from(region.start) + offset_before;
+    let end = i64::from(region.end) + offset_before + offset_inside;
+    Some(start as u32..end as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn regions_are_found_with_and_without_base() {
+        let conflicted = b"a\n<<<<<<< ours\nb\n||||||| base\nB\n=======\nc\n>>>>>>> theirs\nd\n<<<<<<< ours\ne\n=======\n>>>>>>> theirs\n";
+        let lines: Vec<&[u8]> = conflicted.lines_with_terminator().collect();
+        let regions = conflict_regions(&lines);
+        assert_eq!(regions.len(), 2);
+        assert_eq!(regions[0].lines, 1..7);
+        assert_eq!(regions[0].ours, b"b\n");
+        assert_eq!(regions[0].theirs, b"c\n");
+        assert_eq!(regions[1].lines, 8..12);
+        assert_eq!(regions[1].ours, b"e\n");
+        assert_eq!(regions[1].theirs, b"");
+    }
+
+    #[test]
+    fn fingerprints_ignore_the_order_of_sides() {
+        let region = |ours: &[u8], theirs: &[u8]| ConflictRegion {
+            lines: 0..1,
+            ours: ours.to_vec(),
+            theirs: theirs.to_vec(),
+        };
+        assert_eq!(
+            region(b"a\n", b"b\n").fingerprint(),
+            region(b"b\n", b"a\n").fingerprint()
+        );
+        assert_ne!(
+            region(b"a\n", b"b\n").fingerprint(),
+            region(b"a\n", b"c\n").fingerprint()
+        );
+    }
+
+    #[test]
+    fn recorded_resolutions_apply_to_the_same_conflict_elsewhere() -> Result<()> {
+        let tmp = tempfile::tempdir()?;
+        let repo = gix::init(tmp.path())?;
+        let conflicted = b"a\n<<<<<<< ours\nb\n=======\nc\n>>>>>>> theirs\nd\n";
+        assert_eq!(record(&repo, "file".into(), conflicted, b"a\nbc\nd\n")?, 1);
+
+        let swapped = b"x\n<<<<<<< ours\nc\n=======\nb\n>>>>>>> theirs\n";
+        assert_eq!(resolve(&repo, swapped)?.as_deref(), Some(&b"x\nbc\n"[..]));
+
+        let recorded = list(&repo)?;
+        assert_eq!(recorded.len(), 1);
+        assert_eq!(recorded[0].path, "file");
+        assert_eq!(recorded[0].resolution, "bc\n");
+        assert!(forget(&repo, &recorded[0].fingerprint)?);
+        assert_eq!(resolve(&repo, swapped)?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn resolutions_are_located_around_other_changes() {
+        // Line 0 was changed, the conflict in lines 2..5 became one line, and line 6 was removed.
+        let changes = [(0..1, 0..2), (2..5, 3..4), (6..7, 5..5)];
+        assert_eq!(resolved_lines_of(&(2..5), &changes), Some(3..4));
+        // A change that also affects the line before the conflict can't be attributed to it.
+        assert_eq!(resolved_lines_of(&(2..5), &[(1..5, 1..2)]), None);
+    }
+}
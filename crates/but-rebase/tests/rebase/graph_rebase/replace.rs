@@ -44,9 +44,28 @@ fn reword_a_commit() -> Result<()> {
     editor.replace(a_selector, Step::new_pick(a_new))?;
 
     let outcome = editor.rebase()?;
-    outcome.materialize()?;
+    let outcome = outcome.materialize()?;
 
     assert_eq!(head_tree, repo.head_tree()?.id);
+    let rewritten: Vec<_> = outcome
+        .rewritten_commits()
+        .iter()
+        .map(|(old, new)| {
+            (
+                old.to_hex_with_len(7).to_string(),
+                new.to_hex_with_len(7).to_string(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        rewritten,
+        [
+            ("add59d2".to_owned(), "6de6b92".to_owned()),
+            ("2fc288c".to_owned(), "3d1e2c5".to_owned()),
+            ("e8ee978".to_owned(), "b475cbc".to_owned()),
+        ],
+        "the reworded commit and all commits on top of it were rewritten, in order"
+    );
 
     insta::assert_snapshot!(visualize_commit_graph_all(&repo)?, @r"
     * b475cbc (HEAD -> with-inner-merge) on top of inner merge
@@ -10,6 +10,7 @@ use crate::utils::{
 
 mod error_handling;
 mod graph_rebase;
+mod plan;
 
 mod commit {
     mod store_author_globally_if_unset {
@@ -69,6 +70,93 @@ mod commit {
     }
 }
 
+mod merge {
+    use bstr::ByteSlice;
+    use but_rebase::{Rebase, RebaseStep};
+
+    use crate::utils::fixture_writable_with_signing;
+
+    /// Write a merge of `a` and `c` and rebase it onto `b` along with `c`, with Gerrit mode enabled
+    /// and signing enabled if `sign_commits` is set.
+    /// Return the rewritten merge commit.
+    fn remerge(
+        sign_merge: bool,
+        sign_commits: bool,
+    ) -> anyhow::Result<(gix::Repository, gix::ObjectId, tempfile::TempDir)> {
+        let (mut repo, tmp, _meta) = fixture_writable_with_signing("workspace-signed")?;
+        repo.config_snapshot_mut()
+            .set_raw_value(&"gitbutler.gerritMode", "true")?;
+        let a = repo.rev_parse_single("a")?.detach();
+        let c = repo.rev_parse_single("c")?.detach();
+        let mut merge = gix::objs::Commit::try_from(repo.find_commit(c)?.decode()?)?;
+        merge.parents = [a, c].into_iter().collect();
+        merge.message = "Merge c into a\n".into();
+        merge.extra_headers.clear();
+        if sign_merge {
+            but_core::signing::sign_commit(&repo, &mut merge)?;
+        }
+        let merge = repo.write_object(merge)?.detach();
+        repo.config_snapshot_mut().set_raw_value(
+            &"gitbutler.signCommits",
+            if sign_commits { "true" } else { "false" },
+        )?;
+
+        let out = Rebase::new(&repo, repo.rev_parse_single("b")?.detach(), None)?
+            .steps([
+                RebaseStep::Pick {
+                    commit_id: c,
+                    new_message: Some("c - reworded".into()),
+                },
+                RebaseStep::Pick {
+                    commit_id: merge,
+                    new_message: None,
+                },
+            ])?
+            .rebase()?;
+        let merge = repo.find_commit(out.top_commit)?;
+        assert_eq!(merge.parent_ids().count(), 2, "the merge was repeated");
+        assert!(
+            !merge.message_raw()?.contains_str("Change-Id"),
+            "merges don't get Change-Ids, even in Gerrit mode"
+        );
+        Ok((repo, out.top_commit, tmp))
+    }
+
+    #[test]
+    fn merges_are_signed_if_signing_is_enabled() -> anyhow::Result<()> {
+        let (repo, merge, _tmp) = remerge(false, true)?;
+        let merge = repo.find_commit(merge)?;
+        assert!(
+            merge.decode()?.extra_headers().pgp_signature().is_some(),
+            "workspace merges are signed so they pass signature enforcement"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn signed_merges_are_signed_again() -> anyhow::Result<()> {
+        let (repo, merge, _tmp) = remerge(true, true)?;
+        let merge = repo.find_commit(merge)?;
+        assert!(
+            merge.decode()?.extra_headers().pgp_signature().is_some(),
+            "the new merge commit receives a new signature"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn merges_are_unsigned_if_signing_is_disabled() -> anyhow::Result<()> {
+        let (repo, merge, _tmp) = remerge(true, false)?;
+        let merge = repo.find_commit(merge)?;
+        assert_eq!(
+            merge.decode()?.extra_headers().pgp_signature(),
+            None,
+            "the old signature doesn't match the new merge and is removed"
+        );
+        Ok(())
+    }
+}
+
 #[test]
 fn single_stack_journey() -> Result<()> {
     let (repo, commits, _tmp) = four_commits_writable()?;
@@ -0,0 +1,139 @@
+use bstr::ByteSlice;
+use but_rebase::plan::RebasePlan;
+use gix::prelude::ObjectIdExt;
+
+use crate::utils::{assure_nonconflicting, four_commits_writable};
+
+fn messages(
+    repo: &gix::Repository,
+    tip: gix::ObjectId,
+    base: gix::ObjectId,
+) -> anyhow::Result<Vec<String>> {
+    let mut out = Vec::new();
+    for info in tip.attach(repo).ancestors().all()? {
+        let info = info?;
+        if info.id == base {
+            break;
+        }
+        out.push(
+            repo.find_commit(info.id)?
+                .message_raw()?
+                .to_str_lossy()
+                .into_owned(),
+        );
+    }
+    Ok(out)
+}
+
+#[test]
+fn squash_combines_messages_and_fixup_keeps_previous() -> anyhow::Result<()> {
+    let (repo, commits, _tmp) = four_commits_writable()?;
+    let outcome = RebasePlan::new(commits.base, None)
+        .pick(commits.a)
+        .squash(commits.b)
+        .fixup(commits.c)
+        .execute(&repo)?;
+    assure_nonconflicting(&repo, &outcome.output)?;
+    assert!(!outcome.has_conflicts());
+
+    let messages = messages(&repo, outcome.output.top_commit, commits.base)?;
+    assert_eq!(messages, ["a\n\nb\n"], "only one commit remains");
+    Ok(())
+}
+
+#[test]
+fn reword_and_drop() -> anyhow::Result<()> {
+    let (repo, commits, _tmp) = four_commits_writable()?;
+    let outcome = RebasePlan::new(commits.base, None)
+        .reword(commits.a, "reworded a")
+        .drop_commit(commits.b)
+        .pick(commits.c)
+        .execute(&repo)?;
+    assure_nonconflicting(&repo, &outcome.output)?;
+
+    let messages = messages(&repo, outcome.output.top_commit, commits.base)?;
+    assert_eq!(messages, ["c\n", "reworded a"]);
+    Ok(())
+}
+
+#[test]
+fn exec_runs_after_each_chunk() -> anyhow::Result<()> {
+    let (repo, commits, _tmp) = four_commits_writable()?;
+    let outcome = RebasePlan::new(commits.base, None)
+        .pick(commits.a)
+        .exec("true")
+        .pick(commits.b)
+        .pick(commits.c)
+        .execute(&repo)?;
+    assure_nonconflicting(&repo, &outcome.output)?;
+    assert_eq!(outcome.executed_commands.len(), 1);
+    assert_eq!(outcome.output.commit_mapping.len(), 3);
+
+    let messages = messages(&repo, outcome.output.top_commit, commits.base)?;
+    assert_eq!(messages, ["c\n", "b\n", "a\n"]);
+    Ok(())
+}
+
+#[test]
+fn exec_runs_in_a_checkout_of_the_rewritten_commit() -> anyhow::Result<()> {
+    let (repo, commits, _tmp) = four_commits_writable()?;
+    let outcome = RebasePlan::new(commits.base, None)
+        .pick(commits.a)
+        .exec("test -f base && test -f a && test ! -f b")
+        .pick(commits.b)
+        .exec("test -f a && test -f b && test ! -f c")
+        .execute(&repo)?;
+    assert_eq!(
+        outcome.executed_commands.len(),
+        2,
+        "the worktree has all files, so the commands would fail there"
+    );
+    Ok(())
+}
+
+#[test]
+fn squash_right_after_exec_fails() -> anyhow::Result<()> {
+    let (repo, commits, _tmp) = four_commits_writable()?;
+    for fixup in [false, true] {
+        let mut plan = RebasePlan::new(commits.base, None);
+        plan.pick(commits.a).exec("true");
+        if fixup {
+            plan.fixup(commits.b);
+        } else {
+            plan.squash(commits.b);
+        }
+        let err = plan.execute(&repo).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "Cannot squash {} right after an exec step, as the commit before it was already finalized",
+                commits.b
+            )
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn failing_exec_aborts() -> anyhow::Result<()> {
+    let (repo, commits, _tmp) = four_commits_writable()?;
+    let err = RebasePlan::new(commits.base, None)
+        .pick(commits.a)
+        .exec("false")
+        .pick(commits.b)
+        .execute(&repo)
+        .unwrap_err();
+    assert!(err.to_string().starts_with("Command 'false' failed"));
+    Ok(())
+}
+
+#[test]
+fn squash_without_preceding_commit_fails() -> anyhow::Result<()> {
+    let (repo, commits, _tmp) = four_commits_writable()?;
+    let err = RebasePlan::new(commits.base, None)
+        .squash(commits.a)
+        .execute(&repo)
+        .unwrap_err();
+    assert_eq!(err.to_string(), "Squash needs a commit to squash into");
+    Ok(())
+}
@@ -1,7 +1,10 @@
 use std::str::FromStr;
 
 use but_core::{ChangeId, DiffSpec, ref_metadata::StackId};
-use but_ctx::Context;
+use but_ctx::{
+    Context,
+    access::{OperationCategory, Priority},
+};
 use but_hunk_assignment::{HunkAssignment, assign, assignments_to_requests};
 use but_hunk_dependency::ui::HunkDependencies;
 use but_meta::VirtualBranchesTomlMetadata;
@@ -81,7 +84,8 @@ fn handle_amend(
     change_id: &ChangeId,
 ) -> anyhow::Result<()> {
     let changes: Vec<DiffSpec> = assignments.into_iter().map(|a| a.into()).collect();
-    let mut guard = ctx.exclusive_worktree_access();
+    let mut guard =
+        ctx.queued_exclusive_worktree_access(OperationCategory::Commit, Priority::Normal)?;
     let repo = ctx.clone_repo_for_merging()?;
 
     let meta = VirtualBranchesTomlMetadata::from_path(
@@ -176,7 +180,8 @@ fn create_stack(ctx: &Context) -> anyhow::Result<StackId> {
         order: None,
     };
 
-    let mut guard = ctx.exclusive_worktree_access();
+    let mut guard =
+        ctx.queued_exclusive_worktree_access(OperationCategory::Other, Priority::Normal)?;
     let perm = guard.write_permission();
 
     let stack = gitbutler_branch_actions::create_virtual_branch(ctx, &create_req, perm)?;
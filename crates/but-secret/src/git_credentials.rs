@@ -0,0 +1,134 @@
+//! Obtain credentials for HTTPS URLs with `git credential`, so the credential managers configured for Git,
+//! like `osxkeychain`, `wincred`, `libsecret` or `gh auth git-credential`, can supply them.
+//!
+//! Credentials that worked should be [approved](approve()) so helpers can store them,
+//! and those that were refused should be [rejected](reject()) so helpers can forget them.
+use std::{
+    io::Write as _,
+    path::Path,
+    process::{Command, Stdio},
+};
+
+use anyhow::{Context as _, Result, bail};
+
+use crate::Sensitive;
+
+/// A username and password as provided by `git credential fill`.
+#[derive(Debug, Clone)]
+pub struct GitCredential {
+    /// The URL the credential is for, like `https://github.com/owner/repo.git`.
+    pub url: String,
+    /// The name of the user to authenticate as.
+    pub username: String,
+    /// The password or token.
+    pub password: Sensitive<String>,
+}
+
+/// Ask the credential helpers configured for Git for the credential to use with `url`, running `git` in `cwd`
+/// to pick up repository-local configuration if given.
+///
+/// Return `None` if no helper had a credential. Helpers are asked to not prompt the user.
+pub fn fill(url: &str, cwd: Option<&Path>) -> Result<Option<GitCredential>> {
+    if url.contains(['\n', '\0']) {
+        bail!("The credential url must not contain newlines or NUL bytes");
+    }
+    let output = run("fill", &format!("url={url}\n"), cwd)?;
+    let Some(output) = output else {
+        return Ok(None);
+    };
+    let mut username = None;
+    let mut password = None;
+    for line in output.lines() {
+        match line.split_once('=') {
+            Some(("username", value)) => username = Some(value.to_owned()),
+            Some(("password", value)) => password = Some(value.to_owned()),
+            _ => {}
+        }
+    }
+    Ok(password.map(|password| GitCredential {
+        url: url.to_owned(),
+        username: username.unwrap_or_default(),
+        password: Sensitive(password),
+    }))
+}
+
+/// Tell the credential helpers that `credential` was accepted, so they may store it.
+pub fn approve(credential: &GitCredential, cwd: Option<&Path>) -> Result<()> {
+    run("approve", &credential.to_input()?, cwd).map(|_| ())
+}
+
+/// Tell the credential helpers that `credential` was refused, so they may forget it.
+pub fn reject(credential: &GitCredential, cwd: Option<&Path>) -> Result<()> {
+    run("reject", &credential.to_input()?, cwd).map(|_| ())
+}
+
+impl PartialEq for GitCredential {
+    fn eq(&self, other: &Self) -> bool {
+        self.url == other.url
+            && self.username == other.username
+            && self.password.0 == other.password.0
+    }
+}
+
+impl Eq for GitCredential {}
+
+impl GitCredential {
+    /// Fail if a value contains a newline, as it would end the value and could inject attributes
+    /// into the input of `git credential`.
+    fn to_input(&self) -> Result<String> {
+        for (field, value) in [
+            ("url", self.url.as_str()),
+            ("username", self.username.as_str()),
+            ("password", self.password.0.as_str()),
+        ] {
+            if value.contains(['\n', '\0']) {
+                bail!("The credential {field} must not contain newlines or NUL bytes");
+            }
+        }
+        Ok(format!(
+            "url={}\nusername={}\npassword={}\n",
+            self.url, self.username, self.password.0
+        ))
+    }
+}
+
+/// Run `git credential <action>` with `input`, returning its output, or `None` if it failed
+/// as it does when there is no credential to fill.
+fn run(action: &str, input: &str, cwd: Option<&Path>) -> Result<Option<String>> {
+    let mut cmd = Command::new(gix::path::env::exe_invocation());
+    cmd.args(["credential", action])
+        // Never block on a prompt, there may be no terminal and nobody to answer it.
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .env("GCM_INTERACTIVE", "never")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    if let Some(cwd) = cwd {
+        cmd.current_dir(cwd);
+    }
+    let mut child = cmd
+        .spawn()
+        .with_context(|| format!("Failed to run 'git credential {action}'"))?;
+    child
+        .stdin
+        .take()
+        .expect("configured")
+        .write_all(format!("{input}\n").as_bytes())?;
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        if action != "fill" {
+            bail!(
+                "'git credential {action}' failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        tracing::debug!(
+            stderr = %String::from_utf8_lossy(&output.stderr).trim(),
+            "git credential fill did not provide a credential"
+        );
+        return Ok(None);
+    }
+    Ok(Some(
+        String::from_utf8(output.stdout).context("git credential output wasn't UTF-8")?,
+    ))
+}
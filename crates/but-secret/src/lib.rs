@@ -1,3 +1,4 @@
+pub mod git_credentials;
 pub mod secret;
 pub mod sensitive;
 
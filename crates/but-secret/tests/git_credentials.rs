@@ -0,0 +1,127 @@
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use but_secret::{
+    Sensitive,
+    git_credentials::{self, GitCredential},
+};
+
+const URL: &str = "https://example.com/repo.git";
+
+#[test]
+fn fill_returns_the_credential_of_the_helper() -> anyhow::Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let repo = repo_with_recording_helper(tmp.path(), true);
+
+    let credential = git_credentials::fill(URL, Some(&repo))?;
+    assert_eq!(credential, Some(credential_for(URL, "user", "secret")));
+
+    let log = helper_log(&repo);
+    assert!(log.contains("action=get\n"), "{log}");
+    assert!(log.contains("host=example.com\n"), "{log}");
+    Ok(())
+}
+
+#[test]
+fn fill_without_credential_is_none() -> anyhow::Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let repo = repo_with_recording_helper(tmp.path(), false);
+
+    assert_eq!(
+        git_credentials::fill(URL, Some(&repo))?,
+        None,
+        "there is nobody to prompt"
+    );
+    Ok(())
+}
+
+#[test]
+fn approve_and_reject_are_passed_to_the_helper() -> anyhow::Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let repo = repo_with_recording_helper(tmp.path(), false);
+    let credential = credential_for(URL, "user", "secret");
+
+    git_credentials::approve(&credential, Some(&repo))?;
+    let log = helper_log(&repo);
+    assert!(
+        log.starts_with("action=store\n")
+            && log.contains("username=user\n")
+            && log.contains("password=secret\n"),
+        "{log}"
+    );
+
+    std::fs::remove_file(repo.join("helper.log"))?;
+    git_credentials::reject(&credential, Some(&repo))?;
+    let log = helper_log(&repo);
+    assert!(
+        log.starts_with("action=erase\n") && log.contains("username=user\n"),
+        "{log}"
+    );
+    Ok(())
+}
+
+#[test]
+fn newlines_in_values_are_refused() -> anyhow::Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let repo = repo_with_recording_helper(tmp.path(), true);
+
+    for credential in [
+        credential_for(URL, "user\nhost=attacker.example", "secret"),
+        credential_for(URL, "user", "secret\nurl=https://attacker.example"),
+    ] {
+        assert!(git_credentials::approve(&credential, Some(&repo)).is_err());
+        assert!(git_credentials::reject(&credential, Some(&repo)).is_err());
+    }
+    assert!(
+        git_credentials::fill("https://example.com\nhost=attacker.example", Some(&repo)).is_err()
+    );
+    assert!(
+        !repo.join("helper.log").exists(),
+        "the helper is never called with such input"
+    );
+    Ok(())
+}
+
+fn credential_for(url: &str, username: &str, password: &str) -> GitCredential {
+    GitCredential {
+        url: url.into(),
+        username: username.into(),
+        password: Sensitive(password.into()),
+    }
+}
+
+/// Create a repository in `dir` whose only credential helper appends the action and its input to `helper.log`
+/// in the repository, and provides a credential for `user` with password `secret` if `provide` is `true`.
+fn repo_with_recording_helper(dir: &Path, provide: bool) -> PathBuf {
+    let repo = dir.join("repo");
+    git(dir, &["init", "--quiet", "repo"]);
+    let log = repo.join("helper.log");
+    let provide = if provide {
+        "if [ \"$1\" = get ]; then printf 'username=user\\npassword=secret\\n'; fi;"
+    } else {
+        ""
+    };
+    let helper = format!(
+        "!f() {{ {{ echo \"action=$1\"; cat; }} >> '{log}'; {provide} }}; f",
+        log = log.display()
+    );
+    // The empty value clears the helpers configured globally.
+    git(&repo, &["config", "--add", "credential.helper", ""]);
+    git(&repo, &["config", "--add", "credential.helper", &helper]);
+    repo
+}
+
+fn helper_log(repo: &Path) -> String {
+    std::fs::read_to_string(repo.join("helper.log")).expect("the helper was called")
+}
+
+fn git(cwd: &Path, args: &[&str]) {
+    let status = Command::new("git")
+        .args(args)
+        .current_dir(cwd)
+        .status()
+        .unwrap();
+    assert!(status.success(), "git {args:?} failed");
+}
@@ -1,3 +1,5 @@
+mod git_credentials;
+
 use but_secret::Sensitive;
 
 #[test]
@@ -14,7 +14,7 @@ use axum::{
     response::IntoResponse,
     routing::{any, post},
 };
-use but_api::{commit, diff, github, json, legacy};
+use but_api::{bitbucket, commit, diff, github, gitlab, json, legacy, operations};
 use but_claude::{Broadcaster, Claude};
 use but_settings::AppSettingsWithDiskSync;
 use futures_util::{SinkExt, StreamExt as _};
@@ -193,6 +193,7 @@ pub async fn run() {
             "/tree_change_diffs",
             post(json_response(legacy::diff::tree_change_diffs_cmd)),
         )
+        .route("/blame", post(json_response(legacy::diff::blame_cmd)))
         .route(
             "/commit_details_with_line_stats",
             post(json_response(diff::commit_details_with_line_stats_cmd)),
@@ -205,6 +206,14 @@ pub async fn run() {
             "/changes_in_worktree",
             post(json_response(legacy::diff::changes_in_worktree_cmd)),
         )
+        .route(
+            "/explain_ignore",
+            post(json_response(legacy::diff::explain_ignore_cmd)),
+        )
+        .route(
+            "/set_skip_worktree",
+            post(json_response(legacy::diff::set_skip_worktree_cmd)),
+        )
         .route(
             "/assign_hunk",
             post(json_response(legacy::diff::assign_hunk_cmd)),
@@ -224,6 +233,19 @@ pub async fn run() {
         .route(
             "/head_info",
             post(json_response(legacy::workspace::head_info_cmd)),
+        )
+        .route(
+            "/workspace_graph",
+            post(json_response(legacy::workspace::workspace_graph_cmd)),
+        )
+        .route(
+            "/workspace_stats",
+            post(json_response(legacy::workspace::workspace_stats_cmd)),
+        )
+        .route("/fsck", post(json_response(legacy::workspace::fsck_cmd)))
+        .route(
+            "/repair_workspace",
+            post(json_response(legacy::workspace::repair_workspace_cmd)),
         );
 
     #[cfg(unix)]
@@ -287,6 +309,10 @@ pub async fn run() {
             "/canned_branch_name",
             post(json_response(legacy::workspace::canned_branch_name_cmd)),
         )
+        .route(
+            "/suggest_branch_name",
+            post(json_response(legacy::workspace::suggest_branch_name_cmd)),
+        )
         .route(
             "/target_commits",
             post(json_response(legacy::workspace::target_commits_cmd)),
@@ -371,6 +397,12 @@ pub async fn run() {
                 legacy::virtual_branches::integrate_upstream_commits_cmd,
             )),
         )
+        .route(
+            "/integrate_upstream_for_stack",
+            post(json_response(
+                legacy::virtual_branches::integrate_upstream_for_stack_cmd,
+            )),
+        )
         .route(
             "/get_initial_integration_steps_for_branch",
             post(json_response(
@@ -393,6 +425,12 @@ pub async fn run() {
             "/set_base_branch",
             post(json_response(legacy::virtual_branches::set_base_branch_cmd)),
         )
+        .route(
+            "/switch_base_branch",
+            post(json_response(
+                legacy::virtual_branches::switch_base_branch_cmd,
+            )),
+        )
         .route(
             "/switch_back_to_workspace",
             post(json_response(
@@ -415,6 +453,38 @@ pub async fn run() {
             "/unapply_stack",
             post(json_response(legacy::virtual_branches::unapply_stack_cmd)),
         )
+        .route(
+            "/archive_stack",
+            post(json_response(legacy::virtual_branches::archive_stack_cmd)),
+        )
+        .route(
+            "/branch_divergence",
+            post(json_response(
+                legacy::virtual_branches::branch_divergence_cmd,
+            )),
+        )
+        .route(
+            "/conflict_details",
+            post(json_response(
+                legacy::virtual_branches::conflict_details_cmd,
+            )),
+        )
+        .route(
+            "/export_patches",
+            post(json_response(legacy::virtual_branches::export_patches_cmd)),
+        )
+        .route(
+            "/export_stack_bundle",
+            post(json_response(
+                legacy::virtual_branches::export_stack_bundle_cmd,
+            )),
+        )
+        .route(
+            "/import_stack_bundle",
+            post(json_response(
+                legacy::virtual_branches::import_stack_bundle_cmd,
+            )),
+        )
         .route(
             "/amend_virtual_branch",
             post(json_response(
@@ -447,6 +517,46 @@ pub async fn run() {
             "/squash_commits",
             post(json_response(legacy::virtual_branches::squash_commits_cmd)),
         )
+        .route(
+            "/cherry_pick_into_stack",
+            post(json_response(
+                legacy::virtual_branches::cherry_pick_into_stack_cmd,
+            )),
+        )
+        .route(
+            "/revert_commit",
+            post(json_response(legacy::virtual_branches::revert_commit_cmd)),
+        )
+        .route(
+            "/apply_patch_series",
+            post(json_response(
+                legacy::virtual_branches::apply_patch_series_cmd,
+            )),
+        )
+        .route(
+            "/resolve_rewritten",
+            post(json_response(
+                legacy::virtual_branches::resolve_rewritten_cmd,
+            )),
+        )
+        .route(
+            "/external_commits",
+            post(json_response(
+                legacy::virtual_branches::external_commits_cmd,
+            )),
+        )
+        .route(
+            "/adopt_external_commits",
+            post(json_response(
+                legacy::virtual_branches::adopt_external_commits_cmd,
+            )),
+        )
+        .route(
+            "/autosquash_stack",
+            post(json_response(
+                legacy::virtual_branches::autosquash_stack_cmd,
+            )),
+        )
         .route(
             "/fetch_from_remotes",
             post(json_response(
@@ -499,6 +609,10 @@ pub async fn run() {
             "/edit_initial_index_state",
             post(json_response(legacy::modes::edit_initial_index_state_cmd)),
         )
+        .route(
+            "/resolve_with_mergetool",
+            post(json_response(legacy::modes::resolve_with_mergetool_cmd)),
+        )
         .route(
             "/edit_changes_from_initial",
             post(json_response(legacy::modes::edit_changes_from_initial_cmd)),
@@ -539,6 +653,22 @@ pub async fn run() {
             "/message_hook",
             post(json_response(legacy::repo::message_hook_cmd)),
         )
+        .route(
+            "/validate_commit_message",
+            post(json_response(legacy::repo::validate_commit_message_cmd)),
+        )
+        .route(
+            "/fix_commit_message",
+            post(json_response(legacy::repo::fix_commit_message_cmd)),
+        )
+        .route(
+            "/list_recorded_resolutions",
+            post(json_response(legacy::repo::list_recorded_resolutions_cmd)),
+        )
+        .route(
+            "/forget_recorded_resolution",
+            post(json_response(legacy::repo::forget_recorded_resolution_cmd)),
+        )
         .route(
             "/create_branch",
             post(json_response(legacy::stack::create_branch_cmd)),
@@ -559,23 +689,83 @@ pub async fn run() {
             "/update_branch_pr_number",
             post(json_response(legacy::stack::update_branch_pr_number_cmd)),
         )
+        .route(
+            "/stack_identity",
+            post(json_response(legacy::stack::stack_identity_cmd)),
+        )
+        .route(
+            "/set_stack_identity",
+            post(json_response(legacy::stack::set_stack_identity_cmd)),
+        )
+        .route(
+            "/stack_notes",
+            post(json_response(legacy::stack::stack_notes_cmd)),
+        )
+        .route(
+            "/set_stack_description",
+            post(json_response(legacy::stack::set_stack_description_cmd)),
+        )
+        .route(
+            "/set_commit_note",
+            post(json_response(legacy::stack::set_commit_note_cmd)),
+        )
         .route(
             "/push_stack",
             post(json_response(legacy::stack::push_stack_cmd)),
         )
+        .route(
+            "/push_stack_all",
+            post(json_response(legacy::stack::push_stack_all_cmd)),
+        )
         // Undo/Snapshot commands
         .route(
             "/list_snapshots",
             post(json_response(legacy::oplog::list_snapshots_cmd)),
         )
+        .route(
+            "/create_named_snapshot",
+            post(json_response(legacy::oplog::create_named_snapshot_cmd)),
+        )
+        .route(
+            "/list_named_snapshots",
+            post(json_response(legacy::oplog::list_named_snapshots_cmd)),
+        )
+        .route(
+            "/restore_named_snapshot",
+            post(json_response(legacy::oplog::restore_named_snapshot_cmd)),
+        )
         .route(
             "/restore_snapshot",
             post(json_response(legacy::oplog::restore_snapshot_cmd)),
         )
+        .route(
+            "/restore_stack",
+            post(json_response(legacy::oplog::restore_stack_cmd)),
+        )
         .route(
             "/snapshot_diff",
             post(json_response(legacy::oplog::snapshot_diff_cmd)),
         )
+        .route(
+            "/stack_diff",
+            post(json_response(legacy::oplog::stack_diff_cmd)),
+        )
+        .route(
+            "/snapshot_delta",
+            post(json_response(legacy::oplog::snapshot_delta_cmd)),
+        )
+        .route(
+            "/file_history",
+            post(json_response(legacy::oplog::file_history_cmd)),
+        )
+        .route(
+            "/prune_snapshots",
+            post(json_response(legacy::oplog::prune_snapshots_cmd)),
+        )
+        .route(
+            "/run_maintenance",
+            post(json_response(legacy::oplog::run_maintenance_cmd)),
+        )
         .route(
             "/get_gb_config",
             post(json_response(legacy::config::get_gb_config_cmd)),
@@ -584,6 +774,26 @@ pub async fn run() {
             "/set_gb_config",
             post(json_response(legacy::config::set_gb_config_cmd)),
         )
+        .route(
+            "/get_commit_template",
+            post(json_response(legacy::config::get_commit_template_cmd)),
+        )
+        .route(
+            "/set_commit_template",
+            post(json_response(legacy::config::set_commit_template_cmd)),
+        )
+        .route(
+            "/get_workspace_merge_options",
+            post(json_response(
+                legacy::config::get_workspace_merge_options_cmd,
+            )),
+        )
+        .route(
+            "/set_workspace_merge_options",
+            post(json_response(
+                legacy::config::set_workspace_merge_options_cmd,
+            )),
+        )
         .route(
             "/store_author_globally_if_unset",
             post(json_response(
@@ -622,6 +832,22 @@ pub async fn run() {
             "/forget_github_account",
             post(json_response(github::forget_github_account_cmd)),
         )
+        .route(
+            "/forget_gitlab_account",
+            post(json_response(gitlab::forget_gitlab_account_cmd)),
+        )
+        .route(
+            "/list_known_gitlab_accounts",
+            post(json_response(gitlab::list_known_gitlab_accounts_cmd)),
+        )
+        .route(
+            "/forget_bitbucket_account",
+            post(json_response(bitbucket::forget_bitbucket_account_cmd)),
+        )
+        .route(
+            "/list_known_bitbucket_accounts",
+            post(json_response(bitbucket::list_known_bitbucket_accounts_cmd)),
+        )
         .route(
             "/clear_all_github_tokens",
             post(json_response(github::clear_all_github_tokens_cmd)),
@@ -694,6 +920,18 @@ pub async fn run() {
             "/commit_uncommit_changes",
             post(json_response(commit::commit_uncommit_changes_cmd)),
         )
+        .route(
+            "/commit_search",
+            post(json_response(commit::commit_search_cmd)),
+        )
+        .route(
+            "/list_operations",
+            post(json_response(operations::list_operations_cmd)),
+        )
+        .route(
+            "/cancel_operation",
+            post(json_response(operations::cancel_operation_cmd)),
+        )
         // Catch-all for commands that need special handling (app, extra, app_settings_sync)
         .route("/{command}", post(post_handle_command_with_path))
         .route(
@@ -820,9 +1058,31 @@ async fn handle_command(
         "update_fetch" => deserialize_json(request.params).and_then(|params| {
             legacy::settings::update_fetch(&app_settings_sync, params).map(|r| json!(r))
         }),
+        "update_diff" => deserialize_json(request.params).and_then(|params| {
+            legacy::settings::update_diff(&app_settings_sync, params).map(|r| json!(r))
+        }),
+        "update_whitespace_only_hunks" => deserialize_json(request.params).and_then(|params| {
+            legacy::settings::update_whitespace_only_hunks(&app_settings_sync, params)
+                .map(|r| json!(r))
+        }),
+        "update_oplog" => deserialize_json(request.params).and_then(|params| {
+            legacy::settings::update_oplog(&app_settings_sync, params).map(|r| json!(r))
+        }),
+        "update_maintenance" => deserialize_json(request.params).and_then(|params| {
+            legacy::settings::update_maintenance(&app_settings_sync, params).map(|r| json!(r))
+        }),
+        "update_operation_trace" => deserialize_json(request.params).and_then(|params| {
+            legacy::settings::update_operation_trace(&app_settings_sync, params).map(|r| json!(r))
+        }),
+        "update_ai" => deserialize_json(request.params).and_then(|params| {
+            legacy::settings::update_ai(&app_settings_sync, params).map(|r| json!(r))
+        }),
         "update_reviews" => deserialize_json(request.params).and_then(|params| {
             legacy::settings::update_reviews(&app_settings_sync, params).map(|r| json!(r))
         }),
+        "update_forge_hosts" => deserialize_json(request.params).and_then(|params| {
+            legacy::settings::update_forge_hosts(&app_settings_sync, params).map(|r| json!(r))
+        }),
         // Project management (need extra or app)
         "list_projects" => projects::list_projects(&extra).await,
         "set_project_active" => {
@@ -850,6 +1110,17 @@ async fn handle_command(
                 Err(e) => Err(e),
             }
         }
+        "detect_integrated_stacks" => {
+            let params = deserialize_json(request.params);
+            match params {
+                Ok(params) => {
+                    let result =
+                        legacy::virtual_branches::detect_integrated_stacks_cmd(params).await;
+                    result.map(|r| json!(r))
+                }
+                Err(e) => Err(e),
+            }
+        }
         "resolve_upstream_integration" => {
             let params = deserialize_json(request.params);
             match params {
@@ -896,6 +1167,26 @@ async fn handle_command(
                 Err(e) => Err(e),
             }
         }
+        "store_gitlab_pat" => {
+            let params = deserialize_json(request.params);
+            match params {
+                Ok(params) => {
+                    let result = gitlab::store_gitlab_pat_cmd(params).await;
+                    result.map(|r| json!(r))
+                }
+                Err(e) => Err(e),
+            }
+        }
+        "store_bitbucket_api_token" => {
+            let params = deserialize_json(request.params);
+            match params {
+                Ok(params) => {
+                    let result = bitbucket::store_bitbucket_api_token_cmd(params).await;
+                    result.map(|r| json!(r))
+                }
+                Err(e) => Err(e),
+            }
+        }
         "list_known_github_accounts" => {
             github::list_known_github_accounts().await.map(|r| json!(r))
         }
@@ -920,6 +1211,26 @@ async fn handle_command(
                 Err(e) => Err(e),
             }
         }
+        "review_comments_for_stack" => {
+            let params = deserialize_json(request.params);
+            match params {
+                Ok(params) => {
+                    let result = legacy::forge::review_comments_for_stack_cmd(params).await;
+                    result.map(|r| json!(r))
+                }
+                Err(e) => Err(e),
+            }
+        }
+        "checks_for_branch" => {
+            let params = deserialize_json(request.params);
+            match params {
+                Ok(params) => {
+                    let result = legacy::forge::checks_for_branch_cmd(params);
+                    result.map(|r| json!(r))
+                }
+                Err(e) => Err(e),
+            }
+        }
         "publish_review" => {
             let params = deserialize_json(request.params);
             match params {
@@ -930,6 +1241,46 @@ async fn handle_command(
                 Err(e) => Err(e),
             }
         }
+        "create_pr_for_branch" => {
+            let params = deserialize_json(request.params);
+            match params {
+                Ok(params) => {
+                    let result = legacy::forge::create_pr_for_branch_cmd(params).await;
+                    result.map(|r| json!(r))
+                }
+                Err(e) => Err(e),
+            }
+        }
+        "update_review_bases" => {
+            let params = deserialize_json(request.params);
+            match params {
+                Ok(params) => {
+                    let result = legacy::forge::update_review_bases_cmd(params).await;
+                    result.map(|r| json!(r))
+                }
+                Err(e) => Err(e),
+            }
+        }
+        "update_stack_footers" => {
+            let params = deserialize_json(request.params);
+            match params {
+                Ok(params) => {
+                    let result = legacy::forge::update_stack_footers_cmd(params).await;
+                    result.map(|r| json!(r))
+                }
+                Err(e) => Err(e),
+            }
+        }
+        "import_review_suggestions" => {
+            let params = deserialize_json(request.params);
+            match params {
+                Ok(params) => {
+                    let result = legacy::forge::import_review_suggestions_cmd(params).await;
+                    result.map(|r| json!(r))
+                }
+                Err(e) => Err(e),
+            }
+        }
         // Askpass commands (async)
         "submit_prompt_response" => {
             let params = deserialize_json(request.params);
@@ -81,6 +81,33 @@ impl ActiveProjects {
                         name: format!("project://{project_id}/worktree_changes"),
                         payload: serde_json::json!(&changes),
                     },
+                    Change::ExternalCommits {
+                        project_id,
+                        commit_ids,
+                    } => FrontendEvent {
+                        name: format!("project://{project_id}/git/external_commits"),
+                        payload: serde_json::json!({
+                            "commitIds": commit_ids.iter().map(ToString::to_string).collect::<Vec<_>>(),
+                        }),
+                    },
+                    Change::BaseBranchMoved {
+                        project_id,
+                        new_commits,
+                    } => FrontendEvent {
+                        name: format!("project://{project_id}/git/base_branch_moved"),
+                        payload: serde_json::json!({ "newCommits": new_commits }),
+                    },
+                    Change::ProjectSettings {
+                        project_id,
+                        settings,
+                    } => FrontendEvent {
+                        name: format!("project://{project_id}/settings"),
+                        payload: serde_json::json!(settings),
+                    },
+                    Change::WorkspaceEvent { project_id, event } => FrontendEvent {
+                        name: format!("project://{project_id}/workspace_event"),
+                        payload: serde_json::json!(event),
+                    },
                 };
 
                 println!("Sending event");
@@ -1,4 +1,4 @@
-use anyhow::Result;
+use anyhow::{Result, bail};
 use serde::{Deserialize, Serialize};
 
 use crate::AppSettingsWithDiskSync;
@@ -48,6 +48,60 @@ pub struct FetchUpdate {
     pub auto_fetch_interval_minutes: Option<isize>,
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+/// Update request for [`crate::app_settings::Diff`].
+pub struct DiffUpdate {
+    pub algorithm: Option<String>,
+    pub ignore_whitespace: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+/// Update request for [`crate::app_settings::WhitespaceOnlyHunks`].
+pub struct WhitespaceOnlyHunksUpdate {
+    pub handling: Option<String>,
+    pub formatting_branch: Option<String>,
+}
+
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+/// Update request for [`crate::app_settings::Oplog`].
+pub struct OplogUpdate {
+    pub max_age_days: Option<u32>,
+    pub max_snapshots: Option<u32>,
+    pub max_size_mb: Option<u32>,
+    pub auto_prune: Option<bool>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+/// Update request for [`crate::app_settings::Maintenance`].
+pub struct MaintenanceUpdate {
+    pub mode: Option<crate::app_settings::MaintenanceMode>,
+    pub interval_hours: Option<u32>,
+    pub prune_expiry_days: Option<u32>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+/// Update request for [`crate::app_settings::OperationTrace`].
+pub struct OperationTraceUpdate {
+    pub enabled: Option<bool>,
+    pub max_file_size_kb: Option<u32>,
+    pub max_rotated_files: Option<u32>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+/// Update request for [`crate::app_settings::Ai`].
+pub struct AiUpdate {
+    pub provider: Option<String>,
+    pub model: Option<String>,
+    pub endpoint: Option<String>,
+    pub commit_message_candidates: Option<u32>,
+}
+
 #[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 /// Update request for [`crate::app_settings::UiSettings`].
@@ -109,6 +163,16 @@ impl AppSettingsWithDiskSync {
         settings.save()
     }
 
+    /// Replace all configured self-hosted forges with `forge_hosts`.
+    pub fn update_forge_hosts(
+        &self,
+        forge_hosts: Vec<crate::app_settings::ForgeHost>,
+    ) -> Result<()> {
+        let mut settings = self.get_mut_enforce_save()?;
+        settings.forge_hosts = forge_hosts;
+        settings.save()
+    }
+
     pub fn update_claude(&self, update: ClaudeUpdate) -> Result<()> {
         let mut settings = self.get_mut_enforce_save()?;
         if let Some(executable) = update.executable {
@@ -151,6 +215,96 @@ impl AppSettingsWithDiskSync {
         settings.save()
     }
 
+    pub fn update_diff(&self, update: DiffUpdate) -> Result<()> {
+        let mut settings = self.get_mut_enforce_save()?;
+        if let Some(algorithm) = update.algorithm {
+            settings.diff.algorithm = algorithm;
+        }
+        if let Some(ignore_whitespace) = update.ignore_whitespace {
+            settings.diff.ignore_whitespace = ignore_whitespace;
+        }
+        settings.save()
+    }
+
+    pub fn update_whitespace_only_hunks(&self, update: WhitespaceOnlyHunksUpdate) -> Result<()> {
+        let mut settings = self.get_mut_enforce_save()?;
+        if let Some(handling) = update.handling {
+            settings.whitespace_only_hunks.handling = handling;
+        }
+        if let Some(formatting_branch) = update.formatting_branch {
+            settings.whitespace_only_hunks.formatting_branch = formatting_branch;
+        }
+        settings.save()
+    }
+
+    pub fn update_oplog(&self, update: OplogUpdate) -> Result<()> {
+        let mut settings = self.get_mut_enforce_save()?;
+        if let Some(max_age_days) = update.max_age_days {
+            settings.oplog.max_age_days = max_age_days;
+        }
+        if let Some(max_snapshots) = update.max_snapshots {
+            settings.oplog.max_snapshots = max_snapshots;
+        }
+        if let Some(max_size_mb) = update.max_size_mb {
+            settings.oplog.max_size_mb = max_size_mb;
+        }
+        if let Some(auto_prune) = update.auto_prune {
+            settings.oplog.auto_prune = auto_prune;
+        }
+        settings.save()
+    }
+
+    pub fn update_maintenance(&self, update: MaintenanceUpdate) -> Result<()> {
+        if update.interval_hours == Some(0) {
+            bail!("Maintenance must be at least one hour apart");
+        }
+        if update.prune_expiry_days == Some(0) {
+            bail!("Unreachable objects must expire after at least one day");
+        }
+        let mut settings = self.get_mut_enforce_save()?;
+        if let Some(mode) = update.mode {
+            settings.maintenance.mode = mode;
+        }
+        if let Some(interval_hours) = update.interval_hours {
+            settings.maintenance.interval_hours = interval_hours;
+        }
+        if let Some(prune_expiry_days) = update.prune_expiry_days {
+            settings.maintenance.prune_expiry_days = prune_expiry_days;
+        }
+        settings.save()
+    }
+
+    pub fn update_operation_trace(&self, update: OperationTraceUpdate) -> Result<()> {
+        let mut settings = self.get_mut_enforce_save()?;
+        if let Some(enabled) = update.enabled {
+            settings.operation_trace.enabled = enabled;
+        }
+        if let Some(max_file_size_kb) = update.max_file_size_kb {
+            settings.operation_trace.max_file_size_kb = max_file_size_kb;
+        }
+        if let Some(max_rotated_files) = update.max_rotated_files {
+            settings.operation_trace.max_rotated_files = max_rotated_files;
+        }
+        settings.save()
+    }
+
+    pub fn update_ai(&self, update: AiUpdate) -> Result<()> {
+        let mut settings = self.get_mut_enforce_save()?;
+        if let Some(provider) = update.provider {
+            settings.ai.provider = provider;
+        }
+        if let Some(model) = update.model {
+            settings.ai.model = model;
+        }
+        if let Some(endpoint) = update.endpoint {
+            settings.ai.endpoint = endpoint;
+        }
+        if let Some(commit_message_candidates) = update.commit_message_candidates {
+            settings.ai.commit_message_candidates = commit_message_candidates;
+        }
+        settings.save()
+    }
+
     pub fn update_ui(&self, update: UiUpdate) -> Result<()> {
         let mut settings = self.get_mut_enforce_save()?;
         if let Some(use_native_title_bar) = update.use_native_title_bar {
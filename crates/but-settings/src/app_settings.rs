@@ -78,6 +78,8 @@ pub struct FeatureFlags {
     /// "legacy" uses recursive watching.
     /// "modern" uses ignore-aware non-recursive watching.
     pub watch_mode: String,
+    /// How long the filesystem has to be quiet, in milliseconds, before changes are processed as one batch.
+    pub watch_debounce_ms: u32,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, TS)]
@@ -104,6 +106,138 @@ pub struct Fetch {
     pub auto_fetch_interval_minutes: isize,
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, TS)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(
+    feature = "export-ts",
+    ts(export, export_to = "./settings/appSettings.ts")
+)]
+pub struct Diff {
+    /// The diff algorithm, one of `myers`, `minimal`, `patience` or `histogram`.
+    /// If empty, `diff.algorithm` of the Git configuration is used.
+    pub algorithm: String,
+    /// How whitespace changes are ignored, one of `none`, `atEol`, `change` or `all`.
+    pub ignore_whitespace: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, TS)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(
+    feature = "export-ts",
+    ts(export, export_to = "./settings/appSettings.ts")
+)]
+pub struct WhitespaceOnlyHunks {
+    /// One of `show` to list them like any other hunk, `hide` to leave them out of the uncommitted changes,
+    /// or `assign` to assign new ones to the stack of `formatting_branch`.
+    pub handling: String,
+    /// The short name of the branch whose stack receives whitespace-only hunks if `handling` is `assign`.
+    pub formatting_branch: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, TS)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(
+    feature = "export-ts",
+    ts(export, export_to = "./settings/appSettings.ts")
+)]
+pub struct Oplog {
+    /// Snapshots older than this many days are pruned. `0` keeps snapshots of any age.
+    pub max_age_days: u32,
+    /// Only this many of the most recent snapshots are kept. `0` keeps any amount of snapshots.
+    pub max_snapshots: u32,
+    /// The most recent snapshots are kept as long as the data they add takes less than this many megabytes.
+    /// `0` doesn't limit the size.
+    pub max_size_mb: u32,
+    /// Whether snapshots are pruned automatically after a snapshot was created, at most once per hour.
+    /// This keeps the ids of the remaining snapshots, so pruned snapshots are hidden, but still take space
+    /// until they are pruned explicitly.
+    pub auto_prune: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, TS)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(
+    feature = "export-ts",
+    ts(export, export_to = "./settings/appSettings.ts")
+)]
+pub struct Maintenance {
+    /// How thoroughly objects are packed.
+    pub mode: MaintenanceMode,
+    /// Maintenance runs at most once in this many hours, and at least one hour apart.
+    pub interval_hours: u32,
+    /// Unreachable objects, like those of pruned snapshots, are deleted once they are older than this many days,
+    /// and at least one day old.
+    pub prune_expiry_days: u32,
+}
+
+/// How thoroughly objects are packed during [maintenance](Maintenance).
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq, TS)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(
+    feature = "export-ts",
+    ts(export, export_to = "./settings/appSettings.ts")
+)]
+pub enum MaintenanceMode {
+    /// Don't perform any maintenance.
+    #[default]
+    Off,
+    /// Pack loose objects into a new pack, leaving existing packs alone.
+    Incremental,
+    /// Repack all objects into a single pack, which takes longer but yields the smallest object database.
+    Aggressive,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, TS)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(
+    feature = "export-ts",
+    ts(export, export_to = "./settings/appSettings.ts")
+)]
+pub struct OperationTrace {
+    /// Whether mutating operations are recorded in `operations.jsonl` in the GitButler directory of the project.
+    /// The trace never leaves the machine, and only records what was called, not the data it was called with.
+    pub enabled: bool,
+    /// The trace file is rotated once it is larger than this many kilobytes.
+    pub max_file_size_kb: u32,
+    /// Only this many rotated trace files are kept besides the current one.
+    pub max_rotated_files: u32,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, TS)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(
+    feature = "export-ts",
+    ts(export, export_to = "./settings/appSettings.ts")
+)]
+pub struct Ai {
+    /// The provider generating commit messages, one of `openai`, `anthropic` or `ollama`.
+    /// If empty, `gitbutler.aiModelProvider` of the Git configuration is used.
+    pub provider: String,
+    /// The model to ask, like `gpt-5-mini`. If empty, the model configured for the provider is used.
+    pub model: String,
+    /// The base URL of an OpenAI-compatible endpoint, or the `host:port` of an ollama server.
+    /// If empty, the default endpoint of the provider is used.
+    pub endpoint: String,
+    /// How many commit messages to suggest at once.
+    pub commit_message_candidates: u32,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, TS)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(
+    feature = "export-ts",
+    ts(export, export_to = "./settings/appSettings.ts")
+)]
+pub struct ForgeHost {
+    /// The host of remote URLs served by the forge, like `github.example.com`.
+    pub host: String,
+    /// The kind of forge, one of `github`, `gitlab`, `bitbucket` or `azure`.
+    pub forge: String,
+    /// The base URL of the API of the forge, like `https://github.example.com/api/v3`.
+    /// If unset, the default location of the API on `host` for the kind of forge is used.
+    pub api_url: Option<String>,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, TS)]
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(
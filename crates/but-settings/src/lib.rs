@@ -7,6 +7,10 @@ use serde::{Deserialize, Serialize};
 pub struct AppSettings {
     /// The amount of context lines to show in unified diffs, above and below the hunk.
     pub context_lines: u32,
+    /// How unified diffs are computed.
+    pub diff: app_settings::Diff,
+    /// What to do with hunks that only change whitespace.
+    pub whitespace_only_hunks: app_settings::WhitespaceOnlyHunks,
     /// Whether the user has passed the onboarding flow.
     pub onboarding_complete: bool,
     /// Telemetry settings
@@ -19,6 +23,16 @@ pub struct AppSettings {
     pub extra_csp: app_settings::ExtraCsp,
     /// Settings related to fetching.
     pub fetch: app_settings::Fetch,
+    /// Settings related to the retention of snapshots in the operations log.
+    pub oplog: app_settings::Oplog,
+    /// Settings related to the maintenance of the object database.
+    pub maintenance: app_settings::Maintenance,
+    /// Settings related to the local trace of operations for debugging.
+    pub operation_trace: app_settings::OperationTrace,
+    /// Settings related to AI generated commit messages.
+    pub ai: app_settings::Ai,
+    /// Self-hosted forges, like GitHub Enterprise or GitLab instances, which can't be recognized by their host.
+    pub forge_hosts: Vec<app_settings::ForgeHost>,
     /// Settings related to Claude Code.
     pub claude: app_settings::Claude,
     /// Settings related to code reviews and pull requests.
@@ -31,6 +45,16 @@ pub struct AppSettings {
     pub app_updates_check_interval_sec: u64,
 }
 
+/// Access utilities
+impl AppSettings {
+    /// Return the configuration of the self-hosted forge serving `host`, if there is one.
+    pub fn forge_host(&self, host: &str) -> Option<&app_settings::ForgeHost> {
+        self.forge_hosts
+            .iter()
+            .find(|forge_host| forge_host.host.eq_ignore_ascii_case(host))
+    }
+}
+
 impl Default for AppSettings {
     fn default() -> Self {
         // this is safe because we know the default settings are a static assets file that is always valid
@@ -82,6 +106,7 @@ pub mod customization {
 pub mod app_settings;
 mod json;
 mod legacy_settings;
+pub mod migrations;
 mod persistence;
 mod watch;
 use ts_rs::TS;
@@ -0,0 +1,121 @@
+//! Versioned migrations of the settings file.
+//!
+//! The settings file carries the version of its format in the `version` key, which is `0` if it's missing.
+//! Whenever the meaning or location of a setting changes, a migration is added here so values set by the user
+//! are carried over instead of being lost, and [`CURRENT_VERSION`] is incremented.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use serde_json_lenient::to_string_pretty;
+
+/// The version of the settings format this build writes and understands.
+pub const CURRENT_VERSION: u64 = 1;
+
+/// The key holding the version in the settings file.
+pub(crate) const VERSION_KEY: &str = "version";
+
+/// The migrations of the settings format, where the one at index `n` turns version `n` into version `n + 1`.
+///
+/// Each of them receives the customizations as stored on disk, not the settings merged with the defaults.
+const MIGRATIONS: &[fn(&mut serde_json::Value)] = &[v0_to_v1];
+
+/// The settings file was written by a newer version of the application, and can't be read by this one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnsupportedVersionError {
+    /// The settings file.
+    pub path: PathBuf,
+    /// The version of the settings file.
+    pub version: u64,
+    /// The most recent version this build understands.
+    pub supported: u64,
+}
+
+impl std::fmt::Display for UnsupportedVersionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "The settings at '{}' have version {}, but only versions up to {} are supported. Please update GitButler.",
+            self.path.display(),
+            self.version,
+            self.supported
+        )
+    }
+}
+
+impl std::error::Error for UnsupportedVersionError {}
+
+/// Return the version of the settings format of `customizations`.
+pub(crate) fn version_of(customizations: &serde_json::Value) -> u64 {
+    customizations
+        .get(VERSION_KEY)
+        .and_then(|version| version.as_u64())
+        .unwrap_or(0)
+}
+
+/// Return where the settings at `config_path` are backed up before they are migrated away from `version`.
+pub fn backup_path(config_path: &Path, version: u64) -> PathBuf {
+    let mut file_name = config_path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(format!(".v{version}.bak"));
+    config_path.with_file_name(file_name)
+}
+
+/// Bring `customizations`, as read from `config_path`, to [`CURRENT_VERSION`] and return them.
+///
+/// If a migration was needed, the file at `config_path` is backed up first and then overwritten
+/// with the migrated customizations. If that fails, the migrated customizations are still returned
+/// so the migration is retried the next time the settings are loaded.
+/// It's an [`UnsupportedVersionError`] if `customizations` are of a version that is too new.
+pub(crate) fn migrate(
+    config_path: &Path,
+    mut customizations: serde_json::Value,
+) -> Result<serde_json::Value> {
+    let version = version_of(&customizations);
+    if version > CURRENT_VERSION {
+        return Err(UnsupportedVersionError {
+            path: config_path.to_owned(),
+            version,
+            supported: CURRENT_VERSION,
+        }
+        .into());
+    }
+    if version == CURRENT_VERSION {
+        return Ok(customizations);
+    }
+
+    for migration in &MIGRATIONS[version as usize..] {
+        migration(&mut customizations);
+    }
+    if let Some(customizations) = customizations.as_object_mut() {
+        customizations.insert(VERSION_KEY.into(), CURRENT_VERSION.into());
+    }
+
+    let persist = || -> Result<()> {
+        std::fs::copy(config_path, backup_path(config_path, version))?;
+        but_fs::write(config_path, to_string_pretty(&customizations)?)?;
+        Ok(())
+    };
+    if let Err(err) = persist() {
+        tracing::error!(
+            "Failed to persist settings migrated from version {version} to {CURRENT_VERSION}: {err:#}"
+        );
+    }
+    Ok(customizations)
+}
+
+/// Carry a customized interval of the deprecated `ui.checkForUpdatesIntervalInSeconds` over to
+/// `appUpdatesCheckIntervalSec`, unless that is customized as well.
+fn v0_to_v1(customizations: &mut serde_json::Value) {
+    let Some(interval) = customizations
+        .get("ui")
+        .and_then(|ui| ui.get("checkForUpdatesIntervalInSeconds"))
+        .cloned()
+    else {
+        return;
+    };
+    if let Some(customizations) = customizations.as_object_mut() {
+        customizations
+            .entry("appUpdatesCheckIntervalSec")
+            .or_insert(interval);
+    }
+}
@@ -1,6 +1,6 @@
 use std::path::{Path, PathBuf};
 
-use anyhow::Result;
+use anyhow::{Context as _, Result};
 use serde_json::json;
 use serde_json_lenient::to_string_pretty;
 
@@ -8,26 +8,33 @@ use crate::{
     AppSettings,
     json::{json_difference, merge_json_value},
     legacy_settings::maybe_migrate_legacy_settings,
+    migrations::{CURRENT_VERSION, VERSION_KEY, migrate},
     watch::SETTINGS_FILE,
 };
 
 pub(crate) static DEFAULTS: &str = include_str!("../assets/defaults.jsonc");
 
 impl AppSettings {
-    /// Load the settings from the configuration directory, or initialize the file with an empty JSON object at `config_path`.
+    /// Load the settings from the configuration directory, or initialize the file with a JSON object at `config_path`
+    /// that only holds the current version of the settings format.
+    /// Customizations of older versions are migrated to the current version, see [`crate::migrations`].
     /// Finally, merge all customizations from `config_path` into the default settings.
     ///
     /// Use `customization` to alter any of the built-in defaults based on other requirements that are application or distribution defined.
     /// This is the only way to alter the defaults without writing these customizations back to disk.
     pub fn load(config_path: &Path, customization: Option<serde_json::Value>) -> Result<Self> {
-        // If the file on config_path does not exist, create it empty
+        // If the file on config_path does not exist, create it with nothing but the current version
         if !config_path.exists() {
-            but_fs::create_dirs_then_write(config_path, "{}\n")?;
+            but_fs::create_dirs_then_write(
+                config_path,
+                format!("{{\n  \"{VERSION_KEY}\": {CURRENT_VERSION}\n}}\n"),
+            )?;
         }
 
         // merge customizations from disk into the defaults to get a complete set of settings.
         let customizations: serde_json::Value =
             serde_json_lenient::from_str(&std::fs::read_to_string(config_path)?)?;
+        let customizations = migrate(config_path, customizations)?;
         let mut settings: serde_json::Value = serde_json_lenient::from_str(DEFAULTS)?;
 
         merge_json_value(customizations, &mut settings);
@@ -57,6 +64,43 @@ impl AppSettings {
         config_dir.join(SETTINGS_FILE)
     }
 
+    /// Return where the settings overrides of the repository with `git_dir` are placed.
+    pub fn project_settings_path(git_dir: &Path) -> PathBuf {
+        git_dir.join("gitbutler").join(SETTINGS_FILE)
+    }
+
+    /// Merge the settings overrides of the repository with `git_dir` into these settings, if there are any.
+    ///
+    /// Any setting can be overridden this way, and the overrides are never written back.
+    pub fn with_project_overrides(self, git_dir: &Path) -> Result<Self> {
+        let overrides_path = Self::project_settings_path(git_dir);
+        let overrides = match std::fs::read_to_string(&overrides_path) {
+            Ok(overrides) => overrides,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(self),
+            Err(err) => return Err(err.into()),
+        };
+        let overrides: serde_json::Value =
+            serde_json_lenient::from_str(&overrides).with_context(|| {
+                format!("Invalid project settings at '{}'", overrides_path.display())
+            })?;
+        let mut settings = serde_json::to_value(self)?;
+        merge_json_value(overrides, &mut settings);
+        serde_json::from_value(settings)
+            .with_context(|| format!("Invalid project settings at '{}'", overrides_path.display()))
+    }
+
+    /// Like [`Self::with_project_overrides()`], but keep these settings if the overrides can't be applied,
+    /// so a broken settings file of one repository doesn't prevent it from being opened.
+    pub fn with_project_overrides_or_unchanged(self, git_dir: &Path) -> Self {
+        match self.clone().with_project_overrides(git_dir) {
+            Ok(settings) => settings,
+            Err(err) => {
+                tracing::warn!("Ignoring project settings: {err:#}");
+                self
+            }
+        }
+    }
+
     /// Save all value in this instance to the custom configuration file *if they differ* from the defaults.
     pub fn save(&self, config_path: &Path, customization: Option<serde_json::Value>) -> Result<()> {
         // Load the current settings
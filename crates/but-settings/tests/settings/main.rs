@@ -111,3 +111,173 @@ mod load {
         }
     }
 }
+
+mod with_project_overrides {
+    use but_settings::AppSettings;
+
+    #[test]
+    fn without_overrides_file() -> anyhow::Result<()> {
+        let tmp = tempfile::TempDir::new()?;
+        let settings = AppSettings::default();
+        assert_eq!(
+            settings.clone().with_project_overrides(tmp.path())?,
+            settings,
+            "nothing changes if the repository has no settings"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn overrides_are_merged_over_app_settings() -> anyhow::Result<()> {
+        let tmp = tempfile::TempDir::new()?;
+        let overrides_path = AppSettings::project_settings_path(tmp.path());
+        std::fs::create_dir_all(overrides_path.parent().unwrap())?;
+        std::fs::write(
+            &overrides_path,
+            r#"{
+                // comments are allowed
+                "contextLines": 7,
+                "diff": { "algorithm": "histogram" },
+            }"#,
+        )?;
+
+        let app_settings = AppSettings::default();
+        let settings = app_settings.clone().with_project_overrides(tmp.path())?;
+        assert_eq!(settings.context_lines, 7, "overridden");
+        assert_eq!(settings.diff.algorithm, "histogram", "overridden");
+        assert_eq!(
+            settings.diff.ignore_whitespace, app_settings.diff.ignore_whitespace,
+            "siblings of overridden values are kept"
+        );
+        assert_eq!(settings.fetch, app_settings.fetch, "untouched");
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_overrides_are_an_error() -> anyhow::Result<()> {
+        let tmp = tempfile::TempDir::new()?;
+        let overrides_path = AppSettings::project_settings_path(tmp.path());
+        std::fs::create_dir_all(overrides_path.parent().unwrap())?;
+        std::fs::write(&overrides_path, r#"{ "contextLines": "many" }"#)?;
+
+        let err = AppSettings::default()
+            .with_project_overrides(tmp.path())
+            .unwrap_err();
+        assert!(
+            err.to_string().starts_with("Invalid project settings at"),
+            "{err:#}"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_overrides_can_be_ignored() -> anyhow::Result<()> {
+        let tmp = tempfile::TempDir::new()?;
+        let overrides_path = AppSettings::project_settings_path(tmp.path());
+        std::fs::create_dir_all(overrides_path.parent().unwrap())?;
+        std::fs::write(&overrides_path, r#"{ "contextLines": "many" }"#)?;
+
+        let settings = AppSettings::default();
+        assert_eq!(
+            settings
+                .clone()
+                .with_project_overrides_or_unchanged(tmp.path()),
+            settings,
+            "the global settings are used instead"
+        );
+
+        std::fs::write(&overrides_path, r#"{ "contextLines": 7 }"#)?;
+        assert_eq!(
+            settings
+                .with_project_overrides_or_unchanged(tmp.path())
+                .context_lines,
+            7,
+            "valid overrides still apply"
+        );
+        Ok(())
+    }
+}
+
+mod migrations {
+    use but_settings::{
+        AppSettings,
+        migrations::{CURRENT_VERSION, UnsupportedVersionError, backup_path},
+    };
+
+    #[test]
+    fn new_files_are_of_the_current_version() -> anyhow::Result<()> {
+        let tmp = tempfile::TempDir::new()?;
+        let config_path = tmp.path().join("settings.json");
+        AppSettings::load(&config_path, None)?;
+
+        let on_disk: serde_json::Value =
+            serde_json_lenient::from_str(&std::fs::read_to_string(&config_path)?)?;
+        assert_eq!(on_disk["version"], serde_json::json!(CURRENT_VERSION));
+        Ok(())
+    }
+
+    #[test]
+    fn unversioned_files_are_migrated_and_backed_up() -> anyhow::Result<()> {
+        let tmp = tempfile::TempDir::new()?;
+        let config_path = tmp.path().join("settings.json");
+        let original = r#"{
+            "telemetry": { "migratedFromLegacy": true },
+            "ui": { "checkForUpdatesIntervalInSeconds": 0 }
+        }"#;
+        std::fs::write(&config_path, original)?;
+
+        let settings = AppSettings::load(&config_path, None)?;
+        assert_eq!(
+            settings.app_updates_check_interval_sec, 0,
+            "the deprecated interval was carried over"
+        );
+        assert_eq!(
+            std::fs::read_to_string(backup_path(&config_path, 0))?,
+            original,
+            "the file is backed up as it was before the migration"
+        );
+        let on_disk: serde_json::Value =
+            serde_json_lenient::from_str(&std::fs::read_to_string(&config_path)?)?;
+        assert_eq!(
+            on_disk,
+            serde_json::json!({
+                "telemetry": { "migratedFromLegacy": true },
+                "ui": { "checkForUpdatesIntervalInSeconds": 0 },
+                "appUpdatesCheckIntervalSec": 0,
+                "version": CURRENT_VERSION,
+            })
+        );
+
+        std::fs::remove_file(backup_path(&config_path, 0))?;
+        AppSettings::load(&config_path, None)?;
+        assert!(
+            !backup_path(&config_path, 0).exists(),
+            "migrated files aren't migrated again"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn files_of_future_versions_are_rejected() -> anyhow::Result<()> {
+        let tmp = tempfile::TempDir::new()?;
+        let config_path = tmp.path().join("settings.json");
+        let future = format!(r#"{{ "version": {} }}"#, CURRENT_VERSION + 1);
+        std::fs::write(&config_path, &future)?;
+
+        let err = AppSettings::load(&config_path, None).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<UnsupportedVersionError>(),
+            Some(&UnsupportedVersionError {
+                path: config_path.clone(),
+                version: CURRENT_VERSION + 1,
+                supported: CURRENT_VERSION,
+            })
+        );
+        assert_eq!(
+            std::fs::read_to_string(&config_path)?,
+            future,
+            "the file is left untouched"
+        );
+        Ok(())
+    }
+}
@@ -108,6 +108,7 @@ fn resolve_changes(
                     previous_path: previous_path_bytes,
                     path: path_bytes,
                     hunk_headers,
+                    mode_flags: None,
                 }]
             }
             _ => unreachable!("BUG: specifying this shouldn't be possible"),
@@ -292,6 +293,7 @@ fn to_whole_file_diffspec(changes: Vec<TreeChange>) -> Vec<DiffSpec> {
             previous_path: change.previous_path().map(ToOwned::to_owned),
             path: change.path,
             hunk_headers: Vec::new(),
+            mode_flags: None,
         })
         .collect()
 }
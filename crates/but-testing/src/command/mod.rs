@@ -336,6 +336,7 @@ pub mod stacks {
                 rules: false,
                 single_branch: false,
                 watch_mode: "auto".into(),
+                watch_debounce_ms: 300,
             },
             ..AppSettings::default()
         };
@@ -396,6 +397,7 @@ pub mod stacks {
                 rules: false,
                 single_branch: false,
                 watch_mode: "auto".into(),
+                watch_debounce_ms: 300,
             },
             ..AppSettings::default()
         };
@@ -444,6 +446,7 @@ pub(crate) fn discard_change(
         previous_path,
         path,
         hunk_headers,
+        mode_flags: None,
     };
     debug_print(but_workspace::discard_workspace_changes(
         &repo,
@@ -464,6 +467,7 @@ pub async fn watch(args: &super::Args, watch_mode: Option<&str>) -> anyhow::Resu
         workdir,
         tx,
         watch_mode.and_then(|m| m.parse().ok()).unwrap_or_default(),
+        gitbutler_filemonitor::DEFAULT_DEBOUNCE,
     )?;
     let elapsed = start.elapsed();
     eprintln!(
@@ -401,12 +401,21 @@ impl Sandbox {
         use but_settings::{
             AppSettings,
             app_settings::{
-                Claude, ExtraCsp, FeatureFlags, Fetch, GitHubOAuthAppSettings, Reviews,
-                TelemetrySettings, UiSettings,
+                Ai, Claude, Diff, ExtraCsp, FeatureFlags, Fetch, GitHubOAuthAppSettings,
+                Maintenance, MaintenanceMode, OperationTrace, Oplog, Reviews, TelemetrySettings,
+                UiSettings, WhitespaceOnlyHunks,
             },
         };
         let settings = AppSettings {
             context_lines: 3,
+            diff: Diff {
+                algorithm: "".into(),
+                ignore_whitespace: "none".into(),
+            },
+            whitespace_only_hunks: WhitespaceOnlyHunks {
+                handling: "show".into(),
+                formatting_branch: "".into(),
+            },
             onboarding_complete: true,
             telemetry: TelemetrySettings {
                 app_metrics_enabled: false,
@@ -425,6 +434,7 @@ impl Sandbox {
                 rules: true,
                 single_branch: true,
                 watch_mode: "auto".into(),
+                watch_debounce_ms: 300,
             },
             extra_csp: ExtraCsp {
                 hosts: vec![],
@@ -433,6 +443,29 @@ impl Sandbox {
             fetch: Fetch {
                 auto_fetch_interval_minutes: 0,
             },
+            oplog: Oplog {
+                max_age_days: 0,
+                max_snapshots: 0,
+                max_size_mb: 0,
+                auto_prune: false,
+            },
+            maintenance: Maintenance {
+                mode: MaintenanceMode::Off,
+                interval_hours: 24,
+                prune_expiry_days: 14,
+            },
+            operation_trace: OperationTrace {
+                enabled: false,
+                max_file_size_kb: 0,
+                max_rotated_files: 0,
+            },
+            ai: Ai {
+                provider: "".into(),
+                model: "".into(),
+                endpoint: "".into(),
+                commit_message_candidates: 1,
+            },
+            forge_hosts: vec![],
             claude: Claude {
                 executable: "".to_string(),
                 notify_on_completion: false,
@@ -236,7 +236,7 @@ pub fn create_commit(
         params.message_body.trim()
     );
 
-    let outcome = but_workspace::legacy::commit_engine::create_commit_simple(
+    let outcome = gitbutler_branch_actions::hooks::create_commit_with_hooks(
         ctx,
         stack_id,
         None,
@@ -743,6 +743,7 @@ pub fn move_file_changes(
             path: BString::from(f.as_str()),
             previous_path: None,
             hunk_headers: vec![],
+            mode_flags: None,
         })
         .collect::<Vec<_>>();
 
@@ -1612,7 +1613,7 @@ pub fn get_filtered_changes(
     ctx: &mut Context,
     filter_changes: Option<Vec<BString>>,
 ) -> Result<Vec<FileChange>, anyhow::Error> {
-    let guard = ctx.exclusive_worktree_access();
+    let guard = ctx.shared_worktree_access();
     let repo = ctx.repo.get()?.clone();
     let (_, workspace) = ctx.workspace_and_read_only_meta_from_head(guard.read_permission())?;
     let worktree = but_core::diff::worktree_changes(&repo)?;
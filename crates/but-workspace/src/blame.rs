@@ -0,0 +1,148 @@
+use std::{collections::HashMap, ops::Range};
+
+use but_core::ref_metadata::StackId;
+use serde::Serialize;
+
+/// Who is responsible for a single line of a file in the worktree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LineBlame {
+    /// The commit that introduced the line, or `None` if the line isn't committed yet.
+    #[serde(with = "but_serde::object_id_opt")]
+    pub commit_id: Option<gix::ObjectId>,
+    /// The stack that owns the line.
+    ///
+    /// For committed lines, it's the stack that contains `commit_id`, which is `None` for commits
+    /// that are part of the target branch.
+    /// For uncommitted lines, it's the stack the hunk of the line is assigned to, if any.
+    pub stack_id: Option<StackId>,
+}
+
+/// An uncommitted hunk, as seen in the diff between `HEAD` and the worktree, along with the stack it's assigned to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UncommittedHunk {
+    /// The 1-based lines of the hunk in the worktree version of the file, which may include context lines.
+    pub new_lines: Range<u32>,
+    /// The stack the hunk is assigned to, if any.
+    pub stack_id: Option<StackId>,
+}
+
+pub(crate) mod function {
+    use std::ops::Range;
+
+    use anyhow::Context as _;
+    use bstr::BStr;
+
+    use super::{LineBlame, UncommittedHunk, commit_to_stack};
+
+    /// Attribute each line of the worktree version of the file at `path` in `repo` to the commit that
+    /// introduced it and the stack in `workspace` that owns it.
+    ///
+    /// Unlike `git blame`, lines that aren't committed yet aren't left unattributed, but are assigned to
+    /// the stack of the `uncommitted_hunks` they are a part of, which are typically obtained from hunk assignments.
+    ///
+    /// The returned lines are in order, so the first item belongs to the first line of the file.
+    /// Note that the file is read from the worktree as is, without applying any filters.
+    pub fn blame(
+        repo: &gix::Repository,
+        workspace: &but_graph::projection::Workspace,
+        path: &BStr,
+        uncommitted_hunks: &[UncommittedHunk],
+    ) -> anyhow::Result<Vec<LineBlame>> {
+        let stack_by_commit = commit_to_stack(workspace);
+        let head_id = repo.head_id()?;
+        let (committed_data, committed) = if head_id
+            .object()?
+            .peel_to_tree()?
+            .lookup_entry_by_path(gix::path::from_bstr(path))?
+            .is_some()
+        {
+            let outcome = repo.blame_file(
+                path,
+                head_id,
+                gix::blame::Options {
+                    diff_algorithm: repo.diff_algorithm()?,
+                    ..Default::default()
+                },
+            )?;
+            let committed: Vec<_> = outcome
+                .entries
+                .iter()
+                .flat_map(|entry| {
+                    std::iter::repeat_n(
+                        LineBlame {
+                            commit_id: Some(entry.commit_id),
+                            stack_id: stack_by_commit.get(&entry.commit_id).copied(),
+                        },
+                        entry.len.get() as usize,
+                    )
+                })
+                .collect();
+            (outcome.blob, committed)
+        } else {
+            (Vec::new(), Vec::new())
+        };
+
+        let worktree_path = repo
+            .workdir_path(path)
+            .context("need non-bare repository to read worktree files")?;
+        let worktree_data = match std::fs::read(worktree_path) {
+            Ok(data) => data,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(err) => return Err(err.into()),
+        };
+
+        let input = gix::diff::blob::InternedInput::new(
+            gix::diff::blob::sources::byte_lines_with_terminator(&committed_data),
+            gix::diff::blob::sources::byte_lines_with_terminator(&worktree_data),
+        );
+        let mut changes = Vec::new();
+        gix::diff::blob::diff(
+            repo.diff_algorithm()?,
+            &input,
+            |before: Range<u32>, after: Range<u32>| changes.push((before, after)),
+        );
+        let (old_end, new_end) = (input.before.len() as u32, input.after.len() as u32);
+
+        let mut committed = committed.into_iter();
+        let mut lines = Vec::with_capacity(input.after.len());
+        let mut old_pos = 0;
+        for (before, after) in changes
+            .into_iter()
+            .chain(Some((old_end..old_end, new_end..new_end)))
+        {
+            lines.extend(committed.by_ref().take((before.start - old_pos) as usize));
+            committed.by_ref().take(before.len()).for_each(drop);
+            lines.extend(after.map(|new_line| {
+                let line_number = new_line + 1;
+                LineBlame {
+                    commit_id: None,
+                    stack_id: uncommitted_hunks
+                        .iter()
+                        .find(|hunk| hunk.new_lines.contains(&line_number))
+                        .and_then(|hunk| hunk.stack_id),
+                }
+            }));
+            old_pos = before.end;
+        }
+        Ok(lines)
+    }
+}
+
+/// Map each commit in the stacks of `workspace` to the id of its stack.
+fn commit_to_stack(
+    workspace: &but_graph::projection::Workspace,
+) -> HashMap<gix::ObjectId, StackId> {
+    workspace
+        .stacks
+        .iter()
+        .filter_map(|stack| stack.id.map(|id| (stack, id)))
+        .flat_map(|(stack, id)| {
+            stack
+                .segments
+                .iter()
+                .flat_map(|segment| segment.commits.iter())
+                .map(move |commit| (commit.id, id))
+        })
+        .collect()
+}
@@ -61,6 +61,8 @@ pub(crate) mod function {
             );
         }
 
+        but_core::branch::ProtectedBranches::from_repo(repo)?
+            .ensure_unprotected(ref_name.as_bstr())?;
         let deleted_ref = if let Some(r) = repo.try_find_reference(ref_name)? {
             let safe = but_core::branch::SafeDelete::new(repo)?;
             let out = safe.delete_reference(&r)?;
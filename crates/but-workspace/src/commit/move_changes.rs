@@ -3,6 +3,7 @@
 pub(crate) mod function {
     use anyhow::{Result, bail};
     use but_core::{DiffSpec, RepositoryExt};
+    use but_error::ButError;
     use but_rebase::{
         commit::DateMode,
         graph_rebase::{Editor, LookupStep, Selector, Step, SuccessfulRebase},
@@ -66,7 +67,9 @@ pub(crate) mod function {
         let source_tree_id = {
             let source_commit = editor.find_commit(source_commit_id)?;
             if source_commit.is_conflicted() {
-                bail!("Source commit must not be conflicted")
+                bail!(ButError::CommitConflicted {
+                    commit_id: source_commit_id.to_string()
+                })
             }
             source_commit.tree
         };
@@ -98,7 +101,9 @@ pub(crate) mod function {
         let rebased_destination_commit = editor.find_commit(rebased_destination_id)?;
         let destination_tree_id = {
             if rebased_destination_commit.is_conflicted() {
-                bail!("Destination commit must not be conflicted")
+                bail!(ButError::CommitConflicted {
+                    commit_id: rebased_destination_id.to_string()
+                })
             }
             rebased_destination_commit.tree
         };
@@ -115,7 +120,7 @@ pub(crate) mod function {
             )?;
 
             if merge_result.has_unresolved_conflicts(conflict_kind) {
-                bail!("Failed to apply changes to destination commit - merge conflict");
+                bail!(ButError::MergeConflict);
             }
 
             merge_result.tree.write()?.detach()
@@ -3,6 +3,7 @@
 pub(crate) mod function {
     use anyhow::{Result, bail};
     use but_core::DiffSpec;
+    use but_error::ButError;
     use but_rebase::{
         commit::DateMode,
         graph_rebase::{Editor, Selector, Step, SuccessfulRebase},
@@ -33,7 +34,9 @@ pub(crate) mod function {
 
         let commit = editor.find_commit(commit_id)?;
         if commit.is_conflicted() {
-            bail!("Cannot uncommit changes from a conflicted commit")
+            bail!(ButError::CommitConflicted {
+                commit_id: commit_id.to_string()
+            })
         }
 
         let (tree_without_changes, dropped_diffs) = create_tree_without_diff(
@@ -0,0 +1,267 @@
+#![expect(clippy::indexing_slicing)]
+
+use std::collections::HashMap;
+
+use bstr::{BString, ByteSlice};
+use but_core::ref_metadata::StackId;
+use serde::Serialize;
+
+use crate::{
+    RefInfo,
+    ref_info::{Commit, LocalCommitRelation},
+};
+
+/// Options for the [`graph()`](crate::graph()) call.
+#[derive(Default, Debug, Clone)]
+pub struct Options {
+    /// Control how the workspace is obtained.
+    pub ref_info: crate::ref_info::Options,
+    /// If `true`, commits that are only reachable from the remote tracking branch of a stack segment are included
+    /// in a lane next to the lane of their stack.
+    pub upstream_commits: bool,
+}
+
+/// The commits of all stacks in the workspace along with their base, laid out in lanes.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceGraph {
+    /// All commits from the top of the workspace to its base, with children always listed before their parents.
+    pub commits: Vec<GraphCommit>,
+    /// The amount of lanes used by `commits`.
+    pub lane_count: usize,
+}
+
+/// A commit in a [`WorkspaceGraph`].
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphCommit {
+    /// The hash of the commit.
+    #[serde(with = "but_serde::object_id")]
+    pub id: gix::ObjectId,
+    /// The first line of the commit message, or an empty string for the base commit.
+    #[serde(with = "but_serde::bstring_lossy")]
+    pub title: BString,
+    /// The 0-based lane to draw the commit in. Base commits are always in lane 0, along with the first stack.
+    pub lane: usize,
+    /// The indices into [`WorkspaceGraph::commits`] of the parents of this commit that are part of the graph.
+    pub parents: Vec<usize>,
+    /// How the commit relates to the stack and its remote tracking branch.
+    pub kind: GraphCommitKind,
+    /// The id of the stack the commit belongs to, or `None` for base commits or stacks without id.
+    pub stack_id: Option<StackId>,
+    /// The full names of the branches whose tip is this commit.
+    #[serde(with = "but_serde::bstring_vec_lossy")]
+    pub ref_names: Vec<BString>,
+    /// Set on the tip of a branch whose local commits and the commits of its remote tracking branch diverged.
+    pub divergence: Option<Divergence>,
+}
+
+/// The kind of a [`GraphCommit`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum GraphCommitKind {
+    /// The commit was never pushed.
+    LocalOnly,
+    /// The commit is also present on the remote tracking branch.
+    LocalAndRemote,
+    /// The commit is considered integrated into the target branch.
+    Integrated,
+    /// The commit is only reachable from the remote tracking branch.
+    UpstreamOnly,
+    /// The commit the workspace is based on.
+    Base,
+}
+
+/// How a branch and its remote tracking branch diverged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Divergence {
+    /// The amount of commits that weren't pushed yet.
+    pub local_only: usize,
+    /// The amount of commits that are only on the remote tracking branch.
+    pub upstream_only: usize,
+}
+
+impl WorkspaceGraph {
+    /// Lay out the commits of all stacks in `info` along with their base, and include commits only reachable
+    /// from remote tracking branches if `upstream_commits` is `true`.
+    ///
+    /// Each stack gets its own lane, and its upstream commits go into the lane next to it.
+    pub fn from_ref_info(info: &RefInfo, upstream_commits: bool) -> Self {
+        let mut graph = Builder::default();
+        for stack in &info.stacks {
+            let lane = graph.lane_count;
+            let has_upstream_commits = upstream_commits
+                && stack
+                    .segments
+                    .iter()
+                    .any(|segment| !segment.commits_on_remote.is_empty());
+            graph.lane_count += if has_upstream_commits { 2 } else { 1 };
+
+            for segment in &stack.segments {
+                let local_only = segment
+                    .commits
+                    .iter()
+                    .filter(|commit| commit.relation == LocalCommitRelation::LocalOnly)
+                    .count();
+                let upstream_only = segment.commits_on_remote.len();
+                let divergence = (segment.remote_tracking_ref_name.is_some()
+                    && local_only > 0
+                    && upstream_only > 0)
+                    .then_some(Divergence {
+                        local_only,
+                        upstream_only,
+                    });
+
+                if has_upstream_commits {
+                    for (idx, commit) in segment.commits_on_remote.iter().enumerate() {
+                        graph.push(
+                            commit,
+                            lane + 1,
+                            GraphCommitKind::UpstreamOnly,
+                            stack.id,
+                            segment
+                                .remote_tracking_ref_name
+                                .as_ref()
+                                .filter(|_| idx == 0)
+                                .map(|name| name.as_bstr().to_owned()),
+                            None,
+                        );
+                    }
+                }
+                for (idx, commit) in segment.commits.iter().enumerate() {
+                    let kind = match commit.relation {
+                        LocalCommitRelation::LocalOnly => GraphCommitKind::LocalOnly,
+                        LocalCommitRelation::LocalAndRemote(_) => GraphCommitKind::LocalAndRemote,
+                        LocalCommitRelation::Integrated(_) => GraphCommitKind::Integrated,
+                    };
+                    let is_tip = idx == 0;
+                    graph.push(
+                        &commit.inner,
+                        lane,
+                        kind,
+                        stack.id,
+                        segment
+                            .ref_info
+                            .as_ref()
+                            .filter(|_| is_tip)
+                            .map(|ri| ri.ref_name.as_bstr().to_owned()),
+                        divergence.filter(|_| is_tip),
+                    );
+                }
+            }
+        }
+
+        let bases = info
+            .target_commit
+            .as_ref()
+            .map(|target| target.commit_id)
+            .into_iter()
+            .chain(info.stacks.iter().filter_map(|stack| stack.base));
+        for base in bases {
+            if graph.row_by_id.contains_key(&base) {
+                continue;
+            }
+            graph.row_by_id.insert(base, graph.commits.len());
+            graph.commits.push((
+                GraphCommit {
+                    id: base,
+                    title: BString::default(),
+                    lane: 0,
+                    parents: Vec::new(),
+                    kind: GraphCommitKind::Base,
+                    stack_id: None,
+                    ref_names: Vec::new(),
+                    divergence: None,
+                },
+                Vec::new(),
+            ));
+            graph.lane_count = graph.lane_count.max(1);
+        }
+        graph.finish()
+    }
+}
+
+/// Collects commits along with the ids of their parents, which are turned into edges once all commits are known.
+#[derive(Default)]
+struct Builder {
+    commits: Vec<(GraphCommit, Vec<gix::ObjectId>)>,
+    row_by_id: HashMap<gix::ObjectId, usize>,
+    lane_count: usize,
+}
+
+impl Builder {
+    /// Add `commit` unless it was already added by another stack that shares it.
+    fn push(
+        &mut self,
+        commit: &Commit,
+        lane: usize,
+        kind: GraphCommitKind,
+        stack_id: Option<StackId>,
+        ref_name: Option<BString>,
+        divergence: Option<Divergence>,
+    ) {
+        if let Some(&row) = self.row_by_id.get(&commit.id) {
+            self.commits[row].0.ref_names.extend(ref_name);
+            return;
+        }
+        self.row_by_id.insert(commit.id, self.commits.len());
+        self.commits.push((
+            GraphCommit {
+                id: commit.id,
+                title: commit
+                    .message
+                    .lines()
+                    .next()
+                    .unwrap_or_default()
+                    .as_bstr()
+                    .to_owned(),
+                lane,
+                parents: Vec::new(),
+                kind,
+                stack_id,
+                ref_names: ref_name.into_iter().collect(),
+                divergence,
+            },
+            commit.parent_ids.clone(),
+        ));
+    }
+
+    fn finish(self) -> WorkspaceGraph {
+        let Builder {
+            commits,
+            row_by_id,
+            lane_count,
+        } = self;
+        let commits = commits
+            .into_iter()
+            .map(|(mut commit, parent_ids)| {
+                commit.parents = parent_ids
+                    .iter()
+                    .filter_map(|id| row_by_id.get(id).copied())
+                    .collect();
+                commit
+            })
+            .collect();
+        WorkspaceGraph {
+            commits,
+            lane_count,
+        }
+    }
+}
+
+pub(crate) mod function {
+    use super::{Options, WorkspaceGraph};
+
+    /// Obtain the workspace at `HEAD` from `repo` and `meta` and lay out the commits of all of its stacks,
+    /// along with their base, in lanes so they can be drawn without further processing.
+    ///
+    /// See [`WorkspaceGraph::from_ref_info()`] for details.
+    pub fn graph(
+        repo: &gix::Repository,
+        meta: &impl but_core::RefMetadata,
+        opts: Options,
+    ) -> anyhow::Result<WorkspaceGraph> {
+        let info = crate::head_info(repo, meta, opts.ref_info)?;
+        Ok(WorkspaceGraph::from_ref_info(&info, opts.upstream_commits))
+    }
+}
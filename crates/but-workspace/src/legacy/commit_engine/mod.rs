@@ -258,6 +258,7 @@ pub fn create_commit_and_update_refs(
                                             previous_path: None,
                                             path: conflicting_rela_path.to_owned(),
                                             hunk_headers: vec![],
+                                            mode_flags: None,
                                         },
                                     )
                                 })
@@ -436,6 +437,27 @@ pub fn create_commit_and_update_refs_with_project(
         }
         Some(stack_id) => ReferenceFrame::infer(repo, &vb, InferenceMode::StackId(stack_id))?,
     };
+    ensure_rewritten_branches_are_unprotected(repo, &vb, &destination)?;
+    let stack = match maybe_stackid.or_else(|| destination.stack_segment().map(|s| s.stack_id)) {
+        Some(stack_id) => vb.branches.get(&stack_id),
+        // Amends only know the commit, so use the stack whose tip the frame was inferred from.
+        None => frame.branch_tip.and_then(|branch_tip| {
+            vb.branches
+                .values()
+                .filter(|stack| stack.in_workspace)
+                .find(|stack| {
+                    stack
+                        .heads
+                        .last()
+                        .and_then(|head| head.head_oid(repo).ok())
+                        .is_some_and(|tip| tip == branch_tip)
+                })
+        }),
+    };
+    let stack_repo = stack
+        .map(|stack| stack.repo_with_identity(repo))
+        .transpose()?;
+    let repo = stack_repo.as_deref().unwrap_or(repo);
     let out =
         create_commit_and_update_refs(repo, frame, &mut vb, destination, changes, context_lines)?;
 
@@ -443,6 +465,51 @@ pub fn create_commit_and_update_refs_with_project(
     Ok(out)
 }
 
+/// Fail if committing to or amending `destination` would rewrite a branch that is protected.
+/// These are the branch that is committed to, and all branches of the workspace that contain the commit
+/// which is amended or used as parent.
+fn ensure_rewritten_branches_are_unprotected(
+    repo: &gix::Repository,
+    vb: &VirtualBranchesState,
+    destination: &Destination,
+) -> anyhow::Result<()> {
+    let protected = but_core::branch::ProtectedBranches::from_repo(repo)?;
+    if protected.is_empty() {
+        return Ok(());
+    }
+    if let Some(segment) = destination.stack_segment() {
+        protected.ensure_unprotected(segment.segment_ref.as_bstr())?;
+    }
+    let commit_id = match destination {
+        Destination::NewCommit {
+            parent_commit_id, ..
+        } => *parent_commit_id,
+        Destination::AmendCommit { commit_id, .. } => Some(*commit_id),
+    };
+    let Some(commit_id) = commit_id else {
+        return Ok(());
+    };
+    for branch_name in vb
+        .branches
+        .values()
+        .filter(|stack| stack.in_workspace)
+        .flat_map(|stack| stack.heads(true))
+    {
+        let Some(mut reference) = repo.try_find_reference(branch_name.as_str())? else {
+            continue;
+        };
+        let tip = reference.peel_to_id()?.detach();
+        if tip == commit_id
+            || repo
+                .merge_base(commit_id, tip)
+                .is_ok_and(|base| base == commit_id)
+        {
+            protected.ensure_unprotected(branch_name.as_str().into())?;
+        }
+    }
+    Ok(())
+}
+
 impl Destination {
     pub(crate) fn stack_segment(&self) -> Option<&StackSegmentId> {
         match self {
@@ -1,5 +1,5 @@
 use bstr::BString;
-use gitbutler_stack::VirtualBranchesState;
+use gitbutler_stack::{RewrittenCommitsHandle, VirtualBranchesState};
 use gix::refs::transaction::PreviousValue;
 
 use super::StackSegmentId;
@@ -8,7 +8,7 @@ use crate::commit_engine::UpdatedReference;
 /// Rewrite all references as mapped by their target in `refs_by_commit_id` so that those
 /// pointing to `old` in `changed_commits` will then point to `new`.
 /// Do the same for the virtual refs in `state` place information about all performed updates
-/// in `updated_refs`.
+/// in `updated_refs`, record the rewrites, and let the notes about commits of its stacks follow their commits.
 /// `workspace_tip` is used, if present, to help build mappings from change-ids to commit-ids *if*
 /// no target branch is available.
 pub fn rewrite(
@@ -28,6 +28,12 @@ pub fn rewrite(
         .filter(|stack| stack.in_workspace)
         .collect();
     stacks_ordered.sort_by_key(|a| a.name());
+    RewrittenCommitsHandle::new(ctx.project_data_dir()).record(changed_commits.iter().copied());
+    for stack in &mut stacks_ordered {
+        stack
+            .stack_notes
+            .rewrite_commits(changed_commits.iter().copied());
+    }
     for (old, new) in changed_commits {
         let mut already_updated_refs = Vec::<BString>::new();
         for stack in &mut stacks_ordered {
@@ -0,0 +1,137 @@
+//! Validate that the workspace commit, the references of stack branches and the stored workspace metadata agree.
+use std::collections::BTreeSet;
+
+use anyhow::Result;
+use but_ctx::Context;
+use but_oxidize::OidExt;
+use gitbutler_stack::VirtualBranchesHandle;
+use serde::Serialize;
+
+const WORKSPACE_REFS: [&str; 2] = [
+    "refs/heads/gitbutler/workspace",
+    "refs/heads/gitbutler/integration",
+];
+
+/// A problem with the workspace that was found by [`fsck()`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase", tag = "type", content = "subject")]
+pub enum IntegrityIssue {
+    /// `HEAD` doesn't point to the workspace reference, so the workspace can't be checked.
+    #[serde(rename_all = "camelCase")]
+    NotInWorkspace {
+        /// The reference `HEAD` points to, or `None` if it's detached.
+        head: Option<String>,
+    },
+    /// The reference of the target branch doesn't exist.
+    #[serde(rename_all = "camelCase")]
+    MissingTargetRef {
+        /// The full name of the reference, like `refs/remotes/origin/main`.
+        ref_name: String,
+    },
+    /// The workspace reference doesn't point to a workspace commit created by GitButler,
+    /// usually because commits were made on top of it.
+    #[serde(rename_all = "camelCase")]
+    WorkspaceCommitMissing {
+        /// The commit the workspace reference points to instead.
+        #[serde(with = "but_serde::object_id")]
+        tip: gix::ObjectId,
+    },
+    /// The parents of the workspace commit aren't the heads of the stacks in the workspace.
+    #[serde(rename_all = "camelCase")]
+    WorkspaceCommitParentsMismatch {
+        /// The heads of the stacks in the workspace, or the target commit if there are none.
+        #[serde(with = "but_serde::object_id_vec")]
+        expected: Vec<gix::ObjectId>,
+        /// The parents of the workspace commit.
+        #[serde(with = "but_serde::object_id_vec")]
+        actual: Vec<gix::ObjectId>,
+    },
+    /// A branch of a stack in the workspace has no reference.
+    #[serde(rename_all = "camelCase")]
+    MissingBranchRef {
+        /// The id of the stack the branch belongs to.
+        stack_id: gitbutler_stack::StackId,
+        /// The short name of the branch, like `feature`.
+        branch_name: String,
+    },
+    /// Changes are assigned to a stack that isn't in the workspace.
+    #[serde(rename_all = "camelCase")]
+    DanglingAssignment {
+        /// The stack id the change is assigned to.
+        stack_id: String,
+        /// The path of the file the change is in.
+        path: String,
+    },
+}
+
+/// Check the workspace of the project in `ctx` for consistency, and return all issues that were found.
+///
+/// This validates the parents of the workspace commit, the references of all stack branches,
+/// the reference of the target branch and the stacks that changes are assigned to.
+/// Nothing is changed.
+pub fn fsck(ctx: &Context) -> Result<Vec<IntegrityIssue>> {
+    let repo = ctx.repo.get()?;
+    let head = repo.head()?;
+    let head_name = head.referent_name().map(|name| name.as_bstr().to_string());
+    if !head_name
+        .as_deref()
+        .is_some_and(|name| WORKSPACE_REFS.contains(&name))
+    {
+        return Ok(vec![IntegrityIssue::NotInWorkspace { head: head_name }]);
+    }
+
+    let mut issues = Vec::new();
+    let vb_state = VirtualBranchesHandle::new(ctx.project_data_dir());
+    let target = vb_state.get_default_target()?;
+    let target_ref = target.branch.to_string();
+    if repo.try_find_reference(target_ref.as_str())?.is_none() {
+        issues.push(IntegrityIssue::MissingTargetRef {
+            ref_name: target_ref,
+        });
+    }
+
+    let stacks = vb_state.list_stacks_in_workspace()?;
+    let mut expected_parents = BTreeSet::new();
+    for stack in &stacks {
+        let mut stack_head = None;
+        for branch in &stack.heads {
+            match repo.try_find_reference(branch.name.as_str())? {
+                Some(mut reference) => stack_head = Some(reference.peel_to_id()?.detach()),
+                None => issues.push(IntegrityIssue::MissingBranchRef {
+                    stack_id: stack.id,
+                    branch_name: branch.name.clone(),
+                }),
+            }
+        }
+        expected_parents.extend(stack_head);
+    }
+    if stacks.is_empty() {
+        expected_parents.insert(target.sha.to_gix());
+    }
+
+    let tip = repo.head_commit()?;
+    if !but_graph::projection::commit::is_managed_workspace_by_message(tip.message_raw()?) {
+        issues.push(IntegrityIssue::WorkspaceCommitMissing { tip: tip.id });
+    } else {
+        let actual_parents: BTreeSet<_> = tip.parent_ids().map(|id| id.detach()).collect();
+        if actual_parents != expected_parents {
+            issues.push(IntegrityIssue::WorkspaceCommitParentsMismatch {
+                expected: expected_parents.into_iter().collect(),
+                actual: actual_parents.into_iter().collect(),
+            });
+        }
+    }
+
+    let stack_ids: BTreeSet<_> = stacks.iter().map(|stack| stack.id.to_string()).collect();
+    for assignment in ctx.db.get()?.hunk_assignments().list_all()? {
+        if let Some(stack_id) = assignment.stack_id
+            && !stack_ids.contains(&stack_id)
+        {
+            issues.push(IntegrityIssue::DanglingAssignment {
+                stack_id,
+                path: assignment.path,
+            });
+        }
+    }
+    Ok(issues)
+}
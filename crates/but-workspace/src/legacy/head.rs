@@ -9,6 +9,8 @@ use gitbutler_stack::{Stack, VirtualBranchesHandle};
 use gix::merge::tree::TreatAsUnresolved;
 use tracing::instrument;
 
+use super::workspace_merge::{WorkspaceMergeOptions, WorkspaceMergeStrategy};
+
 const WORKSPACE_HEAD: &str = "Workspace Head";
 
 /// Merges the tree of the workspace with the tree of the worktree, agnostic to which branch HEAD is pointing to
@@ -42,7 +44,7 @@ pub fn merge_worktree_with_workspace<'a>(
 }
 
 /// Merge all currently stored stacks together into a new tree and return `(merged_tree, stacks, target_commit)` id accordingly.
-/// `gix_repo` should be optimised for merging.
+/// `gix_repo` should be optimised for merging, and its [`WorkspaceMergeOptions`] control how the stacks are merged.
 pub fn remerged_workspace_tree_v2(
     ctx: &Context,
     gix_repo: &gix::Repository,
@@ -58,7 +60,7 @@ pub fn remerged_workspace_tree_v2(
     let workspace_tree = repo.find_real_tree(&target_commit, Default::default())?;
     let mut workspace_tree_id = workspace_tree.id().to_gix();
 
-    let (merge_options_fail_fast, conflict_kind) = gix_repo.merge_options_fail_fast()?;
+    let merge_options = WorkspaceMergeOptions::from_repo(gix_repo)?;
     let merge_tree_id = repo.find_commit(target.sha)?.tree_id().to_gix();
     for stack in stacks.iter_mut() {
         let branch_head = repo.find_commit(stack.head_oid(ctx)?.to_git2())?;
@@ -66,22 +68,27 @@ pub fn remerged_workspace_tree_v2(
             .find_real_tree(&branch_head, Default::default())?
             .id()
             .to_gix();
-
-        let mut merge = gix_repo.merge_trees(
-            merge_tree_id,
+        let base_tree_id = match merge_options.strategy {
+            WorkspaceMergeStrategy::Sequential => merge_tree_id,
+            WorkspaceMergeStrategy::Octopus => gix_repo
+                .find_commit(stack.merge_base(ctx)?)?
+                .tree_id()?
+                .detach(),
+        };
+
+        match merge_options.merge_stack_tree(
+            gix_repo,
+            base_tree_id,
             workspace_tree_id,
             branch_tree_id,
-            gix_repo.default_merge_labels(),
-            merge_options_fail_fast.clone(),
-        )?;
-
-        if !merge.has_unresolved_conflicts(conflict_kind) {
-            workspace_tree_id = merge.tree.write()?.detach();
-        } else {
-            // This branch should have already been unapplied during the "update" command but for some reason that failed
-            tracing::warn!("Merge conflict between base and {:?}", stack.name());
-            stack.in_workspace = false;
-            vb_state.set_stack(stack.clone())?;
+        )? {
+            Some(merged_tree_id) => workspace_tree_id = merged_tree_id,
+            None => {
+                // This branch should have already been unapplied during the "update" command but for some reason that failed
+                tracing::warn!("Merge conflict between base and {:?}", stack.name());
+                stack.in_workspace = false;
+                vb_state.set_stack(stack.clone())?;
+            }
         }
     }
     Ok((workspace_tree_id.to_git2(), stacks, target_commit.id()))
@@ -6,6 +6,8 @@ use gitbutler_stack::VirtualBranchesHandle;
 use serde::{Deserialize, Serialize};
 
 pub mod commit_engine;
+pub mod fsck;
+pub use fsck::{IntegrityIssue, fsck};
 pub mod head;
 mod integrated;
 mod stacks;
@@ -14,6 +16,8 @@ pub use head::{
 };
 
 pub mod tree_manipulation;
+pub mod workspace_merge;
+pub use workspace_merge::{GeneratedFilesFavor, WorkspaceMergeOptions, WorkspaceMergeStrategy};
 // TODO: _v3 versions are specifically for the UI, so import them into `ui` instead.
 pub use stacks::{
     local_and_remote_commits, stack_branches, stack_details_v3, stack_heads_info, stacks_v3,
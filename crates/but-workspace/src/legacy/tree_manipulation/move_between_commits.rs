@@ -175,7 +175,7 @@ pub fn move_changes_between_commits(
         }
 
         let mut source_stack = source_stack;
-        source_stack.set_heads_from_rebase_output(ctx, result.references)?;
+        source_stack.set_heads_from_rebase_output(ctx, &result)?;
 
         Ok(MoveChangesResult {
             replaced_commits: output_commit_mapping.into_iter().collect::<Vec<_>>(),
@@ -209,8 +209,8 @@ pub fn move_changes_between_commits(
             ))
             .collect();
 
-        source_stack.set_heads_from_rebase_output(ctx, source_stack_result.references)?;
-        destination_stack.set_heads_from_rebase_output(ctx, result.references)?;
+        source_stack.set_heads_from_rebase_output(ctx, &source_stack_result)?;
+        destination_stack.set_heads_from_rebase_output(ctx, &result)?;
 
         Ok(MoveChangesResult {
             replaced_commits: output_commit_mapping,
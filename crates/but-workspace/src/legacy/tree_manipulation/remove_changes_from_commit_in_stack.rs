@@ -55,7 +55,7 @@ pub fn remove_changes_from_commit_in_stack(
         rebase_mapping_with_overrides(&result, [(source_commit_id, rewritten_source_commit)]);
 
     let mut source_stack = source_stack;
-    source_stack.set_heads_from_rebase_output(ctx, result.references)?;
+    source_stack.set_heads_from_rebase_output(ctx, &result)?;
 
     Ok(MoveChangesResult {
         replaced_commits: commit_mapping.into_iter().collect(),
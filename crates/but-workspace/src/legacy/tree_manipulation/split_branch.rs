@@ -225,7 +225,7 @@ pub fn split_into_dependent_branch(
     )?;
 
     source_stack.set_stack_head(&vb_state, &repository, new_head.id().to_git2())?;
-    source_stack.set_heads_from_rebase_output(ctx, source_result.clone().references)?;
+    source_stack.set_heads_from_rebase_output(ctx, &source_result)?;
 
     let move_changes_result = MoveChangesResult {
         replaced_commits: source_result
@@ -268,7 +268,7 @@ fn filter_file_changes_in_branch(
 
     let mut source_stack = source_stack;
 
-    source_stack.set_heads_from_rebase_output(ctx, source_result.clone().references)?;
+    source_stack.set_heads_from_rebase_output(ctx, &source_result)?;
 
     Ok(source_result)
 }
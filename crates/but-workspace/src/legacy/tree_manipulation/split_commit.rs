@@ -48,7 +48,7 @@ pub fn split_commit(
         .collect();
 
     let mut source_stack = source_stack;
-    source_stack.set_heads_from_rebase_output(ctx, result.references)?;
+    source_stack.set_heads_from_rebase_output(ctx, &result)?;
 
     let new_commits = commit_pieces.iter().map(|(id, _)| *id).collect::<Vec<_>>();
 
@@ -0,0 +1,235 @@
+//! How the heads of all stacks are merged into the tree of the workspace commit, configured per project
+//! in its local Git configuration.
+use anyhow::{Result, bail};
+use but_core::RepositoryExt as _;
+use gix::{bstr::ByteSlice as _, merge::tree::TreatAsUnresolved};
+use serde::{Deserialize, Serialize};
+
+const STRATEGY: &str = "gitbutler.workspaceMerge.strategy";
+const RENAME_LIMIT: &str = "gitbutler.workspaceMerge.renameLimit";
+const GENERATED_FILES: &str = "gitbutler.workspaceMerge.generatedFiles";
+const GENERATED_FILES_FAVOR: &str = "gitbutler.workspaceMerge.generatedFilesFavor";
+
+/// The way each stack is merged into the workspace.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum WorkspaceMergeStrategy {
+    /// Merge the stacks one after another, using the target commit as merge-base for each of them.
+    #[default]
+    Sequential,
+    /// Merge each stack using the commit it forked off the target as merge-base, like an octopus merge
+    /// computes a merge-base for each of its heads.
+    ///
+    /// Stacks that aren't rebased onto the latest target then don't conflict with target changes they don't know about.
+    Octopus,
+}
+
+/// The side whose version of a generated file is kept if merging it conflicts.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum GeneratedFilesFavor {
+    /// Keep the version of the workspace, as merged from the stacks before.
+    #[default]
+    Ours,
+    /// Keep the version of the stack that is merged.
+    Theirs,
+}
+
+/// Options to control how stacks are merged into the workspace commit.
+///
+/// The default options merge stacks like it was always done.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceMergeOptions {
+    /// The way each stack is merged.
+    pub strategy: WorkspaceMergeStrategy,
+    /// The amount of files to consider for rename detection, with `0` disabling it, or `None` to use
+    /// what's configured for `git merge`.
+    pub rename_limit: Option<usize>,
+    /// Patterns like `*.lock` or `gen/**` matching generated files, whose conflicts are resolved by
+    /// keeping the version of the [favored side](Self::generated_files_favor) instead of unapplying the stack.
+    ///
+    /// This only happens if all conflicts of a stack are in generated files.
+    pub generated_files: Vec<String>,
+    /// The side to keep when resolving conflicts in generated files.
+    pub generated_files_favor: GeneratedFilesFavor,
+}
+
+/// Lifecycle
+impl WorkspaceMergeOptions {
+    /// Read the options from the configuration of `repo`, using defaults for everything that isn't configured.
+    pub fn from_repo(repo: &gix::Repository) -> Result<Self> {
+        let config = repo.config_snapshot();
+        let strategy = match config.string(STRATEGY) {
+            None => WorkspaceMergeStrategy::default(),
+            Some(value) => match value.to_string().as_str() {
+                "sequential" => WorkspaceMergeStrategy::Sequential,
+                "octopus" => WorkspaceMergeStrategy::Octopus,
+                other => bail!("Invalid value '{other}' for {STRATEGY}"),
+            },
+        };
+        let generated_files_favor = match config.string(GENERATED_FILES_FAVOR) {
+            None => GeneratedFilesFavor::default(),
+            Some(value) => match value.to_string().as_str() {
+                "ours" => GeneratedFilesFavor::Ours,
+                "theirs" => GeneratedFilesFavor::Theirs,
+                other => bail!("Invalid value '{other}' for {GENERATED_FILES_FAVOR}"),
+            },
+        };
+        Ok(WorkspaceMergeOptions {
+            strategy,
+            rename_limit: config
+                .integer(RENAME_LIMIT)
+                .and_then(|limit| usize::try_from(limit).ok()),
+            generated_files: config
+                .string(GENERATED_FILES)
+                .map(|patterns| {
+                    patterns
+                        .to_string()
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|pattern| !pattern.is_empty())
+                        .map(ToOwned::to_owned)
+                        .collect()
+                })
+                .unwrap_or_default(),
+            generated_files_favor,
+        })
+    }
+
+    /// Write the options to the local `.git/config` file of `repo`, where a rename limit of `-1` means it's unset.
+    pub fn persist_to_local_config(&self, repo: &gix::Repository) -> Result<()> {
+        let mut config = repo.local_common_config_for_editing()?;
+        config.set_raw_value(
+            &STRATEGY,
+            match self.strategy {
+                WorkspaceMergeStrategy::Sequential => "sequential",
+                WorkspaceMergeStrategy::Octopus => "octopus",
+            },
+        )?;
+        config.set_raw_value(
+            &RENAME_LIMIT,
+            self.rename_limit
+                .map_or_else(|| "-1".to_owned(), |limit| limit.to_string())
+                .as_str(),
+        )?;
+        config.set_raw_value(&GENERATED_FILES, self.generated_files.join(",").as_str())?;
+        config.set_raw_value(
+            &GENERATED_FILES_FAVOR,
+            match self.generated_files_favor {
+                GeneratedFilesFavor::Ours => "ours",
+                GeneratedFilesFavor::Theirs => "theirs",
+            },
+        )?;
+        repo.write_local_common_config(&config)?;
+        Ok(())
+    }
+}
+
+/// Merging
+impl WorkspaceMergeOptions {
+    /// Merge `stack_tree` into `workspace_tree` using `base_tree` as merge-base, and return the written tree,
+    /// or `None` if the stack conflicts with the workspace.
+    ///
+    /// `repo` should be optimised for merging.
+    pub fn merge_stack_tree(
+        &self,
+        repo: &gix::Repository,
+        base_tree: gix::ObjectId,
+        workspace_tree: gix::ObjectId,
+        stack_tree: gix::ObjectId,
+    ) -> Result<Option<gix::ObjectId>> {
+        let (options, conflict_kind) = repo.merge_options_fail_fast()?;
+        let options = match self.rename_limit {
+            None => options,
+            Some(0) => options.with_rewrites(None),
+            Some(limit) => options.with_rewrites(Some(gix::diff::Rewrites {
+                limit,
+                ..Default::default()
+            })),
+        };
+        if self.generated_files.is_empty() {
+            let mut merge = repo.merge_trees(
+                base_tree,
+                workspace_tree,
+                stack_tree,
+                repo.default_merge_labels(),
+                options,
+            )?;
+            return Ok(if merge.has_unresolved_conflicts(conflict_kind) {
+                None
+            } else {
+                Some(merge.tree.write()?.detach())
+            });
+        }
+
+        // See all conflicts to learn if they are limited to generated files.
+        let mut merge = repo.merge_trees(
+            base_tree,
+            workspace_tree,
+            stack_tree,
+            repo.default_merge_labels(),
+            options.clone().with_fail_on_conflict(None),
+        )?;
+        if !merge.has_unresolved_conflicts(conflict_kind) {
+            return Ok(Some(merge.tree.write()?.detach()));
+        }
+        let only_generated_files_conflict = merge
+            .conflicts
+            .iter()
+            .filter(|conflict| conflict.is_unresolved(conflict_kind))
+            .all(|conflict| {
+                self.is_generated(conflict.ours.location())
+                    && self.is_generated(conflict.theirs.location())
+            });
+        if !only_generated_files_conflict {
+            return Ok(None);
+        }
+
+        let (file_favor, tree_favor) = match self.generated_files_favor {
+            GeneratedFilesFavor::Ours => (
+                gix::merge::tree::FileFavor::Ours,
+                gix::merge::tree::TreeFavor::Ours,
+            ),
+            GeneratedFilesFavor::Theirs => (
+                gix::merge::tree::FileFavor::Theirs,
+                gix::merge::tree::TreeFavor::Theirs,
+            ),
+        };
+        // Forced resolutions are expected now, only what Git couldn't resolve either is a conflict.
+        let conflict_kind = TreatAsUnresolved::git();
+        let mut merge = repo.merge_trees(
+            base_tree,
+            workspace_tree,
+            stack_tree,
+            repo.default_merge_labels(),
+            options
+                .with_fail_on_conflict(Some(conflict_kind))
+                .with_file_favor(Some(file_favor))
+                .with_tree_favor(Some(tree_favor)),
+        )?;
+        Ok(if merge.has_unresolved_conflicts(conflict_kind) {
+            None
+        } else {
+            Some(merge.tree.write()?.detach())
+        })
+    }
+
+    /// Return `true` if `path` matches one of the patterns of generated files.
+    ///
+    /// Like in `.gitattributes`, patterns without a slash match the file name in any directory.
+    pub fn is_generated(&self, path: &gix::bstr::BStr) -> bool {
+        let file_name = path.rsplit_str("/").next().unwrap_or_default().as_bstr();
+        self.generated_files.iter().any(|pattern| {
+            gix::glob::wildmatch(
+                pattern.as_str().into(),
+                if pattern.contains('/') {
+                    path
+                } else {
+                    file_name
+                },
+                gix::glob::wildmatch::Mode::NO_MATCH_SLASH_LITERAL,
+            )
+        })
+    }
+}
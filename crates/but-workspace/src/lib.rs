@@ -59,6 +59,22 @@ mod branch_details;
 pub use branch_details::{branch_details, local_commits_for_branch};
 use but_graph::{SegmentIndex, projection::TargetCommit};
 
+/// A commit graph of the workspace, laid out in lanes so it can be drawn directly.
+pub mod graph;
+pub use graph::{WorkspaceGraph, function::graph};
+
+/// Attribution of the lines of worktree files to commits, stacks and hunk assignments.
+pub mod blame;
+pub use blame::function::blame;
+
+/// Find commits in the stacks of the workspace by message, author, path or content.
+pub mod search;
+pub use search::function::search;
+
+/// Figures about the stacks of the workspace, like their size and signs of upcoming conflicts.
+pub mod stats;
+pub use stats::WorkspaceStats;
+
 /// Information about refs, as seen from within or outsie of a workspace.
 ///
 /// We always try to deduce a set of stacks that are currently applied to a workspace,
@@ -155,7 +171,10 @@ pub fn flatten_diff_specs(input: Vec<DiffSpec>) -> Vec<DiffSpec> {
         );
         output
             .entry(key)
-            .and_modify(|e| e.hunk_headers.extend(spec.hunk_headers.clone()))
+            .and_modify(|e| {
+                e.hunk_headers.extend(spec.hunk_headers.clone());
+                e.mode_flags = e.mode_flags.or(spec.mode_flags);
+            })
             .or_insert(spec);
     }
     output.into_values().collect()
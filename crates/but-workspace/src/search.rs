@@ -0,0 +1,190 @@
+use bstr::{BString, ByteSlice};
+use but_core::{TreeChange, ref_metadata::StackId};
+use serde::{Deserialize, Serialize};
+
+/// What to look for in the commits of the workspace. All criteria that are set must match.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Query {
+    /// Text to find in the commit message, case-insensitively.
+    pub message: Option<String>,
+    /// Text to find in the name or email of the author, case-insensitively.
+    pub author: Option<String>,
+    /// A glob that at least one changed path must match, like `src/**/*.rs`.
+    pub path: Option<String>,
+    /// A string whose number of occurrences must change in at least one file, like with `git log -S`.
+    pub content: Option<String>,
+    /// The amount of matching commits to skip, for paging.
+    #[serde(default)]
+    pub offset: usize,
+    /// The maximum amount of matching commits to return, or `None` to return all of them.
+    pub limit: Option<usize>,
+}
+
+/// The result of a [search](crate::search()).
+#[derive(Debug, Default, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchResult {
+    /// The matching commits, from the first stack to the last, and from the top of each stack to its base.
+    pub matches: Vec<SearchMatch>,
+    /// If `true`, there are more matches after the ones returned, which can be obtained with a larger [`Query::offset`].
+    pub has_more: bool,
+}
+
+/// A commit that matched a [`Query`].
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchMatch {
+    /// The id of the commit.
+    #[serde(with = "but_serde::object_id")]
+    pub commit_id: gix::ObjectId,
+    /// The id of the stack the commit belongs to, if it has one.
+    pub stack_id: Option<StackId>,
+    /// The name of the branch the commit belongs to, if it has one.
+    #[serde(with = "but_serde::bstring_opt_lossy")]
+    pub branch_name: Option<BString>,
+    /// The first line of the commit message.
+    #[serde(with = "but_serde::bstring_lossy")]
+    pub title: BString,
+    /// The name of the author.
+    #[serde(with = "but_serde::bstring_lossy")]
+    pub author: BString,
+    /// The changed paths that matched [`Query::path`] and [`Query::content`], or all changed paths if neither is set.
+    #[serde(with = "but_serde::bstring_vec_lossy")]
+    pub paths: Vec<BString>,
+}
+
+pub(crate) mod function {
+    use bstr::ByteSlice;
+
+    use super::{Query, SearchMatch, SearchResult, matches_path, occurrences_changed};
+
+    /// Find the commits in all stacks of `workspace` that match `query`, looking up objects in `repo`.
+    ///
+    /// Only commits that are part of a stack are searched, so the history of the target branch is never included.
+    /// Note that looking for [content](Query::content) is expensive, as each changed file has to be read.
+    pub fn search(
+        repo: &gix::Repository,
+        workspace: &but_graph::projection::Workspace,
+        query: &Query,
+    ) -> anyhow::Result<SearchResult> {
+        let message = query.message.as_deref().map(str::to_lowercase);
+        let author = query.author.as_deref().map(str::to_lowercase);
+        let limit = query.limit.unwrap_or(usize::MAX);
+
+        let mut out = SearchResult::default();
+        let mut skipped = 0;
+        for stack in &workspace.stacks {
+            for segment in &stack.segments {
+                for stack_commit in &segment.commits {
+                    let commit = repo.find_commit(stack_commit.id)?;
+                    let commit = commit.decode()?;
+                    if let Some(message) = &message
+                        && !commit
+                            .message
+                            .to_str_lossy()
+                            .to_lowercase()
+                            .contains(message.as_str())
+                    {
+                        continue;
+                    }
+                    let commit_author = commit.author()?;
+                    if let Some(author) = &author
+                        && ![commit_author.name, commit_author.email]
+                            .iter()
+                            .any(|field| field.to_str_lossy().to_lowercase().contains(author))
+                    {
+                        continue;
+                    }
+
+                    let parent_tree = stack_commit
+                        .parent_ids
+                        .first()
+                        .map(|id| -> anyhow::Result<_> {
+                            Ok(repo.find_commit(*id)?.tree_id()?.detach())
+                        })
+                        .transpose()?;
+                    let mut paths = Vec::new();
+                    for change in but_core::diff::tree_changes(repo, parent_tree, commit.tree())? {
+                        if query
+                            .path
+                            .as_deref()
+                            .is_some_and(|glob| !matches_path(glob, &change))
+                        {
+                            continue;
+                        }
+                        if let Some(content) = &query.content
+                            && !occurrences_changed(repo, &change, content.as_bytes())?
+                        {
+                            continue;
+                        }
+                        paths.push(change.path);
+                    }
+                    if paths.is_empty() && (query.path.is_some() || query.content.is_some()) {
+                        continue;
+                    }
+
+                    if skipped < query.offset {
+                        skipped += 1;
+                        continue;
+                    }
+                    if out.matches.len() == limit {
+                        out.has_more = true;
+                        return Ok(out);
+                    }
+                    out.matches.push(SearchMatch {
+                        commit_id: stack_commit.id,
+                        stack_id: stack.id,
+                        branch_name: segment.ref_name().map(|name| name.shorten().to_owned()),
+                        title: commit
+                            .message
+                            .lines()
+                            .next()
+                            .unwrap_or_default()
+                            .as_bstr()
+                            .to_owned(),
+                        author: commit_author.name.to_owned(),
+                        paths,
+                    });
+                }
+            }
+        }
+        Ok(out)
+    }
+}
+
+/// Return `true` if the path of `change`, or the path it was renamed from, matches `glob`.
+fn matches_path(glob: &str, change: &TreeChange) -> bool {
+    std::iter::once(change.path.as_bstr())
+        .chain(change.previous_path())
+        .any(|path| {
+            gix::glob::wildmatch(
+                glob.into(),
+                path,
+                gix::glob::wildmatch::Mode::NO_MATCH_SLASH_LITERAL,
+            )
+        })
+}
+
+/// Return `true` if the number of occurrences of `needle` differs between the previous and the current version of `change`.
+/// Non-blob entries, like submodules, never contain it.
+fn occurrences_changed(
+    repo: &gix::Repository,
+    change: &TreeChange,
+    needle: &[u8],
+) -> anyhow::Result<bool> {
+    use gix::object::tree::EntryKind;
+    let count = |state: Option<but_core::ChangeState>| -> anyhow::Result<usize> {
+        Ok(match state {
+            Some(state) if matches!(state.kind, EntryKind::Blob | EntryKind::BlobExecutable) => {
+                repo.find_blob(state.id)?.data.find_iter(needle).count()
+            }
+            _ => 0,
+        })
+    };
+    let previous = change
+        .status
+        .previous_state_and_path()
+        .map(|(state, _)| state);
+    Ok(count(previous)? != count(change.status.state())?)
+}
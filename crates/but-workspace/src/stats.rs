@@ -0,0 +1,172 @@
+use std::collections::HashMap;
+
+use bstr::BString;
+use but_core::ref_metadata::StackId;
+use serde::Serialize;
+
+use crate::{
+    RefInfo,
+    ref_info::{LocalCommitRelation, Segment},
+    ui::PushStatus,
+};
+
+/// Figures about the whole workspace, for dashboards and status output.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceStats {
+    /// Figures about each stack, in the order of the workspace.
+    pub stacks: Vec<StackStats>,
+    /// The amount of uncommitted hunks that aren't assigned to any stack.
+    pub unassigned_hunks: usize,
+    /// The amount of commits the target branch is ahead of the base of the workspace, if there is a target.
+    pub commits_behind_target: Option<usize>,
+}
+
+/// Figures about a single stack.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StackStats {
+    /// The id of the stack, if it's managed by GitButler.
+    pub stack_id: Option<StackId>,
+    /// The short name of the top-most branch of the stack, if it has one.
+    #[serde(with = "but_serde::bstring_opt_lossy")]
+    pub name: Option<BString>,
+    /// The amount of commits in all branches of the stack.
+    pub commits: usize,
+    /// The amount of commits that weren't pushed yet.
+    pub unpushed_commits: usize,
+    /// The amount of commits that are already integrated into the target branch.
+    pub integrated_commits: usize,
+    /// The amount of commits that are only on the remote tracking branches of the stack.
+    pub upstream_only_commits: usize,
+    /// The amount of lines added by all commits of the stack, compared to its base.
+    pub lines_added: u64,
+    /// The amount of lines removed by all commits of the stack, compared to its base.
+    pub lines_removed: u64,
+    /// The amount of seconds since the oldest commit that wasn't pushed yet was authored,
+    /// or `None` if all commits are pushed.
+    pub oldest_unpushed_commit_age_seconds: Option<i64>,
+    /// The amount of uncommitted hunks assigned to this stack.
+    pub assigned_hunks: usize,
+    /// Signs that the stack has or may soon run into conflicts.
+    pub conflict_risk: ConflictRisk,
+}
+
+/// Signs that a stack has or may soon run into conflicts.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConflictRisk {
+    /// The amount of commits that are conflicted already.
+    pub conflicted_commits: usize,
+    /// If `true`, at least one branch diverged from its remote tracking branch and needs a force-push.
+    pub diverged_from_remote: bool,
+    /// The files that are also changed by other stacks, which may conflict once either stack is integrated.
+    #[serde(with = "but_serde::bstring_vec_lossy")]
+    pub paths_changed_by_other_stacks: Vec<BString>,
+}
+
+impl WorkspaceStats {
+    /// Compute figures for all stacks in `info`, using `repo` to compare each stack to its base.
+    ///
+    /// `assignments` are the stacks of all uncommitted hunks, with `None` for unassigned ones,
+    /// and `now` is the current time in seconds since the Unix epoch, used to compute the age of commits.
+    pub fn from_ref_info(
+        repo: &gix::Repository,
+        info: &RefInfo,
+        assignments: impl IntoIterator<Item = Option<StackId>>,
+        now: i64,
+    ) -> anyhow::Result<Self> {
+        let mut assigned_hunks = HashMap::<Option<StackId>, usize>::new();
+        for stack_id in assignments {
+            *assigned_hunks.entry(stack_id).or_default() += 1;
+        }
+
+        let mut stacks = Vec::with_capacity(info.stacks.len());
+        let mut paths_by_stack = Vec::with_capacity(info.stacks.len());
+        for stack in &info.stacks {
+            let commits = || stack.segments.iter().flat_map(|segment| &segment.commits);
+            let unpushed =
+                || commits().filter(|commit| commit.relation == LocalCommitRelation::LocalOnly);
+
+            let (paths, stats) = match stack.tip() {
+                Some(tip) => {
+                    let base_tree = stack
+                        .base
+                        .map(|base| -> anyhow::Result<_> {
+                            Ok(repo.find_commit(base)?.tree_id()?.detach())
+                        })
+                        .transpose()?;
+                    let tip_tree = repo.find_commit(tip)?.tree_id()?.detach();
+                    let (changes, stats) =
+                        but_core::diff::tree_changes_with_line_stats(repo, base_tree, tip_tree)?;
+                    (
+                        changes.into_iter().map(|change| change.path).collect(),
+                        Some(stats),
+                    )
+                }
+                None => (Vec::new(), None),
+            };
+            paths_by_stack.push(paths);
+
+            stacks.push(StackStats {
+                stack_id: stack.id,
+                name: stack
+                    .ref_name()
+                    .map(|name| name.as_ref().shorten().to_owned()),
+                commits: commits().count(),
+                unpushed_commits: unpushed().count(),
+                integrated_commits: commits()
+                    .filter(|commit| matches!(commit.relation, LocalCommitRelation::Integrated(_)))
+                    .count(),
+                upstream_only_commits: stack
+                    .segments
+                    .iter()
+                    .map(|segment| segment.commits_on_remote.len())
+                    .sum(),
+                lines_added: stats.as_ref().map_or(0, |stats| stats.lines_added),
+                lines_removed: stats.as_ref().map_or(0, |stats| stats.lines_removed),
+                oldest_unpushed_commit_age_seconds: unpushed()
+                    .map(|commit| commit.inner.author.time.seconds)
+                    .min()
+                    .map(|time| now - time),
+                assigned_hunks: stack
+                    .id
+                    .and_then(|id| assigned_hunks.get(&Some(id)).copied())
+                    .unwrap_or_default(),
+                conflict_risk: ConflictRisk {
+                    conflicted_commits: commits()
+                        .filter(|commit| commit.inner.has_conflicts)
+                        .count(),
+                    diverged_from_remote: stack.segments.iter().any(needs_force_push),
+                    paths_changed_by_other_stacks: Vec::new(),
+                },
+            });
+        }
+
+        for (idx, stack) in stacks.iter_mut().enumerate() {
+            let Some(paths) = paths_by_stack.get(idx) else {
+                continue;
+            };
+            stack.conflict_risk.paths_changed_by_other_stacks = paths
+                .iter()
+                .filter(|path| {
+                    paths_by_stack
+                        .iter()
+                        .enumerate()
+                        .any(|(other_idx, other)| other_idx != idx && other.contains(*path))
+                })
+                .cloned()
+                .collect();
+        }
+
+        Ok(WorkspaceStats {
+            stacks,
+            unassigned_hunks: assigned_hunks.get(&None).copied().unwrap_or_default(),
+            commits_behind_target: info.target_ref.as_ref().map(|t| t.commits_ahead),
+        })
+    }
+}
+
+fn needs_force_push(segment: &Segment) -> bool {
+    segment.push_status == PushStatus::UnpushedCommitsRequiringForce
+}
@@ -0,0 +1,11 @@
+#!/usr/bin/env bash
+
+### Description
+# A file gets a line added in the worktree and is made executable.
+set -eu -o pipefail
+
+git init
+seq 1 3 >file
+git add . && git commit -m "init"
+
+seq 1 4 >file && chmod +x file
@@ -0,0 +1,14 @@
+#!/usr/bin/env bash
+
+### Description
+# A file is renamed in the worktree so that `.gitattributes` now marks it as text with CRLF line endings,
+# and lines are added to it.
+set -eu -o pipefail
+
+git init
+echo "*.txt text eol=crlf" >.gitattributes
+seq 5 8 >file
+git add . && git commit -m "init"
+
+mv file file.txt
+printf '5\r\n6\r\n7\r\n8\r\n9\r\n10\r\n' >file.txt
@@ -0,0 +1,17 @@
+#!/usr/bin/env bash
+
+source "${BASH_SOURCE[0]%/*}/shared.sh"
+
+git init
+echo "a stack that changes a file of the target, with an uncommitted change on top" >.git/description
+
+printf 'base 1\nbase 2\n' >file
+git add . && git commit -m "init"
+setup_target_to_match_main
+
+git checkout -b A
+  printf 'base 1\nA\nbase 2\n' >file && git commit -am "add line in A"
+
+create_workspace_commit_once A
+
+printf 'base 1\nA\nbase 2\nuncommitted\n' >file
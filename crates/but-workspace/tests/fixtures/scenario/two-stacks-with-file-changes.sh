@@ -0,0 +1,18 @@
+#!/usr/bin/env bash
+
+source "${BASH_SOURCE[0]%/*}/shared.sh"
+
+git init
+echo "two stacks whose commits change files in different directories" >.git/description
+
+commit-file base
+setup_target_to_match_main
+
+git checkout -b A
+  mkdir src
+  echo "fn a() {}" >src/a.rs && git add . && git commit -m "add a.rs"
+  echo "fn a() { todo!() }" >src/a.rs && git commit -am "implement a"
+git checkout -b B main
+  echo "notes" >README.md && git add . && git commit -m "add readme"
+
+create_workspace_commit_once B A
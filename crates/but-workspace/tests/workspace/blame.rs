@@ -0,0 +1,59 @@
+use but_graph::init::Options;
+use but_workspace::blame::{LineBlame, UncommittedHunk};
+
+use crate::ref_info::with_workspace_commit::utils::{
+    StackState, add_stack, named_read_only_in_memory_scenario,
+};
+
+#[test]
+fn committed_and_uncommitted_lines() -> anyhow::Result<()> {
+    let (repo, mut meta) = named_read_only_in_memory_scenario("stack-with-uncommitted-line", "")?;
+    let stack_id = add_stack(&mut meta, 1, "A", StackState::InWorkspace);
+    let graph = but_graph::Graph::from_head(&repo, &*meta, Options::limited())?;
+    let ws = graph.into_workspace()?;
+
+    let base = repo.rev_parse_single("main")?.detach();
+    let in_a = repo.rev_parse_single("A")?.detach();
+    let lines = but_workspace::blame(
+        &repo,
+        &ws,
+        "file".into(),
+        &[UncommittedHunk {
+            new_lines: 4..5,
+            stack_id: Some(stack_id),
+        }],
+    )?;
+    assert_eq!(
+        lines,
+        [
+            LineBlame {
+                commit_id: Some(base),
+                stack_id: None,
+            },
+            LineBlame {
+                commit_id: Some(in_a),
+                stack_id: Some(stack_id),
+            },
+            LineBlame {
+                commit_id: Some(base),
+                stack_id: None,
+            },
+            LineBlame {
+                commit_id: None,
+                stack_id: Some(stack_id),
+            },
+        ],
+        "committed lines belong to the stack of their commit, uncommitted ones to the stack of their hunk"
+    );
+
+    let lines = but_workspace::blame(&repo, &ws, "file".into(), &[])?;
+    assert_eq!(
+        lines.last(),
+        Some(&LineBlame {
+            commit_id: None,
+            stack_id: None,
+        }),
+        "unassigned lines don't belong to any stack"
+    );
+    Ok(())
+}
@@ -12,6 +12,7 @@ fn diff_spec_for_file(path: &str) -> DiffSpec {
         previous_path: None,
         path: path.into(),
         hunk_headers: vec![],
+        mode_flags: None,
     }
 }
 
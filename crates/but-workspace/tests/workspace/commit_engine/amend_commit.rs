@@ -174,6 +174,7 @@ fn new_file_and_deletion_onto_merge_commit_with_hunks() -> anyhow::Result<()> {
                 previous_path: None,
                 path: "file".into(),
                 hunk_headers: vec![],
+                mode_flags: None,
             },
             DiffSpec {
                 previous_path: None,
@@ -184,6 +185,7 @@ fn new_file_and_deletion_onto_merge_commit_with_hunks() -> anyhow::Result<()> {
                     new_start: 1,
                     new_lines: 10,
                 }],
+                mode_flags: None,
             },
         ],
         CONTEXT_LINES,
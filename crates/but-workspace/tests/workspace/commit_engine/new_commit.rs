@@ -105,6 +105,7 @@ fn from_unborn_head_with_selection() -> anyhow::Result<()> {
             previous_path: None,
             path: "not-yet-tracked".into(),
             hunk_headers: vec![hunk_header("-1,0", "+1,1")],
+            mode_flags: None,
         }],
         CONTEXT_LINES,
     )?;
@@ -129,6 +130,7 @@ fn from_unborn_head_with_selection() -> anyhow::Result<()> {
             path: "also-untracked".into(),
             // Take 3 lines in the middle, instead of 10
             hunk_headers: vec![hunk_header("-0,0", "+4,3")],
+            mode_flags: None,
         }],
         CONTEXT_LINES,
     )?;
@@ -157,6 +159,7 @@ fn from_unborn_head_with_selection() -> anyhow::Result<()> {
                 hunk_header("-0,0", "+5,1"),
                 hunk_header("-0,0", "+6,1"),
             ],
+            mode_flags: None,
         }],
         CONTEXT_LINES,
     )?;
@@ -278,6 +281,56 @@ fn unborn_with_added_submodules() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test]
+fn modified_submodule_pointer() -> anyhow::Result<()> {
+    let (repo, _tmp) = writable_scenario("modified-submodule-and-embedded-repo");
+    let head_commit = repo.rev_parse_single("HEAD")?;
+    let submodule_path = "submodule";
+    let previous_pointer = head_commit
+        .object()?
+        .peel_to_tree()?
+        .lookup_entry_by_path(submodule_path)?
+        .expect("the submodule is tracked")
+        .object_id();
+    let submodule_head = gix::open_opts(
+        repo.workdir().expect("non-bare").join(submodule_path),
+        gix::open::Options::isolated(),
+    )?
+    .head_id()?
+    .detach();
+    assert_ne!(
+        previous_pointer, submodule_head,
+        "the fixture checked out another commit in the submodule"
+    );
+
+    let outcome = commit_whole_files_and_all_hunks_from_workspace(
+        &repo,
+        Destination::NewCommit {
+            parent_commit_id: Some(head_commit.into()),
+            message: "submodules are committed as the commit their HEAD points to".into(),
+            stack_segment: None,
+        },
+    )?;
+    assert_eq!(outcome.rejected_specs, vec![]);
+
+    let entry = outcome
+        .new_commit
+        .expect("the submodule change was committed")
+        .attach(&repo)
+        .object()?
+        .peel_to_commit()?
+        .tree()?
+        .lookup_entry_by_path(submodule_path)?
+        .expect("the submodule is still tracked");
+    assert_eq!(entry.mode().kind(), gix::object::tree::EntryKind::Commit);
+    assert_eq!(
+        entry.object_id(),
+        submodule_head,
+        "the pointer changed, while uncommitted changes in the submodule don't matter"
+    );
+    Ok(())
+}
+
 #[test]
 fn deletions() -> anyhow::Result<()> {
     let repo = read_only_in_memory_scenario("delete-all-file-types")?;
@@ -965,6 +1018,7 @@ fn commit_whole_file_to_conflicting_position() -> anyhow::Result<()> {
                     previous_path: None,
                     path: "file",
                     hunk_headers: [],
+                    mode_flags: None,
                 },
             ),
         ]
@@ -1022,6 +1076,7 @@ fn commit_whole_file_to_conflicting_position_one_unconflicting_file_remains() ->
                     previous_path: None,
                     path: "file",
                     hunk_headers: [],
+                    mode_flags: None,
                 },
             ),
         ]
@@ -1142,6 +1197,76 @@ fn unborn_untracked_worktree_filters_are_applied_to_whole_files() -> anyhow::Res
     Ok(())
 }
 
+#[test]
+fn worktree_filters_of_the_new_path_are_applied_to_hunks_of_renamed_files() -> anyhow::Result<()> {
+    let repo = read_only_in_memory_scenario("renamed-into-text-attributes")?;
+    let head_commit_id = repo.rev_parse_single("HEAD")?;
+    let outcome = commit_engine::create_commit(
+        &repo,
+        Destination::NewCommit {
+            parent_commit_id: Some(head_commit_id.into()),
+            message: "the renamed file is normalized like `git add` would".into(),
+            stack_segment: None,
+        },
+        to_change_specs_all_hunks_with_context_lines(
+            &repo,
+            but_core::diff::worktree_changes(&repo)?,
+            UI_CONTEXT_LINES,
+        )?,
+        UI_CONTEXT_LINES,
+    )?;
+    assert_eq!(outcome.rejected_specs, [], "everything was assigned");
+
+    // What's in Git is unix style newlines, as `file.txt` is marked as text.
+    insta::assert_snapshot!(visualize_tree(&repo, &outcome)?, @r#"
+    6eed2d2
+    ├── .gitattributes:100644:c61b241 "*.txt text eol=crlf\n"
+    └── file.txt:100644:c5c4315 "5\n6\n7\n8\n9\n10\n"
+    "#);
+    Ok(())
+}
+
+#[test]
+fn executable_bit_is_committed_with_hunks() -> anyhow::Result<()> {
+    let repo = read_only_in_memory_scenario("executable-bit-added-with-content")?;
+    let head_commit_id = repo.rev_parse_single("HEAD")?;
+    let destination = Destination::NewCommit {
+        parent_commit_id: Some(head_commit_id.into()),
+        message: "the mode change travels with the hunks".into(),
+        stack_segment: None,
+    };
+    // This is what the UI sends, without knowing about the mode change.
+    let outcome = commit_engine::create_commit(
+        &repo,
+        destination.clone(),
+        vec![diff_spec(None, "file", Some(hunk_header("-3,0", "+4,1")))],
+        CONTEXT_LINES,
+    )?;
+    assert_eq!(outcome.rejected_specs, [], "everything was assigned");
+    insta::assert_snapshot!(visualize_tree(&repo, &outcome)?, @r#"
+    db36029
+    └── file:100755:94ebaf9 "1\n2\n3\n4\n"
+    "#);
+
+    let specs = to_change_specs_all_hunks_with_context_lines(
+        &repo,
+        but_core::diff::worktree_changes(&repo)?,
+        CONTEXT_LINES,
+    )?;
+    assert_eq!(
+        specs[0].mode_flags,
+        Some(but_core::ModeFlags::ExecutableBitAdded),
+        "the transition is part of the spec if it comes from a tree-change"
+    );
+    let outcome = commit_engine::create_commit(&repo, destination, specs, CONTEXT_LINES)?;
+    assert_eq!(outcome.rejected_specs, [], "everything was assigned");
+    insta::assert_snapshot!(visualize_tree(&repo, &outcome)?, @r#"
+    db36029
+    └── file:100755:94ebaf9 "1\n2\n3\n4\n"
+    "#);
+    Ok(())
+}
+
 #[test]
 fn signatures_are_redone() -> anyhow::Result<()> {
     let (repo, _tmp) = writable_scenario_with_ssh_key("two-signed-commits-with-line-offset");
@@ -1223,6 +1348,7 @@ fn validate_no_change_on_noop() -> anyhow::Result<()> {
                     previous_path: None,
                     path: "file",
                     hunk_headers: [],
+                    mode_flags: None,
                 },
             ),
         ],
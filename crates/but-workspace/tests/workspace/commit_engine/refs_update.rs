@@ -371,6 +371,7 @@ fn first_partial_commit_to_tip_from_unborn_head() -> anyhow::Result<()> {
             path: "not-yet-tracked".into(),
             // Add the first two lines
             hunk_headers: vec![hunk_header("-0,0", "+1,2")],
+            mode_flags: None,
         }],
         CONTEXT_LINES,
     )?;
@@ -426,6 +427,7 @@ fn first_partial_commit_to_tip_from_unborn_head() -> anyhow::Result<()> {
             path: "not-yet-tracked".into(),
             // Add the last line
             hunk_headers: vec![hunk_header("-0,0", "+4,1")],
+            mode_flags: None,
         }],
         CONTEXT_LINES,
     )?;
@@ -493,18 +495,21 @@ fn first_partial_commit_to_tip_from_unborn_head() -> anyhow::Result<()> {
                 path: "not-yet-tracked".into(),
                 // Add the remainder
                 hunk_headers: vec![hunk_header("-3,0", "+3,1")],
+                mode_flags: None,
             },
             DiffSpec {
                 previous_path: None,
                 path: "other-untracked-non-racy".into(),
                 // Add the first line
                 hunk_headers: vec![hunk_header("-0,0", "+1,1")],
+                mode_flags: None,
             },
             DiffSpec {
                 previous_path: None,
                 path: "other-untracked-added-as-whole".into(),
                 // Add the only line
                 hunk_headers: vec![hunk_header("-0,0", "+1,1")],
+                mode_flags: None,
             },
         ],
         CONTEXT_LINES,
@@ -922,6 +927,7 @@ fn insert_commits_into_workspace_with_conflict() -> anyhow::Result<()> {
                 previous_path: None,
                 path: "file",
                 hunk_headers: [],
+                mode_flags: None,
             },
         ),
     ]
@@ -1045,6 +1051,7 @@ fn workspace_commit_with_merge_conflict() -> anyhow::Result<()> {
                             HunkHeader("-1,10", "+1,0"),
                             HunkHeader("-12,0", "+2,10"),
                         ],
+                        mode_flags: None,
                     },
                 ),
             ],
@@ -1400,6 +1407,7 @@ fn commit_on_top_of_branch_in_workspace() -> anyhow::Result<()> {
             path: "file".into(),
             // Remove 5 lines from the end.
             hunk_headers: vec![hunk_header("-22,5", "+0,0")],
+            mode_flags: None,
         }],
         CONTEXT_LINES,
     )?;
@@ -1,5 +1,5 @@
 use bstr::BString;
-use but_core::{DiffSpec, HunkHeader};
+use but_core::{DiffSpec, HunkHeader, ModeFlags};
 
 use super::*;
 
@@ -21,6 +21,7 @@ fn single() {
             new_start: 1,
             new_lines: 3,
         }],
+        mode_flags: None,
     };
     let input = vec![spec.clone()];
     let result = flatten_diff_specs(input);
@@ -39,6 +40,7 @@ fn different_files() {
             new_start: 1,
             new_lines: 3,
         }],
+        mode_flags: None,
     };
     let spec2 = DiffSpec {
         path: BString::from("file2.txt"),
@@ -49,6 +51,7 @@ fn different_files() {
             new_start: 5,
             new_lines: 2,
         }],
+        mode_flags: None,
     };
     let input = vec![spec1.clone(), spec2.clone()];
     let result = flatten_diff_specs(input);
@@ -76,11 +79,13 @@ fn same_file_merge_hunks() {
         path: BString::from("file.txt"),
         previous_path: None,
         hunk_headers: vec![hunk1],
+        mode_flags: None,
     };
     let spec2 = DiffSpec {
         path: BString::from("file.txt"),
         previous_path: None,
         hunk_headers: vec![hunk2],
+        mode_flags: None,
     };
 
     let input = vec![spec1, spec2];
@@ -105,6 +110,7 @@ fn with_previous_path() {
             new_start: 1,
             new_lines: 3,
         }],
+        mode_flags: None,
     };
     let spec2 = DiffSpec {
         path: BString::from("new_file.txt"),
@@ -115,6 +121,7 @@ fn with_previous_path() {
             new_start: 5,
             new_lines: 2,
         }],
+        mode_flags: None,
     };
 
     let input = vec![spec1.clone(), spec2.clone()];
@@ -145,11 +152,13 @@ fn same_previous_path() {
         path: BString::from("new_file.txt"),
         previous_path: Some(BString::from("old_file.txt")),
         hunk_headers: vec![hunk1],
+        mode_flags: None,
     };
     let spec2 = DiffSpec {
         path: BString::from("new_file.txt"),
         previous_path: Some(BString::from("old_file.txt")),
         hunk_headers: vec![hunk2],
+        mode_flags: None,
     };
 
     let input = vec![spec1, spec2];
@@ -165,3 +174,38 @@ fn same_previous_path() {
     assert!(result.first().unwrap().hunk_headers.contains(&hunk1));
     assert!(result.first().unwrap().hunk_headers.contains(&hunk2));
 }
+
+#[test]
+fn same_file_keeps_mode_flags() {
+    let spec1 = DiffSpec {
+        path: BString::from("file.txt"),
+        previous_path: None,
+        hunk_headers: vec![HunkHeader {
+            old_start: 1,
+            old_lines: 0,
+            new_start: 1,
+            new_lines: 1,
+        }],
+        mode_flags: None,
+    };
+    let spec2 = DiffSpec {
+        hunk_headers: vec![HunkHeader {
+            old_start: 10,
+            old_lines: 0,
+            new_start: 11,
+            new_lines: 1,
+        }],
+        mode_flags: Some(ModeFlags::ExecutableBitAdded),
+        ..spec1.clone()
+    };
+
+    let result = flatten_diff_specs(vec![spec1, spec2]);
+
+    assert_eq!(result.len(), 1);
+    assert_eq!(result.first().unwrap().hunk_headers.len(), 2);
+    assert_eq!(
+        result.first().unwrap().mode_flags,
+        Some(ModeFlags::ExecutableBitAdded),
+        "the mode transition of any spec is kept"
+    );
+}
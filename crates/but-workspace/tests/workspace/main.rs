@@ -1,12 +1,16 @@
 use but_workspace::flatten_diff_specs;
 
+mod blame;
 mod branch;
 mod branch_details;
 mod commit;
 mod commit_engine;
 mod flatten_diff_specs;
 mod ref_info;
+mod search;
+mod stats;
 mod tree_manipulation;
 mod ui;
+mod workspace_merge;
 
 mod utils;
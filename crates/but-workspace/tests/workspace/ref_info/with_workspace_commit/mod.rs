@@ -253,6 +253,47 @@ fn remote_ahead_fast_forwardable() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test]
+fn remote_ahead_fast_forwardable_as_graph() -> anyhow::Result<()> {
+    let (repo, mut meta) = read_only_in_memory_scenario("remote-advanced-ff")?;
+    add_stack(&mut meta, 1, "A", StackState::InWorkspace);
+    let info = head_info(&repo, &meta, standard_options())?;
+
+    let graph = but_workspace::WorkspaceGraph::from_ref_info(&info, true);
+    assert_eq!(graph.lane_count, 2, "the stack and its upstream commits");
+    let rows: Vec<_> = graph
+        .commits
+        .iter()
+        .map(|c| {
+            format!(
+                "{lane} {id} {kind:?} {parents:?} {refs:?}",
+                lane = c.lane,
+                id = c.id.to_hex_with_len(7),
+                kind = c.kind,
+                parents = c.parents,
+                refs = c.ref_names,
+            )
+        })
+        .collect();
+    insta::assert_snapshot!(rows.join("\n"), @r#"
+    1 89cc2d3 UpstreamOnly [1] ["refs/remotes/origin/A"]
+    0 d79bba9 LocalAndRemote [2] ["refs/heads/A"]
+    0 c166d42 Base [] []
+    "#);
+
+    let graph = but_workspace::WorkspaceGraph::from_ref_info(&info, false);
+    assert_eq!(graph.lane_count, 1);
+    assert_eq!(
+        graph.commits.iter().map(|c| c.kind).collect::<Vec<_>>(),
+        [
+            but_workspace::graph::GraphCommitKind::LocalAndRemote,
+            but_workspace::graph::GraphCommitKind::Base
+        ],
+        "upstream commits are only included on request"
+    );
+    Ok(())
+}
+
 #[test]
 fn two_dependent_branches_rebased_with_remotes() -> anyhow::Result<()> {
     let (repo, mut meta) =
@@ -0,0 +1,118 @@
+use but_graph::init::Options;
+use but_workspace::search::{Query, SearchResult};
+
+use crate::ref_info::with_workspace_commit::utils::{
+    StackState, add_stack, named_read_only_in_memory_scenario,
+};
+
+fn titles(result: &SearchResult) -> Vec<String> {
+    let mut titles: Vec<_> = result.matches.iter().map(|m| m.title.to_string()).collect();
+    titles.sort();
+    titles
+}
+
+#[test]
+fn by_message_path_and_content() -> anyhow::Result<()> {
+    let (repo, mut meta) = named_read_only_in_memory_scenario("two-stacks-with-file-changes", "")?;
+    let stack_a = add_stack(&mut meta, 1, "A", StackState::InWorkspace);
+    add_stack(&mut meta, 2, "B", StackState::InWorkspace);
+    let graph = but_graph::Graph::from_head(&repo, &*meta, Options::limited())?;
+    let ws = graph.into_workspace()?;
+
+    let result = but_workspace::search(&repo, &ws, &Query::default())?;
+    assert_eq!(
+        titles(&result),
+        ["add a.rs", "add readme", "implement a"],
+        "without criteria, all commits of all stacks match, but not the ones of the target"
+    );
+    assert!(!result.has_more);
+
+    let result = but_workspace::search(
+        &repo,
+        &ws,
+        &Query {
+            message: Some("IMPLEMENT".into()),
+            ..Default::default()
+        },
+    )?;
+    assert_eq!(
+        titles(&result),
+        ["implement a"],
+        "messages match case-insensitively"
+    );
+    assert_eq!(result.matches[0].stack_id, Some(stack_a));
+    assert_eq!(
+        result.matches[0]
+            .branch_name
+            .as_ref()
+            .map(|n| n.to_string()),
+        Some("A".into())
+    );
+
+    let result = but_workspace::search(
+        &repo,
+        &ws,
+        &Query {
+            path: Some("src/*.rs".into()),
+            ..Default::default()
+        },
+    )?;
+    assert_eq!(titles(&result), ["add a.rs", "implement a"]);
+    assert_eq!(result.matches[0].paths, ["src/a.rs"]);
+
+    let result = but_workspace::search(
+        &repo,
+        &ws,
+        &Query {
+            content: Some("todo!".into()),
+            ..Default::default()
+        },
+    )?;
+    assert_eq!(
+        titles(&result),
+        ["implement a"],
+        "only the commit that changes the number of occurrences matches"
+    );
+    Ok(())
+}
+
+#[test]
+fn paging() -> anyhow::Result<()> {
+    let (repo, mut meta) = named_read_only_in_memory_scenario("two-stacks-with-file-changes", "")?;
+    add_stack(&mut meta, 1, "A", StackState::InWorkspace);
+    add_stack(&mut meta, 2, "B", StackState::InWorkspace);
+    let graph = but_graph::Graph::from_head(&repo, &*meta, Options::limited())?;
+    let ws = graph.into_workspace()?;
+
+    let all = but_workspace::search(&repo, &ws, &Query::default())?;
+    let first_page = but_workspace::search(
+        &repo,
+        &ws,
+        &Query {
+            limit: Some(2),
+            ..Default::default()
+        },
+    )?;
+    assert!(first_page.has_more);
+    let second_page = but_workspace::search(
+        &repo,
+        &ws,
+        &Query {
+            offset: 2,
+            limit: Some(2),
+            ..Default::default()
+        },
+    )?;
+    assert!(!second_page.has_more, "the last page is complete");
+    assert_eq!(
+        first_page
+            .matches
+            .iter()
+            .chain(&second_page.matches)
+            .map(|m| m.commit_id)
+            .collect::<Vec<_>>(),
+        all.matches.iter().map(|m| m.commit_id).collect::<Vec<_>>(),
+        "pages follow each other"
+    );
+    Ok(())
+}
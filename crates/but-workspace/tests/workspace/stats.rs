@@ -0,0 +1,66 @@
+use but_workspace::{WorkspaceStats, ref_info};
+
+use crate::ref_info::with_workspace_commit::{
+    head_info,
+    utils::{StackState, add_stack, named_read_only_in_memory_scenario},
+};
+
+#[test]
+fn two_stacks_with_unassigned_hunks() -> anyhow::Result<()> {
+    let (repo, mut meta) = named_read_only_in_memory_scenario("two-stacks-with-file-changes", "")?;
+    let stack_a = add_stack(&mut meta, 1, "A", StackState::InWorkspace);
+    let stack_b = add_stack(&mut meta, 2, "B", StackState::InWorkspace);
+    let info = head_info(
+        &repo,
+        &meta,
+        ref_info::Options {
+            expensive_commit_info: true,
+            traversal: Default::default(),
+        },
+    )?;
+
+    let first_commit_time = repo
+        .rev_parse_single("A~1")?
+        .object()?
+        .into_commit()
+        .time()?
+        .seconds;
+    let stats = WorkspaceStats::from_ref_info(
+        &repo,
+        &info,
+        [None, Some(stack_a), None],
+        first_commit_time + 100,
+    )?;
+    assert_eq!(stats.unassigned_hunks, 2);
+
+    let a = stats
+        .stacks
+        .iter()
+        .find(|s| s.stack_id == Some(stack_a))
+        .expect("stack A is applied");
+    assert_eq!(a.name.as_ref().map(|n| n.to_string()), Some("A".into()));
+    assert_eq!(
+        (a.commits, a.unpushed_commits, a.integrated_commits),
+        (2, 2, 0)
+    );
+    assert_eq!(
+        (a.lines_added, a.lines_removed),
+        (1, 0),
+        "churn is measured against the base, so the intermediate version doesn't count"
+    );
+    assert_eq!(a.oldest_unpushed_commit_age_seconds, Some(100));
+    assert_eq!(a.assigned_hunks, 1);
+    assert_eq!(a.conflict_risk.conflicted_commits, 0);
+    assert!(
+        a.conflict_risk.paths_changed_by_other_stacks.is_empty(),
+        "the stacks change different files"
+    );
+
+    let b = stats
+        .stacks
+        .iter()
+        .find(|s| s.stack_id == Some(stack_b))
+        .expect("stack B is applied");
+    assert_eq!((b.commits, b.lines_added, b.assigned_hunks), (1, 1, 0));
+    Ok(())
+}
@@ -1034,6 +1034,7 @@ mod util {
             previous_path: None,
             path: name.into(),
             hunk_headers: vec![],
+            mode_flags: None,
         }
     }
 
@@ -1042,6 +1043,7 @@ mod util {
             previous_path: Some(previous.into()),
             path: name.into(),
             hunk_headers: vec![],
+            mode_flags: None,
         }
     }
 
@@ -24,6 +24,7 @@ fn dropped_hunks() -> anyhow::Result<()> {
         previous_path: None,
         path: change.path,
         hunk_headers: hunks_to_discard,
+        mode_flags: None,
     };
     let dropped = discard_workspace_changes(&repo, Some(discard_spec), CONTEXT_LINES)?;
     // It drops just the two missing ones hunks
@@ -36,6 +37,7 @@ fn dropped_hunks() -> anyhow::Result<()> {
                 HunkHeader("-1,1", "+1,0"),
                 HunkHeader("-10,1", "+13,3"),
             ],
+            mode_flags: None,
         },
     ]
     "#);
@@ -66,6 +68,7 @@ fn non_modifications_trigger_error() -> anyhow::Result<()> {
                 previous_path: None,
                 path: file_name.into(),
                 hunk_headers: vec![hunk],
+                mode_flags: None,
             }),
             CONTEXT_LINES,
         )
@@ -143,6 +146,7 @@ fn from_end() -> anyhow::Result<()> {
             previous_path: None,
             path: change.path.clone(),
             hunk_headers: vec![last_hunk.into()],
+            mode_flags: None,
         };
         let dropped = discard_workspace_changes(&repo, Some(discard_spec), CONTEXT_LINES)?;
         assert_eq!(
@@ -215,6 +219,7 @@ fn from_beginning() -> anyhow::Result<()> {
             previous_path: None,
             path: change.path.clone(),
             hunk_headers: vec![first_hun_hunk.into()],
+            mode_flags: None,
         };
         let dropped = discard_workspace_changes(&repo, Some(discard_spec), CONTEXT_LINES)?;
         assert_eq!(
@@ -293,6 +298,7 @@ fn from_selections() -> anyhow::Result<()> {
             // '-18\n'
             hunk_header("-14,1", "+17,0"),
         ],
+        mode_flags: None,
     };
     let dropped = discard_workspace_changes(&repo, Some(discard_spec), CONTEXT_LINES)?;
     assert_eq!(dropped, [], "all sub-hunks could be associated");
@@ -378,6 +384,7 @@ fn from_selections_with_context() -> anyhow::Result<()> {
             // Get 17,18 back
             hunk_header("-13,2", "+1,16"),
         ],
+        mode_flags: None,
     };
     let dropped = discard_workspace_changes(&repo, Some(discard_spec.clone()), ui_context_lines)?;
     assert_eq!(dropped.len(), 0, "all sub-hunks could be associated");
@@ -464,6 +471,7 @@ fn hunk_removal_of_additions_single_line() -> anyhow::Result<()> {
             hunk_header("-1,0", "+5,1"),
             // TODO: figure out a header specification
         ],
+        mode_flags: None,
     };
     let dropped = discard_workspace_changes(&repo, Some(discard_spec), CONTEXT_LINES)?;
     assert_eq!(dropped.len(), 0, "all sub-hunks could be associated");
@@ -517,6 +525,7 @@ fn hunk_removal_of_removal_single_line() -> anyhow::Result<()> {
             // Internally we turn this into [("-1,5", "+1,0"), ("-6,4", "+1,0")].
             hunk_header("-5,1", "+1,0"),
         ],
+        mode_flags: None,
     };
     let dropped = discard_workspace_changes(&repo, Some(discard_spec), CONTEXT_LINES)?;
     assert_eq!(dropped.len(), 0, "all sub-hunks could be associated");
@@ -574,6 +583,7 @@ fn hunk_removal_of_modifications() -> anyhow::Result<()> {
             // This will yield '[("-1,4", "+1,4"), ("-6,5", "+6,5")]' internally.
             hunk_header("-1,10", "+5,1"),
         ],
+        mode_flags: None,
     };
 
     let dropped = discard_workspace_changes(&repo, Some(discard_spec), CONTEXT_LINES)?;
@@ -24,6 +24,7 @@ pub fn to_change_specs_whole_file(changes: but_core::WorktreeChanges) -> Vec<Dif
             previous_path: change.previous_path().map(ToOwned::to_owned),
             path: change.path,
             hunk_headers: Vec::new(),
+            mode_flags: None,
         })
         .collect();
     assert!(
@@ -42,6 +43,7 @@ pub fn diff_spec(
         previous_path: previous_path.map(Into::into),
         path: path.into(),
         hunk_headers: hunks.into_iter().collect(),
+        mode_flags: None,
     }
 }
 
@@ -73,15 +75,16 @@ pub fn to_change_specs_all_hunks_with_context_lines(
                         previous_path: change.previous_path().map(ToOwned::to_owned),
                         path: change.path,
                         hunk_headers: hunks.into_iter().map(Into::into).collect(),
+                        mode_flags: change.status.flags(),
                     },
-                    Some(_) => unreachable!("tests won't be binary or too large"),
-                    None => {
-                        // Assume it's a submodule or something without content, don't do hunks then.
+                    Some(but_core::UnifiedPatch::Submodule { .. }) | None => {
+                        // It's a submodule or something without content, don't do hunks then.
                         DiffSpec {
                             path: change.path,
                             ..Default::default()
                         }
                     }
+                    Some(_) => unreachable!("tests won't be binary or too large"),
                 }
             }
         };
@@ -0,0 +1,62 @@
+use but_testsupport::read_only_in_memory_scenario;
+use but_workspace::legacy::{GeneratedFilesFavor, WorkspaceMergeOptions};
+
+fn tree(repo: &gix::Repository, rev: &str) -> anyhow::Result<gix::ObjectId> {
+    Ok(repo.rev_parse_single(rev)?.object()?.peel_to_tree()?.id)
+}
+
+#[test]
+fn conflicts_in_generated_files_favor_one_side() -> anyhow::Result<()> {
+    let repo = read_only_in_memory_scenario("merge-with-two-branches-conflict")?;
+    let (base, ours, theirs) = (tree(&repo, "A~1")?, tree(&repo, "A")?, tree(&repo, "B")?);
+
+    assert_eq!(
+        WorkspaceMergeOptions::default().merge_stack_tree(&repo, base, ours, theirs)?,
+        None,
+        "by default, conflicting stacks can't be merged"
+    );
+
+    let options = WorkspaceMergeOptions {
+        generated_files: vec!["fi*".into()],
+        ..Default::default()
+    };
+    assert_eq!(
+        options.merge_stack_tree(&repo, base, ours, theirs)?,
+        Some(ours),
+        "the conflicting file is generated, so the version of the workspace is kept"
+    );
+
+    let options = WorkspaceMergeOptions {
+        generated_files_favor: GeneratedFilesFavor::Theirs,
+        ..options
+    };
+    assert_eq!(
+        options.merge_stack_tree(&repo, base, ours, theirs)?,
+        Some(theirs),
+        "or the version of the stack"
+    );
+
+    let options = WorkspaceMergeOptions {
+        generated_files: vec!["*.lock".into()],
+        ..options
+    };
+    assert_eq!(
+        options.merge_stack_tree(&repo, base, ours, theirs)?,
+        None,
+        "conflicts in other files still prevent the merge"
+    );
+    Ok(())
+}
+
+#[test]
+fn generated_file_patterns_without_slash_match_in_all_directories() {
+    let options = WorkspaceMergeOptions {
+        generated_files: vec!["*.lock".into(), "gen/**".into()],
+        ..Default::default()
+    };
+    assert!(options.is_generated("Cargo.lock".into()));
+    assert!(options.is_generated("crates/app/Cargo.lock".into()));
+    assert!(options.is_generated("gen/api/client.rs".into()));
+    assert!(!options.is_generated("src/gen/client.rs".into()));
+    assert!(!options.is_generated("Cargo.toml".into()));
+}
@@ -74,7 +74,7 @@ pub fn worktree_integrate(
 
     status
         .stack
-        .set_heads_from_rebase_output(ctx, status.rebase_output.references)?;
+        .set_heads_from_rebase_output(ctx, &status.rebase_output)?;
     let after = WorkspaceState::create(ctx, perm.read_permission())?;
     update_uncommitted_changes(ctx, before, after, perm)?;
     let vb_state = VirtualBranchesHandle::new(ctx.project_data_dir());
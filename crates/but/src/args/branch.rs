@@ -30,6 +30,8 @@ pub enum Subcommands {
     /// If the branch has unpushed commits, you will be prompted for confirmation
     /// unless the `--force` flag is used.
     ///
+    /// It fails if the branch isn't in the workspace.
+    ///
     #[cfg(feature = "legacy")]
     #[clap(short_flag = 'd')]
     Delete {
@@ -136,6 +138,8 @@ pub enum Subcommands {
     /// directory and you can re-apply it later when needed. You will then
     /// see the branch as unapplied in `but branch list`.
     ///
+    /// Unapplying a branch that isn't applied succeeds without doing anything.
+    ///
     #[cfg(feature = "legacy")]
     Unapply {
         /// Name of the branch to unapply
@@ -19,8 +19,16 @@ pub struct Platform {
     #[clap(short = 'c', long = "create")]
     pub create: bool,
     /// Only commit staged files, not unstaged files
-    #[clap(short = 'o', long = "only")]
+    #[clap(short = 'o', long = "only", visible_alias = "all-assigned")]
     pub only: bool,
+    /// Amend the commit at the top of the branch instead of creating a new one.
+    ///
+    /// Its message is kept unless a new one is given or `--edit` is used.
+    #[clap(long = "amend", conflicts_with = "create")]
+    pub amend: bool,
+    /// Edit the commit message in an editor, starting with the given or amended message
+    #[clap(short = 'e', long = "edit")]
+    pub edit: bool,
     /// Bypass pre-commit hooks
     #[clap(short = 'n', long = "no-hooks", alias = "no-verify")]
     pub no_hooks: bool,
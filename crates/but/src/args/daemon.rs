@@ -0,0 +1,19 @@
+//! Command-line arguments of `butd`, the daemon that serves the workspace API over a local socket.
+use std::path::PathBuf;
+
+#[derive(Debug, clap::Parser)]
+#[clap(
+    name = "butd",
+    about = "Serve the GitButler workspace API over a local socket, using JSON-RPC",
+    version = option_env!("VERSION").unwrap_or("dev")
+)]
+pub struct Args {
+    /// Enable tracing for debug and performance information printed to stderr.
+    #[clap(short = 't', long, action = clap::ArgAction::Count, env = "BUT_TRACE")]
+    pub trace: u8,
+    /// The Unix socket to listen on, or the named pipe on Windows.
+    ///
+    /// Defaults to `butd.sock` in the application data directory, or `//./pipe/gitbutler-butd` on Windows.
+    #[clap(long, value_name = "PATH")]
+    pub socket: Option<PathBuf>,
+}
@@ -9,6 +9,9 @@ pub mod pr {
         /// Create a new pull request for a branch.
         /// If no branch is specified, you will be prompted to select one.
         /// If there is only one branch without a PR, you will be asked to confirm.
+        ///
+        /// With `--format shell`, only the URLs of the PRs are printed, one per line.
+        #[clap(visible_alias = "create")]
         New {
             /// The branch to create a PR for.
             #[clap(value_name = "BRANCH")]
@@ -17,6 +17,9 @@ pub enum CommandName {
     Reword,
     OplogList,
     OplogSnapshot,
+    OplogDiff,
+    OplogShow,
+    OplogPrune,
     Restore,
     Undo,
     Gui,
@@ -50,6 +53,7 @@ pub enum CommandName {
     Resolve,
     Update,
     Merge,
+    Fsck,
     #[default]
     Unknown,
 }
@@ -36,6 +36,18 @@ pub struct Args {
     /// Whether to use JSON output format.
     #[clap(long, short = 'j', global = true)]
     pub json: bool,
+    /// Wrap JSON output into an object that states the version of its schema, which must be the given one.
+    ///
+    /// The output is `{"schemaVersion": <version>, "command": "<subcommand>", "data": <output>}`, where the
+    /// schema of `data` only changes compatibly while the version stays the same. Fails if the version isn't supported.
+    #[clap(
+        long,
+        global = true,
+        env = "BUT_JSON_SCHEMA_VERSION",
+        value_name = "VERSION",
+        hide_env_values = true
+    )]
+    pub json_schema_version: Option<u32>,
     /// Source entity for rub operation (when no subcommand is specified).
     /// If no target is specified, this is treated as a path to open on the GUI.
     #[clap(value_name = "SOURCE")]
@@ -101,6 +113,12 @@ pub enum Subcommands {
         /// Show detailed list of upstream commits that haven't been integrated yet.
         #[clap(short = 'u', long = "upstream", default_value_t = false)]
         upstream: bool,
+        /// Summarize each stack: how far its branches are ahead of or behind their remote tracking branches,
+        /// their conflicted commits and the amount of staged hunks, along with the amount of unstaged hunks.
+        ///
+        /// With `--json`, the figures the app uses for its workspace overview are included as `stats`.
+        #[clap(short = 's', long = "stats", default_value_t = false)]
+        stats: bool,
         /// Disable hints about available commands at the end of output.
         #[clap(long = "no-hint", default_value_t = false)]
         no_hint: bool,
@@ -265,6 +283,32 @@ pub enum Subcommands {
     #[cfg(feature = "legacy")]
     Teardown,
 
+    /// Check the workspace for consistency, and optionally repair it.
+    ///
+    /// This validates the workspace commit, the references of all stack branches,
+    /// the target branch and the stacks that changes are assigned to.
+    ///
+    /// ## Examples
+    ///
+    /// Check the workspace:
+    ///
+    /// ```text
+    /// but fsck
+    /// ```
+    ///
+    /// Fix the issues that can be fixed automatically, after taking a snapshot:
+    ///
+    /// ```text
+    /// but fsck --repair
+    /// ```
+    ///
+    #[cfg(feature = "legacy")]
+    Fsck {
+        /// Fix the issues that were found, as far as possible.
+        #[clap(long)]
+        repair: bool,
+    },
+
     /// Updates all applied branches to be up to date with the target branch.
     ///
     /// This fetches the latest changes from the remote and rebases all applied branches
@@ -359,6 +403,9 @@ pub enum Subcommands {
     /// branch will be included in the commit. If you only want to commit the changes
     /// that are already staged to that branch, you can use the `--only` flag.
     ///
+    /// Use `--amend` to add the changes to the commit at the top of the branch instead,
+    /// and `--edit` to edit the message in an editor before committing.
+    ///
     /// It will not commit changes staged to other branches.
     ///
     /// Use `but commit empty --before <target>` or `but commit empty --after <target>`
@@ -381,6 +428,8 @@ pub enum Subcommands {
     /// - `but push bu` - push the branch with CLI ID "bu"
     /// - `but push feature-branch` - push the branch named "feature-branch"
     ///
+    /// With `--format shell`, the pushed remote refs are printed one per line.
+    ///
     #[cfg(feature = "legacy")]
     Push(push::Command),
 
@@ -422,21 +471,40 @@ pub enum Subcommands {
     #[cfg(feature = "legacy")]
     Oplog(oplog::Platform),
 
+    /// Collect local diagnostics to attach to bug reports.
+    ///
+    /// ## Examples
+    ///
+    /// Bundle the operation trace of this project:
+    ///
+    /// ```text
+    /// but support bundle
+    /// ```
+    ///
+    #[cfg(feature = "legacy")]
+    Support(support::Platform),
+
     /// Restore to a specific oplog snapshot.
     ///
     /// This command allows you to revert the repository to a previous state
     /// captured in an oplog snapshot.
     ///
     /// You need to provide the SHA of the oplog entry you want to restore to,
-    /// which you can find by running `but oplog`.
+    /// which you can find by running `but oplog`, or the label of a named snapshot.
+    ///
+    /// Use `--dry-run` to preview which stacks, branches, commits and files
+    /// the restore would change without touching the workspace.
     ///
     #[cfg(feature = "legacy")]
     Restore {
-        /// Oplog SHA to restore to
+        /// Oplog SHA or snapshot label to restore to
         oplog_sha: String,
         /// Skip confirmation prompt
         #[clap(short = 'f', long = "force")]
         force: bool,
+        /// Only show what restoring would change, without restoring
+        #[clap(long = "dry-run", conflicts_with = "force")]
+        dry_run: bool,
     },
 
     /// Undo the last operation by reverting to the previous snapshot.
@@ -444,8 +512,14 @@ pub enum Subcommands {
     /// This is a shorthand for restoring to the last oplog entry before the
     /// current one. It allows you to quickly undo the most recent operation.
     ///
+    /// Pass a number to undo that many operations at once, like `but undo 3`.
+    ///
     #[cfg(feature = "legacy")]
-    Undo,
+    Undo {
+        /// The number of operations to undo
+        #[clap(default_value_t = 1)]
+        steps: usize,
+    },
     /// Amends changes into the appropriate commits where they belong.
     ///
     /// The semantic for finding "the appropriate commit" is as follows:
@@ -584,11 +658,19 @@ pub enum Subcommands {
     },
 
     /// UTILITY: Generate shell completion scripts for the specified or inferred shell.
+    ///
+    /// For bash, zsh and fish, branch names and aliases are completed as well.
     #[clap(hide = true)]
     Completions {
         /// The shell to generate completions for, or the one extracted from the `SHELL` environment variable.
         #[clap(value_enum)]
         shell: Option<clap_complete::Shell>,
+        /// INTERNAL: Print the candidates completing the command line passed after `--`, as called by the completion scripts.
+        #[clap(long, hide = true, conflicts_with = "shell")]
+        dynamic: bool,
+        /// The command line to complete, including the program name and the word being completed.
+        #[clap(last = true, hide = true)]
+        words: Vec<String>,
     },
 
     /// Manage GitButler CLI and app updates.
@@ -806,6 +888,23 @@ pub enum Subcommands {
         branch: String,
     },
 
+    /// Assigns unassigned hunks to stacks, one hunk at a time.
+    ///
+    /// For each hunk, press the number of a stack to assign it there, `s` to
+    /// decide for each of its lines, `n` to leave it unassigned, or `q` to stop.
+    ///
+    /// ## Examples
+    ///
+    /// ```text
+    /// but assign -i
+    /// ```
+    #[cfg(feature = "legacy")]
+    Assign {
+        /// Walk the unassigned hunks and ask where each of them should go
+        #[clap(short = 'i', long = "interactive", required = true)]
+        interactive: bool,
+    },
+
     /// Unstages a file or hunk from a branch.
     ///
     /// Wrapper for `but rub <file-or-hunk> zz`.
@@ -832,6 +931,8 @@ pub enum Subcommands {
 pub mod alias;
 pub mod commit;
 pub mod config;
+#[cfg(feature = "legacy")]
+pub mod daemon;
 pub mod update;
 
 pub mod actions {
@@ -867,6 +968,8 @@ pub mod oplog;
 pub mod push;
 #[cfg(feature = "legacy")]
 pub mod resolve;
+#[cfg(feature = "legacy")]
+pub mod support;
 
 pub mod claude {
     #[derive(Debug, clap::Parser)]
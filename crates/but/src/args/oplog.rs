@@ -24,6 +24,9 @@ pub enum Subcommands {
         /// Show only on-demand snapshot entries
         #[clap(long, short = 's')]
         snapshot: bool,
+        /// Show only named snapshots, see `but oplog snapshot --label`
+        #[clap(long, conflicts_with_all = ["since", "snapshot"])]
+        named: bool,
     },
 
     /// Create an on-demand snapshot with optional message.
@@ -31,12 +34,74 @@ pub enum Subcommands {
     /// This allows you to create a named snapshot of the current state, which
     /// can be helpful to always be able to return to a known good state.
     ///
-    /// You can provide an optional message to describe the snapshot.
+    /// You can provide an optional message to describe the snapshot, or name it
+    /// with a label like "before risky rebase" to restore it with `but restore <label>`.
+    /// Named snapshots are never pruned.
     ///
     #[cfg(feature = "legacy")]
     Snapshot {
         /// Message to include with the snapshot
         #[clap(short = 'm', long = "message")]
         message: Option<String>,
+        /// Name of the snapshot to find and restore it by
+        #[clap(short = 'l', long = "label", conflicts_with = "message")]
+        label: Option<String>,
     },
+
+    /// Show how stacks, branches, commits and hunk assignments changed since a point in time.
+    ///
+    /// Compares the workspace as recorded by an oplog snapshot with the current state,
+    /// or with another snapshot if `until` is given.
+    ///
+    /// Points in time are either oplog SHAs, or dates like `2 days ago` or `2025-01-31`,
+    /// in which case the latest snapshot taken before that date is used.
+    ///
+    #[cfg(feature = "legacy")]
+    Diff {
+        /// The oplog SHA or date to compare from
+        since: String,
+        /// The oplog SHA or date to compare to, instead of the current state
+        until: Option<String>,
+    },
+
+    /// Show the details of a snapshot and the files that changed in it.
+    ///
+    /// The snapshot is identified by its oplog SHA, as shown by `but oplog`,
+    /// or by the label of a named snapshot.
+    ///
+    #[cfg(feature = "legacy")]
+    Show {
+        /// Oplog SHA or snapshot label to show
+        snapshot: String,
+    },
+
+    /// Restore to a specific oplog snapshot, like `but restore`.
+    ///
+    #[cfg(feature = "legacy")]
+    Restore {
+        /// Oplog SHA or snapshot label to restore to
+        snapshot: String,
+        /// Skip confirmation prompt
+        #[clap(short = 'f', long = "force")]
+        force: bool,
+        /// Only show what restoring would change, without restoring
+        #[clap(long = "dry-run", conflicts_with = "force")]
+        dry_run: bool,
+    },
+
+    /// Remove the oldest snapshots that exceed the retention limits.
+    ///
+    /// Snapshots that are older than `oplog.maxAgeDays`, beyond the most recent
+    /// `oplog.maxSnapshots`, or beyond `oplog.maxSizeMb` of added data are removed,
+    /// as configured in the GitButler settings. The most recent snapshot and all named
+    /// snapshots are always kept.
+    ///
+    /// Note that the SHAs of the remaining snapshots change, so the removed ones can be
+    /// deleted from the repository.
+    ///
+    /// Snapshots are also pruned automatically after they are created, at most once per hour,
+    /// but that only hides them to keep the SHAs of the remaining snapshots.
+    ///
+    #[cfg(feature = "legacy")]
+    Prune,
 }
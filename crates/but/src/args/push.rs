@@ -5,6 +5,10 @@ pub struct Command {
     /// Force push even if it's not fast-forward
     #[clap(long, short = 'f', default_value_t = true)]
     pub with_force: bool,
+    /// Force push, but only overwrite remote commits that were seen before, like `git push --force-with-lease`.
+    /// This enables force push protection even if it's disabled for the project.
+    #[clap(long, conflicts_with = "skip_force_push_protection")]
+    pub force_with_lease: bool,
     /// Skip force push protection checks
     #[clap(long, short = 's')]
     pub skip_force_push_protection: bool,
@@ -20,7 +24,7 @@ pub struct Command {
     /// Add hashtag(s) to change (Gerrit). Can be used multiple times.
     #[clap(long, short = 'a', alias = "tag", value_name = "TAG", hide = true)]
     pub hashtag: Vec<String>,
-    /// Add custom topic to change (Gerrit). At most one topic can be set.
+    /// Add custom topic to change (Gerrit). At most one topic can be set, and it defaults to the name of the stack.
     #[clap(
         long,
         short = 't',
@@ -40,6 +44,10 @@ pub struct Command {
     /// Mark change as private (Gerrit)
     #[clap(long, short = 'p', hide = true)]
     pub private: bool,
+    /// Transmit the given string to the remote, like `git push --push-option`. Can be used multiple times.
+    /// Defaults to the values of `push.pushOption`.
+    #[clap(long, short = 'o', value_name = "OPTION")]
+    pub push_option: Vec<String>,
     /// Show what would be pushed without actually pushing
     #[clap(long, short = 'd')]
     pub dry_run: bool,
@@ -0,0 +1,23 @@
+use std::path::PathBuf;
+
+#[derive(Debug, clap::Parser)]
+pub struct Platform {
+    #[clap(subcommand)]
+    pub cmd: Subcommands,
+}
+
+#[derive(Debug, clap::Subcommand)]
+pub enum Subcommands {
+    /// Collect the operation trace of this project into a directory to attach to a bug report.
+    ///
+    /// Besides the trace, the directory contains the version of `but`, the platform it runs on
+    /// and the trace settings. Nothing is sent anywhere, and the trace records only the shape
+    /// of the inputs of operations, never their values.
+    ///
+    /// The trace is only written if `operationTrace.enabled` is set in the settings.
+    Bundle {
+        /// The directory to write the bundle to, `gitbutler-support-<timestamp>` in the current directory by default.
+        #[clap(short = 'o', long = "output", value_name = "DIR")]
+        output: Option<PathBuf>,
+    },
+}
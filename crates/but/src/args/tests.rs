@@ -7,6 +7,22 @@ fn clap() {
 }
 
 mod push {
+    use clap::Parser;
+
+    use crate::args::push::Command as Args;
+
+    #[test]
+    fn force_with_lease_cannot_skip_force_push_protection() {
+        let args = Args::try_parse_from(["push", "--force-with-lease", "A"]).unwrap();
+        assert!(args.force_with_lease);
+        assert!(!args.skip_force_push_protection);
+
+        let err =
+            Args::try_parse_from(["push", "--force-with-lease", "--skip-force-push-protection"])
+                .unwrap_err();
+        assert_eq!(err.kind(), clap::error::ErrorKind::ArgumentConflict);
+    }
+
     #[cfg(feature = "legacy")]
     mod get_gerrit_flags {
         use crate::{args::push::Command as Args, command::legacy::push::get_gerrit_flags};
@@ -16,6 +32,7 @@ mod push {
             let args = Args {
                 branch_id: Some("test".to_string()),
                 with_force: true,
+                force_with_lease: false,
                 skip_force_push_protection: false,
                 run_hooks: true,
                 wip: false,
@@ -24,6 +41,7 @@ mod push {
                 topic: None,
                 topic_from_branch: false,
                 private: false,
+                push_option: vec![],
                 dry_run: false,
             };
 
@@ -37,6 +55,7 @@ mod push {
             let args = Args {
                 branch_id: Some("test".to_string()),
                 with_force: true,
+                force_with_lease: false,
                 skip_force_push_protection: false,
                 run_hooks: true,
                 wip: true,
@@ -45,6 +64,7 @@ mod push {
                 topic: None,
                 topic_from_branch: false,
                 private: false,
+                push_option: vec![],
                 dry_run: false,
             };
 
@@ -63,6 +83,7 @@ mod push {
             let args = Args {
                 branch_id: Some("test".to_string()),
                 with_force: true,
+                force_with_lease: false,
                 skip_force_push_protection: false,
                 run_hooks: true,
                 wip: false,
@@ -71,6 +92,7 @@ mod push {
                 topic: None,
                 topic_from_branch: false,
                 private: false,
+                push_option: vec![],
                 dry_run: false,
             };
 
@@ -86,6 +108,7 @@ mod push {
             let args = Args {
                 branch_id: Some("test".to_string()),
                 with_force: true,
+                force_with_lease: false,
                 skip_force_push_protection: false,
                 run_hooks: true,
                 wip: true,
@@ -94,6 +117,7 @@ mod push {
                 topic: None,
                 topic_from_branch: false,
                 private: false,
+                push_option: vec![],
                 dry_run: false,
             };
 
@@ -109,6 +133,7 @@ mod push {
             let args = Args {
                 branch_id: Some("test".to_string()),
                 with_force: true,
+                force_with_lease: false,
                 skip_force_push_protection: false,
                 run_hooks: true,
                 wip: false,
@@ -117,6 +142,7 @@ mod push {
                 topic: None,
                 topic_from_branch: false,
                 private: false,
+                push_option: vec![],
                 dry_run: false,
             };
 
@@ -143,6 +169,7 @@ mod push {
             let args = Args {
                 branch_id: Some("test".to_string()),
                 with_force: true,
+                force_with_lease: false,
                 skip_force_push_protection: false,
                 run_hooks: true,
                 wip: false,
@@ -151,6 +178,7 @@ mod push {
                 topic: Some("custom-topic".to_string()),
                 topic_from_branch: false,
                 private: false,
+                push_option: vec![],
                 dry_run: false,
             };
 
@@ -174,6 +202,7 @@ mod push {
             let args = Args {
                 branch_id: Some("test".to_string()),
                 with_force: true,
+                force_with_lease: false,
                 skip_force_push_protection: false,
                 run_hooks: true,
                 wip: false,
@@ -182,6 +211,7 @@ mod push {
                 topic: None,
                 topic_from_branch: true,
                 private: false,
+                push_option: vec![],
                 dry_run: false,
             };
 
@@ -205,6 +235,7 @@ mod push {
             let args = Args {
                 branch_id: Some("test".to_string()),
                 with_force: true,
+                force_with_lease: false,
                 skip_force_push_protection: false,
                 run_hooks: true,
                 wip: false,
@@ -213,6 +244,7 @@ mod push {
                 topic: None,
                 topic_from_branch: false,
                 private: true,
+                push_option: vec![],
                 dry_run: false,
             };
 
@@ -233,6 +265,7 @@ mod push {
             let args = Args {
                 branch_id: Some("test".to_string()),
                 with_force: true,
+                force_with_lease: false,
                 skip_force_push_protection: false,
                 run_hooks: true,
                 wip: true,
@@ -241,6 +274,7 @@ mod push {
                 topic: Some("custom-topic".to_string()),
                 topic_from_branch: false,
                 private: true,
+                push_option: vec![],
                 dry_run: false,
             };
 
@@ -279,6 +313,7 @@ mod push {
             let args = Args {
                 branch_id: Some("test".to_string()),
                 with_force: true,
+                force_with_lease: false,
                 skip_force_push_protection: false,
                 run_hooks: true,
                 wip: false,
@@ -287,6 +322,7 @@ mod push {
                 topic: None,
                 topic_from_branch: false,
                 private: false,
+                push_option: vec![],
                 dry_run: false,
             };
 
@@ -305,6 +341,7 @@ mod push {
             let args = Args {
                 branch_id: Some("test".to_string()),
                 with_force: true,
+                force_with_lease: false,
                 skip_force_push_protection: false,
                 run_hooks: true,
                 wip: false,
@@ -313,6 +350,7 @@ mod push {
                 topic: Some("  ".to_string()),
                 topic_from_branch: false,
                 private: false,
+                push_option: vec![],
                 dry_run: false,
             };
 
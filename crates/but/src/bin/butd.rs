@@ -0,0 +1,4 @@
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    but::handle_daemon_args(std::env::args_os()).await
+}
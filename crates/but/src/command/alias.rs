@@ -158,7 +158,7 @@ pub fn list(repo: &gix::Repository, out: &mut OutputChannel) -> Result<()> {
 }
 
 /// Get all user-configured aliases from local and global git config and defaults
-fn get_all_aliases(repo: &gix::Repository) -> Result<Vec<AliasEntry>> {
+pub(crate) fn get_all_aliases(repo: &gix::Repository) -> Result<Vec<AliasEntry>> {
     // Track aliases by name with their scopes
     let mut alias_map: HashMap<String, (String, bool, bool)> = HashMap::new(); // name -> (value, is_local, is_global)
 
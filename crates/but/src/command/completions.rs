@@ -1,4 +1,4 @@
-use std::io;
+use std::{ffi::OsString, io, io::Write as _, path::Path};
 
 use anyhow::{Context as _, Result};
 use clap::CommandFactory;
@@ -6,7 +6,25 @@ use clap_complete::Shell;
 
 use crate::args::Args;
 
+/// Commands whose positional arguments are branch names, as the words that invoke them.
+const BRANCH_COMMANDS: &[&[&str]] = &[
+    &["push"],
+    &["commit"],
+    &["merge"],
+    &["stage"],
+    &["unstage"],
+    &["pr", "new"],
+    &["pr", "create"],
+    &["branch", "delete"],
+    &["branch", "show"],
+    &["branch", "apply"],
+    &["branch", "unapply"],
+];
+
 /// Generate shell completions for the specified shell
+///
+/// For bash, zsh and fish, a hook is appended that calls `but completions --dynamic` to complete
+/// branch names and aliases, which can't be known statically.
 pub fn generate_completions(shell: Option<Shell>) -> Result<()> {
     let shell = shell.or_else(Shell::from_env).context(
         "Couldn't extract shell from `SHELL` environment variable - please specify it manually",
@@ -14,7 +32,143 @@ pub fn generate_completions(shell: Option<Shell>) -> Result<()> {
     let mut cmd = Args::command();
     let bin_name = cmd.get_name().to_string();
 
-    clap_complete::generate(shell, &mut cmd, bin_name, &mut io::stdout());
+    let mut stdout = io::stdout();
+    clap_complete::generate(shell, &mut cmd, bin_name, &mut stdout);
+    if let Some(hook) = dynamic_completion_hook(shell) {
+        stdout.write_all(hook.as_bytes())?;
+    }
 
     Ok(())
 }
+
+/// Print the candidates that complete the last of `words`, one per line, where `words` is the whole command line
+/// typed so far, including the program name and the possibly empty word that is being completed.
+///
+/// Aliases are offered for the first word, and branch names for the arguments of commands that take
+/// them, also if they are invoked through an alias. Nothing is printed if the candidates can't be determined.
+pub fn complete_dynamically(current_dir: &Path, words: &[String]) -> Result<()> {
+    let Some((_current, typed)) = words
+        .split_first()
+        .and_then(|(_but, rest)| rest.split_last())
+    else {
+        return Ok(());
+    };
+
+    let candidates = if typed.is_empty() {
+        alias_names(current_dir)
+    } else {
+        let args = std::iter::once(OsString::from("but"))
+            .chain(typed.iter().map(OsString::from))
+            .collect();
+        let expanded = crate::alias::expand_aliases(args)?;
+        let commands = expanded[1..]
+            .iter()
+            .filter_map(|word| word.to_str())
+            .filter(|word| !word.starts_with('-'))
+            .collect::<Vec<_>>();
+        if BRANCH_COMMANDS
+            .iter()
+            .any(|command| commands.starts_with(command))
+        {
+            branch_names(current_dir)
+        } else {
+            Vec::new()
+        }
+    };
+
+    let mut stdout = io::stdout().lock();
+    for candidate in candidates {
+        writeln!(stdout, "{candidate}")?;
+    }
+    Ok(())
+}
+
+/// Return the names of all local branches, along with the ones of the stacks in the workspace.
+fn branch_names(current_dir: &Path) -> Vec<String> {
+    let Ok(ctx) = but_ctx::Context::discover(current_dir) else {
+        return Vec::new();
+    };
+    let mut names = ctx
+        .repo
+        .get()
+        .ok()
+        .and_then(|repo| {
+            Some(
+                repo.references()
+                    .ok()?
+                    .local_branches()
+                    .ok()?
+                    .filter_map(Result::ok)
+                    .map(|reference| reference.name().shorten().to_string())
+                    .filter(|name| !name.starts_with("gitbutler/"))
+                    .collect::<Vec<_>>(),
+            )
+        })
+        .unwrap_or_default();
+    #[cfg(feature = "legacy")]
+    if let Ok(stacks) = crate::legacy::commits::stacks(&ctx) {
+        names.extend(
+            stacks
+                .into_iter()
+                .flat_map(|stack| stack.heads)
+                .map(|head| head.name.to_string()),
+        );
+    }
+    names.sort();
+    names.dedup();
+    names
+}
+
+/// Return the names of the user's aliases and of the default aliases.
+fn alias_names(current_dir: &Path) -> Vec<String> {
+    let mut names = crate::alias::get_all_default_aliases()
+        .into_iter()
+        .map(|(name, _value)| name)
+        .collect::<Vec<_>>();
+    if let Ok(repo) = gix::discover(current_dir)
+        && let Ok(aliases) = crate::command::alias::get_all_aliases(&repo)
+    {
+        names.extend(aliases.into_iter().map(|alias| alias.name));
+    }
+    names.sort();
+    names.dedup();
+    names
+}
+
+/// Return the script that hooks `but completions --dynamic` into the completion generated for `shell`,
+/// or `None` if it isn't supported there.
+fn dynamic_completion_hook(shell: Shell) -> Option<&'static str> {
+    Some(match shell {
+        Shell::Bash => {
+            r#"
+_but_dynamic() {
+    _but "$@"
+    local candidates
+    candidates="$(but completions --dynamic -- "${COMP_WORDS[@]:0:COMP_CWORD+1}" 2>/dev/null)"
+    if [[ -n "$candidates" ]]; then
+        local IFS=$'\n'
+        COMPREPLY+=( $(compgen -W "$candidates" -- "${COMP_WORDS[COMP_CWORD]}") )
+    fi
+}
+complete -F _but_dynamic -o bashdefault -o default but
+"#
+        }
+        Shell::Zsh => {
+            r#"
+_but_dynamic() {
+    _but "$@"
+    local -a candidates
+    candidates=(${(f)"$(but completions --dynamic -- "${(@)words[1,CURRENT]}" 2>/dev/null)"})
+    (( ${#candidates} )) && compadd -a candidates
+}
+compdef _but_dynamic but
+"#
+        }
+        Shell::Fish => {
+            r#"
+complete -c but -a '(but completions --dynamic -- (commandline -opc) (commandline -ct) 2>/dev/null)'
+"#
+        }
+        _ => return None,
+    })
+}
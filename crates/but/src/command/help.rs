@@ -36,7 +36,7 @@ pub fn print_grouped(out: &mut dyn std::fmt::Write) -> std::fmt::Result {
         (
             "Branching and Committing".yellow(),
             vec![
-                "commit", "stage", "new", "branch", "merge", "discard", "resolve",
+                "commit", "stage", "assign", "new", "branch", "merge", "discard", "resolve",
             ],
         ),
         ("Rules".yellow(), vec!["mark", "unmark"]),
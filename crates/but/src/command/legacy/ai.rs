@@ -6,21 +6,27 @@
 use std::fmt::Write as _;
 
 use anyhow::{Context, Result};
+use but_action::commit_message::{
+    CommitMessageCandidate, CommitMessageProvider, LlmCommitMessageProvider,
+};
 use but_llm::{ChatMessage, LLMProvider};
 use colored::Colorize;
 use schemars::JsonSchema;
 
-use crate::utils::OutputChannel;
+use crate::utils::{InputOutputChannel, OutputChannel};
 
 /// Generate a commit message using AI based on the unified diff and optional user summary.
 ///
-/// This function uses an LLM (Large Language Model) to analyze the provided diff and generate
-/// a well-formatted commit message that follows best practices. The generated message will
-/// include a short summary line and a longer explanation of the changes.
+/// This function uses the commit message provider configured in the `ai` settings to analyze the
+/// provided diff and generate well-formatted commit messages that follow best practices. Each
+/// message will include a short summary line and a longer explanation of the changes.
+/// If more than one message is suggested, the user is asked to pick one when running in a terminal,
+/// and the first one is used otherwise.
 ///
 /// # Arguments
 ///
 /// * `out` - Output channel for displaying progress messages to the user.
+/// * `settings` - The `ai` settings, determining the provider, the model and the number of messages to suggest.
 /// * `diff` - A unified diff string showing the changes to be committed. Should be in standard
 ///   unified diff format with file headers and hunks.
 /// * `user_summary` - An optional user-provided summary that gives context about the changes.
@@ -30,11 +36,12 @@ use crate::utils::OutputChannel;
 /// # Returns
 ///
 /// Returns a formatted commit message string on success, or an error if:
-/// - The OpenAI LLM provider cannot be initialized (e.g., missing API credentials)
+/// - The configured LLM provider cannot be initialized (e.g., missing API credentials)
 /// - The AI request fails
 /// - The AI response cannot be parsed as a valid commit message
 pub fn generate_commit_message(
     out: &mut OutputChannel,
+    settings: &but_settings::app_settings::Ai,
     diff: &str,
     user_summary: Option<String>,
 ) -> Result<String> {
@@ -43,14 +50,12 @@ pub fn generate_commit_message(
     if out.for_human().is_some() {
         writeln!(progress, "{}", "Generating commit message...".bright_cyan())?;
     }
-    let llm = LLMProvider::default_openai()
-        .ok_or_else(|| anyhow::anyhow!("Failed to initialize default OpenAI LLM provider"))?;
-    let system_message =
-        "You are a version control assistant that helps with Git branch committing.".to_string();
+    let git_config = gix::config::File::from_globals()?;
+    let provider = LlmCommitMessageProvider::from_settings(settings, &git_config)?;
     let summary = user_summary.unwrap_or_default();
-    let user_message = format!(
+    let prompt = format!(
         r#"Extract the git commit data from the user summary if provided and the diff output.
-Return the commit message. Determine from this user summary and diff output what the git commit data should be.
+Determine from this user summary and diff output what the git commit data should be.
 
 {DEFAULT_COMMIT_MESSAGE_INSTRUCTIONS}
 
@@ -65,12 +70,52 @@ unified diff:
 "#
     );
 
-    let chat_messages = vec![ChatMessage::User(user_message)];
-    let response = llm
-        .structured_output::<StructuredOutput>(&system_message, chat_messages, "gpt-5-mini")?
-        .context("Failed to generate structured content for commit message")?;
+    let candidates =
+        provider.suggestions(&prompt, settings.commit_message_candidates.max(1) as usize)?;
+    let candidate = match candidates.len() {
+        0 => anyhow::bail!("Failed to generate structured content for commit message"),
+        1 => &candidates[0],
+        _ => match out.prepare_for_terminal_input() {
+            Some(mut inout) => &candidates[pick_candidate(&mut inout, &candidates)?],
+            None => &candidates[0],
+        },
+    };
+    Ok(candidate.message())
+}
 
-    Ok(response.commit_message)
+/// Show all `candidates` and ask which of them to use until a valid number is entered, returning its index.
+fn pick_candidate(
+    inout: &mut InputOutputChannel<'_>,
+    candidates: &[CommitMessageCandidate],
+) -> Result<usize> {
+    for (idx, candidate) in candidates.iter().enumerate() {
+        writeln!(
+            inout,
+            "{} {}",
+            format!("{}.", idx + 1).dimmed(),
+            candidate.subject.bold()
+        )?;
+        for line in candidate.body.trim().lines() {
+            writeln!(inout, "   {line}")?;
+        }
+        writeln!(inout)?;
+    }
+    loop {
+        let input = inout
+            .prompt(format!(
+                "Which commit message should be used [1-{}]?",
+                candidates.len()
+            ))?
+            .unwrap_or_default();
+        if let Some(num) = input
+            .trim()
+            .parse::<usize>()
+            .ok()
+            .filter(|num| (1..=candidates.len()).contains(num))
+        {
+            return Ok(num - 1);
+        }
+    }
 }
 
 /// Generate an updated commit message when squashing multiple commits together.
@@ -0,0 +1,361 @@
+//! `but assign -i`, which walks the unassigned hunks and asks which stack each of them should go to, akin to `git add -p`.
+use std::fmt::Write as _;
+
+use anyhow::{Context as _, bail};
+use but_core::ref_metadata::StackId;
+use but_ctx::Context;
+use but_hunk_assignment::{HunkAssignment, HunkAssignmentRequest, LineSelection};
+use colored::Colorize;
+
+use crate::{
+    command::legacy::diff::display::DiffDisplay,
+    utils::{InputOutputChannel, OutputChannel},
+};
+
+/// A stack hunks can be assigned to, by the number it's shown with.
+struct Target {
+    stack_id: StackId,
+    name: String,
+}
+
+/// What the user chose to do with a hunk, or with one of its lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Action {
+    /// Assign to the stack at this index of the targets.
+    Assign(usize),
+    /// Decide line by line.
+    Split,
+    /// Leave unassigned.
+    Skip,
+    /// Stop asking, but keep the decisions made so far.
+    Quit,
+}
+
+/// Walk all unassigned hunks, ask where each of them should go, and assign them accordingly.
+pub(crate) fn interactive(ctx: &mut Context, out: &mut OutputChannel) -> anyhow::Result<()> {
+    let changes = but_core::diff::ui::worktree_changes_by_worktree_dir(
+        ctx.legacy_project.worktree_dir()?.into(),
+    )?
+    .changes;
+    let (targets, unassigned) = {
+        let guard = ctx.shared_worktree_access();
+        let repo = ctx.repo.get()?.clone();
+        let (_, workspace) = ctx.workspace_and_read_only_meta_from_head(guard.read_permission())?;
+        let (assignments, _assignments_error) = but_hunk_assignment::assignments_with_fallback(
+            ctx,
+            &repo,
+            &workspace,
+            false,
+            Some(changes),
+            None,
+        )?;
+        let targets = workspace
+            .stacks
+            .iter()
+            .filter_map(|stack| {
+                Some(Target {
+                    stack_id: stack.id?,
+                    name: stack.ref_name()?.shorten().to_string(),
+                })
+            })
+            .take(9)
+            .collect::<Vec<_>>();
+        let unassigned = assignments
+            .into_iter()
+            .filter(|assignment| assignment.stack_id.is_none())
+            .collect::<Vec<_>>();
+        (targets, unassigned)
+    };
+
+    if unassigned.is_empty() {
+        if let Some(out) = out.for_human() {
+            writeln!(out, "There are no unassigned changes.")?;
+        }
+        return Ok(());
+    }
+    if targets.is_empty() {
+        bail!("There are no stacks to assign changes to. Create a branch with `but branch new`.");
+    }
+
+    let requests = {
+        let mut inout = out.prepare_for_terminal_input().context(
+            "Interactive assignment needs a terminal. Use `but stage <file-or-hunk> <branch>` instead.",
+        )?;
+        ask_for_requests(&mut inout, &targets, &unassigned)?
+    };
+    if requests.is_empty() {
+        if let Some(out) = out.for_human() {
+            writeln!(out, "Nothing was assigned.")?;
+        }
+        return Ok(());
+    }
+
+    let num_requests = requests.len();
+    let guard = ctx.exclusive_worktree_access();
+    let repo = ctx.repo.get()?.clone();
+    let (_, workspace) = ctx.workspace_and_read_only_meta_from_head(guard.read_permission())?;
+    let rejections = but_hunk_assignment::assign(ctx, &repo, &workspace, requests, None)?;
+    if let Some(out) = out.for_human() {
+        let num_assigned = num_requests - rejections.len();
+        writeln!(
+            out,
+            "Assigned {num_assigned} {}.",
+            if num_assigned == 1 { "hunk" } else { "hunks" }
+        )?;
+        for rejection in rejections {
+            writeln!(
+                out,
+                "{} {} is locked to a commit in another stack and stays where it is.",
+                "Skipped".yellow(),
+                rejection.request.path_bytes.to_string().bright_white()
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Ask for an [`Action`] for each of `unassigned`, and return the requests to assign them to `targets`.
+fn ask_for_requests(
+    inout: &mut InputOutputChannel<'_>,
+    targets: &[Target],
+    unassigned: &[HunkAssignment],
+) -> anyhow::Result<Vec<HunkAssignmentRequest>> {
+    writeln!(inout, "Stacks:")?;
+    for (idx, target) in targets.iter().enumerate() {
+        writeln!(
+            inout,
+            "  {} {}",
+            idx + 1,
+            format!("[{}]", target.name).green()
+        )?;
+    }
+
+    let mut requests = Vec::new();
+    for (idx, hunk) in unassigned.iter().enumerate() {
+        writeln!(inout)?;
+        write!(inout, "{}", hunk.print_diff(None))?;
+        let progress = format!("({}/{})", idx + 1, unassigned.len());
+        let can_split = hunk.hunk_header.is_some() && hunk.diff.is_some();
+        let target = match ask(inout, &progress, targets.len(), can_split)? {
+            Action::Assign(target) => target,
+            Action::Skip => continue,
+            Action::Quit => break,
+            Action::Split => {
+                let (lines, quit) = ask_line_by_line(inout, targets, hunk)?;
+                requests.extend(lines);
+                if quit {
+                    break;
+                }
+                continue;
+            }
+        };
+        requests.push(HunkAssignmentRequest {
+            hunk_header: hunk.hunk_header,
+            path_bytes: hunk.path_bytes.clone(),
+            stack_id: Some(targets[target].stack_id),
+        });
+    }
+    Ok(requests)
+}
+
+/// Ask for an [`Action`] for each line of `hunk`, and return the requests to assign the lines to `targets`
+/// along with `true` if the user quit.
+///
+/// Skipped lines stay unassigned, and if all lines go to the same stack, the hunk is assigned as a whole.
+fn ask_line_by_line(
+    inout: &mut InputOutputChannel<'_>,
+    targets: &[Target],
+    hunk: &HunkAssignment,
+) -> anyhow::Result<(Vec<HunkAssignmentRequest>, bool)> {
+    let lines = hunk.split_into_line_selections();
+    let mut line_targets = Vec::with_capacity(lines.len());
+    let mut quit = false;
+    for (idx, selection) in lines.iter().enumerate() {
+        let line = selection.line.to_string();
+        let line = if line.starts_with('+') {
+            line.green()
+        } else {
+            line.red()
+        };
+        writeln!(inout, "{line}")?;
+        let progress = format!("(line {}/{})", idx + 1, lines.len());
+        match ask(inout, &progress, targets.len(), false)? {
+            Action::Assign(target) => line_targets.push(Some(target)),
+            Action::Skip => line_targets.push(None),
+            Action::Quit => {
+                quit = true;
+                break;
+            }
+            Action::Split => unreachable!("lines can't be split"),
+        }
+    }
+    Ok((line_requests(targets, hunk, &lines, &line_targets), quit))
+}
+
+/// Turn the `line_targets` chosen for the first `lines` of `hunk` into requests, with `None` for lines to leave unassigned.
+fn line_requests(
+    targets: &[Target],
+    hunk: &HunkAssignment,
+    lines: &[LineSelection],
+    line_targets: &[Option<usize>],
+) -> Vec<HunkAssignmentRequest> {
+    let request = |hunk_header, target: usize| HunkAssignmentRequest {
+        hunk_header: Some(hunk_header),
+        path_bytes: hunk.path_bytes.clone(),
+        stack_id: Some(targets[target].stack_id),
+    };
+    if line_targets.len() == lines.len()
+        && let Some(Some(target)) = line_targets.first()
+        && line_targets.iter().all(|t| *t == Some(*target))
+        && let Some(hunk_header) = hunk.hunk_header
+    {
+        return vec![request(hunk_header, *target)];
+    }
+    lines
+        .iter()
+        .zip(line_targets)
+        .filter_map(|(line, target)| target.map(|target| request(line.hunk_header, target)))
+        .collect()
+}
+
+/// Prompt until a valid action is entered, offering to split if `can_split` is `true`.
+fn ask(
+    inout: &mut InputOutputChannel<'_>,
+    progress: &str,
+    num_targets: usize,
+    can_split: bool,
+) -> anyhow::Result<Action> {
+    let keys = format!(
+        "{},{}n,q,?",
+        if num_targets == 1 {
+            "1".to_string()
+        } else {
+            format!("1-{num_targets}")
+        },
+        if can_split { "s," } else { "" }
+    );
+    loop {
+        let input = inout
+            .prompt(format!("{} Assign to stack [{keys}]?", progress.dimmed()))?
+            .unwrap_or_default();
+        match parse_action(&input, num_targets, can_split) {
+            Some(action) => return Ok(action),
+            None => {
+                writeln!(
+                    inout,
+                    "1-{num_targets} - assign to the stack with this number"
+                )?;
+                if can_split {
+                    writeln!(inout, "s - split into lines and decide for each of them")?;
+                }
+                writeln!(inout, "n - leave unassigned")?;
+                writeln!(inout, "q - quit, keeping what was decided so far")?;
+            }
+        }
+    }
+}
+
+/// Turn the key the user entered in `input` into an action, or `None` if it isn't valid.
+fn parse_action(input: &str, num_targets: usize, can_split: bool) -> Option<Action> {
+    match input.trim() {
+        "s" if can_split => Some(Action::Split),
+        "n" => Some(Action::Skip),
+        "q" => Some(Action::Quit),
+        key => key
+            .parse::<usize>()
+            .ok()
+            .filter(|num| (1..=num_targets).contains(num))
+            .map(|num| Action::Assign(num - 1)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use but_core::{HunkHeader, ref_metadata::StackId};
+    use but_hunk_assignment::HunkAssignment;
+
+    use super::{Action, Target, line_requests, parse_action};
+
+    fn header(old_start: u32, old_lines: u32, new_start: u32, new_lines: u32) -> HunkHeader {
+        HunkHeader {
+            old_start,
+            old_lines,
+            new_start,
+            new_lines,
+        }
+    }
+
+    #[test]
+    fn lines_are_requested_for_the_stacks_they_were_assigned_to() {
+        let targets = [
+            Target {
+                stack_id: StackId::generate(),
+                name: "a".into(),
+            },
+            Target {
+                stack_id: StackId::generate(),
+                name: "b".into(),
+            },
+        ];
+        let hunk = HunkAssignment {
+            id: None,
+            hunk_header: Some(header(1, 2, 1, 3)),
+            path: "file".into(),
+            path_bytes: "file".into(),
+            stack_id: None,
+            hunk_locks: None,
+            line_nums_added: None,
+            line_nums_removed: None,
+            diff: Some("@@ -1,2 +1,3 @@\n-a\n+A\n+B\n c\n".into()),
+            mode_flags: None,
+        };
+        let lines = hunk.split_into_line_selections();
+        let summary = |line_targets: &[Option<usize>]| {
+            line_requests(&targets, &hunk, &lines, line_targets)
+                .into_iter()
+                .map(|request| (request.hunk_header.unwrap(), request.stack_id.unwrap()))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            summary(&[Some(0), None, Some(1)]),
+            [
+                (header(1, 1, 0, 0), targets[0].stack_id),
+                (header(0, 0, 2, 1), targets[1].stack_id),
+            ],
+            "skipped lines aren't assigned, and the others go where they were asked to"
+        );
+        assert_eq!(
+            summary(&[Some(1), Some(1), Some(1)]),
+            [(header(1, 2, 1, 3), targets[1].stack_id)],
+            "the whole hunk is assigned if all lines go to the same stack"
+        );
+        assert_eq!(
+            summary(&[Some(1), Some(1)]),
+            [
+                (header(1, 1, 0, 0), targets[1].stack_id),
+                (header(0, 0, 1, 1), targets[1].stack_id),
+            ],
+            "lines that weren't decided on before quitting stay unassigned"
+        );
+    }
+
+    #[test]
+    fn parse_action_accepts_known_keys_only() {
+        assert_eq!(parse_action("2", 3, true), Some(Action::Assign(1)));
+        assert_eq!(parse_action(" 1\n", 1, true), Some(Action::Assign(0)));
+        assert_eq!(parse_action("s", 3, true), Some(Action::Split));
+        assert_eq!(parse_action("n", 3, true), Some(Action::Skip));
+        assert_eq!(parse_action("q", 3, false), Some(Action::Quit));
+
+        assert_eq!(
+            parse_action("0", 3, true),
+            None,
+            "stacks are numbered from 1"
+        );
+        assert_eq!(parse_action("4", 3, true), None, "there is no 4th stack");
+        assert_eq!(parse_action("s", 3, false), None, "lines can't be split");
+        assert_eq!(parse_action("", 3, true), None);
+        assert_eq!(parse_action("?", 3, true), None);
+    }
+}
@@ -7,6 +7,18 @@ pub struct BranchNewOutput {
     pub anchor: Option<String>,
 }
 
+#[derive(Debug, Serialize)]
+pub struct BranchDeleteOutput {
+    pub branch: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BranchUnapplyOutput {
+    /// The branches of the stack that were unapplied, or none if the branch wasn't applied.
+    pub unapplied: Vec<String>,
+}
+
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct BranchListOutput {
@@ -168,10 +168,8 @@ pub fn handle(
                 }
             }
 
-            if let Some(out) = out.for_human() {
-                writeln!(out, "Branch '{}' not found in any stack", branch_name)?;
-            }
-            Ok(())
+            // Unlike unapplying, deleting is not idempotent, so scripts learn about typos.
+            bail!("Branch '{}' not found in any stack", branch_name)
         }
         Some(Subcommands::Apply { branch_name }) => {
             apply::apply(&ctx.legacy_project, &branch_name, out)
@@ -202,6 +200,10 @@ pub fn handle(
 
             if let Some(out) = out.for_human() {
                 writeln!(out, "Branch '{}' not found in any stack", branch_name)?;
+            } else if let Some(out) = out.for_json() {
+                out.write_value(json::BranchUnapplyOutput {
+                    unapplied: Vec::new(),
+                })?;
             }
             Ok(())
         }
@@ -215,12 +217,12 @@ fn confirm_unapply_stack(
     force: bool,
     out: &mut OutputChannel,
 ) -> Result<(), anyhow::Error> {
-    let branches = stack_entry
+    let unapplied = stack_entry
         .heads
         .iter()
         .map(|head| head.name.to_string())
-        .collect::<Vec<_>>()
-        .join(", ");
+        .collect::<Vec<_>>();
+    let branches = unapplied.join(", ");
 
     if !force
         && let Some(mut inout) = out.prepare_for_terminal_input()
@@ -240,6 +242,8 @@ fn confirm_unapply_stack(
             "Unapplied stack with branches '{}' from workspace",
             branches
         )?;
+    } else if let Some(out) = out.for_json() {
+        out.write_value(json::BranchUnapplyOutput { unapplied })?;
     }
     Ok(())
 }
@@ -265,6 +269,10 @@ fn confirm_branch_deletion(
 
     if let Some(out) = out.for_human() {
         writeln!(out, "Deleted branch {branch_name}")?;
+    } else if let Some(out) = out.for_json() {
+        out.write_value(json::BranchDeleteOutput {
+            branch: branch_name.to_owned(),
+        })?;
     }
     Ok(())
 }
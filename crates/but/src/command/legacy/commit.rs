@@ -6,7 +6,7 @@ use bstr::{BString, ByteSlice};
 use but_api::{
     commit::commit_insert_blank,
     json::HexHash,
-    legacy::{diff, repo, workspace},
+    legacy::{diff, repo, virtual_branches, workspace},
 };
 use but_core::{DiffSpec, ui::TreeChange};
 use but_rebase::graph_rebase::mutate::InsertSide;
@@ -129,6 +129,25 @@ fn generate_unified_diff(
                         but_core::UnifiedPatch::TooLarge { size_in_bytes } => {
                             writeln!(&mut diff_output, "File too large ({} bytes)", size_in_bytes)?;
                         }
+                        but_core::UnifiedPatch::Submodule { previous_id, id } => {
+                            if let Some(previous_id) = previous_id {
+                                writeln!(&mut diff_output, "-Subproject commit {previous_id}")?;
+                            }
+                            if let Some(id) = id {
+                                writeln!(&mut diff_output, "+Subproject commit {id}")?;
+                            }
+                        }
+                        but_core::UnifiedPatch::LfsPointer { previous, current } => {
+                            for (sign, pointer) in [("-", previous), ("+", current)] {
+                                if let Some(pointer) = pointer {
+                                    writeln!(
+                                        &mut diff_output,
+                                        "{sign}LFS object sha256:{} ({} bytes)",
+                                        pointer.oid, pointer.size
+                                    )?;
+                                }
+                            }
+                        }
                         but_core::UnifiedPatch::Patch { hunks, .. } => {
                             for hunk in hunks {
                                 // Add hunk header
@@ -162,6 +181,8 @@ pub(crate) fn commit(
     branch_hint: Option<&str>,
     only: bool,
     create_branch: bool,
+    amend: bool,
+    edit: bool,
     no_hooks: bool,
     generate_message: Option<Option<String>>,
 ) -> anyhow::Result<()> {
@@ -219,7 +240,49 @@ pub(crate) fn commit(
     );
     files_to_commit.extend(stack_assigned);
 
-    if files_to_commit.is_empty() {
+    // If a branch hint was provided, find that specific branch; otherwise use first branch
+    let target_branch = if let Some(hint) = branch_hint {
+        // First try exact name match
+        target_stack
+            .branch_details
+            .iter()
+            .find(|branch| branch.name == hint)
+            .or_else(|| {
+                // If no exact match, try to parse as CLI ID and match
+                if let Ok(cli_ids) = id_map.parse_using_context(hint, ctx) {
+                    for cli_id in cli_ids {
+                        if let CliId::Branch { name, .. } = cli_id
+                            && let Some(branch) =
+                                target_stack.branch_details.iter().find(|b| b.name == name)
+                        {
+                            return Some(branch);
+                        }
+                    }
+                }
+                None
+            })
+            .ok_or_else(|| anyhow::anyhow!("Branch '{}' not found in target stack", hint))?
+    } else {
+        // No branch hint, use first branch (HEAD of stack)
+        target_stack
+            .branch_details
+            .first()
+            .ok_or_else(|| anyhow::anyhow!("No branches found in target stack"))?
+    };
+
+    // The commit to amend is the one at the top of the branch, just like `git commit --amend` would pick.
+    let commit_to_amend =
+        if amend {
+            Some(target_branch.commits.first().with_context(|| {
+                format!("Branch '{}' has no commits to amend", target_branch.name)
+            })?)
+        } else {
+            None
+        };
+
+    // Amending without changes is fine if only the message should change.
+    let rewords = message.is_some() || edit || generate_message.is_some();
+    if files_to_commit.is_empty() && !(commit_to_amend.is_some() && rewords) {
         bail!("No changes to commit.")
     }
 
@@ -238,6 +301,10 @@ pub(crate) fn commit(
                 previous_path: None,
                 path: fa.path.clone(),
                 hunk_headers,
+                mode_flags: fa
+                    .assignments
+                    .iter()
+                    .find_map(|assignment| assignment.inner.mode_flags),
             }
         })
         .collect();
@@ -245,7 +312,7 @@ pub(crate) fn commit(
     // Run pre-commit hook unless --no-hooks was specified
     // This runs BEFORE getting the commit message so the user doesn't waste time writing a message
     // for a commit that will fail the hook
-    if !no_hooks {
+    if !no_hooks && !diff_specs.is_empty() {
         let hook_result = repo::pre_commit_hook_diffspecs(project_id, diff_specs.clone())?;
         match hook_result {
             hooks::HookResult::Success | hooks::HookResult::NotConfigured => {
@@ -260,16 +327,128 @@ pub(crate) fn commit(
         }
     }
 
-    // Get commit message
-    let commit_message = if let Some(user_summary) = generate_message {
+    // Get commit message, which is only needed when amending if it should change.
+    let mut commit_message = if let Some(user_summary) = generate_message {
         let diff = generate_unified_diff(ctx, &files_to_commit, &changes)?;
-        super::ai::generate_commit_message(out, &diff, user_summary)?
-    } else if let Some(msg) = message {
-        msg.to_string()
+        Some(super::ai::generate_commit_message(
+            out,
+            &ctx.settings.ai,
+            &diff,
+            user_summary,
+        )?)
     } else {
-        get_commit_message_from_editor(&files_to_commit, &changes)?
+        message.map(ToOwned::to_owned)
+    };
+    if edit || (commit_message.is_none() && commit_to_amend.is_none()) {
+        let initial_message = commit_message
+            .take()
+            .or_else(|| commit_to_amend.map(|commit| commit.message.to_str_lossy().into_owned()))
+            .unwrap_or_default();
+        commit_message = Some(get_commit_message_from_editor(
+            &files_to_commit,
+            &changes,
+            &initial_message,
+        )?);
+    }
+
+    let final_commit_message = match commit_message {
+        Some(commit_message) => Some(finalize_commit_message(
+            project_id,
+            commit_message,
+            no_hooks,
+            out,
+        )?),
+        None => None,
     };
 
+    if let Some(commit) = commit_to_amend {
+        let mut new_commit = commit.id;
+        if !diff_specs.is_empty() {
+            let outcome = workspace::amend_commit_from_worktree_changes(
+                ctx,
+                target_stack_id,
+                commit.id,
+                diff_specs,
+            )?;
+            new_commit = outcome
+                .new_commit
+                .context("The commit could not be amended with the changes")?;
+        }
+        if let Some(message) = final_commit_message {
+            // Rewording applies the message template just like when creating a commit.
+            let reworded = virtual_branches::update_commit_message(
+                project_id,
+                target_stack_id,
+                new_commit.to_string(),
+                message,
+            )?;
+            new_commit = gix::ObjectId::from_hex(reworded.as_bytes())?;
+        }
+        if let Some(out) = out.for_human() {
+            writeln!(
+                out,
+                "Amended commit {} on branch {}",
+                new_commit.to_hex_with_len(7),
+                target_branch.name
+            )?;
+        }
+    } else {
+        // Get the HEAD commit of the target branch to use as parent (preserves stacking)
+        let parent_commit_id = target_branch.tip;
+
+        // Use but-api to create the commit, with the hooks that ran already unless `--no-hooks` was specified.
+        let outcome = workspace::create_commit_from_worktree_changes(
+            project_id,
+            target_stack_id,
+            Some(HexHash::from(parent_commit_id)),
+            diff_specs,
+            final_commit_message.context("new commits always have a message")?,
+            target_branch.name.to_string(),
+            Some(false),
+        )?;
+
+        if let Some(out) = out.for_human() {
+            let commit_short = match outcome.new_commit {
+                Some(id) => id.to_hex_with_len(7).to_string(),
+                None => "unknown".to_string(),
+            };
+            writeln!(
+                out,
+                "Created commit {} on branch {}",
+                commit_short, target_branch.name
+            )?;
+        }
+    }
+
+    // Run post-commit hook unless --no-hooks was specified
+    // Note: post-commit hooks run after the commit is created, so failures don't prevent the commit
+    if !no_hooks {
+        let hook_result = repo::post_commit_hook(project_id)?;
+        match hook_result {
+            hooks::HookResult::Success | hooks::HookResult::NotConfigured => {
+                // Hook passed or not configured, nothing to do
+            }
+            hooks::HookResult::Failure(error_data) => {
+                // Warn the user but don't fail since the commit is already created
+                if let Some(out) = out.for_human() {
+                    writeln!(out, "\n{}", "Warning: post-commit hook failed:".yellow())?;
+                    writeln!(out, "{}", error_data.error)?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Check `commit_message` with the `commit-msg` hook unless `no_hooks` is set, warn about project rules
+/// it breaks, and return the message to use.
+fn finalize_commit_message(
+    project_id: gitbutler_project::ProjectId,
+    commit_message: String,
+    no_hooks: bool,
+    out: &mut OutputChannel,
+) -> anyhow::Result<String> {
     if commit_message.trim().is_empty() {
         bail!("Aborting commit due to empty commit message.");
     }
@@ -302,80 +481,22 @@ pub(crate) fn commit(
         commit_message
     };
 
-    // If a branch hint was provided, find that specific branch; otherwise use first branch
-    let target_branch = if let Some(hint) = branch_hint {
-        // First try exact name match
-        target_stack
-            .branch_details
-            .iter()
-            .find(|branch| branch.name == hint)
-            .or_else(|| {
-                // If no exact match, try to parse as CLI ID and match
-                if let Ok(cli_ids) = id_map.parse_using_context(hint, ctx) {
-                    for cli_id in cli_ids {
-                        if let CliId::Branch { name, .. } = cli_id
-                            && let Some(branch) =
-                                target_stack.branch_details.iter().find(|b| b.name == name)
-                        {
-                            return Some(branch);
-                        }
-                    }
-                }
-                None
-            })
-            .ok_or_else(|| anyhow::anyhow!("Branch '{}' not found in target stack", hint))?
-    } else {
-        // No branch hint, use first branch (HEAD of stack)
-        target_stack
-            .branch_details
-            .first()
-            .ok_or_else(|| anyhow::anyhow!("No branches found in target stack"))?
-    };
-
-    // Get the HEAD commit of the target branch to use as parent (preserves stacking)
-    let parent_commit_id = target_branch.tip;
-
-    // Use but-api to create the commit
-    let outcome = workspace::create_commit_from_worktree_changes(
-        project_id,
-        target_stack_id,
-        Some(HexHash::from(parent_commit_id)),
-        diff_specs,
-        final_commit_message,
-        target_branch.name.to_string(),
-    )?;
-
-    if let Some(out) = out.for_human() {
-        let commit_short = match outcome.new_commit {
-            Some(id) => id.to_hex_with_len(7).to_string(),
-            None => "unknown".to_string(),
-        };
+    // Warn about messages that don't follow the rules of the project, but let the user decide what to do about it.
+    let findings = repo::validate_commit_message(project_id, final_commit_message.clone())?;
+    if !findings.is_empty()
+        && let Some(out) = out.for_human()
+    {
         writeln!(
             out,
-            "Created commit {} on branch {}",
-            commit_short, target_branch.name
+            "{}",
+            "Warning: the commit message breaks project rules:".yellow()
         )?;
-    }
-
-    // Run post-commit hook unless --no-hooks was specified
-    // Note: post-commit hooks run after the commit is created, so failures don't prevent the commit
-    if !no_hooks {
-        let hook_result = repo::post_commit_hook(project_id)?;
-        match hook_result {
-            hooks::HookResult::Success | hooks::HookResult::NotConfigured => {
-                // Hook passed or not configured, nothing to do
-            }
-            hooks::HookResult::Failure(error_data) => {
-                // Warn the user but don't fail since the commit is already created
-                if let Some(out) = out.for_human() {
-                    writeln!(out, "\n{}", "Warning: post-commit hook failed:".yellow())?;
-                    writeln!(out, "{}", error_data.error)?;
-                }
-            }
+        for finding in &findings {
+            writeln!(out, "  line {}: {}", finding.line, finding.message)?;
         }
     }
 
-    Ok(())
+    Ok(final_commit_message)
 }
 
 fn create_independent_branch(
@@ -532,9 +653,10 @@ fn prompt_for_stack_selection(
 fn get_commit_message_from_editor(
     files_to_commit: &[FileAssignment],
     changes: &[TreeChange],
+    initial_message: &str,
 ) -> anyhow::Result<String> {
     // Generate commit message template
-    let mut template = String::new();
+    let mut template = initial_message.trim_end().to_owned();
     template.push_str("\n# Please enter the commit message for your changes. Lines starting\n");
     template.push_str("# with '#' will be ignored, and an empty message aborts the commit.\n");
     template.push_str("#\n");
@@ -0,0 +1,115 @@
+//! The methods the daemon serves, named like the `but-server` routes that call the same functions.
+use std::{future::Future, pin::Pin};
+
+use but_api::{commit, legacy, operations};
+use serde_json::Value;
+
+type BlockingMethod = fn(Value) -> anyhow::Result<Value>;
+type AsyncMethod = fn(Value) -> Pin<Box<dyn Future<Output = anyhow::Result<Value>> + Send>>;
+
+/// A method that can be called with JSON parameters to produce a JSON result.
+#[derive(Clone, Copy)]
+pub(crate) enum Method {
+    /// A method that blocks, so it runs on a thread that may block.
+    Blocking(BlockingMethod),
+    /// A method that can be awaited directly.
+    Async(AsyncMethod),
+}
+
+impl Method {
+    /// Call the method with `params` and return its result.
+    pub(crate) async fn call(self, params: Value) -> anyhow::Result<Value> {
+        match self {
+            Method::Blocking(method) => tokio::task::spawn_blocking(move || method(params)).await?,
+            Method::Async(method) => method(params).await,
+        }
+    }
+}
+
+/// Return the method called `name`, if there is one.
+pub(crate) fn find(name: &str) -> Option<Method> {
+    use Method::{Async, Blocking};
+    use legacy::{diff, stack, virtual_branches, workspace};
+
+    Some(match name {
+        // Workspace
+        "head_info" => Blocking(workspace::head_info_cmd),
+        "stacks" => Blocking(workspace::stacks_cmd),
+        "stack_details" => Blocking(workspace::stack_details_cmd),
+        "branch_details" => Blocking(workspace::branch_details_cmd),
+        "workspace_graph" => Blocking(workspace::workspace_graph_cmd),
+        "workspace_stats" => Blocking(workspace::workspace_stats_cmd),
+        "target_commits" => Blocking(workspace::target_commits_cmd),
+        "canned_branch_name" => Blocking(workspace::canned_branch_name_cmd),
+        "suggest_branch_name" => Blocking(workspace::suggest_branch_name_cmd),
+        "create_commit_from_worktree_changes" => {
+            Blocking(workspace::create_commit_from_worktree_changes_cmd)
+        }
+        "amend_commit_from_worktree_changes" => {
+            Blocking(workspace::amend_commit_from_worktree_changes_cmd)
+        }
+        "discard_worktree_changes" => Blocking(workspace::discard_worktree_changes_cmd),
+        "uncommit_changes" => Blocking(workspace::uncommit_changes_cmd),
+        "stash_into_branch" => Blocking(workspace::stash_into_branch_cmd),
+        "move_changes_between_commits" => Blocking(workspace::move_changes_between_commits_cmd),
+        "split_branch" => Blocking(workspace::split_branch_cmd),
+        "split_branch_into_dependent_branch" => {
+            Blocking(workspace::split_branch_into_dependent_branch_cmd)
+        }
+        "commit_search" => Blocking(commit::commit_search_cmd),
+        "commit_reword" => Blocking(commit::commit_reword_cmd),
+        "commit_insert_blank" => Blocking(commit::commit_insert_blank_cmd),
+        "commit_uncommit_changes" => Blocking(commit::commit_uncommit_changes_cmd),
+        "commit_move_changes_between" => Blocking(commit::commit_move_changes_between_cmd),
+        // Assignments
+        "changes_in_worktree" => Blocking(diff::changes_in_worktree_cmd),
+        "tree_change_diffs" => Blocking(diff::tree_change_diffs_cmd),
+        "assign_hunk" => Blocking(diff::assign_hunk_cmd),
+        // Branch actions
+        "list_branches" => Blocking(virtual_branches::list_branches_cmd),
+        "get_base_branch_data" => Blocking(virtual_branches::get_base_branch_data_cmd),
+        "set_base_branch" => Blocking(virtual_branches::set_base_branch_cmd),
+        "push_base_branch" => Blocking(virtual_branches::push_base_branch_cmd),
+        "fetch_from_remotes" => Blocking(virtual_branches::fetch_from_remotes_cmd),
+        "create_virtual_branch" => Blocking(virtual_branches::create_virtual_branch_cmd),
+        "create_virtual_branch_from_branch" => {
+            Blocking(virtual_branches::create_virtual_branch_from_branch_cmd)
+        }
+        "delete_local_branch" => Blocking(virtual_branches::delete_local_branch_cmd),
+        "unapply_stack" => Blocking(virtual_branches::unapply_stack_cmd),
+        "amend_virtual_branch" => Blocking(virtual_branches::amend_virtual_branch_cmd),
+        "undo_commit" => Blocking(virtual_branches::undo_commit_cmd),
+        "revert_commit" => Blocking(virtual_branches::revert_commit_cmd),
+        "squash_commits" => Blocking(virtual_branches::squash_commits_cmd),
+        "autosquash_stack" => Blocking(virtual_branches::autosquash_stack_cmd),
+        "cherry_pick_into_stack" => Blocking(virtual_branches::cherry_pick_into_stack_cmd),
+        "move_commit" => Blocking(virtual_branches::move_commit_cmd),
+        "move_branch" => Blocking(virtual_branches::move_branch_cmd),
+        "tear_off_branch" => Blocking(virtual_branches::tear_off_branch_cmd),
+        "reorder_stack" => Blocking(virtual_branches::reorder_stack_cmd),
+        "update_stack_order" => Blocking(virtual_branches::update_stack_order_cmd),
+        "update_commit_message" => Blocking(virtual_branches::update_commit_message_cmd),
+        "integrate_upstream_commits" => Blocking(virtual_branches::integrate_upstream_commits_cmd),
+        "upstream_integration_statuses" => {
+            Async(|params| Box::pin(virtual_branches::upstream_integration_statuses_cmd(params)))
+        }
+        "integrate_upstream" => {
+            Async(|params| Box::pin(virtual_branches::integrate_upstream_cmd(params)))
+        }
+        "resolve_upstream_integration" => {
+            Async(|params| Box::pin(virtual_branches::resolve_upstream_integration_cmd(params)))
+        }
+        "adopt_external_commits" => Blocking(virtual_branches::adopt_external_commits_cmd),
+        "external_commits" => Blocking(virtual_branches::external_commits_cmd),
+        "switch_back_to_workspace" => Blocking(virtual_branches::switch_back_to_workspace_cmd),
+        "create_branch" => Blocking(stack::create_branch_cmd),
+        "remove_branch" => Blocking(stack::remove_branch_cmd),
+        "update_branch_name" => Blocking(stack::update_branch_name_cmd),
+        "push_stack" => Blocking(stack::push_stack_cmd),
+        "push_stack_all" => Blocking(stack::push_stack_all_cmd),
+        // Operations, which are shared by all clients of the daemon.
+        "list_operations" => Blocking(operations::list_operations_cmd),
+        "cancel_operation" => Blocking(operations::cancel_operation_cmd),
+        _ => return None,
+    })
+}
@@ -0,0 +1,194 @@
+//! `butd`, a long-running process that serves the workspace API over a local socket.
+//!
+//! Clients like editor integrations connect to a Unix socket, or a named pipe on Windows, and send
+//! one [JSON-RPC](rpc) request per line. As all requests are handled in the same process, they avoid the
+//! cost of starting `but` for each command, share worktree locks and the operation queue of each project,
+//! and benefit from the [state that is kept warm](warm) between requests.
+use std::path::PathBuf;
+
+use anyhow::Result;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+
+mod methods;
+mod rpc;
+mod warm;
+
+/// Serve requests on `socket`, or on the default socket if `None`, until the process is interrupted.
+pub(crate) async fn serve(socket: Option<PathBuf>) -> Result<()> {
+    let socket = match socket {
+        Some(socket) => socket,
+        None => platform::default_socket()?,
+    };
+    tracing::info!(socket = %socket.display(), "Starting daemon");
+    tokio::select! {
+        res = platform::listen(&socket) => res,
+        res = tokio::signal::ctrl_c() => {
+            tracing::info!("Stopping daemon");
+            platform::cleanup(&socket);
+            res.map_err(Into::into)
+        }
+    }
+}
+
+/// Answer each request read from `stream` in order, until the client disconnects.
+async fn handle_connection(stream: impl AsyncRead + AsyncWrite) -> Result<()> {
+    let (reader, mut writer) = tokio::io::split(stream);
+    let mut lines = BufReader::new(reader).lines();
+    while let Some(line) = lines.next_line().await? {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let Some(response) = rpc::handle(&line).await else {
+            continue;
+        };
+        let mut response = serde_json::to_vec(&response)?;
+        response.push(b'\n');
+        writer.write_all(&response).await?;
+        writer.flush().await?;
+    }
+    Ok(())
+}
+
+/// Handle the connection to a client in the background, logging failures as they can't be reported otherwise.
+fn spawn_connection(stream: impl AsyncRead + AsyncWrite + Send + 'static) {
+    tokio::spawn(async move {
+        if let Err(err) = handle_connection(stream).await {
+            tracing::warn!(?err, "Client connection failed");
+        }
+    });
+}
+
+#[cfg(unix)]
+mod platform {
+    use std::{fs::Permissions, os::unix::fs::PermissionsExt as _, path::Path, path::PathBuf};
+
+    use anyhow::{Context as _, Result, bail};
+    use tokio::net::{UnixListener, UnixStream};
+
+    pub(super) fn default_socket() -> Result<PathBuf> {
+        Ok(but_path::app_data_dir()?.join("butd.sock"))
+    }
+
+    pub(super) async fn listen(socket: &Path) -> Result<()> {
+        let listener = bind(socket).await?;
+        loop {
+            let (stream, _) = listener.accept().await?;
+            super::spawn_connection(stream);
+        }
+    }
+
+    /// Listen on `socket`, which only the current user may connect to as the daemon can do anything they can do.
+    pub(super) async fn bind(socket: &Path) -> Result<UnixListener> {
+        if socket.exists() {
+            if UnixStream::connect(socket).await.is_ok() {
+                bail!(
+                    "Another daemon is already listening on '{}'",
+                    socket.display()
+                );
+            }
+            // Left behind by a daemon that didn't shut down cleanly.
+            std::fs::remove_file(socket)?;
+        }
+        let parent = socket
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+            .unwrap_or(Path::new("."));
+        std::fs::create_dir_all(parent)?;
+        // Bind in a directory only the current user can enter, and move the socket into place only once
+        // its permissions are restricted, so nobody else can connect in the meantime.
+        let private_dir = tempfile::Builder::new()
+            .prefix(".butd-")
+            .tempdir_in(parent)?;
+        let private_socket = private_dir.path().join("butd.sock");
+        let listener = UnixListener::bind(&private_socket)
+            .with_context(|| format!("Could not listen on '{}'", socket.display()))?;
+        std::fs::set_permissions(&private_socket, Permissions::from_mode(0o600))?;
+        std::fs::rename(&private_socket, socket)
+            .with_context(|| format!("Could not listen on '{}'", socket.display()))?;
+        Ok(listener)
+    }
+
+    pub(super) fn cleanup(socket: &Path) {
+        std::fs::remove_file(socket).ok();
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use std::os::unix::fs::PermissionsExt as _;
+
+        use tokio::{
+            io::{AsyncBufReadExt as _, AsyncWriteExt as _, BufReader},
+            net::UnixStream,
+        };
+
+        use super::bind;
+
+        #[tokio::test]
+        async fn only_the_current_user_can_connect() -> anyhow::Result<()> {
+            let tmp = tempfile::tempdir()?;
+            let socket = tmp.path().join("butd.sock");
+            let listener = bind(&socket).await?;
+            assert_eq!(
+                std::fs::metadata(&socket)?.permissions().mode() & 0o777,
+                0o600
+            );
+            assert_eq!(
+                std::fs::read_dir(tmp.path())?.count(),
+                1,
+                "the private directory the socket was bound in is gone"
+            );
+
+            let client = UnixStream::connect(&socket).await?;
+            let (server, _) = listener.accept().await?;
+            super::super::spawn_connection(server);
+            let (reader, mut writer) = client.into_split();
+            writer
+                .write_all(b"{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"nope\"}\n")
+                .await?;
+            let line = BufReader::new(reader)
+                .lines()
+                .next_line()
+                .await?
+                .expect("a response");
+            assert!(line.contains("-32601"), "{line}");
+
+            assert!(
+                bind(&socket).await.is_err(),
+                "a second daemon can't listen on the same socket"
+            );
+            Ok(())
+        }
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use std::path::{Path, PathBuf};
+
+    use anyhow::{Context as _, Result};
+    use tokio::net::windows::named_pipe::ServerOptions;
+
+    pub(super) fn default_socket() -> Result<PathBuf> {
+        // Slashes instead of backslashes to prevent any issues with escaping.
+        Ok(PathBuf::from("//./pipe/gitbutler-butd"))
+    }
+
+    pub(super) async fn listen(socket: &Path) -> Result<()> {
+        // Only local clients may talk to the daemon, as it can do anything the current user can do.
+        let mut options = ServerOptions::new();
+        options.reject_remote_clients(true);
+        let mut server = options
+            .clone()
+            .first_pipe_instance(true)
+            .create(socket)
+            .with_context(|| format!("Could not listen on '{}'", socket.display()))?;
+        loop {
+            server.connect().await?;
+            // The connected server becomes the client connection, so a new one takes its place.
+            let client = std::mem::replace(&mut server, options.create(socket)?);
+            super::spawn_connection(client);
+        }
+    }
+
+    pub(super) fn cleanup(_socket: &Path) {}
+}
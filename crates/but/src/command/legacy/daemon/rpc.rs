@@ -0,0 +1,191 @@
+//! The subset of [JSON-RPC 2.0](https://www.jsonrpc.org/specification) the daemon speaks, with one message per line.
+//!
+//! Batches aren't supported, and the `params` of each method are the same as for the `but-server` route of the same name.
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use super::{methods, warm};
+
+/// The message couldn't be parsed as JSON.
+const PARSE_ERROR: i64 = -32700;
+/// The message isn't a valid request object.
+const INVALID_REQUEST: i64 = -32600;
+/// There is no method with the requested name.
+const METHOD_NOT_FOUND: i64 = -32601;
+/// The parameters don't match what the method expects.
+const INVALID_PARAMS: i64 = -32602;
+/// The method failed, with the serialized [`but_api::json::Error`] as `data`.
+const SERVER_ERROR: i64 = -32000;
+
+#[derive(Debug, Deserialize)]
+struct Request {
+    jsonrpc: String,
+    /// Unset for notifications, which don't get a response.
+    #[serde(default)]
+    id: Option<Value>,
+    method: String,
+    #[serde(default)]
+    params: Option<Value>,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct Response {
+    jsonrpc: &'static str,
+    id: Value,
+    #[serde(flatten)]
+    outcome: Outcome,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Outcome {
+    Result(Value),
+    Error(ErrorObject),
+}
+
+#[derive(Debug, Serialize)]
+struct ErrorObject {
+    code: i64,
+    message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    data: Option<Value>,
+}
+
+impl Response {
+    fn result(id: Value, result: Value) -> Self {
+        Response {
+            jsonrpc: "2.0",
+            id,
+            outcome: Outcome::Result(result),
+        }
+    }
+
+    fn error(id: Value, code: i64, message: impl Into<String>, data: Option<Value>) -> Self {
+        Response {
+            jsonrpc: "2.0",
+            id,
+            outcome: Outcome::Error(ErrorObject {
+                code,
+                message: message.into(),
+                data,
+            }),
+        }
+    }
+
+    /// Turn `err`, as returned by a method, into a response, keeping its code and message for the client.
+    fn from_method_error(id: Value, err: anyhow::Error) -> Self {
+        if let Some(err) = err.downcast_ref::<serde_json::Error>()
+            && err.classify() == serde_json::error::Category::Data
+        {
+            return Response::error(id, INVALID_PARAMS, err.to_string(), None);
+        }
+        let data = json!(but_api::json::Error::from(err));
+        let message = data
+            .get("message")
+            .and_then(Value::as_str)
+            .unwrap_or("Something went wrong")
+            .to_owned();
+        Response::error(id, SERVER_ERROR, message, Some(data))
+    }
+}
+
+/// Handle the JSON-RPC message in `line` and return the response to send back, or `None` if it was a notification.
+pub(crate) async fn handle(line: &str) -> Option<Response> {
+    let message: Value = match serde_json::from_str(line) {
+        Ok(message) => message,
+        Err(err) => {
+            return Some(Response::error(
+                Value::Null,
+                PARSE_ERROR,
+                err.to_string(),
+                None,
+            ));
+        }
+    };
+    let id = message.get("id").cloned().unwrap_or_default();
+    let request: Request = match serde_json::from_value(message) {
+        Ok(request) if request.jsonrpc == "2.0" => request,
+        Ok(_) => {
+            return Some(Response::error(
+                id,
+                INVALID_REQUEST,
+                "Only JSON-RPC 2.0 is supported",
+                None,
+            ));
+        }
+        Err(err) => return Some(Response::error(id, INVALID_REQUEST, err.to_string(), None)),
+    };
+
+    let Some(method) = methods::find(&request.method) else {
+        return request.id.map(|id| {
+            Response::error(
+                id,
+                METHOD_NOT_FOUND,
+                format!("Method '{}' doesn't exist", request.method),
+                None,
+            )
+        });
+    };
+    // Methods without parameters still expect an object.
+    let params = request
+        .params
+        .filter(|params| !params.is_null())
+        .unwrap_or_else(|| json!({}));
+    warm::warm_up_project_of(&params);
+    let outcome = method.call(params).await;
+    let id = request.id?;
+    Some(match outcome {
+        Ok(result) => Response::result(id, result),
+        Err(err) => Response::from_method_error(id, err),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{Value, json};
+
+    use super::handle;
+
+    async fn call(line: &str) -> Value {
+        json!(handle(line).await.expect("not a notification"))
+    }
+
+    #[tokio::test]
+    async fn malformed_messages_are_rejected() {
+        let response = call("{").await;
+        assert_eq!(response["id"], Value::Null);
+        assert_eq!(response["error"]["code"], -32700);
+
+        let response = call(r#"{"jsonrpc":"1.0","id":1,"method":"stacks"}"#).await;
+        assert_eq!(response["id"], 1);
+        assert_eq!(response["error"]["code"], -32600);
+
+        let response = call(r#"{"jsonrpc":"2.0","id":"a","params":{}}"#).await;
+        assert_eq!(response["id"], "a");
+        assert_eq!(response["error"]["code"], -32600, "the method is required");
+    }
+
+    #[tokio::test]
+    async fn unknown_methods_are_reported_unless_notified() {
+        let response = call(r#"{"jsonrpc":"2.0","id":1,"method":"does_not_exist"}"#).await;
+        assert_eq!(response["error"]["code"], -32601);
+        assert!(response.get("result").is_none());
+
+        assert!(
+            handle(r#"{"jsonrpc":"2.0","method":"does_not_exist"}"#)
+                .await
+                .is_none(),
+            "notifications never get a response"
+        );
+    }
+
+    #[tokio::test]
+    async fn invalid_params_are_reported() {
+        let response = call(r#"{"jsonrpc":"2.0","id":2,"method":"stacks"}"#).await;
+        assert_eq!(response["id"], 2);
+        assert_eq!(
+            response["error"]["code"], -32602,
+            "the project id is missing"
+        );
+    }
+}
@@ -0,0 +1,67 @@
+//! State that is kept warm between requests, which is what makes the daemon faster than running `but` for each command.
+//!
+//! Diffs and branch divergences are cached for as long as the process lives, keyed by what they were computed from.
+//! When a project is used for the first time, its worktree is diffed in the background to fill these caches,
+//! so later requests only compute what changed since.
+use std::{collections::BTreeSet, sync::Mutex};
+
+use serde_json::{Value, json};
+
+/// The projects that were warmed up already, by their id.
+static WARM_PROJECTS: Mutex<BTreeSet<String>> = Mutex::new(BTreeSet::new());
+
+/// Warm up the project that `params` of a request are for, in the background, unless it was warmed up already.
+pub(crate) fn warm_up_project_of(params: &Value) {
+    let Some(project_id) =
+        first_use_of_project(&mut WARM_PROJECTS.lock().expect("not poisoned"), params)
+    else {
+        return;
+    };
+    tokio::task::spawn_blocking(move || {
+        let params = json!({ "projectId": project_id });
+        if let Err(err) = but_api::legacy::diff::changes_in_worktree_cmd(params) {
+            tracing::debug!(?err, project_id, "Could not warm up project");
+        }
+    });
+}
+
+/// Return the id of the project that `params` are for if it isn't in `warm_projects` yet, and add it.
+fn first_use_of_project(warm_projects: &mut BTreeSet<String>, params: &Value) -> Option<String> {
+    let project_id = params.get("projectId")?.as_str()?;
+    warm_projects
+        .insert(project_id.to_owned())
+        .then(|| project_id.to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use serde_json::json;
+
+    use super::first_use_of_project;
+
+    #[test]
+    fn projects_are_warmed_up_once() {
+        let mut warm = BTreeSet::new();
+        let params = json!({ "projectId": "a", "stackId": "s" });
+        assert_eq!(
+            first_use_of_project(&mut warm, &params).as_deref(),
+            Some("a")
+        );
+        assert_eq!(
+            first_use_of_project(&mut warm, &params),
+            None,
+            "already warm"
+        );
+        assert_eq!(
+            first_use_of_project(&mut warm, &json!({ "projectId": "b" })).as_deref(),
+            Some("b")
+        );
+        assert_eq!(
+            first_use_of_project(&mut warm, &json!({})),
+            None,
+            "requests without project don't warm anything up"
+        );
+    }
+}
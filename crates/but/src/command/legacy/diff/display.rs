@@ -90,6 +90,30 @@ fn format_patch(patch: &UnifiedPatch, render_header: impl Fn(&mut String)) -> St
                 .dimmed()
             ));
         }
+        UnifiedPatch::Submodule { previous_id, id } => {
+            render_header(&mut output);
+            let short = |id: &Option<gix::ObjectId>| {
+                id.map_or_else(|| "none".to_owned(), |id| id.to_hex_with_len(7).to_string())
+            };
+            output.push_str(&format!(
+                "   {}\n",
+                format!("Submodule {} → {}", short(previous_id), short(id)).dimmed()
+            ));
+        }
+        UnifiedPatch::LfsPointer { previous, current } => {
+            render_header(&mut output);
+            let short = |pointer: &Option<but_core::lfs::LfsPointer>| {
+                pointer.as_ref().map_or_else(
+                    || "none".to_owned(),
+                    |pointer| format!("{} ({} bytes)", &pointer.oid[..7], pointer.size),
+                )
+            };
+            output.push_str(&format!(
+                "   {}
+",
+                format!("LFS object {} → {}", short(previous), short(current)).dimmed()
+            ));
+        }
         UnifiedPatch::Patch {
             hunks,
             is_result_of_binary_to_text_conversion,
@@ -209,6 +233,8 @@ impl DiffDisplay for HunkAssignment {
                 new_start: header.new_start,
                 new_lines: header.new_lines,
                 diff: diff.clone(),
+                encoding: None,
+                word_diff: None,
             };
             output.push_str(&fmt_hunk(&hunk));
         } else if self.hunk_header.is_none() {
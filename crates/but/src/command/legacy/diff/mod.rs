@@ -2,7 +2,7 @@ use but_ctx::Context;
 
 use crate::{CliId, IdMap, command::legacy::diff::show::Filter, utils::OutputChannel};
 
-mod display;
+pub(crate) mod display;
 mod show;
 
 // Note: To use the DiffDisplay trait in other modules,
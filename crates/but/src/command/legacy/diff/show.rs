@@ -72,7 +72,7 @@ pub(crate) fn commit(
     let result = but_api::diff::commit_details(ctx, id, ComputeLineStats::No)?;
     for change in result.diff_with_first_parent {
         if path.as_ref().is_none_or(|p| p == &change.path) {
-            let patch = but_api::legacy::diff::tree_change_diffs(ctx, change.clone().into())
+            let patch = but_api::legacy::diff::tree_change_diffs(ctx, change.clone().into(), None)
                 .ok()
                 .flatten();
             let diff = TreeChangeWithPatch::new(change.into(), patch);
@@ -89,7 +89,7 @@ pub(crate) fn branch(
 ) -> anyhow::Result<()> {
     let result = but_api::branch::branch_diff(ctx, short_name)?;
     for change in result.changes {
-        let patch = but_api::legacy::diff::tree_change_diffs(ctx, change.clone())
+        let patch = but_api::legacy::diff::tree_change_diffs(ctx, change.clone(), None)
             .ok()
             .flatten();
 
@@ -68,6 +68,7 @@ pub fn handle(ctx: &mut Context, out: &mut OutputChannel, id: &str) -> Result<()
                         } else {
                             assignment.hunk_header.into_iter().collect()
                         },
+                        mode_flags: None,
                     });
                 }
             }
@@ -98,6 +99,7 @@ pub fn handle(ctx: &mut Context, out: &mut OutputChannel, id: &str) -> Result<()
                         } else {
                             assignment.hunk_header.into_iter().collect()
                         },
+                        mode_flags: None,
                     });
                 }
             }
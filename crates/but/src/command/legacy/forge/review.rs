@@ -236,6 +236,14 @@ pub async fn handle_multiple_branches_in_workspace(
         out.write_value(overall_outcome)?;
     } else if let Some(out) = out.for_human() {
         display_review_publication_summary(overall_outcome, out)?;
+    } else if let Some(out) = out.for_shell() {
+        for review in overall_outcome
+            .published
+            .iter()
+            .chain(&overall_outcome.already_existing)
+        {
+            writeln!(out, "{}", review.html_url)?;
+        }
     }
 
     Ok(())
@@ -376,6 +384,7 @@ async fn publish_reviews_for_branch_and_dependents(
         branch_name.to_string(),
         run_hooks,
         vec![],
+        vec![],
     )?;
 
     if let Some(out) = out.for_human() {
@@ -427,6 +436,17 @@ async fn publish_reviews_for_branch_and_dependents(
         }
     }
 
+    if let Some(stack_id) = stack_entry.id
+        && let Err(err) = but_api::legacy::forge::update_stack_footers(project.id, stack_id).await
+        && let Some(out) = out.for_human()
+    {
+        writeln!(
+            out,
+            "  {} Failed to update the stack footers of reviews: {err:#}",
+            "⚠".yellow()
+        )?;
+    }
+
     let outcome = PublishReviewsOutcome {
         published: newly_published,
         already_existing,
@@ -0,0 +1,68 @@
+use but_ctx::Context;
+use but_workspace::legacy::IntegrityIssue;
+use colored::Colorize;
+
+use crate::utils::OutputChannel;
+
+pub(crate) fn fsck(ctx: &mut Context, repair: bool, out: &mut OutputChannel) -> anyhow::Result<()> {
+    let project_id = ctx.legacy_project.id;
+    if !repair {
+        let issues = but_api::legacy::workspace::fsck(project_id)?;
+        if let Some(out) = out.for_json() {
+            out.write_value(&issues)?;
+        } else if let Some(out) = out.for_human() {
+            if issues.is_empty() {
+                writeln!(out, "{} The workspace is consistent.", "✓".green().bold())?;
+            } else {
+                writeln!(out, "Found {} issues:", issues.len().to_string().yellow())?;
+                for issue in &issues {
+                    writeln!(out, "  {} {}", "✗".red(), describe(issue))?;
+                }
+                writeln!(out)?;
+                writeln!(out, "{}", "Run `but fsck --repair` to fix them.".dimmed())?;
+            }
+        }
+        return Ok(());
+    }
+
+    let outcome = but_api::legacy::workspace::repair_workspace(project_id)?;
+    if let Some(out) = out.for_json() {
+        out.write_value(&outcome)?;
+    } else if let Some(out) = out.for_human() {
+        if outcome.repaired.is_empty() && outcome.remaining.is_empty() {
+            writeln!(out, "{} The workspace is consistent.", "✓".green().bold())?;
+        }
+        for issue in &outcome.repaired {
+            writeln!(out, "  {} Fixed: {}", "✓".green(), describe(issue))?;
+        }
+        for issue in &outcome.remaining {
+            writeln!(out, "  {} Not fixed: {}", "✗".red(), describe(issue))?;
+        }
+    }
+    Ok(())
+}
+
+fn describe(issue: &IntegrityIssue) -> String {
+    match issue {
+        IntegrityIssue::NotInWorkspace { head } => format!(
+            "HEAD points to {} instead of gitbutler/workspace",
+            head.as_deref().unwrap_or("a detached commit")
+        ),
+        IntegrityIssue::MissingTargetRef { ref_name } => {
+            format!("the target branch {ref_name} doesn't exist")
+        }
+        IntegrityIssue::WorkspaceCommitMissing { tip } => format!(
+            "gitbutler/workspace points to {} instead of a workspace commit",
+            tip.to_hex_with_len(7)
+        ),
+        IntegrityIssue::WorkspaceCommitParentsMismatch { .. } => {
+            "the workspace commit doesn't merge the heads of all stacks".into()
+        }
+        IntegrityIssue::MissingBranchRef { branch_name, .. } => {
+            format!("the branch {branch_name} has no reference")
+        }
+        IntegrityIssue::DanglingAssignment { stack_id, path } => {
+            format!("{path} is assigned to the unknown stack {stack_id}")
+        }
+    }
+}
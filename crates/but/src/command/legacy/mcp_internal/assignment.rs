@@ -0,0 +1,232 @@
+use std::path::Path;
+
+use but_core::{HunkHeader, ref_metadata::StackId};
+use but_ctx::Context;
+use but_hunk_assignment::{HunkAssignment, HunkAssignmentRequest};
+use rmcp::schemars;
+use serde::{Deserialize, Serialize};
+
+use crate::command::legacy::mcp_internal::project;
+
+/// Assign the uncommitted changes picked by `selections` to the stack that contains the branch named `branch_name`,
+/// or make them unassigned if it's `None`.
+pub fn assign_hunks(
+    project_dir: &Path,
+    branch_name: Option<String>,
+    selections: Vec<HunkSelection>,
+) -> anyhow::Result<AssignOutcome> {
+    let project = project::project_from_path(project_dir)?;
+    let mut ctx = Context::new_from_legacy_project(project.clone())?;
+
+    let stack_id = branch_name
+        .map(|name| stack_id_by_branch_name(&project, &name))
+        .transpose()?;
+
+    let changes = but_core::diff::ui::worktree_changes_by_worktree_dir(
+        ctx.legacy_project.worktree_dir()?.into(),
+    )?
+    .changes;
+    let guard = ctx.shared_worktree_access();
+    let repo = ctx.repo.get()?.clone();
+    let (_, workspace) = ctx.workspace_and_read_only_meta_from_head(guard.read_permission())?;
+    let (assignments, _assignments_error) = but_hunk_assignment::assignments_with_fallback(
+        &mut ctx,
+        &repo,
+        &workspace,
+        false,
+        Some(changes),
+        None,
+    )?;
+
+    let mut requests = Vec::new();
+    for selection in &selections {
+        let selected = assignments
+            .iter()
+            .filter(|assignment| selection.matches(assignment))
+            .collect::<Vec<_>>();
+        if selected.is_empty() {
+            anyhow::bail!(
+                "No uncommitted changes in '{}' match the selection",
+                selection.path
+            );
+        }
+        requests.extend(
+            selected
+                .into_iter()
+                .map(|assignment| HunkAssignmentRequest {
+                    hunk_header: assignment.hunk_header,
+                    path_bytes: assignment.path_bytes.clone(),
+                    stack_id,
+                }),
+        );
+    }
+
+    let rejections =
+        but_hunk_assignment::assign(&mut ctx, &repo, &workspace, requests.clone(), None)?;
+    let is_rejected = |request: &HunkAssignmentRequest| {
+        rejections.iter().any(|rejection| {
+            rejection.request.path_bytes == request.path_bytes
+                && rejection.request.hunk_header == request.hunk_header
+        })
+    };
+    let (rejected, assigned): (Vec<_>, Vec<_>) = requests.into_iter().partition(is_rejected);
+    Ok(AssignOutcome {
+        assigned: assigned.into_iter().map(AssignedHunk::from).collect(),
+        rejected: rejected.into_iter().map(AssignedHunk::from).collect(),
+    })
+}
+
+/// Find the id of the stack in the workspace that contains the branch named `branch_name`.
+fn stack_id_by_branch_name(
+    project: &gitbutler_project::Project,
+    branch_name: &str,
+) -> anyhow::Result<StackId> {
+    gitbutler_stack::VirtualBranchesHandle::new(project.gb_dir())
+        .list_stacks_in_workspace()?
+        .iter()
+        .find(|s| s.heads(false).iter().any(|head| head == branch_name))
+        .map(|s| s.id)
+        .ok_or_else(|| anyhow::anyhow!("No branch named '{branch_name}' in the workspace"))
+}
+
+/// A file, or the hunks of a file that touch a range of lines, to assign.
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct HunkSelection {
+    #[schemars(description = "The path of the file with uncommitted changes")]
+    pub path: String,
+    #[schemars(description = "The range of lines in the current version of the file.
+        Only the hunks touching these lines are assigned. If not provided, all hunks of the file are assigned.")]
+    pub lines: Option<LineRange>,
+}
+
+impl HunkSelection {
+    /// Return `true` if `assignment` is in the selected file and touches the selected lines.
+    fn matches(&self, assignment: &HunkAssignment) -> bool {
+        if assignment.path != self.path {
+            return false;
+        }
+        match (&self.lines, &assignment.hunk_header) {
+            (Some(lines), Some(header)) => {
+                let first = header.new_start;
+                let last = first + header.new_lines.saturating_sub(1);
+                lines.start <= last && lines.end >= first
+            }
+            (None, _) | (_, None) => true,
+        }
+    }
+}
+
+/// An inclusive range of 1-based line numbers.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LineRange {
+    #[schemars(description = "The first line of the range, starting at 1")]
+    pub start: u32,
+    #[schemars(description = "The last line of the range, inclusive")]
+    pub end: u32,
+}
+
+/// The hunks that were assigned, and the ones that couldn't be.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AssignOutcome {
+    /// The hunks that are now assigned as requested.
+    pub assigned: Vec<AssignedHunk>,
+    /// The hunks that stay where they are as they depend on commits in the stack they are assigned to.
+    pub rejected: Vec<AssignedHunk>,
+}
+
+/// A hunk of a file, or the whole file if it has no hunks.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AssignedHunk {
+    /// The path of the file.
+    pub path: String,
+    /// The header of the hunk, or `None` for the whole file.
+    pub hunk_header: Option<HunkHeader>,
+}
+
+impl From<HunkAssignmentRequest> for AssignedHunk {
+    fn from(request: HunkAssignmentRequest) -> Self {
+        AssignedHunk {
+            path: request.path_bytes.to_string(),
+            hunk_header: request.hunk_header,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use but_core::HunkHeader;
+    use but_hunk_assignment::HunkAssignment;
+
+    use super::{HunkSelection, LineRange};
+
+    fn hunk(path: &str, new_start: u32, new_lines: u32) -> HunkAssignment {
+        HunkAssignment {
+            id: None,
+            hunk_header: Some(HunkHeader {
+                old_start: new_start,
+                old_lines: 1,
+                new_start,
+                new_lines,
+            }),
+            path: path.into(),
+            path_bytes: path.into(),
+            stack_id: None,
+            hunk_locks: None,
+            line_nums_added: None,
+            line_nums_removed: None,
+            diff: None,
+            mode_flags: None,
+        }
+    }
+
+    fn selection(path: &str, lines: Option<(u32, u32)>) -> HunkSelection {
+        HunkSelection {
+            path: path.into(),
+            lines: lines.map(|(start, end)| LineRange { start, end }),
+        }
+    }
+
+    #[test]
+    fn ranges_select_the_hunks_they_overlap() {
+        // Lines 10 to 14.
+        let hunk = hunk("file", 10, 5);
+        for (range, expected) in [
+            ((10, 14), true),
+            ((8, 10), true),
+            ((14, 20), true),
+            ((12, 12), true),
+            ((1, 100), true),
+            ((1, 9), false),
+            ((15, 20), false),
+        ] {
+            assert_eq!(
+                selection("file", Some(range)).matches(&hunk),
+                expected,
+                "{range:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn ranges_never_select_hunks_of_other_files() {
+        let hunk = hunk("file", 10, 5);
+        assert!(!selection("other", Some((10, 14))).matches(&hunk));
+        assert!(!selection("other", None).matches(&hunk));
+    }
+
+    #[test]
+    fn whole_files_are_selected_without_a_range_or_hunks() {
+        assert!(selection("file", None).matches(&hunk("file", 10, 5)));
+
+        let mut binary = hunk("file", 10, 5);
+        binary.hunk_header = None;
+        assert!(
+            selection("file", Some((1, 2))).matches(&binary),
+            "files without hunks can only be assigned as a whole"
+        );
+    }
+}
@@ -158,6 +158,7 @@ impl From<DiffSpec> for but_core::DiffSpec {
             previous_path: spec.previous_path.map(BString::from),
             path: BString::from(spec.path),
             hunk_headers: vec![],
+            mode_flags: None,
         }
     }
 }
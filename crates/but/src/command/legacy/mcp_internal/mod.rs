@@ -17,6 +17,7 @@ use rmcp::{
 
 use crate::utils::{BackgroundMetrics, metrics};
 
+pub mod assignment;
 pub mod commit;
 pub mod project;
 pub mod stack;
@@ -86,6 +87,68 @@ impl Mcp {
         )?]))
     }
 
+    #[tool(description = "List the stacks in the workspace.
+        Each stack has an id and its branches, from the top-most branch to the one closest to the target branch.")]
+    pub fn list_stacks(
+        &self,
+        params: Parameters<ListStacksParams>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        let client_info = self
+            .client_info
+            .lock()
+            .map_err(|e| rmcp::ErrorData::internal_error(e.to_string(), None))?
+            .clone();
+
+        let start_time = std::time::Instant::now();
+        let project_path = std::path::PathBuf::from(&params.0.current_working_directory);
+        let stacks = stack::list_stacks(&project_path)
+            .map_err(|e| rmcp::ErrorData::internal_error(e.to_string(), None))?;
+
+        let mut event: metrics::Event = metrics::EventKind::McpInternal.into();
+        event.insert_prop("endpoint", "list_stacks");
+        event.insert_prop("durationMs", start_time.elapsed().as_millis());
+        event.insert_prop("clientName", client_info.clone().map(|i| i.name));
+        event.insert_prop("clientVersion", client_info.clone().map(|i| i.version));
+        self.metrics.capture(event);
+
+        Ok(CallToolResult::success(vec![rmcp::model::Content::json(
+            stacks,
+        )?]))
+    }
+
+    #[tool(
+        description = "Assign uncommitted changes to the stack of a branch, so they can be committed there later.
+        Whole files can be assigned, or only the hunks of a file that touch a range of lines.
+        Hunks that depend on commits in the stack they are assigned to can't be moved and are returned as rejected."
+    )]
+    pub fn assign_hunks(
+        &self,
+        params: Parameters<AssignHunksParams>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        let client_info = self
+            .client_info
+            .lock()
+            .map_err(|e| rmcp::ErrorData::internal_error(e.to_string(), None))?
+            .clone();
+
+        let start_time = std::time::Instant::now();
+        let params = params.0;
+        let project_path = std::path::PathBuf::from(&params.current_working_directory);
+        let outcome = assignment::assign_hunks(&project_path, params.branch_name, params.changes)
+            .map_err(|e| rmcp::ErrorData::internal_error(e.to_string(), None))?;
+
+        let mut event: metrics::Event = metrics::EventKind::McpInternal.into();
+        event.insert_prop("endpoint", "assign_hunks");
+        event.insert_prop("durationMs", start_time.elapsed().as_millis());
+        event.insert_prop("clientName", client_info.clone().map(|i| i.name));
+        event.insert_prop("clientVersion", client_info.clone().map(|i| i.version));
+        self.metrics.capture(event);
+
+        Ok(CallToolResult::success(vec![rmcp::model::Content::json(
+            outcome,
+        )?]))
+    }
+
     #[tool(description = "Commit changes to the repository.
         Applies the given diff spec and creates a commit with the provided message.")]
     pub fn commit(
@@ -227,6 +290,29 @@ pub struct ProjectStatusParams {
     pub current_working_directory: String,
 }
 
+#[derive(Debug, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ListStacksParams {
+    #[schemars(description = "The full root path of the Git project to list the stacks of")]
+    pub current_working_directory: String,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AssignHunksParams {
+    #[schemars(description = "The full root path of the Git project to assign changes in")]
+    pub current_working_directory: String,
+
+    #[schemars(
+        description = "The name of a branch whose stack the changes are assigned to.
+        If not provided, the changes become unassigned."
+    )]
+    pub branch_name: Option<String>,
+
+    #[schemars(description = "The files, or ranges of lines in files, whose changes to assign")]
+    pub changes: Vec<assignment::HunkSelection>,
+}
+
 #[derive(Debug, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct CommitParams {
@@ -347,7 +433,7 @@ impl ServerHandler for Mcp {
                 let prompt  = "Handle the file changes following the steps below:
 1. Take a look at the **project status**. Understand the branches applied (if any), the uncommitted file changes and the files assigned to them.
 2. Determine which file changes should be committed together. Try to be granular and commit only the changes that are related to each other.
-3. Determine which file changes belong to which branch. Do this by looking at the file changes and the branch names and descriptions. If no branch matches the changes create a new branch with a descriptive name and a detailed description.
+3. Determine which file changes belong to which branch. Do this by looking at the file changes and the branch names and descriptions. If no branch matches the changes create a new branch with a descriptive name and a detailed description. Assign the changes to the stack of their branch, down to the lines that belong together.
 4. Determine if some changes should be **amended** to an existing commit. Do this by looking a the **branch details** and its commits. If so, use the amend tool to update the commit with the new changes. Otherwise, use the commit tool to create a new commit with the changes.
 4. Be descriptive in your commit messages. Explain what the changes are, not why.
 5. If you are not sure about the changes, ask for clarification. Otherwise, proceed with committing the changes.
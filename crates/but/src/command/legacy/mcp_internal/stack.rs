@@ -19,6 +19,15 @@ pub fn branch_details(ref_name: &str, current_dir: &Path) -> anyhow::Result<Bran
     Ok(parse_branch_details(&repo, details))
 }
 
+/// List the stacks in the workspace, each with its branches from top to bottom.
+pub fn list_stacks(
+    current_dir: &Path,
+) -> anyhow::Result<Vec<but_workspace::legacy::ui::StackEntry>> {
+    let project = super::project::project_from_path(current_dir)?;
+    let ctx = Context::new_from_legacy_project(project.clone())?;
+    crate::legacy::commits::stacks(&ctx)
+}
+
 /// Create a new stack containing only a branch with the given name.
 pub fn create_stack_with_branch(
     name: &str,
@@ -4,11 +4,14 @@
 pub mod absorb;
 pub mod actions;
 pub mod ai;
+pub mod assign;
 pub mod branch;
 pub mod commit;
+pub mod daemon;
 pub mod diff;
 pub mod discard;
 pub mod forge;
+pub mod fsck;
 pub mod mark;
 pub mod mcp;
 pub mod mcp_internal;
@@ -23,5 +26,6 @@ pub mod rub;
 pub mod setup;
 pub mod show;
 pub mod status;
+pub mod support;
 pub mod teardown;
 pub mod worktree;
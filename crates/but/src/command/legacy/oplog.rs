@@ -1,9 +1,16 @@
-use but_oxidize::TimeExt;
+use anyhow::bail;
+use but_oxidize::{OidExt as _, TimeExt};
 use colored::Colorize;
-use gitbutler_oplog::entry::{OperationKind, Snapshot};
+use gitbutler_oplog::{
+    entry::{OperationKind, Snapshot},
+    stack_diff::StackDiff,
+};
 use gix::date::time::CustomFormat;
 
-use crate::utils::{Confirm, ConfirmDefault, OutputChannel};
+use crate::{
+    command::legacy::status::{path_with_color_ui, status_letter_ui},
+    utils::{Confirm, ConfirmDefault, OutputChannel},
+};
 
 pub const ISO8601_NO_TZ: CustomFormat = CustomFormat::new("%Y-%m-%d %H:%M:%S");
 
@@ -12,13 +19,15 @@ pub const ISO8601_NO_TZ: CustomFormat = CustomFormat::new("%Y-%m-%d %H:%M:%S");
 pub enum OplogFilter {
     /// Show only on-demand snapshot entries
     Snapshot,
+    /// Show only named snapshots
+    Named,
 }
 
 impl OplogFilter {
     /// Convert the filter to a list of OperationKind to include
     fn to_include_kinds(self) -> Vec<OperationKind> {
         match self {
-            OplogFilter::Snapshot => vec![OperationKind::OnDemandSnapshot],
+            OplogFilter::Snapshot | OplogFilter::Named => vec![OperationKind::OnDemandSnapshot],
         }
     }
 }
@@ -43,13 +52,17 @@ pub(crate) fn show_oplog(
         None
     };
 
-    let snapshots = but_api::legacy::oplog::list_snapshots(
-        ctx.legacy_project.id,
-        20,
-        since_sha,
-        None,
-        include_kind,
-    )?;
+    let snapshots = if matches!(filter, Some(OplogFilter::Named)) {
+        but_api::legacy::oplog::list_named_snapshots(ctx.legacy_project.id, None)?
+    } else {
+        but_api::legacy::oplog::list_snapshots(
+            ctx.legacy_project.id,
+            20,
+            since_sha,
+            None,
+            include_kind,
+        )?
+    };
 
     if snapshots.is_empty() {
         if let Some(out) = out.for_json() {
@@ -103,7 +116,9 @@ pub(crate) fn show_oplog(
                 };
                 // For OnDemandSnapshot, show the message (body) if available
                 // For Discard, show file names from trailers if available
-                let display_title = if details.operation == OperationKind::OnDemandSnapshot {
+                let display_title = if let Some(label) = &details.label {
+                    format!("🏷 {label}")
+                } else if details.operation == OperationKind::OnDemandSnapshot {
                     details
                         .body
                         .as_ref()
@@ -173,19 +188,128 @@ fn snapshot_time_string(snapshot: &Snapshot) -> String {
         .unwrap_or_else(|_| time.seconds.to_string())
 }
 
+/// Resolve `spec` to an oplog snapshot, either as SHA or as label of a named snapshot.
+fn resolve_snapshot_or_label(ctx: &but_ctx::Context, spec: &str) -> anyhow::Result<gix::ObjectId> {
+    let repo = ctx.repo.get()?;
+    Ok(match repo.rev_parse_single(spec) {
+        Ok(id) => id.detach(),
+        Err(err) => but_api::legacy::oplog::list_named_snapshots(
+            ctx.legacy_project.id,
+            Some(spec.to_owned()),
+        )?
+        .first()
+        .map(|snapshot| snapshot.commit_id.to_gix())
+        .ok_or(err)?,
+    })
+}
+
+/// Show the details of the snapshot identified by `spec`, along with the files that changed in it.
+pub(crate) fn show_snapshot(
+    ctx: &mut but_ctx::Context,
+    out: &mut OutputChannel,
+    spec: &str,
+) -> anyhow::Result<()> {
+    let commit_id = resolve_snapshot_or_label(ctx, spec)?;
+    let snapshot =
+        but_api::legacy::oplog::get_snapshot(ctx.legacy_project.id, commit_id.to_string())?;
+    let files =
+        but_api::legacy::oplog::snapshot_diff(ctx.legacy_project.id, commit_id.to_string())?;
+
+    if let Some(out) = out.for_json() {
+        out.write_value(serde_json::json!({
+            "snapshot": snapshot,
+            "files": files,
+        }))?;
+    } else if let Some(out) = out.for_human() {
+        let commit_sha_string = commit_id.to_string();
+        writeln!(
+            out,
+            "{} {}{}",
+            "Snapshot".blue().bold(),
+            commit_sha_string[..7].blue().underline(),
+            commit_sha_string[7..12].blue().dimmed()
+        )?;
+        writeln!(out, "{}", "─".repeat(50).dimmed())?;
+        match &snapshot.details {
+            Some(details) => {
+                writeln!(out, "Operation: {}", details.title.green())?;
+                if let Some(label) = &details.label {
+                    writeln!(out, "Label:     {}", label.cyan())?;
+                }
+                writeln!(
+                    out,
+                    "Time:      {}",
+                    snapshot_time_string(&snapshot).dimmed()
+                )?;
+                if let Some(body) = details.body.as_ref().filter(|body| !body.is_empty()) {
+                    writeln!(out, "\n{body}")?;
+                }
+            }
+            None => {
+                writeln!(out, "Operation: {}", "Unknown operation".yellow())?;
+                writeln!(
+                    out,
+                    "Time:      {}",
+                    snapshot_time_string(&snapshot).dimmed()
+                )?;
+            }
+        }
+        writeln!(out)?;
+        if files.is_empty() {
+            writeln!(out, "No files changed.")?;
+        } else {
+            write_worktree_changes(out, &files)?;
+        }
+    }
+    Ok(())
+}
+
 pub(crate) fn restore_to_oplog(
     ctx: &mut but_ctx::Context,
     out: &mut OutputChannel,
     oplog_sha: &str,
     force: bool,
+    dry_run: bool,
 ) -> anyhow::Result<()> {
-    let repo = ctx.repo.get()?;
-    let commit_id = repo.rev_parse_single(oplog_sha)?.detach();
+    let commit_id = resolve_snapshot_or_label(ctx, oplog_sha)?;
     let target_snapshot =
         &but_api::legacy::oplog::get_snapshot(ctx.legacy_project.id, commit_id.to_string())?;
 
     let commit_sha_string = commit_id.to_string();
 
+    if dry_run {
+        let delta = but_api::legacy::oplog::snapshot_delta(
+            ctx.legacy_project.id,
+            commit_sha_string.clone(),
+            None,
+        )?;
+        if let Some(out) = out.for_json() {
+            out.write_value(&delta)?;
+        } else if let Some(out) = out.for_human() {
+            writeln!(
+                out,
+                "{} {}",
+                "Restoring would undo these changes made since"
+                    .blue()
+                    .bold(),
+                commit_sha_string[..7].blue().underline()
+            )?;
+            writeln!(out, "{}", "─".repeat(50).dimmed())?;
+            if delta.workspace.is_empty() && delta.worktree_changes.is_empty() {
+                writeln!(out, "Nothing changed, restoring would have no effect.")?;
+                return Ok(());
+            }
+            write_stack_diff(out, &delta.workspace)?;
+            if !delta.worktree_changes.is_empty() {
+                if !delta.workspace.is_empty() {
+                    writeln!(out)?;
+                }
+                write_worktree_changes(out, &delta.worktree_changes)?;
+            }
+        }
+        return Ok(());
+    }
+
     let target_operation = target_snapshot
         .details
         .as_ref()
@@ -244,23 +368,40 @@ pub(crate) fn restore_to_oplog(
     Ok(())
 }
 
+/// Undo the last `steps` operations by restoring the snapshot that many entries before the most recent one.
 pub(crate) fn undo_last_operation(
     ctx: &mut but_ctx::Context,
     out: &mut OutputChannel,
+    steps: usize,
 ) -> anyhow::Result<()> {
-    // Get the last two snapshots - restore to the second one back
+    if steps == 0 {
+        bail!("Need to undo at least one operation");
+    }
+    // Get the last `steps + 1` snapshots - restore to the oldest one of them
     let snapshots =
-        but_api::legacy::oplog::list_snapshots(ctx.legacy_project.id, 2, None, None, None)?;
+        but_api::legacy::oplog::list_snapshots(ctx.legacy_project.id, steps + 1, None, None, None)?;
 
-    if snapshots.len() < 2 {
+    if snapshots.len() < steps + 1 {
         if let Some(out) = out.for_human() {
-            writeln!(out, "{}", "No previous operations to undo.".yellow())?;
+            if snapshots.len() < 2 {
+                writeln!(out, "{}", "No previous operations to undo.".yellow())?;
+            } else {
+                writeln!(
+                    out,
+                    "{}",
+                    format!(
+                        "Can't undo {steps} operations, there are only {} previous ones.",
+                        snapshots.len() - 1
+                    )
+                    .yellow()
+                )?;
+            }
         }
         return Ok(());
     }
 
     // TODO: Why the second most recent one, and not use the most recent one?
-    let target_snapshot = &snapshots[1];
+    let target_snapshot = &snapshots[steps];
 
     let target_operation = target_snapshot
         .details
@@ -311,14 +452,23 @@ pub(crate) fn create_snapshot(
     ctx: &mut but_ctx::Context,
     out: &mut OutputChannel,
     message: Option<&str>,
+    label: Option<&str>,
 ) -> anyhow::Result<()> {
-    let snapshot_id =
-        but_api::legacy::oplog::create_snapshot(ctx.legacy_project.id, message.map(String::from))?;
+    let snapshot_id = match label {
+        Some(label) => {
+            but_api::legacy::oplog::create_named_snapshot(ctx.legacy_project.id, label.to_owned())?
+        }
+        None => but_api::legacy::oplog::create_snapshot(
+            ctx.legacy_project.id,
+            message.map(String::from),
+        )?,
+    };
 
     if let Some(out) = out.for_json() {
         out.write_value(serde_json::json!({
             "snapshot_id": snapshot_id.to_string(),
             "message": message.unwrap_or(""),
+            "label": label,
             "operation": "create_snapshot"
         }))?;
     } else if let Some(out) = out.for_human() {
@@ -327,6 +477,9 @@ pub(crate) fn create_snapshot(
         if let Some(msg) = message {
             writeln!(out, "  Message: {}", msg.cyan())?;
         }
+        if let Some(label) = label {
+            writeln!(out, "  Label: {}", label.cyan())?;
+        }
 
         writeln!(
             out,
@@ -345,3 +498,180 @@ pub(crate) fn create_snapshot(
 
     Ok(())
 }
+
+/// Resolve `spec` to an oplog snapshot, either as SHA, or as date for the latest snapshot taken before it.
+///
+/// Revisions that aren't snapshots of the oplog are rejected.
+fn resolve_snapshot(ctx: &but_ctx::Context, spec: &str) -> anyhow::Result<String> {
+    let snapshots = but_api::legacy::oplog::list_snapshots(
+        ctx.legacy_project.id,
+        usize::MAX,
+        None,
+        None,
+        None,
+    )?;
+    if let Ok(id) = ctx.repo.get()?.rev_parse_single(spec) {
+        let id = id.detach();
+        return snapshots
+            .iter()
+            .find(|snapshot| snapshot.commit_id.to_gix() == id)
+            .map(|snapshot| snapshot.commit_id.to_string())
+            .ok_or_else(|| anyhow::anyhow!("'{spec}' is not an oplog snapshot"));
+    }
+    let time = gix::date::parse(spec, Some(std::time::SystemTime::now()))
+        .map_err(|_| anyhow::anyhow!("'{spec}' is neither an oplog SHA nor a date"))?;
+    snapshots
+        .into_iter()
+        .find(|snapshot| snapshot.created_at.seconds() <= time.seconds)
+        .map(|snapshot| snapshot.commit_id.to_string())
+        .ok_or_else(|| anyhow::anyhow!("No oplog entry found before {spec}"))
+}
+
+pub(crate) fn show_stack_diff(
+    ctx: &mut but_ctx::Context,
+    out: &mut OutputChannel,
+    since: &str,
+    until: Option<&str>,
+) -> anyhow::Result<()> {
+    let from_sha = resolve_snapshot(ctx, since)?;
+    let to_sha = until.map(|spec| resolve_snapshot(ctx, spec)).transpose()?;
+    let diff = but_api::legacy::oplog::stack_diff(ctx.legacy_project.id, from_sha.clone(), to_sha)?;
+
+    if let Some(out) = out.for_json() {
+        out.write_value(&diff)?;
+    } else if let Some(out) = out.for_human() {
+        writeln!(
+            out,
+            "{} {}",
+            "Workspace changes since".blue().bold(),
+            from_sha[..7].blue().underline()
+        )?;
+        writeln!(out, "{}", "─".repeat(50).dimmed())?;
+        if diff.is_empty() {
+            writeln!(
+                out,
+                "No changes to stacks, branches, commits or assignments."
+            )?;
+            return Ok(());
+        }
+        write_stack_diff(out, &diff)?;
+    }
+    Ok(())
+}
+
+/// Write each change of `diff` as one line, prefixed with `+`, `-`, `~` or `→`.
+fn write_stack_diff(out: &mut dyn std::fmt::Write, diff: &StackDiff) -> std::fmt::Result {
+    for stack in &diff.stacks_added {
+        writeln!(out, "{} stack {}", "+".green(), stack.name.green())?;
+    }
+    for stack in &diff.stacks_removed {
+        writeln!(out, "{} stack {}", "-".red(), stack.name.red())?;
+    }
+    for branch in &diff.branches_added {
+        writeln!(out, "{} branch {}", "+".green(), branch.name.green())?;
+    }
+    for branch in &diff.branches_removed {
+        writeln!(out, "{} branch {}", "-".red(), branch.name.red())?;
+    }
+    let short = |id: &gix::ObjectId| id.to_hex_with_len(7).to_string();
+    let on_branch = |branch: &Option<String>| {
+        branch
+            .as_ref()
+            .map(|name| format!(" ({name})").dimmed().to_string())
+            .unwrap_or_default()
+    };
+    for commit in &diff.commits_added {
+        writeln!(
+            out,
+            "{} {} {}{}",
+            "+".green(),
+            short(&commit.commit_id).blue(),
+            commit.title,
+            on_branch(&commit.branch)
+        )?;
+    }
+    for commit in &diff.commits_removed {
+        writeln!(
+            out,
+            "{} {} {}{}",
+            "-".red(),
+            short(&commit.commit_id).blue(),
+            commit.title,
+            on_branch(&commit.branch)
+        )?;
+    }
+    for rewritten in &diff.commits_rewritten {
+        writeln!(
+            out,
+            "{} {} → {} {}{}",
+            "~".yellow(),
+            short(&rewritten.old.commit_id).blue(),
+            short(&rewritten.new.commit_id).blue(),
+            rewritten.new.title,
+            on_branch(&rewritten.new.branch)
+        )?;
+    }
+    let stack_name = |id: &Option<but_core::ref_metadata::StackId>| {
+        id.map_or_else(|| "unassigned".to_owned(), |id| id.to_string())
+    };
+    for moved in &diff.assignments_moved {
+        writeln!(
+            out,
+            "{} {}{}: {} → {}",
+            "→".cyan(),
+            moved.path,
+            moved
+                .hunk_header
+                .as_ref()
+                .map(|header| format!(" {header}"))
+                .unwrap_or_default(),
+            stack_name(&moved.from).dimmed(),
+            stack_name(&moved.to)
+        )?;
+    }
+    Ok(())
+}
+
+/// Write each of `changes` as one line with its status letter and path.
+fn write_worktree_changes(
+    out: &mut dyn std::fmt::Write,
+    changes: &[but_core::ui::TreeChange],
+) -> std::fmt::Result {
+    for change in changes {
+        writeln!(
+            out,
+            "{} {}",
+            status_letter_ui(&change.status),
+            path_with_color_ui(&change.status, change.path.to_string())
+        )?;
+    }
+    Ok(())
+}
+
+pub(crate) fn prune_snapshots(
+    ctx: &mut but_ctx::Context,
+    out: &mut OutputChannel,
+) -> anyhow::Result<()> {
+    let outcome = but_api::legacy::oplog::prune_snapshots(ctx.legacy_project.id)?;
+
+    if let Some(out) = out.for_json() {
+        out.write_value(outcome)?;
+    } else if let Some(out) = out.for_human() {
+        if outcome.snapshots_pruned == 0 {
+            writeln!(
+                out,
+                "Nothing to prune, all {} snapshots are within the retention limits.",
+                outcome.snapshots_kept
+            )?;
+        } else {
+            writeln!(
+                out,
+                "{} Pruned {} snapshots, {} remain.",
+                "✓".green().bold(),
+                outcome.snapshots_pruned.to_string().yellow(),
+                outcome.snapshots_kept
+            )?;
+        }
+    }
+    Ok(())
+}
@@ -606,6 +606,8 @@ fn push_single_branch(
 
     if let Some(out) = out.for_json() {
         out.write_value(&result)?;
+    } else if let Some(out) = out.for_shell() {
+        write_remote_refs(out, &result)?;
     }
 
     if out.for_human().is_some() {
@@ -660,15 +662,21 @@ fn push_single_branch_impl(
     // Convert CLI args to gerrit flags with validation
     let gerrit_flags = get_gerrit_flags(args, branch_name, gerrit_mode)?;
 
+    // `--force-with-lease` protects the push even if force push protection is disabled for the project.
+    let mut project = project.clone();
+    project.force_push_protection |= args.force_with_lease;
+    let mut push_ctx = Context::new_from_legacy_project(project)?;
+
     // Call push_stack
-    let result: PushResult = but_api::legacy::stack::push_stack(
-        project.id,
+    let result: PushResult = gitbutler_branch_actions::stack::push_stack(
+        &mut push_ctx,
         stack_id,
-        args.with_force,
+        args.with_force || args.force_with_lease,
         args.skip_force_push_protection,
         branch_name.to_string(),
         args.run_hooks,
         gerrit_flags,
+        args.push_option.clone(),
     )?;
 
     Ok(result)
@@ -758,6 +766,10 @@ fn push_all_branches(
             failed: failed_branches.clone(),
         };
         out.write_value(&batch_result)?;
+    } else if let Some(out) = out.for_shell() {
+        for result in &pushed_results {
+            write_remote_refs(out, result)?;
+        }
     }
 
     if out.for_human().is_some() {
@@ -830,6 +842,14 @@ fn push_all_branches(
     Ok(())
 }
 
+/// Write the remote ref of each branch pushed with `result`, one per line, for use in scripts.
+fn write_remote_refs(out: &mut dyn std::fmt::Write, result: &PushResult) -> std::fmt::Result {
+    for (_branch, remote_ref) in &result.branch_to_remote {
+        writeln!(out, "{remote_ref}")?;
+    }
+    Ok(())
+}
+
 fn handle_no_branch_specified(
     ctx: &Context,
     project: &Project,
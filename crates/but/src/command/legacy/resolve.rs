@@ -125,8 +125,12 @@ fn enter_resolution(ctx: &mut Context, out: &mut OutputChannel, commit_id_str: &
     })?;
 
     // Enter edit mode
-    enter_edit_mode(ctx.legacy_project.id, commit_oid.to_string(), stack_id)
-        .context("Failed to enter edit mode")?;
+    enter_edit_mode(
+        ctx.legacy_project.id,
+        commit_oid.to_string(),
+        Some(stack_id),
+    )
+    .context("Failed to enter edit mode")?;
 
     // Drop the git2 objects to release the borrow
     drop(commit);
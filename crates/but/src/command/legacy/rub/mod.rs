@@ -984,6 +984,7 @@ mod tests {
                 line_nums_added: None,
                 line_nums_removed: None,
                 diff: None,
+                mode_flags: None,
             }),
             is_entire_file: true,
         })
@@ -125,10 +125,6 @@ pub(crate) fn repo(repo_path: &Path, out: &mut OutputChannel, init: bool) -> any
             "The repository at {} is bare. GitButler requires a non-bare repository.",
             repo_path.display()
         )),
-        gitbutler_project::AddProjectOutcome::NonMainWorktree => Err(anyhow::anyhow!(
-            "The repository at {} is a non-main worktree. GitButler requires the main worktree.",
-            repo_path.display()
-        )),
         gitbutler_project::AddProjectOutcome::NoWorkdir => Err(anyhow::anyhow!(
             "The repository at {} has no working directory. GitButler requires a working directory.",
             repo_path.display()
@@ -29,6 +29,9 @@ pub(crate) struct WorkspaceStatus {
     merge_base: Commit,
     /// Information about how ahead the target upstream branch is compared to the merge base
     upstream_state: UpstreamState,
+    /// Figures about each stack and the unassigned hunks, as used by the app (only populated when requested with --stats flag)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stats: Option<but_workspace::WorkspaceStats>,
 }
 
 /// Represents the state of the upstream branch compared to the merge base
@@ -58,8 +61,15 @@ impl WorkspaceStatus {
             stacks,
             merge_base,
             upstream_state,
+            stats: None,
         }
     }
+
+    /// Include `stats` in the output.
+    pub fn with_stats(mut self, stats: Option<but_workspace::WorkspaceStats>) -> Self {
+        self.stats = stats;
+        self
+    }
 }
 
 /// Represents a stack of branches applied in the current workspace
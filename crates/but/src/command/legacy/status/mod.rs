@@ -7,6 +7,7 @@ use but_core::{TreeStatus, ui};
 use but_ctx::Context;
 use but_forge::ForgeReview;
 use but_oxidize::OidExt;
+use but_workspace::WorkspaceStats;
 use but_workspace::ref_info::LocalCommitRelation;
 use but_workspace::ui::PushStatus;
 use colored::{ColoredString, Colorize};
@@ -69,6 +70,7 @@ fn show_edit_mode_status(ctx: &mut Context, out: &mut OutputChannel) -> anyhow::
     crate::command::legacy::resolve::show_resolve_status(ctx, out)
 }
 
+#[expect(clippy::too_many_arguments)]
 pub(crate) async fn worktree(
     ctx: &mut Context,
     out: &mut OutputChannel,
@@ -76,6 +78,7 @@ pub(crate) async fn worktree(
     verbose: bool,
     refresh_prs: bool,
     show_upstream: bool,
+    show_stats: bool,
     hint: bool,
 ) -> anyhow::Result<()> {
     // Check if we're in edit mode first, before doing any expensive operations
@@ -127,6 +130,19 @@ pub(crate) async fn worktree(
     )?;
 
     let worktree_changes = but_api::legacy::diff::changes_in_worktree(ctx)?;
+    let stats = show_stats
+        .then(|| {
+            WorkspaceStats::from_ref_info(
+                &*ctx.repo.get()?,
+                &head_info,
+                worktree_changes
+                    .assignments
+                    .iter()
+                    .map(|assignment| assignment.stack_id),
+                gix::date::Time::now_local_or_utc().seconds,
+            )
+        })
+        .transpose()?;
 
     let id_map = IdMap::new(head_info.stacks, worktree_changes.assignments.clone())?;
 
@@ -270,7 +286,8 @@ pub(crate) async fn worktree(
             &id_map,
             base_branch.as_ref(),
             show_upstream,
-        )?;
+        )?
+        .with_stats(stats);
         out.write_value(workspace_status)?;
         return Ok(());
     }
@@ -406,6 +423,11 @@ pub(crate) async fn worktree(
         }
     )?;
 
+    if let Some(stats) = &stats {
+        writeln!(out)?;
+        print_stats(stats, id_map.stacks(), out)?;
+    }
+
     let not_on_workspace = matches!(
         mode,
         gitbutler_operating_modes::OperatingMode::OutsideWorkspace(_)
@@ -455,6 +477,93 @@ pub(crate) async fn worktree(
     Ok(())
 }
 
+/// Print how far each branch in `stacks` is from its remote tracking branch and how many of its commits are conflicted,
+/// along with the amount of staged and unstaged hunks from `stats`.
+fn print_stats(
+    stats: &WorkspaceStats,
+    stacks: &[StackWithId],
+    out: &mut dyn std::fmt::Write,
+) -> std::fmt::Result {
+    writeln!(out, "{}", "Summary".bold())?;
+    for stack in stacks {
+        let staged_hunks = stats
+            .stacks
+            .iter()
+            .find(|stack_stats| stack_stats.stack_id == stack.id)
+            .map_or(0, |stack_stats| stack_stats.assigned_hunks);
+        let name = stack
+            .segments
+            .first()
+            .and_then(SegmentWithId::branch_name)
+            .unwrap_or_default();
+        writeln!(
+            out,
+            "  Stack {}: {}",
+            name.to_string().green().bold(),
+            count(staged_hunks, "staged hunk")
+        )?;
+        for segment in &stack.segments {
+            let mut figures = Vec::new();
+            match segment
+                .inner
+                .remote_tracking_ref_name
+                .as_ref()
+                .map(|rtb| rtb.as_bstr())
+            {
+                Some(remote) => {
+                    let ahead = segment
+                        .workspace_commits
+                        .iter()
+                        .filter(|commit| match commit.relation() {
+                            LocalCommitRelation::LocalOnly => true,
+                            LocalCommitRelation::LocalAndRemote(remote_id) => {
+                                remote_id != commit.commit_id()
+                            }
+                            LocalCommitRelation::Integrated(_) => false,
+                        })
+                        .count();
+                    figures.push(format!("{ahead} ahead"));
+                    figures.push(format!(
+                        "{} behind {}",
+                        segment.remote_commits.len(),
+                        remote
+                            .strip_prefix(b"refs/remotes/")
+                            .map_or(remote, BStr::new)
+                    ));
+                }
+                None => {
+                    figures.push(count(segment.workspace_commits.len(), "commit"));
+                    figures.push("not pushed".to_string());
+                }
+            }
+            let conflicted = segment
+                .workspace_commits
+                .iter()
+                .filter(|commit| commit.inner.inner.has_conflicts)
+                .count();
+            if conflicted > 0 {
+                figures.push(format!("{conflicted} conflicted").red().to_string());
+            }
+            writeln!(
+                out,
+                "    {}: {}",
+                segment.branch_name().unwrap_or_default(),
+                figures.join(", ")
+            )?;
+        }
+    }
+    writeln!(out, "  Unstaged: {}", count(stats.unassigned_hunks, "hunk"))
+}
+
+/// Return `amount` along with `noun`, which is turned into plural if needed.
+fn count(amount: usize, noun: &str) -> String {
+    if amount == 1 {
+        format!("{amount} {noun}")
+    } else {
+        format!("{amount} {noun}s")
+    }
+}
+
 fn ci_map(
     ctx: &mut Context,
     cache_config: &but_forge::CacheConfig,
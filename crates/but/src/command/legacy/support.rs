@@ -0,0 +1,78 @@
+use std::path::PathBuf;
+
+use anyhow::Context as _;
+use but_ctx::Context;
+use colored::Colorize;
+
+use crate::utils::OutputChannel;
+
+/// Copy the operation trace of the project of `ctx` into the directory `output`, along with information
+/// about this build, so it can be attached to a bug report.
+pub(crate) fn bundle(
+    ctx: &Context,
+    out: &mut OutputChannel,
+    output: Option<PathBuf>,
+) -> anyhow::Result<()> {
+    let output = match output {
+        Some(output) => output,
+        None => {
+            let now = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|duration| duration.as_secs())
+                .unwrap_or_default();
+            PathBuf::from(format!("gitbutler-support-{now}"))
+        }
+    };
+    std::fs::create_dir_all(&output)
+        .with_context(|| format!("Failed to create '{}'", output.display()))?;
+
+    let mut files = Vec::new();
+    for trace_file in but_api::operation_trace::trace_files(&ctx.project_data_dir()) {
+        let Some(file_name) = trace_file.file_name() else {
+            continue;
+        };
+        std::fs::copy(&trace_file, output.join(file_name))
+            .with_context(|| format!("Failed to copy '{}'", trace_file.display()))?;
+        files.push(file_name.to_string_lossy().into_owned());
+    }
+
+    let info = serde_json::json!({
+        "version": option_env!("VERSION").unwrap_or("dev"),
+        "os": std::env::consts::OS,
+        "arch": std::env::consts::ARCH,
+        "operationTrace": ctx.settings().operation_trace,
+        "files": files,
+    });
+    std::fs::write(
+        output.join("info.json"),
+        serde_json::to_string_pretty(&info)?,
+    )?;
+
+    if let Some(out) = out.for_json() {
+        out.write_value(serde_json::json!({
+            "path": output,
+            "files": files,
+        }))?;
+    } else if let Some(out) = out.for_human() {
+        writeln!(
+            out,
+            "{} Wrote the support bundle to {}",
+            "✓".green().bold(),
+            output.display().to_string().cyan()
+        )?;
+        if files.is_empty() {
+            writeln!(
+                out,
+                "{}",
+                "No operations were traced. Set `operationTrace.enabled` to `true` in the settings, reproduce the issue and run this again."
+                    .dimmed()
+            )?;
+        }
+        writeln!(
+            out,
+            "{}",
+            "Nothing was sent, attach the directory to your bug report.".dimmed()
+        )?;
+    }
+    Ok(())
+}
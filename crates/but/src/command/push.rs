@@ -54,6 +54,10 @@ pub mod help {
             out,
             "  -f, --with-force                  Force push even if it's not fast-forward"
         )?;
+        writeln!(
+            out,
+            "      --force-with-lease            Force push, but only overwrite remote commits that were seen before"
+        )?;
         writeln!(
             out,
             "  -s, --skip-force-push-protection  Skip force push protection checks"
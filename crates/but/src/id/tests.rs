@@ -400,6 +400,7 @@ fn non_commit_ids_do_not_collide() -> anyhow::Result<()> {
                         line_nums_added: None,
                         line_nums_removed: None,
                         diff: None,
+                        mode_flags: None,
                     },
                     tail: [
                         HunkAssignment {
@@ -414,6 +415,7 @@ fn non_commit_ids_do_not_collide() -> anyhow::Result<()> {
                             line_nums_added: None,
                             line_nums_removed: None,
                             diff: None,
+                            mode_flags: None,
                         },
                     ],
                 },
@@ -441,6 +443,7 @@ fn non_commit_ids_do_not_collide() -> anyhow::Result<()> {
                         line_nums_added: None,
                         line_nums_removed: None,
                         diff: None,
+                        mode_flags: None,
                     },
                     tail: [],
                 },
@@ -463,6 +466,7 @@ fn non_commit_ids_do_not_collide() -> anyhow::Result<()> {
                         line_nums_added: None,
                         line_nums_removed: None,
                         diff: None,
+                        mode_flags: None,
                     },
                     tail: [],
                 },
@@ -485,6 +489,7 @@ fn non_commit_ids_do_not_collide() -> anyhow::Result<()> {
                         line_nums_added: None,
                         line_nums_removed: None,
                         diff: None,
+                        mode_flags: None,
                     },
                     tail: [],
                 },
@@ -505,6 +510,7 @@ fn non_commit_ids_do_not_collide() -> anyhow::Result<()> {
                         line_nums_added: None,
                         line_nums_removed: None,
                         diff: None,
+                        mode_flags: None,
                     },
                     tail: [],
                 },
@@ -587,6 +593,7 @@ fn ids_are_case_sensitive() -> anyhow::Result<()> {
                         line_nums_added: None,
                         line_nums_removed: None,
                         diff: None,
+                        mode_flags: None,
                     },
                     tail: [],
                 },
@@ -658,6 +665,7 @@ fn branch_and_file_by_name() -> anyhow::Result<()> {
                         line_nums_added: None,
                         line_nums_removed: None,
                         diff: None,
+                        mode_flags: None,
                     },
                     tail: [],
                 },
@@ -706,6 +714,7 @@ fn colon_uncommitted_filename() -> anyhow::Result<()> {
                         line_nums_added: None,
                         line_nums_removed: None,
                         diff: None,
+                        mode_flags: None,
                     },
                     tail: [],
                 },
@@ -734,6 +743,7 @@ fn colon_uncommitted_filename() -> anyhow::Result<()> {
                         line_nums_added: None,
                         line_nums_removed: None,
                         diff: None,
+                        mode_flags: None,
                     },
                     tail: [],
                 },
@@ -760,6 +770,7 @@ fn colon_uncommitted_filename() -> anyhow::Result<()> {
                         line_nums_added: None,
                         line_nums_removed: None,
                         diff: None,
+                        mode_flags: None,
                     },
                     tail: [],
                 },
@@ -971,6 +982,7 @@ mod util {
             line_nums_added: None,
             line_nums_removed: None,
             diff: None,
+            mode_flags: None,
         }
     }
 
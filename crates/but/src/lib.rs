@@ -54,6 +54,16 @@ mod tui;
 
 const CLI_DATE: CustomFormat = gix::date::time::format::ISO8601;
 
+/// Run the `butd` daemon with `args`, which must be what's passed by `std::env::args_os()`.
+#[cfg(feature = "legacy")]
+pub async fn handle_daemon_args(args: impl Iterator<Item = OsString>) -> Result<()> {
+    let args = args::daemon::Args::parse_from(args);
+    if args.trace > 0 {
+        trace::init(args.trace)?;
+    }
+    command::legacy::daemon::serve(args.socket).await
+}
+
 /// Handle `args` which must be what's passed by `std::env::args_os()`.
 pub async fn handle_args(args: impl Iterator<Item = OsString>) -> Result<()> {
     let args: Vec<_> = args.collect();
@@ -133,6 +143,9 @@ pub async fn handle_args(args: impl Iterator<Item = OsString>) -> Result<()> {
                 .target
                 .as_ref()
                 .expect("target is checked to be Some in match guard");
+            if let Some(version) = args.json_schema_version {
+                out.set_json_envelope(version, "rub")?;
+            }
             #[cfg(feature = "legacy")]
             {
                 let mut ctx = setup::init_ctx(&args, InitCtxOptions::default(), &mut out)?;
@@ -172,6 +185,7 @@ pub async fn handle_args(args: impl Iterator<Item = OsString>) -> Result<()> {
             let default_args = vec![OsString::from("but"), OsString::from("default")];
             let expanded = alias::expand_aliases(default_args)?;
             let mut new_args: Args = clap::Parser::parse_from(expanded);
+            new_args.json_schema_version = args.json_schema_version;
 
             // Take the command from the newly parsed args and execute it
             match new_args.cmd.take() {
@@ -193,6 +207,9 @@ async fn match_subcommand(
     app_settings: AppSettings,
     mut output: OutputChannel,
 ) -> Result<()> {
+    if let Some(version) = args.json_schema_version {
+        output.set_json_envelope(version, cmd.as_ref())?;
+    }
     let out = &mut output;
     let metrics_ctx = cmd.to_metrics_context(&app_settings);
 
@@ -209,7 +226,15 @@ async fn match_subcommand(
             Ok(())
         }
         Subcommands::Gui => command::gui::open(&args.current_dir).emit_metrics(metrics_ctx),
-        Subcommands::Completions { shell } => {
+        Subcommands::Completions {
+            dynamic: true,
+            words,
+            ..
+        } => {
+            // This runs on every key press that asks for completions, so it doesn't emit metrics.
+            command::completions::complete_dynamically(&args.current_dir, &words)
+        }
+        Subcommands::Completions { shell, .. } => {
             command::completions::generate_completions(shell).emit_metrics(metrics_ctx)
         }
         Subcommands::Update(update_args::Platform { cmd }) => {
@@ -405,6 +430,7 @@ async fn match_subcommand(
             verbose,
             refresh_prs: sync_prs,
             upstream,
+            stats,
             no_hint,
         } => {
             let mut ctx = setup::init_ctx(
@@ -416,7 +442,7 @@ async fn match_subcommand(
                 out,
             )?;
             command::legacy::status::worktree(
-                &mut ctx, out, show_files, verbose, sync_prs, upstream, !no_hint,
+                &mut ctx, out, show_files, verbose, sync_prs, upstream, stats, !no_hint,
             )
             .await
             .emit_metrics(metrics_ctx)
@@ -535,6 +561,12 @@ async fn match_subcommand(
                     if commit_args.only {
                         anyhow::bail!("--only cannot be used with 'commit empty'.");
                     }
+                    if commit_args.amend {
+                        anyhow::bail!("--amend cannot be used with 'commit empty'.");
+                    }
+                    if commit_args.edit {
+                        anyhow::bail!("--edit cannot be used with 'commit empty'.");
+                    }
                     if commit_args.no_hooks {
                         anyhow::bail!("--no-hooks cannot be used with 'commit empty'.");
                     }
@@ -628,6 +660,8 @@ async fn match_subcommand(
                         commit_args.branch.as_deref(),
                         commit_args.only,
                         commit_args.create,
+                        commit_args.amend,
+                        commit_args.edit,
                         commit_args.no_hooks,
                         commit_args.ai.clone(),
                     )
@@ -667,8 +701,14 @@ async fn match_subcommand(
         Subcommands::Oplog(args::oplog::Platform { cmd }) => {
             let mut ctx = setup::init_ctx(&args, InitCtxOptions::default(), out)?;
             match cmd {
-                Some(args::oplog::Subcommands::List { since, snapshot }) => {
-                    let filter = if snapshot {
+                Some(args::oplog::Subcommands::List {
+                    since,
+                    snapshot,
+                    named,
+                }) => {
+                    let filter = if named {
+                        Some(command::legacy::oplog::OplogFilter::Named)
+                    } else if snapshot {
                         Some(command::legacy::oplog::OplogFilter::Snapshot)
                     } else {
                         None
@@ -676,10 +716,34 @@ async fn match_subcommand(
                     command::legacy::oplog::show_oplog(&mut ctx, out, since.as_deref(), filter)
                         .emit_metrics(metrics_ctx)
                 }
-                Some(args::oplog::Subcommands::Snapshot { message }) => {
-                    command::legacy::oplog::create_snapshot(&mut ctx, out, message.as_deref())
+                Some(args::oplog::Subcommands::Snapshot { message, label }) => {
+                    command::legacy::oplog::create_snapshot(
+                        &mut ctx,
+                        out,
+                        message.as_deref(),
+                        label.as_deref(),
+                    )
+                    .emit_metrics(metrics_ctx)
+                }
+                Some(args::oplog::Subcommands::Diff { since, until }) => {
+                    command::legacy::oplog::show_stack_diff(&mut ctx, out, &since, until.as_deref())
+                        .emit_metrics(metrics_ctx)
+                }
+                Some(args::oplog::Subcommands::Show { snapshot }) => {
+                    command::legacy::oplog::show_snapshot(&mut ctx, out, &snapshot)
                         .emit_metrics(metrics_ctx)
                 }
+                Some(args::oplog::Subcommands::Restore {
+                    snapshot,
+                    force,
+                    dry_run,
+                }) => command::legacy::oplog::restore_to_oplog(
+                    &mut ctx, out, &snapshot, force, dry_run,
+                )
+                .emit_metrics(metrics_ctx),
+                Some(args::oplog::Subcommands::Prune) => {
+                    command::legacy::oplog::prune_snapshots(&mut ctx, out).emit_metrics(metrics_ctx)
+                }
                 None => {
                     // Default to list when no subcommand is provided
                     command::legacy::oplog::show_oplog(&mut ctx, out, None, None)
@@ -688,15 +752,20 @@ async fn match_subcommand(
             }
         }
         #[cfg(feature = "legacy")]
-        Subcommands::Restore { oplog_sha, force } => {
+        Subcommands::Restore {
+            oplog_sha,
+            force,
+            dry_run,
+        } => {
             let mut ctx = setup::init_ctx(&args, InitCtxOptions::default(), out)?;
-            command::legacy::oplog::restore_to_oplog(&mut ctx, out, &oplog_sha, force)
+            command::legacy::oplog::restore_to_oplog(&mut ctx, out, &oplog_sha, force, dry_run)
                 .emit_metrics(metrics_ctx)
         }
         #[cfg(feature = "legacy")]
-        Subcommands::Undo => {
+        Subcommands::Undo { steps } => {
             let mut ctx = setup::init_ctx(&args, InitCtxOptions::default(), out)?;
-            command::legacy::oplog::undo_last_operation(&mut ctx, out).emit_metrics(metrics_ctx)
+            command::legacy::oplog::undo_last_operation(&mut ctx, out, steps)
+                .emit_metrics(metrics_ctx)
         }
         #[cfg(feature = "legacy")]
         Subcommands::Absorb { source, dry_run } => {
@@ -742,6 +811,36 @@ async fn match_subcommand(
                 .emit_metrics(metrics_ctx)
         }
         #[cfg(feature = "legacy")]
+        Subcommands::Fsck { repair } => {
+            let mut ctx = setup::init_ctx(
+                &args,
+                InitCtxOptions {
+                    workspace_check: setup::WorkspaceCheck::Disabled,
+                    ..Default::default()
+                },
+                out,
+            )?;
+            command::legacy::fsck::fsck(&mut ctx, repair, out).emit_metrics(metrics_ctx)
+        }
+        #[cfg(feature = "legacy")]
+        Subcommands::Support(args::support::Platform { cmd }) => {
+            let ctx = setup::init_ctx(
+                &args,
+                InitCtxOptions {
+                    workspace_check: setup::WorkspaceCheck::Disabled,
+                    ..Default::default()
+                },
+                out,
+            )?;
+            match cmd {
+                args::support::Subcommands::Bundle { output } => {
+                    command::legacy::support::bundle(&ctx, out, output)
+                        .context("Failed to create the support bundle.")
+                        .emit_metrics(metrics_ctx)
+                }
+            }
+        }
+        #[cfg(feature = "legacy")]
         Subcommands::Pr(forge::pr::Platform { cmd }) => {
             let mut ctx = setup::init_ctx(
                 &args,
@@ -865,6 +964,21 @@ async fn match_subcommand(
                 .show_root_cause_error_then_exit_without_destructors(output)
         }
         #[cfg(feature = "legacy")]
+        Subcommands::Assign { interactive: _ } => {
+            let mut ctx = setup::init_ctx(
+                &args,
+                InitCtxOptions {
+                    background_sync: BackgroundSync::Enabled,
+                    ..Default::default()
+                },
+                out,
+            )?;
+            command::legacy::assign::interactive(&mut ctx, out)
+                .context("Failed to assign changes.")
+                .emit_metrics(metrics_ctx)
+                .show_root_cause_error_then_exit_without_destructors(output)
+        }
+        #[cfg(feature = "legacy")]
         Subcommands::Unstage {
             file_or_hunk,
             branch,
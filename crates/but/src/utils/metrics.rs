@@ -105,11 +105,15 @@ impl Subcommands {
                 None => OplogList,
                 Some(crate::args::oplog::Subcommands::List { .. }) => OplogList,
                 Some(crate::args::oplog::Subcommands::Snapshot { .. }) => OplogSnapshot,
+                Some(crate::args::oplog::Subcommands::Diff { .. }) => OplogDiff,
+                Some(crate::args::oplog::Subcommands::Show { .. }) => OplogShow,
+                Some(crate::args::oplog::Subcommands::Restore { .. }) => Restore,
+                Some(crate::args::oplog::Subcommands::Prune) => OplogPrune,
             },
             #[cfg(feature = "legacy")]
             Subcommands::Restore { .. } => Restore,
             #[cfg(feature = "legacy")]
-            Subcommands::Undo => Undo,
+            Subcommands::Undo { .. } => Undo,
             #[cfg(feature = "legacy")]
             Subcommands::Claude(claude::Platform { cmd }) => match cmd {
                 claude::Subcommands::PreTool => ClaudePreTool,
@@ -133,9 +137,12 @@ impl Subcommands {
                 Some(forge::pr::Subcommands::Template { .. }) => PrTemplate,
             },
             #[cfg(feature = "legacy")]
+            Subcommands::Fsck { .. } => Fsck,
+            #[cfg(feature = "legacy")]
             Subcommands::Actions(_)
             | Subcommands::Mcp { .. }
             | Subcommands::Setup { .. }
+            | Subcommands::Support(_)
             | Subcommands::Teardown => Unknown,
             Subcommands::Config(config::Platform { cmd }) => match cmd {
                 Some(config::Subcommands::Forge {
@@ -169,6 +176,8 @@ impl Subcommands {
             #[cfg(feature = "legacy")]
             Subcommands::Stage { .. } => Rub,
             #[cfg(feature = "legacy")]
+            Subcommands::Assign { .. } => Rub,
+            #[cfg(feature = "legacy")]
             Subcommands::Unstage { .. } => Rub,
             #[cfg(feature = "legacy")]
             Subcommands::Squash { .. } => Rub,
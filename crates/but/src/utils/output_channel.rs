@@ -24,10 +24,15 @@ pub enum ConfirmOrEmpty {
     NoInput,
 }
 
+/// The version of the schema of all JSON output, to be increased whenever the output of a command changes incompatibly.
+pub const JSON_SCHEMA_VERSION: u32 = 1;
+
 /// A utility `std::io::Write` implementation that can always be used to generate output for humans or for scripts.
 pub struct OutputChannel {
     /// How to print the output, one should match on it. Match on this if you prefer this style.
     format: OutputFormat,
+    /// The name of the command to put into the envelope JSON values are wrapped into, if they should be wrapped.
+    json_envelope_command: Option<String>,
     /// The output to use if there is no pager.
     stdout: std::io::Stdout,
     /// Possibly a pager we are using. If `Some`, the pager itself is used for output instead of `stdout`.
@@ -155,8 +160,35 @@ impl OutputChannel {
     /// Write `value` as pretty JSON to the output.
     ///
     /// Note that it's owned to avoid double-printing with [ResultJsonExt::output_json]
+    ///
+    /// If [an envelope](Self::set_json_envelope()) was requested, `value` is wrapped into it.
     pub fn write_value(&mut self, value: impl serde::Serialize) -> std::io::Result<()> {
-        json_pretty_to_stdout(&value)
+        match &self.json_envelope_command {
+            Some(command) => {
+                let data = serde_json::to_value(&value).map_err(std::io::Error::other)?;
+                if data.is_null() {
+                    return Ok(());
+                }
+                json_pretty_to_stdout(&serde_json::json!({
+                    "schemaVersion": JSON_SCHEMA_VERSION,
+                    "command": command,
+                    "data": data,
+                }))
+            }
+            None => json_pretty_to_stdout(&value),
+        }
+    }
+
+    /// Wrap all values written with [`Self::write_value()`] into an object that names the schema version
+    /// and the `command` that produced them, after making sure that `version` is the one we produce.
+    pub fn set_json_envelope(&mut self, version: u32, command: &str) -> anyhow::Result<()> {
+        if version != JSON_SCHEMA_VERSION {
+            anyhow::bail!(
+                "JSON schema version {version} isn't supported, the only supported version is {JSON_SCHEMA_VERSION}"
+            );
+        }
+        self.json_envelope_command = Some(command.to_owned());
+        Ok(())
     }
 }
 
@@ -326,6 +358,7 @@ impl OutputChannel {
     pub fn new_with_optional_pager(format: OutputFormat, use_pager: bool) -> Self {
         OutputChannel {
             format,
+            json_envelope_command: None,
             stdout: std::io::stdout(),
             pager: if !matches!(format, OutputFormat::Human)
                 || std::env::var_os("NOPAGER").is_some()
@@ -350,6 +383,7 @@ impl OutputChannel {
                 OutputFormat::Human | OutputFormat::Shell | OutputFormat::None => format,
                 OutputFormat::Json => OutputFormat::None,
             },
+            json_envelope_command: None,
             stdout: std::io::stdout(),
             pager: None,
         }
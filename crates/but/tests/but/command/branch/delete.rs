@@ -0,0 +1,53 @@
+use snapbox::str;
+
+use crate::utils::{CommandExt, Sandbox};
+
+#[test]
+fn dependent_branch_with_json_output() -> anyhow::Result<()> {
+    let env = Sandbox::init_scenario_with_target_and_default_settings("one-stack")?;
+    env.setup_metadata(&["A"])?;
+
+    env.but("branch new --anchor A dependent")
+        .assert()
+        .success();
+    env.but("--json branch delete dependent")
+        .allow_json()
+        .assert()
+        .success()
+        .stderr_eq(str![])
+        .stdout_eq(str![[r#"
+{
+  "branch": "dependent"
+}
+
+"#]]);
+
+    Ok(())
+}
+
+#[test]
+fn nonexistent_branch_fails() -> anyhow::Result<()> {
+    let env = Sandbox::init_scenario_with_target_and_default_settings("one-stack")?;
+    env.setup_metadata(&["A"])?;
+
+    env.but("branch delete nonexistent-branch")
+        .assert()
+        .failure()
+        .stdout_eq(str![])
+        .stderr_eq(str![[r#"
+Error: Branch 'nonexistent-branch' not found in any stack
+
+"#]]);
+
+    env.but("--json branch delete nonexistent-branch")
+        .allow_json()
+        .assert()
+        .failure()
+        .stdout_eq(str![])
+        .stderr_eq(str![[r#"
+Error: Branch 'nonexistent-branch' not found in any stack
+
+"#]]);
+
+    Ok(())
+}
@@ -1,4 +1,6 @@
 mod apply;
 #[cfg(feature = "legacy")]
+mod delete;
+#[cfg(feature = "legacy")]
 mod new;
 mod unapply;
@@ -76,7 +76,14 @@ fn unapply_with_json_output() -> anyhow::Result<()> {
         .allow_json()
         .assert()
         .success()
-        .stdout_eq(str![""])
+        .stdout_eq(str![[r#"
+{
+  "unapplied": [
+    "feature-branch"
+  ]
+}
+
+"#]])
         .stderr_eq(str![]);
 
     insta::assert_snapshot!(env.git_log()?, @"
@@ -169,7 +176,12 @@ fn unapply_nonexistent_branch_with_json() -> anyhow::Result<()> {
         .allow_json()
         .assert()
         .success()
-        .stdout_eq(str![""])
+        .stdout_eq(str![[r#"
+{
+  "unapplied": []
+}
+
+"#]])
         .stderr_eq(str![""]);
 
     Ok(())
@@ -1,6 +1,6 @@
 use snapbox::str;
 
-use crate::utils::Sandbox;
+use crate::utils::{CommandExt, Sandbox};
 
 #[test]
 fn commit_with_message_from_file() -> anyhow::Result<()> {
@@ -191,6 +191,72 @@ Operations History
     Ok(())
 }
 
+#[test]
+fn commit_amend_keeps_message_unless_given() -> anyhow::Result<()> {
+    let env = Sandbox::init_scenario_with_target_and_default_settings("one-stack")?;
+    env.setup_metadata(&["A"])?;
+
+    env.file("new-file.txt", "test content");
+    env.but("commit --amend")
+        .assert()
+        .success()
+        .stdout_eq(str![[r#"
+Amended commit [..] on branch A
+
+"#]]);
+
+    let log = env.git_log()?;
+    assert!(log.contains("add A"), "the message is kept: {log}");
+    env.but("--json status -f")
+        .allow_json()
+        .assert()
+        .success()
+        .stdout_eq(str![[r#"
+{
+  "unassignedChanges": [],
+...
+
+"#]]);
+
+    env.but("commit --amend -m 'add A and a new file'")
+        .assert()
+        .success()
+        .stdout_eq(str![[r#"
+Amended commit [..] on branch A
+
+"#]]);
+    let log = env.git_log()?;
+    assert!(
+        log.contains("add A and a new file"),
+        "without changes, only the message changes: {log}"
+    );
+    Ok(())
+}
+
+#[test]
+fn commit_amend_without_changes_or_message_fails() -> anyhow::Result<()> {
+    let env = Sandbox::init_scenario_with_target_and_default_settings("one-stack")?;
+    env.setup_metadata(&["A"])?;
+
+    env.but("commit --amend")
+        .assert()
+        .failure()
+        .stderr_eq(str![[r#"
+Error: No changes to commit.
+
+"#]]);
+
+    env.file("new-file.txt", "test content");
+    env.but("commit --amend -c A")
+        .assert()
+        .failure()
+        .stderr_eq(str![[r#"
+error: the argument '--amend' cannot be used with '--create'
+...
+"#]]);
+    Ok(())
+}
+
 #[test]
 fn commit_empty_with_before_flag() -> anyhow::Result<()> {
     let env = Sandbox::init_scenario_with_target_and_default_settings("one-stack")?;
@@ -0,0 +1,46 @@
+use snapbox::str;
+
+use crate::utils::Sandbox;
+
+#[test]
+fn branch_names_and_aliases_are_completed_dynamically() -> anyhow::Result<()> {
+    let env = Sandbox::init_scenario_with_target_and_default_settings("one-stack")?;
+    env.setup_metadata(&["A"])?;
+
+    env.but("completions --dynamic -- but push ''")
+        .assert()
+        .success()
+        .stdout_eq(str![[r#"
+...
+A
+...
+"#]]);
+
+    // Aliases are expanded before deciding what to complete.
+    env.but("alias add p push").assert().success();
+    env.but("completions --dynamic -- but p ''")
+        .assert()
+        .success()
+        .stdout_eq(str![[r#"
+...
+A
+...
+"#]]);
+
+    env.but("completions --dynamic -- but ''")
+        .assert()
+        .success()
+        .stdout_eq(str![[r#"
+default
+p
+st
+stf
+
+"#]]);
+
+    env.but("completions --dynamic -- but status ''")
+        .assert()
+        .success()
+        .stdout_eq(str![""]);
+    Ok(())
+}
@@ -41,3 +41,36 @@ Please run 'but setup' to initialize the project.
 
     Ok(())
 }
+
+#[test]
+fn json_can_be_wrapped_into_a_versioned_envelope() -> anyhow::Result<()> {
+    use crate::utils::CommandExt;
+    use snapbox::str;
+
+    let env = Sandbox::open_or_init_scenario_with_target_and_default_settings("one-stack")?;
+
+    env.but("alias list --json --json-schema-version 1")
+        .allow_json()
+        .assert()
+        .success()
+        .stdout_eq(str![[r#"
+{
+  "schemaVersion": 1,
+  "command": "alias",
+  "data": {
+...
+  }
+}
+
+"#]]);
+
+    env.but("alias list --json --json-schema-version 2")
+        .allow_json()
+        .assert()
+        .failure()
+        .stderr_eq(str![[r#"
+Error: JSON schema version 2 isn't supported, the only supported version is 1
+
+"#]]);
+    Ok(())
+}
@@ -0,0 +1,55 @@
+use snapbox::str;
+
+use crate::utils::{CommandExt, Sandbox};
+
+#[test]
+fn consistent_workspace() -> anyhow::Result<()> {
+    let env = Sandbox::init_scenario_with_target_and_default_settings("one-stack")?;
+    env.setup_metadata(&["A"])?;
+
+    env.but("fsck")
+        .assert()
+        .success()
+        .stderr_eq(str![])
+        .stdout_eq(str![[r#"
+✓ The workspace is consistent.
+
+"#]]);
+    Ok(())
+}
+
+#[test]
+fn missing_branch_ref_is_repaired() -> anyhow::Result<()> {
+    let env = Sandbox::init_scenario_with_target_and_default_settings("one-stack")?;
+    env.setup_metadata(&["A"])?;
+    env.invoke_git("update-ref -d refs/heads/A");
+
+    env.but("fsck")
+        .assert()
+        .success()
+        .stderr_eq(str![])
+        .stdout_eq(str![[r#"
+Found 2 issues:
+  ✗ the branch A has no reference
+  ✗ the workspace commit doesn't merge the heads of all stacks
+
+Run `but fsck --repair` to fix them.
+
+"#]]);
+
+    env.but("fsck --repair")
+        .assert()
+        .success()
+        .stderr_eq(str![])
+        .stdout_eq(str![[r#"
+  ✓ Fixed: the branch A has no reference
+  ✓ Fixed: the workspace commit doesn't merge the heads of all stacks
+
+"#]]);
+
+    env.but("fsck").assert().success().stdout_eq(str![[r#"
+✓ The workspace is consistent.
+
+"#]]);
+    Ok(())
+}
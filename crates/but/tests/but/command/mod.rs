@@ -9,8 +9,12 @@ mod claude;
 #[cfg(feature = "legacy")]
 mod commit;
 #[cfg(feature = "legacy")]
+mod completions;
+#[cfg(feature = "legacy")]
 mod cursor;
 mod format;
+#[cfg(feature = "legacy")]
+mod fsck;
 mod gui;
 mod help;
 #[cfg(feature = "legacy")]
@@ -18,6 +22,12 @@ mod merge;
 #[cfg(feature = "legacy")]
 mod r#move;
 #[cfg(feature = "legacy")]
+mod oplog;
+#[cfg(feature = "legacy")]
+mod pr;
+#[cfg(feature = "legacy")]
+mod push;
+#[cfg(feature = "legacy")]
 mod reword;
 #[cfg(feature = "legacy")]
 mod rub;
@@ -28,6 +38,8 @@ mod squash;
 #[cfg(feature = "legacy")]
 mod status;
 #[cfg(feature = "legacy")]
+mod support;
+#[cfg(feature = "legacy")]
 mod teardown;
 
 mod util {
@@ -59,6 +71,17 @@ mod util {
         .success();
     }
 
+    /// Make `origin` a bare repository that only has `main`, so branches can actually be pushed to it.
+    /// The remote URL stored with the target, which determines the forge, stays the same.
+    pub fn origin_as_bare_repository(env: &Sandbox) -> anyhow::Result<tempfile::TempDir> {
+        let remote = tempfile::TempDir::new()?;
+        let remote_path = remote.path().display();
+        env.invoke_git(&format!("init --bare '{remote_path}'"))
+            .invoke_git(&format!("push '{remote_path}' main"))
+            .invoke_git(&format!("remote set-url origin '{remote_path}'"));
+        Ok(remote)
+    }
+
     /// Create a file with `filename`, commit it to `branch`, then edit it once more to have two uncommitted hunks.
     pub fn commit_file_with_worktree_changes_as_two_hunks(
         env: &Sandbox,
@@ -0,0 +1,55 @@
+use snapbox::str;
+
+use crate::utils::{CommandExt, Sandbox};
+
+#[test]
+fn restore_dry_run_previews_without_restoring() -> anyhow::Result<()> {
+    let env = Sandbox::init_scenario_with_target_and_default_settings("one-stack")?;
+    env.setup_metadata(&["A"])?;
+
+    env.but("oplog snapshot --label before").assert().success();
+    env.file("new-file", "content\n");
+    env.but("commit A -m 'add new-file'").assert().success();
+
+    env.but("restore before --dry-run --json")
+        .allow_json()
+        .assert()
+        .success()
+        .stdout_eq(str![[r#"
+{
+...
+  "commitsAdded": [
+    {
+      "stackId": "[..]",
+      "branch": "A",
+      "commitId": "[..]",
+      "title": "add new-file"
+    }
+  ],
+...
+}
+
+"#]]);
+
+    // Nothing was restored.
+    let repo = env.open_repo()?;
+    let head = repo.rev_parse_single("A")?.object()?.into_commit();
+    assert_eq!(head.message()?.title, "add new-file");
+    Ok(())
+}
+
+#[test]
+fn diff_rejects_commits_that_are_not_snapshots() -> anyhow::Result<()> {
+    let env = Sandbox::init_scenario_with_target_and_default_settings("one-stack")?;
+    env.setup_metadata(&["A"])?;
+    env.but("oplog snapshot").assert().success();
+
+    env.but("oplog diff A")
+        .assert()
+        .failure()
+        .stderr_eq(str![[r#"
+Error: 'A' is not an oplog snapshot
+
+"#]]);
+    Ok(())
+}
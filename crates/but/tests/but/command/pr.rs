@@ -0,0 +1,64 @@
+use snapbox::str;
+
+use crate::{command::util::origin_as_bare_repository, utils::Sandbox};
+
+#[test]
+fn create_prints_review_urls_for_shells() -> anyhow::Result<()> {
+    let env = Sandbox::init_scenario_with_target_and_default_settings("one-stack")?;
+    let _remote = origin_as_bare_repository(&env)?;
+    // Don't ask the forge to update the descriptions of reviews.
+    env.invoke_git("config gitbutler.forgeStackFooter false");
+
+    env.but("branch new branchB").assert().success();
+    env.file("test-file.txt", "line 1\n");
+    env.but("commit -m 'first commit' branchB")
+        .assert()
+        .success();
+
+    // The forge already knows a review for the branch, so the branch is pushed without creating one.
+    let mut ctx = env.context()?;
+    ctx.db
+        .get_mut()?
+        .forge_reviews_mut()?
+        .set_all(vec![cached_review(
+            "branchB",
+            "https://github.com/gitbutlerapp/gitbutler/pull/1",
+        )])?;
+    drop(ctx);
+
+    env.but("--format shell pr create branchB --default")
+        .assert()
+        .success()
+        .stdout_eq(str![[r#"
+https://github.com/gitbutlerapp/gitbutler/pull/1
+
+"#]]);
+
+    Ok(())
+}
+
+fn cached_review(branch_name: &str, html_url: &str) -> but_db::ForgeReview {
+    but_db::ForgeReview {
+        html_url: html_url.into(),
+        number: 1,
+        title: branch_name.into(),
+        body: None,
+        author: None,
+        labels: "[]".into(),
+        draft: false,
+        source_branch: branch_name.into(),
+        target_branch: "main".into(),
+        sha: String::new(),
+        created_at: None,
+        modified_at: None,
+        merged_at: None,
+        closed_at: None,
+        repository_ssh_url: None,
+        repository_https_url: None,
+        repo_owner: None,
+        reviewers: "[]".into(),
+        unit_symbol: "#".into(),
+        last_sync_at: chrono::Local::now().naive_local(),
+        struct_version: but_forge::ForgeReview::struct_version(),
+    }
+}
@@ -1,6 +1,31 @@
 use snapbox::str;
 
-use crate::utils::{CommandExt, Sandbox};
+use crate::{
+    command::util::origin_as_bare_repository,
+    utils::{CommandExt, Sandbox},
+};
+
+#[test]
+fn shell_output_is_the_pushed_remote_refs() -> anyhow::Result<()> {
+    let env = Sandbox::init_scenario_with_target_and_default_settings("one-stack")?;
+    let _remote = origin_as_bare_repository(&env)?;
+
+    env.but("branch new branchB").assert().success();
+    env.file("test-file.txt", "line 1\n");
+    env.but("commit -m 'first commit' branchB")
+        .assert()
+        .success();
+
+    env.but("--format shell push branchB")
+        .assert()
+        .success()
+        .stdout_eq(str![[r#"
+refs/remotes/origin/branchB
+
+"#]]);
+
+    Ok(())
+}
 
 #[test]
 fn push_refuses_conflicted_commits() -> anyhow::Result<()> {
@@ -553,6 +553,34 @@ fn stage_command() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test]
+fn assign_interactive_needs_a_terminal() -> anyhow::Result<()> {
+    let env = Sandbox::init_scenario_with_target_and_default_settings("two-stacks")?;
+
+    env.setup_metadata(&["A", "B"])?;
+    env.but("assign -i").assert().success().stdout_eq(str![[r#"
+There are no unassigned changes.
+
+"#]]);
+
+    env.file("new.txt", "content\n");
+    env.but("assign -i")
+        .assert()
+        .failure()
+        .stderr_eq(str![[r#"
+Failed to assign changes. Interactive assignment needs a terminal. Use `but stage <file-or-hunk> <branch>` instead.
+
+"#]]);
+
+    env.but("assign").assert().failure().stderr_eq(str![[r#"
+error: the following required arguments were not provided:
+  --interactive
+...
+"#]]);
+
+    Ok(())
+}
+
 #[test]
 fn unstage_command() -> anyhow::Result<()> {
     let env = Sandbox::init_scenario_with_target_and_default_settings("two-stacks")?;
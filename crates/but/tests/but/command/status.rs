@@ -207,6 +207,50 @@ fn json_shows_paths_as_strings() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test]
+fn stats_summarize_stacks_and_hunks() -> anyhow::Result<()> {
+    let env = Sandbox::init_scenario_with_target_and_default_settings("two-stacks")?;
+
+    // Must set metadata to match the scenario, or else the old APIs used here won't deliver.
+    env.setup_metadata(&["A", "B"])?;
+    env.file("test-file.txt", "test content");
+
+    env.but("status --stats --no-hint")
+        .assert()
+        .success()
+        .stderr_eq(snapbox::str![])
+        .stdout_eq(snapbox::str![[r#"
+...
+Summary
+  Stack A: 0 staged hunks
+    A: 1 commit, not pushed
+  Stack B: 0 staged hunks
+    B: 1 commit, not pushed
+  Unstaged: 1 hunk
+
+"#]]);
+
+    env.but("--json status --stats")
+        .allow_json()
+        .assert()
+        .success()
+        .stderr_eq(snapbox::str![])
+        .stdout_eq(snapbox::str![[r#"
+...
+  "stats": {
+    "stacks": [
+...
+    ],
+    "unassignedHunks": 1,
+...
+  }
+}
+
+"#]]);
+
+    Ok(())
+}
+
 // TODO This test demonstrates how IDs are assigned to uncommitted and committed
 // files that have multiple hunks. This test can be removed when we have CLI
 // IDs for hunks, a command (e.g. `rub`) is taught to use them, and that command
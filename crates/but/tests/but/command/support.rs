@@ -0,0 +1,40 @@
+use snapbox::str;
+
+use crate::utils::{CommandExt, Sandbox};
+
+#[test]
+fn bundle_copies_the_operation_trace() -> anyhow::Result<()> {
+    let env = Sandbox::init_scenario_with_target_and_default_settings("one-stack")?;
+    env.setup_metadata(&["A"])?;
+    let trace = r#"{"startedAtMs":0,"operation":"undo_commit","durationMs":1,"input":{},"result":"ok"}
+"#;
+    env.file(".git/gitbutler/operations.1.jsonl", trace);
+    env.file(".git/gitbutler/operations.jsonl", trace);
+
+    env.but("support bundle -o bundle")
+        .assert()
+        .success()
+        .stderr_eq(str![])
+        .stdout_eq(str![[r#"
+✓ Wrote the support bundle to bundle
+Nothing was sent, attach the directory to your bug report.
+
+"#]]);
+
+    let bundle = env.projects_root().join("bundle");
+    assert_eq!(
+        std::fs::read_to_string(bundle.join("operations.jsonl"))?,
+        trace
+    );
+    assert_eq!(
+        std::fs::read_to_string(bundle.join("operations.1.jsonl"))?,
+        trace
+    );
+    let info: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(bundle.join("info.json"))?)?;
+    assert_eq!(
+        info["files"],
+        serde_json::json!(["operations.1.jsonl", "operations.jsonl"])
+    );
+    Ok(())
+}
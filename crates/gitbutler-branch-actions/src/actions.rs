@@ -1,6 +1,9 @@
 use anyhow::{Context as _, Result};
 use but_core::DiffSpec;
-use but_ctx::{Context, access::WorktreeWritePermission};
+use but_ctx::{
+    Context,
+    access::{OperationCategory, Priority, WorktreeWritePermission},
+};
 use but_oxidize::{ObjectIdExt, OidExt};
 use but_workspace::legacy::{commit_engine, stack_heads_info, ui};
 use gitbutler_branch::{BranchCreateRequest, BranchUpdateRequest};
@@ -18,14 +21,19 @@ use tracing::instrument;
 
 use super::r#virtual as vbranch;
 use crate::{
-    VirtualBranchesExt, base,
+    AdoptedCommits, CherryPickedCommit, PatchApplyMode, PatchSeriesOutcome, RepairOutcome,
+    RevertMode, RevertOutcome, VirtualBranchesExt, base,
     base::BaseBranch,
+    base_switch, branch_divergence,
     branch_manager::BranchManagerExt,
     branch_upstream_integration,
     branch_upstream_integration::IntegrationStrategy,
+    conflict_details, integrated_stacks,
     move_branch::MoveBranchResult,
     move_commits::{self, MoveCommitIllegalAction},
+    patch_export,
     reorder::{self, StackOrder},
+    stack_bundle,
     upstream_integration::{
         self, BaseBranchResolution, BaseBranchResolutionApproach, IntegrationOutcome, Resolution,
         StackStatuses, UpstreamIntegrationContext,
@@ -56,6 +64,8 @@ pub fn create_virtual_branch(
 /// If there is no such local reference, this function will return an error.
 pub fn delete_local_branch(ctx: &Context, refname: &Refname, given_name: String) -> Result<()> {
     ctx.verify(ctx.exclusive_worktree_access().write_permission())?;
+    but_core::branch::ProtectedBranches::from_repo(&*ctx.repo.get()?)?
+        .ensure_unprotected(given_name.as_str().into())?;
     let repo = &*ctx.git2_repo.get()?;
     let handle = ctx.legacy_project.virtual_branches();
     let stack = handle.list_all_stacks()?.into_iter().find(|stack| {
@@ -97,6 +107,65 @@ pub fn set_base_branch(
     base::set_base_branch(ctx, target_branch)
 }
 
+pub fn switch_base_branch(
+    ctx: &Context,
+    new_target_branch: &RemoteRefname,
+    abort_on_incompatible: bool,
+) -> Result<base_switch::BaseBranchSwitch> {
+    let mut guard = ctx.exclusive_worktree_access();
+    ctx.verify(guard.write_permission())?;
+    ensure_open_workspace_mode(ctx)
+        .context("Switching the base branch requires open workspace mode")?;
+    base_switch::switch_base_branch(
+        ctx,
+        new_target_branch,
+        abort_on_incompatible,
+        guard.write_permission(),
+    )
+}
+
+pub fn detect_integrated_stacks(
+    ctx: &Context,
+    review_map: &std::collections::HashMap<String, but_forge::ForgeReview>,
+) -> Result<Vec<integrated_stacks::IntegratedStack>> {
+    let _guard = ctx.shared_worktree_access();
+    integrated_stacks::detect_integrated_stacks(ctx, review_map)
+}
+
+pub fn archive_stack(ctx: &Context, stack_id: StackId) -> Result<()> {
+    let mut guard = ctx.exclusive_worktree_access();
+    ctx.verify(guard.write_permission())?;
+    ensure_open_workspace_mode(ctx).context("Archiving a stack requires open workspace mode")?;
+    integrated_stacks::archive_stack(ctx, stack_id, guard.write_permission())
+}
+
+pub fn branch_divergence(
+    ctx: &Context,
+    stack_id: StackId,
+) -> Result<Vec<branch_divergence::BranchDivergence>> {
+    let _guard = ctx.shared_worktree_access();
+    branch_divergence::branch_divergence(ctx, stack_id)
+}
+
+/// Return the versions of all sides of each file the commit with `commit_id` conflicts in,
+/// or `None` if it isn't conflicted.
+pub fn conflict_details(
+    ctx: &Context,
+    commit_id: gix::ObjectId,
+) -> Result<Option<conflict_details::ConflictDetails>> {
+    let _guard = ctx.shared_worktree_access();
+    conflict_details::conflict_details(ctx, commit_id)
+}
+
+pub fn export_patches(
+    ctx: &Context,
+    stack_id: StackId,
+    out_dir: &std::path::Path,
+) -> Result<Vec<patch_export::PatchSeries>> {
+    let _guard = ctx.shared_worktree_access();
+    patch_export::export_patches(ctx, stack_id, out_dir)
+}
+
 pub fn set_target_push_remote(ctx: &Context, push_remote: &str) -> Result<()> {
     base::set_target_push_remote(ctx, push_remote)
 }
@@ -111,7 +180,8 @@ pub fn integrate_upstream_commits(
     series_name: String,
     integration_strategy: Option<IntegrationStrategy>,
 ) -> Result<()> {
-    let mut guard = ctx.exclusive_worktree_access();
+    let mut guard =
+        ctx.queued_exclusive_worktree_access(OperationCategory::Integrate, Priority::Normal)?;
     ctx.verify(guard.write_permission())?;
     ensure_open_workspace_mode(ctx)
         .context("Integrating upstream commits requires open workspace mode")?;
@@ -119,13 +189,49 @@ pub fn integrate_upstream_commits(
         SnapshotDetails::new(OperationKind::MergeUpstream),
         guard.write_permission(),
     );
-    branch_upstream_integration::integrate_upstream_commits_for_series(
+    guard.operation().set_progress(
+        0,
+        Some(1),
+        Some(format!("Integrating upstream commits into {series_name}")),
+    );
+    let res = branch_upstream_integration::integrate_upstream_commits_for_series(
         ctx,
         stack_id,
         guard.write_permission(),
         series_name,
         integration_strategy,
-    )
+    );
+    guard.operation().set_progress(1, Some(1), None);
+    res
+}
+
+pub fn integrate_upstream_for_stack(
+    ctx: &Context,
+    stack_id: StackId,
+    strategy: IntegrationStrategy,
+) -> Result<()> {
+    let mut guard =
+        ctx.queued_exclusive_worktree_access(OperationCategory::Integrate, Priority::Normal)?;
+    ctx.verify(guard.write_permission())?;
+    ensure_open_workspace_mode(ctx)
+        .context("Integrating upstream commits requires open workspace mode")?;
+    let _ = ctx.create_snapshot(
+        SnapshotDetails::new(OperationKind::MergeUpstream),
+        guard.write_permission(),
+    );
+    guard.operation().set_progress(
+        0,
+        Some(1),
+        Some("Integrating upstream commits into the stack".into()),
+    );
+    let res = branch_upstream_integration::integrate_upstream_for_stack(
+        ctx,
+        stack_id,
+        strategy,
+        guard.write_permission(),
+    );
+    guard.operation().set_progress(1, Some(1), None);
+    res
 }
 
 pub fn get_initial_integration_steps_for_branch(
@@ -148,7 +254,8 @@ pub fn integrate_branch_with_steps(
     branch_name: String,
     steps: Vec<branch_upstream_integration::InteractiveIntegrationStep>,
 ) -> Result<()> {
-    let mut guard = ctx.exclusive_worktree_access();
+    let mut guard =
+        ctx.queued_exclusive_worktree_access(OperationCategory::Integrate, Priority::Normal)?;
     ctx.verify(guard.write_permission())?;
     ensure_open_workspace_mode(ctx)
         .context("Integrating a branch with steps requires open workspace mode")?;
@@ -156,13 +263,23 @@ pub fn integrate_branch_with_steps(
         SnapshotDetails::new(OperationKind::MergeUpstream),
         guard.write_permission(),
     );
-    branch_upstream_integration::integrate_branch_with_steps(
+    let step_count = steps.len();
+    guard.operation().set_progress(
+        0,
+        Some(step_count),
+        Some(format!("Integrating {branch_name}")),
+    );
+    let res = branch_upstream_integration::integrate_branch_with_steps(
         ctx,
         stack_id,
         branch_name,
         steps,
         guard.write_permission(),
-    )
+    );
+    guard
+        .operation()
+        .set_progress(step_count, Some(step_count), None);
+    res
 }
 
 pub fn update_stack_order(ctx: &Context, updates: Vec<BranchUpdateRequest>) -> Result<()> {
@@ -251,7 +368,8 @@ fn amend_with_commit_engine(
 }
 
 pub fn undo_commit(ctx: &Context, stack_id: StackId, commit_oid: git2::Oid) -> Result<()> {
-    let mut guard = ctx.exclusive_worktree_access();
+    let mut guard =
+        ctx.queued_exclusive_worktree_access(OperationCategory::Rebase, Priority::Normal)?;
     ctx.verify(guard.write_permission())?;
     ensure_open_workspace_mode(ctx).context("Undoing a commit requires open workspace mode")?;
     let snapshot_tree = ctx.prepare_snapshot(guard.read_permission());
@@ -270,7 +388,8 @@ pub fn undo_commit(ctx: &Context, stack_id: StackId, commit_oid: git2::Oid) -> R
 }
 
 pub fn reorder_stack(ctx: &Context, stack_id: StackId, stack_order: StackOrder) -> Result<()> {
-    let mut guard = ctx.exclusive_worktree_access();
+    let mut guard =
+        ctx.queued_exclusive_worktree_access(OperationCategory::Rebase, Priority::Normal)?;
     ctx.verify(guard.write_permission())?;
     ensure_open_workspace_mode(ctx).context("Reordering a commit requires open workspace mode")?;
     let _ = ctx.create_snapshot(
@@ -287,7 +406,8 @@ pub fn squash_commits(
     source_ids: Vec<git2::Oid>,
     destination_id: git2::Oid,
 ) -> Result<git2::Oid> {
-    let mut guard = ctx.exclusive_worktree_access();
+    let mut guard =
+        ctx.queued_exclusive_worktree_access(OperationCategory::Rebase, Priority::Normal)?;
     ctx.verify(guard.write_permission())?;
     ensure_open_workspace_mode(ctx).context("Squashing a commit requires open workspace mode")?;
     crate::squash::squash_commits(
@@ -299,6 +419,145 @@ pub fn squash_commits(
     )
 }
 
+/// Place copies of `commit_ids` from anywhere in the repository on top of the stack with `stack_id`,
+/// picking merge commits relative to their `mainline` parent.
+pub fn cherry_pick_into_stack(
+    ctx: &Context,
+    stack_id: StackId,
+    commit_ids: Vec<gix::ObjectId>,
+    mainline: Option<usize>,
+) -> Result<Vec<CherryPickedCommit>> {
+    let mut guard =
+        ctx.queued_exclusive_worktree_access(OperationCategory::Rebase, Priority::Normal)?;
+    ctx.verify(guard.write_permission())?;
+    ensure_open_workspace_mode(ctx).context("Cherry-picking requires open workspace mode")?;
+    let _ = ctx.create_snapshot(
+        SnapshotDetails::new(OperationKind::CherryPick),
+        guard.write_permission(),
+    );
+    crate::cherry_pick::cherry_pick_into_stack(
+        ctx,
+        stack_id,
+        commit_ids,
+        mainline,
+        guard.write_permission(),
+    )
+}
+
+/// Revert the commit with `commit_id` of the stack with `stack_id`, either with a new commit on top of the stack,
+/// or by applying the inverse changes to the worktree, depending on `mode`.
+///
+/// `perm` is taken by the caller so the changes made to the worktree can be assigned under the same exclusive access.
+pub fn revert_commit(
+    ctx: &Context,
+    stack_id: StackId,
+    commit_id: gix::ObjectId,
+    mode: RevertMode,
+    perm: &mut WorktreeWritePermission,
+) -> Result<RevertOutcome> {
+    ctx.verify(perm)?;
+    ensure_open_workspace_mode(ctx).context("Reverting a commit requires open workspace mode")?;
+    let _ = ctx.create_snapshot(SnapshotDetails::new(OperationKind::RevertCommit), perm);
+    crate::revert::revert_commit(ctx, stack_id, commit_id, mode, perm)
+}
+
+/// Apply the patches in `files` to the stack with `stack_id`, either as new commits on top of the stack,
+/// or to the worktree to review them first, depending on `mode`.
+pub fn apply_patch_series(
+    ctx: &Context,
+    stack_id: StackId,
+    files: Vec<std::path::PathBuf>,
+    mode: PatchApplyMode,
+) -> Result<PatchSeriesOutcome> {
+    let mut guard =
+        ctx.queued_exclusive_worktree_access(OperationCategory::Rebase, Priority::Normal)?;
+    ctx.verify(guard.write_permission())?;
+    ensure_open_workspace_mode(ctx).context("Applying patches requires open workspace mode")?;
+    let _ = ctx.create_snapshot(
+        SnapshotDetails::new(OperationKind::ApplyPatchSeries),
+        guard.write_permission(),
+    );
+    crate::patch_apply::apply_patch_series(ctx, stack_id, files, mode, guard.write_permission())
+}
+
+/// Write the stack with `stack_id` to a Git bundle at `bundle_path`, returning the metadata needed to import it
+/// elsewhere along with `uncommitted`, the uncommitted changes assigned to the stack.
+pub fn export_stack_bundle(
+    ctx: &Context,
+    stack_id: StackId,
+    bundle_path: &std::path::Path,
+    uncommitted: Vec<DiffSpec>,
+) -> Result<stack_bundle::StackBundleMetadata> {
+    let _guard = ctx.shared_worktree_access();
+    stack_bundle::export_stack_bundle(ctx, stack_id, bundle_path, uncommitted)
+}
+
+/// Recreate the stack described by `metadata` from the Git bundle at `bundle_path` and apply it to the workspace.
+pub fn import_stack_bundle(
+    ctx: &Context,
+    bundle_path: &std::path::Path,
+    metadata: &stack_bundle::StackBundleMetadata,
+) -> Result<stack_bundle::ImportedStackBundle> {
+    let mut guard = ctx.exclusive_worktree_access();
+    ctx.verify(guard.write_permission())?;
+    ensure_open_workspace_mode(ctx).context("Importing a stack requires open workspace mode")?;
+    let _ = ctx.create_snapshot(
+        SnapshotDetails::new(OperationKind::ApplyBranch),
+        guard.write_permission(),
+    );
+    stack_bundle::import_stack_bundle(ctx, bundle_path, metadata, guard.write_permission())
+}
+
+/// Collapse all `fixup!` and `squash!` commits in the stack with `stack_id` into the commits they refer to,
+/// and return how many commits were squashed away.
+pub fn autosquash_stack(ctx: &Context, stack_id: StackId) -> Result<usize> {
+    let mut guard =
+        ctx.queued_exclusive_worktree_access(OperationCategory::Rebase, Priority::Normal)?;
+    ctx.verify(guard.write_permission())?;
+    ensure_open_workspace_mode(ctx).context("Autosquashing requires open workspace mode")?;
+    let _ = ctx.create_snapshot(
+        SnapshotDetails::new(OperationKind::AutosquashStack),
+        guard.write_permission(),
+    );
+    crate::autosquash::autosquash_stack(ctx, stack_id, guard.write_permission())
+}
+
+/// Return the commit that `commit_id` was most recently rewritten into by rebases and amends,
+/// or `None` if it wasn't rewritten.
+pub fn resolve_rewritten(ctx: &Context, commit_id: gix::ObjectId) -> Result<Option<gix::ObjectId>> {
+    gitbutler_stack::RewrittenCommitsHandle::new(ctx.project_data_dir()).resolve(commit_id)
+}
+
+/// Return the commits that were made on top of the workspace commit with Git directly, like with `git commit`,
+/// the oldest one first.
+pub fn external_commits(ctx: &Context) -> Result<Vec<gix::ObjectId>> {
+    Ok(crate::adopt::find_external_commits(ctx)?
+        .map(|external| external.commits.iter().map(|id| id.to_gix()).collect())
+        .unwrap_or_default())
+}
+
+/// Move the commits that were made on top of the workspace commit with Git directly onto the stack with `stack_id`,
+/// or onto a new stack if `None`, keeping their authorship.
+pub fn adopt_external_commits(ctx: &Context, stack_id: Option<StackId>) -> Result<AdoptedCommits> {
+    let mut guard = ctx.exclusive_worktree_access();
+    ensure_open_workspace_mode(ctx).context("Adopting commits requires open workspace mode")?;
+    let _ = ctx.create_snapshot(
+        SnapshotDetails::new(OperationKind::AdoptExternalCommits),
+        guard.write_permission(),
+    );
+    crate::adopt::adopt_external_commits(ctx, stack_id, guard.write_permission())
+}
+
+/// Check the workspace for consistency and fix the issues that can be fixed automatically.
+pub fn repair_workspace(ctx: &Context) -> Result<RepairOutcome> {
+    let mut guard = ctx.exclusive_worktree_access();
+    let _ = ctx.create_snapshot(
+        SnapshotDetails::new(OperationKind::RepairWorkspace),
+        guard.write_permission(),
+    );
+    crate::repair::repair_workspace(ctx, guard.write_permission())
+}
+
 pub fn update_commit_message(
     ctx: &Context,
     stack_id: StackId,
@@ -316,16 +575,22 @@ pub fn update_commit_message(
     vbranch::update_commit_message(ctx, stack_id, commit_oid, message)
 }
 
+/// Fetch all remotes, reporting progress after each of them, and stop before the next one if the operation was cancelled.
 pub fn fetch_from_remotes(ctx: &Context, askpass: Option<String>) -> Result<FetchResult> {
+    let operation = ctx
+        .enqueue_operation(OperationCategory::Fetch, Priority::Normal)
+        .wait()?;
+    let progress = operation.progress();
     let remotes = ctx.git2_repo.get()?.remotes_as_string()?;
-    let fetch_errors: Vec<_> = remotes
-        .iter()
-        .filter_map(|remote| {
-            ctx.fetch(remote, askpass.clone())
-                .err()
-                .map(|err| err.to_string())
-        })
-        .collect();
+    let mut fetch_errors = Vec::new();
+    for (idx, remote) in remotes.iter().enumerate() {
+        progress.check_cancelled()?;
+        progress.set_progress(idx, Some(remotes.len()), Some(format!("Fetching {remote}")));
+        if let Err(err) = ctx.fetch(remote, askpass.clone()) {
+            fetch_errors.push(err.to_string());
+        }
+    }
+    progress.set_progress(remotes.len(), Some(remotes.len()), None);
 
     let timestamp = std::time::SystemTime::now();
     let project_data_last_fetched = if fetch_errors.is_empty() {
@@ -349,7 +614,8 @@ pub fn move_commit(
     commit_oid: git2::Oid,
     source_stack_id: StackId,
 ) -> Result<Option<MoveCommitIllegalAction>> {
-    let mut guard = ctx.exclusive_worktree_access();
+    let mut guard =
+        ctx.queued_exclusive_worktree_access(OperationCategory::Rebase, Priority::Normal)?;
     ctx.verify(guard.write_permission())?;
     ensure_open_workspace_mode(ctx).context("Moving a commit requires open workspace mode")?;
     let _ = ctx.create_snapshot(
@@ -454,7 +720,9 @@ pub fn integrate_upstream(
     base_branch_resolution: Option<BaseBranchResolution>,
     review_map: &std::collections::HashMap<String, but_forge::ForgeReview>,
 ) -> Result<IntegrationOutcome> {
-    let mut guard = ctx.exclusive_worktree_access();
+    let mut guard =
+        ctx.queued_exclusive_worktree_access(OperationCategory::Integrate, Priority::Normal)?;
+    let progress = guard.operation().progress();
 
     let _ = ctx.create_snapshot(
         SnapshotDetails::new(OperationKind::UpdateWorkspaceBase),
@@ -467,6 +735,7 @@ pub fn integrate_upstream(
         base_branch_resolution,
         review_map,
         guard.write_permission(),
+        &progress,
     )
 }
 
@@ -0,0 +1,155 @@
+use anyhow::{Context as _, Result, bail};
+use bstr::ByteSlice as _;
+use but_ctx::{Context, access::WorktreeWritePermission};
+use but_oxidize::{ObjectIdExt as _, OidExt as _};
+use but_rebase::cherry_pick::{EmptyCommit, PickMode};
+use gitbutler_branch::BranchCreateRequest;
+use gitbutler_commit::commit_ext::CommitMessageBstr as _;
+use gitbutler_repo::logging::{LogUntil, RepositoryExt as _};
+use gitbutler_stack::StackId;
+use serde::Serialize;
+
+use crate::{
+    VirtualBranchesExt as _,
+    branch_manager::BranchManagerExt as _,
+    integration::{
+        GITBUTLER_INTEGRATION_COMMIT_TITLE, GITBUTLER_WORKSPACE_COMMIT_TITLE,
+        update_workspace_commit,
+    },
+};
+
+/// Commits that were made on top of the workspace commit with Git directly, like with `git commit`.
+#[derive(Debug, Clone)]
+pub(crate) struct ExternalCommits {
+    /// The workspace commit the commits were made on.
+    pub workspace_commit: git2::Oid,
+    /// The commits on top of the workspace commit, the oldest one first.
+    pub commits: Vec<git2::Oid>,
+}
+
+/// The result of [`adopt_external_commits()`](crate::adopt_external_commits()).
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AdoptedCommits {
+    /// The stack the commits were added to.
+    pub stack_id: StackId,
+    /// The commits that were made with Git, the oldest one first.
+    #[serde(with = "but_serde::object_id_vec")]
+    pub external_commits: Vec<gix::ObjectId>,
+    /// The commits they became in the stack, in the same order.
+    #[serde(with = "but_serde::object_id_vec")]
+    pub adopted_commits: Vec<gix::ObjectId>,
+}
+
+/// Return the commits that were made on top of the workspace commit, or `None` if there are none.
+pub(crate) fn find_external_commits(ctx: &Context) -> Result<Option<ExternalCommits>> {
+    let git2_repo = &*ctx.git2_repo.get()?;
+    let head_commit = git2_repo
+        .head()
+        .context("failed to get head")?
+        .peel_to_commit()
+        .context("failed to peel to commit")?;
+    let default_target = ctx
+        .legacy_project
+        .virtual_branches()
+        .get_default_target()
+        .context("failed to get default target")?;
+
+    let commits = git2_repo
+        .log(
+            head_commit.id(),
+            LogUntil::Commit(default_target.sha),
+            false,
+        )
+        .context("failed to get log")?;
+    let workspace_index = commits
+        .iter()
+        .position(|commit| {
+            commit.message().is_some_and(|message| {
+                message.starts_with(GITBUTLER_WORKSPACE_COMMIT_TITLE)
+                    || message.starts_with(GITBUTLER_INTEGRATION_COMMIT_TITLE)
+            })
+        })
+        .context("GitButler workspace commit not found")?;
+    if workspace_index == 0 {
+        return Ok(None);
+    }
+    Ok(Some(ExternalCommits {
+        workspace_commit: commits[workspace_index].id(),
+        commits: commits[..workspace_index]
+            .iter()
+            .rev()
+            .map(|commit| commit.id())
+            .collect(),
+    }))
+}
+
+/// Move the commits that were made on top of the workspace commit with Git onto the stack with `stack_id`,
+/// or onto a new stack named after the most recent of them if `None`.
+///
+/// The commits keep their author and message, and the worktree isn't changed.
+/// Commits that don't apply cleanly onto the stack are kept as conflicted commits.
+pub(crate) fn adopt_external_commits(
+    ctx: &Context,
+    stack_id: Option<StackId>,
+    perm: &mut WorktreeWritePermission,
+) -> Result<AdoptedCommits> {
+    let Some(external) = find_external_commits(ctx)? else {
+        bail!("There are no commits on top of the workspace commit to adopt");
+    };
+    let vb_state = ctx.legacy_project.virtual_branches();
+    let mut stack = match stack_id {
+        Some(stack_id) => vb_state.get_stack_in_workspace(stack_id)?,
+        None => {
+            let name = {
+                let git2_repo = &*ctx.git2_repo.get()?;
+                let newest = git2_repo.find_commit(*external.commits.last().expect("not empty"))?;
+                newest
+                    .message_bstr()
+                    .lines()
+                    .next()
+                    .map(|title| title.to_str_lossy().into_owned())
+            };
+            ctx.branch_manager()
+                .create_virtual_branch(
+                    &BranchCreateRequest {
+                        name,
+                        ..Default::default()
+                    },
+                    perm,
+                )
+                .context("failed to create virtual branch")?
+        }
+    };
+
+    {
+        let git2_repo = &*ctx.git2_repo.get()?;
+        let workspace_commit = git2_repo.find_commit(external.workspace_commit)?;
+        git2_repo
+            .reset(workspace_commit.as_object(), git2::ResetType::Soft, None)
+            .context("failed to reset to workspace commit")?;
+    }
+
+    let repo = ctx.repo.get()?;
+    let mut head = stack.head_oid(ctx)?;
+    let mut adopted_commits = Vec::with_capacity(external.commits.len());
+    for commit_id in &external.commits {
+        head = but_rebase::cherry_pick_one(
+            &repo,
+            head,
+            commit_id.to_gix(),
+            PickMode::Unconditionally,
+            EmptyCommit::Keep,
+        )
+        .with_context(|| format!("failed to move commit {commit_id} onto the stack"))?;
+        adopted_commits.push(head);
+    }
+    stack.set_stack_head(&vb_state, &repo, head.to_git2())?;
+    update_workspace_commit(&vb_state, ctx, false)?;
+
+    Ok(AdoptedCommits {
+        stack_id: stack.id,
+        external_commits: external.commits.iter().map(|id| id.to_gix()).collect(),
+        adopted_commits,
+    })
+}
@@ -0,0 +1,308 @@
+use anyhow::{Context as _, Result};
+use bstr::{BString, ByteSlice};
+use but_ctx::{Context, access::WorktreeWritePermission};
+use but_oxidize::ObjectIdExt;
+use but_rebase::{
+    RebaseStep,
+    plan::{PlanStep, RebasePlan},
+};
+use but_workspace::legacy::stack_ext::StackExt;
+use gitbutler_stack::StackId;
+use gitbutler_workspace::branch_trees::{WorkspaceState, update_uncommitted_changes};
+use tracing::instrument;
+
+use crate::VirtualBranchesExt as _;
+
+/// Collapses all `fixup!` and `squash!` commits of a stack into the commits they refer to,
+/// the same way `git rebase --autosquash` would.
+///
+/// Returns the number of commits that were squashed away, which is `0` if the stack was left untouched.
+#[instrument(level = "debug", skip(ctx, perm))]
+pub(crate) fn autosquash_stack(
+    ctx: &Context,
+    stack_id: StackId,
+    perm: &mut WorktreeWritePermission,
+) -> Result<usize> {
+    let old_workspace = WorkspaceState::create(ctx, perm.read_permission())?;
+    let vb_state = ctx.legacy_project.virtual_branches();
+    let mut stack = vb_state.get_stack_in_workspace(stack_id)?;
+
+    let merge_base = stack.merge_base(ctx)?;
+    let repo = ctx.repo.get()?;
+    let repo = stack.repo_with_identity(&repo)?;
+    let steps = stack.as_rebase_steps(ctx, &repo)?;
+    let Some((plan_steps, squashed)) = autosquash_steps(steps, |id| {
+        Ok(repo.find_commit(id)?.message_raw()?.to_owned())
+    })?
+    else {
+        return Ok(0);
+    };
+
+    let mut plan = RebasePlan::new(merge_base, None);
+    plan.rebase_noops(false);
+    for step in plan_steps {
+        plan.step(step);
+    }
+    let output = plan.execute(&repo)?.output;
+
+    stack.set_stack_head(&vb_state, &repo, output.top_commit.to_git2())?;
+    stack.set_heads_from_rebase_output(ctx, &output)?;
+
+    let new_workspace = WorkspaceState::create(ctx, perm.read_permission())?;
+    // Even if this fails, it's not actionable
+    let _ = update_uncommitted_changes(ctx, old_workspace, new_workspace, perm);
+    crate::integration::update_workspace_commit(&vb_state, ctx, false)
+        .context("failed to update gitbutler workspace")?;
+
+    Ok(squashed)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Fixup,
+    Squash,
+}
+
+/// Strip all `fixup! ` and `squash! ` prefixes from `subject`, and return what kind of squash it is along
+/// with the subject of the commit it refers to. The first prefix determines the kind.
+fn parse_autosquash_subject(subject: &[u8]) -> Option<(Kind, &[u8])> {
+    let mut kind = None;
+    let mut rest = subject;
+    loop {
+        if let Some(stripped) = rest.strip_prefix(b"fixup! ") {
+            kind.get_or_insert(Kind::Fixup);
+            rest = stripped;
+        } else if let Some(stripped) = rest.strip_prefix(b"squash! ") {
+            kind.get_or_insert(Kind::Squash);
+            rest = stripped;
+        } else {
+            break;
+        }
+    }
+    let rest = rest.trim();
+    kind.filter(|_| !rest.is_empty()).map(|kind| (kind, rest))
+}
+
+/// Reorder `steps`, which are ordered from the merge base to the top of the stack, so that each `fixup!` and `squash!`
+/// commit follows the commit it refers to, and turn them into the respective plan steps.
+/// `message_of` is used to obtain the message of the picked commits.
+///
+/// A target is found like Git does it: by exact subject, then by commit id prefix, then by subject prefix,
+/// and only among the commits that come before it.
+/// Commits whose target can't be found are left alone.
+///
+/// Return the steps and the number of squashed commits, or `None` if there is nothing to do.
+fn autosquash_steps(
+    steps: Vec<RebaseStep>,
+    message_of: impl Fn(gix::ObjectId) -> Result<BString>,
+) -> Result<Option<(Vec<PlanStep>, usize)>> {
+    struct Pick {
+        id: gix::ObjectId,
+        message: BString,
+        /// The index of the pick this one should be squashed into, along with the kind of squash.
+        target: Option<(usize, Kind)>,
+    }
+
+    let mut picks: Vec<Pick> = Vec::new();
+    for step in &steps {
+        let RebaseStep::Pick {
+            commit_id,
+            new_message,
+        } = step
+        else {
+            continue;
+        };
+        let message = match new_message {
+            Some(message) => message.clone(),
+            None => message_of(*commit_id)?,
+        };
+        let subject = message.lines().next().unwrap_or_default();
+        let target = parse_autosquash_subject(subject).and_then(|(kind, wanted)| {
+            let candidates = || {
+                picks
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, pick)| (idx, pick, pick.message.lines().next().unwrap_or_default()))
+            };
+            let hex_prefix = wanted.len() >= 4 && wanted.iter().all(u8::is_ascii_hexdigit);
+            candidates()
+                .find(|(_, _, subject)| *subject == wanted)
+                .or_else(|| {
+                    hex_prefix
+                        .then(|| {
+                            candidates().find(|(_, pick, _)| {
+                                pick.id.to_hex().to_string().as_bytes().starts_with(wanted)
+                            })
+                        })
+                        .flatten()
+                })
+                .or_else(|| candidates().find(|(_, _, subject)| subject.starts_with(wanted)))
+                // Squashing into a fixup means squashing into what it squashes into.
+                .map(|(idx, pick, _)| (pick.target.map_or(idx, |(target, _)| target), kind))
+        });
+        picks.push(Pick {
+            id: *commit_id,
+            message,
+            target,
+        });
+    }
+
+    let squashed = picks.iter().filter(|pick| pick.target.is_some()).count();
+    if squashed == 0 {
+        return Ok(None);
+    }
+
+    let mut out = Vec::with_capacity(steps.len());
+    let mut pick_idx = 0;
+    for step in steps {
+        match step {
+            RebaseStep::Pick {
+                commit_id,
+                new_message,
+            } => {
+                let idx = pick_idx;
+                pick_idx += 1;
+                let pick = &picks[idx];
+                if pick.target.is_some() {
+                    continue;
+                }
+                out.push(match new_message {
+                    Some(message) => PlanStep::Reword { commit_id, message },
+                    None => PlanStep::Pick(commit_id),
+                });
+
+                // Fixups keep whatever message the plan accumulated so far, squashes extend it.
+                let mut message = pick.message.clone();
+                for squash in picks
+                    .iter()
+                    .filter(|squash| squash.target.is_some_and(|(target, _)| target == idx))
+                {
+                    match squash.target.map(|(_, kind)| kind) {
+                        Some(Kind::Fixup) => out.push(PlanStep::Fixup(squash.id)),
+                        _ => {
+                            let body = squash
+                                .message
+                                .lines_with_terminator()
+                                .skip(1)
+                                .flat_map(|line| line.iter().copied())
+                                .collect::<Vec<u8>>();
+                            if !body.trim().is_empty() {
+                                let mut combined = message.trim_end().to_owned();
+                                combined.extend_from_slice(b"\n\n");
+                                combined.extend_from_slice(body.trim());
+                                combined.push(b'\n');
+                                message = combined.into();
+                            }
+                            out.push(PlanStep::Squash {
+                                commit_id: squash.id,
+                                message: Some(message.clone()),
+                            });
+                        }
+                    }
+                }
+            }
+            RebaseStep::SquashIntoPreceding {
+                commit_id,
+                new_message,
+            } => out.push(PlanStep::Squash {
+                commit_id,
+                message: new_message,
+            }),
+            RebaseStep::Reference(reference) => out.push(PlanStep::Reference(reference)),
+        }
+    }
+    Ok(Some((out, squashed)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn id(n: u8) -> gix::ObjectId {
+        gix::ObjectId::from_bytes_or_panic(&[n; 20])
+    }
+
+    fn run(commits: &[(u8, &str)]) -> Option<(Vec<PlanStep>, usize)> {
+        let messages: HashMap<_, BString> = commits
+            .iter()
+            .map(|(n, message)| (id(*n), (*message).into()))
+            .collect();
+        let steps = commits
+            .iter()
+            .map(|(n, _)| RebaseStep::Pick {
+                commit_id: id(*n),
+                new_message: None,
+            })
+            .collect();
+        autosquash_steps(steps, |id| Ok(messages[&id].clone())).unwrap()
+    }
+
+    #[test]
+    fn nothing_to_do() {
+        assert!(run(&[(1, "one\n"), (2, "two\n")]).is_none());
+        assert!(
+            run(&[(1, "fixup! two\n"), (2, "two\n")]).is_none(),
+            "targets must come first"
+        );
+    }
+
+    #[test]
+    fn fixup_moves_behind_target() {
+        let (steps, squashed) = run(&[(1, "one\n"), (2, "two\n"), (3, "fixup! one\n")]).unwrap();
+        assert_eq!(squashed, 1);
+        assert_eq!(
+            steps,
+            [
+                PlanStep::Pick(id(1)),
+                PlanStep::Fixup(id(3)),
+                PlanStep::Pick(id(2))
+            ]
+        );
+    }
+
+    #[test]
+    fn squash_combines_body_without_subject() {
+        let (steps, _) = run(&[
+            (1, "one\n\nbody\n"),
+            (2, "squash! one\n\nmore\n"),
+            (3, "fixup! fixup! one\n"),
+        ])
+        .unwrap();
+        assert_eq!(
+            steps,
+            [
+                PlanStep::Pick(id(1)),
+                PlanStep::Squash {
+                    commit_id: id(2),
+                    message: Some("one\n\nbody\n\nmore\n".into())
+                },
+                PlanStep::Fixup(id(3)),
+            ]
+        );
+    }
+
+    #[test]
+    fn targets_by_id_and_subject_prefix() {
+        let (steps, squashed) = run(&[
+            (0xab, "one with a long subject\n"),
+            (2, "two\n"),
+            (3, "fixup! abababab\n"),
+            (4, "fixup! two\n"),
+            (5, "fixup! one with\n"),
+        ])
+        .unwrap();
+        assert_eq!(squashed, 3);
+        assert_eq!(
+            steps,
+            [
+                PlanStep::Pick(id(0xab)),
+                PlanStep::Fixup(id(3)),
+                PlanStep::Fixup(id(5)),
+                PlanStep::Pick(id(2)),
+                PlanStep::Fixup(id(4)),
+            ]
+        );
+    }
+}
@@ -3,7 +3,7 @@ use std::{path::Path, time};
 use anyhow::{Context as _, Result, anyhow};
 use but_core::worktree::checkout::UncommitedWorktreeChanges;
 use but_ctx::Context;
-use but_error::Marker;
+use but_error::{ButError, Marker};
 use but_forge::ForgeRepoInfo;
 use but_oxidize::{ObjectIdExt, OidExt};
 use gitbutler_branch::GITBUTLER_WORKSPACE_REFERENCE;
@@ -96,7 +96,11 @@ fn go_back_to_integration(ctx: &Context, default_target: &Target) -> Result<Base
 
         let repo = &*ctx.git2_repo.get()?;
         let final_tree = repo.find_tree(final_tree_id.to_git2())?;
+        // The worktree is at the stored workspace tree along with uncommitted changes, which are part of the final tree,
+        // so only what differs from the stored workspace tree has to be updated. This avoids hashing the worktree again.
+        let workspace_tree = repo.head()?.peel_to_tree()?;
         repo.checkout_tree_builder(&final_tree)
+            .only_changes_from(&workspace_tree)?
             .force()
             .checkout()
             .context("failed to checkout tree")?;
@@ -113,6 +117,7 @@ pub(crate) fn set_base_branch(
     target_branch_ref: &RemoteRefname,
 ) -> Result<BaseBranch> {
     let repo = &*ctx.git2_repo.get()?;
+    crate::integration::ensure_workspace_not_checked_out_in_other_worktree(&*ctx.repo.get()?)?;
 
     // if target exists, and it is the same as the requested branch, we should go back
     if let Ok(target) = default_target(&ctx.project_data_dir())
@@ -121,21 +126,33 @@ pub(crate) fn set_base_branch(
         return go_back_to_integration(ctx, &target);
     }
 
+    prepare_unborn_head(repo, target_branch_ref)?;
+
     // lookup a branch by name
     let target_branch = repo
         .maybe_find_branch_by_refname(&target_branch_ref.clone().into())?
-        .ok_or(anyhow!("remote branch '{}' not found", target_branch_ref))?;
+        .ok_or_else(|| ButError::BranchNotFound {
+            name: target_branch_ref.to_string(),
+        })?;
 
-    let remote = repo
-        .find_remote(target_branch_ref.remote())
-        .context(format!(
-            "failed to find remote for branch {}",
-            target_branch.get().name().unwrap()
-        ))?;
-    let remote_url = remote.url().context(format!(
-        "failed to get remote url for {}",
-        target_branch_ref.remote()
-    ))?;
+    // Local targets don't have a remote, which can be configured once there is one.
+    let remote_url = if target_branch_ref.is_local() {
+        String::new()
+    } else {
+        let remote = repo
+            .find_remote(target_branch_ref.remote())
+            .context(format!(
+                "failed to find remote for branch {}",
+                target_branch.get().name().unwrap()
+            ))?;
+        remote
+            .url()
+            .context(format!(
+                "failed to get remote url for {}",
+                target_branch_ref.remote()
+            ))?
+            .to_owned()
+    };
 
     let target_branch_head = target_branch.get().peel_to_commit().context(format!(
         "failed to peel branch {} to commit",
@@ -156,11 +173,17 @@ pub(crate) fn set_base_branch(
             target_branch_head.id()
         ))?;
 
+    // Keep pushing to the previously configured remote when switching to another target branch,
+    // as long as it still exists and differs from the remote the target is fetched from.
+    let push_remote_name = default_target(&ctx.project_data_dir())
+        .ok()
+        .and_then(|previous| previous.push_remote_name)
+        .filter(|name| name != target_branch_ref.remote() && repo.find_remote(name).is_ok());
     let target = Target {
         branch: target_branch_ref.clone(),
-        remote_url: remote_url.to_string(),
+        remote_url,
         sha: target_commit_oid,
-        push_remote_name: None,
+        push_remote_name,
     };
 
     let vb_state = ctx.legacy_project.virtual_branches();
@@ -184,6 +207,9 @@ pub(crate) fn set_base_branch(
                 let upstream_name = target_branch_ref.with_branch(head_name.branch());
                 if upstream_name.eq(target_branch_ref) {
                     (None, true)
+                } else if target_branch_ref.is_local() {
+                    // Without a remote, there is no upstream.
+                    (None, false)
                 } else {
                     match repo.find_reference(&Refname::from(&upstream_name).to_string()) {
                         Ok(_upstream) => Ok((Some(upstream_name), false)),
@@ -227,19 +253,63 @@ pub(crate) fn set_base_branch(
     Ok(base)
 }
 
+/// Give an unborn `HEAD`, like the one of a freshly initialized repository, a commit to start from,
+/// so the workspace can be created on top of it. Nothing happens if `HEAD` already points to a commit.
+///
+/// A local target branch that doesn't exist yet is created with an empty initial commit, and `HEAD` is pointed at it
+/// as there is nothing on the unborn branch to lose. With a remote target, the unborn branch is created at the commit
+/// of the target branch and checked out, like `git clone` would.
+fn prepare_unborn_head(repo: &git2::Repository, target_branch_ref: &RemoteRefname) -> Result<()> {
+    if !matches!(repo.head(), Err(err) if err.code() == git2::ErrorCode::UnbornBranch) {
+        return Ok(());
+    }
+    if target_branch_ref.is_local() {
+        let branch_ref = target_branch_ref.to_string();
+        if repo.find_reference(&branch_ref).is_err() {
+            let (author, committer) = repo.signatures()?;
+            let empty_tree = repo.find_tree(repo.treebuilder(None)?.write()?)?;
+            repo.commit(
+                Some(&branch_ref),
+                &author,
+                &committer,
+                "Initial commit",
+                &empty_tree,
+                &[],
+            )
+            .context(format!("failed to create base branch {branch_ref}"))?;
+        }
+        repo.set_head(&branch_ref)?;
+    } else if let Some(target_branch) =
+        repo.maybe_find_branch_by_refname(&target_branch_ref.clone().into())?
+    {
+        let unborn_branch = repo
+            .find_reference("HEAD")?
+            .symbolic_target()
+            .context("HEAD of a repository without commits must point to a branch")?
+            .to_owned();
+        repo.reference(
+            &unborn_branch,
+            target_branch.get().peel_to_commit()?.id(),
+            false,
+            "GitButler: start branch at target",
+        )?;
+        repo.checkout_head(Some(git2::build::CheckoutBuilder::new().safe()))
+            .context("failed to check out the target branch")?;
+    }
+    Ok(())
+}
+
 pub(crate) fn set_target_push_remote(ctx: &Context, push_remote_name: &str) -> Result<()> {
     let git2_repo = &*ctx.git2_repo.get()?;
     let remote = git2_repo
         .find_remote(push_remote_name)
         .context(format!("failed to find remote {push_remote_name}"))?;
 
-    // if target exists, and it is the same as the requested branch, we should go back
     let mut target = default_target(&ctx.project_data_dir())?;
-    target.push_remote_name = remote
-        .name()
-        .context("failed to get remote name")?
-        .to_string()
-        .into();
+    let push_remote_name = remote.name().context("failed to get remote name")?;
+    // Pushing to the fetch remote is the default, so there is nothing to remember then.
+    target.push_remote_name =
+        (push_remote_name != target.fetch_remote_name()).then(|| push_remote_name.to_owned());
     let vb_state = ctx.legacy_project.virtual_branches();
     vb_state.set_default_target(target)?;
 
@@ -347,7 +417,7 @@ pub(crate) fn target_to_base_branch(ctx: &Context, target: &Target) -> Result<Ba
     };
 
     // Fallback to the remote URL of the branch if the target remote URL is empty
-    let remote_url = if target.remote_url.is_empty() {
+    let remote_url = if target.remote_url.is_empty() && !target.is_local() {
         let remote = repo.find_remote(target.branch.remote()).context(format!(
             "failed to find remote for branch {}",
             target.branch.fullname()
@@ -361,7 +431,25 @@ pub(crate) fn target_to_base_branch(ctx: &Context, target: &Target) -> Result<Ba
         target.remote_url.clone()
     };
 
-    let forge_repo_info = but_forge::derive_forge_repo_info(&remote_url);
+    // Self-managed forges can't be recognized by their host, so let `gitbutler.forge` say which one it is,
+    // or the forge hosts configured in the app settings.
+    let forge_override = gix_repo
+        .config_snapshot()
+        .string("gitbutler.forge")
+        .and_then(
+            |forge| match forge.to_string().parse::<but_forge::ForgeName>() {
+                Ok(forge) => Some(forge),
+                Err(err) => {
+                    tracing::warn!(?err, "ignoring invalid gitbutler.forge setting");
+                    None
+                }
+            },
+        );
+    let forge_repo_info = but_forge::derive_forge_repo_info_with_hosts(
+        &remote_url,
+        forge_override,
+        &ctx.settings().forge_hosts,
+    );
 
     let base = BaseBranch {
         branch_name: target.branch.fullname(),
@@ -393,15 +481,33 @@ fn default_target(base_path: &Path) -> Result<Target> {
     VirtualBranchesHandle::new(base_path).get_default_target()
 }
 
+/// Push the base of the workspace to the target branch on the push remote, which is the fetch remote
+/// unless a different one was configured.
 pub(crate) fn push(ctx: &Context, with_force: bool) -> Result<()> {
     let target = default_target(&ctx.project_data_dir())?;
+    let remote_refname = RemoteRefname::new(&target.push_remote_name(), target.branch.branch());
+    if with_force {
+        let repo = ctx.repo.get()?;
+        let remote_tip = repo
+            .try_find_reference(remote_refname.to_string().as_str())?
+            .map(|mut reference| reference.peel_to_id())
+            .transpose()?
+            .map(|id| id.detach());
+        but_core::branch::ProtectedBranches::from_repo(&repo)?.ensure_push_unprotected(
+            &repo,
+            remote_refname.branch().into(),
+            remote_tip,
+            target.sha.to_gix(),
+        )?;
+    }
     let _ = ctx.push(
         target.sha,
-        &target.branch,
+        &remote_refname,
         with_force,
         ctx.legacy_project.force_push_protection,
         None,
         None,
+        None,
         vec![],
     );
     Ok(())
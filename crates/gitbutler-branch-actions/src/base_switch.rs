@@ -0,0 +1,248 @@
+//! Switch the target branch of the workspace, like from `origin/main` to `origin/release/1.2`,
+//! without tearing the workspace down.
+
+use std::collections::HashSet;
+
+use anyhow::{Context as _, Result, bail};
+use but_ctx::{Context, access::WorktreeWritePermission};
+use but_error::ButError;
+use but_oxidize::{ObjectIdExt, OidExt};
+use but_rebase::{RebaseOutput, RebaseStep};
+use but_workspace::legacy::stack_ext::StackDetailsExt;
+use gitbutler_commit::commit_ext::CommitExt as _;
+use gitbutler_oplog::{
+    OplogExt,
+    entry::{OperationKind, SnapshotDetails},
+};
+use gitbutler_reference::RemoteRefname;
+use gitbutler_repo::RepositoryExt as _;
+use gitbutler_stack::{StackId, Target};
+use gitbutler_workspace::branch_trees::{WorkspaceState, update_uncommitted_changes};
+use serde::Serialize;
+
+use crate::{
+    BranchManagerExt, VirtualBranchesExt as _,
+    base::{self, BaseBranch},
+    upstream_integration,
+};
+
+/// What happened to a stack when switching the base branch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", content = "subject", rename_all = "camelCase")]
+pub enum StackSwitchStatus {
+    /// The commits of the stack were rebased onto the new base without conflicts.
+    Rebased,
+    /// The commits of the stack would conflict with the new base, so the stack was unapplied.
+    Incompatible,
+}
+
+/// The outcome of switching the base branch for a single stack.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StackSwitchOutcome {
+    pub stack_id: StackId,
+    /// The name of the top-most branch of the stack.
+    pub name: String,
+    pub status: StackSwitchStatus,
+}
+
+/// The outcome of [`switch_base_branch()`](crate::switch_base_branch()).
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BaseBranchSwitch {
+    /// The new base branch.
+    pub base_branch: BaseBranch,
+    /// What happened to each stack that was applied before the switch.
+    pub stacks: Vec<StackSwitchOutcome>,
+}
+
+/// A stack re-evaluated against the new base, before anything was written.
+struct PlannedStack {
+    stack_id: StackId,
+    name: String,
+    /// The stack rebased onto the new base, or `None` if that conflicted.
+    rebase: Option<RebaseOutput>,
+}
+
+/// Make `new_target_ref` the target branch, rebasing the commits of all applied stacks from the current base onto it.
+/// Stacks that would conflict with the new base are unapplied, or, if `abort_on_incompatible` is `true`,
+/// nothing is changed at all.
+///
+/// All stacks are rebased in memory first. Should writing the result fail, the workspace is restored from the
+/// snapshot taken right before, so either all stacks are switched or none.
+pub(crate) fn switch_base_branch(
+    ctx: &Context,
+    new_target_ref: &RemoteRefname,
+    abort_on_incompatible: bool,
+    perm: &mut WorktreeWritePermission,
+) -> Result<BaseBranchSwitch> {
+    let vb_state = ctx.legacy_project.virtual_branches();
+    let old_target = vb_state.get_default_target()?;
+    if old_target.branch == *new_target_ref {
+        bail!("{new_target_ref} already is the target branch");
+    }
+
+    let new_target = {
+        let git2_repo = &*ctx.git2_repo.get()?;
+        let new_target_branch = git2_repo
+            .maybe_find_branch_by_refname(&new_target_ref.clone().into())?
+            .ok_or_else(|| ButError::BranchNotFound {
+                name: new_target_ref.to_string(),
+            })?;
+        let remote_url = git2_repo
+            .find_remote(new_target_ref.remote())?
+            .url()
+            .with_context(|| {
+                format!(
+                    "failed to get remote url for {remote}",
+                    remote = new_target_ref.remote()
+                )
+            })?
+            .to_owned();
+        // Keep pushing to the previously configured remote, like `set_base_branch()` does.
+        let push_remote_name = old_target
+            .push_remote_name
+            .clone()
+            .filter(|name| name != new_target_ref.remote() && git2_repo.find_remote(name).is_ok());
+        Target {
+            branch: new_target_ref.clone(),
+            remote_url,
+            sha: new_target_branch.get().peel_to_commit()?.id(),
+            push_remote_name,
+        }
+    };
+
+    let planned = plan(ctx, &new_target)?;
+    let incompatible: Vec<_> = planned
+        .iter()
+        .filter(|stack| stack.rebase.is_none())
+        .map(|stack| stack.name.as_str())
+        .collect();
+    if abort_on_incompatible && !incompatible.is_empty() {
+        bail!(
+            "The base branch wasn't switched to {new_target_ref} as these stacks would conflict with it: {}",
+            incompatible.join(", ")
+        );
+    }
+
+    // Unlike most operations, the snapshot isn't optional as it's what we roll back to.
+    let snapshot = ctx.create_snapshot(SnapshotDetails::new(OperationKind::SetBaseBranch), perm)?;
+    let stacks = planned
+        .iter()
+        .map(|stack| StackSwitchOutcome {
+            stack_id: stack.stack_id,
+            name: stack.name.clone(),
+            status: if stack.rebase.is_some() {
+                StackSwitchStatus::Rebased
+            } else {
+                StackSwitchStatus::Incompatible
+            },
+        })
+        .collect();
+    match apply(ctx, &new_target, planned, perm) {
+        Ok(base_branch) => Ok(BaseBranchSwitch {
+            base_branch,
+            stacks,
+        }),
+        Err(err) => {
+            if let Err(restore_err) = ctx.restore_snapshot(snapshot, perm) {
+                tracing::error!(
+                    ?restore_err,
+                    "Failed to restore the workspace after a failed switch of the base branch"
+                );
+                return Err(err.context(format!(
+                    "Switching the base branch failed, and restoring snapshot {snapshot} failed as well"
+                )));
+            }
+            Err(err.context("Switching the base branch failed, the workspace was restored"))
+        }
+    }
+}
+
+/// Rebase all applied stacks onto the tip of `new_target` in memory, without changing any reference.
+fn plan(ctx: &Context, new_target: &Target) -> Result<Vec<PlannedStack>> {
+    let gix_repo = ctx.repo.get()?;
+    let vb_state = ctx.legacy_project.virtual_branches();
+    let mut planned = Vec::new();
+    for stack in upstream_integration::stacks(ctx, &gix_repo)? {
+        let Some(stack_id) = stack.id else {
+            continue;
+        };
+        // The details are still relative to the current base, so they only contain the commits of the stack.
+        let details = upstream_integration::stack_details(ctx, Some(stack_id))?;
+        let integrated: HashSet<_> = details
+            .branch_details
+            .iter()
+            .flat_map(|branch| &branch.commits)
+            .filter(|commit| matches!(commit.state, but_workspace::ui::CommitState::Integrated))
+            .map(|commit| commit.id)
+            .collect();
+        let steps: Vec<_> = details
+            .as_rebase_steps(&gix_repo)?
+            .into_iter()
+            .filter(|step| {
+                !matches!(step, RebaseStep::Pick { commit_id, .. } if integrated.contains(commit_id))
+            })
+            .collect();
+
+        let stack_repo = vb_state
+            .get_stack(stack_id)?
+            .repo_with_identity(&gix_repo)?;
+        let mut rebase = but_rebase::Rebase::new(&stack_repo, Some(new_target.sha.to_gix()), None)?;
+        rebase.rebase_noops(false);
+        rebase.steps(steps)?;
+        let output = rebase.rebase()?;
+        let is_conflicted = output.commit_mapping.iter().any(|(_base, _old, new)| {
+            gix_repo
+                .find_commit(*new)
+                .is_ok_and(|commit| commit.is_conflicted())
+        });
+        planned.push(PlannedStack {
+            stack_id,
+            name: details.derived_name,
+            rebase: (!is_conflicted).then_some(output),
+        });
+    }
+    Ok(planned)
+}
+
+/// Write the `planned` stacks and make `new_target` the default target.
+fn apply(
+    ctx: &Context,
+    new_target: &Target,
+    planned: Vec<PlannedStack>,
+    perm: &mut WorktreeWritePermission,
+) -> Result<BaseBranch> {
+    let old_workspace = WorkspaceState::create(ctx, perm.read_permission())?;
+    for stack in planned.iter().filter(|stack| stack.rebase.is_none()) {
+        ctx.branch_manager().unapply(
+            stack.stack_id,
+            perm,
+            false,
+            Vec::new(),
+            ctx.settings().feature_flags.cv3,
+        )?;
+    }
+
+    let vb_state = ctx.legacy_project.virtual_branches();
+    vb_state.set_default_target(new_target.clone())?;
+
+    let gix_repo = ctx.repo.get()?;
+    let mut stacks = vb_state.list_stacks_in_workspace()?;
+    for (stack_id, output) in planned
+        .into_iter()
+        .filter_map(|stack| Some((stack.stack_id, stack.rebase?)))
+    {
+        let Some(stack) = stacks.iter_mut().find(|stack| stack.id == stack_id) else {
+            continue;
+        };
+        stack.set_heads_from_rebase_output(ctx, &output)?;
+        stack.set_stack_head(&vb_state, &gix_repo, output.top_commit.to_git2())?;
+    }
+
+    let new_workspace = WorkspaceState::create(ctx, perm.read_permission())?;
+    update_uncommitted_changes(ctx, old_workspace, new_workspace, perm)?;
+    crate::integration::update_workspace_commit(&vb_state, ctx, false)?;
+
+    base::target_to_base_branch(ctx, new_target)
+}
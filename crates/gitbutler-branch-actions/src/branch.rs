@@ -9,6 +9,7 @@ use std::{
 
 use anyhow::{Context as _, Result, bail};
 use bstr::{BStr, BString, ByteSlice};
+use but_core::ref_metadata::{CommitNote, StackNotes};
 use but_ctx::Context;
 use but_oxidize::{git2_to_gix_object_id, gix_to_git2_oid};
 use but_serde::BStringForFrontend;
@@ -74,10 +75,17 @@ pub fn list_branches(
                     expensive_commit_info: false,
                 },
             )?;
-            info.stacks
+            let mut stacks = info
+                .stacks
                 .into_iter()
                 .filter_map(|s| GitButlerStack::try_new(s, &remote_names).transpose())
-                .collect::<Result<Vec<_>, _>>()?
+                .collect::<Result<Vec<_>, _>>()?;
+            for stack in &mut stacks {
+                if let Some(legacy_stack) = vb_handle.try_stack(stack.id)? {
+                    stack.notes = legacy_stack.stack_notes;
+                }
+            }
+            stacks
         } else {
             Vec::new()
         }
@@ -270,6 +278,8 @@ fn branch_group_to_branch(
             pull_requests: unarchived_branches
                 .filter_map(|b| b.pr_or_mr.map(|pr| (b.short_name().to_owned(), pr)))
                 .collect(),
+            description: stack.notes.description.clone(),
+            commit_notes: stack.notes.commits.clone(),
         }
     });
 
@@ -341,6 +351,8 @@ struct GitButlerStack {
     // All segments of the stack, as long as they are not archived.
     // The tip comes first.
     unarchived_segments: Vec<GitbutlerStackSegment>,
+    /// The description of the stack and notes about its commits.
+    notes: StackNotes,
 }
 
 #[derive(Debug, Clone)]
@@ -403,6 +415,7 @@ impl GitButlerStack {
                     pr_or_mr: s.metadata.as_ref().and_then(|md| md.review.pull_request),
                 })
                 .collect(),
+            notes: StackNotes::default(),
         }))
     }
 }
@@ -606,6 +619,10 @@ pub struct StackReference {
     pub branches: Vec<String>,
     /// Pull Request numbers by branch name associated with the stack
     pub pull_requests: HashMap<String, usize>,
+    /// The description of the stack, set by the user
+    pub description: Option<String>,
+    /// Notes about the commits of the stack, set by the user
+    pub commit_notes: Vec<CommitNote>,
 }
 
 /// Takes a list of `branch_names` (the given name, as returned by `BranchListing`) and returns
@@ -0,0 +1,189 @@
+//! How the branches of a stack diverged from their remote tracking branches, to tell if there is
+//! anything to push or to pull without attempting an integration.
+
+use std::{collections::BTreeMap, path::PathBuf, sync::Mutex};
+
+use anyhow::Result;
+use but_ctx::Context;
+use but_oxidize::OidExt;
+use gitbutler_stack::StackId;
+use serde::Serialize;
+
+use crate::VirtualBranchesExt as _;
+
+/// The commits a branch of a stack and its remote tracking branch don't have in common.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BranchDivergence {
+    /// The name of the branch, like `feature`.
+    pub name: String,
+    /// The full name of the remote tracking branch, like `refs/remotes/origin/feature`, or `None` if it doesn't exist.
+    pub remote_tracking_branch: Option<String>,
+    /// The commits of the branch that aren't on its remote tracking branch, top-most first.
+    #[serde(with = "but_serde::object_id_vec")]
+    pub local_only: Vec<gix::ObjectId>,
+    /// The commits of the remote tracking branch that aren't on the branch, top-most first.
+    #[serde(with = "but_serde::object_id_vec")]
+    pub remote_only: Vec<gix::ObjectId>,
+    /// The most recent commit the branch and its remote tracking branch have in common, or `None` if there is
+    /// no remote tracking branch or both are unrelated.
+    #[serde(with = "but_serde::object_id_opt")]
+    pub common_ancestor: Option<gix::ObjectId>,
+}
+
+/// Compute how each branch of the stack with `stack_id` diverged from its remote tracking branch on the push remote,
+/// listing the branches from the top of the stack to its bottom.
+///
+/// Only the commits of the branch itself are listed as local, not those of the branches below it.
+/// Commits of the target branch are never listed as remote, so a remote tracking branch that was rebased
+/// onto a newer target only shows the commits that were added to it.
+pub(crate) fn branch_divergence(ctx: &Context, stack_id: StackId) -> Result<Vec<BranchDivergence>> {
+    let repo = ctx.repo.get()?;
+    let vb_state = ctx.legacy_project.virtual_branches();
+    let target = vb_state.get_default_target()?;
+    let stack = vb_state.get_stack(stack_id)?;
+    let remote = target.push_remote_name();
+    let upstream_target_tip = repo
+        .try_find_reference(target.branch.to_string().as_str())?
+        .map(|mut reference| reference.peel_to_id_in_place())
+        .transpose()?
+        .map(|id| id.detach());
+
+    let mut segment_base = stack.merge_base(ctx)?;
+    let mut out = Vec::with_capacity(stack.heads.len());
+    for head in &stack.heads {
+        let local_tip = head.head_oid(&repo)?;
+        if head.archived {
+            // Integrated branches have nothing left to push or pull.
+            segment_base = local_tip;
+            continue;
+        }
+        let remote_tracking_branch = head.remote_reference(&remote);
+        let remote_tip = repo
+            .try_find_reference(remote_tracking_branch.as_str())?
+            .map(|mut reference| reference.peel_to_id_in_place())
+            .transpose()?
+            .map(|id| id.detach());
+
+        let key = DivergenceKey {
+            common_dir: repo.common_dir().to_owned(),
+            local_tip,
+            remote_tip,
+            segment_base,
+            target_tips: [Some(target.sha.to_gix()), upstream_target_tip],
+        };
+        let cached = DIVERGENCE_CACHE
+            .lock()
+            .ok()
+            .and_then(|cache| cache.get(&key).cloned());
+        let divergence = match cached {
+            Some(divergence) => divergence,
+            None => {
+                let divergence = compute(&repo, &key)?;
+                if let Ok(mut cache) = DIVERGENCE_CACHE.lock() {
+                    cache.insert(key, divergence.clone());
+                }
+                divergence
+            }
+        };
+        out.push(BranchDivergence {
+            name: head.name.clone(),
+            remote_tracking_branch: remote_tip.is_some().then_some(remote_tracking_branch),
+            local_only: divergence.local_only,
+            remote_only: divergence.remote_only,
+            common_ancestor: divergence.common_ancestor,
+        });
+        segment_base = local_tip;
+    }
+    out.reverse();
+    Ok(out)
+}
+
+/// The amount of branches whose divergence is kept in the [`DIVERGENCE_CACHE`].
+const DIVERGENCE_CACHE_CAPACITY: usize = 512;
+
+/// Divergences by the ids they were computed from, so they are recomputed as soon as one of the references moved.
+static DIVERGENCE_CACHE: Mutex<DivergenceCache> = Mutex::new(DivergenceCache::new());
+
+/// Everything that affects the divergence of a branch.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct DivergenceKey {
+    common_dir: PathBuf,
+    local_tip: gix::ObjectId,
+    remote_tip: Option<gix::ObjectId>,
+    /// The tip of the branch below, or the merge-base of the stack with the target, which bounds the local commits.
+    segment_base: gix::ObjectId,
+    /// The workspace base and the tip of the remote target branch, which bound the remote commits.
+    target_tips: [Option<gix::ObjectId>; 2],
+}
+
+#[derive(Debug, Clone)]
+struct Divergence {
+    local_only: Vec<gix::ObjectId>,
+    remote_only: Vec<gix::ObjectId>,
+    common_ancestor: Option<gix::ObjectId>,
+}
+
+struct DivergenceCache {
+    entries: BTreeMap<DivergenceKey, Divergence>,
+}
+
+impl DivergenceCache {
+    const fn new() -> Self {
+        DivergenceCache {
+            entries: BTreeMap::new(),
+        }
+    }
+
+    fn get(&self, key: &DivergenceKey) -> Option<&Divergence> {
+        self.entries.get(key)
+    }
+
+    fn insert(&mut self, key: DivergenceKey, divergence: Divergence) {
+        // Entries never become outdated, so starting over is fine and spares tracking which were used last.
+        if self.entries.len() >= DIVERGENCE_CACHE_CAPACITY {
+            self.entries.clear();
+        }
+        self.entries.insert(key, divergence);
+    }
+}
+
+fn compute(repo: &gix::Repository, key: &DivergenceKey) -> Result<Divergence> {
+    let Some(remote_tip) = key.remote_tip else {
+        return Ok(Divergence {
+            local_only: commits_between(repo, key.local_tip, [key.segment_base])?,
+            remote_only: Vec::new(),
+            common_ancestor: None,
+        });
+    };
+    let common_ancestor = repo
+        .merge_base(key.local_tip, remote_tip)
+        .ok()
+        .map(|id| id.detach());
+    Ok(Divergence {
+        local_only: commits_between(repo, key.local_tip, [key.segment_base, remote_tip])?,
+        remote_only: commits_between(
+            repo,
+            remote_tip,
+            key.target_tips
+                .iter()
+                .flatten()
+                .copied()
+                .chain(Some(key.local_tip)),
+        )?,
+        common_ancestor,
+    })
+}
+
+/// Return all commits reachable from `tip` but not from any of `hidden`, the most recent one first.
+fn commits_between(
+    repo: &gix::Repository,
+    tip: gix::ObjectId,
+    hidden: impl IntoIterator<Item = gix::ObjectId>,
+) -> Result<Vec<gix::ObjectId>> {
+    repo.rev_walk([tip])
+        .with_hidden(hidden)
+        .all()?
+        .map(|info| Ok(info?.id))
+        .collect()
+}
@@ -343,7 +343,7 @@ impl BranchManager<'_> {
 
             stack.set_stack_head(&vb_state, &gix_repo, new_head.id())?;
 
-            stack.set_heads_from_rebase_output(self.ctx, output.references)?;
+            stack.set_heads_from_rebase_output(self.ctx, &output)?;
         }
 
         // apply the branch
@@ -108,6 +108,7 @@ impl BranchManager<'_> {
                 repo.find_tree(merge.tree.write()?.to_git2())?;
 
             repo.checkout_tree_builder(&new_workspace_tree_with_worktree_changes)
+                .only_changes_from(&repo.find_tree(cwdt.to_git2())?)?
                 .force()
                 .checkout()
                 .context("failed to checkout tree")?;
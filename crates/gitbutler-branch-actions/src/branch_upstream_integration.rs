@@ -1,12 +1,16 @@
 use anyhow::{Result, bail};
+use bstr::ByteSlice;
 use but_ctx::{Context, access::WorktreeWritePermission};
 use but_meta::VirtualBranchesTomlMetadata;
 use but_oxidize::ObjectIdExt;
 use but_rebase::{Rebase, RebaseStep};
-use but_workspace::{legacy::stack_ext::StackExt, ui::CommitState};
+use but_workspace::{
+    legacy::stack_ext::StackExt,
+    ui::{BranchDetails, CommitState},
+};
 use gitbutler_stack::{StackId, VirtualBranchesHandle};
 use gitbutler_workspace::branch_trees::{WorkspaceState, update_uncommitted_changes};
-use gix::ObjectId;
+use gix::{ObjectId, prelude::ObjectIdExt as _};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
@@ -47,13 +51,22 @@ pub fn get_initial_integration_steps_for_branch(
     stack_id: Option<StackId>,
     branch_name: String,
 ) -> Result<Vec<InteractiveIntegrationStep>> {
+    let branch_details = branch_details(ctx, stack_id, &branch_name)?;
+    Ok(integration_steps(branch_details, true))
+}
+
+fn branch_details(
+    ctx: &Context,
+    stack_id: Option<StackId>,
+    branch_name: &str,
+) -> Result<BranchDetails> {
     let repo = ctx.repo.get()?;
     let project = &ctx.legacy_project;
     let meta =
         VirtualBranchesTomlMetadata::from_path(project.gb_dir().join("virtual_branches.toml"))?;
     let stack_details = but_workspace::legacy::stack_details_v3(stack_id, &repo, &meta)?;
 
-    let branch_details = stack_details
+    stack_details
         .branch_details
         .into_iter()
         .find(|b| b.name == branch_name)
@@ -63,8 +76,15 @@ pub fn get_initial_integration_steps_for_branch(
                 branch_name,
                 stack_id
             )
-        })?;
+        })
+}
 
+/// List the steps to put the local-only commits of `branch_details` on top of its upstream commits,
+/// or to drop them if `keep_local_only` is `false`.
+fn integration_steps(
+    branch_details: BranchDetails,
+    keep_local_only: bool,
+) -> Vec<InteractiveIntegrationStep> {
     let mut initial_steps = vec![];
 
     let (local_only_commits, local_and_remote_commits) = branch_details
@@ -73,9 +93,16 @@ pub fn get_initial_integration_steps_for_branch(
         .partition::<Vec<_>, _>(|c| matches!(c.state, CommitState::LocalOnly));
 
     for commit in local_only_commits {
-        initial_steps.push(InteractiveIntegrationStep::Pick {
-            id: Uuid::new_v4(),
-            commit_id: commit.id,
+        initial_steps.push(if keep_local_only {
+            InteractiveIntegrationStep::Pick {
+                id: Uuid::new_v4(),
+                commit_id: commit.id,
+            }
+        } else {
+            InteractiveIntegrationStep::Skip {
+                id: Uuid::new_v4(),
+                commit_id: commit.id,
+            }
         });
     }
 
@@ -93,7 +120,7 @@ pub fn get_initial_integration_steps_for_branch(
         });
     }
 
-    Ok(initial_steps)
+    initial_steps
 }
 
 /// Integrate a branch with the given steps.
@@ -173,7 +200,7 @@ pub fn integrate_branch_with_steps(
     source_stack.set_stack_head(&vb_state, &repository, head)?;
     let new_workspace = WorkspaceState::create(ctx, perm.read_permission())?;
     update_uncommitted_changes(ctx, old_workspace, new_workspace, perm)?;
-    source_stack.set_heads_from_rebase_output(ctx, result.references)?;
+    source_stack.set_heads_from_rebase_output(ctx, &result)?;
 
     crate::integration::update_workspace_commit(&vb_state, ctx, false)?;
 
@@ -241,26 +268,100 @@ pub fn integrate_upstream_commits_for_series(
     integration_strategy: Option<IntegrationStrategy>,
 ) -> Result<()> {
     let strategy = integration_strategy.unwrap_or(IntegrationStrategy::Rebase);
-    match strategy {
-        IntegrationStrategy::Merge => {
-            // TODO: Have a nice way of doing merge integration
-            bail!("Merge strategy is not supported yet. Please use Rebase strategy.");
-        }
-        IntegrationStrategy::Rebase => {
-            let steps = get_initial_integration_steps_for_branch(
-                ctx,
-                Some(stack_id),
-                series_name.to_owned(),
-            )?;
-            integrate_branch_with_steps(ctx, stack_id, series_name, steps, perm)
-        }
+    let branch_details = branch_details(ctx, Some(stack_id), &series_name)?;
+    let steps = match strategy {
+        IntegrationStrategy::Merge => merge_steps(ctx, branch_details)?,
+        IntegrationStrategy::Rebase => integration_steps(branch_details, true),
+        IntegrationStrategy::HardReset => integration_steps(branch_details, false),
+    };
+    integrate_branch_with_steps(ctx, stack_id, series_name, steps, perm)
+}
+
+/// Integrate the upstream commits of all branches of the stack with `stack_id` using `strategy`, from the bottom
+/// of the stack to its top, without changing the target or any other stack.
+///
+/// Branches without upstream commits are left as they are.
+pub fn integrate_upstream_for_stack(
+    ctx: &Context,
+    stack_id: StackId,
+    strategy: IntegrationStrategy,
+    perm: &mut WorktreeWritePermission,
+) -> Result<()> {
+    let repo = ctx.repo.get()?;
+    let meta = VirtualBranchesTomlMetadata::from_path(
+        ctx.legacy_project.gb_dir().join("virtual_branches.toml"),
+    )?;
+    let branches_with_upstream_commits: Vec<_> =
+        but_workspace::legacy::stack_details_v3(Some(stack_id), &repo, &meta)?
+            .branch_details
+            .into_iter()
+            .rev()
+            .filter(|branch| !branch.upstream_commits.is_empty())
+            .map(|branch| branch.name.to_string())
+            .collect();
+
+    for branch_name in branches_with_upstream_commits {
+        integrate_upstream_commits_for_series(ctx, stack_id, perm, branch_name, Some(strategy))?;
     }
+    Ok(())
+}
+
+/// Keep all commits of the branch and put a merge with its remote tracking branch on top.
+fn merge_steps(
+    ctx: &Context,
+    branch_details: BranchDetails,
+) -> Result<Vec<InteractiveIntegrationStep>> {
+    let repo = ctx.repo.get()?;
+    let Some(remote_tracking_branch) = branch_details.remote_tracking_branch.as_ref() else {
+        bail!(
+            "Branch '{}' has no remote tracking branch to merge",
+            branch_details.name
+        );
+    };
+    let remote_tip = repo
+        .find_reference(remote_tracking_branch.as_bstr())?
+        .peel_to_id_in_place()?
+        .detach();
+    let remote_name = remote_tracking_branch.as_bstr();
+    let remote_name = remote_name
+        .strip_prefix(b"refs/remotes/")
+        .map_or(remote_name, ByteSlice::as_bstr);
+    let merge_commit_id = gitbutler_repo::rebase::merge_commits(
+        &repo,
+        branch_details.tip,
+        remote_tip,
+        &format!("Merge `{remote_name}` into `{}`", branch_details.name),
+    )?;
+    if but_core::Commit::from_id(merge_commit_id.attach(&repo))?.is_conflicted() {
+        bail!(
+            "Merging '{remote_name}' into '{}' conflicts, rebase or reset the branch instead",
+            branch_details.name
+        );
+    }
+
+    Ok(std::iter::once(InteractiveIntegrationStep::Pick {
+        id: Uuid::new_v4(),
+        commit_id: merge_commit_id,
+    })
+    .chain(
+        branch_details
+            .commits
+            .iter()
+            .map(|commit| InteractiveIntegrationStep::Pick {
+                id: Uuid::new_v4(),
+                commit_id: commit.id,
+            }),
+    )
+    .collect())
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
 #[serde(tag = "type", rename_all = "lowercase")]
 pub enum IntegrationStrategy {
+    /// Merge the remote tracking branch into the branch.
     Merge,
+    /// Put the local-only commits of the branch on top of its upstream commits.
     Rebase,
-    // TODO: HardReset,
+    /// Make the branch look like its remote tracking branch, dropping all local-only commits.
+    HardReset,
 }
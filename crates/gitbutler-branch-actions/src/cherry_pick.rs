@@ -0,0 +1,206 @@
+use anyhow::{Context as _, Result, bail};
+use bstr::{BString, ByteSlice};
+use but_ctx::{Context, access::WorktreeWritePermission};
+use but_oxidize::ObjectIdExt as _;
+use but_rebase::RebaseStep;
+use but_workspace::legacy::stack_ext::StackExt;
+use gitbutler_stack::StackId;
+use gitbutler_workspace::branch_trees::{WorkspaceState, update_uncommitted_changes};
+use gix::prelude::ObjectIdExt as _;
+use serde::Serialize;
+use tracing::instrument;
+
+use crate::VirtualBranchesExt as _;
+
+/// A commit that was cherry-picked into a stack.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CherryPickedCommit {
+    /// The commit that was picked.
+    #[serde(with = "but_serde::object_id")]
+    pub source_id: gix::ObjectId,
+    /// The newly created commit at the top of the stack.
+    #[serde(with = "but_serde::object_id")]
+    pub new_id: gix::ObjectId,
+    /// If `true`, the commit couldn't be applied cleanly and is now conflicted.
+    pub conflicted: bool,
+}
+
+/// Place copies of `commit_ids`, in order, on top of the stack with `stack_id`.
+///
+/// The commits may come from anywhere in the repository. Merge commits are picked relative to their
+/// parent at the 1-based `mainline`, like `git cherry-pick -m`, which is required if there are any.
+/// Each new commit gets a `(cherry picked from commit <id>)` line, like `git cherry-pick -x` would add.
+///
+/// Commits that don't apply cleanly are still picked, but in a conflicted state.
+#[instrument(level = "debug", skip(ctx, perm))]
+pub(crate) fn cherry_pick_into_stack(
+    ctx: &Context,
+    stack_id: StackId,
+    commit_ids: Vec<gix::ObjectId>,
+    mainline: Option<usize>,
+    perm: &mut WorktreeWritePermission,
+) -> Result<Vec<CherryPickedCommit>> {
+    if commit_ids.is_empty() {
+        bail!("At least one commit to cherry-pick is required");
+    }
+    let repo = ctx.repo.get()?;
+    let mut to_pick = Vec::with_capacity(commit_ids.len());
+    for source_id in &commit_ids {
+        to_pick.push(commit_to_pick(&repo, *source_id, mainline)?);
+    }
+
+    let new_ids = pick_onto_stack(ctx, stack_id, &to_pick, perm)?;
+    let mut picked = Vec::with_capacity(commit_ids.len());
+    for (source_id, new_id) in commit_ids.into_iter().zip(new_ids) {
+        picked.push(CherryPickedCommit {
+            source_id,
+            new_id,
+            conflicted: but_core::Commit::from_id(new_id.attach(&repo))?.is_conflicted(),
+        });
+    }
+    Ok(picked)
+}
+
+/// Pick the single-parent commits `to_pick`, in order, on top of the stack with `stack_id`, and update the
+/// stack, the workspace commit and the worktree accordingly.
+///
+/// Return the ids of the new commits in the order of `to_pick`.
+pub(crate) fn pick_onto_stack(
+    ctx: &Context,
+    stack_id: StackId,
+    to_pick: &[gix::ObjectId],
+    perm: &mut WorktreeWritePermission,
+) -> Result<Vec<gix::ObjectId>> {
+    let old_workspace = WorkspaceState::create(ctx, perm.read_permission())?;
+    let vb_state = ctx.legacy_project.virtual_branches();
+    let mut stack = vb_state.get_stack_in_workspace(stack_id)?;
+    let repo = ctx.repo.get()?;
+    let repo = stack.repo_with_identity(&repo)?;
+
+    let mut steps = stack.as_rebase_steps(ctx, &repo)?;
+    // Insert before the reference of the top-most branch, which is the last step.
+    let top_reference = steps.pop().context("Stacks always have a head")?;
+    steps.extend(to_pick.iter().map(|commit_id| RebaseStep::Pick {
+        commit_id: *commit_id,
+        new_message: None,
+    }));
+    steps.push(top_reference);
+
+    let mut rebase = but_rebase::Rebase::new(&repo, stack.merge_base(ctx)?, None)?;
+    rebase.rebase_noops(false);
+    rebase.steps(steps)?;
+    let output = rebase.rebase()?;
+
+    let new_ids = to_pick
+        .iter()
+        .map(|to_pick| {
+            output
+                .commit_mapping
+                .iter()
+                .find_map(|(_base, old, new)| (old == to_pick).then_some(*new))
+                // The commit already sits on the right parent and was kept as is.
+                .unwrap_or(*to_pick)
+        })
+        .collect();
+
+    stack.set_stack_head(&vb_state, &repo, output.top_commit.to_git2())?;
+    stack.set_heads_from_rebase_output(ctx, &output)?;
+
+    let new_workspace = WorkspaceState::create(ctx, perm.read_permission())?;
+    update_uncommitted_changes(ctx, old_workspace, new_workspace, perm)?;
+    crate::integration::update_workspace_commit(&vb_state, ctx, false)
+        .context("failed to update gitbutler workspace")?;
+
+    Ok(new_ids)
+}
+
+/// Write a commit that is like `source_id`, but with a single parent selected by `mainline` and with the
+/// source recorded in its message, so it can be picked like any other commit.
+fn commit_to_pick(
+    repo: &gix::Repository,
+    source_id: gix::ObjectId,
+    mainline: Option<usize>,
+) -> Result<gix::ObjectId> {
+    let source = but_core::Commit::from_id(source_id.attach(repo))?;
+    if source.is_conflicted() {
+        bail!("Cannot cherry-pick conflicted commit {source_id}");
+    }
+    let parent = match (source.parents.as_slice(), mainline) {
+        ([], _) => bail!("Cannot cherry-pick root commit {source_id}"),
+        ([parent], None | Some(1)) => *parent,
+        ([_], Some(mainline)) => {
+            bail!("Commit {source_id} is not a merge commit, but mainline {mainline} was given")
+        }
+        (_, None) => bail!("Commit {source_id} is a merge commit, but no mainline was given"),
+        (parents, Some(mainline)) => *mainline
+            .checked_sub(1)
+            .and_then(|idx| parents.get(idx))
+            .with_context(|| {
+                format!(
+                    "Commit {source_id} has {} parents, so mainline {mainline} is out of range",
+                    parents.len()
+                )
+            })?,
+    };
+
+    let mut commit = source.inner;
+    commit.parents = vec![parent].into();
+    commit.message = message_with_source(commit.message.as_ref(), source_id);
+    // Signatures and GitButler headers belong to the original commit, new ones will be created.
+    commit.extra_headers.clear();
+    Ok(repo.write_object(&commit)?.detach())
+}
+
+/// Append the line `git cherry-pick -x` would add for `source_id` to `message`.
+fn message_with_source(message: &[u8], source_id: gix::ObjectId) -> BString {
+    let line = format!("(cherry picked from commit {source_id})");
+    let mut out = BString::from(message.trim_end());
+    if out
+        .lines()
+        .last()
+        .is_some_and(|last| last == line.as_bytes())
+    {
+        out.push(b'\n');
+        return out;
+    }
+    let ends_with_trailers = out
+        .lines()
+        .last()
+        .is_some_and(|last| last.starts_with(b"(cherry picked from commit "));
+    out.push(b'\n');
+    if !ends_with_trailers {
+        out.push(b'\n');
+    }
+    out.extend_from_slice(line.as_bytes());
+    out.push(b'\n');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::message_with_source;
+
+    fn id(hex: char) -> gix::ObjectId {
+        gix::ObjectId::from_hex(hex.to_string().repeat(40).as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn source_line_is_appended_once() {
+        let once = message_with_source(b"subject\n\nbody\n", id('a'));
+        assert_eq!(
+            once,
+            "subject\n\nbody\n\n(cherry picked from commit aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa)\n"
+        );
+        assert_eq!(message_with_source(once.as_ref(), id('a')), once);
+    }
+
+    #[test]
+    fn source_lines_are_grouped() {
+        let twice = message_with_source(message_with_source(b"subject", id('a')).as_ref(), id('b'));
+        assert_eq!(
+            twice,
+            "subject\n\n(cherry picked from commit aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa)\n(cherry picked from commit bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb)\n"
+        );
+    }
+}
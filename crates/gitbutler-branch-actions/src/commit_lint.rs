@@ -0,0 +1,396 @@
+//! Linting of commit messages, with rules configured per project in its local Git configuration.
+//!
+//! Findings are meant to warn before committing, and those that are [fixable](LintFinding::fixable)
+//! can be addressed with [`LintRules::fix()`].
+use anyhow::Result;
+use but_core::RepositoryExt as _;
+use but_ctx::Context;
+use serde::{Deserialize, Serialize};
+
+const CONVENTIONAL: &str = "gitbutler.commitLint.conventional";
+const TYPES: &str = "gitbutler.commitLint.types";
+const MAX_SUBJECT_LENGTH: &str = "gitbutler.commitLint.maxSubjectLength";
+const BODY_WRAP: &str = "gitbutler.commitLint.bodyWrap";
+
+/// The commit types allowed by default if [conventional commits](LintRules::conventional) are enforced.
+pub const DEFAULT_TYPES: &[&str] = &[
+    "build", "chore", "ci", "docs", "feat", "fix", "perf", "refactor", "revert", "style", "test",
+];
+const DEFAULT_MAX_SUBJECT_LENGTH: usize = 72;
+const DEFAULT_BODY_WRAP: usize = 72;
+
+/// The rules to check commit messages against.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LintRules {
+    /// If `true`, subjects must look like `type(scope)!: description`, where scope and `!` are optional.
+    pub conventional: bool,
+    /// The types allowed in conventional commit subjects.
+    pub types: Vec<String>,
+    /// The maximum amount of characters in the subject, or `None` to allow any length.
+    pub max_subject_length: Option<usize>,
+    /// The column to wrap body lines at, or `None` to allow any length.
+    pub body_wrap: Option<usize>,
+}
+
+impl Default for LintRules {
+    fn default() -> Self {
+        LintRules {
+            conventional: false,
+            types: DEFAULT_TYPES.iter().map(|t| t.to_string()).collect(),
+            max_subject_length: Some(DEFAULT_MAX_SUBJECT_LENGTH),
+            body_wrap: Some(DEFAULT_BODY_WRAP),
+        }
+    }
+}
+
+/// The rule a [`LintFinding`] is about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LintRule {
+    /// The subject is empty.
+    EmptySubject,
+    /// The subject isn't a conventional commit subject.
+    ConventionalFormat,
+    /// The subject has a conventional commit type that isn't allowed.
+    ConventionalType,
+    /// The subject is too long.
+    SubjectLength,
+    /// The subject isn't followed by an empty line.
+    BlankLineAfterSubject,
+    /// A line of the body is longer than the wrapping column.
+    BodyLineLength,
+}
+
+/// A violation of one of the [`LintRules`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LintFinding {
+    /// The rule that was violated.
+    pub rule: LintRule,
+    /// The 1-based number of the line that violates the rule.
+    pub line: usize,
+    /// A description of the violation, for display to the user.
+    pub message: String,
+    /// If `true`, [`LintRules::fix()`] will address the violation.
+    pub fixable: bool,
+}
+
+/// Lifecycle
+impl LintRules {
+    /// Read the rules from the configuration of `repo`, using defaults for everything that isn't configured.
+    pub fn from_repo(repo: &gix::Repository) -> Result<Self> {
+        let config = repo.config_snapshot();
+        let defaults = LintRules::default();
+        let length = |key: &str, default: Option<usize>| {
+            config
+                .integer(key)
+                .map(|len| usize::try_from(len).ok().filter(|len| *len > 0))
+                .unwrap_or(default)
+        };
+        Ok(LintRules {
+            conventional: config
+                .boolean(CONVENTIONAL)
+                .unwrap_or(defaults.conventional),
+            types: config
+                .string(TYPES)
+                .map(|types| {
+                    types
+                        .to_string()
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|t| !t.is_empty())
+                        .map(ToOwned::to_owned)
+                        .collect()
+                })
+                .unwrap_or(defaults.types),
+            max_subject_length: length(MAX_SUBJECT_LENGTH, defaults.max_subject_length),
+            body_wrap: length(BODY_WRAP, defaults.body_wrap),
+        })
+    }
+
+    /// Write the rules to the local `.git/config` file of `repo`, where `0` disables length limits.
+    pub fn persist_to_local_config(&self, repo: &gix::Repository) -> Result<()> {
+        let mut config = repo.local_common_config_for_editing()?;
+        config.set_raw_value(
+            &CONVENTIONAL,
+            if self.conventional { "true" } else { "false" },
+        )?;
+        config.set_raw_value(&TYPES, self.types.join(",").as_str())?;
+        config.set_raw_value(
+            &MAX_SUBJECT_LENGTH,
+            self.max_subject_length.unwrap_or(0).to_string().as_str(),
+        )?;
+        config.set_raw_value(&BODY_WRAP, self.body_wrap.unwrap_or(0).to_string().as_str())?;
+        repo.write_local_common_config(&config)?;
+        Ok(())
+    }
+}
+
+/// Linting
+impl LintRules {
+    /// Check `message` against all rules and return what violates them, in order of appearance.
+    pub fn lint(&self, message: &str) -> Vec<LintFinding> {
+        let mut findings = Vec::new();
+        let mut lines = message.lines();
+        let subject = lines.next().unwrap_or_default();
+        if subject.trim().is_empty() {
+            findings.push(LintFinding {
+                rule: LintRule::EmptySubject,
+                line: 1,
+                message: "The subject is empty".into(),
+                fixable: false,
+            });
+        } else if self.conventional {
+            match conventional_type(subject) {
+                None => findings.push(LintFinding {
+                    rule: LintRule::ConventionalFormat,
+                    line: 1,
+                    message: "The subject should look like 'type(scope): description'".into(),
+                    fixable: false,
+                }),
+                Some(ty) if !self.types.iter().any(|t| t == ty) => findings.push(LintFinding {
+                    rule: LintRule::ConventionalType,
+                    line: 1,
+                    message: format!("The type '{ty}' isn't one of {}", self.types.join(", ")),
+                    fixable: false,
+                }),
+                Some(_) => {}
+            }
+        }
+        if let Some(max) = self.max_subject_length {
+            let len = subject.chars().count();
+            if len > max {
+                findings.push(LintFinding {
+                    rule: LintRule::SubjectLength,
+                    line: 1,
+                    message: format!("The subject has {len} characters, more than {max}"),
+                    fixable: false,
+                });
+            }
+        }
+        if lines.next().is_some_and(|line| !line.trim().is_empty()) {
+            findings.push(LintFinding {
+                rule: LintRule::BlankLineAfterSubject,
+                line: 2,
+                message: "The subject should be followed by an empty line".into(),
+                fixable: true,
+            });
+        }
+        if let Some(width) = self.body_wrap {
+            for (idx, paragraph) in body_paragraphs(message) {
+                let fixable = is_rewrappable(&paragraph);
+                for (offset, line) in paragraph.iter().enumerate() {
+                    let len = line.chars().count();
+                    if len > width && line.contains(' ') && !is_trailer(line) {
+                        findings.push(LintFinding {
+                            rule: LintRule::BodyLineLength,
+                            line: idx + offset + 1,
+                            message: format!("The line has {len} characters, more than {width}"),
+                            fixable,
+                        });
+                    }
+                }
+            }
+        }
+        findings
+    }
+
+    /// Return `message` with all [fixable](LintFinding::fixable) findings addressed, which is separating the
+    /// subject from the body and rewrapping body paragraphs that aren't indented, lists or trailers.
+    pub fn fix(&self, message: &str) -> String {
+        let mut lines = message.lines();
+        let mut out = lines.next().unwrap_or_default().to_owned();
+        if lines.next().is_none() {
+            return message.to_owned();
+        }
+        out.push('\n');
+        for (_idx, paragraph) in body_paragraphs(message) {
+            out.push('\n');
+            match self.body_wrap {
+                Some(width) if is_rewrappable(&paragraph) => {
+                    let words: Vec<_> = paragraph
+                        .iter()
+                        .flat_map(|line| line.split_whitespace())
+                        .collect();
+                    out.push_str(&wrap(&words, width));
+                }
+                _ => out.push_str(&paragraph.join("\n")),
+            }
+            out.push('\n');
+        }
+        if !message.ends_with('\n') {
+            out.pop();
+        }
+        out
+    }
+}
+
+/// Check `message` with the rules of the project in `ctx`, see [`LintRules::lint()`].
+pub fn validate_commit_message(ctx: &Context, message: &str) -> Result<Vec<LintFinding>> {
+    Ok(LintRules::from_repo(&*ctx.repo.get()?)?.lint(message))
+}
+
+/// Fix `message` with the rules of the project in `ctx`, see [`LintRules::fix()`].
+pub fn fix_commit_message(ctx: &Context, message: &str) -> Result<String> {
+    Ok(LintRules::from_repo(&*ctx.repo.get()?)?.fix(message))
+}
+
+/// Return the type of the conventional commit `subject`, or `None` if it's not a conventional commit subject.
+fn conventional_type(subject: &str) -> Option<&str> {
+    let (head, description) = subject.split_once(": ")?;
+    if description.trim().is_empty() {
+        return None;
+    }
+    let head = head.strip_suffix('!').unwrap_or(head);
+    let ty = match head.split_once('(') {
+        Some((ty, scope)) => {
+            let scope = scope.strip_suffix(')')?;
+            if scope.is_empty() || scope.contains(['(', ')']) {
+                return None;
+            }
+            ty
+        }
+        None => head,
+    };
+    (!ty.is_empty() && ty.chars().all(|c| c.is_ascii_alphabetic())).then_some(ty)
+}
+
+/// Return all paragraphs after the subject, along with the 0-based index of their first line.
+fn body_paragraphs(message: &str) -> Vec<(usize, Vec<&str>)> {
+    let mut paragraphs = Vec::new();
+    let mut current: Option<(usize, Vec<&str>)> = None;
+    // The subject ends at the first line, whether it's followed by a blank line or not.
+    for (idx, line) in message.lines().enumerate().skip(1) {
+        if line.trim().is_empty() {
+            paragraphs.extend(current.take());
+        } else {
+            current
+                .get_or_insert_with(|| (idx, Vec::new()))
+                .1
+                .push(line);
+        }
+    }
+    paragraphs.extend(current);
+    paragraphs
+}
+
+/// A paragraph can be rewrapped if it's just prose, and not something that is formatted on purpose.
+fn is_rewrappable(paragraph: &[&str]) -> bool {
+    !paragraph.iter().any(|line| {
+        line.starts_with(char::is_whitespace)
+            || line.starts_with(['-', '*', '>', '#', '|'])
+            || line
+                .split_once(". ")
+                .is_some_and(|(n, _)| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+            || is_trailer(line)
+    })
+}
+
+/// A trailer has the format `Token: value`, with a token without spaces.
+fn is_trailer(line: &str) -> bool {
+    line.split_once(": ").is_some_and(|(token, value)| {
+        !token.is_empty() && !token.contains(' ') && !value.is_empty()
+    })
+}
+
+/// Greedily fill lines with `words` so they don't exceed `width`, unless a single word is longer.
+fn wrap(words: &[&str], width: usize) -> String {
+    let mut out = String::new();
+    let mut line_len = 0;
+    for word in words {
+        let word_len = word.chars().count();
+        if line_len > 0 && line_len + 1 + word_len > width {
+            out.push('\n');
+            line_len = 0;
+        }
+        if line_len > 0 {
+            out.push(' ');
+            line_len += 1;
+        }
+        out.push_str(word);
+        line_len += word_len;
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(mutate: impl FnOnce(&mut LintRules)) -> LintRules {
+        let mut rules = LintRules::default();
+        mutate(&mut rules);
+        rules
+    }
+
+    fn lint_rules(rules: &LintRules, message: &str) -> Vec<LintRule> {
+        rules.lint(message).into_iter().map(|f| f.rule).collect()
+    }
+
+    #[test]
+    fn conventional_subjects() {
+        let rules = rules(|r| r.conventional = true);
+        for subject in [
+            "feat: add",
+            "fix(ui): fix",
+            "refactor!: break",
+            "feat(a-b)!: x",
+        ] {
+            assert!(lint_rules(&rules, subject).is_empty(), "{subject}");
+        }
+        assert_eq!(
+            lint_rules(&rules, "add things"),
+            [LintRule::ConventionalFormat]
+        );
+        assert_eq!(
+            lint_rules(&rules, "feat(): add"),
+            [LintRule::ConventionalFormat]
+        );
+        assert_eq!(lint_rules(&rules, "wip: add"), [LintRule::ConventionalType]);
+        assert_eq!(lint_rules(&rules, ""), [LintRule::EmptySubject]);
+        assert!(
+            lint_rules(&LintRules::default(), "add things").is_empty(),
+            "not enforced by default"
+        );
+    }
+
+    #[test]
+    fn subject_and_body_layout() {
+        let rules = rules(|r| {
+            r.max_subject_length = Some(10);
+            r.body_wrap = Some(20);
+        });
+        let findings = rules.lint(
+            "a subject that is long\nbody right away\n\nthis line is longer than twenty\n  indented, but still longer than twenty\nhttps://example.com/a/very/long/url\nSigned-off-by: Someone With A Long Name <someone@example.com>",
+        );
+        assert_eq!(
+            findings
+                .iter()
+                .map(|f| (f.rule, f.line, f.fixable))
+                .collect::<Vec<_>>(),
+            [
+                (LintRule::SubjectLength, 1, false),
+                (LintRule::BlankLineAfterSubject, 2, true),
+                (LintRule::BodyLineLength, 4, false),
+                (LintRule::BodyLineLength, 5, false),
+            ],
+            "the paragraph with the indented line can't be rewrapped, URLs and trailers are fine"
+        );
+    }
+
+    #[test]
+    fn fix_rewraps_prose_only() {
+        let rules = rules(|r| r.body_wrap = Some(20));
+        let fixed = rules.fix(
+            "subject\nthis is a paragraph that is\nway too long\n\n- a list item that stays as it is\n\nKey: value\n",
+        );
+        assert_eq!(
+            fixed,
+            "subject\n\nthis is a paragraph\nthat is way too long\n\n- a list item that stays as it is\n\nKey: value\n"
+        );
+        assert_eq!(rules.fix(&fixed), fixed, "fixing is idempotent");
+        assert_eq!(rules.lint(&fixed).iter().filter(|f| f.fixable).count(), 0);
+        assert_eq!(rules.fix("subject only"), "subject only");
+    }
+}
@@ -0,0 +1,425 @@
+//! The three versions of each file of a conflicted commit, split into hunks, so conflicts can be resolved
+//! in a merge editor instead of editing conflict markers.
+//!
+//! When a commit conflicts while it's rebased, it's kept along with the trees of all sides of the merge:
+//! *ours* is the commit it was rebased onto, *theirs* is the commit itself as it was before, and *base*
+//! is the commit it was originally based on.
+
+use std::{collections::HashMap, ops::Range};
+
+use anyhow::Result;
+use bstr::{BString, ByteSlice};
+use but_core::commit::TreeKind;
+use but_ctx::Context;
+use but_oxidize::OidExt;
+use gitbutler_stack::RewrittenCommitsHandle;
+use gix::prelude::ObjectIdExt as _;
+use serde::Serialize;
+
+use crate::VirtualBranchesExt as _;
+
+/// Everything needed to resolve the conflicts of a conflicted commit.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConflictDetails {
+    /// The conflicted commit.
+    #[serde(with = "but_serde::object_id")]
+    pub commit_id: gix::ObjectId,
+    /// Where the base version of the files comes from.
+    pub base: ConflictSide,
+    /// Where our version of the files comes from.
+    pub ours: ConflictSide,
+    /// Where their version of the files comes from.
+    pub theirs: ConflictSide,
+    /// The conflicted files, sorted by path.
+    pub files: Vec<ConflictedFile>,
+}
+
+/// The commit one side of a conflict comes from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConflictSide {
+    /// The commit, or `None` if it isn't known anymore.
+    #[serde(with = "but_serde::object_id_opt")]
+    pub commit_id: Option<gix::ObjectId>,
+    /// The name of the branch in the workspace that contains the commit, or `None` if no branch does.
+    pub branch_name: Option<String>,
+    /// What to call the side in a merge editor, like `feature: 1a2b3c4 Add login`.
+    pub label: String,
+}
+
+/// The versions of a conflicted file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConflictedFile {
+    /// The path of the file, relative to the worktree.
+    #[serde(serialize_with = "but_serde::bstring_lossy::serialize")]
+    pub path: BString,
+    /// The blob of the base version, or `None` if the file didn't exist in it.
+    #[serde(with = "but_serde::object_id_opt")]
+    pub base_id: Option<gix::ObjectId>,
+    /// The blob of our version, or `None` if the file doesn't exist in it.
+    #[serde(with = "but_serde::object_id_opt")]
+    pub ours_id: Option<gix::ObjectId>,
+    /// The blob of their version, or `None` if the file doesn't exist in it.
+    #[serde(with = "but_serde::object_id_opt")]
+    pub theirs_id: Option<gix::ObjectId>,
+    /// `true` if one of the versions is binary or not a file, so it can't be split into `hunks`.
+    pub is_binary: bool,
+    /// The lines that were changed by either side, in order.
+    pub hunks: Vec<ConflictHunk>,
+}
+
+/// Lines that were changed by one or both sides of a conflict, as they read in each version.
+///
+/// Lines are counted from 1, and if there are no lines on a side, its start is the line they would be inserted before.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConflictHunk {
+    /// The first line in the base version.
+    pub base_start: u32,
+    /// The amount of lines in the base version.
+    pub base_lines: u32,
+    /// The first line in our version.
+    pub ours_start: u32,
+    /// The amount of lines in our version.
+    pub ours_lines: u32,
+    /// The first line in their version.
+    pub theirs_start: u32,
+    /// The amount of lines in their version.
+    pub theirs_lines: u32,
+    /// The lines of the base version.
+    #[serde(serialize_with = "but_serde::bstring_lossy::serialize")]
+    pub base: BString,
+    /// The lines of our version.
+    #[serde(serialize_with = "but_serde::bstring_lossy::serialize")]
+    pub ours: BString,
+    /// The lines of their version.
+    #[serde(serialize_with = "but_serde::bstring_lossy::serialize")]
+    pub theirs: BString,
+    /// The sides that changed the lines.
+    pub changed_by: ChangedBy,
+    /// `true` if both sides changed the lines differently, which is what needs to be resolved.
+    pub is_conflict: bool,
+}
+
+/// The sides of a conflict that changed lines of the base version.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ChangedBy {
+    /// Only our side changed the lines.
+    Ours,
+    /// Only their side changed the lines.
+    Theirs,
+    /// Both sides changed the lines.
+    Both,
+}
+
+/// Return the conflicts of the commit with `commit_id` with the versions of all sides of each conflicted file,
+/// or `None` if the commit isn't conflicted.
+pub(crate) fn conflict_details(
+    ctx: &Context,
+    commit_id: gix::ObjectId,
+) -> Result<Option<ConflictDetails>> {
+    let repo = ctx.repo.get()?;
+    let commit = but_core::Commit::from_id(commit_id.attach(&repo))?;
+    let Some(entries) = commit.conflict_entries()? else {
+        return Ok(None);
+    };
+
+    // Their side is the commit as it was before it was rebased, which is contained in the same branch.
+    let original_id = RewrittenCommitsHandle::new(ctx.project_data_dir())
+        .read_file()?
+        .into_iter()
+        .rev()
+        .find_map(|(old, new)| (new == commit_id).then_some(old))
+        .filter(|id| repo.has_object(id));
+    let original_parent_id = original_id
+        .map(|id| -> Result<_> { Ok(repo.find_commit(id)?.parent_ids().next()) })
+        .transpose()?
+        .flatten()
+        .map(|id| id.detach());
+    let branch_names = branch_names_by_commit(ctx)?;
+    let side = |side_commit_id: Option<gix::ObjectId>,
+                branch_commit_id: Option<gix::ObjectId>,
+                fallback: &str|
+     -> Result<ConflictSide> {
+        let Some(side_commit_id) = side_commit_id else {
+            return Ok(ConflictSide {
+                commit_id: None,
+                branch_name: None,
+                label: fallback.to_owned(),
+            });
+        };
+        let branch_name = branch_commit_id.and_then(|id| branch_names.get(&id).cloned());
+        let title = format!(
+            "{} {}",
+            side_commit_id.to_hex_with_len(7),
+            repo.find_commit(side_commit_id)?.message()?.summary()
+        );
+        Ok(ConflictSide {
+            commit_id: Some(side_commit_id),
+            label: match &branch_name {
+                Some(name) => format!("{name}: {title}"),
+                None => title,
+            },
+            branch_name,
+        })
+    };
+    let parent_id = commit.parents.first().copied();
+    let base = side(original_parent_id, original_parent_id, "base")?;
+    let ours = side(parent_id, parent_id, "ours")?;
+    let theirs = side(
+        Some(original_id.unwrap_or(commit_id)),
+        Some(commit_id),
+        "theirs",
+    )?;
+
+    let base_tree = commit
+        .tree_id_or_kind(TreeKind::Base)?
+        .object()?
+        .into_tree();
+    let ours_tree = commit
+        .tree_id_or_kind(TreeKind::Ours)?
+        .object()?
+        .into_tree();
+    let theirs_tree = commit
+        .tree_id_or_kind(TreeKind::Theirs)?
+        .object()?
+        .into_tree();
+    let mut paths: Vec<_> = entries
+        .ancestor_entries
+        .iter()
+        .chain(&entries.our_entries)
+        .chain(&entries.their_entries)
+        .collect();
+    paths.sort();
+    paths.dedup();
+    let files = paths
+        .into_iter()
+        .map(|path| {
+            let [base, ours, theirs] = [&base_tree, &ours_tree, &theirs_tree].map(|tree| {
+                tree.lookup_entry_by_path(path)
+                    .map(|entry| entry.map(|entry| (entry.mode(), entry.object_id())))
+            });
+            let (base, ours, theirs) = (base?, ours?, theirs?);
+            let mut file = ConflictedFile {
+                path: gix::path::into_bstr(path.as_path()).into_owned(),
+                base_id: base.map(|(_, id)| id),
+                ours_id: ours.map(|(_, id)| id),
+                theirs_id: theirs.map(|(_, id)| id),
+                is_binary: [base, ours, theirs]
+                    .iter()
+                    .flatten()
+                    .any(|(mode, _)| !mode.is_blob()),
+                hunks: Vec::new(),
+            };
+            if file.is_binary {
+                return Ok(file);
+            }
+            let [base, ours, theirs] = [file.base_id, file.ours_id, file.theirs_id].map(|id| {
+                id.map(|id| -> Result<_> { Ok(repo.find_blob(id)?.take_data()) })
+                    .transpose()
+                    .map(Option::unwrap_or_default)
+            });
+            let (base, ours, theirs) = (base?, ours?, theirs?);
+            file.is_binary = [&base, &ours, &theirs]
+                .iter()
+                .any(|data| data[..data.len().min(8000)].contains(&0));
+            if !file.is_binary {
+                file.hunks = three_way_hunks(&base, &ours, &theirs);
+            }
+            Ok(file)
+        })
+        .collect::<Result<_>>()?;
+
+    Ok(Some(ConflictDetails {
+        commit_id,
+        base,
+        ours,
+        theirs,
+        files,
+    }))
+}
+
+/// Return the names of the branches in the workspace by the ids of the commits they contain.
+fn branch_names_by_commit(ctx: &Context) -> Result<HashMap<gix::ObjectId, String>> {
+    let git2_repo = &*ctx.git2_repo.get()?;
+    let mut out = HashMap::new();
+    for stack in ctx
+        .legacy_project
+        .virtual_branches()
+        .list_stacks_in_workspace()?
+    {
+        for head in stack.heads.iter().filter(|head| !head.archived) {
+            for commit in head.commits(git2_repo, ctx, &stack)?.local_commits {
+                out.insert(commit.id().to_gix(), head.name().to_owned());
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// Split the changes `ours` and `theirs` made to `base` into hunks, where changes of both sides
+/// that overlap or touch each other end up in the same hunk.
+fn three_way_hunks(base: &[u8], ours: &[u8], theirs: &[u8]) -> Vec<ConflictHunk> {
+    let base_lines: Vec<&[u8]> = base.lines_with_terminator().collect();
+    let ours_lines: Vec<&[u8]> = ours.lines_with_terminator().collect();
+    let theirs_lines: Vec<&[u8]> = theirs.lines_with_terminator().collect();
+    let mut interner = gix::diff::blob::intern::Interner::new(
+        base_lines.len() + ours_lines.len() + theirs_lines.len(),
+    );
+    let base_tokens: Vec<_> = base_lines
+        .iter()
+        .map(|line| interner.intern(*line))
+        .collect();
+    let ours_tokens: Vec<_> = ours_lines
+        .iter()
+        .map(|line| interner.intern(*line))
+        .collect();
+    let theirs_tokens: Vec<_> = theirs_lines
+        .iter()
+        .map(|line| interner.intern(*line))
+        .collect();
+
+    let mut changes = Vec::<(Side, Range<u32>, Range<u32>)>::new();
+    for (side, tokens) in [(Side::Ours, &ours_tokens), (Side::Theirs, &theirs_tokens)] {
+        gix::diff::blob::diff_with_tokens(
+            gix::diff::blob::Algorithm::Histogram,
+            &base_tokens,
+            tokens,
+            interner.num_tokens(),
+            |before: Range<u32>, after: Range<u32>| changes.push((side, before, after)),
+        );
+    }
+    changes.sort_by_key(|(side, before, _)| (before.start, before.end, *side));
+
+    /// Return the lines in `range` of `lines`.
+    fn content(lines: &[&[u8]], range: &Range<u32>) -> BString {
+        lines[range.start as usize..range.end as usize]
+            .concat()
+            .into()
+    }
+
+    // How many lines each side has more than the base before the current hunk.
+    let (mut ours_offset, mut theirs_offset) = (0i64, 0i64);
+    let mut hunks = Vec::new();
+    let mut changes = changes.into_iter().peekable();
+    while let Some((side, before, after)) = changes.next() {
+        let mut base_range = before.clone();
+        let mut group = vec![(side, before, after)];
+        while let Some(change) = changes.next_if(|(_, before, _)| before.start <= base_range.end) {
+            base_range.end = base_range.end.max(change.1.end);
+            group.push(change);
+        }
+
+        let side_range = |side: Side, offset: &mut i64| {
+            let start = (i64::from(base_range.start) + *offset) as u32;
+            for (_, before, after) in group.iter().filter(|(s, _, _)| *s == side) {
+                *offset +=
+                    i64::from(after.end - after.start) - i64::from(before.end - before.start);
+            }
+            start..(i64::from(base_range.end) + *offset) as u32
+        };
+        let ours_range = side_range(Side::Ours, &mut ours_offset);
+        let theirs_range = side_range(Side::Theirs, &mut theirs_offset);
+        let changed_by = match (
+            group.iter().any(|(side, ..)| *side == Side::Ours),
+            group.iter().any(|(side, ..)| *side == Side::Theirs),
+        ) {
+            (true, true) => ChangedBy::Both,
+            (true, false) => ChangedBy::Ours,
+            (false, _) => ChangedBy::Theirs,
+        };
+        let (ours, theirs) = (
+            content(&ours_lines, &ours_range),
+            content(&theirs_lines, &theirs_range),
+        );
+        hunks.push(ConflictHunk {
+            base_start: base_range.start + 1,
+            base_lines: base_range.end - base_range.start,
+            ours_start: ours_range.start + 1,
+            ours_lines: ours_range.end - ours_range.start,
+            theirs_start: theirs_range.start + 1,
+            theirs_lines: theirs_range.end - theirs_range.start,
+            base: content(&base_lines, &base_range),
+            is_conflict: changed_by == ChangedBy::Both && ours != theirs,
+            ours,
+            theirs,
+            changed_by,
+        });
+    }
+    hunks
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Side {
+    Ours,
+    Theirs,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn changes_of_one_side_are_not_conflicts() {
+        let hunks = three_way_hunks(b"a\nb\nc\nd\n", b"a\nB\nc\nd\n", b"a\nb\nc\nD\nE\n");
+        assert_eq!(hunks.len(), 2);
+        assert_eq!(hunks[0].changed_by, ChangedBy::Ours);
+        assert_eq!(
+            (
+                hunks[0].base_start,
+                hunks[0].ours_start,
+                hunks[0].theirs_start
+            ),
+            (2, 2, 2)
+        );
+        assert_eq!(hunks[0].ours, "B\n");
+        assert_eq!(hunks[0].theirs, "b\n");
+        assert!(!hunks[0].is_conflict);
+
+        assert_eq!(hunks[1].changed_by, ChangedBy::Theirs);
+        assert_eq!(
+            (
+                hunks[1].base_lines,
+                hunks[1].ours_lines,
+                hunks[1].theirs_lines
+            ),
+            (1, 1, 2)
+        );
+        assert_eq!(hunks[1].theirs, "D\nE\n");
+        assert!(!hunks[1].is_conflict);
+    }
+
+    #[test]
+    fn overlapping_changes_of_both_sides_conflict() {
+        let hunks = three_way_hunks(b"a\nb\nc\nd\n", b"x\na\nB\nC\nd\n", b"a\nb\nZ\nd\n");
+        assert_eq!(hunks.len(), 2);
+        assert_eq!(hunks[0].changed_by, ChangedBy::Ours);
+        assert_eq!(hunks[0].ours, "x\n");
+
+        let conflict = &hunks[1];
+        assert_eq!(conflict.changed_by, ChangedBy::Both);
+        assert!(conflict.is_conflict);
+        assert_eq!(
+            (
+                conflict.base_start,
+                conflict.ours_start,
+                conflict.theirs_start
+            ),
+            (2, 3, 2)
+        );
+        assert_eq!(conflict.base, "b\nc\n");
+        assert_eq!(conflict.ours, "B\nC\n");
+        assert_eq!(conflict.theirs, "b\nZ\n");
+    }
+
+    #[test]
+    fn identical_changes_of_both_sides_are_not_conflicts() {
+        let hunks = three_way_hunks(b"a\nb\n", b"a\nB\n", b"a\nB\n");
+        assert_eq!(hunks.len(), 1);
+        assert_eq!(hunks[0].changed_by, ChangedBy::Both);
+        assert!(!hunks[0].is_conflict);
+    }
+}
@@ -1,5 +1,11 @@
-use but_ctx::Context;
-use gitbutler_repo::hooks::{self, HookResult};
+use anyhow::{Context as _, Result, anyhow};
+use but_core::{DiffSpec, RepositoryExt as _};
+use but_ctx::{Context, access::WorktreeWritePermission};
+use but_error::Code;
+use but_oxidize::ObjectIdExt as _;
+use but_workspace::commit_engine::CreateCommitOutcome;
+use gitbutler_repo::hooks::{self, HookResult, MessageHookResult};
+use gitbutler_stack::StackId;
 
 pub fn pre_commit_with_tree(
     ctx: &Context,
@@ -7,3 +13,90 @@ pub fn pre_commit_with_tree(
 ) -> Result<HookResult, anyhow::Error> {
     hooks::pre_commit_with_tree(ctx, tree_id)
 }
+
+/// Return `true` if the Git hooks of the project in `ctx` should run when GitButler commits,
+/// which can be turned off with `gitbutler.runHooks`.
+pub fn hooks_enabled(ctx: &Context) -> Result<bool> {
+    Ok(ctx
+        .repo
+        .get()?
+        .git_settings()?
+        .gitbutler_run_hooks
+        .unwrap_or(true))
+}
+
+/// Like [`create_commit_simple()`](but_workspace::legacy::commit_engine::create_commit_simple), but run the
+/// `pre-commit`, `commit-msg` and `post-commit` hooks around it like `git commit` would, unless
+/// [they are disabled](hooks_enabled()).
+///
+/// The commit isn't created if `pre-commit` or `commit-msg` fail, and `commit-msg` may change `message`.
+/// Failures of `post-commit` are logged, as the commit exists by then.
+pub fn create_commit_with_hooks(
+    ctx: &Context,
+    stack_id: StackId,
+    parent_id: Option<gix::ObjectId>,
+    worktree_changes: Vec<DiffSpec>,
+    message: String,
+    stack_branch_name: String,
+    perm: &mut WorktreeWritePermission,
+) -> Result<CreateCommitOutcome> {
+    let run_hooks = hooks_enabled(ctx)?;
+    let message = if run_hooks {
+        run_pre_commit_hooks(ctx, &worktree_changes, message)?
+    } else {
+        message
+    };
+
+    let outcome = but_workspace::legacy::commit_engine::create_commit_simple(
+        ctx,
+        stack_id,
+        parent_id,
+        worktree_changes,
+        message,
+        stack_branch_name,
+        perm,
+    )?;
+
+    if run_hooks
+        && outcome.new_commit.is_some()
+        && let HookResult::Failure(error_data) = hooks::post_commit(ctx)?
+    {
+        tracing::warn!(error = error_data.error, "post-commit hook failed");
+    }
+    Ok(outcome)
+}
+
+/// Run `pre-commit` on the tree that would be committed with `worktree_changes`, and `commit-msg` on `message`,
+/// returning the message to commit with.
+fn run_pre_commit_hooks(
+    ctx: &Context,
+    worktree_changes: &[DiffSpec],
+    message: String,
+) -> Result<String> {
+    let repo = ctx.repo.get()?;
+    let head = repo
+        .head_tree_id_or_empty()
+        .context("Failed to get head tree")?;
+    let mut changes = worktree_changes.iter().cloned().map(Ok).collect::<Vec<_>>();
+    let (new_tree, ..) = but_core::tree::apply_worktree_changes(
+        head.detach(),
+        &repo,
+        &mut changes,
+        ctx.settings().context_lines,
+    )?;
+    drop(repo);
+
+    if let HookResult::Failure(error_data) = hooks::pre_commit_with_tree(ctx, new_tree.to_git2())? {
+        return Err(
+            anyhow!("The pre-commit hook failed:\n{}", error_data.error).context(Code::Validation)
+        );
+    }
+    Ok(match hooks::commit_msg(ctx, message.clone())? {
+        MessageHookResult::Success | MessageHookResult::NotConfigured => message,
+        MessageHookResult::Message(data) => data.message,
+        MessageHookResult::Failure(error_data) => {
+            return Err(anyhow!("The commit-msg hook failed:\n{}", error_data.error)
+                .context(Code::Validation));
+        }
+    })
+}
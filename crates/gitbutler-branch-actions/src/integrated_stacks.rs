@@ -0,0 +1,255 @@
+//! Find stacks whose changes already made it into the target branch, so they can be cleaned up.
+
+use std::collections::{HashMap, HashSet};
+
+use anyhow::Result;
+use but_ctx::{Context, access::WorktreeWritePermission};
+use but_error::ButError;
+use but_oxidize::OidExt;
+use gitbutler_oplog::{
+    OplogExt,
+    entry::{OperationKind, SnapshotDetails, Trailer},
+};
+use gitbutler_repo::RepositoryExt as _;
+use gitbutler_stack::StackId;
+use serde::Serialize;
+
+use crate::{BranchManagerExt, VirtualBranchesExt as _, upstream_integration};
+
+/// How the changes of a stack were found in the target branch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum IntegrationReason {
+    /// The tip of the stack is reachable from the target branch, so it was merged or fast-forwarded.
+    Reachable,
+    /// A single commit of the target branch has the same changes as the whole stack.
+    SquashMerged,
+    /// The forge reports the reviews of all branches of the stack as merged at their current tips.
+    ReviewMerged,
+}
+
+/// A stack in the workspace whose changes are part of the target branch.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IntegratedStack {
+    pub stack_id: StackId,
+    /// The name of the top-most branch of the stack.
+    pub name: String,
+    pub reason: IntegrationReason,
+}
+
+/// Return all applied stacks whose changes are contained in the remote tip of the target branch, which may be ahead
+/// of the workspace base after a fetch. Stacks without commits are never considered integrated.
+///
+/// `review_map` maps branch names to their reviews, and is used to detect merges that neither preserved the commits
+/// nor their changes, like a rebase-merge that had to resolve conflicts.
+pub(crate) fn detect_integrated_stacks(
+    ctx: &Context,
+    review_map: &HashMap<String, but_forge::ForgeReview>,
+) -> Result<Vec<IntegratedStack>> {
+    let repo = ctx.repo.get()?;
+    let target = ctx.legacy_project.virtual_branches().get_default_target()?;
+    let upstream_tip = ctx
+        .git2_repo
+        .get()?
+        .maybe_find_branch_by_refname(&target.branch.clone().into())?
+        .ok_or_else(|| ButError::BranchNotFound {
+            name: target.branch.to_string(),
+        })?
+        .get()
+        .peel_to_commit()?
+        .id()
+        .to_gix();
+
+    let mut upstream = UpstreamPatchIds::new(&repo, upstream_tip);
+    let mut out = Vec::new();
+    for stack in upstream_integration::stacks(ctx, &repo)? {
+        let Some(stack_id) = stack.id else {
+            continue;
+        };
+        let details = upstream_integration::stack_details(ctx, Some(stack_id))?;
+        let Some(stack_base) = details
+            .branch_details
+            .last()
+            .map(|branch| branch.base_commit)
+        else {
+            continue;
+        };
+        if stack.tip == stack_base {
+            continue;
+        }
+
+        let merge_base = repo
+            .merge_base(stack.tip, upstream_tip)
+            .map_err(|err| {
+                but_core::shallow::into_shallow_history_error(&repo, stack.tip, err.into())
+            })?
+            .detach();
+        let reason = if merge_base == stack.tip {
+            IntegrationReason::Reachable
+        } else if upstream.contains_changes(stack_base, stack.tip, merge_base)? {
+            IntegrationReason::SquashMerged
+        } else if details
+            .branch_details
+            .iter()
+            .filter(|branch| branch.tip != branch.base_commit)
+            .all(|branch| {
+                review_map
+                    .get(&branch.name.to_string())
+                    .is_some_and(|review| review.is_merged_at_commit(&branch.tip.to_string()))
+            })
+        {
+            IntegrationReason::ReviewMerged
+        } else {
+            continue;
+        };
+        out.push(IntegratedStack {
+            stack_id,
+            name: details.derived_name,
+            reason,
+        });
+    }
+    Ok(out)
+}
+
+/// The number of commits of the target branch that are checked for squash-merges.
+const MAX_SQUASH_MERGE_CANDIDATES: usize = 500;
+
+/// Patch-ids of the commits of the target branch, computed on demand and only once, to find the changes
+/// of squash-merged branches.
+pub(crate) struct UpstreamPatchIds<'repo> {
+    repo: &'repo gix::Repository,
+    upstream_tip: gix::ObjectId,
+    by_commit: HashMap<gix::ObjectId, Option<gix::ObjectId>>,
+}
+
+impl<'repo> UpstreamPatchIds<'repo> {
+    pub(crate) fn new(repo: &'repo gix::Repository, upstream_tip: gix::ObjectId) -> Self {
+        UpstreamPatchIds {
+            repo,
+            upstream_tip,
+            by_commit: HashMap::new(),
+        }
+    }
+
+    /// Return `true` if a commit between `merge_base` and the upstream tip has the same changes as
+    /// all commits between `base` and `tip` combined.
+    pub(crate) fn contains_changes(
+        &mut self,
+        base: gix::ObjectId,
+        tip: gix::ObjectId,
+        merge_base: gix::ObjectId,
+    ) -> Result<bool> {
+        let Some(patch_id) = but_core::diff::patch_id(self.repo, Some(base), tip)? else {
+            return Ok(false);
+        };
+        for info in self
+            .repo
+            .rev_walk([self.upstream_tip])
+            .with_hidden([merge_base])
+            .all()?
+            .take(MAX_SQUASH_MERGE_CANDIDATES)
+        {
+            let info = info?;
+            // Merge commits would have to be diffed against their first parent, which is what a rebase would do anyway.
+            let mut parents = info.parent_ids();
+            let (Some(parent), None) = (parents.next(), parents.next()) else {
+                continue;
+            };
+            let upstream_patch_id = match self.by_commit.get(&info.id) {
+                Some(patch_id) => *patch_id,
+                None => {
+                    let patch_id =
+                        but_core::diff::patch_id(self.repo, Some(parent.detach()), info.id)?;
+                    self.by_commit.insert(info.id, patch_id);
+                    patch_id
+                }
+            };
+            if upstream_patch_id == Some(patch_id) {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+}
+
+/// Return the ids of all commits of the stack described by `details` whose changes landed in the target branch
+/// as part of a squash-merge, either of a single branch or of a branch along with all branches below it.
+pub(crate) fn squash_merged_commits(
+    upstream: &mut UpstreamPatchIds<'_>,
+    details: &but_workspace::ui::StackDetails,
+) -> Result<HashSet<gix::ObjectId>> {
+    let mut out = HashSet::new();
+    let (Some(top), Some(bottom)) = (
+        details.branch_details.first(),
+        details.branch_details.last(),
+    ) else {
+        return Ok(out);
+    };
+    let stack_base = bottom.base_commit;
+    let merge_base = upstream
+        .repo
+        .merge_base(top.tip, upstream.upstream_tip)
+        .map_err(|err| {
+            but_core::shallow::into_shallow_history_error(upstream.repo, top.tip, err.into())
+        })?
+        .detach();
+
+    // Branches are listed from top to bottom, but squash-merging a branch usually includes the branches below it.
+    let mut commits_up_to_branch = Vec::new();
+    for branch in details.branch_details.iter().rev() {
+        commits_up_to_branch.extend(branch.commits.iter().map(|commit| commit.id));
+        if branch.tip == branch.base_commit {
+            continue;
+        }
+        if upstream.contains_changes(branch.base_commit, branch.tip, merge_base)? {
+            out.extend(branch.commits.iter().map(|commit| commit.id));
+        } else if branch.base_commit != stack_base
+            && upstream.contains_changes(stack_base, branch.tip, merge_base)?
+        {
+            out.extend(commits_up_to_branch.iter().copied());
+        }
+    }
+    Ok(out)
+}
+
+/// Remove the integrated stack with `stack_id` from the workspace and delete the local branches of all its heads,
+/// recording the archival in the oplog.
+///
+/// Fails without changing anything if any of its branches is protected.
+pub(crate) fn archive_stack(
+    ctx: &Context,
+    stack_id: StackId,
+    perm: &mut WorktreeWritePermission,
+) -> Result<()> {
+    let vb_state = ctx.legacy_project.virtual_branches();
+    let stack = vb_state.get_stack(stack_id)?;
+    let branch_names: Vec<_> = stack.heads.iter().map(|head| head.name.clone()).collect();
+    let protected = but_core::branch::ProtectedBranches::from_repo(&*ctx.repo.get()?)?;
+    for name in &branch_names {
+        protected.ensure_unprotected(name.as_str().into())?;
+    }
+
+    ctx.create_snapshot(
+        SnapshotDetails::new(OperationKind::ArchiveBranch).with_trailers(vec![Trailer {
+            key: "name".to_string(),
+            value: stack.name(),
+        }]),
+        perm,
+    )?;
+    ctx.branch_manager().unapply(
+        stack_id,
+        perm,
+        true,
+        Vec::new(),
+        ctx.settings().feature_flags.cv3,
+    )?;
+
+    let repo = &*ctx.git2_repo.get()?;
+    for name in &branch_names {
+        if let Ok(mut branch) = repo.find_branch(name, git2::BranchType::Local) {
+            branch.delete()?;
+        }
+    }
+    Ok(())
+}
@@ -1,24 +1,22 @@
 use std::path::PathBuf;
 
 use anyhow::{Context as _, Result, anyhow};
-use bstr::ByteSlice;
 use but_core::worktree::checkout::UncommitedWorktreeChanges;
 use but_ctx::{Context, access::WorktreeWritePermission};
 use but_error::Marker;
-use but_oxidize::{ObjectIdExt, OidExt, RepoExt};
-use gitbutler_branch::{self, BranchCreateRequest, GITBUTLER_WORKSPACE_REFERENCE};
-use gitbutler_commit::commit_ext::CommitMessageBstr as _;
+use but_oxidize::{ObjectIdExt, OidExt, RepoExt, git2_signature_to_gix_signature};
+use gitbutler_branch::{self, GITBUTLER_WORKSPACE_REFERENCE};
 use gitbutler_operating_modes::OPEN_WORKSPACE_REFS;
-use gitbutler_repo::{
-    RepositoryExt, SignaturePurpose,
-    logging::{LogUntil, RepositoryExt as _},
-};
+use gitbutler_repo::SignaturePurpose;
 use gitbutler_stack::{Stack, VirtualBranchesHandle};
 use tracing::instrument;
 
-use crate::{VirtualBranchesExt, branch_manager::BranchManagerExt};
+use crate::{
+    VirtualBranchesExt,
+    adopt::{adopt_external_commits, find_external_commits},
+};
 
-const GITBUTLER_INTEGRATION_COMMIT_TITLE: &str = "GitButler Integration Commit";
+pub(crate) const GITBUTLER_INTEGRATION_COMMIT_TITLE: &str = "GitButler Integration Commit";
 pub const GITBUTLER_WORKSPACE_COMMIT_TITLE: &str = "GitButler Workspace Commit";
 
 // Before switching the user to our gitbutler workspace branch we save
@@ -49,6 +47,36 @@ fn write_workspace_file(head: &git2::Reference, path: PathBuf) -> Result<()> {
     std::fs::write(path, format!(":{sha}"))?;
     Ok(())
 }
+
+/// Fail if another worktree of `repo` has the workspace checked out.
+///
+/// All worktrees share the workspace and target references, so only one of them can be a workspace at a time.
+pub(crate) fn ensure_workspace_not_checked_out_in_other_worktree(
+    repo: &gix::Repository,
+) -> Result<()> {
+    let mut other_worktrees = Vec::new();
+    if repo.worktree().is_some_and(|wt| !wt.is_main()) {
+        other_worktrees.push(repo.main_repo()?);
+    }
+    let git_dir = gix::path::realpath(repo.git_dir())?;
+    for proxy in repo.worktrees()? {
+        if gix::path::realpath(proxy.git_dir())? != git_dir {
+            other_worktrees.push(proxy.into_repo_with_possibly_inaccessible_worktree()?);
+        }
+    }
+    for other in other_worktrees {
+        if let Some(head_name) = other.head_name()?
+            && OPEN_WORKSPACE_REFS.contains(&head_name.as_bstr().to_string().as_str())
+        {
+            return Err(anyhow!(
+                "The GitButler workspace is already checked out in the worktree at '{}', and worktrees of a repository share it",
+                other.workdir().unwrap_or(other.git_dir()).display()
+            ));
+        }
+    }
+    Ok(())
+}
+
 #[instrument(level = "debug", skip(vb_state, ctx), err(Debug))]
 pub fn update_workspace_commit(
     vb_state: &VirtualBranchesHandle,
@@ -61,6 +89,7 @@ pub fn update_workspace_commit(
 
     let repo: &git2::Repository = &*ctx.git2_repo.get()?;
     let gix_repo = repo.to_gix_repo()?;
+    ensure_workspace_not_checked_out_in_other_worktree(&gix_repo)?;
 
     // get current repo head for reference
     let head_ref = repo.head()?;
@@ -140,14 +169,19 @@ pub fn update_workspace_commit(
     let parents = workspace_head.parents().collect::<Vec<_>>();
     let workspace_tree = workspace_head.tree()?;
 
-    let final_commit = repo.commit(
-        None,
-        &author,
-        &committer,
-        &message,
-        &workspace_tree,
-        parents.iter().collect::<Vec<_>>().as_slice(),
-    )?;
+    let mut workspace_commit = gix::objs::Commit {
+        message: message.into(),
+        tree: workspace_tree.id().to_gix(),
+        author: git2_signature_to_gix_signature(&author),
+        committer: git2_signature_to_gix_signature(&committer),
+        encoding: None,
+        parents: parents.iter().map(|parent| parent.id().to_gix()).collect(),
+        extra_headers: Default::default(),
+    };
+    // Sign it if possible so it doesn't trip up repositories that enforce signatures,
+    // but don't let it fail the update as only GitButler uses this commit.
+    but_core::signing::try_sign_commit(&gix_repo, &mut workspace_commit)?;
+    let final_commit = gix_repo.write_object(&workspace_commit)?.to_git2();
 
     let checkout_res = if checkout_new_worktree && let Some(prev_head_id) = prev_head_id {
         let res = but_core::worktree::safe_checkout(
@@ -232,92 +266,11 @@ fn verify_current_branch_name(ctx: &Context) -> Result<&Context> {
 
 // TODO(ST): Probably there should not be an implicit vbranch creation here.
 fn verify_head_is_clean(ctx: &Context, perm: &mut WorktreeWritePermission) -> Result<()> {
-    let git2_repo = &*ctx.git2_repo.get()?;
-    let head_commit = git2_repo
-        .head()
-        .context("failed to get head")?
-        .peel_to_commit()
-        .context("failed to peel to commit")?;
-
-    let vb_handle = VirtualBranchesHandle::new(ctx.project_data_dir());
-    let default_target = vb_handle
-        .get_default_target()
-        .context("failed to get default target")?;
-
-    let commits = git2_repo
-        .log(
-            head_commit.id(),
-            LogUntil::Commit(default_target.sha),
-            false,
-        )
-        .context("failed to get log")?;
-
-    let workspace_index = commits
-        .iter()
-        .position(|commit| {
-            commit.message().is_some_and(|message| {
-                message.starts_with(GITBUTLER_WORKSPACE_COMMIT_TITLE)
-                    || message.starts_with(GITBUTLER_INTEGRATION_COMMIT_TITLE)
-            })
-        })
-        .context("GitButler workspace commit not found")?;
-    let workspace_commit = &commits[workspace_index];
-    let mut extra_commits = commits[..workspace_index].to_vec();
-    extra_commits.reverse();
-
-    if extra_commits.is_empty() {
+    if find_external_commits(ctx)?.is_none() {
         // no extra commits found, so we're good
         return Ok(());
     }
-
-    git2_repo
-        .reset(workspace_commit.as_object(), git2::ResetType::Soft, None)
-        .context("failed to reset to workspace commit")?;
-
-    let branch_manager = ctx.branch_manager();
-    let mut new_branch = branch_manager
-        .create_virtual_branch(
-            &BranchCreateRequest {
-                name: extra_commits
-                    .last()
-                    .map(|commit| commit.message_bstr().to_string()),
-                ..Default::default()
-            },
-            perm,
-        )
-        .context("failed to create virtual branch")?;
-
-    // rebasing the extra commits onto the new branch
-    let gix_repo = git2_repo.to_gix_repo()?;
-    let mut head = new_branch.head_oid(ctx)?.to_git2();
-    for commit in extra_commits {
-        let new_branch_head = git2_repo
-            .find_commit(head)
-            .context("failed to find new branch head")?;
-
-        let rebased_commit_oid = git2_repo
-            .commit_with_signature(
-                None,
-                &commit.author(),
-                &commit.committer(),
-                &commit.message_bstr().to_str_lossy(),
-                &commit.tree().unwrap(),
-                &[&new_branch_head],
-                None,
-            )
-            .context(format!(
-                "failed to rebase commit {} onto new branch",
-                commit.id()
-            ))?;
-
-        let rebased_commit = git2_repo.find_commit(rebased_commit_oid).context(format!(
-            "failed to find rebased commit {rebased_commit_oid}"
-        ))?;
-
-        new_branch.set_stack_head(&vb_handle, &gix_repo, rebased_commit.id())?;
-
-        head = rebased_commit.id();
-    }
+    adopt_external_commits(ctx, None, perm)?;
     Ok(())
 }
 
@@ -2,14 +2,32 @@
 mod actions;
 // This is our API
 pub use actions::{
-    amend, create_virtual_branch, create_virtual_branch_from_branch, delete_local_branch,
-    fetch_from_remotes, get_initial_integration_steps_for_branch, integrate_branch_with_steps,
-    integrate_upstream, integrate_upstream_commits, move_branch, move_commit, push_base_branch,
-    reorder_stack, resolve_upstream_integration, set_base_branch, set_target_push_remote,
-    squash_commits, tear_off_branch, unapply_stack, undo_commit, update_commit_message,
-    update_stack_order, upstream_integration_statuses,
+    adopt_external_commits, amend, apply_patch_series, archive_stack, autosquash_stack,
+    branch_divergence, cherry_pick_into_stack, conflict_details, create_virtual_branch,
+    create_virtual_branch_from_branch, delete_local_branch, detect_integrated_stacks,
+    export_patches, export_stack_bundle, external_commits, fetch_from_remotes,
+    get_initial_integration_steps_for_branch, import_stack_bundle, integrate_branch_with_steps,
+    integrate_upstream, integrate_upstream_commits, integrate_upstream_for_stack, move_branch,
+    move_commit, push_base_branch, reorder_stack, repair_workspace, resolve_rewritten,
+    resolve_upstream_integration, revert_commit, set_base_branch, set_target_push_remote,
+    squash_commits, switch_base_branch, tear_off_branch, unapply_stack, undo_commit,
+    update_commit_message, update_stack_order, upstream_integration_statuses,
 };
+mod autosquash;
+mod cherry_pick;
+pub use cherry_pick::CherryPickedCommit;
+mod revert;
+pub use revert::{RevertMode, RevertOutcome};
+mod patch_apply;
+pub use patch_apply::{AppliedPatch, PatchApplyMode, PatchSeriesOutcome};
+pub mod commit_lint;
+pub mod message_template;
+pub use message_template::MessageTemplate;
+mod adopt;
+mod repair;
 mod squash;
+pub use adopt::AdoptedCommits;
+pub use repair::RepairOutcome;
 
 mod r#virtual;
 /// Avoid using these!
@@ -24,6 +42,18 @@ pub use branch_manager::{BranchManagerExt, CreateBranchFromBranchOutcome};
 pub mod base;
 pub use base::BaseBranch;
 
+pub mod base_switch;
+
+pub mod branch_divergence;
+
+pub mod conflict_details;
+
+pub mod integrated_stacks;
+
+pub mod patch_export;
+
+pub mod stack_bundle;
+
 pub mod upstream_integration;
 
 mod integration;
@@ -0,0 +1,342 @@
+//! Commit message templates, configured per project in its local Git configuration.
+//!
+//! They are applied to the messages of new commits and when rewording commits, and can reject
+//! messages that don't validate.
+use anyhow::{Result, anyhow};
+use but_core::RepositoryExt as _;
+use but_ctx::Context;
+use but_error::Code;
+use gitbutler_stack::StackId;
+use serde::{Deserialize, Serialize};
+
+use crate::VirtualBranchesExt as _;
+
+const BRANCH_PREFIX: &str = "gitbutler.commitTemplate.branchPrefix";
+const SIGN_OFF: &str = "gitbutler.commitTemplate.signOff";
+const ISSUE_KEYS: &str = "gitbutler.commitTemplate.issueKeys";
+const ISSUE_TRAILER: &str = "gitbutler.commitTemplate.issueTrailer";
+const REQUIRE_ISSUE: &str = "gitbutler.commitTemplate.requireIssue";
+const MAX_SUBJECT_LENGTH: &str = "gitbutler.commitTemplate.maxSubjectLength";
+
+const DEFAULT_ISSUE_TRAILER: &str = "Refs";
+
+/// How to turn the message given by the user into the message of the commit.
+///
+/// The default template leaves messages untouched.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageTemplate {
+    /// If `true`, prefix the subject with the name of the branch the commit is on, like `<branch>: <subject>`.
+    pub branch_prefix: bool,
+    /// If `true`, add a `Signed-off-by` trailer with the author of the commit.
+    pub sign_off: bool,
+    /// The keys of the issues to find in the name of the stack, like `ABC` to find `abc-123` and add an
+    /// `ABC-123` trailer. If empty, no issue trailers are added.
+    pub issue_keys: Vec<String>,
+    /// The token of issue trailers, `Refs` if unset.
+    pub issue_trailer: Option<String>,
+    /// If `true`, reject messages without an issue trailer, after the issues of the stack name were added.
+    pub require_issue: bool,
+    /// If set, reject messages with subjects that have more characters than this.
+    pub max_subject_length: Option<usize>,
+}
+
+/// What the template needs to know about the commit whose message it produces.
+#[derive(Debug, Clone, Copy)]
+pub struct TemplateContext<'a> {
+    /// The short name of the branch the commit is on.
+    pub branch_name: &'a str,
+    /// The name of the stack the commit is in, which is searched for issues.
+    pub stack_name: &'a str,
+    /// The author of the commit, used for signing off.
+    pub author: &'a gix::actor::Signature,
+}
+
+/// Lifecycle
+impl MessageTemplate {
+    /// Read the template from the configuration of `repo`.
+    pub fn from_repo(repo: &gix::Repository) -> Result<Self> {
+        let config = repo.config_snapshot();
+        Ok(MessageTemplate {
+            branch_prefix: config.boolean(BRANCH_PREFIX).unwrap_or_default(),
+            sign_off: config.boolean(SIGN_OFF).unwrap_or_default(),
+            issue_keys: config
+                .string(ISSUE_KEYS)
+                .map(|keys| {
+                    keys.to_string()
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|key| !key.is_empty())
+                        .map(ToOwned::to_owned)
+                        .collect()
+                })
+                .unwrap_or_default(),
+            issue_trailer: config.string(ISSUE_TRAILER).map(|t| t.to_string()),
+            require_issue: config.boolean(REQUIRE_ISSUE).unwrap_or_default(),
+            max_subject_length: config
+                .integer(MAX_SUBJECT_LENGTH)
+                .and_then(|len| usize::try_from(len).ok())
+                .filter(|len| *len > 0),
+        })
+    }
+
+    /// Write the template to the local `.git/config` file of `repo`.
+    pub fn persist_to_local_config(&self, repo: &gix::Repository) -> Result<()> {
+        let mut config = repo.local_common_config_for_editing()?;
+        let bool_str = |value: bool| if value { "true" } else { "false" };
+        config.set_raw_value(&BRANCH_PREFIX, bool_str(self.branch_prefix))?;
+        config.set_raw_value(&SIGN_OFF, bool_str(self.sign_off))?;
+        config.set_raw_value(&ISSUE_KEYS, self.issue_keys.join(",").as_str())?;
+        config.set_raw_value(
+            &ISSUE_TRAILER,
+            self.issue_trailer
+                .as_deref()
+                .unwrap_or(DEFAULT_ISSUE_TRAILER),
+        )?;
+        config.set_raw_value(&REQUIRE_ISSUE, bool_str(self.require_issue))?;
+        config.set_raw_value(
+            &MAX_SUBJECT_LENGTH,
+            self.max_subject_length
+                .unwrap_or_default()
+                .to_string()
+                .as_str(),
+        )?;
+        repo.write_local_common_config(&config)?;
+        Ok(())
+    }
+}
+
+/// Application
+impl MessageTemplate {
+    /// Return `message` with this template applied, with the details of the commit in `ctx`.
+    ///
+    /// Applying the template to its own output changes nothing, so rewording a commit keeps it as is.
+    pub fn apply(&self, message: &str, ctx: TemplateContext<'_>) -> String {
+        let message = message.trim_end();
+        let (subject, body) = message.split_once('\n').unwrap_or((message, ""));
+        let prefix = format!("{}: ", ctx.branch_name);
+        let mut out = if self.branch_prefix && !subject.starts_with(&prefix) {
+            format!("{prefix}{subject}")
+        } else {
+            subject.to_owned()
+        };
+        if !body.is_empty() {
+            out.push('\n');
+            out.push_str(body);
+        }
+
+        let mut trailers = Vec::new();
+        let token = self.issue_trailer();
+        for issue in issues_in(ctx.stack_name, &self.issue_keys) {
+            trailers.push(format!("{token}: {issue}"));
+        }
+        if self.sign_off {
+            trailers.push(format!(
+                "Signed-off-by: {} <{}>",
+                ctx.author.name, ctx.author.email
+            ));
+        }
+        append_trailers(out, &trailers)
+    }
+
+    /// Return an error if `message` doesn't satisfy the rules of this template.
+    pub fn validate(&self, message: &str) -> Result<()> {
+        let subject = message.lines().next().unwrap_or_default();
+        if let Some(max) = self.max_subject_length {
+            let len = subject.chars().count();
+            if len > max {
+                return Err(anyhow!(
+                    "The commit subject has {len} characters, but at most {max} are allowed"
+                )
+                .context(Code::Validation));
+            }
+        }
+        if self.require_issue {
+            let prefix = format!("{}: ", self.issue_trailer());
+            if !trailers_of(message).any(|line| line.starts_with(&prefix)) {
+                return Err(anyhow!(
+                    "The commit message needs a '{}' trailer with an issue",
+                    self.issue_trailer()
+                )
+                .context(Code::Validation));
+            }
+        }
+        Ok(())
+    }
+
+    /// Return `true` if this template neither changes nor rejects messages.
+    pub fn is_noop(&self) -> bool {
+        !self.branch_prefix
+            && !self.sign_off
+            && self.issue_keys.is_empty()
+            && !self.require_issue
+            && self.max_subject_length.is_none()
+    }
+
+    fn issue_trailer(&self) -> &str {
+        self.issue_trailer
+            .as_deref()
+            .filter(|t| !t.is_empty())
+            .unwrap_or(DEFAULT_ISSUE_TRAILER)
+    }
+}
+
+/// Apply the template of the project in `ctx` to `message` of a commit on `branch_name` in the stack with `stack_id`,
+/// and validate the result.
+pub fn apply_message_template(
+    ctx: &Context,
+    stack_id: StackId,
+    branch_name: &str,
+    message: &str,
+) -> Result<String> {
+    let repo = ctx.repo.get()?;
+    let template = MessageTemplate::from_repo(&repo)?;
+    if template.is_noop() {
+        return Ok(message.to_owned());
+    }
+    let stack = ctx.legacy_project.virtual_branches().get_stack(stack_id)?;
+    let (author, _committer) = stack.repo_with_identity(&repo)?.commit_signatures()?;
+    let message = template.apply(
+        message,
+        TemplateContext {
+            branch_name,
+            stack_name: &stack.derived_name()?,
+            author: &author,
+        },
+    );
+    template.validate(&message)?;
+    Ok(message)
+}
+
+/// Find all issues with one of `keys` in `name`, like `ABC-123` for `abc-123-fix` with `ABC`.
+fn issues_in(name: &str, keys: &[String]) -> Vec<String> {
+    let mut issues = Vec::new();
+    let lower = name.to_ascii_lowercase();
+    for key in keys {
+        let needle = format!("{}-", key.to_ascii_lowercase());
+        let mut start = 0;
+        while let Some(pos) = lower[start..].find(&needle).map(|pos| start + pos) {
+            start = pos + needle.len();
+            let at_word_start = lower[..pos]
+                .chars()
+                .next_back()
+                .is_none_or(|c| !c.is_ascii_alphanumeric());
+            let digits: String = lower[start..]
+                .chars()
+                .take_while(char::is_ascii_digit)
+                .collect();
+            if !at_word_start || digits.is_empty() {
+                continue;
+            }
+            let issue = format!("{}-{digits}", key.to_ascii_uppercase());
+            if !issues.contains(&issue) {
+                issues.push(issue);
+            }
+        }
+    }
+    issues
+}
+
+/// Return the lines of the last paragraph of `message` if they are all trailers.
+fn trailers_of(message: &str) -> impl Iterator<Item = &str> {
+    let message = message.trim_end();
+    let last_paragraph = message
+        .rsplit_once("\n\n")
+        .map(|(_, last)| last)
+        .unwrap_or_default();
+    let all_trailers = !last_paragraph.is_empty() && last_paragraph.lines().all(is_trailer);
+    last_paragraph.lines().filter(move |_| all_trailers)
+}
+
+/// A trailer has the format `Token: value`, with a token without spaces.
+fn is_trailer(line: &str) -> bool {
+    line.split_once(": ").is_some_and(|(token, value)| {
+        !token.is_empty() && !token.contains(' ') && !value.is_empty()
+    })
+}
+
+/// Add all `trailers` that aren't present yet to the trailers of `message`, or as new paragraph.
+fn append_trailers(mut message: String, trailers: &[String]) -> String {
+    let existing: Vec<_> = trailers_of(&message).map(ToOwned::to_owned).collect();
+    let missing: Vec<_> = trailers
+        .iter()
+        .filter(|trailer| !existing.contains(trailer))
+        .collect();
+    if missing.is_empty() {
+        return message;
+    }
+    message.push('\n');
+    if existing.is_empty() {
+        message.push('\n');
+    }
+    for trailer in missing {
+        message.push_str(trailer);
+        message.push('\n');
+    }
+    message.truncate(message.trim_end().len());
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static AUTHOR: std::sync::LazyLock<gix::actor::Signature> =
+        std::sync::LazyLock::new(|| gix::actor::Signature {
+            name: "Jane".into(),
+            email: "jane@example.com".into(),
+            time: Default::default(),
+        });
+
+    fn ctx(stack_name: &str) -> TemplateContext<'_> {
+        TemplateContext {
+            branch_name: "feat",
+            stack_name,
+            author: &AUTHOR,
+        }
+    }
+
+    #[test]
+    fn default_template_keeps_message() {
+        let template = MessageTemplate::default();
+        assert_eq!(
+            template.apply("subject\n\nbody", ctx("abc-1")),
+            "subject\n\nbody"
+        );
+        assert!(template.validate("").is_ok());
+    }
+
+    #[test]
+    fn apply_is_idempotent() {
+        let template = MessageTemplate {
+            branch_prefix: true,
+            sign_off: true,
+            issue_keys: vec!["ABC".into()],
+            ..Default::default()
+        };
+        let once = template.apply("subject\n\nbody\n", ctx("user/abc-12-and-ABC-3"));
+        assert_eq!(
+            once,
+            "feat: subject\n\nbody\n\nRefs: ABC-12\nRefs: ABC-3\nSigned-off-by: Jane <jane@example.com>"
+        );
+        assert_eq!(template.apply(&once, ctx("user/abc-12-and-ABC-3")), once);
+    }
+
+    #[test]
+    fn issues_need_word_boundaries_and_digits() {
+        let keys = ["ABC".to_owned()];
+        assert_eq!(issues_in("xabc-1-abc-x-abc-22", &keys), ["ABC-22"]);
+    }
+
+    #[test]
+    fn validation() {
+        let template = MessageTemplate {
+            require_issue: true,
+            max_subject_length: Some(5),
+            ..Default::default()
+        };
+        assert!(template.validate("too long").is_err());
+        assert!(template.validate("ok").is_err(), "the issue is missing");
+        assert!(template.validate("ok\n\nRefs: ABC-1").is_ok());
+    }
+}
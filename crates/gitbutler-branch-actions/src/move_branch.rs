@@ -190,8 +190,7 @@ fn inject_branch_steps_into_destination(
 
     destination_stack.set_stack_head(vb_state, repo, new_destination_head.id().to_git2())?;
 
-    destination_stack
-        .set_heads_from_rebase_output(ctx, destination_rebase_result.clone().references)?;
+    destination_stack.set_heads_from_rebase_output(ctx, &destination_rebase_result)?;
     Ok(())
 }
 
@@ -226,7 +225,7 @@ fn extract_and_rebase_source_branch(
 
         source_stack.set_stack_head(vb_state, repository, new_source_head.id().to_git2())?;
 
-        source_stack.set_heads_from_rebase_output(ctx, source_rebase_result.clone().references)?;
+        source_stack.set_heads_from_rebase_output(ctx, &source_rebase_result)?;
     }
     Ok((subject_branch_steps, deleted_stacks))
 }
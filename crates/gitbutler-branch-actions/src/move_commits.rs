@@ -1,5 +1,6 @@
-use anyhow::{Context as _, Result, anyhow, bail};
+use anyhow::{Context as _, Result};
 use but_ctx::{Context, access::WorktreeWritePermission};
+use but_error::ButError;
 use but_oxidize::{ObjectIdExt, OidExt};
 use but_rebase::RebaseStep;
 use but_workspace::legacy::stack_ext::StackExt;
@@ -28,16 +29,16 @@ pub(crate) fn move_commit(
         .context("failed to read virtual branches")?;
 
     if !applied_stacks.iter().any(|b| b.id == target_stack_id) {
-        bail!("Destination branch not found");
+        return Err(stack_not_found(target_stack_id));
     }
 
     let mut source_stack = vb_state
         .try_stack(source_stack_id)?
-        .ok_or(anyhow!("Source stack not found"))?;
+        .ok_or_else(|| stack_not_found(source_stack_id))?;
 
     let destination_stack = vb_state
         .try_stack(target_stack_id)?
-        .ok_or(anyhow!("Destination branch not found"))?;
+        .ok_or_else(|| stack_not_found(target_stack_id))?;
 
     let subject_commit = repo
         .find_commit(subject_commit_oid)
@@ -77,6 +78,7 @@ fn take_commit_from_source_stack(
 ) -> Result<Option<MoveCommitIllegalAction>, anyhow::Error> {
     let merge_base = source_stack.merge_base(ctx)?;
     let gix_repo = ctx.repo.get()?;
+    let gix_repo = source_stack.repo_with_identity(&gix_repo)?;
     let steps = source_stack
         .as_rebase_steps(ctx, &gix_repo)?
         .into_iter()
@@ -94,7 +96,7 @@ fn take_commit_from_source_stack(
     let output = rebase.rebase()?;
     let new_source_head = output.top_commit.to_git2();
 
-    source_stack.set_heads_from_rebase_output(ctx, output.references)?;
+    source_stack.set_heads_from_rebase_output(ctx, &output)?;
     let vb_state = ctx.legacy_project.virtual_branches();
     source_stack.set_stack_head(&vb_state, &gix_repo, new_source_head)?;
     Ok(None)
@@ -108,6 +110,7 @@ fn move_commit_to_destination_stack(
     commit_id: git2::Oid,
 ) -> Result<(), anyhow::Error> {
     let gix_repo = ctx.repo.get()?;
+    let gix_repo = destination_stack.repo_with_identity(&gix_repo)?;
     let merge_base = destination_stack.merge_base(ctx)?;
     let mut steps = destination_stack.as_rebase_steps(ctx, &gix_repo)?;
     // TODO: In the future we can make the API provide additional info for exactly where to place the commit on the destination stack
@@ -124,7 +127,14 @@ fn move_commit_to_destination_stack(
     let output = rebase.rebase()?;
     let new_destination_head_oid = output.top_commit.to_git2();
 
-    destination_stack.set_heads_from_rebase_output(ctx, output.references)?;
+    destination_stack.set_heads_from_rebase_output(ctx, &output)?;
     destination_stack.set_stack_head(vb_state, &gix_repo, new_destination_head_oid)?;
     Ok(())
 }
+
+fn stack_not_found(stack_id: StackId) -> anyhow::Error {
+    ButError::StackNotFound {
+        stack_id: stack_id.to_string(),
+    }
+    .into()
+}
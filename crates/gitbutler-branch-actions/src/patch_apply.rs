@@ -0,0 +1,644 @@
+//! Apply patch series, as sent to mailing lists or written by `git format-patch`, to a stack.
+//! This is the counterpart of [`patch_export`](crate::patch_export).
+
+use std::path::PathBuf;
+
+use anyhow::{Context as _, Result, bail};
+use bstr::{BStr, BString, ByteSlice};
+use but_core::{RepositoryExt as _, TreeChange};
+use but_ctx::{Context, access::WorktreeWritePermission};
+use but_oxidize::{ObjectIdExt as _, OidExt as _};
+use gitbutler_cherry_pick::GixRepositoryExt as _;
+use gitbutler_repo::RepositoryExt as _;
+use gitbutler_stack::StackId;
+use gix::objs::tree::EntryKind;
+use serde::{Deserialize, Serialize};
+use tracing::instrument;
+
+use crate::VirtualBranchesExt as _;
+
+/// Where the changes of applied patches should go.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PatchApplyMode {
+    /// Create a commit for each patch on top of the stack, with the author and date of the patch.
+    #[default]
+    Commit,
+    /// Apply the changes of all patches to the worktree, where they remain uncommitted so they can be reviewed first.
+    Worktree,
+}
+
+/// A patch that was applied to a stack.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppliedPatch {
+    /// The subject of the patch, without prefixes like `[PATCH 1/2]`.
+    pub subject: String,
+    /// The commit created for the patch if it was applied with [`PatchApplyMode::Commit`].
+    #[serde(with = "but_serde::object_id_opt")]
+    pub new_commit: Option<gix::ObjectId>,
+}
+
+/// The result of [applying](crate::apply_patch_series()) a patch series.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PatchSeriesOutcome {
+    /// All applied patches, in the order they were applied.
+    pub patches: Vec<AppliedPatch>,
+    /// The changes made to the worktree if the series was applied with [`PatchApplyMode::Worktree`],
+    /// as seen from the worktree before the patches were applied.
+    #[serde(skip)]
+    pub worktree_changes: Vec<TreeChange>,
+}
+
+/// Apply the patches in `files`, in order, to the stack with `stack_id` according to `mode`.
+///
+/// Each file is either an mbox with one or more messages, as written by `git format-patch`, or a plain diff.
+/// Messages without changes, like cover letters, are skipped. Plain diffs are committed with the configured author.
+/// Nothing is changed if any of the patches doesn't apply.
+#[instrument(level = "debug", skip(ctx, perm))]
+pub(crate) fn apply_patch_series(
+    ctx: &Context,
+    stack_id: StackId,
+    files: Vec<PathBuf>,
+    mode: PatchApplyMode,
+    perm: &mut WorktreeWritePermission,
+) -> Result<PatchSeriesOutcome> {
+    let mut patches = Vec::new();
+    for file in &files {
+        let data = std::fs::read(file)
+            .with_context(|| format!("Could not read patch file at '{}'", file.display()))?;
+        let file_name = file
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        patches.extend(parse_patches(&data, &file_name)?);
+    }
+    patches.retain(|patch| !patch.files.is_empty());
+    if patches.is_empty() {
+        bail!("No patches were found in the given files");
+    }
+
+    let vb_state = ctx.legacy_project.virtual_branches();
+    let stack = vb_state.get_stack_in_workspace(stack_id)?;
+    let repo = ctx.repo.get()?;
+    let repo = stack.repo_with_identity(&repo)?;
+    match mode {
+        PatchApplyMode::Commit => {
+            let (default_author, committer) = repo.commit_signatures()?;
+            let mut parent = stack.head_oid(ctx)?;
+            let mut tree_id = repo.find_real_tree(&parent, Default::default())?.detach();
+            let mut to_pick = Vec::with_capacity(patches.len());
+            for patch in &patches {
+                tree_id = apply_to_tree(&repo, tree_id, &patch.files)
+                    .with_context(|| format!("Patch '{}' doesn't apply", patch.subject))?;
+                let commit = gix::objs::Commit {
+                    tree: tree_id,
+                    parents: vec![parent].into(),
+                    author: patch
+                        .author
+                        .clone()
+                        .unwrap_or_else(|| default_author.clone()),
+                    committer: committer.clone(),
+                    encoding: None,
+                    message: patch.message.clone(),
+                    extra_headers: Vec::new(),
+                };
+                parent = repo.write_object(&commit)?.detach();
+                to_pick.push(parent);
+            }
+            let new_ids = crate::cherry_pick::pick_onto_stack(ctx, stack_id, &to_pick, perm)?;
+            Ok(PatchSeriesOutcome {
+                patches: patches
+                    .into_iter()
+                    .zip(new_ids)
+                    .map(|(patch, new_id)| AppliedPatch {
+                        subject: patch.subject,
+                        new_commit: Some(new_id),
+                    })
+                    .collect(),
+                worktree_changes: Vec::new(),
+            })
+        }
+        PatchApplyMode::Worktree => {
+            let worktree_changes = apply_to_worktree(ctx, &repo, &patches)?;
+            Ok(PatchSeriesOutcome {
+                patches: patches
+                    .into_iter()
+                    .map(|patch| AppliedPatch {
+                        subject: patch.subject,
+                        new_commit: None,
+                    })
+                    .collect(),
+                worktree_changes,
+            })
+        }
+    }
+}
+
+/// Apply the changes in `diff`, in the format of `git diff`, to the worktree and return them
+/// as seen from the worktree before they were applied.
+pub(crate) fn apply_diff_to_worktree(ctx: &Context, diff: &[u8]) -> Result<Vec<TreeChange>> {
+    let patch = Patch {
+        author: None,
+        subject: "uncommitted changes".into(),
+        message: BString::default(),
+        files: parse_diff(diff)?,
+    };
+    let repo = ctx.repo.get()?;
+    apply_to_worktree(ctx, &repo, &[patch])
+}
+
+fn apply_to_worktree(
+    ctx: &Context,
+    repo: &gix::Repository,
+    patches: &[Patch],
+) -> Result<Vec<TreeChange>> {
+    let git2_repo = ctx.git2_repo.get()?;
+    let worktree_tree_id = git2_repo.create_wd_tree(0)?.id().to_gix();
+    let mut new_tree_id = worktree_tree_id;
+    for patch in patches {
+        new_tree_id = apply_to_tree(repo, new_tree_id, &patch.files)
+            .with_context(|| format!("Patch '{}' doesn't apply", patch.subject))?;
+    }
+    let worktree_changes = but_core::diff::tree_changes(repo, Some(worktree_tree_id), new_tree_id)?;
+
+    let new_tree = git2_repo.find_tree(new_tree_id.to_git2())?;
+    git2_repo
+        .checkout_tree_builder(&new_tree)
+        .only_changes_from(&git2_repo.find_tree(worktree_tree_id.to_git2())?)?
+        .force()
+        .checkout()
+        .context("failed to checkout tree")?;
+    Ok(worktree_changes)
+}
+
+/// A single patch, i.e. the changes of one commit.
+#[derive(Debug)]
+struct Patch {
+    /// The author and date of the mail the patch was sent with, if it was sent as mail.
+    author: Option<gix::actor::Signature>,
+    subject: String,
+    /// The message of the commit to create, consisting of the subject and the body of the mail.
+    message: BString,
+    files: Vec<FilePatch>,
+}
+
+/// The changes to a single file.
+#[derive(Debug, Default)]
+struct FilePatch {
+    /// The path of the file before the change, or `None` if it was added.
+    old_path: Option<BString>,
+    /// The path of the file after the change, or `None` if it was deleted.
+    new_path: Option<BString>,
+    /// The kind of the file after the change, if it's known from the patch.
+    new_kind: Option<EntryKind>,
+    hunks: Vec<Hunk>,
+    is_binary: bool,
+}
+
+#[derive(Debug)]
+struct Hunk {
+    /// The 1-based line number of the first line of `old_lines`, or the line after which `new_lines` are inserted
+    /// if there are no `old_lines`.
+    old_start: usize,
+    /// The context and removed lines, including their line terminator unless it's the last line of the file.
+    old_lines: Vec<BString>,
+    /// The context and added lines, including their line terminator unless it's the last line of the file.
+    new_lines: Vec<BString>,
+}
+
+/// Parse `data` as mbox with one or more messages if it looks like one, or as a plain diff otherwise,
+/// which is named after `file_name`.
+fn parse_patches(data: &[u8], file_name: &str) -> Result<Vec<Patch>> {
+    if !(data.starts_with(b"From ") || data.starts_with(b"From:")) {
+        let subject = format!("Apply {file_name}");
+        return Ok(vec![Patch {
+            author: None,
+            message: format!("{subject}\n").into(),
+            subject,
+            files: parse_diff(data)?,
+        }]);
+    }
+
+    let mut messages = Vec::new();
+    let mut start = 0;
+    let mut previous_line_is_empty = true;
+    let mut pos = 0;
+    for line in data.lines_with_terminator() {
+        // Like in any mbox, messages start with a `From ` line right after an empty line.
+        if pos != 0 && previous_line_is_empty && line.starts_with(b"From ") {
+            messages.push(&data[start..pos]);
+            start = pos;
+        }
+        previous_line_is_empty = line.trim_end_with(|c| c == '\r' || c == '\n').is_empty();
+        pos += line.len();
+    }
+    messages.push(&data[start..]);
+    messages
+        .into_iter()
+        .map(parse_message)
+        .collect::<Result<Vec<_>>>()
+}
+
+fn parse_message(message: &[u8]) -> Result<Patch> {
+    let mut lines = message.lines_with_terminator().peekable();
+    if lines.peek().is_some_and(|line| line.starts_with(b"From ")) {
+        lines.next();
+    }
+
+    let mut headers: Vec<(BString, BString)> = Vec::new();
+    for line in lines.by_ref() {
+        let line = line.trim_end_with(|c| c == '\r' || c == '\n');
+        if line.is_empty() {
+            break;
+        }
+        if line.starts_with(b" ") || line.starts_with(b"\t") {
+            // A folded header continues the previous one.
+            if let Some((_, value)) = headers.last_mut() {
+                value.push(b' ');
+                value.extend_from_slice(line.trim());
+            }
+        } else if let Some((key, value)) = line.split_once_str(":") {
+            headers.push((key.trim().to_ascii_lowercase().into(), value.trim().into()));
+        }
+    }
+    let header = |name: &str| {
+        headers
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_bstr())
+    };
+
+    let subject = strip_subject_prefixes(header("subject").unwrap_or_default());
+    let author = header("from")
+        .map(|from| -> Result<_> {
+            let (name, email) = match from.rfind_byte(b'<') {
+                Some(pos) => (
+                    from[..pos].trim().trim_with(|c| c == '"'),
+                    from[pos + 1..].trim_end_with(|c| c == '>'),
+                ),
+                None => (from.as_bytes(), from.as_bytes()),
+            };
+            let time = match header("date") {
+                Some(date) => gix::date::parse(
+                    date.to_str().context("Date header isn't valid UTF-8")?,
+                    Some(std::time::SystemTime::now()),
+                )?,
+                None => gix::date::Time::now_local_or_utc(),
+            };
+            Ok(gix::actor::Signature {
+                name: name.into(),
+                email: email.into(),
+                time,
+            })
+        })
+        .transpose()?;
+
+    // The body of the mail ends where the patch starts, which usually is after a line with `---`.
+    let rest: Vec<&[u8]> = lines.collect();
+    let body_end = rest
+        .iter()
+        .position(|line| {
+            line.trim_end() == b"---"
+                || line.starts_with(b"diff --git ")
+                || line.starts_with(b"--- ")
+        })
+        .unwrap_or(rest.len());
+    let body = rest[..body_end].concat();
+    let diff = rest[body_end..].concat();
+
+    let mut message = BString::from(subject.as_str());
+    let body = body.trim();
+    if !body.is_empty() {
+        message.extend_from_slice(b"\n\n");
+        message.extend_from_slice(body);
+    }
+    message.push(b'\n');
+    Ok(Patch {
+        author,
+        subject,
+        message,
+        files: parse_diff(&diff)?,
+    })
+}
+
+/// Remove prefixes like `[PATCH v2 1/3]` and `Re:` from `subject`, like `git mailinfo` does.
+fn strip_subject_prefixes(subject: &BStr) -> String {
+    let mut subject = subject.trim();
+    loop {
+        if subject.starts_with(b"[") {
+            match subject.find_byte(b']') {
+                Some(pos) => subject = subject[pos + 1..].trim_start(),
+                None => break,
+            }
+        } else if subject.len() >= 3 && subject[..3].eq_ignore_ascii_case(b"re:") {
+            subject = subject[3..].trim_start();
+        } else {
+            break;
+        }
+    }
+    subject.to_str_lossy().into_owned()
+}
+
+/// Parse all file changes of the diff in `data`, ignoring everything that isn't part of it.
+fn parse_diff(data: &[u8]) -> Result<Vec<FilePatch>> {
+    let mut files: Vec<FilePatch> = Vec::new();
+    let mut lines = data.lines_with_terminator().peekable();
+    while let Some(line) = lines.next() {
+        let content = line.trim_end_with(|c| c == '\r' || c == '\n');
+        if let Some(paths) = content.strip_prefix(b"diff --git ") {
+            let path = path_from_git_header(paths);
+            files.push(FilePatch {
+                old_path: path.clone(),
+                new_path: path,
+                ..Default::default()
+            });
+        } else if content.starts_with(b"--- ")
+            && lines.peek().is_some_and(|next| next.starts_with(b"+++ "))
+        {
+            // Plain diffs start with these lines, while in Git diffs they follow the `diff --git` line.
+            if files
+                .last()
+                .is_none_or(|file| !file.hunks.is_empty() || file.is_binary)
+            {
+                files.push(FilePatch::default());
+            }
+            let new_line = lines.next().unwrap_or_default();
+            let file = files.last_mut().expect("just made sure there is one");
+            file.old_path = path_from_file_line(&content[4..]);
+            file.new_path =
+                path_from_file_line(&new_line.trim_end_with(|c| c == '\r' || c == '\n')[4..]);
+        } else if let Some(header) = content.strip_prefix(b"@@ -") {
+            let file = files
+                .last_mut()
+                .context("Found a hunk that doesn't belong to a file")?;
+            file.hunks.push(parse_hunk(header, &mut lines)?);
+        } else if let Some(file) = files.last_mut() {
+            if let Some(mode) = content.strip_prefix(b"new file mode ") {
+                file.old_path = None;
+                file.new_kind = Some(entry_kind_from_mode(mode)?);
+            } else if content.starts_with(b"deleted file mode ") {
+                file.new_path = None;
+            } else if let Some(mode) = content.strip_prefix(b"new mode ") {
+                file.new_kind = Some(entry_kind_from_mode(mode)?);
+            } else if let Some(path) = content.strip_prefix(b"rename from ") {
+                file.old_path = Some(path.into());
+            } else if let Some(path) = content.strip_prefix(b"rename to ") {
+                file.new_path = Some(path.into());
+            } else if content.starts_with(b"copy from ") {
+                bail!("Copies aren't supported in patches");
+            } else if content.starts_with(b"GIT binary patch")
+                || (content.starts_with(b"Binary files ") && content.ends_with(b" differ"))
+            {
+                file.is_binary = true;
+            }
+        }
+    }
+    Ok(files)
+}
+
+/// Get the path out of `a/path b/path`, which is only possible if both are the same as they might contain spaces.
+fn path_from_git_header(paths: &[u8]) -> Option<BString> {
+    let (old, new) = paths.split_at(paths.len() / 2);
+    let old = old.strip_prefix(b"a/")?;
+    let new = new.strip_prefix(b" b/")?;
+    (old == new).then(|| old.into())
+}
+
+/// Get the path out of the `---` or `+++` line of a diff, or `None` if it's `/dev/null`.
+fn path_from_file_line(line: &[u8]) -> Option<BString> {
+    // Plain diffs may have a timestamp after the path, separated by a tab.
+    let path = line.split_str("\t").next().unwrap_or_default();
+    if path == b"/dev/null" {
+        return None;
+    }
+    let path = path
+        .strip_prefix(b"a/")
+        .or_else(|| path.strip_prefix(b"b/"))
+        .unwrap_or(path);
+    Some(path.into())
+}
+
+fn entry_kind_from_mode(mode: &[u8]) -> Result<EntryKind> {
+    Ok(match mode.trim() {
+        b"100644" | b"100664" => EntryKind::Blob,
+        b"100755" => EntryKind::BlobExecutable,
+        b"120000" => EntryKind::Link,
+        _ => bail!("Unsupported file mode in patch: {}", mode.as_bstr()),
+    })
+}
+
+/// Parse the hunk whose `header` follows `@@ -`, consuming as many of `lines` as the header says it has.
+fn parse_hunk<'a>(
+    header: &[u8],
+    lines: &mut std::iter::Peekable<impl Iterator<Item = &'a [u8]>>,
+) -> Result<Hunk> {
+    let parse_range = |range: &[u8]| -> Option<(usize, usize)> {
+        let (start, len) = match range.split_once_str(",") {
+            Some((start, len)) => (start, len.to_str().ok()?.parse().ok()?),
+            None => (range, 1),
+        };
+        Some((start.to_str().ok()?.parse().ok()?, len))
+    };
+    let mut ranges = header.splitn_str(3, " ");
+    let (old_start, mut old_len) = ranges
+        .next()
+        .and_then(parse_range)
+        .with_context(|| format!("Invalid hunk header: @@ -{}", header.as_bstr()))?;
+    let (_new_start, mut new_len) = ranges
+        .next()
+        .and_then(|range| range.strip_prefix(b"+"))
+        .and_then(parse_range)
+        .with_context(|| format!("Invalid hunk header: @@ -{}", header.as_bstr()))?;
+
+    let mut hunk = Hunk {
+        old_start,
+        old_lines: Vec::with_capacity(old_len),
+        new_lines: Vec::with_capacity(new_len),
+    };
+    let mut previous_sign = None;
+    while old_len > 0 || new_len > 0 || lines.peek().is_some_and(|line| line.starts_with(b"\\")) {
+        let line = lines
+            .next()
+            .context("The patch ends in the middle of a hunk")?;
+        let (sign, content) = match line {
+            // Some tools strip the space of empty context lines.
+            b"\n" | b"\r\n" => (b' ', line),
+            _ => match line.split_first() {
+                Some((sign, content)) => (*sign, content),
+                None => bail!("The patch ends in the middle of a hunk"),
+            },
+        };
+        match sign {
+            b' ' if old_len > 0 && new_len > 0 => {
+                hunk.old_lines.push(content.into());
+                hunk.new_lines.push(content.into());
+                old_len -= 1;
+                new_len -= 1;
+            }
+            b'-' if old_len > 0 => {
+                hunk.old_lines.push(content.into());
+                old_len -= 1;
+            }
+            b'+' if new_len > 0 => {
+                hunk.new_lines.push(content.into());
+                new_len -= 1;
+            }
+            // `\ No newline at end of file` applies to the previous line.
+            b'\\' => {
+                let strip = |lines: &mut Vec<BString>| {
+                    if let Some(last) = lines.last_mut() {
+                        if last.ends_with(b"\n") {
+                            last.pop();
+                        }
+                    }
+                };
+                match previous_sign {
+                    Some(b' ') => {
+                        strip(&mut hunk.old_lines);
+                        strip(&mut hunk.new_lines);
+                    }
+                    Some(b'-') => strip(&mut hunk.old_lines),
+                    Some(b'+') => strip(&mut hunk.new_lines),
+                    _ => {}
+                }
+                continue;
+            }
+            _ => bail!("Unexpected line in hunk: {}", line.as_bstr()),
+        }
+        previous_sign = Some(sign);
+    }
+    Ok(hunk)
+}
+
+/// Apply the changes of `files` to the tree with `tree_id` and return the id of the new tree.
+fn apply_to_tree(
+    repo: &gix::Repository,
+    tree_id: gix::ObjectId,
+    files: &[FilePatch],
+) -> Result<gix::ObjectId> {
+    let tree = repo.find_tree(tree_id)?;
+    let mut editor = repo.edit_tree(tree_id)?;
+    for file in files {
+        let Some(path) = file.new_path.as_ref().or(file.old_path.as_ref()) else {
+            bail!("A patch changes a file without path");
+        };
+        if file.is_binary {
+            bail!("'{path}' has a binary patch, which can't be applied");
+        }
+        let old = match &file.old_path {
+            Some(old_path) => {
+                let entry = tree
+                    .lookup_entry(old_path.split_str("/"))?
+                    .with_context(|| format!("'{old_path}' doesn't exist"))?;
+                Some((entry.mode().kind(), entry.object()?.detach().data))
+            }
+            None => None,
+        };
+        if matches!(old, Some((EntryKind::Commit | EntryKind::Tree, _))) {
+            bail!("'{path}' isn't a file, and only changes to files can be applied");
+        }
+
+        let new_data = apply_hunks(
+            old.as_ref().map_or(&[][..], |(_, data)| data.as_slice()),
+            &file.hunks,
+        )
+        .with_context(|| format!("The changes to '{path}' don't apply"))?;
+        if let Some(old_path) = &file.old_path {
+            if file.new_path.as_ref() != Some(old_path) {
+                editor.remove(old_path.as_bstr())?;
+            }
+        }
+        if let Some(new_path) = &file.new_path {
+            if file.old_path.is_none() && tree.lookup_entry(new_path.split_str("/"))?.is_some() {
+                bail!("'{new_path}' is added by the patch, but already exists");
+            }
+            let kind = file
+                .new_kind
+                .or(old.map(|(kind, _)| kind))
+                .unwrap_or(EntryKind::Blob);
+            editor.upsert(
+                new_path.as_bstr(),
+                kind,
+                repo.write_blob(&new_data)?.detach(),
+            )?;
+        }
+    }
+    Ok(editor.write()?.detach())
+}
+
+/// Apply `hunks` to `old`, searching for the lines they change close to where they are supposed to be,
+/// in case lines were added or removed above them.
+fn apply_hunks(old: &[u8], hunks: &[Hunk]) -> Result<Vec<u8>> {
+    let old_lines: Vec<&[u8]> = old.lines_with_terminator().collect();
+    let mut out = Vec::with_capacity(old.len());
+    let mut applied_up_to = 0;
+    let mut offset = 0isize;
+    for hunk in hunks {
+        let expected = if hunk.old_lines.is_empty() {
+            hunk.old_start
+        } else {
+            hunk.old_start.saturating_sub(1)
+        };
+        let expected = expected.saturating_add_signed(offset).max(applied_up_to);
+        let pos = find_lines(&old_lines, &hunk.old_lines, expected, applied_up_to)
+            .with_context(|| format!("The hunk at line {} doesn't match", hunk.old_start))?;
+        out.extend(old_lines[applied_up_to..pos].concat());
+        for line in &hunk.new_lines {
+            out.extend_from_slice(line);
+        }
+        applied_up_to = pos + hunk.old_lines.len();
+        offset += pos as isize - expected as isize;
+    }
+    out.extend(old_lines[applied_up_to..].concat());
+    Ok(out)
+}
+
+/// Find the position of `needle` in `lines` that is closest to `expected`, but not before `min`.
+fn find_lines(lines: &[&[u8]], needle: &[BString], expected: usize, min: usize) -> Option<usize> {
+    let matches_at = |pos: usize| {
+        lines
+            .get(pos..pos + needle.len())
+            .is_some_and(|window| window.iter().zip(needle).all(|(a, b)| *a == b.as_slice()))
+    };
+    (0..=lines.len().max(expected))
+        .flat_map(|distance| {
+            [
+                expected.checked_add(distance),
+                expected.checked_sub(distance).filter(|_| distance > 0),
+            ]
+        })
+        .flatten()
+        .filter(|pos| *pos >= min)
+        .find(|pos| matches_at(*pos))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{apply_hunks, parse_diff, strip_subject_prefixes};
+
+    #[test]
+    fn subject_prefixes_are_stripped() {
+        assert_eq!(
+            strip_subject_prefixes("[PATCH v2 1/3] [net] Re: fix it".into()),
+            "fix it"
+        );
+        assert_eq!(strip_subject_prefixes("fix [it]".into()), "fix [it]");
+    }
+
+    #[test]
+    fn hunks_apply_with_offset_and_missing_newline() {
+        let files = parse_diff(
+            b"--- a/file\n+++ b/file\n@@ -2,2 +2,2 @@\n b\n-c\n\\ No newline at end of file\n+C\n\\ No newline at end of file\n",
+        )
+        .unwrap();
+        assert_eq!(files.len(), 1);
+        // A line was added above, so the hunk applies one line further down.
+        assert_eq!(
+            apply_hunks(b"new\na\nb\nc", &files[0].hunks).unwrap(),
+            b"new\na\nb\nC"
+        );
+        assert!(apply_hunks(b"a\nb\nd", &files[0].hunks).is_err());
+    }
+}
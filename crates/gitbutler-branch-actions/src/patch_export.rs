@@ -0,0 +1,334 @@
+//! Export the branches of a stack as patch series, like `git format-patch --cover-letter` would,
+//! for projects that review changes on mailing lists.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context as _, Result, bail};
+use bstr::{BString, ByteSlice, ByteVec};
+use but_core::{HunkHeader, TreeChange, TreeStatus, UnifiedPatch};
+use but_ctx::Context;
+use gitbutler_stack::StackId;
+use gix::prelude::ObjectIdExt as _;
+use serde::Serialize;
+
+use crate::upstream_integration;
+
+/// The patch files written for a single branch of a stack.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PatchSeries {
+    /// The name of the branch, like `feature`.
+    pub branch_name: String,
+    /// The cover letter of the series, `0000-cover-letter.patch`.
+    pub cover_letter: PathBuf,
+    /// One file per commit of the branch, in the order they have to be applied.
+    pub patches: Vec<PathBuf>,
+}
+
+/// The date Git uses in the `From` line of each message, which marks it as generated by `git format-patch`.
+const MBOX_FROM_DATE: &str = "Mon Sep 17 00:00:00 2001";
+
+/// The amount of characters of a commit subject used in the name of its patch file, like Git does.
+const MAX_FILE_NAME_SUBJECT_LEN: usize = 52;
+
+/// Write the commits of each branch of the stack with `stack_id` as a series of mbox patch files into a directory
+/// named after the branch within `out_dir`, starting with the bottom-most branch.
+///
+/// Each series has its own cover letter, whose subject and text are taken from the `branch.<name>.description`
+/// configuration of the branch as `git branch --edit-description` sets it. Series of branches without
+/// commits aren't written. As the patches of a branch only contain its own commits, a series has to be applied
+/// on top of the series of the branches below it.
+pub(crate) fn export_patches(
+    ctx: &Context,
+    stack_id: StackId,
+    out_dir: &Path,
+) -> Result<Vec<PatchSeries>> {
+    let repo = ctx.repo.get()?;
+    let details = upstream_integration::stack_details(ctx, Some(stack_id))?;
+
+    let mut out = Vec::new();
+    for branch in details.branch_details.iter().rev() {
+        if branch.commits.is_empty() {
+            continue;
+        }
+        let branch_name = branch.name.to_str_lossy().into_owned();
+        let series_dir = out_dir.join(branch_name.replace('/', "-"));
+        std::fs::create_dir_all(&series_dir).with_context(|| {
+            format!(
+                "Could not create directory for patches at '{}'",
+                series_dir.display()
+            )
+        })?;
+
+        let total = branch.commits.len();
+        let mut patches = Vec::with_capacity(total);
+        let mut commits = Vec::with_capacity(total);
+        for (idx, commit) in branch.commits.iter().rev().enumerate() {
+            let commit = but_core::Commit::from_id(commit.id.attach(&repo))?;
+            if commit.is_conflicted() {
+                bail!(
+                    "Commit {} of branch '{branch_name}' is conflicted and can't be exported as patch",
+                    commit.id
+                );
+            }
+            let message = gix::objs::commit::MessageRef::from_bytes(&commit.inner.message);
+            let subject = message.summary().to_str_lossy().into_owned();
+            let number = idx + 1;
+            let path = series_dir.join(format!(
+                "{number:04}-{}.patch",
+                file_name_from_subject(&subject)
+            ));
+            std::fs::write(
+                &path,
+                format_patch(&repo, &commit, &subject, message.body(), number, total)?,
+            )?;
+            patches.push(path);
+            commits.push((commit.inner.author.clone(), subject));
+        }
+
+        let cover_letter = series_dir.join("0000-cover-letter.patch");
+        let description = repo
+            .config_snapshot()
+            .string(format!("branch.{branch_name}.description").as_str())
+            .map(|description| description.into_owned());
+        std::fs::write(
+            &cover_letter,
+            format_cover_letter(&branch_name, description, &commits),
+        )?;
+        out.push(PatchSeries {
+            branch_name,
+            cover_letter,
+            patches,
+        });
+    }
+    Ok(out)
+}
+
+/// Turn `subject` into something usable as file name, keeping only alphanumerics, `.` and `_`.
+fn file_name_from_subject(subject: &str) -> String {
+    let mut out = String::with_capacity(subject.len());
+    for c in subject.chars() {
+        if c.is_ascii_alphanumeric() || c == '.' || c == '_' {
+            out.push(c);
+        } else if !out.ends_with('-') {
+            out.push('-');
+        }
+        if out.len() >= MAX_FILE_NAME_SUBJECT_LEN {
+            break;
+        }
+    }
+    out.trim_matches(['-', '.']).to_owned()
+}
+
+/// Write the header of a message, with `number` counting from `1`, or `0` for the cover letter.
+fn write_header(
+    out: &mut BString,
+    id: gix::ObjectId,
+    author: &gix::actor::Signature,
+    subject: &str,
+    number: usize,
+    total: usize,
+) {
+    out.push_str(format!("From {id} {MBOX_FROM_DATE}\n"));
+    out.push_str("From: ");
+    out.push_str(&author.name);
+    out.push_str(" <");
+    out.push_str(&author.email);
+    out.push_str(">\n");
+    out.push_str(format!(
+        "Date: {}\n",
+        author.time.format_or_unix(gix::date::time::format::RFC2822)
+    ));
+    if total == 1 && number == 1 {
+        out.push_str(format!("Subject: [PATCH] {subject}\n\n"));
+    } else {
+        out.push_str(format!("Subject: [PATCH {number}/{total}] {subject}\n\n"));
+    }
+}
+
+fn format_patch(
+    repo: &gix::Repository,
+    commit: &but_core::Commit<'_>,
+    subject: &str,
+    body: Option<&bstr::BStr>,
+    number: usize,
+    total: usize,
+) -> Result<BString> {
+    let mut out = BString::default();
+    write_header(
+        &mut out,
+        commit.id.detach(),
+        &commit.inner.author,
+        subject,
+        number,
+        total,
+    );
+    if let Some(body) = body.map(|body| body.trim()).filter(|body| !body.is_empty()) {
+        out.push_str(body);
+        out.push(b'\n');
+    }
+    out.push_str("---\n");
+
+    let parent = commit.inner.parents.first().copied();
+    let mut changes = but_core::diff::tree_changes(repo, parent, commit.id.detach())?;
+    changes.sort_by(|a, b| a.path.cmp(&b.path));
+    for change in &changes {
+        write_diff(repo, change, 3, None, &mut out)?;
+    }
+    out.push_str("-- \nGitButler\n\n");
+    Ok(out)
+}
+
+/// Write `change` with `context_lines` in the format of `git diff`, so it can be applied with `git am`.
+///
+/// If `selected` is set, only the hunks with these headers are written, and nothing at all if there is none of them.
+pub(crate) fn write_diff(
+    repo: &gix::Repository,
+    change: &TreeChange,
+    context_lines: u32,
+    selected: Option<&[HunkHeader]>,
+    out: &mut BString,
+) -> Result<()> {
+    let mut patch = change.unified_patch(repo, context_lines)?;
+    if let Some(selected) = selected {
+        let Some(UnifiedPatch::Patch { hunks, .. }) = &mut patch else {
+            return Ok(());
+        };
+        hunks.retain(|hunk| selected.contains(&HunkHeader::from(hunk)));
+        if hunks.is_empty() {
+            return Ok(());
+        }
+    }
+
+    let previous_path = change.previous_path().unwrap_or(change.path.as_bstr());
+    out.push_str("diff --git a/");
+    out.push_str(previous_path);
+    out.push_str(" b/");
+    out.push_str(&change.path);
+    out.push(b'\n');
+    match &change.status {
+        TreeStatus::Addition { state, .. } => {
+            out.push_str(format!("new file mode {}\n", state.kind.as_octal_str()));
+        }
+        TreeStatus::Deletion { previous_state } => {
+            out.push_str(format!(
+                "deleted file mode {}\n",
+                previous_state.kind.as_octal_str()
+            ));
+        }
+        TreeStatus::Modification {
+            previous_state,
+            state,
+            ..
+        }
+        | TreeStatus::Rename {
+            previous_state,
+            state,
+            ..
+        } => {
+            if previous_state.kind != state.kind {
+                out.push_str(format!(
+                    "old mode {}\nnew mode {}\n",
+                    previous_state.kind.as_octal_str(),
+                    state.kind.as_octal_str()
+                ));
+            }
+            if matches!(change.status, TreeStatus::Rename { .. }) {
+                out.push_str("rename from ");
+                out.push_str(previous_path);
+                out.push_str("\nrename to ");
+                out.push_str(&change.path);
+                out.push(b'\n');
+            }
+        }
+    }
+
+    let (previous_state, state) = (
+        change
+            .status
+            .previous_state_and_path()
+            .map(|(state, _)| state),
+        change.status.state(),
+    );
+    let old_path = previous_state.map(|_| format!("a/{previous_path}"));
+    let new_path = state.map(|_| format!("b/{}", change.path));
+    match patch {
+        Some(UnifiedPatch::Patch { hunks, .. }) if !hunks.is_empty() => {
+            out.push_str(format!(
+                "--- {}\n+++ {}\n",
+                old_path.as_deref().unwrap_or("/dev/null"),
+                new_path.as_deref().unwrap_or("/dev/null")
+            ));
+            for hunk in hunks {
+                out.push_str(&hunk.diff);
+                if !hunk.diff.ends_with(b"\n") {
+                    out.push_str("\n\\ No newline at end of file\n");
+                }
+            }
+        }
+        Some(UnifiedPatch::Binary) => {
+            out.push_str(format!(
+                "Binary files {} and {} differ\n",
+                old_path.as_deref().unwrap_or("/dev/null"),
+                new_path.as_deref().unwrap_or("/dev/null")
+            ));
+        }
+        Some(UnifiedPatch::TooLarge { .. }) => {
+            bail!(
+                "'{}' is too large to be diffed and can't be exported as patch",
+                change.path
+            );
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Write the cover letter of a series, using the first paragraph of `description` as subject and the rest as text.
+fn format_cover_letter(
+    branch_name: &str,
+    description: Option<BString>,
+    commits: &[(gix::actor::Signature, String)],
+) -> BString {
+    let description = description.unwrap_or_default();
+    let description = description.trim();
+    let (subject, text) = match description.find("\n\n") {
+        Some(pos) => (&description[..pos], description[pos..].trim()),
+        None => (description, &[][..]),
+    };
+    let subject = if subject.is_empty() {
+        branch_name.to_owned()
+    } else {
+        // Like a commit subject, the first paragraph is joined into a single line.
+        subject
+            .lines()
+            .map(|line| line.trim().to_str_lossy())
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+
+    let mut out = BString::default();
+    let (author, _) = commits.last().expect("a series has at least one commit");
+    write_header(
+        &mut out,
+        gix::ObjectId::null(gix::hash::Kind::Sha1),
+        author,
+        &subject,
+        0,
+        commits.len(),
+    );
+    if !text.is_empty() {
+        out.push_str(text);
+        out.push_str("\n\n");
+    }
+    out.push_str(format!("Branch: {branch_name}\n\n"));
+    for (author, subject) in commits {
+        out.push_str("  ");
+        out.push_str(&author.name);
+        out.push_str(": ");
+        out.push_str(subject);
+        out.push(b'\n');
+    }
+    out.push_str("\n-- \nGitButler\n\n");
+    out
+}
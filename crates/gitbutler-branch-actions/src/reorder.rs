@@ -35,6 +35,7 @@ pub fn reorder_stack(
     new_order.validate(current_order.clone())?;
 
     let gix_repo = ctx.repo.get()?;
+    let gix_repo = stack.repo_with_identity(&gix_repo)?;
     let default_target = state.get_default_target()?;
     let default_target_commit = repo
         .find_reference(&default_target.branch.to_string())?
@@ -63,7 +64,7 @@ pub fn reorder_stack(
     // Ensure the stack head is set to the new oid after rebasing
     stack.set_stack_head(&state, &gix_repo, new_head)?;
 
-    stack.set_heads_from_rebase_output(ctx, output.references.clone())?;
+    stack.set_heads_from_rebase_output(ctx, &output)?;
 
     let new_workspace = WorkspaceState::create(ctx, perm.read_permission())?;
     // Even if this fails, it's not actionable
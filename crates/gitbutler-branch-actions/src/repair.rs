@@ -0,0 +1,89 @@
+use anyhow::Result;
+use but_ctx::{Context, access::WorktreeWritePermission};
+use but_workspace::legacy::{IntegrityIssue, fsck};
+use serde::Serialize;
+
+use crate::{VirtualBranchesExt as _, integration};
+
+/// The result of [`repair_workspace()`](crate::repair_workspace()).
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RepairOutcome {
+    /// The issues that were found and fixed.
+    pub repaired: Vec<IntegrityIssue>,
+    /// The issues that couldn't be fixed automatically.
+    pub remaining: Vec<IntegrityIssue>,
+}
+
+/// Fix the issues [`fsck()`] finds in the workspace, as far as possible.
+///
+/// * Changes assigned to stacks that aren't in the workspace are unassigned.
+/// * Missing references of stack branches are re-created from the workspace metadata.
+/// * The workspace commit is re-created from the heads of all stacks. Commits that were made on top of it
+///   are moved into a new stack first, so they aren't lost.
+///
+/// A missing target reference or `HEAD` pointing outside the workspace can't be fixed here.
+pub(crate) fn repair_workspace(
+    ctx: &Context,
+    perm: &mut WorktreeWritePermission,
+) -> Result<RepairOutcome> {
+    let issues = fsck(ctx)?;
+    let vb_state = ctx.legacy_project.virtual_branches();
+
+    let mut recreate_workspace_commit = false;
+    let mut dangling_stack_ids = Vec::new();
+    for issue in &issues {
+        match issue {
+            IntegrityIssue::MissingBranchRef {
+                stack_id,
+                branch_name,
+            } => {
+                let stack = vb_state.get_stack(*stack_id)?;
+                if let Some(branch) = stack.heads.iter().find(|b| &b.name == branch_name) {
+                    branch.set_reference_to_head_value(&*ctx.repo.get()?)?;
+                }
+                // The workspace commit may have been built without this branch.
+                recreate_workspace_commit = true;
+            }
+            IntegrityIssue::WorkspaceCommitMissing { .. } => {
+                integration::verify_branch(ctx, perm)?;
+                recreate_workspace_commit = true;
+            }
+            IntegrityIssue::WorkspaceCommitParentsMismatch { .. } => {
+                recreate_workspace_commit = true;
+            }
+            IntegrityIssue::DanglingAssignment { stack_id, .. } => {
+                dangling_stack_ids.push(stack_id.clone());
+            }
+            IntegrityIssue::NotInWorkspace { .. } | IntegrityIssue::MissingTargetRef { .. } => {}
+        }
+    }
+
+    if !dangling_stack_ids.is_empty() {
+        let mut db = ctx.db.get_mut()?;
+        let mut assignments = db.hunk_assignments().list_all()?;
+        for assignment in &mut assignments {
+            if assignment
+                .stack_id
+                .as_ref()
+                .is_some_and(|id| dangling_stack_ids.contains(id))
+            {
+                assignment.stack_id = None;
+            }
+        }
+        db.hunk_assignments_mut()?.set_all(assignments)?;
+    }
+    if recreate_workspace_commit {
+        integration::update_workspace_commit(&vb_state, ctx, false)?;
+    }
+
+    let remaining = fsck(ctx)?;
+    let repaired = issues
+        .into_iter()
+        .filter(|issue| !remaining.contains(issue))
+        .collect();
+    Ok(RepairOutcome {
+        repaired,
+        remaining,
+    })
+}
@@ -0,0 +1,155 @@
+use anyhow::{Context as _, Result, bail};
+use bstr::{BString, ByteSlice};
+use but_core::{RepositoryExt as _, TreeChange};
+use but_ctx::{Context, access::WorktreeWritePermission};
+use but_oxidize::OidExt as _;
+use but_rebase::RebaseStep;
+use but_workspace::legacy::stack_ext::StackExt;
+use gitbutler_cherry_pick::GixRepositoryExt as _;
+use gitbutler_repo::RepositoryExt as _;
+use gitbutler_stack::StackId;
+use gix::prelude::ObjectIdExt as _;
+use serde::{Deserialize, Serialize};
+use tracing::instrument;
+
+use crate::VirtualBranchesExt as _;
+
+/// Where the changes reverting a commit should go.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RevertMode {
+    /// Create a new commit with the inverse changes on top of the stack.
+    #[default]
+    Commit,
+    /// Apply the inverse changes to the worktree, where they remain uncommitted.
+    Worktree,
+}
+
+/// The result of [reverting](crate::revert_commit()) a commit.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RevertOutcome {
+    /// The commit that reverts the original one, if it was reverted with [`RevertMode::Commit`].
+    #[serde(with = "but_serde::object_id_opt")]
+    pub new_commit: Option<gix::ObjectId>,
+    /// If `true`, the new commit couldn't be created cleanly and is now conflicted.
+    pub conflicted: bool,
+    /// The changes made to the worktree if it was reverted with [`RevertMode::Worktree`],
+    /// as seen from the worktree before the revert.
+    #[serde(skip)]
+    pub worktree_changes: Vec<TreeChange>,
+}
+
+/// Revert the commit with `commit_id`, which must be part of the stack with `stack_id`, according to `mode`.
+#[instrument(level = "debug", skip(ctx, perm))]
+pub(crate) fn revert_commit(
+    ctx: &Context,
+    stack_id: StackId,
+    commit_id: gix::ObjectId,
+    mode: RevertMode,
+    perm: &mut WorktreeWritePermission,
+) -> Result<RevertOutcome> {
+    let vb_state = ctx.legacy_project.virtual_branches();
+    let stack = vb_state.get_stack_in_workspace(stack_id)?;
+    let repo = ctx.repo.get()?;
+    let repo = stack.repo_with_identity(&repo)?;
+    let in_stack = stack
+        .as_rebase_steps(ctx, &repo)?
+        .iter()
+        .any(|step| matches!(step, RebaseStep::Pick { commit_id: id, .. } if *id == commit_id));
+    if !in_stack {
+        bail!("Commit {commit_id} is not part of the stack it should be reverted in");
+    }
+
+    let commit = but_core::Commit::from_id(commit_id.attach(&repo))?;
+    if commit.is_conflicted() {
+        bail!("Cannot revert conflicted commit {commit_id}");
+    }
+    let parent_id = match commit.parents.as_slice() {
+        [parent] => *parent,
+        [] => bail!("Cannot revert root commit {commit_id}"),
+        _ => bail!("Cannot revert merge commit {commit_id}"),
+    };
+    let parent_tree_id = repo.find_commit(parent_id)?.tree_id()?.detach();
+
+    match mode {
+        RevertMode::Commit => {
+            let (author, committer) = repo.commit_signatures()?;
+            let revert = gix::objs::Commit {
+                tree: parent_tree_id,
+                parents: vec![commit_id].into(),
+                author,
+                committer,
+                encoding: None,
+                message: revert_message(commit.message.as_ref(), commit_id),
+                extra_headers: Vec::new(),
+            };
+            let to_pick = repo.write_object(&revert)?.detach();
+            let new_id = crate::cherry_pick::pick_onto_stack(ctx, stack_id, &[to_pick], perm)?
+                .pop()
+                .context("one commit was picked")?;
+            Ok(RevertOutcome {
+                new_commit: Some(new_id),
+                conflicted: but_core::Commit::from_id(new_id.attach(&repo))?.is_conflicted(),
+                worktree_changes: Vec::new(),
+            })
+        }
+        RevertMode::Worktree => {
+            let git2_repo = ctx.git2_repo.get()?;
+            let worktree_tree_id = git2_repo.create_wd_tree(0)?.id().to_gix();
+            let commit_tree_id = repo.find_real_tree(&commit_id, Default::default())?;
+            let (merge_options, conflict_kind) = repo.merge_options_fail_fast()?;
+            let mut merge = repo.merge_trees(
+                commit_tree_id,
+                worktree_tree_id,
+                parent_tree_id,
+                repo.default_merge_labels(),
+                merge_options,
+            )?;
+            if merge.has_unresolved_conflicts(conflict_kind) {
+                bail!(
+                    "Commit {commit_id} can't be reverted cleanly into the worktree, revert it as commit instead"
+                );
+            }
+            let new_tree_id = merge.tree.write()?.detach();
+            let worktree_changes =
+                but_core::diff::tree_changes(&repo, Some(worktree_tree_id), new_tree_id)?;
+
+            but_core::worktree::safe_checkout(
+                worktree_tree_id,
+                new_tree_id,
+                &repo,
+                but_core::worktree::checkout::Options::default(),
+            )
+            .context("failed to checkout tree")?;
+            Ok(RevertOutcome {
+                new_commit: None,
+                conflicted: false,
+                worktree_changes,
+            })
+        }
+    }
+}
+
+/// Produce the message `git revert` would use for a commit with `message` and `commit_id`.
+fn revert_message(message: &[u8], commit_id: gix::ObjectId) -> BString {
+    let subject = message.lines().next().unwrap_or_default().trim();
+    let mut out = BString::from(b"Revert \"".as_slice());
+    out.extend_from_slice(subject);
+    out.extend_from_slice(format!("\"\n\nThis reverts commit {commit_id}.\n").as_bytes());
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::revert_message;
+
+    #[test]
+    fn revert_message_quotes_subject_only() {
+        let id = gix::ObjectId::from_hex(b"aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa").unwrap();
+        assert_eq!(
+            revert_message(b"add feature\n\nwith a body\n", id),
+            "Revert \"add feature\"\n\nThis reverts commit aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa.\n"
+        );
+    }
+}
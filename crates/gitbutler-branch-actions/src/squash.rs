@@ -65,6 +65,7 @@ fn do_squash_commits(
     let vb_state = ctx.legacy_project.virtual_branches();
     let stack = vb_state.get_stack_in_workspace(stack_id)?;
     let gix_repo = ctx.repo.get()?;
+    let gix_repo = stack.repo_with_identity(&gix_repo)?;
 
     let default_target = vb_state.get_default_target()?;
     let repo = ctx.git2_repo.get()?;
@@ -219,12 +220,13 @@ fn do_squash_commits(
     let obj = gix_destination.decode()?.into_owned()?;
     let headers = Headers::try_from_commit(&obj);
 
-    // Create a new commit with the final tree
+    // Create a new commit with the final tree, committed by whoever commits in this stack.
+    let (_, committer) = stack.signatures(&gix_repo)?;
     let new_commit_oid = repo
         .commit_with_signature(
             None,
             &destination_commit.author(),
-            &destination_commit.author(),
+            &committer,
             &new_message,
             &final_tree,
             &parents.iter().collect::<Vec<_>>(),
@@ -270,7 +272,7 @@ fn do_squash_commits(
     update_uncommitted_changes(ctx, old_workspace, new_workspace, perm)?;
     crate::integration::update_workspace_commit(&vb_state, ctx, false)
         .context("failed to update gitbutler workspace")?;
-    stack.set_heads_from_rebase_output(ctx, output.references)?;
+    stack.set_heads_from_rebase_output(ctx, &output)?;
     Ok(new_commit_oid)
 }
 
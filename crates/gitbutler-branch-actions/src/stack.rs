@@ -1,13 +1,16 @@
-use anyhow::{Context as _, Result};
-use but_core::RepositoryExt;
-use but_ctx::Context;
+use anyhow::{Context as _, Result, bail};
+use but_core::{RepositoryExt, branch::ProtectedBranches, ref_metadata::StackIdentity};
+use but_ctx::{
+    Context,
+    access::{OperationCategory, Priority},
+};
 use but_oxidize::{ObjectIdExt, OidExt};
 use gitbutler_operating_modes::ensure_open_workspace_mode;
 use gitbutler_oplog::{
     OplogExt, SnapshotExt,
     entry::{OperationKind, SnapshotDetails},
 };
-use gitbutler_reference::normalize_branch_name;
+use gitbutler_reference::{Refname, normalize_branch_name};
 use gitbutler_repo::hooks;
 use gitbutler_repo_actions::RepoActionsExt;
 use gitbutler_stack::{PatchReferenceUpdate, StackBranch, StackId};
@@ -71,6 +74,7 @@ pub struct CreateSeriesRequest {
 pub fn remove_branch(ctx: &Context, stack_id: StackId, branch_name: &str) -> Result<()> {
     let mut guard = ctx.exclusive_worktree_access();
     ctx.verify(guard.write_permission())?;
+    ProtectedBranches::from_repo(&*ctx.repo.get()?)?.ensure_unprotected(branch_name.into())?;
     let _ = ctx.snapshot_remove_dependent_branch(branch_name, guard.write_permission());
     ensure_open_workspace_mode(ctx).context("Requires an open workspace mode")?;
     let mut stack = ctx.legacy_project.virtual_branches().get_stack(stack_id)?;
@@ -126,8 +130,107 @@ pub fn update_branch_pr_number(
     stack.set_pr_number(ctx, &branch_name, pr_number)
 }
 
+/// Sets the identity to author and commit as in the given stack, or removes it if `identity` is `None`,
+/// to fall back to the Git configuration.
+///
+/// # Errors
+/// This method will return an error if:
+///  - The stack can't be found
+///  - The project is not in workspace mode
+///  - Persisting the changes failed
+pub fn set_stack_identity(
+    ctx: &Context,
+    stack_id: StackId,
+    identity: Option<StackIdentity>,
+) -> Result<()> {
+    let mut guard =
+        ctx.queued_exclusive_worktree_access(OperationCategory::Other, Priority::Normal)?;
+    ctx.verify(guard.write_permission())?;
+    let _ = ctx.create_snapshot(
+        SnapshotDetails::new(OperationKind::UpdateStackIdentity),
+        guard.write_permission(),
+    );
+    ensure_open_workspace_mode(ctx).context("Requires an open workspace mode")?;
+    let vb_state = ctx.legacy_project.virtual_branches();
+    let mut stack = vb_state.get_stack(stack_id)?;
+    stack.identity = identity.filter(|identity| !identity.is_empty());
+    vb_state.set_stack(stack)
+}
+
+/// Sets the description of the given stack, or removes it if `description` is `None` or empty.
+///
+/// # Errors
+/// This method will return an error if:
+///  - The stack can't be found
+///  - The project is not in workspace mode
+///  - Persisting the changes failed
+pub fn set_stack_description(
+    ctx: &Context,
+    stack_id: StackId,
+    description: Option<String>,
+) -> Result<()> {
+    let mut guard =
+        ctx.queued_exclusive_worktree_access(OperationCategory::Other, Priority::Normal)?;
+    ctx.verify(guard.write_permission())?;
+    let _ = ctx.create_snapshot(
+        SnapshotDetails::new(OperationKind::UpdateBranchNotes),
+        guard.write_permission(),
+    );
+    ensure_open_workspace_mode(ctx).context("Requires an open workspace mode")?;
+    let vb_state = ctx.legacy_project.virtual_branches();
+    let mut stack = vb_state.get_stack(stack_id)?;
+    stack.stack_notes.description = description.filter(|text| !text.trim().is_empty());
+    vb_state.set_stack(stack)
+}
+
+/// Sets the note about the commit with `commit_id`, or removes it if `text` is `None` or empty.
+/// The note is stored with the applied stack that contains the commit, or the commit it was rewritten into,
+/// and follows the commit when it's rewritten.
+///
+/// # Errors
+/// This method will return an error if:
+///  - No applied stack contains the commit
+///  - The project is not in workspace mode
+///  - Persisting the changes failed
+pub fn set_commit_note(
+    ctx: &Context,
+    commit_id: gix::ObjectId,
+    text: Option<String>,
+) -> Result<()> {
+    let mut guard =
+        ctx.queued_exclusive_worktree_access(OperationCategory::Other, Priority::Normal)?;
+    ctx.verify(guard.write_permission())?;
+    let _ = ctx.create_snapshot(
+        SnapshotDetails::new(OperationKind::UpdateBranchNotes),
+        guard.write_permission(),
+    );
+    ensure_open_workspace_mode(ctx).context("Requires an open workspace mode")?;
+    let vb_state = ctx.legacy_project.virtual_branches();
+    let stacks = vb_state.list_stacks_in_workspace()?;
+    // The commit may have been rewritten since it was shown to the user.
+    let candidates = [Some(commit_id), crate::resolve_rewritten(ctx, commit_id)?];
+    for commit_id in candidates.into_iter().flatten() {
+        for stack in &stacks {
+            if stack.commits(ctx)?.contains(&commit_id.to_git2()) {
+                let mut stack = stack.clone();
+                stack.stack_notes.set_commit_note(commit_id, text);
+                return vb_state.set_stack(stack);
+            }
+        }
+    }
+    bail!("Commit {commit_id} isn't part of any applied stack")
+}
+
 /// Pushes all series in the stack to the remote.
 /// This operation will error out if the target has no push remote configured.
+///
+/// Force-pushes with force push protection are refused with [`RemoteDiverged`] if the remote branch moved since
+/// GitButler last pushed it, unless the remote commits are part of the branch.
+///
+/// `push_options` are passed to the remote with `--push-option`, and default to the values of `push.pushOption`
+/// of the project if empty, just like Git does. Gerrit `push_opts` are added to them in Gerrit mode,
+/// where the topic defaults to the name of the stack.
+/// The `pre-push` hook runs if `run_hooks` is `true`, unless hooks are disabled for the project.
 pub fn push_stack(
     ctx: &mut Context,
     stack_id: StackId,
@@ -136,11 +239,16 @@ pub fn push_stack(
     branch_limit: String,
     run_hooks: bool,
     push_opts: Vec<but_gerrit::PushFlag>,
+    push_options: Vec<String>,
 ) -> Result<PushResult> {
+    // Pushes are serialized with all other queued operations, but don't hold the worktree lock while talking to the remote.
+    let operation = ctx
+        .enqueue_operation(OperationCategory::Push, Priority::Normal)
+        .wait()?;
     ctx.verify(ctx.exclusive_worktree_access().write_permission())?;
     ensure_open_workspace_mode(ctx).context("Requires an open workspace mode")?;
     let state = ctx.legacy_project.virtual_branches();
-    let stack = state.get_stack(stack_id)?;
+    let mut stack = state.get_stack(stack_id)?;
 
     let git2_repo = ctx.git2_repo.get()?;
     let default_target = state.get_default_target()?;
@@ -150,11 +258,12 @@ pub fn push_stack(
         .id()
         .to_gix();
 
-    // First fetch, because we dont want to push integrated series
-    ctx.fetch(
-        &default_target.push_remote_name(),
-        Some("push_stack".into()),
-    )?;
+    // First fetch, because we dont want to push integrated series.
+    // Integration is checked against the fetch remote, while the push remote may differ in fork-based workflows.
+    for remote in default_target.remote_names() {
+        operation.set_progress(0, None, Some(format!("Fetching {remote}")));
+        ctx.fetch(&remote, Some("push_stack".into()))?;
+    }
     let cache = gix_repo.commit_graph_if_enabled()?;
     let stack_branches = stack.branches();
     let mut result = PushResult {
@@ -169,9 +278,22 @@ pub fn push_stack(
 
     let force_push_protection =
         !skip_force_push_protection && ctx.legacy_project.force_push_protection;
+    let protected_branches = ProtectedBranches::from_repo(&gix_repo)?;
+    let run_hooks = run_hooks && crate::hooks::hooks_enabled(ctx)?;
+    let push_options = if push_options.is_empty() {
+        configured_push_options(&gix_repo)
+    } else {
+        push_options
+    };
 
     drop(git2_repo);
-    for branch in stack_branches {
+    let branch_count = stack_branches.len();
+    for (idx, branch) in stack_branches.into_iter().enumerate() {
+        operation.set_progress(
+            idx,
+            Some(branch_count),
+            Some(format!("Pushing {}", branch.name())),
+        );
         let git2_repo = ctx.git2_repo.get()?;
         if branch.archived {
             // Nothing to push for this one
@@ -205,6 +327,23 @@ pub fn push_stack(
             .unwrap_or_else(|_| git2::Oid::zero());
         let local_sha = push_details.head;
 
+        if with_force {
+            ensure_force_push_unprotected(
+                &gix_repo,
+                &protected_branches,
+                &branch,
+                &push_details.remote_refname,
+                (!before_sha.is_zero()).then_some(before_sha),
+                local_sha,
+            )?;
+        }
+        let remote_sha = (!before_sha.is_zero()).then_some(before_sha);
+        let lease = if with_force && force_push_protection {
+            force_push_lease(&git2_repo, &branch, remote_sha, local_sha)?
+        } else {
+            None
+        };
+
         if run_hooks {
             let remote_name = default_target.push_remote_name();
             let remote = git2_repo.find_remote(&remote_name)?;
@@ -238,21 +377,31 @@ pub fn push_stack(
             None
         };
 
-        let push_opts = if gerrit_mode {
+        let mut push_opts: Vec<String> = if gerrit_mode {
             push_opts.iter().map(|o| o.to_string()).collect()
         } else {
             vec![]
         };
+        push_opts.extend(push_options.iter().cloned());
+        // Group all changes of the stack on Gerrit unless a topic was chosen.
+        if gerrit_mode && !push_opts.iter().any(|opt| opt.starts_with("topic=")) {
+            push_opts.push(but_gerrit::PushFlag::Topic(stack.name()).to_string());
+        }
 
-        let out = ctx.push(
-            push_details.head,
-            &push_details.remote_refname,
-            with_force,
-            force_push_protection,
-            refspec,
-            Some(Some(stack.id)),
-            push_opts,
-        )?;
+        let out = ctx
+            .push(
+                push_details.head,
+                &push_details.remote_refname,
+                with_force,
+                force_push_protection,
+                lease,
+                refspec,
+                Some(Some(stack.id)),
+                push_opts,
+            )
+            .map_err(|err| {
+                with_remote_diverged(err, &push_details.remote_refname, lease, remote_sha)
+            })?;
 
         drop(git2_repo);
         if gerrit_mode {
@@ -270,6 +419,12 @@ pub fn push_stack(
             push_details.remote_refname.to_owned().into(),
         ));
 
+        // Remember where we left the remote, it's the lease for the next force-push.
+        if let Some(pushed_branch) = stack.heads.iter_mut().find(|b| b.name == branch.name) {
+            pushed_branch.remote_head = Some(local_sha.to_gix());
+            state.set_stack(stack.clone())?;
+        }
+
         // Record the SHA update (before -> after)
         result.branch_sha_updates.push((
             branch.name().to_owned(),
@@ -281,10 +436,353 @@ pub fn push_stack(
             break;
         }
     }
+    operation.set_progress(branch_count, Some(branch_count), None);
 
     Ok(result)
 }
 
+/// The result of pushing one branch of a stack with [`push_stack_all()`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RefPushResult {
+    /// The name of the branch.
+    pub branch_name: String,
+    /// The remote refname the branch is pushed to.
+    pub remote_refname: Refname,
+    /// The commit the remote branch pointed to before the push, if it existed.
+    #[serde(with = "but_serde::object_id_opt")]
+    pub before: Option<gix::ObjectId>,
+    /// The commit the branch was pushed with.
+    #[serde(with = "but_serde::object_id")]
+    pub after: gix::ObjectId,
+    /// What happened to the remote branch.
+    pub status: RefPushStatus,
+}
+
+/// What happened to a remote branch in [`push_stack_all()`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", content = "subject", rename_all = "camelCase")]
+pub enum RefPushStatus {
+    /// The remote branch now points to the pushed commit.
+    Pushed,
+    /// The push was rejected, with the reason.
+    Failed(String),
+    /// A branch below this one failed to push, so this one wasn't pushed either.
+    NotAttempted,
+}
+
+/// The result of [`push_stack_all()`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StackPushOutcome {
+    /// The name of the remote the branches were pushed to.
+    pub remote: String,
+    /// If `true`, all branches were pushed in a single atomic push.
+    pub atomic: bool,
+    /// The branches that had something to push, ordered from the bottom of the stack to the top.
+    pub refs: Vec<RefPushResult>,
+}
+
+/// Push all branches of the stack with `stack_id` that have something to push in a single atomic push,
+/// so the remote never sees only a part of the stack.
+/// If the remote doesn't support atomic pushes, the branches are pushed one by one from the bottom of the stack to
+/// the top, and pushing stops at the first branch that fails as the branches above depend on it.
+///
+/// The `pre-push` hook runs for every branch unless hooks are disabled for the project, and failures abort the push.
+/// Force-pushes are protected like in [`push_stack()`].
+pub fn push_stack_all(
+    ctx: &mut Context,
+    stack_id: StackId,
+    with_force: bool,
+) -> Result<StackPushOutcome> {
+    let operation = ctx
+        .enqueue_operation(OperationCategory::Push, Priority::Normal)
+        .wait()?;
+    ctx.verify(ctx.exclusive_worktree_access().write_permission())?;
+    ensure_open_workspace_mode(ctx).context("Requires an open workspace mode")?;
+    let state = ctx.legacy_project.virtual_branches();
+    let mut stack = state.get_stack(stack_id)?;
+    let default_target = state.get_default_target()?;
+    let gix_repo = ctx.clone_repo_for_merging_non_persisting()?;
+    if gix_repo
+        .git_settings()?
+        .gitbutler_gerrit_mode
+        .unwrap_or(false)
+    {
+        bail!("Stacks can't be pushed atomically in Gerrit mode");
+    }
+
+    let remote_name = default_target.push_remote_name();
+    for remote in default_target.remote_names() {
+        operation.set_progress(0, None, Some(format!("Fetching {remote}")));
+        ctx.fetch(&remote, Some("push_stack_all".into()))?;
+    }
+
+    let git2_repo = ctx.git2_repo.get()?;
+    let merge_base_id = git2_repo
+        .merge_base(stack.head_oid(ctx)?.to_git2(), default_target.sha)?
+        .to_gix();
+    let force_push_protection = ctx.legacy_project.force_push_protection;
+    let protected_branches = ProtectedBranches::from_repo(&gix_repo)?;
+    let run_hooks = crate::hooks::hooks_enabled(ctx)?;
+    let remote_url = git2_repo
+        .find_remote(&remote_name)?
+        .url()
+        .map(ToOwned::to_owned)
+        .with_context(|| format!("Remote named {remote_name} didn't have a URL"))?;
+    let push_options = configured_push_options(&gix_repo);
+
+    let cache = gix_repo.commit_graph_if_enabled()?;
+    let mut to_push = Vec::new();
+    for branch in stack.branches() {
+        if branch.archived || branch.head_oid(&gix_repo)? == merge_base_id {
+            continue;
+        }
+        let mut graph = gix_repo.revision_graph(cache.as_ref());
+        let mut check_commit =
+            IsCommitIntegrated::new(ctx, &default_target, &gix_repo, &mut graph)?;
+        if branch_integrated(&mut check_commit, &branch, &git2_repo, &gix_repo)? {
+            continue;
+        }
+        drop(graph);
+        let push_details = stack.push_details(ctx, branch.name().to_owned())?;
+        let before = git2_repo
+            .find_reference(&push_details.remote_refname.to_string())
+            .and_then(|r| r.peel_to_commit())
+            .map(|c| c.id())
+            .ok();
+        if before == Some(push_details.head) {
+            continue;
+        }
+        if with_force {
+            ensure_force_push_unprotected(
+                &gix_repo,
+                &protected_branches,
+                &branch,
+                &push_details.remote_refname,
+                before,
+                push_details.head,
+            )?;
+        }
+        let lease = if with_force && force_push_protection {
+            force_push_lease(&git2_repo, &branch, before, push_details.head)?
+        } else {
+            None
+        };
+        if run_hooks
+            && let hooks::HookResult::Failure(error_data) = hooks::pre_push(
+                &git2_repo,
+                &remote_name,
+                &remote_url,
+                push_details.head,
+                &push_details.remote_refname,
+            )?
+        {
+            bail!("pre-push hook failed: {}", error_data.error);
+        }
+        to_push.push((branch.name().to_owned(), push_details, before, lease));
+    }
+    drop(git2_repo);
+
+    let updates: Vec<_> = to_push
+        .iter()
+        .map(|(_, details, _, lease)| (details.head, details.remote_refname.clone(), *lease))
+        .collect();
+    operation.set_progress(
+        0,
+        Some(to_push.len()),
+        Some(format!(
+            "Pushing {} branches to {remote_name}",
+            to_push.len()
+        )),
+    );
+    let atomic = ctx
+        .push_atomic(
+            &updates,
+            with_force,
+            force_push_protection,
+            Some(Some(stack_id)),
+            push_options.clone(),
+        )
+        .map_err(|err| {
+            to_push
+                .iter()
+                .fold(err, |err, (_, details, before, lease)| {
+                    with_remote_diverged(err, &details.remote_refname, *lease, *before)
+                })
+        })?
+        .is_some();
+
+    let mut refs = Vec::with_capacity(to_push.len());
+    let mut failed = false;
+    let branch_count = to_push.len();
+    for (idx, (branch_name, details, before, lease)) in to_push.into_iter().enumerate() {
+        if !atomic {
+            operation.set_progress(
+                idx,
+                Some(branch_count),
+                Some(format!("Pushing {branch_name}")),
+            );
+        }
+        let status = if atomic {
+            RefPushStatus::Pushed
+        } else if failed {
+            RefPushStatus::NotAttempted
+        } else {
+            match ctx
+                .push(
+                    details.head,
+                    &details.remote_refname,
+                    with_force,
+                    force_push_protection,
+                    lease,
+                    None,
+                    Some(Some(stack_id)),
+                    push_options.clone(),
+                )
+                .map_err(|err| with_remote_diverged(err, &details.remote_refname, lease, before))
+            {
+                Ok(_) => RefPushStatus::Pushed,
+                Err(err) => {
+                    failed = true;
+                    RefPushStatus::Failed(format!("{err:#}"))
+                }
+            }
+        };
+        if status == RefPushStatus::Pushed
+            && let Some(pushed_branch) = stack.heads.iter_mut().find(|b| b.name == branch_name)
+        {
+            pushed_branch.remote_head = Some(details.head.to_gix());
+        }
+        refs.push(RefPushResult {
+            branch_name,
+            remote_refname: details.remote_refname.into(),
+            before: before.map(|id| id.to_gix()),
+            after: details.head.to_gix(),
+            status,
+        });
+    }
+    state.set_stack(stack)?;
+    operation.set_progress(branch_count, Some(branch_count), None);
+
+    Ok(StackPushOutcome {
+        remote: remote_name,
+        atomic,
+        refs,
+    })
+}
+
+/// The context of a refused force-push of a branch that would have overwritten commits on its remote that GitButler hasn't seen.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteDiverged {
+    /// The name of the remote branch, like `refs/remotes/origin/feat`.
+    pub remote_refname: String,
+    /// The commit the remote branch pointed to when GitButler last pushed it.
+    pub expected: gix::ObjectId,
+    /// The commit the remote branch points to now.
+    pub actual: gix::ObjectId,
+}
+
+impl std::fmt::Display for RemoteDiverged {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "The remote branch {} moved from {} to {} since it was last pushed, and force-pushing would overwrite these commits",
+            self.remote_refname,
+            self.expected.to_hex_with_len(7),
+            self.actual.to_hex_with_len(7)
+        )
+    }
+}
+
+impl std::error::Error for RemoteDiverged {}
+
+/// Fail with [`ButError::BranchProtected`](but_error::ButError::BranchProtected) if `branch` or the branch
+/// on the remote it's pushed to is protected, and force-pushing `local_sha` would rewrite `remote_sha`, its tip on the remote.
+fn ensure_force_push_unprotected(
+    repo: &gix::Repository,
+    protected_branches: &ProtectedBranches,
+    branch: &StackBranch,
+    remote_refname: &gitbutler_reference::RemoteRefname,
+    remote_sha: Option<git2::Oid>,
+    local_sha: git2::Oid,
+) -> Result<()> {
+    let (remote_tip, local_tip) = (remote_sha.map(|id| id.to_gix()), local_sha.to_gix());
+    protected_branches.ensure_push_unprotected(
+        repo,
+        branch.name().as_str().into(),
+        remote_tip,
+        local_tip,
+    )?;
+    protected_branches.ensure_push_unprotected(
+        repo,
+        remote_refname.branch().into(),
+        remote_tip,
+        local_tip,
+    )
+}
+
+/// Return the commit the remote branch of `branch` has to point to for force-pushing `local_sha` with force-push
+/// protection, which is where it was last pushed to.
+///
+/// It's `None` if `branch` was never pushed by us, or if `remote_sha`, the tip of the remote branch as of the last fetch,
+/// is missing or contained in `local_sha`, so the remote tracking branch can be used as lease instead.
+fn force_push_lease(
+    repo: &git2::Repository,
+    branch: &StackBranch,
+    remote_sha: Option<git2::Oid>,
+    local_sha: git2::Oid,
+) -> Result<Option<git2::Oid>> {
+    let (Some(expected), Some(remote_sha)) = (branch.remote_head, remote_sha) else {
+        return Ok(None);
+    };
+    if remote_sha == local_sha || repo.graph_descendant_of(local_sha, remote_sha)? {
+        return Ok(None);
+    }
+    Ok(Some(expected.to_git2()))
+}
+
+/// Explain `err` with [`RemoteDiverged`] if it's a refused force-push to `remote_refname` that had `lease`
+/// as lease, but was at `remote_sha` as of the last fetch.
+fn with_remote_diverged(
+    err: anyhow::Error,
+    remote_refname: &gitbutler_reference::RemoteRefname,
+    lease: Option<git2::Oid>,
+    remote_sha: Option<git2::Oid>,
+) -> anyhow::Error {
+    let refused = matches!(
+        err.downcast_ref::<but_error::ButError>(),
+        Some(but_error::ButError::ForcePushProtection { .. })
+    );
+    match (lease, remote_sha) {
+        (Some(expected), Some(actual)) if refused && expected != actual => {
+            err.context(RemoteDiverged {
+                remote_refname: remote_refname.to_string(),
+                expected: expected.to_gix(),
+                actual: actual.to_gix(),
+            })
+        }
+        _ => err,
+    }
+}
+
+/// Return the values of `push.pushOption` in `repo`, where an empty value clears all previous ones, like in Git.
+fn configured_push_options(repo: &gix::Repository) -> Vec<String> {
+    let mut options = Vec::new();
+    for value in repo
+        .config_snapshot()
+        .strings("push.pushOption")
+        .unwrap_or_default()
+    {
+        if value.is_empty() {
+            options.clear();
+        } else {
+            options.push(value.to_string());
+        }
+    }
+    options
+}
+
 pub(crate) fn branch_integrated(
     check_commit: &mut IsCommitIntegrated,
     branch: &StackBranch,
@@ -298,3 +796,154 @@ pub(crate) fn branch_integrated(
     let branch_head = repo.find_commit(oid.to_git2())?;
     check_commit.is_integrated(&branch_head)
 }
+
+#[cfg(test)]
+mod tests {
+    use but_oxidize::OidExt;
+    use gitbutler_reference::RemoteRefname;
+    use gitbutler_stack::StackBranch;
+
+    use super::{RemoteDiverged, configured_push_options, force_push_lease, with_remote_diverged};
+
+    #[test]
+    fn push_options_are_reset_by_empty_values() -> anyhow::Result<()> {
+        let tmp = tempfile::tempdir()?;
+        let open_with_options = |values: &[&str]| -> anyhow::Result<gix::Repository> {
+            let repo = gix::init(tmp.path().join(values.len().to_string()))?;
+            let mut config = std::fs::read_to_string(repo.git_dir().join("config"))?;
+            config.push_str("[push]\n");
+            for value in values {
+                config.push_str(&format!("\tpushOption = {value}\n"));
+            }
+            std::fs::write(repo.git_dir().join("config"), config)?;
+            Ok(gix::open_opts(
+                repo.git_dir(),
+                gix::open::Options::isolated(),
+            )?)
+        };
+
+        let repo = open_with_options(&[])?;
+        assert!(configured_push_options(&repo).is_empty());
+
+        let repo = open_with_options(&["ci.skip", "merge_request.create"])?;
+        assert_eq!(
+            configured_push_options(&repo),
+            ["ci.skip", "merge_request.create"]
+        );
+
+        let repo = open_with_options(&["ci.skip", "", "merge_request.create", "topic=feat"])?;
+        assert_eq!(
+            configured_push_options(&repo),
+            ["merge_request.create", "topic=feat"],
+            "values before the empty one are dropped, like in Git"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn force_push_lease_is_the_last_pushed_commit_if_the_remote_moved_elsewhere()
+    -> anyhow::Result<()> {
+        let tmp = tempfile::tempdir()?;
+        let repo = git2::Repository::init(tmp.path())?;
+        let commit = |message: &str, parents: &[git2::Oid]| -> anyhow::Result<git2::Oid> {
+            let signature = git2::Signature::now("author", "author@example.com")?;
+            let tree = repo.find_tree(repo.treebuilder(None)?.write()?)?;
+            let parents = parents
+                .iter()
+                .map(|id| repo.find_commit(*id))
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(repo.commit(
+                None,
+                &signature,
+                &signature,
+                message,
+                &tree,
+                &parents.iter().collect::<Vec<_>>(),
+            )?)
+        };
+        let pushed = commit("pushed", &[])?;
+        let rewritten = commit("rewritten", &[])?;
+        let theirs = commit("theirs", &[pushed])?;
+        let ours_on_theirs = commit("ours", &[theirs])?;
+
+        let mut branch = StackBranch::new_with_zero_head("feat".into(), None, None, false);
+        let lease = |branch: &StackBranch, remote: Option<git2::Oid>, local: git2::Oid| {
+            force_push_lease(&repo, branch, remote, local)
+        };
+
+        assert_eq!(
+            lease(&branch, Some(theirs), rewritten)?,
+            None,
+            "never pushed by us, so the remote tracking branch is the lease"
+        );
+
+        branch.remote_head = Some(pushed.to_gix());
+        assert_eq!(
+            lease(&branch, None, rewritten)?,
+            None,
+            "the remote branch is gone"
+        );
+        assert_eq!(
+            lease(&branch, Some(theirs), ours_on_theirs)?,
+            None,
+            "their commits are contained in ours"
+        );
+        assert_eq!(
+            lease(&branch, Some(pushed), rewritten)?,
+            Some(pushed),
+            "the remote is where we left it"
+        );
+        assert_eq!(
+            lease(&branch, Some(theirs), rewritten)?,
+            Some(pushed),
+            "their commits would be lost, so only overwrite what we pushed"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn refused_force_pushes_are_explained_if_the_remote_moved_away_from_the_lease()
+    -> anyhow::Result<()> {
+        let remote_refname: RemoteRefname = "refs/remotes/origin/feat".parse()?;
+        let (pushed, theirs) = (
+            git2::Oid::from_str("1111111111111111111111111111111111111111")?,
+            git2::Oid::from_str("2222222222222222222222222222222222222222")?,
+        );
+        let refused = || {
+            anyhow::anyhow!("rejected").context(but_error::ButError::ForcePushProtection {
+                remote_ref: remote_refname.to_string(),
+            })
+        };
+
+        let err = with_remote_diverged(refused(), &remote_refname, Some(pushed), Some(theirs));
+        assert_eq!(
+            err.downcast_ref::<RemoteDiverged>(),
+            Some(&RemoteDiverged {
+                remote_refname: "refs/remotes/origin/feat".into(),
+                expected: pushed.to_gix(),
+                actual: theirs.to_gix(),
+            })
+        );
+        assert!(
+            matches!(
+                err.downcast_ref::<but_error::ButError>(),
+                Some(but_error::ButError::ForcePushProtection { .. })
+            ),
+            "the refusal is still typed"
+        );
+
+        for err in [
+            with_remote_diverged(refused(), &remote_refname, Some(pushed), Some(pushed)),
+            with_remote_diverged(refused(), &remote_refname, None, Some(theirs)),
+            with_remote_diverged(
+                anyhow::anyhow!("network"),
+                &remote_refname,
+                Some(pushed),
+                Some(theirs),
+            ),
+        ] {
+            assert!(err.downcast_ref::<RemoteDiverged>().is_none());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,245 @@
+//! Move stacks between machines without a remote, using a Git bundle for the commits and metadata for everything
+//! Git doesn't know about, like the order of the branches and the uncommitted changes assigned to the stack.
+
+use std::{
+    ffi::OsStr,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context as _, Result, bail};
+use bstr::{BString, ByteSlice};
+use but_core::{DiffSpec, RepositoryExt as _, TreeChange};
+use but_ctx::{Context, access::WorktreeWritePermission};
+use gitbutler_reference::{LocalRefname, Refname};
+use gitbutler_stack::{StackBranch, StackId};
+use gix::refs::transaction::PreviousValue;
+use serde::{Deserialize, Serialize};
+
+use crate::{BranchManagerExt, VirtualBranchesExt as _};
+
+/// Everything about a stack that isn't part of the Git bundle it was exported to.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StackBundleMetadata {
+    /// The branches of the stack, bottom-most first.
+    pub branches: Vec<BundledBranch>,
+    /// The commit the stack is based on, which the repository the bundle is imported into needs to have.
+    #[serde(with = "but_serde::object_id")]
+    pub base: gix::ObjectId,
+    /// The uncommitted changes that were assigned to the stack, in the format of `git diff`.
+    pub uncommitted_changes: String,
+}
+
+/// A branch of a stack in a bundle.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundledBranch {
+    /// The name of the branch, like `feature`.
+    pub name: String,
+    /// The description of the branch, as set with `git branch --edit-description`.
+    pub description: Option<String>,
+    #[serde(with = "but_serde::object_id")]
+    pub tip: gix::ObjectId,
+}
+
+/// The result of [importing](crate::import_stack_bundle()) a stack bundle.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportedStackBundle {
+    /// The id of the stack that was created in the workspace.
+    pub stack_id: StackId,
+    /// The uncommitted changes that were applied to the worktree, as seen from the worktree before.
+    #[serde(skip)]
+    pub worktree_changes: Vec<TreeChange>,
+}
+
+/// Return the path of the metadata that belongs to the bundle at `bundle_path`, which is the bundle path with
+/// `.json` appended, like `stack.bundle.json`.
+pub fn metadata_path(bundle_path: &Path) -> PathBuf {
+    let mut path = bundle_path.as_os_str().to_owned();
+    path.push(".json");
+    path.into()
+}
+
+/// Write the commits of all branches of the stack with `stack_id` to a Git bundle at `bundle_path`,
+/// and return the metadata needed to recreate the stack from it.
+///
+/// `uncommitted` are the uncommitted changes assigned to the stack, which are added to the metadata as diff.
+/// The bundle only contains the commits of the stack, so it can only be imported into repositories that also
+/// have the commit the stack is based on.
+pub(crate) fn export_stack_bundle(
+    ctx: &Context,
+    stack_id: StackId,
+    bundle_path: &Path,
+    uncommitted: Vec<DiffSpec>,
+) -> Result<StackBundleMetadata> {
+    let repo = ctx.repo.get()?;
+    let stack = ctx
+        .legacy_project
+        .virtual_branches()
+        .get_stack_in_workspace(stack_id)?;
+    let base = stack.merge_base(ctx)?;
+    let config = repo.config_snapshot();
+    let mut branches = Vec::new();
+    for head in stack.heads.iter().filter(|head| !head.archived) {
+        branches.push(BundledBranch {
+            name: head.name.clone(),
+            description: config
+                .string(format!("branch.{}.description", head.name).as_str())
+                .map(|description| description.to_str_lossy().into_owned()),
+            tip: head.head_oid(&repo)?,
+        });
+    }
+    if branches.iter().all(|branch| branch.tip == base) {
+        bail!("The stack has no commits that could be exported");
+    }
+
+    // Branches without commits are left out of the bundle by Git, but they are still part of the metadata.
+    let mut args = vec![
+        OsStr::new("bundle").to_owned(),
+        OsStr::new("create").to_owned(),
+        bundle_path.as_os_str().to_owned(),
+    ];
+    args.extend(
+        branches
+            .iter()
+            .map(|branch| format!("refs/heads/{}", branch.name).into()),
+    );
+    args.push(format!("^{base}").into());
+    run_git(&repo, &args)?;
+
+    let mut uncommitted_changes = BString::default();
+    let worktree_changes = but_core::diff::worktree_changes(&repo)?.changes;
+    for spec in but_workspace::flatten_diff_specs(uncommitted) {
+        let Some(change) = worktree_changes
+            .iter()
+            .find(|change| change.path == spec.path)
+        else {
+            continue;
+        };
+        crate::patch_export::write_diff(
+            &repo,
+            change,
+            ctx.settings().context_lines,
+            (!spec.hunk_headers.is_empty()).then_some(spec.hunk_headers.as_slice()),
+            &mut uncommitted_changes,
+        )?;
+    }
+
+    Ok(StackBundleMetadata {
+        branches,
+        base,
+        uncommitted_changes: uncommitted_changes.to_str_lossy().into_owned(),
+    })
+}
+
+/// Fetch the commits of the bundle at `bundle_path` and recreate the stack described by `metadata` from them,
+/// applying its uncommitted changes to the worktree.
+///
+/// Fails without changing anything if any of the branches of the stack already exists.
+pub(crate) fn import_stack_bundle(
+    ctx: &Context,
+    bundle_path: &Path,
+    metadata: &StackBundleMetadata,
+    perm: &mut WorktreeWritePermission,
+) -> Result<ImportedStackBundle> {
+    let Some((top, below)) = metadata.branches.split_last() else {
+        bail!("The stack bundle doesn't contain any branch");
+    };
+    let repo = ctx.repo.get()?;
+    for branch in &metadata.branches {
+        if repo
+            .try_find_reference(format!("refs/heads/{}", branch.name).as_str())?
+            .is_some()
+        {
+            bail!(
+                "Branch '{}' already exists, delete or rename it to import the stack",
+                branch.name
+            );
+        }
+    }
+
+    let mut args = vec![
+        OsStr::new("fetch").to_owned(),
+        OsStr::new("--no-tags").to_owned(),
+        OsStr::new("--no-write-fetch-head").to_owned(),
+        bundle_path.as_os_str().to_owned(),
+    ];
+    args.extend(
+        metadata
+            .branches
+            .iter()
+            .filter(|branch| branch.tip != metadata.base)
+            .map(|branch| format!("refs/heads/{}", branch.name).into()),
+    );
+    run_git(&repo, &args).context("Could not fetch the commits of the stack bundle")?;
+
+    repo.reference(
+        format!("refs/heads/{}", top.name),
+        top.tip,
+        PreviousValue::MustNotExist,
+        "GitButler: import stack bundle",
+    )?;
+    let (stack_id, _, _) = ctx.branch_manager().create_virtual_branch_from_branch(
+        &Refname::Local(LocalRefname::new(&top.name, None)),
+        None,
+        None,
+        perm,
+    )?;
+    let mut stack = ctx.legacy_project.virtual_branches().get_stack(stack_id)?;
+    let mut preceding_head = None;
+    for branch in below {
+        stack.add_series(
+            ctx,
+            StackBranch::new(branch.tip, branch.name.clone(), &repo)?,
+            preceding_head.replace(branch.name.clone()),
+        )?;
+    }
+
+    let mut config = repo.local_common_config_for_editing()?;
+    let mut has_descriptions = false;
+    for branch in &metadata.branches {
+        if let Some(description) = &branch.description {
+            config.set_raw_value_by(
+                "branch",
+                Some(branch.name.as_str().into()),
+                "description",
+                description.as_str(),
+            )?;
+            has_descriptions = true;
+        }
+    }
+    if has_descriptions {
+        repo.write_local_common_config(&config)?;
+    }
+
+    let worktree_changes = if metadata.uncommitted_changes.is_empty() {
+        Vec::new()
+    } else {
+        crate::patch_apply::apply_diff_to_worktree(ctx, metadata.uncommitted_changes.as_bytes())
+            .context("Could not apply the uncommitted changes of the stack bundle")?
+    };
+    Ok(ImportedStackBundle {
+        stack_id,
+        worktree_changes,
+    })
+}
+
+fn run_git(repo: &gix::Repository, args: &[std::ffi::OsString]) -> Result<()> {
+    let output =
+        std::process::Command::from(gix::command::prepare(gix::path::env::exe_invocation()))
+            .current_dir(repo.workdir().unwrap_or(repo.git_dir()))
+            .args(args)
+            .output()?;
+    if !output.status.success() {
+        bail!(
+            "`git {}` failed\n\n{}",
+            args.iter()
+                .map(|arg| arg.to_string_lossy())
+                .collect::<Vec<_>>()
+                .join(" "),
+            output.stderr.to_str_lossy()
+        );
+    }
+    Ok(())
+}
@@ -32,6 +32,7 @@ pub(crate) fn undo_commit(
 
     let merge_base = stack.merge_base(ctx)?;
     let repo = ctx.repo.get()?;
+    let repo = stack.repo_with_identity(&repo)?;
     let steps = stack
         .as_rebase_steps(ctx, &repo)?
         .into_iter()
@@ -52,7 +53,7 @@ pub(crate) fn undo_commit(
     let new_head = output.top_commit.to_git2();
     stack.set_stack_head(&vb_state, &repo, new_head)?;
 
-    stack.set_heads_from_rebase_output(ctx, output.references)?;
+    stack.set_heads_from_rebase_output(ctx, &output)?;
 
     crate::integration::update_workspace_commit(&vb_state, ctx, false)
         .context("failed to update gitbutler workspace")?;
@@ -1,9 +1,13 @@
 use std::collections::HashMap;
 
-use anyhow::{Context as _, Result, anyhow, bail};
+use anyhow::{Context as _, Result, bail};
 use bstr::ByteSlice;
 use but_core::{Reference, RepositoryExt};
-use but_ctx::{Context, access::WorktreeWritePermission};
+use but_ctx::{
+    Context,
+    access::{ProgressHandle, WorktreeWritePermission},
+};
+use but_error::ButError;
 use but_meta::VirtualBranchesTomlMetadata;
 use but_oxidize::{ObjectIdExt, OidExt, git2_to_gix_object_id, gix_to_git2_oid};
 use but_rebase::{RebaseOutput, RebaseStep};
@@ -20,7 +24,10 @@ use gitbutler_workspace::branch_trees::{WorkspaceState, update_uncommitted_chang
 use gix::merge::tree::TreatAsUnresolved;
 use serde::{Deserialize, Serialize};
 
-use crate::{BranchManagerExt, VirtualBranchesExt as _};
+use crate::{
+    BranchManagerExt, VirtualBranchesExt as _,
+    integrated_stacks::{UpstreamPatchIds, squash_merged_commits},
+};
 
 #[derive(Serialize, PartialEq, Debug)]
 #[serde(rename_all = "camelCase")]
@@ -196,7 +203,9 @@ impl<'a> UpstreamIntegrationContext<'a> {
         let target = virtual_branches_handle.get_default_target()?;
         let target_branch = git2_repo
             .maybe_find_branch_by_refname(&target.branch.clone().into())?
-            .ok_or(anyhow!("Branch not found"))?;
+            .ok_or_else(|| ButError::BranchNotFound {
+                name: target.branch.to_string(),
+            })?;
 
         let new_target = target_commit_oid
             .map_or_else(
@@ -219,7 +228,7 @@ impl<'a> UpstreamIntegrationContext<'a> {
     }
 }
 
-fn stacks(
+pub(crate) fn stacks(
     ctx: &Context,
     repo: &gix::Repository,
 ) -> anyhow::Result<Vec<but_workspace::legacy::ui::StackEntry>> {
@@ -234,7 +243,7 @@ fn stacks(
     )
 }
 
-fn stack_details(
+pub(crate) fn stack_details(
     ctx: &Context,
     stack_id: Option<StackId>,
 ) -> anyhow::Result<but_workspace::ui::StackDetails> {
@@ -260,6 +269,10 @@ fn get_stack_status(
     let mut branch_statuses: Vec<NameAndStatus> = vec![];
 
     let details = stack_details(ctx, stack_id)?;
+    let squash_merged = squash_merged_commits(
+        &mut UpstreamPatchIds::new(gix_repo, new_target_commit_id),
+        &details,
+    )?;
 
     let branches = details.branch_details;
     for branch in branches.into_iter().rev() {
@@ -283,7 +296,7 @@ fn get_stack_status(
         let is_integrated_via_commits = matches!(
             branch_head.state,
             but_workspace::ui::CommitState::Integrated
-        );
+        ) || squash_merged.contains(&branch_head.id);
 
         if is_integrated_via_commits || is_integrated_via_review {
             branch_statuses.push(NameAndStatus {
@@ -300,7 +313,10 @@ fn get_stack_status(
         // to the frontend at the minute.
         let local_commit_ids = local_commits
             .iter()
-            .filter(|c| !matches!(c.state, but_workspace::ui::CommitState::Integrated))
+            .filter(|c| {
+                !matches!(c.state, but_workspace::ui::CommitState::Integrated)
+                    && !squash_merged.contains(&c.id)
+            })
             .map(|commit| commit.id)
             .rev()
             .collect::<Vec<_>>();
@@ -451,7 +467,9 @@ pub(crate) fn integrate_upstream(
     base_branch_resolution: Option<BaseBranchResolution>,
     review_map: &HashMap<String, but_forge::ForgeReview>,
     permission: &mut WorktreeWritePermission,
+    progress: &ProgressHandle,
 ) -> Result<IntegrationOutcome> {
+    progress.set_progress(0, None, Some("Checking branches".into()));
     let old_workspace = WorkspaceState::create(ctx, permission.read_permission())?;
 
     let (target_commit_oid, base_branch_resolution_approach) = base_branch_resolution
@@ -503,9 +521,20 @@ pub(crate) fn integrate_upstream(
         }
     }
 
-    let integration_results =
-        compute_resolutions(&context, resolutions, base_branch_resolution_approach)?;
+    let integration_results = compute_resolutions(
+        &context,
+        resolutions,
+        base_branch_resolution_approach,
+        progress,
+    )?;
 
+    // This is the last chance to stop, as nothing was written yet.
+    progress.check_cancelled()?;
+    progress.set_progress(
+        resolutions.len(),
+        Some(resolutions.len() + 1),
+        Some("Updating workspace".into()),
+    );
     {
         // We perform the updates in stages. If deleting or unapplying fails, we
         // could enter a much worse state if we're simultaneously updating trees
@@ -608,7 +637,7 @@ pub(crate) fn integrate_upstream(
 
             // Update the branch heads
             if let Some(output) = rebase_output {
-                stack.set_heads_from_rebase_output(ctx, output.references.clone())?;
+                stack.set_heads_from_rebase_output(ctx, &output)?;
             }
 
             // Dissociate closed reviews
@@ -659,7 +688,15 @@ pub(crate) fn resolve_upstream_integration(
     let repo = &*ctx.git2_repo.get()?;
     let new_target_id = context.new_target;
     let old_target_id = context.target.sha;
-    let fork_point = repo.merge_base(old_target_id, new_target_id)?;
+    let fork_point = repo
+        .merge_base(old_target_id, new_target_id)
+        .map_err(|err| {
+            but_core::shallow::into_shallow_history_error(
+                &gix_repo,
+                new_target_id.to_gix(),
+                err.into(),
+            )
+        })?;
 
     match resolution_approach {
         BaseBranchResolutionApproach::HardReset => Ok(new_target_id),
@@ -672,6 +709,7 @@ pub(crate) fn resolve_upstream_integration(
                 repo.find_commit(context.new_target)?,
                 &branch_name,
                 &branch_name,
+                None,
             )?;
 
             Ok(new_head.id())
@@ -701,6 +739,7 @@ fn compute_resolutions(
     context: &UpstreamIntegrationContext,
     resolutions: &[Resolution],
     base_branch_resolution_approach: Option<BaseBranchResolutionApproach>,
+    progress: &ProgressHandle,
 ) -> Result<Vec<(Option<StackId>, IntegrationResult)>> {
     let UpstreamIntegrationContext {
         new_target,
@@ -713,7 +752,14 @@ fn compute_resolutions(
     let repo = &*ctx.git2_repo.get()?;
     let results = resolutions
         .iter()
-        .map(|resolution| {
+        .enumerate()
+        .map(|(idx, resolution)| {
+            progress.check_cancelled()?;
+            progress.set_progress(
+                idx,
+                Some(resolutions.len() + 1),
+                Some("Rebasing branches".into()),
+            );
             let Some(stack) = stacks_in_workspace
                 .iter()
                 .find(|stack| stack.id == Some(resolution.stack_id))
@@ -735,12 +781,18 @@ fn compute_resolutions(
                     let incoming_branch_name = target.branch.fullname();
                     let target_branch_name = top_branch.name.to_str()?;
 
+                    let identity = ctx
+                        .legacy_project
+                        .virtual_branches()
+                        .get_stack(resolution.stack_id)?
+                        .identity;
                     let new_head = gitbutler_merge_commits(
                         repo,
                         target_commit,
                         repo.find_commit(*new_target)?,
                         target_branch_name,
                         &incoming_branch_name,
+                        identity.as_ref(),
                     )?;
 
                     Ok((
@@ -772,6 +824,12 @@ fn compute_resolutions(
                         }
                     }
 
+                    // Commits whose changes were squash-merged can't be matched by id.
+                    let squash_merged = squash_merged_commits(
+                        &mut UpstreamPatchIds::new(context.gix_repo, new_target.to_gix()),
+                        &details,
+                    )?;
+
                     let all_steps = details.as_rebase_steps(context.gix_repo)?;
                     let branches_before = as_buckets(all_steps.clone());
                     // Filter out any integrated commits
@@ -782,9 +840,13 @@ fn compute_resolutions(
                                 commit_id,
                                 new_message: _,
                             } => {
-                                let is_integrated = commit_map.get(&commit_id).is_some_and(|c| {
-                                    matches!(c.state, but_workspace::ui::CommitState::Integrated)
-                                });
+                                let is_integrated = squash_merged.contains(&commit_id)
+                                    || commit_map.get(&commit_id).is_some_and(|c| {
+                                        matches!(
+                                            c.state,
+                                            but_workspace::ui::CommitState::Integrated
+                                        )
+                                    });
                                 if is_integrated { None } else { Some(s) }
                             }
                             _ => Some(s),
@@ -807,11 +869,13 @@ fn compute_resolutions(
                         }
                     }
 
-                    let mut rebase = but_rebase::Rebase::new(
-                        context.gix_repo,
-                        Some(lower_bound.to_gix()),
-                        None,
-                    )?;
+                    let stack_repo = ctx
+                        .legacy_project
+                        .virtual_branches()
+                        .get_stack(resolution.stack_id)?
+                        .repo_with_identity(context.gix_repo)?;
+                    let mut rebase =
+                        but_rebase::Rebase::new(&stack_repo, Some(lower_bound.to_gix()), None)?;
                     rebase.rebase_noops(false);
                     rebase.steps(steps)?;
                     let output = rebase.rebase()?;
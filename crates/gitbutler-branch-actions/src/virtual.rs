@@ -194,6 +194,7 @@ pub(crate) fn update_commit_message(
     let gix_repo = ctx.repo.get()?;
 
     let mut stack = vb_state.get_stack_in_workspace(stack_id)?;
+    let gix_repo = stack.repo_with_identity(&gix_repo)?;
     let branch_commit_oids = ctx.git2_repo.get()?.l(
         stack.head_oid(ctx)?.to_git2(),
         LogUntil::Commit(default_target.sha),
@@ -205,6 +206,18 @@ pub(crate) fn update_commit_message(
     }
 
     let mut steps = stack.as_rebase_steps(ctx, &gix_repo)?;
+    // The branch of a commit is the first reference above it.
+    let branch_name = steps
+        .iter()
+        .skip_while(|step| !matches!(step, RebaseStep::Pick { commit_id: id, .. } if *id == commit_id.to_gix()))
+        .find_map(|step| match step {
+            RebaseStep::Reference(but_core::Reference::Virtual(name)) => Some(name.clone()),
+            RebaseStep::Reference(but_core::Reference::Git(name)) => Some(name.shorten().to_string()),
+            _ => None,
+        })
+        .context("commits in stacks are always below a branch")?;
+    let message =
+        crate::message_template::apply_message_template(ctx, stack_id, &branch_name, message)?;
     // Update the commit message
     for step in steps.iter_mut() {
         if let RebaseStep::Pick {
@@ -213,7 +226,7 @@ pub(crate) fn update_commit_message(
         } = step
             && *id == commit_id.to_gix()
         {
-            *new_message = Some(message.into());
+            *new_message = Some(message.as_str().into());
         }
     }
     let merge_base = stack.merge_base(ctx)?;
@@ -224,7 +237,7 @@ pub(crate) fn update_commit_message(
 
     let new_head = output.top_commit.to_git2();
     stack.set_stack_head(&vb_state, &gix_repo, new_head)?;
-    stack.set_heads_from_rebase_output(ctx, output.references)?;
+    stack.set_heads_from_rebase_output(ctx, &output)?;
 
     crate::integration::update_workspace_commit(&vb_state, ctx, false)
         .context("failed to update gitbutler workspace")?;
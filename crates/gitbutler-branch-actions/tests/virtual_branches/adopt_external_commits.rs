@@ -0,0 +1,56 @@
+use gitbutler_branch::BranchCreateRequest;
+use gitbutler_testsupport::stack_details;
+
+use super::*;
+
+#[test]
+fn commits_made_with_git_are_moved_onto_the_stack() -> anyhow::Result<()> {
+    let Test { repo, ctx, .. } = &Test::default();
+
+    gitbutler_branch_actions::set_base_branch(
+        ctx,
+        &"refs/remotes/origin/master".parse().unwrap(),
+        ctx.exclusive_worktree_access().write_permission(),
+    )
+    .unwrap();
+
+    let stack_entry = gitbutler_branch_actions::create_virtual_branch(
+        ctx,
+        &BranchCreateRequest::default(),
+        ctx.exclusive_worktree_access().write_permission(),
+    )
+    .unwrap();
+    fs::write(repo.path().join("file.txt"), "content").unwrap();
+    super::create_commit(ctx, stack_entry.id, "commit one").unwrap();
+
+    // commit on top of the workspace commit, like `git commit` would
+    fs::write(repo.path().join("file2.txt"), "content2").unwrap();
+    let external_commit = repo.commit_all("commit with git");
+    assert_eq!(
+        gitbutler_branch_actions::external_commits(ctx)?,
+        vec![external_commit.to_gix()]
+    );
+
+    let adopted = gitbutler_branch_actions::adopt_external_commits(ctx, Some(stack_entry.id))?;
+    assert_eq!(adopted.stack_id, stack_entry.id);
+    assert_eq!(adopted.external_commits, vec![external_commit.to_gix()]);
+    assert!(gitbutler_branch_actions::external_commits(ctx)?.is_empty());
+
+    let (_, details) = stack_details(ctx)
+        .into_iter()
+        .find(|d| d.0 == stack_entry.id)
+        .unwrap();
+    let commits = &details.branch_details[0].commits;
+    let messages: Vec<_> = commits.iter().map(|c| c.message.clone()).collect();
+    assert_eq!(messages, vec!["commit with git", "commit one"]);
+    assert_eq!(
+        commits[0].author.name, "test",
+        "the authorship is preserved"
+    );
+    assert_eq!(
+        list_commit_files(ctx, commits[0].id.to_git2())?.len(),
+        1,
+        "only the changes of the commit are adopted"
+    );
+    Ok(())
+}
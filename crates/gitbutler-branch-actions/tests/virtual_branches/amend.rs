@@ -55,6 +55,7 @@ fn forcepush_allowed() -> anyhow::Result<()> {
         stack_entry.name().map(|s| s.to_string()).unwrap(),
         false, // run_hooks
         vec![],
+        vec![],
     )
     .unwrap();
 
@@ -71,6 +72,7 @@ fn forcepush_allowed() -> anyhow::Result<()> {
                 new_start: 1,
                 new_lines: 1,
             }],
+            mode_flags: None,
         }];
         gitbutler_branch_actions::amend(ctx, stack_entry.id, commit_id, to_amend).unwrap();
 
@@ -128,6 +130,7 @@ fn non_locked_hunk() -> anyhow::Result<()> {
                 new_start: 1,
                 new_lines: 1,
             }],
+            mode_flags: None,
         }];
         gitbutler_branch_actions::amend(ctx, stack_entry.id, commit_oid, to_amend).unwrap();
 
@@ -184,12 +187,13 @@ fn non_existing_ownership() {
                 new_start: 1,
                 new_lines: 1,
             }],
+            mode_flags: None,
         }];
         assert_eq!(
             gitbutler_branch_actions::amend(ctx, stack_entry.id, commit_oid, to_amend)
                 .unwrap_err()
                 .to_string(),
-            r#"Failed to amend with commit engine. Rejected specs: [(NoEffectiveChanges, DiffSpec { previous_path: None, path: "file2.txt", hunk_headers: [HunkHeader("-1,0", "+1,1")] })]"#,
+            r#"Failed to amend with commit engine. Rejected specs: [(NoEffectiveChanges, DiffSpec { previous_path: None, path: "file2.txt", hunk_headers: [HunkHeader("-1,0", "+1,1")], mode_flags: None })]"#,
         );
     }
 }
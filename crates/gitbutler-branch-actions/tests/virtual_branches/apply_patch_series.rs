@@ -0,0 +1,148 @@
+use gitbutler_branch_actions::PatchApplyMode;
+use gitbutler_testsupport::stack_details;
+
+use super::*;
+
+const PATCH: &str = "From 0123456789012345678901234567890123456789 Mon Sep 17 00:00:00 2001
+From: Jane Doe <jane@example.com>
+Date: Tue, 5 Mar 2024 10:00:00 +0100
+Subject: [PATCH 1/1] add greeting
+
+Say hello to everyone.
+---
+ greeting.txt | 1 +
+ 1 file changed, 1 insertion(+)
+
+diff --git a/greeting.txt b/greeting.txt
+new file mode 100644
+index 0000000..ce01362
+--- /dev/null
++++ b/greeting.txt
+@@ -0,0 +1 @@
++hello
+-- 
+2.45.0
+
+";
+
+fn stack_for_patches(ctx: &Context) -> anyhow::Result<StackId> {
+    gitbutler_branch_actions::set_base_branch(
+        ctx,
+        &"refs/remotes/origin/master".parse()?,
+        ctx.exclusive_worktree_access().write_permission(),
+    )?;
+    Ok(gitbutler_branch_actions::create_virtual_branch(
+        ctx,
+        &BranchCreateRequest {
+            name: Some("patches".to_string()),
+            ..Default::default()
+        },
+        ctx.exclusive_worktree_access().write_permission(),
+    )?
+    .id)
+}
+
+#[test]
+fn commit_per_patch_with_author_of_mail() -> anyhow::Result<()> {
+    let Test { repo, ctx, .. } = &Test::default();
+    let stack_id = stack_for_patches(ctx)?;
+    let patch_dir = tempfile::tempdir()?;
+    let patch_file = patch_dir.path().join("0001-add-greeting.patch");
+    fs::write(&patch_file, PATCH)?;
+
+    let outcome = gitbutler_branch_actions::apply_patch_series(
+        ctx,
+        stack_id,
+        vec![patch_file],
+        PatchApplyMode::Commit,
+    )?;
+    assert_eq!(outcome.patches.len(), 1);
+    assert_eq!(outcome.patches[0].subject, "add greeting");
+    let new_commit = outcome.patches[0]
+        .new_commit
+        .expect("commits are created in this mode");
+
+    let commit = repo.find_commit(new_commit.to_git2())?;
+    assert_eq!(
+        commit.message(),
+        Some("add greeting\n\nSay hello to everyone.\n")
+    );
+    assert_eq!(commit.author().name(), Some("Jane Doe"));
+    assert_eq!(commit.author().email(), Some("jane@example.com"));
+    assert_eq!(commit.author().when().seconds(), 1_709_629_200);
+
+    let stacks = stack_details(ctx);
+    assert_eq!(stacks[0].1.branch_details[0].commits[0].id, new_commit);
+    assert_eq!(
+        fs::read_to_string(repo.path().join("greeting.txt"))?,
+        "hello\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn review_first_in_worktree() -> anyhow::Result<()> {
+    let Test { repo, ctx, .. } = &Test::default();
+    let stack_id = stack_for_patches(ctx)?;
+    let unrelated = repo.path().join("unrelated.txt");
+    fs::write(&unrelated, "untouched")?;
+    let mtime = std::time::SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_000_000_000);
+    fs::File::options()
+        .write(true)
+        .open(&unrelated)?
+        .set_modified(mtime)?;
+    let patch_dir = tempfile::tempdir()?;
+    let patch_file = patch_dir.path().join("greeting.diff");
+    // Plain diffs, like those of `git diff`, work as well.
+    fs::write(
+        &patch_file,
+        "--- /dev/null\n+++ b/greeting.txt\n@@ -0,0 +1 @@\n+hello\n",
+    )?;
+
+    let outcome = gitbutler_branch_actions::apply_patch_series(
+        ctx,
+        stack_id,
+        vec![patch_file],
+        PatchApplyMode::Worktree,
+    )?;
+    assert_eq!(outcome.patches[0].subject, "Apply greeting.diff");
+    assert_eq!(outcome.patches[0].new_commit, None);
+    assert_eq!(outcome.worktree_changes.len(), 1);
+    assert_eq!(
+        fs::read_to_string(repo.path().join("greeting.txt"))?,
+        "hello\n"
+    );
+    assert_eq!(
+        fs::metadata(&unrelated)?.modified()?,
+        mtime,
+        "files the patch doesn't change aren't written"
+    );
+    assert!(stack_details(ctx)[0].1.branch_details[0].commits.is_empty());
+    Ok(())
+}
+
+#[test]
+fn nothing_changes_if_a_patch_does_not_apply() -> anyhow::Result<()> {
+    let Test { repo, ctx, .. } = &Test::default();
+    let stack_id = stack_for_patches(ctx)?;
+    let patch_dir = tempfile::tempdir()?;
+    let good = patch_dir.path().join("0001-add-greeting.patch");
+    fs::write(&good, PATCH)?;
+    let bad = patch_dir.path().join("missing.diff");
+    fs::write(
+        &bad,
+        "--- a/missing.txt\n+++ b/missing.txt\n@@ -1 +1 @@\n-old\n+new\n",
+    )?;
+
+    let err = gitbutler_branch_actions::apply_patch_series(
+        ctx,
+        stack_id,
+        vec![good, bad],
+        PatchApplyMode::Commit,
+    )
+    .unwrap_err();
+    assert!(format!("{err:#}").contains("'missing.txt' doesn't exist"));
+    assert!(stack_details(ctx)[0].1.branch_details[0].commits.is_empty());
+    assert!(!repo.path().join("greeting.txt").exists());
+    Ok(())
+}
@@ -2,7 +2,9 @@ use std::collections::HashMap;
 
 use but_forge::ForgeReview;
 use gitbutler_branch::BranchCreateRequest;
-use gitbutler_branch_actions::upstream_integration::{BranchStatus, StackStatuses, TreeStatus};
+use gitbutler_branch_actions::upstream_integration::{
+    BranchStatus, Resolution, ResolutionApproach, StackStatuses, TreeStatus,
+};
 use gitbutler_reference::Refname;
 use gitbutler_testsupport::stack_details;
 
@@ -281,6 +283,94 @@ fn upstream_integration_status_with_merged_pr() {
     }
 }
 
+#[test]
+fn upstream_integration_with_squash_merged_branch() {
+    let Test { repo, ctx, .. } = &Test::default();
+
+    // Setup: the remote moves on, then receives the squashed changes of the branch created below.
+    {
+        fs::write(repo.path().join("file.txt"), "initial").unwrap();
+        let first_commit_oid = repo.commit_all("initial commit");
+        fs::write(repo.path().join("file.txt"), "second").unwrap();
+        repo.commit_all("second commit");
+        fs::write(repo.path().join("feature-file.txt"), "feature work").unwrap();
+        fs::write(repo.path().join("more-feature.txt"), "more feature work").unwrap();
+        repo.commit_all("feature-branch (#1)");
+        repo.push();
+        repo.reset_hard(Some(first_commit_oid));
+    }
+
+    gitbutler_branch_actions::set_base_branch(
+        ctx,
+        &"refs/remotes/origin/master".parse().unwrap(),
+        ctx.exclusive_worktree_access().write_permission(),
+    )
+    .unwrap();
+
+    let stack_id = {
+        let stack_entry = gitbutler_branch_actions::create_virtual_branch(
+            ctx,
+            &BranchCreateRequest {
+                name: Some("feature-branch".to_string()),
+                ..Default::default()
+            },
+            ctx.exclusive_worktree_access().write_permission(),
+        )
+        .unwrap();
+
+        fs::write(repo.path().join("feature-file.txt"), "feature work").unwrap();
+        super::create_commit(ctx, stack_entry.id, "feature commit").unwrap();
+        fs::write(repo.path().join("more-feature.txt"), "more feature work").unwrap();
+        super::create_commit(ctx, stack_entry.id, "more feature commit").unwrap();
+
+        stack_entry.id
+    };
+
+    let statuses =
+        gitbutler_branch_actions::upstream_integration_statuses(ctx, None, &HashMap::new())
+            .unwrap();
+    let StackStatuses::UpdatesRequired { statuses, .. } = statuses else {
+        panic!("Expected UpdatesRequired status");
+    };
+    assert_eq!(statuses.len(), 1);
+    assert_eq!(
+        statuses[0].1.branch_statuses[0].status,
+        BranchStatus::Integrated,
+        "the commit ids differ, but the changes of the branch landed in a single commit"
+    );
+
+    gitbutler_branch_actions::integrate_upstream(
+        ctx,
+        &[Resolution {
+            stack_id,
+            approach: ResolutionApproach::Rebase,
+            delete_integrated_branches: true,
+        }],
+        None,
+        &HashMap::new(),
+    )
+    .unwrap();
+    assert!(
+        ctx.git2_repo
+            .get()
+            .unwrap()
+            .find_branch("feature-branch", git2::BranchType::Local)
+            .is_err(),
+        "the integrated branch was deleted as requested"
+    );
+    assert!(
+        stack_details(ctx).iter().all(|(_, details)| details
+            .branch_details
+            .iter()
+            .all(|branch| branch.commits.is_empty())),
+        "the squash-merged commits were removed from the stack"
+    );
+    assert_eq!(
+        fs::read_to_string(repo.path().join("more-feature.txt")).unwrap(),
+        "more feature work"
+    );
+}
+
 #[test]
 fn upstream_integration_status_with_merged_pr_mismatched_head() {
     let Test { repo, ctx, .. } = &Test::default();
@@ -0,0 +1,52 @@
+use gitbutler_reference::LocalRefname;
+
+use super::*;
+
+#[test]
+fn local_and_remote_commits_per_branch() -> anyhow::Result<()> {
+    let Test { repo, ctx, .. } = &Test::default();
+
+    gitbutler_branch_actions::set_base_branch(
+        ctx,
+        &"refs/remotes/origin/master".parse()?,
+        ctx.exclusive_worktree_access().write_permission(),
+    )?;
+    let stack_entry = gitbutler_branch_actions::create_virtual_branch(
+        ctx,
+        &BranchCreateRequest {
+            name: Some("feature".to_string()),
+            ..Default::default()
+        },
+        ctx.exclusive_worktree_access().write_permission(),
+    )?;
+    fs::write(repo.path().join("one.txt"), "one")?;
+    let commit1_id = super::create_commit(ctx, stack_entry.id, "commit one")?;
+
+    let divergence = gitbutler_branch_actions::branch_divergence(ctx, stack_entry.id)?;
+    assert_eq!(divergence.len(), 1);
+    assert_eq!(divergence[0].name, "feature");
+    assert_eq!(divergence[0].remote_tracking_branch, None, "not pushed yet");
+    assert_eq!(divergence[0].local_only, [commit1_id.to_gix()]);
+    assert!(divergence[0].remote_only.is_empty());
+    assert_eq!(divergence[0].common_ancestor, None);
+
+    fs::write(repo.path().join("two.txt"), "two")?;
+    let commit2_id = super::create_commit(ctx, stack_entry.id, "commit two")?;
+    repo.push_branch(&LocalRefname::new("feature", None));
+    gitbutler_branch_actions::undo_commit(ctx, stack_entry.id, commit2_id)?;
+    let commit3_id = super::create_commit(ctx, stack_entry.id, "commit three")?;
+
+    let divergence = gitbutler_branch_actions::branch_divergence(ctx, stack_entry.id)?;
+    assert_eq!(
+        divergence[0].remote_tracking_branch.as_deref(),
+        Some("refs/remotes/origin/feature")
+    );
+    assert_eq!(divergence[0].local_only, [commit3_id.to_gix()]);
+    assert_eq!(
+        divergence[0].remote_only,
+        [commit2_id.to_gix()],
+        "the commit that was replaced locally is still on the remote"
+    );
+    assert_eq!(divergence[0].common_ancestor, Some(commit1_id.to_gix()));
+    Ok(())
+}
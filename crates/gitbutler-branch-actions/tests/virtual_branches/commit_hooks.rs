@@ -0,0 +1,91 @@
+use gitbutler_branch::BranchCreateRequest;
+use gitbutler_branch_actions::hooks::create_commit_with_hooks;
+use gitbutler_testsupport::stack_details;
+
+use super::*;
+
+/// Commit all worktree changes to the stack with `stack_id` with hooks.
+fn commit_with_hooks(
+    ctx: &Context,
+    stack_id: StackId,
+    message: &str,
+) -> anyhow::Result<Option<gix::ObjectId>> {
+    let worktree_changes = {
+        let repo = ctx.repo.get()?;
+        but_core::diff::worktree_changes(&repo)?
+            .changes
+            .iter()
+            .map(Into::into)
+            .collect()
+    };
+    let (_, details) = stack_details(ctx)
+        .into_iter()
+        .find(|(id, _)| *id == stack_id)
+        .expect("stack exists");
+    let outcome = create_commit_with_hooks(
+        ctx,
+        stack_id,
+        None,
+        worktree_changes,
+        message.to_owned(),
+        details.branch_details[0].name.to_string(),
+        ctx.exclusive_worktree_access().write_permission(),
+    )?;
+    Ok(outcome.new_commit)
+}
+
+fn setup(test: &Test) -> anyhow::Result<StackId> {
+    let Test { repo, ctx, .. } = test;
+    gitbutler_branch_actions::set_base_branch(
+        ctx,
+        &"refs/remotes/origin/master".parse()?,
+        ctx.exclusive_worktree_access().write_permission(),
+    )?;
+    let stack = gitbutler_branch_actions::create_virtual_branch(
+        ctx,
+        &BranchCreateRequest::default(),
+        ctx.exclusive_worktree_access().write_permission(),
+    )?;
+    fs::write(repo.path().join("file.txt"), "content")?;
+
+    let hook = b"#!/bin/sh\necho 'rejected'\nexit 1\n";
+    git2_hooks::create_hook(&repo.local_repo, git2_hooks::HOOK_PRE_COMMIT, hook);
+    Ok(stack.id)
+}
+
+#[test]
+fn failing_pre_commit_hook_prevents_commit() -> anyhow::Result<()> {
+    let test = Test::default();
+    let stack_id = setup(&test)?;
+
+    let err = commit_with_hooks(&test.ctx, stack_id, "commit").unwrap_err();
+    assert!(
+        format!("{err:#}").contains("The pre-commit hook failed:\nrejected"),
+        "{err:#}"
+    );
+    let (_, details) = stack_details(&test.ctx)
+        .into_iter()
+        .find(|(id, _)| *id == stack_id)
+        .expect("stack exists");
+    assert!(
+        details.branch_details[0].commits.is_empty(),
+        "nothing was committed"
+    );
+    Ok(())
+}
+
+#[test]
+fn hooks_can_be_disabled_with_git_config() -> anyhow::Result<()> {
+    let test = Test::default();
+    let stack_id = setup(&test)?;
+    test.repo
+        .local_repo
+        .config()?
+        .set_bool("gitbutler.runHooks", false)?;
+
+    assert!(
+        commit_with_hooks(&test.ctx, stack_id, "commit")?.is_some(),
+        "the failing hook didn't run"
+    );
+    Ok(())
+}
@@ -0,0 +1,62 @@
+use super::*;
+
+#[test]
+fn one_series_per_branch_with_cover_letter() -> anyhow::Result<()> {
+    let Test { repo, ctx, .. } = &Test::default();
+    // Configure this before the repository is opened, so its configuration contains the description.
+    git2::Repository::open(repo.path())?.config()?.set_str(
+        "branch.feature.description",
+        "Make files great\n\nThis adds a file and changes it.",
+    )?;
+
+    gitbutler_branch_actions::set_base_branch(
+        ctx,
+        &"refs/remotes/origin/master".parse()?,
+        ctx.exclusive_worktree_access().write_permission(),
+    )?;
+    let stack_entry = gitbutler_branch_actions::create_virtual_branch(
+        ctx,
+        &BranchCreateRequest {
+            name: Some("feature".to_string()),
+            ..Default::default()
+        },
+        ctx.exclusive_worktree_access().write_permission(),
+    )?;
+    fs::write(repo.path().join("file.txt"), "content\n")?;
+    super::create_commit(ctx, stack_entry.id, "add file")?;
+    fs::write(repo.path().join("file.txt"), "changed content\n")?;
+    super::create_commit(ctx, stack_entry.id, "change file: for real")?;
+    let out_dir = tempfile::tempdir()?;
+    let series = gitbutler_branch_actions::export_patches(ctx, stack_entry.id, out_dir.path())?;
+    assert_eq!(series.len(), 1);
+    let series = &series[0];
+    assert_eq!(series.branch_name, "feature");
+    let series_dir = out_dir.path().join("feature");
+    assert_eq!(
+        series.cover_letter,
+        series_dir.join("0000-cover-letter.patch")
+    );
+    assert_eq!(
+        series.patches,
+        [
+            series_dir.join("0001-add-file.patch"),
+            series_dir.join("0002-change-file-for-real.patch")
+        ]
+    );
+
+    let cover_letter = fs::read_to_string(&series.cover_letter)?;
+    assert!(cover_letter.contains("Subject: [PATCH 0/2] Make files great\n"));
+    assert!(cover_letter.contains("This adds a file and changes it."));
+
+    let first = fs::read_to_string(&series.patches[0])?;
+    assert!(first.contains("Subject: [PATCH 1/2] add file\n"));
+    assert!(first.contains("diff --git a/file.txt b/file.txt\nnew file mode 100644\n"));
+    assert!(first.contains("--- /dev/null\n+++ b/file.txt\n"));
+    assert!(first.contains("+content\n"));
+
+    let second = fs::read_to_string(&series.patches[1])?;
+    assert!(second.contains("Subject: [PATCH 2/2] change file: for real\n"));
+    assert!(second.contains("--- a/file.txt\n+++ b/file.txt\n"));
+    assert!(second.contains("-content\n+changed content\n"));
+    Ok(())
+}
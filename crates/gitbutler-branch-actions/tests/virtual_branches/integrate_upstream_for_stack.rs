@@ -0,0 +1,161 @@
+use gitbutler_branch_actions::branch_upstream_integration::IntegrationStrategy;
+use gitbutler_reference::LocalRefname;
+use gitbutler_testsupport::stack_details;
+
+use super::*;
+
+/// Create a stack whose `feature` branch has `commit one` and `commit three` locally, while its remote tracking branch
+/// has `commit one` and `commit two`, returning the id of the stack.
+fn stack_with_diverged_branch(repo: &TestProject, ctx: &Context) -> anyhow::Result<StackId> {
+    gitbutler_branch_actions::set_base_branch(
+        ctx,
+        &"refs/remotes/origin/master".parse()?,
+        ctx.exclusive_worktree_access().write_permission(),
+    )?;
+    let stack_entry = gitbutler_branch_actions::create_virtual_branch(
+        ctx,
+        &BranchCreateRequest {
+            name: Some("feature".to_string()),
+            ..Default::default()
+        },
+        ctx.exclusive_worktree_access().write_permission(),
+    )?;
+    fs::write(repo.path().join("one.txt"), "one")?;
+    super::create_commit(ctx, stack_entry.id, "commit one")?;
+    fs::write(repo.path().join("two.txt"), "two\n")?;
+    let commit2_id = super::create_commit(ctx, stack_entry.id, "commit two")?;
+    repo.push_branch(&LocalRefname::new("feature", None));
+
+    gitbutler_branch_actions::undo_commit(ctx, stack_entry.id, commit2_id)?;
+    fs::remove_file(repo.path().join("two.txt"))?;
+    fs::write(repo.path().join("three.txt"), "three")?;
+    super::create_commit(ctx, stack_entry.id, "commit three")?;
+    Ok(stack_entry.id)
+}
+
+fn commit_messages(ctx: &Context) -> Vec<String> {
+    let stacks = stack_details(ctx);
+    assert_eq!(stacks.len(), 1);
+    stacks[0].1.branch_details[0]
+        .commits
+        .iter()
+        .map(|commit| commit.message.to_string())
+        .collect()
+}
+
+#[test]
+fn rebase_puts_local_commits_on_top() -> anyhow::Result<()> {
+    let Test { repo, ctx, .. } = &Test::default();
+    let stack_id = stack_with_diverged_branch(repo, ctx)?;
+
+    gitbutler_branch_actions::integrate_upstream_for_stack(
+        ctx,
+        stack_id,
+        IntegrationStrategy::Rebase,
+    )?;
+    assert_eq!(
+        commit_messages(ctx),
+        ["commit three", "commit two", "commit one"]
+    );
+    assert!(repo.path().join("two.txt").exists());
+    assert!(repo.path().join("three.txt").exists());
+    Ok(())
+}
+
+#[test]
+fn hard_reset_drops_local_commits() -> anyhow::Result<()> {
+    let Test { repo, ctx, .. } = &Test::default();
+    let stack_id = stack_with_diverged_branch(repo, ctx)?;
+
+    gitbutler_branch_actions::integrate_upstream_for_stack(
+        ctx,
+        stack_id,
+        IntegrationStrategy::HardReset,
+    )?;
+    assert_eq!(commit_messages(ctx), ["commit two", "commit one"]);
+    assert!(!repo.path().join("three.txt").exists());
+    Ok(())
+}
+
+#[test]
+fn merge_keeps_local_commits() -> anyhow::Result<()> {
+    let Test { repo, ctx, .. } = &Test::default();
+    let stack_id = stack_with_diverged_branch(repo, ctx)?;
+
+    gitbutler_branch_actions::integrate_upstream_for_stack(
+        ctx,
+        stack_id,
+        IntegrationStrategy::Merge,
+    )?;
+    let messages = commit_messages(ctx);
+    assert_eq!(messages[0], "Merge `origin/feature` into `feature`");
+    assert!(messages.contains(&"commit three".to_string()));
+    assert!(repo.path().join("two.txt").exists());
+    assert!(repo.path().join("three.txt").exists());
+    Ok(())
+}
+
+#[test]
+fn rebase_leaves_files_that_do_not_change_untouched() -> anyhow::Result<()> {
+    let Test { repo, ctx, .. } = &Test::default();
+    let stack_id = stack_with_diverged_branch(repo, ctx)?;
+    let uncommitted = repo.path().join("one.txt");
+    fs::write(&uncommitted, "one, changed")?;
+    let mtime = std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_000_000_000);
+    fs::File::options()
+        .write(true)
+        .open(&uncommitted)?
+        .set_modified(mtime)?;
+
+    gitbutler_branch_actions::integrate_upstream_for_stack(
+        ctx,
+        stack_id,
+        IntegrationStrategy::Rebase,
+    )?;
+    assert_eq!(fs::read_to_string(repo.path().join("two.txt"))?, "two\n");
+    assert_eq!(fs::read_to_string(&uncommitted)?, "one, changed");
+    assert_eq!(
+        fs::metadata(&uncommitted)?.modified()?,
+        mtime,
+        "files that are the same before and after aren't written again"
+    );
+    Ok(())
+}
+
+#[test]
+fn rebase_writes_conflict_markers_into_conflicting_uncommitted_files() -> anyhow::Result<()> {
+    let Test { repo, ctx, .. } = &Test::default();
+    let stack_id = stack_with_diverged_branch(repo, ctx)?;
+    fs::write(repo.path().join("two.txt"), "mine\n")?;
+
+    gitbutler_branch_actions::integrate_upstream_for_stack(
+        ctx,
+        stack_id,
+        IntegrationStrategy::Rebase,
+    )?;
+    let two = fs::read_to_string(repo.path().join("two.txt"))?;
+    assert!(
+        two.contains("<<<<<<<") && two.contains("mine") && two.contains("two"),
+        "the in-memory merge can't write conflicts, so the merged index is checked out: {two:?}"
+    );
+    Ok(())
+}
+
+#[test]
+fn rebase_converts_incoming_files_as_configured_by_attributes() -> anyhow::Result<()> {
+    let Test { repo, ctx, .. } = &Test::default();
+    let stack_id = stack_with_diverged_branch(repo, ctx)?;
+    fs::write(repo.path().join(".gitattributes"), "*.txt text eol=crlf\n")?;
+
+    gitbutler_branch_actions::integrate_upstream_for_stack(
+        ctx,
+        stack_id,
+        IntegrationStrategy::Rebase,
+    )?;
+    assert_eq!(
+        fs::read_to_string(repo.path().join("two.txt"))?,
+        "two\r\n",
+        "files with conversion attributes are checked out from the merged index like Git would"
+    );
+    Ok(())
+}
@@ -0,0 +1,68 @@
+use std::collections::HashMap;
+
+use gitbutler_branch_actions::integrated_stacks::IntegrationReason;
+use gitbutler_oplog::entry::OperationKind;
+use gitbutler_testsupport::stack_details;
+
+use super::*;
+
+#[test]
+fn squash_merged_stacks_are_detected_and_archived() -> anyhow::Result<()> {
+    let Test { repo, ctx, .. } = &Test::default();
+
+    // The remote receives the squashed changes of the stack that is yet to be created, but we don't see it yet.
+    let base = ctx.git2_repo.get()?.head()?.peel_to_commit()?.id();
+    fs::write(repo.path().join("feature.txt"), "feature")?;
+    fs::write(repo.path().join("more.txt"), "more")?;
+    repo.commit_all("squash-merge feature");
+    repo.push();
+    repo.reset_hard(Some(base));
+    ctx.git2_repo
+        .get()?
+        .reference("refs/remotes/origin/master", base, true, "reset")?;
+
+    gitbutler_branch_actions::set_base_branch(
+        ctx,
+        &"refs/remotes/origin/master".parse()?,
+        ctx.exclusive_worktree_access().write_permission(),
+    )?;
+    let stack_entry = gitbutler_branch_actions::create_virtual_branch(
+        ctx,
+        &BranchCreateRequest::default(),
+        ctx.exclusive_worktree_access().write_permission(),
+    )?;
+    fs::write(repo.path().join("feature.txt"), "feature")?;
+    super::create_commit(ctx, stack_entry.id, "feature")?;
+    fs::write(repo.path().join("more.txt"), "more")?;
+    super::create_commit(ctx, stack_entry.id, "more")?;
+
+    assert!(
+        gitbutler_branch_actions::detect_integrated_stacks(ctx, &HashMap::new())?.is_empty(),
+        "the squash-merge wasn't fetched yet"
+    );
+
+    repo.fetch();
+    let integrated = gitbutler_branch_actions::detect_integrated_stacks(ctx, &HashMap::new())?;
+    assert_eq!(integrated.len(), 1);
+    assert_eq!(integrated[0].stack_id, stack_entry.id);
+    assert_eq!(integrated[0].reason, IntegrationReason::SquashMerged);
+
+    gitbutler_branch_actions::archive_stack(ctx, stack_entry.id)?;
+    assert!(stack_details(ctx).is_empty(), "the stack was removed");
+    assert!(
+        ctx.git2_repo
+            .get()?
+            .find_branch(&integrated[0].name, git2::BranchType::Local)
+            .is_err(),
+        "its branch was deleted"
+    );
+    let snapshots = ctx.list_snapshots(2, None, Vec::new(), None)?;
+    assert!(
+        snapshots.iter().any(|snapshot| snapshot
+            .details
+            .as_ref()
+            .is_some_and(|details| details.operation == OperationKind::ArchiveBranch)),
+        "archiving is recorded in the oplog, along with the unapply it implies"
+    );
+    Ok(())
+}
@@ -66,16 +66,28 @@ impl Test {
     }
 }
 
+mod adopt_external_commits;
 mod amend;
+mod apply_patch_series;
 mod apply_virtual_branch;
+mod branch_divergence;
+mod commit_hooks;
 mod create_virtual_branch_from_branch;
+mod export_patches;
 mod init;
+mod integrate_upstream_for_stack;
+mod integrated_stacks;
 mod list;
 mod list_details;
 mod move_commit_to_vbranch;
 mod oplog;
+mod push_stack_all;
+mod revert_commit;
 mod save_and_unapply_virtual_branch;
 mod set_base_branch;
+mod stack_bundle;
+mod stack_identity;
+mod stack_notes;
 mod unapply_without_saving_virtual_branch;
 mod undo_commit;
 mod update_commit_message;
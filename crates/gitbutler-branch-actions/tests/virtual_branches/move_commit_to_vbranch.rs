@@ -544,7 +544,9 @@ fn no_branch() {
     assert_eq!(
         gitbutler_branch_actions::move_commit(ctx, id, commit_oid, source_branch_id)
             .unwrap_err()
-            .to_string(),
-        "Destination branch not found"
+            .downcast_ref::<but_error::ButError>(),
+        Some(&but_error::ButError::StackNotFound {
+            stack_id: id.to_string()
+        })
     );
 }
@@ -1,8 +1,11 @@
 use std::{io::Write, path::Path};
 
-use but_oxidize::ObjectIdExt;
+use but_oxidize::{ObjectIdExt, OidExt};
 use gitbutler_branch::BranchCreateRequest;
-use gitbutler_oplog::OplogExt;
+use gitbutler_oplog::{
+    OplogExt,
+    entry::{OperationKind, SnapshotDetails},
+};
 use gitbutler_stack::VirtualBranchesHandle;
 use gitbutler_testsupport::stack_details;
 use itertools::Itertools;
@@ -341,6 +344,65 @@ fn restores_gitbutler_workspace() -> anyhow::Result<()> {
 }
 
 // test operations-log.toml head is not a commit
+#[test]
+fn restore_stack_leaves_other_stacks_alone() -> anyhow::Result<()> {
+    let Test { repo, ctx, .. } = &Test::default();
+
+    gitbutler_branch_actions::set_base_branch(
+        ctx,
+        &"refs/remotes/origin/master".parse()?,
+        ctx.exclusive_worktree_access().write_permission(),
+    )?;
+
+    let mut stack_ids = Vec::new();
+    for name in ["a", "b"] {
+        let stack_entry = gitbutler_branch_actions::create_virtual_branch(
+            ctx,
+            &BranchCreateRequest {
+                name: Some(name.into()),
+                ..Default::default()
+            },
+            ctx.exclusive_worktree_access().write_permission(),
+        )?;
+        fs::write(repo.path().join(format!("{name}.txt")), "one")?;
+        super::create_commit(ctx, stack_entry.id, &format!("{name} one"))?;
+        stack_ids.push(stack_entry.id);
+    }
+    let (stack_a, stack_b) = (stack_ids[0], stack_ids[1]);
+
+    let snapshot = ctx.list_snapshots(1, None, Vec::new(), None)?[0].commit_id;
+    let vb_state = VirtualBranchesHandle::new(ctx.project_data_dir());
+    let head_a_before = vb_state.get_stack(stack_a)?.head_oid(ctx)?;
+
+    for name in ["a", "b"] {
+        fs::write(repo.path().join(format!("{name}.txt")), "two")?;
+        let stack_id = if name == "a" { stack_a } else { stack_b };
+        super::create_commit(ctx, stack_id, &format!("{name} two"))?;
+    }
+    let head_b_after = vb_state.get_stack(stack_b)?.head_oid(ctx)?;
+
+    let mut guard = ctx.exclusive_worktree_access();
+    ctx.restore_stack(snapshot, stack_a, guard.write_permission())?;
+    drop(guard);
+
+    assert_eq!(
+        vb_state.get_stack(stack_a)?.head_oid(ctx)?,
+        head_a_before,
+        "the restored stack is back to its first commit"
+    );
+    assert_eq!(
+        vb_state.get_stack(stack_b)?.head_oid(ctx)?,
+        head_b_after,
+        "the other stack keeps its second commit"
+    );
+    assert_eq!(
+        fs::read_to_string(repo.path().join("a.txt"))?,
+        "two",
+        "the worktree isn't touched"
+    );
+    Ok(())
+}
+
 #[test]
 fn head_corrupt_is_recreated_automatically() {
     let Test { repo, ctx, .. } = &Test::default();
@@ -424,3 +486,149 @@ fn first_snapshot_diff_works() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn file_history_includes_rewritten_commits() -> anyhow::Result<()> {
+    let Test { repo, ctx, .. } = &Test::default();
+
+    gitbutler_branch_actions::set_base_branch(
+        ctx,
+        &"refs/remotes/origin/master".parse()?,
+        ctx.exclusive_worktree_access().write_permission(),
+    )?;
+
+    let stack_entry = gitbutler_branch_actions::create_virtual_branch(
+        ctx,
+        &BranchCreateRequest::default(),
+        ctx.exclusive_worktree_access().write_permission(),
+    )?;
+
+    fs::write(repo.path().join("file.txt"), "content")?;
+    let original = super::create_commit(ctx, stack_entry.id, "add file")?;
+    fs::write(repo.path().join("other.txt"), "content")?;
+    super::create_commit(ctx, stack_entry.id, "add other file")?;
+
+    // Rewording rewrites the commit and rebases the one on top, after taking a snapshot.
+    let reworded = gitbutler_branch_actions::update_commit_message(
+        ctx,
+        stack_entry.id,
+        original,
+        "add file (reworded)",
+    )?;
+
+    let guard = ctx.shared_worktree_access();
+    let history = ctx.file_history("file.txt".into(), guard.read_permission())?;
+    assert_eq!(history.len(), 1, "both versions belong to the same change");
+    let entry = &history[0];
+    assert_eq!(entry.title, "add file (reworded)");
+    assert_eq!(entry.stack_id, stack_entry.id);
+    assert_eq!(
+        entry
+            .versions
+            .iter()
+            .map(|v| (v.commit_id, v.is_current))
+            .collect::<Vec<_>>(),
+        [(original.to_gix(), false), (reworded.to_gix(), true)],
+        "the original version comes first and isn't in the workspace anymore"
+    );
+    assert!(
+        entry.versions[0].snapshot_id.is_some(),
+        "the original version was recorded in a snapshot"
+    );
+
+    let history = ctx.file_history("other.txt".into(), guard.read_permission())?;
+    assert_eq!(
+        history[0].versions.len(),
+        2,
+        "the commit on top was rebased, but without changing the file"
+    );
+    Ok(())
+}
+
+#[test]
+fn automatic_pruning_hides_old_snapshots_but_keeps_ids_and_explicit_pruning_compacts()
+-> anyhow::Result<()> {
+    let Test { ctx, .. } = &Test::new_with_settings(|settings| {
+        settings.oplog.max_snapshots = 2;
+    });
+    assert!(
+        ctx.settings().oplog.auto_prune,
+        "pruning is automatic by default"
+    );
+    gitbutler_branch_actions::set_base_branch(
+        ctx,
+        &"refs/remotes/origin/master".parse()?,
+        ctx.exclusive_worktree_access().write_permission(),
+    )?;
+
+    let mut guard = ctx.exclusive_worktree_access();
+    ctx.create_named_snapshot("keep me", guard.write_permission())?;
+    for _ in 0..3 {
+        ctx.create_snapshot(
+            SnapshotDetails::new(OperationKind::OnDemandSnapshot),
+            guard.write_permission(),
+        )?;
+    }
+    let snapshot_ids = |ctx: &Context| -> anyhow::Result<Vec<git2::Oid>> {
+        Ok(ctx
+            .list_snapshots(100, None, Vec::new(), None)?
+            .into_iter()
+            .map(|snapshot| snapshot.commit_id)
+            .collect())
+    };
+    let before = snapshot_ids(ctx)?;
+    assert_eq!(
+        before.len(),
+        4,
+        "pruning happened with the first snapshot, and isn't due again yet"
+    );
+
+    make_pruning_due(ctx)?;
+    ctx.create_snapshot(
+        SnapshotDetails::new(OperationKind::OnDemandSnapshot),
+        guard.write_permission(),
+    )?;
+    let after_auto_prune = snapshot_ids(ctx)?;
+    assert_eq!(
+        after_auto_prune[1..],
+        [before[0], before[3]],
+        "the most recent snapshots and the named one are kept with the same ids"
+    );
+
+    let outcome = ctx.prune_snapshots(guard.write_permission())?;
+    assert_eq!(outcome.snapshots_kept, 3);
+    assert_eq!(
+        outcome.snapshots_pruned, 0,
+        "the snapshots were already pruned automatically"
+    );
+    let snapshots = ctx.list_snapshots(100, None, Vec::new(), None)?;
+    assert_eq!(snapshots.len(), 3, "the two most recent and the named one");
+    assert_ne!(
+        snapshots[0].commit_id, after_auto_prune[0],
+        "snapshots are rewritten so pruned ones become unreachable"
+    );
+    assert_eq!(
+        snapshots[2]
+            .details
+            .as_ref()
+            .and_then(|details| details.label.as_deref()),
+        Some("keep me")
+    );
+    assert_eq!(
+        ctx.git2_repo
+            .get()?
+            .find_commit(snapshots[2].commit_id)?
+            .parent_count(),
+        0,
+        "the chain of snapshots is cut after the oldest one that is kept"
+    );
+    Ok(())
+}
+
+/// Forget when snapshots were last pruned, so they are pruned automatically with the next snapshot.
+fn make_pruning_due(ctx: &Context) -> anyhow::Result<()> {
+    let path = ctx.project_data_dir().join("operations-log.toml");
+    let state = std::fs::read_to_string(&path)?;
+    std::fs::write(&path, state.replace("pruned_at", "previously_pruned_at"))?;
+    Ok(())
+}
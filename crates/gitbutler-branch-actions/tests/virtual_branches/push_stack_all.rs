@@ -0,0 +1,149 @@
+use gitbutler_branch_actions::stack::{
+    CreateSeriesRequest, RefPushStatus, StackPushOutcome, push_stack_all,
+};
+use gitbutler_project::AuthKey;
+use gitbutler_stack::VirtualBranchesHandle;
+
+use super::*;
+
+#[test]
+fn atomic_push_updates_all_branches() -> anyhow::Result<()> {
+    let Test { repo, ctx, .. } = &mut Test::default();
+    let (stack_id, branches) = stack_of_three(repo, ctx)?;
+
+    let outcome = push_stack_all(ctx, stack_id, false)?;
+    assert!(outcome.atomic, "local remotes support atomic pushes");
+    assert_eq!(branch_names(&outcome), branches);
+    assert!(
+        outcome
+            .refs
+            .iter()
+            .all(|r| r.status == RefPushStatus::Pushed)
+    );
+
+    let remote = remote_repo(ctx)?;
+    let stack = VirtualBranchesHandle::new(ctx.project_data_dir()).get_stack(stack_id)?;
+    for (pushed, branch) in outcome.refs.iter().zip(&stack.heads) {
+        assert_eq!(remote_tip(&remote, &pushed.branch_name), Some(pushed.after));
+        assert_eq!(
+            branch.remote_head,
+            Some(pushed.after),
+            "it remembers what it pushed"
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn fallback_push_stops_at_first_failure() -> anyhow::Result<()> {
+    let Test { repo, ctx, .. } = &mut Test::default();
+    let (stack_id, branches) = stack_of_three(repo, ctx)?;
+
+    // Someone else pushed an unrelated commit to the middle branch, so ours doesn't fast-forward.
+    let remote = remote_repo(ctx)?;
+    let unrelated = {
+        let signature = git2::Signature::now("other", "other@example.com")?;
+        let tree = remote.find_tree(remote.treebuilder(None)?.write()?)?;
+        remote.commit(
+            Some(&format!("refs/heads/{}", branches[1])),
+            &signature,
+            &signature,
+            "unrelated",
+            &tree,
+            &[],
+        )?
+    };
+    // Only the Git executable pushes atomically.
+    ctx.legacy_project.preferred_key = AuthKey::GitCredentialsHelper;
+
+    let outcome = push_stack_all(ctx, stack_id, false)?;
+    assert!(!outcome.atomic);
+    assert_eq!(branch_names(&outcome), branches);
+    assert_eq!(outcome.refs[0].status, RefPushStatus::Pushed);
+    assert!(
+        matches!(outcome.refs[1].status, RefPushStatus::Failed(_)),
+        "{:?}",
+        outcome.refs[1].status
+    );
+    assert_eq!(outcome.refs[1].before, Some(unrelated.to_gix()));
+    assert_eq!(
+        outcome.refs[2].status,
+        RefPushStatus::NotAttempted,
+        "it depends on the branch below that failed"
+    );
+
+    assert_eq!(
+        remote_tip(&remote, &branches[0]),
+        Some(outcome.refs[0].after)
+    );
+    assert_eq!(remote_tip(&remote, &branches[1]), Some(unrelated.to_gix()));
+    assert_eq!(remote_tip(&remote, &branches[2]), None);
+
+    let stack = VirtualBranchesHandle::new(ctx.project_data_dir()).get_stack(stack_id)?;
+    let remote_heads: Vec<_> = stack.heads.iter().map(|b| b.remote_head).collect();
+    assert_eq!(
+        remote_heads,
+        [Some(outcome.refs[0].after), None, None],
+        "only what was pushed is remembered"
+    );
+    Ok(())
+}
+
+/// Create a stack with three branches that have one commit each, and return its id along with the branch names,
+/// from the bottom of the stack to the top.
+fn stack_of_three(repo: &TestProject, ctx: &Context) -> anyhow::Result<(StackId, Vec<String>)> {
+    gitbutler_branch_actions::set_base_branch(
+        ctx,
+        &"refs/remotes/origin/master".parse()?,
+        ctx.exclusive_worktree_access().write_permission(),
+    )?;
+    let stack_entry = gitbutler_branch_actions::create_virtual_branch(
+        ctx,
+        &BranchCreateRequest::default(),
+        ctx.exclusive_worktree_access().write_permission(),
+    )?;
+    let mut branches = vec![
+        stack_entry
+            .name()
+            .expect("the stack has a branch")
+            .to_string(),
+    ];
+    fs::write(repo.path().join("bottom.txt"), "bottom")?;
+    super::create_commit(ctx, stack_entry.id, "bottom")?;
+
+    for name in ["middle", "top"] {
+        gitbutler_branch_actions::stack::create_branch(
+            ctx,
+            stack_entry.id,
+            CreateSeriesRequest {
+                name: name.into(),
+                target_patch: None,
+                preceding_head: None,
+            },
+        )?;
+        fs::write(repo.path().join(format!("{name}.txt")), name)?;
+        super::create_commit(ctx, stack_entry.id, name)?;
+        branches.push(name.into());
+    }
+    Ok((stack_entry.id, branches))
+}
+
+fn branch_names(outcome: &StackPushOutcome) -> Vec<String> {
+    outcome.refs.iter().map(|r| r.branch_name.clone()).collect()
+}
+
+fn remote_repo(ctx: &Context) -> anyhow::Result<git2::Repository> {
+    let git2_repo = ctx.git2_repo.get()?;
+    let remote = git2_repo.find_remote("origin")?;
+    Ok(git2::Repository::open(
+        remote.url().expect("the test remote is a path"),
+    )?)
+}
+
+fn remote_tip(remote: &git2::Repository, branch_name: &str) -> Option<gix::ObjectId> {
+    remote
+        .find_reference(&format!("refs/heads/{branch_name}"))
+        .ok()
+        .and_then(|r| r.target())
+        .map(|id| id.to_gix())
+}
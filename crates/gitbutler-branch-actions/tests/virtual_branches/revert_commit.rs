@@ -0,0 +1,118 @@
+use std::path::Path;
+
+use gitbutler_branch::BranchCreateRequest;
+use gitbutler_branch_actions::RevertMode;
+use gitbutler_testsupport::stack_details;
+
+use super::*;
+
+/// Create a stack with two commits changing `file.txt`, and return its id along with the id of the second commit.
+fn stack_with_two_commits(
+    repo: &gitbutler_testsupport::TestProject,
+    ctx: &Context,
+) -> anyhow::Result<(StackId, git2::Oid)> {
+    gitbutler_branch_actions::set_base_branch(
+        ctx,
+        &"refs/remotes/origin/master".parse().unwrap(),
+        ctx.exclusive_worktree_access().write_permission(),
+    )?;
+    let stack_entry = gitbutler_branch_actions::create_virtual_branch(
+        ctx,
+        &BranchCreateRequest::default(),
+        ctx.exclusive_worktree_access().write_permission(),
+    )?;
+
+    fs::write(repo.path().join("file.txt"), "one\n")?;
+    super::create_commit(ctx, stack_entry.id, "commit one")?;
+    fs::write(repo.path().join("file.txt"), "one\ntwo\n")?;
+    let commit2_id = super::create_commit(ctx, stack_entry.id, "commit two")?;
+    Ok((stack_entry.id, commit2_id))
+}
+
+fn commit_messages(ctx: &Context, stack_id: StackId) -> Vec<String> {
+    let (_, details) = stack_details(ctx)
+        .into_iter()
+        .find(|d| d.0 == stack_id)
+        .unwrap();
+    details.branch_details[0]
+        .commits
+        .iter()
+        .map(|c| c.message.to_string())
+        .collect()
+}
+
+#[test]
+fn as_commit() -> anyhow::Result<()> {
+    let Test { repo, ctx, .. } = &Test::default();
+    let (stack_id, commit2_id) = stack_with_two_commits(repo, ctx)?;
+
+    let outcome = gitbutler_branch_actions::revert_commit(
+        ctx,
+        stack_id,
+        commit2_id.to_gix(),
+        RevertMode::Commit,
+        ctx.exclusive_worktree_access().write_permission(),
+    )?;
+    assert!(outcome.new_commit.is_some());
+    assert!(!outcome.conflicted);
+    assert!(outcome.worktree_changes.is_empty());
+
+    let messages = commit_messages(ctx, stack_id);
+    assert_eq!(
+        messages[0],
+        format!("Revert \"commit two\"\n\nThis reverts commit {commit2_id}.\n"),
+        "the revert is placed on top of the stack"
+    );
+    assert_eq!(messages[1..], ["commit two", "commit one"]);
+    assert_eq!(fs::read_to_string(repo.path().join("file.txt"))?, "one\n");
+    let changes = but_core::diff::worktree_changes(&*ctx.repo.get()?)?.changes;
+    assert!(changes.is_empty(), "the revert was committed");
+    Ok(())
+}
+
+#[test]
+fn into_worktree() -> anyhow::Result<()> {
+    let Test { repo, ctx, .. } = &Test::default();
+    let (stack_id, commit2_id) = stack_with_two_commits(repo, ctx)?;
+    fs::write(repo.path().join("staged.txt"), "staged\n")?;
+    {
+        let git2_repo = git2::Repository::open(repo.path())?;
+        let mut index = git2_repo.index()?;
+        index.add_path(Path::new("staged.txt"))?;
+        index.write()?;
+    }
+
+    let outcome = gitbutler_branch_actions::revert_commit(
+        ctx,
+        stack_id,
+        commit2_id.to_gix(),
+        RevertMode::Worktree,
+        ctx.exclusive_worktree_access().write_permission(),
+    )?;
+    assert_eq!(outcome.new_commit, None);
+    assert!(!outcome.conflicted);
+    let reverted_paths: Vec<_> = outcome
+        .worktree_changes
+        .iter()
+        .map(|c| c.path.to_string())
+        .collect();
+    assert_eq!(reverted_paths, ["file.txt"]);
+
+    assert_eq!(
+        commit_messages(ctx, stack_id),
+        ["commit two", "commit one"],
+        "the stack itself is unchanged"
+    );
+    assert_eq!(fs::read_to_string(repo.path().join("file.txt"))?, "one\n");
+    assert_eq!(
+        fs::read_to_string(repo.path().join("staged.txt"))?,
+        "staged\n",
+        "unrelated changes are kept"
+    );
+    let index = git2::Repository::open(repo.path())?.index()?;
+    assert!(
+        index.get_path(Path::new("staged.txt"), 0).is_some(),
+        "the staged state of other files is untouched"
+    );
+    Ok(())
+}
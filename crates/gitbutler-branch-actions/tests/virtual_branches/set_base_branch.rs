@@ -12,6 +12,154 @@ fn success() {
     .unwrap();
 }
 
+#[test]
+fn local_target_in_repository_without_commits() -> anyhow::Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let repo = git2::Repository::init(tmp.path())?;
+    {
+        let mut config = repo.config()?;
+        config.set_str("user.name", "Author")?;
+        config.set_str("user.email", "author@example.com")?;
+    }
+    fs::write(tmp.path().join("file.txt"), "content")?;
+    let data_dir = paths::data_dir();
+    let project =
+        gitbutler_project::add_at_app_data_dir(data_dir.as_ref(), tmp.path())?.unwrap_project();
+    let ctx = Context::new_from_legacy_project_and_settings(&project, AppSettings::default());
+
+    let base = gitbutler_branch_actions::set_base_branch(
+        &ctx,
+        &"refs/remotes/./main".parse()?,
+        ctx.exclusive_worktree_access().write_permission(),
+    )?;
+    assert_eq!(base.branch_name, "./main");
+    assert_eq!(base.remote_url, "", "the remote can be configured later");
+
+    let main = repo.find_reference("refs/heads/main")?.peel_to_commit()?;
+    assert_eq!(
+        main.parent_count(),
+        0,
+        "the base branch was created with an initial commit"
+    );
+    assert_eq!(base.base_sha, main.id());
+    assert_eq!(repo.head()?.name(), Some("refs/heads/gitbutler/workspace"));
+    Ok(())
+}
+
+#[test]
+fn remote_target_in_repository_without_commits() -> anyhow::Result<()> {
+    let signature = git2::Signature::now("Author", "author@example.com")?;
+    let remote_tmp = tempfile::tempdir()?;
+    let remote_repo = git2::Repository::init(remote_tmp.path())?;
+    let remote_tree = {
+        let mut builder = remote_repo.treebuilder(None)?;
+        builder.insert(
+            "file.txt",
+            remote_repo.blob(b"remote content")?,
+            git2::FileMode::Blob.into(),
+        )?;
+        remote_repo.find_tree(builder.write()?)?
+    };
+    let remote_head = remote_repo.commit(
+        Some("refs/heads/main"),
+        &signature,
+        &signature,
+        "remote commit",
+        &remote_tree,
+        &[],
+    )?;
+
+    let tmp = tempfile::tempdir()?;
+    let repo = git2::Repository::init(tmp.path())?;
+    {
+        let mut config = repo.config()?;
+        config.set_str("user.name", "Author")?;
+        config.set_str("user.email", "author@example.com")?;
+    }
+    repo.remote("origin", remote_tmp.path().to_str().unwrap())?
+        .fetch(&["refs/heads/main:refs/remotes/origin/main"], None, None)?;
+    let unborn_branch = repo
+        .find_reference("HEAD")?
+        .symbolic_target()
+        .expect("HEAD is symbolic")
+        .to_owned();
+
+    let data_dir = paths::data_dir();
+    let project =
+        gitbutler_project::add_at_app_data_dir(data_dir.as_ref(), tmp.path())?.unwrap_project();
+    let ctx = Context::new_from_legacy_project_and_settings(&project, AppSettings::default());
+    let base = gitbutler_branch_actions::set_base_branch(
+        &ctx,
+        &"refs/remotes/origin/main".parse()?,
+        ctx.exclusive_worktree_access().write_permission(),
+    )?;
+    assert_eq!(base.branch_name, "origin/main");
+    assert_eq!(base.base_sha, remote_head);
+
+    assert_eq!(
+        repo.find_reference(&unborn_branch)?.target(),
+        Some(remote_head),
+        "the unborn branch starts at the target, like after a clone"
+    );
+    assert_eq!(
+        fs::read_to_string(tmp.path().join("file.txt"))?,
+        "remote content",
+        "the target was checked out"
+    );
+    assert_eq!(repo.head()?.name(), Some("refs/heads/gitbutler/workspace"));
+    Ok(())
+}
+
+#[test]
+fn workspace_is_not_shared_with_linked_worktrees() -> anyhow::Result<()> {
+    let Test {
+        repo,
+        ctx,
+        data_dir,
+        ..
+    } = &Test::default();
+    gitbutler_branch_actions::set_base_branch(
+        ctx,
+        &"refs/remotes/origin/master".parse()?,
+        ctx.exclusive_worktree_access().write_permission(),
+    )?;
+
+    let worktree_tmp = tempfile::tempdir()?;
+    let worktree_dir = worktree_tmp.path().join("linked");
+    repo.local_repo.worktree("linked", &worktree_dir, None)?;
+    let project = gitbutler_project::add_at_app_data_dir(
+        data_dir.as_ref().expect("kept until drop"),
+        &worktree_dir,
+    )?
+    .unwrap_project();
+    let linked_ctx =
+        Context::new_from_legacy_project_and_settings(&project, AppSettings::default());
+    let err = gitbutler_branch_actions::set_base_branch(
+        &linked_ctx,
+        &"refs/remotes/origin/master".parse()?,
+        linked_ctx.exclusive_worktree_access().write_permission(),
+    )
+    .unwrap_err();
+    assert!(
+        err.to_string()
+            .starts_with("The GitButler workspace is already checked out in the worktree at"),
+        "{err:#}"
+    );
+
+    let linked_repo = git2::Repository::open(&worktree_dir)?;
+    assert_eq!(
+        linked_repo.head()?.name(),
+        Some("refs/heads/linked"),
+        "the linked worktree stays on its branch"
+    );
+    assert_eq!(
+        repo.local_repo.head()?.name(),
+        Some("refs/heads/gitbutler/workspace"),
+        "the workspace of the main worktree is untouched"
+    );
+    Ok(())
+}
+
 mod error {
     use gitbutler_reference::RemoteRefname;
 
@@ -28,8 +176,10 @@ mod error {
                 ctx.exclusive_worktree_access().write_permission(),
             )
             .unwrap_err()
-            .to_string(),
-            "remote branch 'refs/remotes/origin/missing' not found"
+            .downcast_ref::<but_error::ButError>(),
+            Some(&but_error::ButError::BranchNotFound {
+                name: "refs/remotes/origin/missing".into()
+            })
         );
     }
 }
@@ -225,3 +375,113 @@ mod go_back_to_workspace {
         assert_eq!(base_two, base);
     }
 }
+
+mod switch {
+    use gitbutler_branch::BranchCreateRequest;
+    use gitbutler_branch_actions::base_switch::StackSwitchStatus;
+    use gitbutler_reference::LocalRefname;
+    use gitbutler_testsupport::stack_details;
+
+    use super::*;
+
+    /// Push a `release` branch that changes `file.txt` to `content`, while `master` stays as is.
+    fn push_release_branch(repo: &TestProject, content: &str) {
+        let branch_name: LocalRefname = "refs/heads/release".parse().unwrap();
+        repo.checkout(&branch_name);
+        fs::write(repo.path().join("file.txt"), content).unwrap();
+        repo.commit_all("release");
+        repo.push_branch(&branch_name);
+        repo.checkout(&"refs/heads/master".parse().unwrap());
+    }
+
+    #[test]
+    fn stacks_are_rebased_onto_the_new_base() {
+        let Test { repo, ctx, .. } = &Test::default();
+        push_release_branch(repo, "release");
+
+        gitbutler_branch_actions::set_base_branch(
+            ctx,
+            &"refs/remotes/origin/master".parse().unwrap(),
+            ctx.exclusive_worktree_access().write_permission(),
+        )
+        .unwrap();
+        let stack_entry = gitbutler_branch_actions::create_virtual_branch(
+            ctx,
+            &BranchCreateRequest::default(),
+            ctx.exclusive_worktree_access().write_permission(),
+        )
+        .unwrap();
+        fs::write(repo.path().join("feature.txt"), "feature").unwrap();
+        super::create_commit(ctx, stack_entry.id, "feature").unwrap();
+
+        let outcome = gitbutler_branch_actions::switch_base_branch(
+            ctx,
+            &"refs/remotes/origin/release".parse().unwrap(),
+            false,
+        )
+        .unwrap();
+        assert_eq!(outcome.base_branch.branch_name, "origin/release");
+        assert_eq!(outcome.stacks.len(), 1);
+        assert_eq!(outcome.stacks[0].stack_id, stack_entry.id);
+        assert_eq!(outcome.stacks[0].status, StackSwitchStatus::Rebased);
+
+        let stacks = stack_details(ctx);
+        assert_eq!(stacks.len(), 1, "the stack is still applied");
+        assert_eq!(stacks[0].1.branch_details[0].commits.len(), 1);
+        assert_eq!(
+            fs::read_to_string(repo.path().join("file.txt")).unwrap(),
+            "release",
+            "the worktree now contains the new base"
+        );
+        assert_eq!(
+            fs::read_to_string(repo.path().join("feature.txt")).unwrap(),
+            "feature",
+            "…along with the commits of the stack"
+        );
+    }
+
+    #[test]
+    fn incompatible_stacks_abort_the_switch_if_requested() {
+        let Test { repo, ctx, .. } = &Test::default();
+        push_release_branch(repo, "release");
+
+        gitbutler_branch_actions::set_base_branch(
+            ctx,
+            &"refs/remotes/origin/master".parse().unwrap(),
+            ctx.exclusive_worktree_access().write_permission(),
+        )
+        .unwrap();
+        let stack_entry = gitbutler_branch_actions::create_virtual_branch(
+            ctx,
+            &BranchCreateRequest::default(),
+            ctx.exclusive_worktree_access().write_permission(),
+        )
+        .unwrap();
+        fs::write(repo.path().join("file.txt"), "conflicting").unwrap();
+        super::create_commit(ctx, stack_entry.id, "conflicting").unwrap();
+
+        let err = gitbutler_branch_actions::switch_base_branch(
+            ctx,
+            &"refs/remotes/origin/release".parse().unwrap(),
+            true,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("would conflict"));
+
+        let base = gitbutler_branch_actions::base::get_base_branch_data(ctx).unwrap();
+        assert_eq!(base.branch_name, "origin/master", "nothing changed");
+        assert_eq!(stack_details(ctx).len(), 1);
+
+        let outcome = gitbutler_branch_actions::switch_base_branch(
+            ctx,
+            &"refs/remotes/origin/release".parse().unwrap(),
+            false,
+        )
+        .unwrap();
+        assert_eq!(outcome.stacks[0].status, StackSwitchStatus::Incompatible);
+        assert!(
+            stack_details(ctx).is_empty(),
+            "the incompatible stack was unapplied"
+        );
+    }
+}
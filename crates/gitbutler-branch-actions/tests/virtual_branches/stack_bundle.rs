@@ -0,0 +1,66 @@
+use but_core::DiffSpec;
+
+use super::*;
+
+#[test]
+fn export_writes_bundle_with_metadata() -> anyhow::Result<()> {
+    let Test { repo, ctx, .. } = &Test::default();
+    git2::Repository::open(repo.path())?
+        .config()?
+        .set_str("branch.feature.description", "Make files great")?;
+
+    gitbutler_branch_actions::set_base_branch(
+        ctx,
+        &"refs/remotes/origin/master".parse()?,
+        ctx.exclusive_worktree_access().write_permission(),
+    )?;
+    let stack_entry = gitbutler_branch_actions::create_virtual_branch(
+        ctx,
+        &BranchCreateRequest {
+            name: Some("feature".to_string()),
+            ..Default::default()
+        },
+        ctx.exclusive_worktree_access().write_permission(),
+    )?;
+    fs::write(repo.path().join("file.txt"), "content\n")?;
+    super::create_commit(ctx, stack_entry.id, "add file")?;
+    fs::write(repo.path().join("file.txt"), "changed content\n")?;
+
+    let out_dir = tempfile::tempdir()?;
+    let bundle_path = out_dir.path().join("feature.bundle");
+    let metadata = gitbutler_branch_actions::export_stack_bundle(
+        ctx,
+        stack_entry.id,
+        &bundle_path,
+        vec![DiffSpec {
+            previous_path: None,
+            path: "file.txt".into(),
+            hunk_headers: Vec::new(),
+        }],
+    )?;
+    assert!(bundle_path.is_file());
+    assert_eq!(metadata.branches.len(), 1);
+    assert_eq!(metadata.branches[0].name, "feature");
+    assert_eq!(
+        metadata.branches[0].description.as_deref(),
+        Some("Make files great")
+    );
+    assert!(
+        metadata
+            .uncommitted_changes
+            .contains("--- a/file.txt\n+++ b/file.txt\n")
+    );
+    assert!(
+        metadata
+            .uncommitted_changes
+            .contains("-content\n+changed content\n")
+    );
+
+    let err =
+        gitbutler_branch_actions::import_stack_bundle(ctx, &bundle_path, &metadata).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Branch 'feature' already exists, delete or rename it to import the stack"
+    );
+    Ok(())
+}
@@ -0,0 +1,106 @@
+use but_core::{
+    DiffSpec,
+    ref_metadata::{Identity, StackIdentity},
+};
+use but_workspace::{commit_engine::Destination, legacy::commit_engine};
+use gitbutler_branch_actions::{StackOrder, reorder::SeriesOrder};
+use gitbutler_testsupport::stack_details;
+
+use super::*;
+
+#[test]
+fn amend_and_reorder_commit_as_the_stack_identity() -> anyhow::Result<()> {
+    let Test { repo, ctx, .. } = &Test::default();
+
+    gitbutler_branch_actions::set_base_branch(
+        ctx,
+        &"refs/remotes/origin/master".parse()?,
+        ctx.exclusive_worktree_access().write_permission(),
+    )?;
+    let stack_entry = gitbutler_branch_actions::create_virtual_branch(
+        ctx,
+        &BranchCreateRequest::default(),
+        ctx.exclusive_worktree_access().write_permission(),
+    )?;
+    let identity = |name: &str| Identity {
+        name: name.into(),
+        email: format!("{name}@example.com"),
+    };
+    gitbutler_branch_actions::stack::set_stack_identity(
+        ctx,
+        stack_entry.id,
+        Some(StackIdentity {
+            author: Some(identity("stack author")),
+            committer: Some(identity("stack committer")),
+        }),
+    )?;
+
+    fs::write(repo.path().join("file one.txt"), "")?;
+    let commit_one = super::create_commit(ctx, stack_entry.id, "commit one")?;
+    fs::write(repo.path().join("file two.txt"), "")?;
+    super::create_commit(ctx, stack_entry.id, "commit two")?;
+
+    // Amends only know the commit, and have to find the stack by themselves.
+    fs::write(repo.path().join("file one.txt"), "amended")?;
+    let outcome = commit_engine::create_commit_and_update_refs_with_project(
+        &*ctx.repo.get()?,
+        &ctx.project_data_dir(),
+        None,
+        Destination::AmendCommit {
+            commit_id: commit_one.to_gix(),
+            new_message: None,
+        },
+        vec![DiffSpec {
+            previous_path: None,
+            path: "file one.txt".into(),
+            hunk_headers: vec![],
+            mode_flags: None,
+        }],
+        3,
+        ctx.exclusive_worktree_access().write_permission(),
+    )?;
+    assert!(
+        outcome.new_commit.is_some(),
+        "amend failed: {:?}",
+        outcome.rejected_specs
+    );
+    let (branch_name, commits) = commits_by_stack_identity(ctx, stack_entry.id)?;
+    assert_eq!(commits.len(), 2);
+
+    gitbutler_branch_actions::reorder_stack(
+        ctx,
+        stack_entry.id,
+        StackOrder {
+            series: vec![SeriesOrder {
+                name: branch_name,
+                commit_ids: commits.iter().rev().map(|id| id.to_git2()).collect(),
+            }],
+        },
+    )?;
+    let (_, reordered) = commits_by_stack_identity(ctx, stack_entry.id)?;
+    assert_ne!(reordered, commits, "the commits were rewritten");
+    Ok(())
+}
+
+/// Return the name of the only branch in the stack with `stack_id` and its commits, newest first,
+/// after asserting that all of them were authored and committed as the identity of the stack.
+fn commits_by_stack_identity(
+    ctx: &Context,
+    stack_id: StackId,
+) -> anyhow::Result<(String, Vec<gix::ObjectId>)> {
+    let (_, details) = stack_details(ctx)
+        .into_iter()
+        .find(|(id, _)| *id == stack_id)
+        .expect("the stack exists");
+    let branch = &details.branch_details[0];
+    let repo = ctx.repo.get()?;
+    for commit in &branch.commits {
+        let commit = repo.find_commit(commit.id)?;
+        assert_eq!(commit.author()?.name, "stack author");
+        assert_eq!(commit.committer()?.name, "stack committer");
+    }
+    Ok((
+        branch.name.to_string(),
+        branch.commits.iter().map(|commit| commit.id).collect(),
+    ))
+}
@@ -0,0 +1,62 @@
+use gitbutler_stack::VirtualBranchesHandle;
+
+use super::*;
+
+#[test]
+fn commit_notes_follow_rewritten_commits() -> anyhow::Result<()> {
+    let Test { repo, ctx, .. } = &Test::default();
+
+    gitbutler_branch_actions::set_base_branch(
+        ctx,
+        &"refs/remotes/origin/master".parse()?,
+        ctx.exclusive_worktree_access().write_permission(),
+    )?;
+    let stack_entry = gitbutler_branch_actions::create_virtual_branch(
+        ctx,
+        &BranchCreateRequest::default(),
+        ctx.exclusive_worktree_access().write_permission(),
+    )?;
+    fs::write(repo.path().join("file one.txt"), "")?;
+    let commit_one = super::create_commit(ctx, stack_entry.id, "commit one")?;
+    fs::write(repo.path().join("file two.txt"), "")?;
+    let commit_two = super::create_commit(ctx, stack_entry.id, "commit two")?;
+
+    gitbutler_branch_actions::stack::set_stack_description(
+        ctx,
+        stack_entry.id,
+        Some("All about files".into()),
+    )?;
+    gitbutler_branch_actions::stack::set_commit_note(
+        ctx,
+        commit_one.to_gix(),
+        Some("The first file".into()),
+    )?;
+    gitbutler_branch_actions::stack::set_commit_note(
+        ctx,
+        commit_two.to_gix(),
+        Some("The second file".into()),
+    )?;
+
+    let new_commit_one = gitbutler_branch_actions::update_commit_message(
+        ctx,
+        stack_entry.id,
+        commit_one,
+        "commit one updated",
+    )?;
+
+    let notes = VirtualBranchesHandle::new(ctx.project_data_dir())
+        .get_stack(stack_entry.id)?
+        .stack_notes;
+    assert_eq!(notes.description.as_deref(), Some("All about files"));
+    assert_eq!(notes.commit_note(&commit_one.to_gix()), None);
+    assert_eq!(
+        notes.commit_note(&new_commit_one.to_gix()),
+        Some("The first file")
+    );
+    assert_eq!(
+        notes.commits.len(),
+        2,
+        "the rewritten second commit keeps its note"
+    );
+    Ok(())
+}
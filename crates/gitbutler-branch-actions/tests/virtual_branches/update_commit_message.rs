@@ -176,6 +176,7 @@ fn forcepush_allowed() {
         stack_entry.name().map(|n| n.to_string()).unwrap(),
         false, // run_hooks
         vec![],
+        vec![],
     )
     .unwrap();
 
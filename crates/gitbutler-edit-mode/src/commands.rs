@@ -7,12 +7,16 @@ use gitbutler_oplog::{
     entry::{OperationKind, SnapshotDetails},
 };
 
-use crate::ConflictEntryPresence;
+use crate::{ConflictEntryPresence, MergeToolOutcome};
 
+/// Check out `commit_oid` so it can be edited, even if it's in the middle of a stack.
+///
+/// If `stack_id` is `None`, the stack is looked up from the commit.
+/// The uncommitted changes and hunk assignments of the workspace are kept aside until edit mode is left.
 pub fn enter_edit_mode(
     ctx: &Context,
     commit_oid: git2::Oid,
-    stack_id: StackId,
+    stack_id: Option<StackId>,
 ) -> Result<EditModeMetadata> {
     let mut guard = ctx.exclusive_worktree_access();
 
@@ -40,6 +44,8 @@ pub fn enter_edit_mode(
     Ok(edit_mode_metadata)
 }
 
+/// Amend the edited commit with the changes in the worktree, rebase its descendants in all stacks
+/// that contain it, and return to the workspace.
 pub fn save_and_return_to_workspace(ctx: &Context) -> Result<()> {
     let mut guard = ctx.exclusive_worktree_access();
 
@@ -56,10 +62,27 @@ pub fn abort_and_return_to_workspace(ctx: &Context) -> Result<()> {
     crate::abort_and_return_to_workspace(ctx, guard.write_permission())
 }
 
+/// Resolve the conflicts of the file at `path` in the commit with `commit_oid`, which is being edited, with the merge tool
+/// configured for `git mergetool`.
+///
+/// The result of the tool is left in the worktree, so it becomes part of the commit when the edit is saved.
+pub fn resolve_with_mergetool(
+    ctx: &Context,
+    commit_oid: git2::Oid,
+    path: &std::path::Path,
+) -> Result<MergeToolOutcome> {
+    let mut guard = ctx.exclusive_worktree_access();
+
+    ensure_edit_mode(ctx)
+        .context("Conflicts may only be resolved with a merge tool in edit mode")?;
+
+    crate::mergetool::resolve_with_mergetool(ctx, commit_oid, path, guard.write_permission())
+}
+
 pub fn starting_index_state(
     ctx: &Context,
 ) -> Result<Vec<(TreeChange, Option<ConflictEntryPresence>)>> {
-    let guard = ctx.exclusive_worktree_access();
+    let guard = ctx.shared_worktree_access();
 
     ensure_edit_mode(ctx)?;
 
@@ -68,7 +91,7 @@ pub fn starting_index_state(
 }
 
 pub fn changes_from_initial(ctx: &Context) -> Result<Vec<TreeChange>> {
-    let guard = ctx.exclusive_worktree_access();
+    let guard = ctx.shared_worktree_access();
 
     ensure_edit_mode(ctx)?;
 
@@ -2,7 +2,11 @@ use std::collections::HashMap;
 
 use anyhow::{Context as _, Result, bail};
 use bstr::{BString, ByteSlice};
-use but_core::{RepositoryExt, TreeChange, commit::Headers, ref_metadata::StackId};
+use but_core::{
+    RepositoryExt, TreeChange,
+    commit::{Headers, TreeKind},
+    ref_metadata::StackId,
+};
 use but_ctx::{
     Context,
     access::{WorktreeReadPermission, WorktreeWritePermission},
@@ -18,13 +22,16 @@ use gitbutler_operating_modes::{
     read_edit_mode_metadata, write_edit_mode_metadata,
 };
 use gitbutler_repo::{RepositoryExt as _, SignaturePurpose, signature};
-use gitbutler_stack::VirtualBranchesHandle;
+use gitbutler_stack::{Stack, VirtualBranchesHandle};
 use gitbutler_workspace::branch_trees::{WorkspaceState, update_uncommitted_changes_with_tree};
 use serde::Serialize;
 
 pub mod commands;
+mod mergetool;
+pub use mergetool::MergeToolOutcome;
 
 const UNCOMMITTED_CHANGES_REF: &str = "refs/gitbutler/edit-uncommitted-changes";
+const ASSIGNMENTS_FILE: &str = "edit_mode_assignments.json";
 
 /// Returns an index of the tree of `commit` if it is unconflicted, *or* produce a merged tree
 /// if `commit` is conflicted. That tree is turned into an index that records the conflicts that occurred
@@ -174,22 +181,85 @@ fn checkout_edit_branch(ctx: &Context, commit: git2::Commit) -> Result<()> {
     Ok(())
 }
 
+/// Returns all stacks in the workspace which contain `commit_id`, along with their rebase steps.
+fn stacks_containing_commit(
+    ctx: &Context,
+    repo: &gix::Repository,
+    commit_id: gix::ObjectId,
+) -> Result<Vec<(Stack, Vec<but_rebase::RebaseStep>)>> {
+    let vb_state = VirtualBranchesHandle::new(ctx.project_data_dir());
+    let mut stacks = Vec::new();
+    for stack in vb_state.list_stacks_in_workspace()? {
+        let steps = stack.as_rebase_steps(ctx, repo)?;
+        let contains_commit = steps.iter().any(|step| {
+            matches!(step, but_rebase::RebaseStep::Pick { commit_id: id, .. } if *id == commit_id)
+        });
+        if contains_commit {
+            stacks.push((stack, steps));
+        }
+    }
+    Ok(stacks)
+}
+
+/// Remember the hunk assignments of the workspace, so they can be restored when leaving edit mode.
+fn save_assignments(ctx: &Context) -> Result<()> {
+    let assignments = ctx.db.get()?.hunk_assignments().list_all()?;
+    but_fs::write(
+        ctx.project_data_dir().join(ASSIGNMENTS_FILE),
+        serde_json::to_string(&assignments)?,
+    )
+    .context("Failed to write hunk assignments")?;
+    Ok(())
+}
+
+/// Restore the hunk assignments that were saved when entering edit mode, if any.
+fn restore_assignments(ctx: &Context) -> Result<()> {
+    let path = ctx.project_data_dir().join(ASSIGNMENTS_FILE);
+    let assignments = match std::fs::read_to_string(&path) {
+        Ok(assignments) => assignments,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(err).context("Failed to read hunk assignments"),
+    };
+    let assignments: Vec<but_db::HunkAssignment> =
+        serde_json::from_str(&assignments).context("Failed to parse hunk assignments")?;
+    let mut db = ctx.db.get_mut()?;
+    db.hunk_assignments_mut()?.set_all(assignments)?;
+    std::fs::remove_file(&path)?;
+    Ok(())
+}
+
 pub(crate) fn enter_edit_mode(
     ctx: &Context,
     commit: git2::Commit,
-    stack_id: StackId,
+    stack_id: Option<StackId>,
     _perm: &mut WorktreeWritePermission,
 ) -> Result<EditModeMetadata> {
+    let stack_id = match stack_id {
+        Some(stack_id) => {
+            // Validate the stack_id
+            VirtualBranchesHandle::new(ctx.project_data_dir()).get_stack_in_workspace(stack_id)?;
+            stack_id
+        }
+        None => {
+            let repo = ctx.repo.get()?;
+            stacks_containing_commit(ctx, &repo, commit.id().to_gix())?
+                .first()
+                .map(|(stack, _)| stack.id)
+                .with_context(|| {
+                    format!(
+                        "Commit {} isn't part of any stack in the workspace",
+                        commit.id()
+                    )
+                })?
+        }
+    };
     let edit_mode_metadata = EditModeMetadata {
         commit_oid: commit.id(),
         stack_id,
     };
 
-    let vb_state = VirtualBranchesHandle::new(ctx.project_data_dir());
-    // Validate the stack_id
-    vb_state.get_stack_in_workspace(stack_id)?;
-
     commit_uncommited_changes(ctx)?;
+    save_assignments(ctx)?;
     write_edit_mode_metadata(ctx, &edit_mode_metadata).context("Failed to persist metadata")?;
     checkout_edit_branch(ctx, commit).context("Failed to checkout edit branch")?;
 
@@ -214,6 +284,7 @@ pub(crate) fn abort_and_return_to_workspace(
         uncommited_changes.as_object(),
         Some(CheckoutBuilder::new().force().remove_untracked(true)),
     )?;
+    restore_assignments(ctx)?;
 
     Ok(())
 }
@@ -236,8 +307,6 @@ pub(crate) fn save_and_return_to_workspace(
     let gix_commit = gix_repo.find_commit(commit.id().to_gix())?;
     let commit_obj = gix_commit.decode()?.into_owned()?;
 
-    let mut stack = vb_state.get_stack_in_workspace(edit_mode_metadata.stack_id)?;
-
     let parents = commit.parents().collect::<Vec<_>>();
 
     // Write out all the changes, including unstaged changes to a tree for re-committing
@@ -246,7 +315,32 @@ pub(crate) fn save_and_return_to_workspace(
     index.write()?;
     let tree = repository.create_wd_tree(0)?;
 
-    let (_, committer) = repository.signatures()?;
+    // Remember how the conflicts were resolved to resolve them the same way when they appear again.
+    if gix_commit.is_conflicted() {
+        let record = || -> Result<usize> {
+            let conflicted = but_core::Commit::from_id(gix_commit.id())?;
+            but_rebase::rerere::record_merge_resolutions(
+                gix_repo,
+                conflicted.tree_id_or_kind(TreeKind::Base)?.detach(),
+                conflicted.tree_id_or_kind(TreeKind::Ours)?.detach(),
+                conflicted.tree_id_or_kind(TreeKind::Theirs)?.detach(),
+                tree.id().to_gix(),
+            )
+        };
+        if let Err(err) = record() {
+            tracing::warn!(?err, "could not record conflict resolutions");
+        }
+    }
+
+    // The commit may be part of more than one stack, and all of them need their descendants rebased.
+    let affected_stacks = stacks_containing_commit(ctx, gix_repo, commit.id().to_gix())?;
+    let Some((first_stack, _)) = affected_stacks.first() else {
+        bail!(
+            "Commit {} isn't part of any stack in the workspace anymore",
+            commit.id()
+        );
+    };
+    let (_, committer) = first_stack.signatures(gix_repo)?;
     let commit_headers = Headers::try_from_commit(&commit_obj).map(|commit_headers| Headers {
         conflicted: None,
         ..commit_headers
@@ -267,22 +361,24 @@ pub(crate) fn save_and_return_to_workspace(
 
     let gix_repo = repository.to_gix_repo()?;
 
-    let mut steps = stack.as_rebase_steps(ctx, &gix_repo)?;
-    // swap out the old commit with the new, updated one
-    steps.iter_mut().for_each(|step| {
-        if let but_rebase::RebaseStep::Pick { commit_id, .. } = step
-            && commit.id() == commit_id.to_git2()
-        {
-            *commit_id = new_commit_oid.to_gix();
-        }
-    });
-    let merge_base = stack.merge_base(ctx)?;
-    let mut rebase = but_rebase::Rebase::new(&gix_repo, Some(merge_base), None)?;
-    rebase.rebase_noops(false);
-    rebase.steps(steps)?;
-    let output = rebase.rebase()?;
-
-    stack.set_heads_from_rebase_output(ctx, output.references)?;
+    for (mut stack, mut steps) in affected_stacks {
+        // swap out the old commit with the new, updated one
+        steps.iter_mut().for_each(|step| {
+            if let but_rebase::RebaseStep::Pick { commit_id, .. } = step
+                && commit.id() == commit_id.to_git2()
+            {
+                *commit_id = new_commit_oid.to_gix();
+            }
+        });
+        let merge_base = stack.merge_base(ctx)?;
+        let stack_repo = stack.repo_with_identity(&gix_repo)?;
+        let mut rebase = but_rebase::Rebase::new(&stack_repo, Some(merge_base), None)?;
+        rebase.rebase_noops(false);
+        rebase.steps(steps)?;
+        let output = rebase.rebase()?;
+
+        stack.set_heads_from_rebase_output(ctx, &output)?;
+    }
 
     // Switch branch to gitbutler/workspace
     repository
@@ -310,6 +406,8 @@ pub(crate) fn save_and_return_to_workspace(
     index.read_tree(&repository.head()?.peel_to_tree()?)?;
     index.write()?;
 
+    restore_assignments(ctx)?;
+
     Ok(())
 }
 
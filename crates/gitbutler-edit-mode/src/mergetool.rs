@@ -0,0 +1,150 @@
+//! Resolve conflicts of the commit in edit mode with the merge tool configured for `git mergetool`.
+//!
+//! The tool writes its result into the worktree, where it's picked up when the edit is saved,
+//! just like conflicts that were resolved by hand.
+use std::path::{Component, Path};
+
+use anyhow::{Context as _, Result, bail};
+use bstr::ByteSlice as _;
+use but_core::commit::TreeKind;
+use but_ctx::{Context, access::WorktreeWritePermission};
+use but_oxidize::OidExt as _;
+use gitbutler_operating_modes::read_edit_mode_metadata;
+use gix::prelude::ObjectIdExt as _;
+use serde::Serialize;
+
+/// What happened when a merge tool was used to resolve the conflicts of a file.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct MergeToolOutcome {
+    /// The name of the merge tool, as configured in `merge.tool`.
+    pub tool: String,
+    /// `true` if the tool resolved the conflicts and its result was kept in the worktree.
+    ///
+    /// Otherwise the file was restored to what it was before the tool was launched.
+    pub resolved: bool,
+}
+
+/// Launch the merge tool configured in `merge.tool` with its `mergetool.<tool>.cmd` to resolve the conflicts of
+/// the file at `path`, relative to the worktree, in the commit with `commit_id`, which must be the commit in edit mode.
+///
+/// Like `git mergetool`, the command is run by the shell with `$BASE`, `$LOCAL` and `$REMOTE` pointing to temporary files
+/// with the three versions of the file, and `$MERGED` pointing to the file in the worktree. Unless
+/// `mergetool.<tool>.trustExitCode` is set, the file counts as resolved if the tool changed it.
+pub(crate) fn resolve_with_mergetool(
+    ctx: &Context,
+    commit_id: git2::Oid,
+    path: &Path,
+    _perm: &mut WorktreeWritePermission,
+) -> Result<MergeToolOutcome> {
+    let metadata = read_edit_mode_metadata(ctx).context("Failed to read metadata")?;
+    if metadata.commit_oid != commit_id {
+        bail!("Commit {commit_id} must be in edit mode to resolve its conflicts with a merge tool");
+    }
+    if !path
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)))
+    {
+        bail!("Path '{}' must be relative to the worktree", path.display());
+    }
+
+    let repo = ctx.repo.get()?;
+    let commit = but_core::Commit::from_id(commit_id.to_gix().attach(&repo))?;
+    let Some(entries) = commit.conflict_entries()? else {
+        bail!("Commit {commit_id} isn't conflicted");
+    };
+    if !entries
+        .ancestor_entries
+        .iter()
+        .chain(&entries.our_entries)
+        .chain(&entries.their_entries)
+        .any(|entry| entry == path)
+    {
+        bail!(
+            "'{}' isn't conflicted in commit {commit_id}",
+            path.display()
+        );
+    }
+
+    let config = repo.config_snapshot();
+    let tool = config
+        .string("merge.tool")
+        .map(|tool| tool.to_str_lossy().into_owned())
+        .context("No merge tool is configured in 'merge.tool'")?;
+    let cmd = config
+        .string(format!("mergetool.{tool}.cmd").as_str())
+        .map(|cmd| cmd.to_str_lossy().into_owned())
+        .with_context(|| {
+            format!("Merge tool '{tool}' needs its command configured in 'mergetool.{tool}.cmd'")
+        })?;
+    let trust_exit_code = config
+        .boolean(format!("mergetool.{tool}.trustExitCode").as_str())
+        .unwrap_or(false);
+
+    // Name the versions like `git mergetool` does, keeping the extension so tools can highlight the syntax.
+    let tmp = tempfile::tempdir()?;
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let extension = path
+        .extension()
+        .map(|ext| format!(".{}", ext.to_string_lossy()))
+        .unwrap_or_default();
+    let mut versions = Vec::with_capacity(3);
+    for (kind, name) in [
+        (TreeKind::Base, "BASE"),
+        (TreeKind::Ours, "LOCAL"),
+        (TreeKind::Theirs, "REMOTE"),
+    ] {
+        let tree = commit.tree_id_or_kind(kind)?.object()?.into_tree();
+        let data = match tree.lookup_entry_by_path(path)? {
+            Some(entry) => entry.object()?.into_blob().take_data(),
+            None => Vec::new(),
+        };
+        let version_path = tmp.path().join(format!("{stem}_{name}{extension}"));
+        std::fs::write(&version_path, data)?;
+        versions.push((name, version_path));
+    }
+
+    let workdir = repo
+        .workdir()
+        .context("Merge tools need a repository with worktree")?;
+    let merged = workdir.join(path);
+    let previous = match std::fs::read(&merged) {
+        Ok(data) => Some(data),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => None,
+        Err(err) => return Err(err.into()),
+    };
+
+    let status = std::process::Command::from(gix::command::prepare(cmd).with_shell())
+        .current_dir(workdir)
+        .envs(
+            versions
+                .iter()
+                .map(|(name, path)| (*name, path.as_os_str())),
+        )
+        .env("MERGED", &merged)
+        .status()
+        .with_context(|| format!("Failed to launch merge tool '{tool}'"))?;
+
+    let current = match std::fs::read(&merged) {
+        Ok(data) => Some(data),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => None,
+        Err(err) => return Err(err.into()),
+    };
+    let resolved = if trust_exit_code {
+        status.success()
+    } else {
+        current != previous
+    };
+    if !resolved {
+        match &previous {
+            Some(data) => std::fs::write(&merged, data)?,
+            None if merged.exists() => std::fs::remove_file(&merged)?,
+            None => {}
+        }
+    }
+    tracing::info!(%tool, %commit_id, ?path, resolved, "ran merge tool");
+    Ok(MergeToolOutcome { tool, resolved })
+}
@@ -1,7 +1,9 @@
 use anyhow::Result;
 use but_ctx::Context;
 use git2::build::CheckoutBuilder;
-use gitbutler_edit_mode::commands::{enter_edit_mode, save_and_return_to_workspace};
+use gitbutler_edit_mode::commands::{
+    enter_edit_mode, resolve_with_mergetool, save_and_return_to_workspace,
+};
 use gitbutler_stack::VirtualBranchesHandle;
 use tempfile::TempDir;
 
@@ -28,7 +30,7 @@ fn conficted_entries_get_written_when_leaving_edit_mode() -> Result<()> {
     let vb_state = VirtualBranchesHandle::new(ctx.project_data_dir());
     let stacks = vb_state.list_stacks_in_workspace()?;
     let stack = stacks.first().unwrap();
-    enter_edit_mode(&ctx, foobar.id(), stack.id)?;
+    enter_edit_mode(&ctx, foobar.id(), Some(stack.id))?;
 
     let init = repository
         .find_reference("refs/heads/main")?
@@ -66,3 +68,49 @@ fn conficted_entries_get_written_when_leaving_edit_mode() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn stack_is_found_from_the_commit() -> Result<()> {
+    let (ctx, _tempdir) = command_ctx("stack_is_found_from_the_commit")?;
+    let repository = &*ctx.git2_repo.get()?;
+
+    let foobar = repository.head()?.peel_to_commit()?.parent(0)?;
+
+    let vb_state = VirtualBranchesHandle::new(ctx.project_data_dir());
+    let stacks = vb_state.list_stacks_in_workspace()?;
+    let stack = stacks.first().unwrap();
+    let metadata = enter_edit_mode(&ctx, foobar.id(), None)?;
+    assert_eq!(metadata.stack_id, stack.id);
+
+    std::fs::write(repository.path().parent().unwrap().join("edited"), "edited")?;
+    save_and_return_to_workspace(&ctx)?;
+
+    let head = repository.head()?;
+    assert_eq!(head.name(), Some("refs/heads/gitbutler/workspace"));
+    let edited = head.peel_to_commit()?.parent(0)?;
+    assert_eq!(edited.message(), foobar.message());
+    assert!(edited.tree()?.get_name("edited").is_some());
+
+    Ok(())
+}
+
+#[test]
+fn mergetool_only_resolves_conflicted_commits() -> Result<()> {
+    let (ctx, _tempdir) = command_ctx("stack_is_found_from_the_commit")?;
+    let repository = &*ctx.git2_repo.get()?;
+
+    let foobar = repository.head()?.peel_to_commit()?.parent(0)?;
+    let init = foobar.parent(0)?;
+    enter_edit_mode(&ctx, foobar.id(), None)?;
+
+    let err = resolve_with_mergetool(&ctx, init.id(), "file".as_ref()).unwrap_err();
+    assert!(err.to_string().contains("must be in edit mode"), "{err:#}");
+
+    let err = resolve_with_mergetool(&ctx, foobar.id(), "file".as_ref()).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        format!("Commit {} isn't conflicted", foobar.id())
+    );
+
+    Ok(())
+}
@@ -37,4 +37,17 @@ git clone repo conficted_entries_get_written_when_leaving_edit_mode
   echo b > file
   $CLI branches create --set-default branchy
   $CLI branches commit  branchy --message foobar
-)
\ No newline at end of file
+)
+# Setup:
+# * (HEAD -> gitbutler/workspace) GitButler Workspace Commit
+# * foobar
+# * (origin/main, origin/HEAD, main) init
+git clone repo stack_is_found_from_the_commit
+(cd stack_is_found_from_the_commit
+  git config user.name "Author"
+  git config user.email "author@example.com"
+  $CLI project add --switch-to-workspace "$(git rev-parse --symbolic-full-name origin/main)"
+  echo b > file
+  $CLI branches create --set-default branchy
+  $CLI branches commit  branchy --message foobar
+)
@@ -7,7 +7,19 @@ use gitbutler_project::ProjectId;
 pub enum InternalEvent {
     // From file monitor
     GitFilesChange(ProjectId, Vec<PathBuf>),
-    ProjectFilesChange(ProjectId, Vec<PathBuf>),
+    /// The worktree-relative paths that changed, along with the renames among them.
+    ProjectFilesChange(ProjectId, Vec<PathBuf>, Vec<Rename>),
+}
+
+/// A file or directory in the worktree that was moved, with both paths relative to the worktree.
+///
+/// Both paths are also part of the paths of the [`InternalEvent::ProjectFilesChange`] the rename is in.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Rename {
+    /// The path before the rename.
+    pub from: PathBuf,
+    /// The path after the rename.
+    pub to: PathBuf,
 }
 
 impl Display for InternalEvent {
@@ -21,13 +33,17 @@ impl Display for InternalEvent {
                     comma_separated_paths(paths)
                 )
             }
-            InternalEvent::ProjectFilesChange(project_id, paths) => {
+            InternalEvent::ProjectFilesChange(project_id, paths, renames) => {
                 write!(
                     f,
-                    "ProjectFileChange({}, {})",
+                    "ProjectFileChange({}, {}",
                     project_id,
                     comma_separated_paths(paths)
-                )
+                )?;
+                if !renames.is_empty() {
+                    write!(f, ", {} renames", renames.len())?;
+                }
+                write!(f, ")")
             }
         }
     }
@@ -48,3 +64,47 @@ fn comma_separated_paths(paths: &[PathBuf]) -> String {
         listing
     }
 }
+
+/// Turn the `(from, to)` pairs of consecutive renames into one rename per moved path, in the order they happened.
+///
+/// A path that is moved more than once is reported with its first and last location,
+/// and a path that was moved back to where it was is dropped.
+pub(crate) fn coalesce_renames(pairs: impl IntoIterator<Item = (PathBuf, PathBuf)>) -> Vec<Rename> {
+    let mut renames: Vec<Rename> = Vec::new();
+    for (from, to) in pairs {
+        match renames.iter_mut().find(|rename| rename.to == from) {
+            Some(previous) => previous.to = to,
+            None => renames.push(Rename { from, to }),
+        }
+    }
+    renames.retain(|rename| rename.from != rename.to);
+    renames
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::{Rename, coalesce_renames};
+
+    fn pair(from: &str, to: &str) -> (PathBuf, PathBuf) {
+        (from.into(), to.into())
+    }
+
+    #[test]
+    fn chains_are_coalesced_and_round_trips_dropped() {
+        let renames = coalesce_renames([
+            pair("a", "b"),
+            pair("x", "y"),
+            pair("b", "c"),
+            pair("y", "x"),
+        ]);
+        assert_eq!(
+            renames,
+            [Rename {
+                from: "a".into(),
+                to: "c".into()
+            }]
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use crate::events::InternalEvent;
+use crate::events::{InternalEvent, coalesce_renames};
 use crate::watch_plan::{
     build_index_icase_accelerator_if_needed, compute_watch_plan_for_repo, is_tracked_in_index,
     is_watchable_directory, to_repo_relative_path,
@@ -8,7 +8,7 @@ use gitbutler_notify_debouncer::{Debouncer, NoCache, new_debouncer};
 use gitbutler_project::ProjectId;
 use gix::bstr::BStr;
 use notify::{RecommendedWatcher, Watcher};
-use std::collections::BTreeSet;
+use std::collections::{BTreeSet, HashMap};
 use std::{collections::HashSet, path::Path, time::Duration};
 use tokio::task;
 use tracing::Level;
@@ -28,11 +28,10 @@ const TICK_RATE: Duration = if cfg!(windows) {
     Duration::from_millis(100)
 };
 
-// The number of TICK_RATE intervals required of "dead air" (i.e. no new events
-// arriving) before we will automatically flush pending events. This means that
-// after the disk is quiet for TICK_RATE * FLUSH_AFTER_EMPTY, we will process
-// the pending events, even if DEBOUNCE_TIMEOUT hasn't expired yet
-const FLUSH_AFTER_EMPTY: u32 = 3;
+/// The default time of "dead air" (i.e. no new events arriving) before pending events
+/// are flushed, even if [`DEBOUNCE_TIMEOUT`] hasn't expired yet.
+/// It's rounded up to a multiple of the tick rate.
+pub const DEFAULT_DEBOUNCE: Duration = Duration::from_millis(300);
 
 enum Command {
     Flush,
@@ -204,6 +203,7 @@ fn setup_legacy_watch(
     debouncer: &mut Debouncer<RecommendedWatcher, NoCache>,
     worktree_path: &Path,
     git_dir: &Path,
+    common_dir: &Path,
 ) -> Result<()> {
     let extra_git_dir_to_watch = {
         let mut enclosing_worktree_dir = git_dir.to_owned();
@@ -214,6 +214,16 @@ fn setup_legacy_watch(
             None
         }
     };
+    // Linked worktrees share the local and remote branches of the main worktree, which are outside their own git-dir.
+    let common_refs_dirs_to_watch: Vec<_> = if common_dir != git_dir {
+        ["heads", "remotes"]
+            .into_iter()
+            .map(|kind| common_dir.join("refs").join(kind))
+            .filter(|dir| dir.is_dir())
+            .collect()
+    } else {
+        Vec::new()
+    };
 
     // Start the watcher, but retry if there are transient errors.
     backoff::retry(watch_backoff_policy(), || {
@@ -229,6 +239,13 @@ fn setup_legacy_watch(
                     Ok(())
                 }
             })
+            .and_then(|()| {
+                common_refs_dirs_to_watch.iter().try_for_each(|refs_dir| {
+                    debouncer
+                        .watcher()
+                        .watch(refs_dir, notify::RecursiveMode::Recursive)
+                })
+            })
             .map_err(|err| into_backoff_err(err, worktree_path))
     })
     .map_err(backoff_err_to_anyhow)
@@ -239,7 +256,12 @@ fn setup_legacy_watch(
 /// classify them, and associate them with `project_id`.
 /// These are sent through the passed `out` channel, to indicate either **Git** repository changes
 /// or **ProjectWorktree** changes
-/// Use `watch_mode` to control how exactly the directory is watched.
+/// Use `watch_mode` to control how exactly the directory is watched, and `debounce` to control how long the
+/// filesystem has to be quiet before all changes since the last event are sent as one batch, see [`DEFAULT_DEBOUNCE`].
+///
+/// Renames are sent along with the changed paths, and changes of paths that are ignored by `.gitignore` files
+/// and not tracked are dropped before they are sent. Below directories that are ignored as a whole, like
+/// the `target` directory of a build, this is decided once per batch and directory.
 ///
 /// ### Why is this not an iterator?
 ///
@@ -256,13 +278,16 @@ pub fn spawn(
     worktree_path: &std::path::Path,
     out: tokio::sync::mpsc::UnboundedSender<InternalEvent>,
     watch_mode: WatchMode,
+    debounce: Duration,
 ) -> Result<FileMonitorHandle> {
     let (cmd_tx, cmd_rx) = std::sync::mpsc::channel();
     let (notify_tx, notify_rx) = std::sync::mpsc::channel();
+    // The number of TICK_RATE intervals of dead air before pending events are flushed.
+    let flush_after_empty = debounce.as_millis().div_ceil(TICK_RATE.as_millis()).max(1) as u32;
     let mut debouncer = new_debouncer(
         DEBOUNCE_TIMEOUT,
         Some(TICK_RATE),
-        Some(FLUSH_AFTER_EMPTY),
+        Some(flush_after_empty),
         notify_tx,
     )
     .context("failed to create debouncer")?;
@@ -273,12 +298,13 @@ pub fn spawn(
         worktree_path.display()
     ))?;
     let git_dir = repo.path().to_owned();
+    let common_dir = repo.common_dir().to_owned();
 
     let mut effective_watch_mode = watch_mode;
 
     match watch_mode {
         WatchMode::Legacy => {
-            setup_legacy_watch(&mut debouncer, &worktree_path, &git_dir)?;
+            setup_legacy_watch(&mut debouncer, &worktree_path, &git_dir, &common_dir)?;
         }
         WatchMode::Modern => {
             setup_watch_plan(&mut debouncer, project_id, &repo, &worktree_path, &git_dir)?;
@@ -297,12 +323,12 @@ pub fn spawn(
                             "watch-plan setup failed; falling back to legacy watch mode"
                         );
                         effective_watch_mode = WatchMode::Legacy;
-                        setup_legacy_watch(&mut debouncer, &worktree_path, &git_dir)?;
+                        setup_legacy_watch(&mut debouncer, &worktree_path, &git_dir, &common_dir)?;
                     }
                 }
             } else {
                 effective_watch_mode = WatchMode::Legacy;
-                setup_legacy_watch(&mut debouncer, &worktree_path, &git_dir)?;
+                setup_legacy_watch(&mut debouncer, &worktree_path, &git_dir, &common_dir)?;
             }
         }
     }
@@ -356,12 +382,25 @@ pub fn spawn(
                 }
                 Ok(events) => {
                     let num_events = events.len();
+                    // The debouncer stitches the two halves of a rename into one event with the old and the new path.
+                    let rename_pairs: Vec<_> = events
+                        .iter()
+                        .filter(|event| {
+                            matches!(
+                                event.kind,
+                                notify::EventKind::Modify(notify::event::ModifyKind::Name(
+                                    notify::event::RenameMode::Both
+                                ))
+                            ) && event.paths.len() == 2
+                        })
+                        .map(|event| (event.paths[0].clone(), event.paths[1].clone()))
+                        .collect();
                     let mut classified_file_paths: Vec<_> = events
                         .into_iter()
                         .filter(|event| is_interesting_kind(event.kind))
                         .flat_map(|event| event.event.paths)
                         .map(|file| {
-                            let kind = classify_file(&git_dir, &file);
+                            let kind = classify_file(&git_dir, &common_dir, &file);
                             (file, kind)
                         })
                         .collect();
@@ -380,8 +419,34 @@ pub fn spawn(
                         let is_untracked = |relative_path: &BStr, is_dir: bool| -> bool {
                             !is_tracked_in_index(relative_path, is_dir, &index, icase_acc.as_ref())
                         };
+                        // Whether a directory is ignored as a whole, so builds writing many files into it
+                        // only need the rules to be evaluated once.
+                        let mut ignored_dirs = HashMap::<std::path::PathBuf, bool>::new();
                         for (file_path, kind) in classified_file_paths.iter_mut() {
                             if let Ok(relative_path) = file_path.strip_prefix(&worktree_path) {
+                                let mut parent_dirs: Vec<_> = relative_path
+                                    .ancestors()
+                                    .skip(1)
+                                    .filter(|dir| !dir.as_os_str().is_empty())
+                                    .collect();
+                                parent_dirs.reverse();
+                                let mut in_ignored_dir = false;
+                                for dir in parent_dirs {
+                                    let is_ignored = *ignored_dirs.entry(dir.to_owned()).or_insert_with(|| {
+                                        excludes
+                                            .at_path(dir, Some(gix::index::entry::Mode::DIR))
+                                            .is_ok_and(|platform| platform.is_excluded())
+                                            && is_untracked(&to_repo_relative_path(dir), true)
+                                    });
+                                    if is_ignored {
+                                        in_ignored_dir = true;
+                                        break;
+                                    }
+                                }
+                                if in_ignored_dir {
+                                    *kind = FileKind::ProjectIgnored;
+                                    continue;
+                                }
                                 let is_dir = file_path.is_dir();
                                 let is_excluded = excludes
                                     .at_path(relative_path, is_dir
@@ -423,25 +488,46 @@ pub fn spawn(
                         } else {
                             BTreeSet::new()
                         };
+                    // Renames of which either side is ignored are a creation or a deletion as far as the worktree is concerned.
+                    let renames =
+                        coalesce_renames(rename_pairs.into_iter().filter_map(|(from, to)| {
+                            let is_project = |path: &std::path::PathBuf| {
+                                classified_file_paths
+                                    .iter()
+                                    .any(|(file, kind)| file == path && *kind == FileKind::Project)
+                            };
+                            if !(is_project(&from) && is_project(&to)) {
+                                return None;
+                            }
+                            Some((
+                                from.strip_prefix(&worktree_path).ok()?.to_owned(),
+                                to.strip_prefix(&worktree_path).ok()?.to_owned(),
+                            ))
+                        }));
                     let (mut stripped_git_paths, mut worktree_relative_paths) =
                         (HashSet::new(), HashSet::new());
                     for (file_path, kind) in classified_file_paths {
                         match kind {
                             FileKind::ProjectIgnored => ignored += 1,
                             FileKind::GitUninteresting => git_noop += 1,
-                            FileKind::Project | FileKind::Git => match file_path
-                                .strip_prefix(&worktree_path)
+                            // The git-dir of linked worktrees isn't inside the worktree, and their branches are in the common dir.
+                            FileKind::Git => match file_path
+                                .strip_prefix(&git_dir)
+                                .or_else(|_| file_path.strip_prefix(&common_dir))
                             {
+                                Ok(stripped) => {
+                                    stripped_git_paths.insert(stripped.to_owned());
+                                }
+                                Err(_) => {
+                                    tracing::warn!(%project_id, ?file_path, ?git_dir, "failed to strip prefix");
+                                }
+                            },
+                            FileKind::Project => match file_path.strip_prefix(&worktree_path) {
                                 Ok(relative_file_path) => {
                                     if relative_file_path.as_os_str().is_empty() {
                                         continue;
                                     }
-                                    if let Ok(stripped) = relative_file_path.strip_prefix(".git") {
-                                        stripped_git_paths.insert(stripped.to_owned());
-                                    } else {
-                                        worktree_relative_paths
-                                            .insert(relative_file_path.to_owned());
-                                    };
+                                    worktree_relative_paths.insert(relative_file_path.to_owned());
                                 }
                                 Err(_) => {
                                     tracing::warn!(%project_id, ?file_path, ?worktree_path, "failed to strip prefix");
@@ -507,7 +593,8 @@ pub fn spawn(
                     if !worktree_relative_paths.is_empty() {
                         let paths_dedup: Vec<_> = worktree_relative_paths.into_iter().collect();
                         stats.record("project_dedup", paths_dedup.len());
-                        let event = InternalEvent::ProjectFilesChange(project_id, paths_dedup);
+                        let event =
+                            InternalEvent::ProjectFilesChange(project_id, paths_dedup, renames);
                         if out.send(event).is_err() {
                             tracing::info!("channel closed - stopping file watcher");
                             break 'outer;
@@ -566,11 +653,15 @@ fn backoff_err_to_anyhow(
 }
 
 pub const LOCAL_REFS_DIR: &str = "refs/heads/";
+pub const REMOTE_REFS_DIR: &str = "refs/remotes/";
+pub const PACKED_REFS: &str = "packed-refs";
 pub const FETCH_HEAD: &str = "FETCH_HEAD";
 pub const HEAD: &str = "HEAD";
 pub const HEAD_ACTIVITY: &str = "logs/HEAD";
 pub const INDEX: &str = "index";
 pub const GB_FLUSH: &str = "GB_FLUSH";
+/// The settings overrides of the project, see `but_settings::AppSettings::project_settings_path()`.
+pub const PROJECT_SETTINGS: &str = "gitbutler/settings.json";
 
 /// A classification for a changed file.
 #[derive(Debug, Eq, PartialEq)]
@@ -585,14 +676,31 @@ enum FileKind {
     ProjectIgnored,
 }
 
-fn classify_file(git_dir: &Path, file_path: &Path) -> FileKind {
+fn classify_file(git_dir: &Path, common_dir: &Path, file_path: &Path) -> FileKind {
+    if git_dir != common_dir
+        && let Ok(check_file_path) = file_path.strip_prefix(common_dir)
+        && !file_path.starts_with(git_dir)
+    {
+        // Only branches of the common dir affect linked worktrees, everything else is their own.
+        return if check_file_path.starts_with(LOCAL_REFS_DIR)
+            || check_file_path.starts_with(REMOTE_REFS_DIR)
+            || check_file_path == Path::new(PACKED_REFS)
+        {
+            FileKind::Git
+        } else {
+            FileKind::GitUninteresting
+        };
+    }
     if let Ok(check_file_path) = file_path.strip_prefix(git_dir) {
         if check_file_path == Path::new(FETCH_HEAD)
             || check_file_path == Path::new(HEAD_ACTIVITY)
             || check_file_path == Path::new(HEAD)
             || check_file_path == Path::new(GB_FLUSH)
             || check_file_path == Path::new(INDEX)
+            || check_file_path == Path::new(PROJECT_SETTINGS)
+            || check_file_path == Path::new(PACKED_REFS)
             || check_file_path.starts_with(LOCAL_REFS_DIR)
+            || check_file_path.starts_with(REMOTE_REFS_DIR)
         {
             FileKind::Git
         } else {
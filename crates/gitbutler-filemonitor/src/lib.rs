@@ -5,9 +5,10 @@
 mod watch_plan;
 
 mod events;
-pub use events::InternalEvent;
+pub use events::{InternalEvent, Rename};
 
 mod file_monitor;
 pub use file_monitor::{
-    FETCH_HEAD, FileMonitorHandle, HEAD, HEAD_ACTIVITY, INDEX, LOCAL_REFS_DIR, WatchMode, spawn,
+    DEFAULT_DEBOUNCE, FETCH_HEAD, FileMonitorHandle, HEAD, HEAD_ACTIVITY, INDEX, LOCAL_REFS_DIR,
+    PACKED_REFS, PROJECT_SETTINGS, REMOTE_REFS_DIR, WatchMode, spawn,
 };
@@ -39,6 +39,14 @@ pub(crate) fn compute_watch_plan_for_repo(
     if emit_git_dir_watches(git_dir, &mut visit_dir)?.is_break() {
         return Ok(());
     }
+    // Linked worktrees share the branches of the main worktree, which are outside their own git-dir.
+    let common_refs_heads_dir = repo.common_dir().join("refs").join("heads");
+    if repo.common_dir() != git_dir
+        && common_refs_heads_dir.is_dir()
+        && visit_dir(&common_refs_heads_dir, RecursiveMode::Recursive)?.is_break()
+    {
+        return Ok(());
+    }
 
     seen.insert(worktree_path.to_owned());
     push_child_dirs_sorted(worktree_path, &mut stack);
@@ -109,6 +117,12 @@ fn emit_git_dir_watches(
     if logs_dir.is_dir() && visit_dir(&logs_dir, RecursiveMode::NonRecursive)?.is_break() {
         return Ok(ControlFlow::Break(()));
     }
+    let gitbutler_dir = git_dir.join("gitbutler");
+    // Non-recursive as we are only interested in the project settings, `.git/gitbutler/settings.json`.
+    if gitbutler_dir.is_dir() && visit_dir(&gitbutler_dir, RecursiveMode::NonRecursive)?.is_break()
+    {
+        return Ok(ControlFlow::Break(()));
+    }
     let refs_heads_dir = git_dir.join("refs").join("heads");
     // For `.git/refs/heads`, the built-in watch mode is working well enough, and we have no facility
     // to auto-track newly added directories here. But if that would change, we could non-recursively
@@ -116,6 +130,15 @@ fn emit_git_dir_watches(
     if refs_heads_dir.is_dir() && visit_dir(&refs_heads_dir, RecursiveMode::Recursive)?.is_break() {
         return Ok(ControlFlow::Break(()));
     }
+    let refs_remotes_dir = git_dir.join("refs").join("remotes");
+    // Remote tracking branches change when fetching, also with Git directly. Like for `.git/refs/heads`,
+    // this directory is only watched if it exists when the watch starts, while `packed-refs` is picked up
+    // by the watch of the git-dir itself.
+    if refs_remotes_dir.is_dir()
+        && visit_dir(&refs_remotes_dir, RecursiveMode::Recursive)?.is_break()
+    {
+        return Ok(ControlFlow::Break(()));
+    }
     Ok(ControlFlow::Continue(()))
 }
 
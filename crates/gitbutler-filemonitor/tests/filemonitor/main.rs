@@ -5,7 +5,7 @@ mod spawn {
         time::Duration,
     };
 
-    use gitbutler_filemonitor::{InternalEvent, WatchMode};
+    use gitbutler_filemonitor::{InternalEvent, Rename, WatchMode};
     use gitbutler_project::ProjectId;
     use tokio::sync::mpsc;
 
@@ -39,12 +39,18 @@ mod spawn {
         let project_id = ProjectId::from_number_for_testing(1);
 
         let (tx, mut rx) = mpsc::unbounded_channel();
-        let monitor = gitbutler_filemonitor::spawn(project_id, &workdir, tx, WatchMode::Modern)?;
+        let monitor = gitbutler_filemonitor::spawn(
+            project_id,
+            &workdir,
+            tx,
+            WatchMode::Modern,
+            gitbutler_filemonitor::DEFAULT_DEBOUNCE,
+        )?;
 
         std::fs::create_dir(workdir.join("dir"))?;
         monitor.flush()?;
         expect_matching_event(&mut rx, generous_timeout_for_ci, |event| match event {
-            InternalEvent::ProjectFilesChange(id, paths) => {
+            InternalEvent::ProjectFilesChange(id, paths, _) => {
                 *id == project_id && contains_path(paths, Path::new("dir"))
             }
             _ => false,
@@ -54,7 +60,7 @@ mod spawn {
         std::fs::write(workdir.join("dir/new-file"), "hi")?;
         monitor.flush()?;
         expect_matching_event(&mut rx, generous_timeout_for_ci, |event| match event {
-            InternalEvent::ProjectFilesChange(id, paths) => {
+            InternalEvent::ProjectFilesChange(id, paths, _) => {
                 *id == project_id && contains_path(paths, &Path::new("dir").join("new-file"))
             }
             _ => false,
@@ -64,7 +70,7 @@ mod spawn {
         std::fs::rename(workdir.join("dir"), workdir.join("old-dir"))?;
         monitor.flush()?;
         expect_matching_event(&mut rx, generous_timeout_for_ci, |event| match event {
-            InternalEvent::ProjectFilesChange(id, paths) => {
+            InternalEvent::ProjectFilesChange(id, paths, _) => {
                 *id == project_id && contains_path(paths, Path::new("old-dir"))
             }
             _ => false,
@@ -74,7 +80,7 @@ mod spawn {
         std::fs::write(workdir.join("old-dir/other-file"), "ho")?;
         monitor.flush()?;
         expect_matching_event(&mut rx, generous_timeout_for_ci, |event| match event {
-            InternalEvent::ProjectFilesChange(id, paths) => {
+            InternalEvent::ProjectFilesChange(id, paths, _) => {
                 *id == project_id && contains_path(paths, &Path::new("old-dir").join("other-file"))
             }
             _ => false,
@@ -84,7 +90,7 @@ mod spawn {
         std::fs::remove_dir_all(workdir.join("old-dir"))?;
         monitor.flush()?;
         expect_matching_event(&mut rx, generous_timeout_for_ci, |event| match event {
-            InternalEvent::ProjectFilesChange(id, paths) => {
+            InternalEvent::ProjectFilesChange(id, paths, _) => {
                 *id == project_id && contains_path(paths, Path::new("old-dir"))
             }
             _ => false,
@@ -94,7 +100,7 @@ mod spawn {
         std::fs::create_dir(workdir.join("old-dir"))?;
         monitor.flush()?;
         expect_matching_event(&mut rx, generous_timeout_for_ci, |event| match event {
-            InternalEvent::ProjectFilesChange(id, paths) => {
+            InternalEvent::ProjectFilesChange(id, paths, _) => {
                 *id == project_id && contains_path(paths, Path::new("old-dir"))
             }
             _ => false,
@@ -104,7 +110,7 @@ mod spawn {
         std::fs::write(workdir.join("old-dir/other-file"), "")?;
         monitor.flush()?;
         expect_matching_event(&mut rx, generous_timeout_for_ci, |event| match event {
-            InternalEvent::ProjectFilesChange(id, paths) => {
+            InternalEvent::ProjectFilesChange(id, paths, _) => {
                 *id == project_id && contains_path(paths, &Path::new("old-dir").join("other-file"))
             }
             _ => false,
@@ -113,6 +119,42 @@ mod spawn {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn renames_are_paired() -> anyhow::Result<()> {
+        let generous_timeout_for_ci = Duration::from_secs(10);
+        let (repo, _tmp) = but_testsupport::writable_scenario("watch-plan-rename-dir");
+        let workdir = repo.workdir().expect("non-bare").to_owned();
+        let project_id = ProjectId::from_number_for_testing(1);
+        std::fs::write(workdir.join("file"), "content")?;
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let monitor = gitbutler_filemonitor::spawn(
+            project_id,
+            &workdir,
+            tx,
+            WatchMode::Modern,
+            gitbutler_filemonitor::DEFAULT_DEBOUNCE,
+        )?;
+
+        std::fs::rename(workdir.join("file"), workdir.join("renamed"))?;
+        monitor.flush()?;
+        let expected = Rename {
+            from: "file".into(),
+            to: "renamed".into(),
+        };
+        expect_matching_event(&mut rx, generous_timeout_for_ci, |event| match event {
+            InternalEvent::ProjectFilesChange(id, paths, renames) => {
+                *id == project_id
+                    && contains_path(paths, Path::new("file"))
+                    && contains_path(paths, Path::new("renamed"))
+                    && renames.contains(&expected)
+            }
+            _ => false,
+        })
+        .await?;
+        Ok(())
+    }
 }
 
 mod watch_mode {
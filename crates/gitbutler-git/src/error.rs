@@ -36,4 +36,7 @@ pub enum Error<BE: std::error::Error + core::fmt::Debug + Send + Sync + 'static>
         "the force push was blocked because the remote branch contains commits that would be overwritten"
     )]
     ForcePushProtection(BE),
+    /// An atomic push was attempted, but the remote doesn't support it.
+    #[error("the remote does not support atomic pushes: {0}")]
+    AtomicPushUnsupported(BE),
 }
@@ -18,6 +18,6 @@ mod repository;
 pub use self::executor::tokio;
 pub use self::{
     error::Error,
-    refspec::{Error as RefSpecError, RefSpec},
-    repository::{clone, fetch, push},
+    refspec::{Error as RefSpecError, Lease, RefSpec},
+    repository::{clone, fetch, push, push_atomic},
 };
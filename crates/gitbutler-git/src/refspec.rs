@@ -115,6 +115,24 @@ impl<S: AsRef<str>, D: AsRef<str>> From<(S, D)> for RefSpec {
     }
 }
 
+/// The commit a remote ref has to point to for a force-push with force-push protection to overwrite it,
+/// passed to Git as `--force-with-lease=<refname>:<expected>`.
+///
+/// Refs without a lease are expected to point to their remote tracking branch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lease {
+    /// The full name of the ref on the remote, like `refs/heads/main`.
+    pub refname: String,
+    /// The hex-id of the commit the remote ref is expected to point to.
+    pub expected: String,
+}
+
+impl fmt::Display for Lease {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "--force-with-lease={}:{}", self.refname, self.expected)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
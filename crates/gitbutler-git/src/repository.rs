@@ -5,7 +5,7 @@ use gix::bstr::ByteSlice;
 use rand::{Rng, SeedableRng};
 
 use super::executor::{AskpassServer, GitExecutor, Pid, Socket};
-use crate::RefSpec;
+use crate::{Lease, RefSpec};
 
 /// The number of characters in the secret used for checking
 /// askpass invocations by ssh/git when connecting to our process.
@@ -372,6 +372,9 @@ where
 /// Any prompts for the user are passed to the asynchronous callback `on_prompt`,
 /// which should return the user's response or `None` if the operation should be
 /// aborted, in which case an `Err` value is returned from this function.
+///
+/// With `force` and `force_push_protection`, remote refs are only overwritten if they point to
+/// the commit of their `leases`, or to their remote tracking branch if they have none.
 #[expect(clippy::too_many_arguments)]
 pub async fn push<P, F, Fut, E, Extra>(
     repo_path: P,
@@ -380,6 +383,82 @@ pub async fn push<P, F, Fut, E, Extra>(
     refspec: RefSpec,
     force: bool,
     force_push_protection: bool,
+    leases: &[Lease],
+    on_prompt: F,
+    extra: Extra,
+    push_opts: Vec<String>,
+) -> Result<String, crate::Error<Error<E>>>
+where
+    P: AsRef<Path>,
+    E: GitExecutor,
+    F: FnMut(String, Extra) -> Fut,
+    Fut: std::future::Future<Output = Option<String>>,
+    Extra: Send + Clone,
+{
+    push_refspecs(
+        repo_path,
+        executor,
+        remote,
+        &[refspec],
+        false,
+        force,
+        force_push_protection,
+        leases,
+        on_prompt,
+        extra,
+        push_opts,
+    )
+    .await
+}
+
+/// Like [`push()`], but pushes all `refspecs` with `--atomic`, so either all refs are updated on the remote or none.
+/// Fails with [`Error::AtomicPushUnsupported`](crate::Error::AtomicPushUnsupported) if the remote doesn't support it.
+#[expect(clippy::too_many_arguments)]
+pub async fn push_atomic<P, F, Fut, E, Extra>(
+    repo_path: P,
+    executor: E,
+    remote: &str,
+    refspecs: &[RefSpec],
+    force: bool,
+    force_push_protection: bool,
+    leases: &[Lease],
+    on_prompt: F,
+    extra: Extra,
+    push_opts: Vec<String>,
+) -> Result<String, crate::Error<Error<E>>>
+where
+    P: AsRef<Path>,
+    E: GitExecutor,
+    F: FnMut(String, Extra) -> Fut,
+    Fut: std::future::Future<Output = Option<String>>,
+    Extra: Send + Clone,
+{
+    push_refspecs(
+        repo_path,
+        executor,
+        remote,
+        refspecs,
+        true,
+        force,
+        force_push_protection,
+        leases,
+        on_prompt,
+        extra,
+        push_opts,
+    )
+    .await
+}
+
+#[expect(clippy::too_many_arguments)]
+async fn push_refspecs<P, F, Fut, E, Extra>(
+    repo_path: P,
+    executor: E,
+    remote: &str,
+    refspecs: &[RefSpec],
+    atomic: bool,
+    force: bool,
+    force_push_protection: bool,
+    leases: &[Lease],
     on_prompt: F,
     extra: Extra,
     push_opts: Vec<String>,
@@ -392,15 +471,20 @@ where
     Extra: Send + Clone,
 {
     let mut args = vec!["push", "--quiet", "--no-verify"];
+    if atomic {
+        args.push("--atomic");
+    }
 
-    let refspec = refspec.to_string();
+    let refspecs: Vec<_> = refspecs.iter().map(ToString::to_string).collect();
+    let leases: Vec<_> = leases.iter().map(ToString::to_string).collect();
 
     args.push(remote);
-    args.push(&refspec);
+    args.extend(refspecs.iter().map(String::as_str));
 
     if force {
         if force_push_protection {
             args.push("--force-with-lease");
+            args.extend(leases.iter().map(String::as_str));
             args.push("--force-if-includes");
         } else {
             args.push("--force");
@@ -433,6 +517,14 @@ where
         stderr: stderr.clone(),
     };
 
+    if atomic
+        && stderr
+            .to_lowercase()
+            .contains("does not support --atomic push")
+    {
+        return Err(crate::Error::AtomicPushUnsupported(base_error));
+    }
+
     if status == 1 && force && force_push_protection {
         return Err(crate::Error::ForcePushProtection(base_error));
     }
@@ -1,3 +1,4 @@
+mod push;
 mod refspec;
 
 #[cfg(test)]
@@ -0,0 +1,244 @@
+use std::{path::Path, process::Command};
+
+use gitbutler_git::{RefSpec, tokio::TokioExecutor};
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn atomic_push_updates_all_refs() {
+    let tmp = tempfile::tempdir().unwrap();
+    let local = local_with_remote(tmp.path());
+    git(&local, &["branch", "one"]);
+    git(&local, &["branch", "two"]);
+
+    gitbutler_git::push_atomic(
+        &local,
+        TokioExecutor,
+        "origin",
+        &[refspec("one"), refspec("two")],
+        false,
+        false,
+        &[],
+        no_prompt,
+        (),
+        vec![],
+    )
+    .await
+    .expect("the push succeeds");
+
+    let head = git(&local, &["rev-parse", "HEAD"]);
+    let remote = tmp.path().join("remote.git");
+    assert_eq!(git(&remote, &["rev-parse", "refs/heads/one"]), head);
+    assert_eq!(git(&remote, &["rev-parse", "refs/heads/two"]), head);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn atomic_push_updates_no_ref_if_one_is_rejected() {
+    let tmp = tempfile::tempdir().unwrap();
+    let local = local_with_remote(tmp.path());
+    git(&local, &["branch", "one"]);
+    git(&local, &["branch", "two"]);
+    git(&local, &["push", "--quiet", "origin", "two"]);
+    let remote_two = git(&local, &["rev-parse", "two"]);
+
+    // Rewrite `two` so it doesn't fast-forward anymore.
+    git(&local, &["checkout", "--quiet", "two"]);
+    git(&local, &["commit", "--quiet", "--amend", "-m", "rewritten"]);
+
+    let err = gitbutler_git::push_atomic(
+        &local,
+        TokioExecutor,
+        "origin",
+        &[refspec("one"), refspec("two")],
+        false,
+        false,
+        &[],
+        no_prompt,
+        (),
+        vec![],
+    )
+    .await
+    .expect_err("`two` isn't a fast-forward");
+    assert!(
+        !matches!(err, gitbutler_git::Error::AtomicPushUnsupported(_)),
+        "local remotes support atomic pushes"
+    );
+
+    let remote = tmp.path().join("remote.git");
+    assert!(
+        !Command::new("git")
+            .args(["rev-parse", "--verify", "--quiet", "refs/heads/one"])
+            .current_dir(&remote)
+            .status()
+            .unwrap()
+            .success(),
+        "`one` would have been pushed fine, but wasn't as the push is atomic"
+    );
+    assert_eq!(git(&remote, &["rev-parse", "refs/heads/two"]), remote_two);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn force_push_with_protection_fails_if_the_remote_moved() {
+    let tmp = tempfile::tempdir().unwrap();
+    let local = local_with_remote(tmp.path());
+    git(&local, &["push", "--quiet", "origin", "main"]);
+
+    // Someone else pushed to the remote, and we didn't fetch it.
+    let other = tmp.path().join("other");
+    git(
+        tmp.path(),
+        &[
+            "clone",
+            "--quiet",
+            "--branch",
+            "main",
+            "remote.git",
+            other.to_str().unwrap(),
+        ],
+    );
+    commit(&other, "theirs");
+    git(&other, &["push", "--quiet", "origin", "main"]);
+
+    git(&local, &["commit", "--quiet", "--amend", "-m", "ours"]);
+    let err = gitbutler_git::push_atomic(
+        &local,
+        TokioExecutor,
+        "origin",
+        &[refspec("main")],
+        true,
+        true,
+        &[],
+        no_prompt,
+        (),
+        vec![],
+    )
+    .await
+    .expect_err("the lease doesn't match the remote");
+    assert!(
+        matches!(err, gitbutler_git::Error::ForcePushProtection(_)),
+        "{err:?}"
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn force_push_with_protection_uses_the_lease_over_the_remote_tracking_branch() {
+    let tmp = tempfile::tempdir().unwrap();
+    let local = local_with_remote(tmp.path());
+    git(&local, &["push", "--quiet", "origin", "main"]);
+    let pushed = git(&local, &["rev-parse", "main"]);
+
+    // Someone else pushed to the remote, and we fetched it, so the remote tracking branch is up to date.
+    let other = tmp.path().join("other");
+    git(
+        tmp.path(),
+        &[
+            "clone",
+            "--quiet",
+            "--branch",
+            "main",
+            "remote.git",
+            other.to_str().unwrap(),
+        ],
+    );
+    commit(&other, "theirs");
+    git(&other, &["push", "--quiet", "origin", "main"]);
+    git(&local, &["fetch", "--quiet", "origin"]);
+    let theirs = git(&local, &["rev-parse", "origin/main"]);
+
+    git(&local, &["commit", "--quiet", "--amend", "-m", "ours"]);
+    let lease = |expected: &str| gitbutler_git::Lease {
+        refname: "refs/heads/main".into(),
+        expected: expected.into(),
+    };
+    let err = gitbutler_git::push_atomic(
+        &local,
+        TokioExecutor,
+        "origin",
+        &[refspec("main")],
+        true,
+        true,
+        &[lease(&pushed)],
+        no_prompt,
+        (),
+        vec![],
+    )
+    .await
+    .expect_err("the remote isn't where we last pushed it");
+    assert!(
+        matches!(err, gitbutler_git::Error::ForcePushProtection(_)),
+        "{err:?}"
+    );
+
+    gitbutler_git::push_atomic(
+        &local,
+        TokioExecutor,
+        "origin",
+        &[refspec("main")],
+        true,
+        true,
+        &[lease(&theirs)],
+        no_prompt,
+        (),
+        vec![],
+    )
+    .await
+    .expect("the remote is where the lease expects it");
+    let remote = tmp.path().join("remote.git");
+    assert_eq!(
+        git(&remote, &["rev-parse", "refs/heads/main"]),
+        git(&local, &["rev-parse", "main"])
+    );
+}
+
+async fn no_prompt(prompt: String, _extra: ()) -> Option<String> {
+    panic!("local pushes don't prompt, got: {prompt}")
+}
+
+fn refspec(branch: &str) -> RefSpec {
+    RefSpec::parse(format!("refs/heads/{branch}:refs/heads/{branch}")).unwrap()
+}
+
+/// Create a bare `remote.git` and a `local` repository with one commit on `main` in `root`,
+/// with the former set up as `origin` of the latter.
+fn local_with_remote(root: &Path) -> std::path::PathBuf {
+    git(root, &["init", "--quiet", "--bare", "remote.git"]);
+    git(root, &["init", "--quiet", "-b", "main", "local"]);
+    let local = root.join("local");
+    git(&local, &["remote", "add", "origin", "../remote.git"]);
+    commit(&local, "initial");
+    local
+}
+
+fn commit(repo: &Path, message: &str) {
+    git(
+        repo,
+        &[
+            "-c",
+            "user.name=author",
+            "-c",
+            "user.email=author@example.com",
+            "commit",
+            "--quiet",
+            "--allow-empty",
+            "-m",
+            message,
+        ],
+    );
+}
+
+/// Run `git` with `args` in `cwd`, assert it succeeds and return its trimmed stdout.
+fn git(cwd: &Path, args: &[&str]) -> String {
+    let out = Command::new("git")
+        .args(args)
+        .current_dir(cwd)
+        .env("GIT_AUTHOR_NAME", "author")
+        .env("GIT_AUTHOR_EMAIL", "author@example.com")
+        .env("GIT_COMMITTER_NAME", "committer")
+        .env("GIT_COMMITTER_EMAIL", "committer@example.com")
+        .output()
+        .unwrap();
+    assert!(
+        out.status.success(),
+        "git {args:?} failed: {}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    String::from_utf8(out.stdout).unwrap().trim().to_owned()
+}
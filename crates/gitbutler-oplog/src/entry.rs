@@ -38,6 +38,10 @@ pub struct SnapshotDetails {
     pub title: String,
     /// Additional text describing the snapshot
     pub body: Option<String>,
+    /// The name the user gave to the snapshot to find it again, like `before risky rebase`.
+    pub label: Option<String>,
+    /// Why the snapshot was taken automatically, if it wasn't taken for an operation performed by GitButler.
+    pub reason: Option<SnapshotReason>,
     /// Additional key value pairs that describe the snapshot
     pub trailers: Vec<Trailer>,
 }
@@ -50,6 +54,8 @@ impl SnapshotDetails {
             operation,
             title,
             body: None,
+            label: None,
+            reason: None,
             trailers: vec![],
         }
     }
@@ -57,6 +63,16 @@ impl SnapshotDetails {
         self.trailers = trailers;
         self
     }
+    /// Name the snapshot `label`, so it can be found by it.
+    pub fn with_label(mut self, label: impl Into<String>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+    /// Record that the snapshot was taken automatically for `reason`.
+    pub fn with_reason(mut self, reason: SnapshotReason) -> Self {
+        self.reason = Some(reason);
+        self
+    }
 }
 
 impl FromStr for SnapshotDetails {
@@ -90,14 +106,26 @@ impl FromStr for SnapshotDetails {
             .parse()
             .unwrap_or_default();
 
-        // remove the version and operation attributes from the trailers since they have dedicated fields
-        trailers.retain(|t| t.key != "Version" && t.key != "Operation");
+        let label = trailers
+            .iter()
+            .find(|t| t.key == "Label")
+            .map(|t| t.value.clone());
+
+        let reason = trailers
+            .iter()
+            .find(|t| t.key == "Reason")
+            .and_then(|t| t.value.parse().ok());
+
+        // remove the attributes from the trailers that have dedicated fields
+        trailers.retain(|t| !["Version", "Operation", "Label", "Reason"].contains(&t.key.as_str()));
 
         Ok(SnapshotDetails {
             version,
             operation,
             title,
             body,
+            label,
+            reason,
             trailers,
         })
     }
@@ -111,6 +139,19 @@ impl Display for SnapshotDetails {
         }
         writeln!(f, "Version: {}", self.version)?;
         writeln!(f, "Operation: {}", self.operation)?;
+        if let Some(label) = &self.label {
+            writeln!(
+                f,
+                "{}",
+                Trailer {
+                    key: "Label".into(),
+                    value: label.clone(),
+                }
+            )?;
+        }
+        if let Some(reason) = &self.reason {
+            writeln!(f, "Reason: {reason}")?;
+        }
         for line in &self.trailers {
             writeln!(f, "{line}")?;
         }
@@ -165,6 +206,15 @@ pub enum OperationKind {
     AutoHandleChangesAfter,
     SplitBranch,
     OnDemandSnapshot,
+    ApplyReviewSuggestions,
+    AutosquashStack,
+    RevertCommit,
+    UpdateStackIdentity,
+    ExternalChange,
+    RepairWorkspace,
+    AdoptExternalCommits,
+    ArchiveBranch,
+    ApplyPatchSeries,
     #[default]
     Unknown,
 }
@@ -181,6 +231,23 @@ impl fmt::Display for OperationKind {
     }
 }
 
+/// Why a snapshot was taken without GitButler performing an operation.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize, EnumString)]
+#[serde(rename_all = "camelCase")]
+pub enum SnapshotReason {
+    /// `HEAD` was moved away from the workspace, for instance by a `git checkout` or while `git rebase` is running.
+    HeadMoved,
+    /// The workspace reference was moved so it doesn't point to the workspace commit anymore,
+    /// for instance by committing to it or rebasing it with Git.
+    WorkspaceRefMoved,
+}
+
+impl fmt::Display for SnapshotReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        Debug::fmt(self, f)
+    }
+}
+
 #[derive(Debug, PartialEq, Clone, Copy, Serialize)]
 pub struct Version(pub u32);
 impl Default for Version {
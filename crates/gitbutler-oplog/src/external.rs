@@ -0,0 +1,82 @@
+//! Snapshots of the workspace taken when Git tools other than GitButler move `HEAD` or the workspace reference,
+//! so the state before they did can be restored.
+use anyhow::Result;
+use but_ctx::{Context, access::WorktreeWritePermission};
+use gitbutler_stack::VirtualBranchesHandle;
+
+use crate::{
+    OplogExt as _,
+    entry::{OperationKind, SnapshotDetails, SnapshotReason, Trailer},
+    state::OplogHandle,
+};
+
+const WORKSPACE_REFS: [&str; 2] = [
+    "refs/heads/gitbutler/workspace",
+    "refs/heads/gitbutler/integration",
+];
+const EDIT_REF: &str = "refs/heads/gitbutler/edit";
+
+/// Take a snapshot if `HEAD` or the workspace reference were moved by someone other than GitButler,
+/// before GitButler gets to reconcile its state with theirs.
+///
+/// Only one snapshot is taken for each such change, even if it takes many steps, like a `git rebase` does.
+/// Returns the id of the snapshot if one was taken.
+pub(crate) fn snapshot_external_change(
+    ctx: &Context,
+    perm: &mut WorktreeWritePermission,
+) -> Result<Option<git2::Oid>> {
+    let oplog_state = OplogHandle::new(&ctx.project_data_dir());
+    let Some((reason, head)) = unexpected_head(ctx)? else {
+        if oplog_state.external_change()?.is_some() {
+            oplog_state.set_external_change(None)?;
+        }
+        return Ok(None);
+    };
+    let key = format!("{reason} {head}");
+    if oplog_state.external_change()?.as_deref() == Some(key.as_str()) {
+        return Ok(None);
+    }
+
+    let details = SnapshotDetails::new(OperationKind::ExternalChange)
+        .with_reason(reason)
+        .with_trailers(vec![Trailer {
+            key: "head".into(),
+            value: head,
+        }]);
+    let snapshot_id = ctx.create_snapshot(details, perm)?;
+    oplog_state.set_external_change(Some(key))?;
+    tracing::info!(%reason, %snapshot_id, "took a snapshot after an external change");
+    Ok(Some(snapshot_id))
+}
+
+/// Return why `HEAD` isn't where GitButler left it, along with the name of the reference it points to,
+/// or `HEAD` if it's detached. Return `None` if it's as expected, or if GitButler doesn't manage the project.
+fn unexpected_head(ctx: &Context) -> Result<Option<(SnapshotReason, String)>> {
+    if VirtualBranchesHandle::new(ctx.project_data_dir())
+        .maybe_get_default_target()?
+        .is_none()
+    {
+        return Ok(None);
+    }
+    let repo = ctx.repo.get()?;
+    let head = repo.head()?;
+    if head.is_unborn() {
+        return Ok(None);
+    }
+    let head_name = head
+        .referent_name()
+        .map(|name| name.as_bstr().to_string())
+        .unwrap_or_else(|| "HEAD".into());
+    if head_name == EDIT_REF {
+        return Ok(None);
+    }
+    if !WORKSPACE_REFS.contains(&head_name.as_str()) {
+        return Ok(Some((SnapshotReason::HeadMoved, head_name)));
+    }
+    let tip = repo.head_commit()?;
+    if but_graph::projection::commit::is_managed_workspace_by_message(tip.message_raw()?) {
+        Ok(None)
+    } else {
+        Ok(Some((SnapshotReason::WorkspaceRefMoved, head_name)))
+    }
+}
@@ -0,0 +1,204 @@
+//! Follow a file through the rewrites of stack commits, using the commits recorded in snapshots.
+use std::collections::{HashMap, HashSet, hash_map::Entry};
+
+use anyhow::Result;
+use but_core::{commit::Headers, ref_metadata::StackId};
+use gix::bstr::{BStr, ByteSlice};
+use serde::Serialize;
+
+/// A change to a file, along with all versions of the commit that made it as it was amended or rebased.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileHistoryEntry {
+    /// The change-id all versions share, or `None` if the commit has no change-id and thus only one version.
+    pub change_id: Option<String>,
+    /// The id of the stack the most recent version belongs to.
+    pub stack_id: StackId,
+    /// The first line of the commit message of the most recent version.
+    pub title: String,
+    /// All versions of the commit that changed the file, the oldest first.
+    pub versions: Vec<FileVersion>,
+}
+
+/// A version of a commit that changed a file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileVersion {
+    /// The id of the commit.
+    #[serde(with = "but_serde::object_id")]
+    pub commit_id: gix::ObjectId,
+    /// The content of the file in this commit, or `None` if the commit deleted it.
+    #[serde(with = "but_serde::object_id_opt")]
+    pub blob_id: Option<gix::ObjectId>,
+    /// The first snapshot this version was seen in, or `None` if it was only seen in the current workspace.
+    #[serde(with = "but_serde::object_id_opt")]
+    pub snapshot_id: Option<gix::ObjectId>,
+    /// If `true`, this version is part of the current workspace.
+    pub is_current: bool,
+}
+
+/// A commit of a stack as recorded in a snapshot.
+struct RecordedCommit {
+    stack_id: StackId,
+    commit: gix::objs::Commit,
+    tree_id: gix::ObjectId,
+}
+
+/// Find all commits in the `snapshots` that change the file at `path`, and group the versions of each commit by change-id.
+///
+/// `snapshots` are `(snapshot_commit_id, snapshot_tree_id)` pairs, the oldest first, where the last one
+/// may have no commit as it represents the current state of the workspace.
+/// Return the changes to `path`, the most recent first.
+///
+/// Note that only the given `path` is followed, so renames end the history.
+pub(crate) fn file_history(
+    repo: &gix::Repository,
+    snapshots: &[(Option<gix::ObjectId>, gix::ObjectId)],
+    path: &BStr,
+) -> Result<Vec<FileHistoryEntry>> {
+    let path = gix::path::from_bstr(path);
+    let blob_at = |tree_id: gix::ObjectId| -> Result<Option<gix::ObjectId>> {
+        Ok(repo
+            .find_tree(tree_id)?
+            .lookup_entry_by_path(&path)?
+            .map(|entry| entry.object_id()))
+    };
+
+    // All commits by id, along with the index of the first snapshot they were seen in.
+    let mut commits: HashMap<gix::ObjectId, (RecordedCommit, usize)> = HashMap::new();
+    // The ids of all commits, in the order they were first seen.
+    let mut order = Vec::new();
+    for (snapshot_idx, (_, snapshot_tree)) in snapshots.iter().enumerate() {
+        for (commit_id, recorded) in read_commits(repo, *snapshot_tree)? {
+            if let Entry::Vacant(entry) = commits.entry(commit_id) {
+                entry.insert((recorded, snapshot_idx));
+                order.push(commit_id);
+            }
+        }
+    }
+    let current: HashSet<_> = match snapshots.last() {
+        Some((_, tree)) => read_commits(repo, *tree)?
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect(),
+        None => Default::default(),
+    };
+
+    let mut entries: Vec<(FileHistoryEntry, usize)> = Vec::new();
+    let mut entry_by_change_id: HashMap<String, usize> = HashMap::new();
+    for commit_id in order {
+        let (recorded, first_seen) = &commits[&commit_id];
+        let parent_tree = match recorded.commit.parents.first() {
+            Some(parent_id) => match commits.get(parent_id) {
+                Some((parent, _)) => Some(parent.tree_id),
+                None => match repo.try_find_object(*parent_id)? {
+                    Some(parent) => Some(parent.peel_to_tree()?.id),
+                    // Without the parent, it's unknown what the commit changed.
+                    None => continue,
+                },
+            },
+            None => None,
+        };
+        let blob_id = blob_at(recorded.tree_id)?;
+        let parent_blob_id = parent_tree.map(&blob_at).transpose()?.flatten();
+        if blob_id == parent_blob_id {
+            continue;
+        }
+
+        let change_id = Headers::try_from_commit(&recorded.commit)
+            .and_then(|hdr| hdr.change_id)
+            .map(|id| id.to_string());
+        let title = recorded
+            .commit
+            .message
+            .lines()
+            .next()
+            .unwrap_or_default()
+            .to_str_lossy()
+            .into_owned();
+        let version = FileVersion {
+            commit_id,
+            blob_id,
+            snapshot_id: snapshots
+                .get(*first_seen)
+                .and_then(|(snapshot_id, _)| *snapshot_id),
+            is_current: current.contains(&commit_id),
+        };
+        match change_id
+            .as_ref()
+            .and_then(|id| entry_by_change_id.get(id).copied())
+        {
+            Some(idx) => {
+                let (entry, last_seen) = &mut entries[idx];
+                entry.stack_id = recorded.stack_id;
+                entry.title = title;
+                entry.versions.push(version);
+                *last_seen = *first_seen;
+            }
+            None => {
+                if let Some(change_id) = &change_id {
+                    entry_by_change_id.insert(change_id.clone(), entries.len());
+                }
+                entries.push((
+                    FileHistoryEntry {
+                        change_id,
+                        stack_id: recorded.stack_id,
+                        title,
+                        versions: vec![version],
+                    },
+                    *first_seen,
+                ));
+            }
+        }
+    }
+
+    entries.sort_by_key(|(_, last_seen)| std::cmp::Reverse(*last_seen));
+    Ok(entries.into_iter().map(|(entry, _)| entry).collect())
+}
+
+/// Read the commits of all stacks recorded in the snapshot tree `snapshot_tree`, in no particular order.
+fn read_commits(
+    repo: &gix::Repository,
+    snapshot_tree: gix::ObjectId,
+) -> Result<Vec<(gix::ObjectId, RecordedCommit)>> {
+    let tree = repo.find_tree(snapshot_tree)?;
+    let Some(stacks) = tree.lookup_entry_by_path("virtual_branches")? else {
+        return Ok(Vec::new());
+    };
+    let mut out = Vec::new();
+    for stack in stacks.object()?.into_tree().iter() {
+        let stack = stack?;
+        // The workspace commit is stored here as well, but it's not part of any stack.
+        let Ok(stack_id) = stack.filename().to_str_lossy().parse::<StackId>() else {
+            continue;
+        };
+        let Some(commits_tree) = stack
+            .object()?
+            .into_tree()
+            .lookup_entry_by_path("commits")?
+        else {
+            continue;
+        };
+        for entry in commits_tree.object()?.into_tree().iter() {
+            let entry = entry?;
+            let commit_id = gix::ObjectId::from_hex(entry.filename())?;
+            let commit_tree = entry.object()?.into_tree();
+            let (Some(data), Some(tree)) = (
+                commit_tree.lookup_entry_by_path("commit")?,
+                commit_tree.lookup_entry_by_path("tree")?,
+            ) else {
+                continue;
+            };
+            let data = data.object()?.detach();
+            out.push((
+                commit_id,
+                RecordedCommit {
+                    stack_id,
+                    commit: gix::objs::CommitRef::from_bytes(&data.data)?.into_owned()?,
+                    tree_id: tree.object_id(),
+                },
+            ));
+        }
+    }
+    Ok(out)
+}
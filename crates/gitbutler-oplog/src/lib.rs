@@ -1,9 +1,14 @@
 pub mod entry;
+mod external;
+pub mod file_history;
+pub mod maintenance;
 mod oplog;
 pub use oplog::OplogExt;
+pub mod prune;
 mod reflog;
 mod snapshot;
 pub use snapshot::SnapshotExt;
+pub mod stack_diff;
 mod state;
 
 /// The name of the file holding our state, useful for watching for changes.
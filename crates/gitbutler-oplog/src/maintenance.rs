@@ -0,0 +1,210 @@
+//! Keep the object database of a project compact, as configured in the
+//! [maintenance settings](but_settings::app_settings::Maintenance).
+//!
+//! Snapshots and rebases leave many loose objects behind, and pruned snapshots become unreachable.
+//! Maintenance packs the loose objects, deletes unreachable ones once they are old enough and writes
+//! the commit-graph file, which speeds up walking the history.
+use std::{ffi::OsStr, path::Path, time::Duration};
+
+use anyhow::{Result, bail};
+use but_ctx::{
+    Context,
+    access::{OperationCategory, Priority, WorktreeWritePermission},
+};
+use but_settings::app_settings::{Maintenance as Settings, MaintenanceMode};
+use gix::bstr::ByteSlice as _;
+
+use crate::state::OplogHandle;
+
+/// Run all maintenance tasks on the repository of `ctx` according to `settings`, and wait for them to finish.
+///
+/// Nothing happens if maintenance is [turned off](MaintenanceMode::Off).
+pub(crate) fn run_maintenance(
+    ctx: &Context,
+    settings: &Settings,
+    _perm: &mut WorktreeWritePermission,
+) -> Result<()> {
+    if settings.mode == MaintenanceMode::Off {
+        return Ok(());
+    }
+    OplogHandle::new(&ctx.project_data_dir()).set_maintained_now()?;
+    run_tasks(&ctx.gitdir, settings.mode, prune_expiry_days(settings))
+}
+
+/// Start maintenance in the background if it's turned on and didn't run within the configured interval.
+/// Errors are logged, as they shouldn't fail the operation that triggered maintenance.
+///
+/// Maintenance is queued with low priority and holds exclusive worktree access while it runs,
+/// so it never runs while other operations write objects or move references.
+pub(crate) fn run_maintenance_if_due(ctx: &Context) {
+    let settings = &ctx.settings().maintenance;
+    if settings.mode == MaintenanceMode::Off {
+        return;
+    }
+    let oplog_state = OplogHandle::new(&ctx.project_data_dir());
+    let is_due = oplog_state
+        .maintained_at()
+        .map(|maintained_at| {
+            maintained_at
+                .elapsed()
+                .is_ok_and(|age| age >= interval(settings))
+        })
+        .unwrap_or(true);
+    if !is_due {
+        return;
+    }
+    // Mark it as done right away so concurrent operations don't start maintenance as well.
+    if let Err(err) = oplog_state.set_maintained_now() {
+        tracing::warn!("Could not record object database maintenance: {err:#}");
+        return;
+    }
+    let git_dir = ctx.gitdir.clone();
+    let mode = settings.mode;
+    let prune_expiry_days = prune_expiry_days(settings);
+    std::thread::spawn(move || {
+        let _guard = match but_core::sync::queue::queued_exclusive_worktree_access(
+            git_dir.clone(),
+            OperationCategory::Other,
+            Priority::Low,
+        ) {
+            Ok(guard) => guard,
+            Err(err) => {
+                tracing::warn!("Skipping object database maintenance: {err:#}");
+                return;
+            }
+        };
+        if let Err(err) = run_tasks(&git_dir, mode, prune_expiry_days) {
+            tracing::warn!("Object database maintenance failed: {err:#}");
+        }
+    });
+}
+
+/// The time between two runs of maintenance, which is at least an hour.
+fn interval(settings: &Settings) -> Duration {
+    Duration::from_secs(u64::from(settings.interval_hours.max(1)) * 60 * 60)
+}
+
+/// The age at which unreachable objects are deleted, which is at least a day so objects that were just written
+/// by an operation, but aren't referenced yet, are never deleted.
+fn prune_expiry_days(settings: &Settings) -> u32 {
+    settings.prune_expiry_days.max(1)
+}
+
+/// Run the maintenance tasks with Git in `dir`, which is the worktree or the `.git` directory of a repository.
+fn run_tasks(dir: &Path, mode: MaintenanceMode, prune_expiry_days: u32) -> Result<()> {
+    let expiry = format!("{prune_expiry_days}.days.ago");
+    let unpack_unreachable = format!("--unpack-unreachable={expiry}");
+    let prune_expire = format!("--expire={expiry}");
+    match mode {
+        MaintenanceMode::Off => return Ok(()),
+        MaintenanceMode::Incremental => {
+            run_git(dir, ["repack", "-d", "-l", "-q"])?;
+            run_git(dir, ["prune", prune_expire.as_str()])?;
+            run_git(dir, ["commit-graph", "write", "--reachable", "--split"])?;
+        }
+        MaintenanceMode::Aggressive => {
+            // Unreachable objects are unpacked instead of dropped so they are only deleted once they expire.
+            run_git(
+                dir,
+                [
+                    "repack",
+                    "-A",
+                    "-d",
+                    "-l",
+                    "-q",
+                    unpack_unreachable.as_str(),
+                ],
+            )?;
+            run_git(dir, ["prune", prune_expire.as_str()])?;
+            run_git(
+                dir,
+                ["commit-graph", "write", "--reachable", "--split=replace"],
+            )?;
+        }
+    }
+    tracing::info!(?mode, "maintained object database");
+    Ok(())
+}
+
+fn run_git<'a>(dir: &Path, args: impl IntoIterator<Item = &'a str> + Clone) -> Result<()> {
+    let output =
+        std::process::Command::from(gix::command::prepare(gix::path::env::exe_invocation()))
+            .current_dir(dir)
+            .args(args.clone().into_iter().map(OsStr::new))
+            .output()?;
+    if !output.status.success() {
+        bail!(
+            "`git {}` failed\n\n{}",
+            args.into_iter().collect::<Vec<_>>().join(" "),
+            output.stderr.to_str_lossy()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(interval_hours: u32, prune_expiry_days: u32) -> Settings {
+        Settings {
+            mode: MaintenanceMode::Incremental,
+            interval_hours,
+            prune_expiry_days,
+        }
+    }
+
+    #[test]
+    fn limits_are_clamped() {
+        assert_eq!(interval(&settings(0, 0)), Duration::from_secs(60 * 60));
+        assert_eq!(prune_expiry_days(&settings(0, 0)), 1);
+        assert_eq!(
+            interval(&settings(24, 14)),
+            Duration::from_secs(24 * 60 * 60)
+        );
+        assert_eq!(prune_expiry_days(&settings(24, 14)), 14);
+    }
+
+    #[test]
+    fn loose_objects_are_packed() -> Result<()> {
+        let tmp = tempfile::tempdir()?;
+        let repo = gix::init(tmp.path())?;
+        let unreachable = repo.write_blob("content")?.detach();
+        run_git(
+            tmp.path(),
+            [
+                "-c",
+                "user.name=Author",
+                "-c",
+                "user.email=author@example.com",
+                "commit",
+                "--allow-empty",
+                "-m",
+                "initial",
+            ],
+        )?;
+        let head = gix::open(tmp.path())?.head_id()?.detach();
+        let loose_path = |id: gix::ObjectId| {
+            let hex = id.to_hex().to_string();
+            repo.objects_dir().join(&hex[..2]).join(&hex[2..])
+        };
+        assert!(loose_path(head).exists());
+
+        for mode in [MaintenanceMode::Incremental, MaintenanceMode::Aggressive] {
+            run_tasks(tmp.path(), mode, 1)?;
+            assert!(
+                !loose_path(head).exists(),
+                "{mode:?}: reachable objects are packed"
+            );
+            assert!(
+                repo.objects_dir().join("info/commit-graphs").is_dir(),
+                "{mode:?}: the commit-graph was written"
+            );
+        }
+        assert!(
+            gix::open(tmp.path())?.find_object(unreachable).is_ok(),
+            "unreachable objects that didn't expire are kept"
+        );
+        Ok(())
+    }
+}
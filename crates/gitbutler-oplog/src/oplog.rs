@@ -6,7 +6,7 @@ use std::{
 };
 
 use anyhow::{Context as _, Result, anyhow, bail};
-use but_core::{RepositoryExt, TreeChange, diff::tree_changes};
+use but_core::{RepositoryExt, TreeChange, diff::tree_changes, sync::events::WorkspaceEvent};
 use but_ctx::{
     Context,
     access::{WorktreeReadPermission, WorktreeWritePermission},
@@ -18,7 +18,7 @@ use but_oxidize::{
 use git2::FileMode;
 use gitbutler_cherry_pick::RepositoryExtLite;
 use gitbutler_repo::{RepositoryExt as _, SignaturePurpose};
-use gitbutler_stack::{VirtualBranchesHandle, VirtualBranchesState};
+use gitbutler_stack::{StackId, VirtualBranchesHandle, VirtualBranchesState};
 use gix::{ObjectId, bstr::ByteSlice, prelude::ObjectIdExt};
 use tracing::instrument;
 
@@ -27,7 +27,15 @@ use super::{
     reflog::set_reference_to_oplog,
     state::OplogHandle,
 };
-use crate::{entry::Version, reflog::ReflogCommits};
+use crate::{
+    entry::Version,
+    external::snapshot_external_change,
+    file_history::FileHistoryEntry,
+    maintenance::run_maintenance_if_due,
+    prune::{PruneOutcome, prune_snapshots_if_due},
+    reflog::ReflogCommits,
+    stack_diff::{HUNK_ASSIGNMENTS_FILE, HunkAssignments, SnapshotDelta, StackDiff},
+};
 
 /// The maximum size of files to automatically start tracking, i.e. untracked files we pick up for tree-creation.
 /// **Inactive for now** while it's hard to tell if it's safe *not* to pick up everything.
@@ -40,6 +48,7 @@ const AUTO_TRACK_LIMIT_BYTES: u64 = 0;
 /// ```text
 /// .
 /// ├── conflicts/…
+/// ├── hunk_assignments.toml (if there are any)
 /// ├── index/
 /// ├── target_tree/…
 /// ├── virtual_branches
@@ -89,6 +98,30 @@ pub trait OplogExt {
         perm: &mut WorktreeWritePermission,
     ) -> Result<git2::Oid>;
 
+    /// Creates an on-demand snapshot named `label`, like `before risky rebase`, to make it easy to find and restore it later.
+    /// Named snapshots survive [pruning](Self::prune_snapshots).
+    ///
+    /// Returns the sha of the created snapshot commit.
+    fn create_named_snapshot(
+        &self,
+        label: &str,
+        perm: &mut WorktreeWritePermission,
+    ) -> Result<git2::Oid>;
+
+    /// Takes a snapshot if `HEAD` or the workspace reference were moved by Git tools other than GitButler,
+    /// like an external `git rebase` or the Git integration of an IDE, so the state before can be restored.
+    /// The [reason](crate::entry::SnapshotReason) for the snapshot is recorded in its details.
+    ///
+    /// Returns the sha of the snapshot commit if one was taken, which happens only once per external change.
+    fn snapshot_external_change(
+        &self,
+        perm: &mut WorktreeWritePermission,
+    ) -> Result<Option<git2::Oid>>;
+
+    /// Returns all snapshots that were named with [`create_named_snapshot`](Self::create_named_snapshot), the most recent first,
+    /// or only those named `label` if it is set.
+    fn list_named_snapshots(&self, label: Option<&str>) -> Result<Vec<Snapshot>>;
+
     /// Lists the snapshots that have been created for the given repository, up to the given limit,
     /// and with the most recent snapshot first, and at the end of the vec.
     ///
@@ -132,6 +165,73 @@ pub trait OplogExt {
     /// Gets a specific snapshot by its commit sha.
     fn get_snapshot(&self, sha: git2::Oid) -> Result<Snapshot>;
 
+    /// Reverts only the stack with `stack_id` to its state in the snapshot `snapshot_commit_id`, leaving all other
+    /// stacks as they are. Upon success, a new snapshot is created representing the state right before this call.
+    ///
+    /// This will restore the following:
+    ///  - The commits of the stack, recreating them from the snapshot if they don't exist anymore.
+    ///  - The entry of the stack in `virtual_branches.toml`, along with the references of its branches.
+    ///  - The hunk assignments to the stack.
+    ///
+    /// The working directory isn't touched, so the caller has to update the workspace commit to include the restored stack.
+    /// Returns the sha of the created revert snapshot commit.
+    fn restore_stack(
+        &self,
+        snapshot_commit_id: git2::Oid,
+        stack_id: StackId,
+        perm: &mut WorktreeWritePermission,
+    ) -> Result<git2::Oid>;
+
+    /// Compares the stacks, branches, commits and hunk assignments recorded in the snapshot `from_sha`
+    /// with the ones recorded in the snapshot `to_sha`, or with the current state of the workspace if it is `None`.
+    ///
+    /// This is useful to show what changed in the workspace since a given point in time.
+    fn stack_diff(
+        &self,
+        from_sha: git2::Oid,
+        to_sha: Option<git2::Oid>,
+        perm: &WorktreeReadPermission,
+    ) -> Result<StackDiff>;
+
+    /// Like [`stack_diff`](Self::stack_diff), but also compares the worktree recorded in the snapshot `from_sha`
+    /// with the one in `to_sha`, or with the current worktree if it is `None`.
+    ///
+    /// This is useful to preview everything that restoring a snapshot would change.
+    fn snapshot_delta(
+        &self,
+        from_sha: git2::Oid,
+        to_sha: Option<git2::Oid>,
+        perm: &WorktreeReadPermission,
+    ) -> Result<SnapshotDelta>;
+
+    /// Returns all changes to the file at `path` made by commits of stacks, as recorded in all snapshots and
+    /// the current state of the workspace, the most recent first.
+    ///
+    /// Unlike `git log --follow`, commits that were since amended or rebased are included, and all versions
+    /// of a commit are grouped by their change-id.
+    fn file_history(
+        &self,
+        path: &gix::bstr::BStr,
+        perm: &WorktreeReadPermission,
+    ) -> Result<Vec<FileHistoryEntry>>;
+
+    /// Removes the oldest snapshots that exceed the limits of the oplog settings, i.e. that are too old,
+    /// too many or take too much space, but always keeps the most recent snapshot and all named snapshots.
+    ///
+    /// The remaining snapshots are rewritten so the removed ones can be deleted by [maintenance](Self::run_maintenance),
+    /// which changes their ids.
+    /// Pruning also happens automatically after snapshots are created, at most once per hour, if `autoPrune`
+    /// is turned on in the oplog settings. That keeps the ids of the remaining snapshots, which is why it only
+    /// hides the removed snapshots and can't free the space they take.
+    fn prune_snapshots(&self, perm: &mut WorktreeWritePermission) -> Result<PruneOutcome>;
+
+    /// Packs loose objects, deletes unreachable objects older than the configured expiry, like those of pruned
+    /// snapshots, and writes the commit-graph file, as configured in the maintenance settings.
+    ///
+    /// This also happens automatically in the background after snapshots are created, at most once per
+    /// configured interval.
+    fn run_maintenance(&self, perm: &mut WorktreeWritePermission) -> Result<()>;
+
     /// Gets the sha of the last snapshot commit if present.
     fn oplog_head(&self) -> Result<Option<git2::Oid>>;
 }
@@ -147,13 +247,16 @@ impl OplogExt for Context {
         details: SnapshotDetails,
         perm: &mut WorktreeWritePermission,
     ) -> Result<git2::Oid> {
-        commit_snapshot(
+        let snapshot_commit_id = commit_snapshot(
             &self.project_data_dir(),
             &*self.git2_repo.get()?,
             snapshot_tree_id,
             details,
             perm,
-        )
+        )?;
+        prune_snapshots_if_due(self, perm);
+        run_maintenance_if_due(self);
+        Ok(snapshot_commit_id)
     }
 
     #[instrument(skip(self, details, perm), err(Debug))]
@@ -163,15 +266,62 @@ impl OplogExt for Context {
         perm: &mut WorktreeWritePermission,
     ) -> Result<git2::Oid> {
         let tree_id = prepare_snapshot(self, perm.read_permission())?;
-        commit_snapshot(
+        let snapshot_commit_id = commit_snapshot(
             &self.project_data_dir(),
             &*self.git2_repo.get()?,
             tree_id,
             details,
             perm,
+        )?;
+        prune_snapshots_if_due(self, perm);
+        run_maintenance_if_due(self);
+        Ok(snapshot_commit_id)
+    }
+
+    #[instrument(skip(self, perm), err(Debug))]
+    fn create_named_snapshot(
+        &self,
+        label: &str,
+        perm: &mut WorktreeWritePermission,
+    ) -> Result<git2::Oid> {
+        let label = label.trim();
+        if label.is_empty() {
+            bail!("The name of a snapshot must not be empty");
+        }
+        self.create_snapshot(
+            SnapshotDetails::new(OperationKind::OnDemandSnapshot).with_label(label),
+            perm,
         )
     }
 
+    #[instrument(skip(self, perm), err(Debug))]
+    fn snapshot_external_change(
+        &self,
+        perm: &mut WorktreeWritePermission,
+    ) -> Result<Option<git2::Oid>> {
+        snapshot_external_change(self, perm)
+    }
+
+    #[instrument(skip(self), err(Debug))]
+    fn list_named_snapshots(&self, label: Option<&str>) -> Result<Vec<Snapshot>> {
+        Ok(self
+            .list_snapshots(
+                usize::MAX,
+                None,
+                Vec::new(),
+                Some(vec![OperationKind::OnDemandSnapshot]),
+            )?
+            .into_iter()
+            .filter(|snapshot| {
+                let snapshot_label = snapshot.details.as_ref().and_then(|d| d.label.as_deref());
+                match label {
+                    Some(label) => snapshot_label == Some(label),
+                    None => snapshot_label.is_some(),
+                }
+            })
+            .collect())
+    }
+
     #[instrument(skip(self), err(Debug))]
     fn get_snapshot(&self, sha: git2::Oid) -> Result<Snapshot> {
         let repo = self.clone_repo_for_merging()?;
@@ -201,10 +351,10 @@ impl OplogExt for Context {
         include_kind: Option<Vec<OperationKind>>,
     ) -> Result<Vec<Snapshot>> {
         let repo = self.clone_repo_for_merging()?;
+        let oplog_state = OplogHandle::new(&self.project_data_dir());
         let traversal_root_id = git2_to_gix_object_id(match oplog_commit_id {
             Some(id) => id,
             None => {
-                let oplog_state = OplogHandle::new(&self.project_data_dir());
                 if let Some(id) = oplog_state.oplog_head()? {
                     id
                 } else {
@@ -213,14 +363,19 @@ impl OplogExt for Context {
             }
         })
         .attach(&repo);
+        let oldest_kept = oplog_state.oldest_kept()?.map(git2_to_gix_object_id);
 
         let mut snapshots = Vec::new();
+        // Snapshots older than the oldest one that is kept were pruned, except for named ones.
+        let mut is_pruned = false;
 
         for commit_info in traversal_root_id.ancestors().all()? {
             if snapshots.len() == limit {
                 break;
             }
             let commit_id = commit_info?.id();
+            let was_pruned = is_pruned;
+            is_pruned |= Some(commit_id) == oldest_kept;
             if oplog_commit_id.is_some() && commit_id == traversal_root_id {
                 continue;
             }
@@ -247,6 +402,13 @@ impl OplogExt for Context {
                 .to_str()
                 .ok()
                 .and_then(|msg| SnapshotDetails::from_str(msg).ok());
+            if was_pruned
+                && details
+                    .as_ref()
+                    .is_none_or(|details| details.label.is_none())
+            {
+                continue;
+            }
             let commit_time = gix_time_to_git2(commit.time()?);
             if let Some(details) = &details {
                 // Skip if this kind is excluded
@@ -286,6 +448,15 @@ impl OplogExt for Context {
         restore_snapshot(self, snapshot_commit_id, guard)
     }
 
+    fn restore_stack(
+        &self,
+        snapshot_commit_id: git2::Oid,
+        stack_id: StackId,
+        perm: &mut WorktreeWritePermission,
+    ) -> Result<git2::Oid> {
+        restore_stack(self, snapshot_commit_id, stack_id, perm)
+    }
+
     fn snapshot_diff(&self, sha: git2::Oid) -> Result<Vec<TreeChange>> {
         let gix_repo = self.clone_repo_for_merging()?;
         let repo = self.git2_repo.get()?;
@@ -306,6 +477,62 @@ impl OplogExt for Context {
         tree_changes(&gix_repo, old_wd_tree_id, wd_tree.id().to_gix())
     }
 
+    #[instrument(skip(self, perm), err(Debug))]
+    fn stack_diff(
+        &self,
+        from_sha: git2::Oid,
+        to_sha: Option<git2::Oid>,
+        perm: &WorktreeReadPermission,
+    ) -> Result<StackDiff> {
+        let repo = self.repo.get()?;
+        let snapshot_tree = |sha: git2::Oid| -> Result<gix::ObjectId> {
+            Ok(repo.find_commit(sha.to_gix())?.tree_id()?.detach())
+        };
+        let from_tree_id = snapshot_tree(from_sha)?;
+        let to_tree_id = match to_sha {
+            Some(sha) => snapshot_tree(sha)?,
+            None => prepare_snapshot(self, perm)?.to_gix(),
+        };
+        crate::stack_diff::stack_diff(&repo, from_tree_id, to_tree_id)
+    }
+
+    #[instrument(skip(self, perm), err(Debug))]
+    fn snapshot_delta(
+        &self,
+        from_sha: git2::Oid,
+        to_sha: Option<git2::Oid>,
+        perm: &WorktreeReadPermission,
+    ) -> Result<SnapshotDelta> {
+        let gix_repo = self.clone_repo_for_merging()?;
+        let snapshot_tree = |sha: git2::Oid| -> Result<gix::ObjectId> {
+            Ok(gix_repo.find_commit(sha.to_gix())?.tree_id()?.detach())
+        };
+        let from_tree_id = snapshot_tree(from_sha)?;
+        let from_worktree_id = tree_from_applied_vbranches(&gix_repo, from_sha, self)?.to_gix();
+        let (to_tree_id, to_worktree_id) = match to_sha {
+            Some(sha) => (
+                snapshot_tree(sha)?,
+                tree_from_applied_vbranches(&gix_repo, sha, self)?.to_gix(),
+            ),
+            None => {
+                let tree_id = prepare_snapshot(self, perm)?.to_gix();
+                let worktree_id = gix_repo
+                    .find_tree(tree_id)?
+                    .find_entry("worktree")
+                    .context("Failed to find workspace tree in snapshot")?
+                    .object_id();
+                (tree_id, worktree_id)
+            }
+        };
+        Ok(SnapshotDelta {
+            workspace: crate::stack_diff::stack_diff(&gix_repo, from_tree_id, to_tree_id)?,
+            worktree_changes: tree_changes(&gix_repo, Some(from_worktree_id), to_worktree_id)?
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+        })
+    }
+
     fn snapshot_workspace_tree(&self, sha: gix::ObjectId) -> Result<gix::ObjectId> {
         let repo = self.repo.get()?;
         let tree = repo.find_commit(sha)?.tree()?;
@@ -315,6 +542,39 @@ impl OplogExt for Context {
         Ok(workspace.object_id())
     }
 
+    #[instrument(skip(self, perm), err(Debug))]
+    fn file_history(
+        &self,
+        path: &gix::bstr::BStr,
+        perm: &WorktreeReadPermission,
+    ) -> Result<Vec<FileHistoryEntry>> {
+        let repo = self.clone_repo_for_merging()?;
+        let mut snapshots = self
+            .list_snapshots(usize::MAX, None, Vec::new(), None)?
+            .into_iter()
+            .rev()
+            .map(|snapshot| {
+                let commit_id = snapshot.commit_id.to_gix();
+                Ok((
+                    Some(commit_id),
+                    repo.find_commit(commit_id)?.tree_id()?.detach(),
+                ))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        snapshots.push((None, prepare_snapshot(self, perm)?.to_gix()));
+        crate::file_history::file_history(&repo, &snapshots, path)
+    }
+
+    #[instrument(skip(self, perm), err(Debug))]
+    fn prune_snapshots(&self, perm: &mut WorktreeWritePermission) -> Result<PruneOutcome> {
+        crate::prune::prune_snapshots(self, &self.settings().oplog, true, perm)
+    }
+
+    #[instrument(skip(self, perm), err(Debug))]
+    fn run_maintenance(&self, perm: &mut WorktreeWritePermission) -> Result<()> {
+        crate::maintenance::run_maintenance(self, &self.settings().maintenance, perm)
+    }
+
     /// Gets the sha of the last snapshot commit if present.
     fn oplog_head(&self) -> Result<Option<git2::Oid>> {
         let oplog_state = OplogHandle::new(&self.project_data_dir());
@@ -398,6 +658,15 @@ pub fn prepare_snapshot(
     tree_builder.insert("conflicts", conflicts_tree_id, FileMode::Tree.into())?;
     tree_builder.insert("virtual_branches.toml", vb_blob_id, FileMode::Blob.into())?;
 
+    // Hunk assignments live in the database, so remember them for comparisons, but don't fail the snapshot for them.
+    match hunk_assignments_blob(ctx, &repo) {
+        Ok(Some(blob_id)) => {
+            tree_builder.insert(HUNK_ASSIGNMENTS_FILE, blob_id, FileMode::Blob.into())?;
+        }
+        Ok(None) => {}
+        Err(err) => tracing::warn!("Could not store hunk assignments in snapshot: {err:#}"),
+    }
+
     // go through all virtual branches and create a subtree for each with the tree and any commits encoded
     let mut branches_tree_builder = repo.treebuilder(None)?;
     let mut head_tree_ids = Vec::new();
@@ -493,6 +762,17 @@ pub fn prepare_snapshot(
     Ok(tree_id)
 }
 
+/// Write all hunk assignments as blob, sorted for stable snapshot trees, or return `None` if there are none.
+fn hunk_assignments_blob(ctx: &Context, repo: &git2::Repository) -> Result<Option<git2::Oid>> {
+    let mut assignments = ctx.db.get()?.hunk_assignments().list_all()?;
+    if assignments.is_empty() {
+        return Ok(None);
+    }
+    assignments.sort_by(|a, b| (&a.path, &a.hunk_header).cmp(&(&b.path, &b.hunk_header)));
+    let content = toml::to_string(&HunkAssignments { assignments })?;
+    Ok(Some(repo.blob(content.as_bytes())?))
+}
+
 fn commit_snapshot(
     project_data_dir: &Path,
     repo: &git2::Repository,
@@ -526,6 +806,12 @@ fn commit_snapshot(
     oplog_state.set_oplog_head(snapshot_commit_id)?;
 
     set_reference_to_oplog(repo.path(), ReflogCommits::new(project_data_dir)?)?;
+    but_core::sync::events::publish(
+        project_data_dir,
+        WorkspaceEvent::SnapshotCreated {
+            snapshot_id: snapshot_commit_id.to_gix(),
+        },
+    );
 
     Ok(snapshot_commit_id)
 }
@@ -686,6 +972,8 @@ fn restore_snapshot(
         operation: OperationKind::RestoreFromSnapshot,
         title: "Restored from snapshot".to_string(),
         body: None,
+        label: None,
+        reason: None,
         trailers: vec![
             Trailer {
                 key: "restored_from".to_string(),
@@ -710,6 +998,150 @@ fn restore_snapshot(
     )
 }
 
+fn restore_stack(
+    ctx: &Context,
+    snapshot_commit_id: git2::Oid,
+    stack_id: StackId,
+    exclusive_access: &mut WorktreeWritePermission,
+) -> Result<git2::Oid> {
+    let git2_repo = ctx.git2_repo.get()?;
+    let repo = ctx.repo.get()?;
+
+    let before_restore_snapshot_result = prepare_snapshot(ctx, exclusive_access.read_permission());
+    let snapshot_commit = git2_repo.find_commit(snapshot_commit_id)?;
+    let snapshot_tree = snapshot_commit.tree()?;
+
+    let vb_toml_entry = snapshot_tree
+        .get_name("virtual_branches.toml")
+        .context("failed to get virtual_branches.toml blob")?;
+    let vb_toml_blob = git2_repo
+        .find_blob(vb_toml_entry.id())
+        .context("failed to convert virtual_branches tree entry to blob")?;
+    let vbs_from_toml: VirtualBranchesState = toml::from_str::<
+        virtual_branches_legacy_types::VirtualBranches,
+    >(from_utf8(vb_toml_blob.content())?)?
+    .into();
+    let mut stack = vbs_from_toml
+        .branches
+        .get(&stack_id)
+        .filter(|stack| stack.in_workspace)
+        .cloned()
+        .with_context(|| format!("Stack {stack_id} wasn't in the workspace in the snapshot"))?;
+
+    // Make sure the commits of the stack exist, they may have been garbage-collected since.
+    if let Some(commits_tree) = snapshot_tree
+        .get_path(Path::new(&format!("virtual_branches/{stack_id}/commits")))
+        .ok()
+        .map(|entry| git2_repo.find_tree(entry.id()))
+        .transpose()?
+    {
+        for commit_entry in commits_tree.iter() {
+            let Some(commit_id) = commit_entry.name() else {
+                continue;
+            };
+            let commit_oid = git2::Oid::from_str(commit_id)?;
+            if !repo.has_object(commit_oid.to_gix()) {
+                let new_commit_oid = deserialize_commit(&git2_repo, &commit_entry)?;
+                if new_commit_oid != commit_oid {
+                    bail!("commit id mismatch: failed to recreate a commit from its parts");
+                }
+            }
+        }
+    }
+
+    // Replace only this stack, keeping its place among the stacks of today.
+    let vb_state = VirtualBranchesHandle::new(ctx.project_data_dir());
+    if let Ok(current) = vb_state.get_stack(stack_id) {
+        stack.order = current.order;
+    }
+    let gix_repo = ctx.clone_repo_for_merging()?;
+    for branch in &stack.heads {
+        branch.set_reference_to_head_value(&gix_repo).ok();
+    }
+    vb_state.set_stack(stack)?;
+
+    if let Err(err) = restore_stack_assignments(ctx, &snapshot_tree, &git2_repo, stack_id) {
+        tracing::warn!("failed to restore hunk assignments of stack - ignoring: {err:#}");
+    }
+
+    let restored_operation = snapshot_commit
+        .message()
+        .and_then(|msg| SnapshotDetails::from_str(msg).ok())
+        .map(|d| d.operation.to_string())
+        .unwrap_or_default();
+
+    let before_restore_snapshot_tree_id = before_restore_snapshot_result?;
+    let restored_date_ms = snapshot_commit.time().seconds() * 1000;
+    let details = SnapshotDetails {
+        version: Default::default(),
+        operation: OperationKind::RestoreFromSnapshot,
+        title: "Restored stack from snapshot".to_string(),
+        body: None,
+        label: None,
+        reason: None,
+        trailers: vec![
+            Trailer {
+                key: "restored_from".to_string(),
+                value: snapshot_commit_id.to_string(),
+            },
+            Trailer {
+                key: "restored_operation".to_string(),
+                value: restored_operation,
+            },
+            Trailer {
+                key: "restored_date".to_string(),
+                value: restored_date_ms.to_string(),
+            },
+            Trailer {
+                key: "restored_stack".to_string(),
+                value: stack_id.to_string(),
+            },
+        ],
+    };
+    commit_snapshot(
+        &ctx.project_data_dir(),
+        &git2_repo,
+        before_restore_snapshot_tree_id,
+        details,
+        exclusive_access,
+    )
+}
+
+/// Assign the hunks that were assigned to `stack_id` in `snapshot_tree` to it again, and unassign the ones it
+/// didn't have back then. Assignments to other stacks are left alone.
+fn restore_stack_assignments(
+    ctx: &Context,
+    snapshot_tree: &git2::Tree,
+    repo: &git2::Repository,
+    stack_id: StackId,
+) -> Result<()> {
+    let stack_id = stack_id.to_string();
+    let snapshot_assignments = match snapshot_tree.get_name(HUNK_ASSIGNMENTS_FILE) {
+        Some(entry) => {
+            let blob = repo.find_blob(entry.id())?;
+            toml::from_str::<HunkAssignments<but_db::HunkAssignment>>(from_utf8(blob.content())?)?
+                .assignments
+        }
+        None => Vec::new(),
+    };
+    let mut db = ctx.db.get_mut()?;
+    let mut assignments = db.hunk_assignments().list_all()?;
+    for assignment in &mut assignments {
+        let was_assigned = snapshot_assignments.iter().any(|a| {
+            a.stack_id.as_deref() == Some(stack_id.as_str())
+                && a.path == assignment.path
+                && a.hunk_header == assignment.hunk_header
+        });
+        if was_assigned {
+            assignment.stack_id = Some(stack_id.clone());
+        } else if assignment.stack_id.as_deref() == Some(stack_id.as_str()) {
+            assignment.stack_id = None;
+        }
+    }
+    db.hunk_assignments_mut()?.set_all(assignments)?;
+    Ok(())
+}
+
 /// Restore the state of .git/base_merge_parent and .git/conflicts from the snapshot
 /// Will remove those files if they are not present in the snapshot
 fn restore_conflicts_tree(snapshot_tree: &git2::Tree, repo: &git2::Repository) -> Result<()> {
@@ -0,0 +1,261 @@
+//! Keep the operations log within the limits configured in the [oplog settings](but_settings::app_settings::Oplog),
+//! by dropping the oldest snapshots.
+use std::{
+    str::FromStr as _,
+    time::{Duration, SystemTime},
+};
+
+use anyhow::Result;
+use but_ctx::{Context, access::WorktreeWritePermission};
+use but_oxidize::{ObjectIdExt as _, OidExt as _};
+use but_settings::app_settings::Oplog as Limits;
+use gix::bstr::ByteSlice as _;
+use serde::Serialize;
+
+use crate::{
+    entry::SnapshotDetails,
+    reflog::{ReflogCommits, set_reference_to_oplog},
+    state::OplogHandle,
+};
+
+/// How long to wait before pruning again after snapshots were pruned automatically.
+const AUTO_PRUNE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// The result of [`prune_snapshots()`](crate::OplogExt::prune_snapshots()).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PruneOutcome {
+    /// The amount of snapshots that remain in the oplog.
+    pub snapshots_kept: usize,
+    /// The amount of snapshots that were removed from the oplog.
+    pub snapshots_pruned: usize,
+}
+
+/// Drop the oldest snapshots that exceed any of the `limits`, but always keep the most recent snapshot
+/// and all [named](crate::OplogExt::create_named_snapshot) snapshots.
+///
+/// Without `compact`, only the oldest snapshot that is kept is remembered, so all snapshot ids stay the same.
+/// Pruned snapshots are hidden then, but their objects remain reachable through the snapshots that are kept.
+/// With `compact`, all snapshots that are kept are rewritten to cut the chain of snapshot commits, which changes
+/// their ids, so the pruned snapshots become unreachable and are removed by the next
+/// [maintenance](crate::OplogExt::run_maintenance).
+pub(crate) fn prune_snapshots(
+    ctx: &Context,
+    limits: &Limits,
+    compact: bool,
+    _exclusive_access: &mut WorktreeWritePermission,
+) -> Result<PruneOutcome> {
+    let project_data_dir = ctx.project_data_dir();
+    let oplog_state = OplogHandle::new(&project_data_dir);
+    let Some(head) = oplog_state.oplog_head()? else {
+        return Ok(PruneOutcome::default());
+    };
+    let oldest_kept = oplog_state.oldest_kept()?.map(|id| id.to_gix());
+    let repo = ctx.repo.get()?;
+
+    // All snapshots that weren't pruned yet along with the time they were created and if they are named,
+    // the most recent one first, and the amount of commits in the chain.
+    let mut snapshots = Vec::new();
+    let mut num_commits = 0;
+    let mut is_pruned = false;
+    let mut next = Some(head.to_gix());
+    while let Some(id) = next {
+        let commit = repo.find_commit(id)?;
+        let is_named = commit
+            .message_raw()?
+            .to_str()
+            .ok()
+            .and_then(|msg| SnapshotDetails::from_str(msg).ok())
+            .is_some_and(|details| details.label.is_some());
+        if !is_pruned || is_named {
+            snapshots.push((id, commit.time()?.seconds, is_named));
+        }
+        is_pruned |= Some(id) == oldest_kept;
+        num_commits += 1;
+        next = commit.parent_ids().next().map(|id| id.detach());
+    }
+
+    let now = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)?
+        .as_secs() as i64;
+    let times: Vec<_> = snapshots.iter().map(|(_, time, _)| *time).collect();
+    let keep = snapshots_to_keep(&times, limits, now, |idx| {
+        added_bytes(
+            &*repo,
+            snapshots[idx].0,
+            snapshots.get(idx + 1).map(|s| s.0),
+        )
+    })?;
+    let kept: Vec<_> = snapshots
+        .iter()
+        .enumerate()
+        .filter(|(idx, (_, _, is_named))| *idx < keep || *is_named)
+        .map(|(_, (id, _, _))| *id)
+        .collect();
+    let outcome = PruneOutcome {
+        snapshots_kept: kept.len(),
+        snapshots_pruned: snapshots.len() - kept.len(),
+    };
+    oplog_state.set_pruned_now()?;
+
+    if compact && kept.len() < num_commits {
+        // Rewrite the kept snapshots, starting with the oldest one which loses its parent.
+        let mut parent = None;
+        for id in kept.iter().rev() {
+            let mut commit = repo.find_commit(*id)?.decode()?.into_owned()?;
+            commit.parents = parent.into_iter().collect();
+            parent = Some(repo.write_object(&commit)?.detach());
+        }
+        let new_head = parent.expect("at least the most recent snapshot is kept");
+        oplog_state.set_oplog_head(new_head.to_git2())?;
+        oplog_state.set_oldest_kept(None)?;
+        set_reference_to_oplog(repo.git_dir(), ReflogCommits::new(&project_data_dir)?)?;
+    } else if outcome.snapshots_pruned != 0 {
+        oplog_state.set_oldest_kept(Some(snapshots[keep - 1].0.to_git2()))?;
+    } else {
+        return Ok(outcome);
+    }
+
+    tracing::info!(
+        kept = outcome.snapshots_kept,
+        pruned = outcome.snapshots_pruned,
+        compact,
+        "pruned oplog snapshots"
+    );
+    Ok(outcome)
+}
+
+/// Prune snapshots according to the settings of `ctx` if automatic pruning is turned on,
+/// unless that was done recently or no limit is set.
+/// Snapshots aren't [compacted](prune_snapshots()), so the ids of the snapshots that are kept don't change.
+/// Errors are logged, as they shouldn't fail the operation that created a snapshot.
+pub(crate) fn prune_snapshots_if_due(ctx: &Context, perm: &mut WorktreeWritePermission) {
+    let limits = &ctx.settings().oplog;
+    if !limits.auto_prune {
+        return;
+    }
+    if limits.max_age_days == 0 && limits.max_snapshots == 0 && limits.max_size_mb == 0 {
+        return;
+    }
+    let is_due = OplogHandle::new(&ctx.project_data_dir())
+        .pruned_at()
+        .map(|pruned_at| {
+            pruned_at
+                .elapsed()
+                .is_ok_and(|age| age >= AUTO_PRUNE_INTERVAL)
+        })
+        .unwrap_or(true);
+    if !is_due {
+        return;
+    }
+    if let Err(err) = prune_snapshots(ctx, limits, false, perm) {
+        tracing::warn!("Could not prune oplog snapshots: {err:#}");
+    }
+}
+
+/// Return how many of the snapshots created at `times`, in seconds since epoch and with the most recent one first,
+/// are within `limits` at `now`.
+/// `added_bytes(idx)` returns the size of the data the snapshot at `idx` added to the one before it.
+fn snapshots_to_keep(
+    times: &[i64],
+    limits: &Limits,
+    now: i64,
+    mut added_bytes: impl FnMut(usize) -> Result<u64>,
+) -> Result<usize> {
+    let max_age_seconds = i64::from(limits.max_age_days) * 24 * 60 * 60;
+    let max_bytes = u64::from(limits.max_size_mb) * 1024 * 1024;
+    let mut total_bytes = 0;
+    let mut keep = 0;
+    for (idx, time) in times.iter().enumerate() {
+        // The most recent snapshot is always kept, so there is something to restore to.
+        if idx > 0 {
+            if limits.max_snapshots != 0 && idx >= limits.max_snapshots as usize {
+                break;
+            }
+            if limits.max_age_days != 0 && now - time > max_age_seconds {
+                break;
+            }
+        }
+        if limits.max_size_mb != 0 {
+            total_bytes += added_bytes(idx)?;
+            if idx > 0 && total_bytes > max_bytes {
+                break;
+            }
+        }
+        keep += 1;
+    }
+    Ok(keep)
+}
+
+/// Return the size of the blobs that the snapshot `id` has, but its `previous` snapshot doesn't.
+fn added_bytes(
+    repo: &gix::Repository,
+    id: gix::ObjectId,
+    previous: Option<gix::ObjectId>,
+) -> Result<u64> {
+    let tree = repo.find_commit(id)?.tree()?;
+    let previous_tree = previous
+        .map(|id| repo.find_commit(id)?.tree())
+        .transpose()?;
+    let mut bytes = 0;
+    for change in repo.diff_tree_to_tree(previous_tree.as_ref(), &tree, None)? {
+        if matches!(
+            change,
+            gix::object::tree::diff::ChangeDetached::Deletion { .. }
+        ) || !change.entry_mode().is_blob()
+        {
+            continue;
+        }
+        bytes += repo.find_header(change.id().to_owned())?.size();
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: i64 = 24 * 60 * 60;
+
+    fn limits(max_age_days: u32, max_snapshots: u32, max_size_mb: u32) -> Limits {
+        Limits {
+            max_age_days,
+            max_snapshots,
+            max_size_mb,
+            auto_prune: false,
+        }
+    }
+
+    fn keep(times: &[i64], limits: &Limits, sizes_mb: &[u64]) -> usize {
+        snapshots_to_keep(times, limits, 100 * DAY, |idx| {
+            Ok(sizes_mb[idx] * 1024 * 1024)
+        })
+        .expect("sizes don't fail")
+    }
+
+    #[test]
+    fn unlimited() {
+        let times = [100 * DAY, 10 * DAY, 0];
+        assert_eq!(keep(&times, &limits(0, 0, 0), &[1, 1, 1]), 3);
+    }
+
+    #[test]
+    fn by_count_age_and_size() {
+        let times = [100 * DAY, 99 * DAY, 90 * DAY, 50 * DAY];
+        let sizes = [1, 1, 1, 1];
+        assert_eq!(keep(&times, &limits(0, 2, 0), &sizes), 2);
+        assert_eq!(keep(&times, &limits(30, 0, 0), &sizes), 3);
+        assert_eq!(keep(&times, &limits(0, 0, 2), &sizes), 2);
+        assert_eq!(
+            keep(&times, &limits(30, 3, 1), &sizes),
+            1,
+            "the strictest limit wins"
+        );
+    }
+
+    #[test]
+    fn the_most_recent_snapshot_is_always_kept() {
+        let times = [0, 0];
+        assert_eq!(keep(&times, &limits(1, 0, 1), &[5, 5]), 1);
+    }
+}
@@ -56,13 +56,6 @@ impl ReflogCommits {
 ///
 /// The reflog entry is continuously updated to refer to the current target and oplog head commits.
 pub fn set_reference_to_oplog(git_dir: &Path, reflog_commits: ReflogCommits) -> Result<()> {
-    let reflog_file_path = git_dir
-        .join("logs")
-        .join("refs")
-        .join("heads")
-        .join("gitbutler")
-        .join("target");
-
     let mut repo = gix::open_opts(
         git_dir,
         // We may override the username as we only write a specific commit log, unrelated to the user.
@@ -86,6 +79,14 @@ pub fn set_reference_to_oplog(git_dir: &Path, reflog_commits: ReflogCommits) ->
         )?;
     }
 
+    // Branches and their reflogs are shared by all worktrees, so they are in the common dir.
+    let reflog_file_path = repo
+        .common_dir()
+        .join("logs")
+        .join("refs")
+        .join("heads")
+        .join("gitbutler")
+        .join("target");
     let content = build_reflog_content(
         &[
             Some(reflog_commits.target),
@@ -0,0 +1,530 @@
+//! Compare the structure of the workspace, i.e. its stacks, branches, commits and hunk assignments,
+//! as recorded in two snapshots.
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use anyhow::{Context as _, Result};
+use but_core::{commit::Headers, ref_metadata::StackId};
+use but_meta::virtual_branches_legacy_types;
+use gix::bstr::ByteSlice;
+use serde::{Deserialize, Serialize};
+
+/// The path of the blob with hunk assignments in a snapshot tree.
+pub(crate) const HUNK_ASSIGNMENTS_FILE: &str = "hunk_assignments.toml";
+
+/// What changed in the workspace between two snapshots.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StackDiff {
+    /// Stacks that were applied to the workspace.
+    pub stacks_added: Vec<StackRef>,
+    /// Stacks that were removed from the workspace.
+    pub stacks_removed: Vec<StackRef>,
+    /// Branches that were added to stacks, including the ones of added stacks.
+    pub branches_added: Vec<BranchRef>,
+    /// Branches that were removed from stacks, including the ones of removed stacks.
+    pub branches_removed: Vec<BranchRef>,
+    /// Branches that exist in both snapshots, but point to another commit.
+    pub refs_moved: Vec<RefMove>,
+    /// Commits that are new.
+    pub commits_added: Vec<CommitRef>,
+    /// Commits that are gone, without a rewritten version.
+    pub commits_removed: Vec<CommitRef>,
+    /// Commits that were rewritten, like by amending, rewording or rebasing them.
+    pub commits_rewritten: Vec<RewrittenCommit>,
+    /// Uncommitted hunks that were assigned to another stack.
+    pub assignments_moved: Vec<AssignmentMove>,
+}
+
+impl StackDiff {
+    /// Return `true` if nothing changed.
+    pub fn is_empty(&self) -> bool {
+        self == &StackDiff::default()
+    }
+}
+
+/// A stack as it was seen in a snapshot.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StackRef {
+    /// The id of the stack.
+    pub stack_id: StackId,
+    /// The name of its top-most branch.
+    pub name: String,
+}
+
+/// A branch of a stack as it was seen in a snapshot.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BranchRef {
+    /// The id of the stack the branch belongs to.
+    pub stack_id: StackId,
+    /// The short name of the branch.
+    pub name: String,
+}
+
+/// A branch whose head changed between two snapshots.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RefMove {
+    /// The branch that moved.
+    pub branch: BranchRef,
+    /// The commit the branch pointed to before.
+    #[serde(with = "but_serde::object_id")]
+    pub from: gix::ObjectId,
+    /// The commit the branch points to now.
+    #[serde(with = "but_serde::object_id")]
+    pub to: gix::ObjectId,
+}
+
+/// A commit of a stack as it was seen in a snapshot.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitRef {
+    /// The id of the stack the commit belongs to.
+    pub stack_id: StackId,
+    /// The branch the commit belongs to, if it could be determined.
+    pub branch: Option<String>,
+    /// The id of the commit.
+    #[serde(with = "but_serde::object_id")]
+    pub commit_id: gix::ObjectId,
+    /// The first line of the commit message.
+    pub title: String,
+}
+
+/// A commit whose id changed, while its change-id stayed the same.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RewrittenCommit {
+    /// The commit before it was rewritten.
+    pub old: CommitRef,
+    /// The commit after it was rewritten.
+    pub new: CommitRef,
+    /// The change-id both commits share.
+    pub change_id: String,
+}
+
+/// An uncommitted hunk that was assigned to another stack.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AssignmentMove {
+    /// The path of the file the hunk belongs to.
+    pub path: String,
+    /// The header of the hunk, or `None` if the whole file is assigned.
+    pub hunk_header: Option<String>,
+    /// The stack the hunk was assigned to, or `None` if it was unassigned.
+    pub from: Option<StackId>,
+    /// The stack the hunk is assigned to now, or `None` if it is unassigned.
+    pub to: Option<StackId>,
+}
+
+/// Everything that changed between two snapshots, to preview what restoring one of them would do.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotDelta {
+    /// What changed in the structure of the workspace.
+    #[serde(flatten)]
+    pub workspace: StackDiff,
+    /// The files that changed in the worktree.
+    pub worktree_changes: Vec<but_core::ui::TreeChange>,
+}
+
+/// The data we store to remember hunk assignments in a snapshot.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct HunkAssignments<T> {
+    pub assignments: Vec<T>,
+}
+
+#[derive(Deserialize)]
+struct Assignment {
+    path: String,
+    hunk_header: Option<String>,
+    stack_id: Option<String>,
+}
+
+/// The workspace structure as recorded in a snapshot.
+struct State {
+    stacks: Vec<(StackRef, Vec<BranchRef>)>,
+    /// The commit each branch points to.
+    heads: HashMap<BranchRef, gix::ObjectId>,
+    commits: Vec<(CommitRef, Option<String>)>,
+    /// Assignments by `(path, hunk_header)`, or `None` if the snapshot didn't record them.
+    assignments: Option<BTreeMap<(String, Option<String>), Option<StackId>>>,
+}
+
+/// Compare the workspace as recorded in the snapshot tree `from` with the one in the snapshot tree `to`.
+pub(crate) fn stack_diff(
+    repo: &gix::Repository,
+    from: gix::ObjectId,
+    to: gix::ObjectId,
+) -> Result<StackDiff> {
+    Ok(diff_states(read_state(repo, from)?, read_state(repo, to)?))
+}
+
+fn diff_states(from: State, to: State) -> StackDiff {
+    let mut out = StackDiff::default();
+
+    let stack_ids = |state: &State| -> HashSet<StackId> {
+        state
+            .stacks
+            .iter()
+            .map(|(stack, _)| stack.stack_id)
+            .collect()
+    };
+    let (from_stacks, to_stacks) = (stack_ids(&from), stack_ids(&to));
+    out.stacks_removed = from
+        .stacks
+        .iter()
+        .filter(|(stack, _)| !to_stacks.contains(&stack.stack_id))
+        .map(|(stack, _)| stack.clone())
+        .collect();
+    out.stacks_added = to
+        .stacks
+        .iter()
+        .filter(|(stack, _)| !from_stacks.contains(&stack.stack_id))
+        .map(|(stack, _)| stack.clone())
+        .collect();
+
+    let branches = |state: &State| -> Vec<BranchRef> {
+        state
+            .stacks
+            .iter()
+            .flat_map(|(_, branches)| branches.iter().cloned())
+            .collect()
+    };
+    let (from_branches, to_branches) = (branches(&from), branches(&to));
+    out.branches_removed = from_branches
+        .iter()
+        .filter(|branch| !to_branches.contains(branch))
+        .cloned()
+        .collect();
+    out.branches_added = to_branches
+        .iter()
+        .filter(|branch| !from_branches.contains(branch))
+        .cloned()
+        .collect();
+    out.refs_moved = to_branches
+        .iter()
+        .filter_map(|branch| {
+            let (from, to) = (from.heads.get(branch)?, to.heads.get(branch)?);
+            (from != to).then(|| RefMove {
+                branch: branch.clone(),
+                from: *from,
+                to: *to,
+            })
+        })
+        .collect();
+
+    let from_ids: HashSet<_> = from.commits.iter().map(|(c, _)| c.commit_id).collect();
+    let to_ids: HashSet<_> = to.commits.iter().map(|(c, _)| c.commit_id).collect();
+    let to_by_change_id: HashMap<&str, &CommitRef> = to
+        .commits
+        .iter()
+        .filter(|(c, _)| !from_ids.contains(&c.commit_id))
+        .filter_map(|(c, change_id)| change_id.as_deref().map(|id| (id, c)))
+        .collect();
+    let mut rewritten_to = HashSet::new();
+    for (commit, change_id) in from
+        .commits
+        .iter()
+        .filter(|(c, _)| !to_ids.contains(&c.commit_id))
+    {
+        match change_id
+            .as_deref()
+            .and_then(|id| to_by_change_id.get(id).map(|new| (id, *new)))
+        {
+            Some((change_id, new)) => {
+                rewritten_to.insert(new.commit_id);
+                out.commits_rewritten.push(RewrittenCommit {
+                    old: commit.clone(),
+                    new: new.clone(),
+                    change_id: change_id.to_owned(),
+                });
+            }
+            None => out.commits_removed.push(commit.clone()),
+        }
+    }
+    out.commits_added = to
+        .commits
+        .iter()
+        .filter(|(c, _)| !from_ids.contains(&c.commit_id) && !rewritten_to.contains(&c.commit_id))
+        .map(|(c, _)| c.clone())
+        .collect();
+
+    if let (Some(from), Some(to)) = (from.assignments, to.assignments) {
+        for ((path, hunk_header), to_stack) in to {
+            match from.get(&(path.clone(), hunk_header.clone())) {
+                Some(from_stack) if *from_stack != to_stack => {
+                    out.assignments_moved.push(AssignmentMove {
+                        path,
+                        hunk_header,
+                        from: *from_stack,
+                        to: to_stack,
+                    })
+                }
+                _ => {}
+            }
+        }
+    }
+    out
+}
+
+fn read_state(repo: &gix::Repository, snapshot_tree: gix::ObjectId) -> Result<State> {
+    let tree = repo.find_tree(snapshot_tree)?;
+    let vb_toml = tree
+        .lookup_entry_by_path("virtual_branches.toml")?
+        .context("failed to get virtual_branches.toml blob")?
+        .object()?
+        .detach();
+    let vb: virtual_branches_legacy_types::VirtualBranches =
+        toml::from_str(vb_toml.data.to_str()?)?;
+
+    let mut stacks: Vec<_> = vb
+        .branches
+        .into_values()
+        .filter(|stack| stack.in_workspace)
+        .collect();
+    stacks.sort_by_key(|stack| stack.order);
+
+    let mut out = State {
+        stacks: Vec::new(),
+        heads: HashMap::new(),
+        commits: Vec::new(),
+        assignments: None,
+    };
+    for stack in stacks {
+        let heads: Vec<_> = stack.heads.iter().filter(|head| !head.archived).collect();
+        let stack_ref = StackRef {
+            stack_id: stack.id,
+            name: heads
+                .last()
+                .map(|head| head.name.clone())
+                .unwrap_or_default(),
+        };
+        let branches: Vec<_> = heads
+            .iter()
+            .map(|head| BranchRef {
+                stack_id: stack.id,
+                name: head.name.clone(),
+            })
+            .collect();
+        for (branch, head) in branches.iter().zip(&heads) {
+            out.heads.insert(branch.clone(), head.head);
+        }
+        out.stacks.push((stack_ref, branches));
+
+        let Some(commits_tree) =
+            tree.lookup_entry_by_path(format!("virtual_branches/{}/commits", stack.id))?
+        else {
+            continue;
+        };
+        // Commits by id, along with their first parent and change-id.
+        let mut commits: HashMap<
+            gix::ObjectId,
+            (CommitRef, Option<gix::ObjectId>, Option<String>),
+        > = HashMap::new();
+        for entry in commits_tree.object()?.into_tree().iter() {
+            let entry = entry?;
+            let commit_id = gix::ObjectId::from_hex(entry.filename())?;
+            let Some(data) = entry.object()?.into_tree().lookup_entry_by_path("commit")? else {
+                continue;
+            };
+            let data = data.object()?.detach();
+            let commit = gix::objs::CommitRef::from_bytes(&data.data)?.into_owned()?;
+            let change_id = Headers::try_from_commit(&commit)
+                .and_then(|hdr| hdr.change_id)
+                .map(|id| id.to_string());
+            commits.insert(
+                commit_id,
+                (
+                    CommitRef {
+                        stack_id: stack.id,
+                        branch: None,
+                        commit_id,
+                        title: commit
+                            .message
+                            .lines()
+                            .next()
+                            .unwrap_or_default()
+                            .to_str_lossy()
+                            .into_owned(),
+                    },
+                    commit.parents.first().copied(),
+                    change_id,
+                ),
+            );
+        }
+
+        // Attribute commits to branches bottom-up, so each branch only claims what's not below it.
+        for head in &heads {
+            let mut cur = Some(head.head);
+            while let Some((commit, parent, _)) = cur.and_then(|id| commits.get_mut(&id)) {
+                if commit.branch.is_some() {
+                    break;
+                }
+                commit.branch = Some(head.name.clone());
+                cur = *parent;
+            }
+        }
+        let mut commits: Vec<_> = commits
+            .into_values()
+            .map(|(commit, _, change_id)| (commit, change_id))
+            .collect();
+        commits.sort_by_key(|(commit, _)| commit.commit_id);
+        out.commits.extend(commits);
+    }
+
+    if let Some(entry) = tree.lookup_entry_by_path(HUNK_ASSIGNMENTS_FILE)? {
+        let blob = entry.object()?.detach();
+        let file: HunkAssignments<Assignment> = toml::from_str(blob.data.to_str()?)?;
+        out.assignments = Some(
+            file.assignments
+                .into_iter()
+                .map(|a| {
+                    let stack_id = a.stack_id.and_then(|id| id.parse().ok());
+                    ((a.path, a.hunk_header), stack_id)
+                })
+                .collect(),
+        );
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stack(n: u8, branches: &[&str]) -> (StackRef, Vec<BranchRef>) {
+        let stack_id = StackId::from_number_for_testing(n as u128);
+        (
+            StackRef {
+                stack_id,
+                name: branches.last().unwrap().to_string(),
+            },
+            branches
+                .iter()
+                .map(|name| BranchRef {
+                    stack_id,
+                    name: name.to_string(),
+                })
+                .collect(),
+        )
+    }
+
+    fn commit(stack: u8, id: u8, change_id: Option<&str>) -> (CommitRef, Option<String>) {
+        (
+            CommitRef {
+                stack_id: StackId::from_number_for_testing(stack as u128),
+                branch: Some("a".into()),
+                commit_id: gix::ObjectId::from_bytes_or_panic(&[id; 20]),
+                title: format!("commit {id}"),
+            },
+            change_id.map(ToOwned::to_owned),
+        )
+    }
+
+    fn state(
+        stacks: Vec<(StackRef, Vec<BranchRef>)>,
+        commits: Vec<(CommitRef, Option<String>)>,
+    ) -> State {
+        State {
+            stacks,
+            heads: HashMap::new(),
+            commits,
+            assignments: None,
+        }
+    }
+
+    #[test]
+    fn unchanged() {
+        let from = state(vec![stack(1, &["a"])], vec![commit(1, 1, Some("x"))]);
+        let to = state(vec![stack(1, &["a"])], vec![commit(1, 1, Some("x"))]);
+        assert!(diff_states(from, to).is_empty());
+    }
+
+    #[test]
+    fn stacks_and_branches() {
+        let from = state(vec![stack(1, &["a"]), stack(2, &["b"])], vec![]);
+        let to = state(vec![stack(1, &["a", "a2"]), stack(3, &["c"])], vec![]);
+        let diff = diff_states(from, to);
+        assert_eq!(diff.stacks_removed, [stack(2, &["b"]).0]);
+        assert_eq!(diff.stacks_added, [stack(3, &["c"]).0]);
+        assert_eq!(diff.branches_removed, stack(2, &["b"]).1);
+        let names: Vec<_> = diff
+            .branches_added
+            .iter()
+            .map(|b| b.name.as_str())
+            .collect();
+        assert_eq!(names, ["a2", "c"]);
+    }
+
+    #[test]
+    fn refs_moved() {
+        let oid = |n: u8| gix::ObjectId::from_bytes_or_panic(&[n; 20]);
+        let branch = |name: &str| stack(1, &[name]).1.remove(0);
+        let mut from = state(vec![stack(1, &["a", "b", "c"])], vec![]);
+        from.heads = [
+            (branch("a"), oid(1)),
+            (branch("b"), oid(2)),
+            (branch("c"), oid(3)),
+        ]
+        .into();
+        let mut to = state(vec![stack(1, &["a", "b"])], vec![]);
+        to.heads = [(branch("a"), oid(1)), (branch("b"), oid(4))].into();
+        let diff = diff_states(from, to);
+        assert_eq!(
+            diff.refs_moved,
+            [RefMove {
+                branch: branch("b"),
+                from: oid(2),
+                to: oid(4),
+            }],
+            "removed branches aren't moved"
+        );
+    }
+
+    #[test]
+    fn commits_are_matched_by_change_id() {
+        let from = state(
+            vec![stack(1, &["a"])],
+            vec![
+                commit(1, 1, Some("x")),
+                commit(1, 2, Some("y")),
+                commit(1, 3, None),
+            ],
+        );
+        let to = state(
+            vec![stack(1, &["a"])],
+            vec![
+                commit(1, 1, Some("x")),
+                commit(1, 4, Some("y")),
+                commit(1, 5, None),
+            ],
+        );
+        let diff = diff_states(from, to);
+        assert_eq!(diff.commits_rewritten.len(), 1);
+        assert_eq!(diff.commits_rewritten[0].old, commit(1, 2, None).0);
+        assert_eq!(diff.commits_rewritten[0].new, commit(1, 4, None).0);
+        assert_eq!(diff.commits_removed, [commit(1, 3, None).0]);
+        assert_eq!(diff.commits_added, [commit(1, 5, None).0]);
+    }
+
+    #[test]
+    fn assignments_moved_between_stacks() {
+        let id = |n: u128| Some(StackId::from_number_for_testing(n));
+        let key = |path: &str| (path.to_owned(), Some("-1,1 +1,1".to_owned()));
+        let mut from = state(vec![], vec![]);
+        from.assignments = Some([(key("a"), id(1)), (key("b"), id(1)), (key("c"), None)].into());
+        let mut to = state(vec![], vec![]);
+        to.assignments = Some([(key("a"), id(1)), (key("b"), id(2)), (key("d"), id(2))].into());
+        let diff = diff_states(from, to);
+        assert_eq!(
+            diff.assignments_moved,
+            [AssignmentMove {
+                path: "b".into(),
+                hunk_header: Some("-1,1 +1,1".into()),
+                from: id(1),
+                to: id(2),
+            }]
+        );
+    }
+}
@@ -28,19 +28,42 @@ pub struct Oplog {
     /// This is the sha of the last oplog commit
     #[serde(with = "but_serde::oid_opt", default)]
     pub head_sha: Option<git2::Oid>,
+    /// The sha of the oldest snapshot that was kept when snapshots were last pruned without rewriting them.
+    /// Older snapshots are pruned, unless they are named.
+    #[serde(with = "but_serde::oid_opt", default)]
+    pub oldest_kept_sha: Option<git2::Oid>,
     /// The time when the last snapshot was created. Seconds since Epoch
     #[serde(
         deserialize_with = "unfailing_system_time_deserialize",
         default = "unix_epoch"
     )]
     pub modified_at: SystemTime,
+    /// The time when snapshots were last pruned.
+    #[serde(
+        deserialize_with = "unfailing_system_time_deserialize",
+        default = "unix_epoch"
+    )]
+    pub pruned_at: SystemTime,
+    /// The time when the object database was last maintained.
+    #[serde(
+        deserialize_with = "unfailing_system_time_deserialize",
+        default = "unix_epoch"
+    )]
+    pub maintained_at: SystemTime,
+    /// The unexpected state of `HEAD` that a snapshot was last taken for, to take only one snapshot per external change.
+    #[serde(default)]
+    pub external_change: Option<String>,
 }
 
 impl Default for Oplog {
     fn default() -> Self {
         Self {
             head_sha: None,
+            oldest_kept_sha: None,
             modified_at: SystemTime::UNIX_EPOCH,
+            pruned_at: SystemTime::UNIX_EPOCH,
+            maintained_at: SystemTime::UNIX_EPOCH,
+            external_change: None,
         }
     }
 }
@@ -75,6 +98,78 @@ impl OplogHandle {
         Ok(oplog.head_sha)
     }
 
+    /// Persists the oldest snapshot that is kept, or `None` if all snapshots are kept.
+    ///
+    /// Errors if the file cannot be read or written.
+    pub fn set_oldest_kept(&self, sha: Option<git2::Oid>) -> Result<()> {
+        let mut oplog = self.read_file()?;
+        oplog.oldest_kept_sha = sha;
+        self.write_file(oplog)?;
+        Ok(())
+    }
+
+    /// Gets the oldest snapshot that is kept, if snapshots were pruned without rewriting them.
+    ///
+    /// Errors if the file cannot be read or written.
+    pub fn oldest_kept(&self) -> Result<Option<git2::Oid>> {
+        let oplog = self.read_file()?;
+        Ok(oplog.oldest_kept_sha)
+    }
+
+    /// Gets the time when snapshots were last pruned.
+    ///
+    /// Errors if the file cannot be read or written.
+    pub fn pruned_at(&self) -> Result<SystemTime> {
+        let oplog = self.read_file()?;
+        Ok(oplog.pruned_at)
+    }
+
+    /// Persists that snapshots were pruned just now.
+    ///
+    /// Errors if the file cannot be read or written.
+    pub fn set_pruned_now(&self) -> Result<()> {
+        let mut oplog = self.read_file()?;
+        oplog.pruned_at = SystemTime::now();
+        self.write_file(oplog)?;
+        Ok(())
+    }
+
+    /// Gets the time when the object database was last maintained.
+    ///
+    /// Errors if the file cannot be read or written.
+    pub fn maintained_at(&self) -> Result<SystemTime> {
+        let oplog = self.read_file()?;
+        Ok(oplog.maintained_at)
+    }
+
+    /// Persists that the object database was maintained just now.
+    ///
+    /// Errors if the file cannot be read or written.
+    pub fn set_maintained_now(&self) -> Result<()> {
+        let mut oplog = self.read_file()?;
+        oplog.maintained_at = SystemTime::now();
+        self.write_file(oplog)?;
+        Ok(())
+    }
+
+    /// Gets the unexpected state of `HEAD` that a snapshot was last taken for.
+    ///
+    /// Errors if the file cannot be read or written.
+    pub fn external_change(&self) -> Result<Option<String>> {
+        let oplog = self.read_file()?;
+        Ok(oplog.external_change)
+    }
+
+    /// Persists the unexpected state of `HEAD` that a snapshot was taken for, or `None` once it's expected again.
+    ///
+    /// Errors if the file cannot be read or written.
+    pub fn set_external_change(&self, external_change: Option<String>) -> Result<()> {
+        let mut oplog = self.read_file()?;
+        oplog.external_change = external_change;
+        self.write_file(oplog)?;
+        Ok(())
+    }
+
     /// Reads and parses the state file.
     ///
     /// If the file does not exist, it will be created.
@@ -96,7 +96,9 @@ mod operation_kind {
 mod snapshot_details {
     use std::str::FromStr;
 
-    use gitbutler_oplog::entry::{OperationKind, Snapshot, SnapshotDetails, Trailer, Version};
+    use gitbutler_oplog::entry::{
+        OperationKind, Snapshot, SnapshotDetails, SnapshotReason, Trailer, Version,
+    };
 
     #[test]
     fn new() {
@@ -145,6 +147,29 @@ mod snapshot_details {
         )
     }
 
+    #[test]
+    fn label_round_trips() {
+        let snapshot_details = SnapshotDetails::new(OperationKind::OnDemandSnapshot)
+            .with_label("before risky\nrebase");
+        let serialized = snapshot_details.to_string();
+        assert!(serialized.contains("Label: before risky\\nrebase\n"));
+        let deserialized = SnapshotDetails::from_str(&serialized).unwrap();
+        assert_eq!(
+            deserialized, snapshot_details,
+            "the label has its own field and isn't among the trailers"
+        );
+    }
+
+    #[test]
+    fn reason_round_trips() {
+        let snapshot_details = SnapshotDetails::new(OperationKind::ExternalChange)
+            .with_reason(SnapshotReason::WorkspaceRefMoved);
+        let serialized = snapshot_details.to_string();
+        assert!(serialized.contains("Reason: WorkspaceRefMoved\n"));
+        let deserialized = SnapshotDetails::from_str(&serialized).unwrap();
+        assert_eq!(deserialized, snapshot_details);
+    }
+
     #[test]
     fn new_with_space_in_trailer_key() {
         for value in ["trailing-space ", " leading-space"] {
@@ -168,6 +193,8 @@ mod snapshot_details {
             operation: OperationKind::CreateCommit,
             title: "Create a new snapshot".to_string(),
             body: None,
+            label: None,
+            reason: None,
             trailers: vec![trailer],
         }
     }
@@ -110,12 +110,8 @@ impl Controller {
             Ok(repo) if repo.is_bare() => {
                 return Ok(AddProjectOutcome::BareRepository);
             }
-            Ok(repo) if repo.worktree().is_some_and(|wt| !wt.is_main()) => {
-                if worktree_dir.join(".git").is_file() {
-                    return Ok(AddProjectOutcome::NonMainWorktree);
-                };
-                repo
-            }
+            // Linked worktrees have a `.git` file pointing to their private git-dir within the main repository.
+            Ok(repo) if repo.worktree().is_some_and(|wt| !wt.is_main()) => repo,
             Ok(repo) => match repo.workdir() {
                 None => {
                     return Ok(AddProjectOutcome::NoWorkdir);
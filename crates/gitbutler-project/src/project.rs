@@ -392,7 +392,6 @@ pub enum AddProjectOutcome {
     PathNotFound,
     NotADirectory,
     BareRepository,
-    NonMainWorktree,
     NoWorkdir,
     NoDotGitDirectory,
     NotAGitRepository(String),
@@ -422,9 +421,6 @@ impl AddProjectOutcome {
             AddProjectOutcome::BareRepository => {
                 Err(anyhow::anyhow!("bare repositories are not supported"))
             }
-            AddProjectOutcome::NonMainWorktree => {
-                Err(anyhow::anyhow!("non-main worktrees are not supported"))
-            }
             AddProjectOutcome::NoWorkdir => Err(anyhow::anyhow!("no workdir found for repository")),
             AddProjectOutcome::NoDotGitDirectory => {
                 Err(anyhow::anyhow!("no .git directory found in repository"))
@@ -24,6 +24,37 @@ mod add {
         Ok(())
     }
 
+    #[test]
+    fn linked_worktree() -> anyhow::Result<()> {
+        let data_dir = paths::data_dir();
+        let tmp = tempfile::tempdir()?;
+        let main_worktree_dir = tmp.path().join("main");
+        let worktree_dir = tmp.path().join("worktree");
+
+        let repo = git2::Repository::init(main_worktree_dir)?;
+        error::create_initial_commit(&repo);
+
+        let worktree = repo.worktree("feature", &worktree_dir, None)?;
+        let project = gitbutler_project::add_at_app_data_dir(data_dir.path(), worktree.path())?
+            .unwrap_project();
+        let worktree_dir = gix::path::realpath(&worktree_dir)?;
+        assert_eq!(project.worktree_dir()?, worktree_dir);
+        assert_eq!(
+            gix::path::realpath(project.git_dir())?,
+            gix::path::realpath(repo.path())?
+                .join("worktrees")
+                .join("feature"),
+            "the private git-dir of the linked worktree is used to store GitButler data"
+        );
+        assert_eq!(
+            gix::path::realpath(project.common_git_dir()?)?,
+            gix::path::realpath(repo.path())?,
+            "branches are shared through the git-dir of the main worktree, including the workspace, \
+             so only one worktree can be a workspace at a time"
+        );
+        Ok(())
+    }
+
     mod error {
         use std::path::PathBuf;
 
@@ -104,23 +135,7 @@ mod add {
             assert!(matches!(outcome, AddProjectOutcome::BareRepository));
         }
 
-        #[test]
-        fn worktree() {
-            let data_dir = paths::data_dir();
-            let tmp = tempfile::tempdir().unwrap();
-            let main_worktree_dir = tmp.path().join("main");
-            let worktree_dir = tmp.path().join("worktree");
-
-            let repo = git2::Repository::init(main_worktree_dir).unwrap();
-            create_initial_commit(&repo);
-
-            let worktree = repo.worktree("feature", &worktree_dir, None).unwrap();
-            let outcome =
-                gitbutler_project::add_at_app_data_dir(data_dir.path(), worktree.path()).unwrap();
-            assert!(matches!(outcome, AddProjectOutcome::NonMainWorktree));
-        }
-
-        fn create_initial_commit(repo: &git2::Repository) -> git2::Oid {
+        pub(super) fn create_initial_commit(repo: &git2::Repository) -> git2::Oid {
             let signature = git2::Signature::now("test", "test@email.com").unwrap();
 
             let mut index = repo.index().unwrap();
@@ -16,6 +16,9 @@ pub struct Refname {
 }
 
 impl Refname {
+    /// The name of the remote that refers to the repository itself, like in `branch.<name>.remote = .`.
+    pub const LOCAL_REMOTE: &'static str = ".";
+
     pub fn new(remote: &str, branch: &str) -> Self {
         Self {
             remote: remote.to_string(),
@@ -41,11 +44,21 @@ impl Refname {
     pub fn fullname(&self) -> String {
         format!("{}/{}", &self.remote, &self.branch)
     }
+
+    /// Return `true` if this refers to the local branch `refs/heads/<branch>` through the [local remote](Self::LOCAL_REMOTE),
+    /// which is parsed from `refs/remotes/./<branch>`.
+    pub fn is_local(&self) -> bool {
+        self.remote == Self::LOCAL_REMOTE
+    }
 }
 
 impl fmt::Display for Refname {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "refs/remotes/{}/{}", self.remote, self.branch)
+        if self.is_local() {
+            write!(f, "refs/heads/{}", self.branch)
+        } else {
+            write!(f, "refs/remotes/{}/{}", self.remote, self.branch)
+        }
     }
 }
 
@@ -58,6 +71,10 @@ impl Serialize for Refname {
 impl<'d> Deserialize<'d> for Refname {
     fn deserialize<D: serde::Deserializer<'d>>(deserializer: D) -> Result<Self, D::Error> {
         let name = String::deserialize(deserializer)?;
+        // Local branches are serialized by their actual name.
+        if let Some(branch) = name.strip_prefix("refs/heads/") {
+            return Ok(Self::new(Self::LOCAL_REMOTE, branch));
+        }
         name.as_str().parse().map_err(serde::de::Error::custom)
     }
 }
@@ -109,6 +126,10 @@ impl PartialEq<FullNameRef> for Refname {
         let Some((category, shortname)) = other.category_and_short_name() else {
             return false;
         };
+        if self.is_local() {
+            return matches!(category, gix::reference::Category::LocalBranch)
+                && shortname == self.branch.as_bytes();
+        }
         if !matches!(category, gix::reference::Category::RemoteBranch) {
             return false;
         }
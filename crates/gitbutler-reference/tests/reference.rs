@@ -87,4 +87,26 @@ mod remote_refname {
             assert_eq!(multi_slash, *fullname_ref("refs/remotes/my/one/feature"));
         }
     }
+
+    mod local {
+        use gitbutler_reference::RemoteRefname;
+
+        #[test]
+        fn local_remote_refers_to_local_branch() -> anyhow::Result<()> {
+            let name: RemoteRefname = "refs/remotes/./main".parse()?;
+            assert!(name.is_local());
+            assert_eq!(name.branch(), "main");
+            assert_eq!(name.fullname(), "./main");
+            assert_eq!(
+                name.to_string(),
+                "refs/heads/main",
+                "it's the local branch itself"
+            );
+
+            let name: RemoteRefname = "refs/remotes/origin/main".parse()?;
+            assert!(!name.is_local());
+            assert_eq!(name.to_string(), "refs/remotes/origin/main");
+            Ok(())
+        }
+    }
 }
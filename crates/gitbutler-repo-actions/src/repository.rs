@@ -3,7 +3,7 @@ use std::str::FromStr;
 use anyhow::{Context as _, Result, anyhow, bail};
 use but_core::commit::Headers;
 use but_ctx::Context;
-use but_error::Code;
+use but_error::ButError;
 use but_oxidize::ObjectIdExt;
 use gitbutler_project::AuthKey;
 use gitbutler_reference::{Refname, RemoteRefname};
@@ -19,16 +19,31 @@ use crate::askpass;
 pub trait RepoActionsExt {
     fn fetch(&self, remote_name: &str, askpass: Option<String>) -> Result<()>;
     /// Returns the stderr output of the git executable if used.
+    ///
+    /// With `force_push_protection`, a force-push only overwrites the remote branch if it points to `lease`,
+    /// or to its remote tracking branch if there is no lease.
     fn push(
         &self,
         head: git2::Oid,
         branch: &RemoteRefname,
         with_force: bool,
         force_push_protection: bool,
+        lease: Option<git2::Oid>,
         refspec: Option<String>,
         askpass_broker: Option<Option<StackId>>,
         push_opts: Vec<String>,
     ) -> Result<String>;
+    /// Push all `updates` of `(head, branch, lease)`, which must all be on the same remote, with a single atomic push.
+    /// Returns the stderr output of the git executable, or `None` if atomic pushes aren't possible because
+    /// the remote doesn't support them or the Git executable isn't used.
+    fn push_atomic(
+        &self,
+        updates: &[(git2::Oid, RemoteRefname, Option<git2::Oid>)],
+        with_force: bool,
+        force_push_protection: bool,
+        askpass_broker: Option<Option<StackId>>,
+        push_opts: Vec<String>,
+    ) -> Result<Option<String>>;
     fn commit(
         &self,
         message: &str,
@@ -183,6 +198,7 @@ impl RepoActionsExt for Context {
         branch: &RemoteRefname,
         with_force: bool,
         force_push_protection: bool,
+        lease: Option<git2::Oid>,
         refspec: Option<String>,
         askpass_broker: Option<Option<StackId>>,
         push_opts: Vec<String>,
@@ -211,6 +227,13 @@ impl RepoActionsExt for Context {
         if use_git_executable {
             let repo_path = self.workdir_or_gitdir()?;
             let remote = branch.remote().to_string();
+            let leases: Vec<_> = lease
+                .map(|expected| gitbutler_git::Lease {
+                    refname: format!("refs/heads/{}", branch.branch()),
+                    expected: expected.to_string(),
+                })
+                .into_iter()
+                .collect();
             match std::thread::spawn(move || {
                 tokio::runtime::Runtime::new()
                     .unwrap()
@@ -221,33 +244,39 @@ impl RepoActionsExt for Context {
                         gitbutler_git::RefSpec::parse(refspec).unwrap(),
                         with_force,
                         force_push_protection,
+                        &leases,
                         handle_git_prompt_push,
                         askpass_broker,
                         push_opts,
                     ))
             })
             .join()
-            .unwrap() {
+            .unwrap()
+            {
                 Ok(result) => Ok(result),
                 Err(err) => match err {
                     gitbutler_git::Error::ForcePushProtection(e) => {
-                        Err(anyhow!("The force push was blocked because the remote branch contains commits that would be overwritten.\n\n{e}")
-                            .context(Code::GitForcePushProtection))
-                    },
+                        Err(e).context(ButError::ForcePushProtection {
+                            remote_ref: branch.to_string(),
+                        })
+                    }
                     gitbutler_git::Error::GerritNoNewChanges(_) => {
                         // Treat "no new changes" as success for Gerrit
                         Ok("".to_string())
-                    },
-                    _ => Err(err.into())
-                }
+                    }
+                    _ => Err(err.into()),
+                },
             }
         } else {
             let git2_repo = self.git2_repo.get()?;
             let auth_flows = credentials::help(&git2_repo, &self.legacy_project, branch.remote())?;
+            let mut tried = Vec::new();
             for (mut remote, callbacks) in auth_flows {
                 let mut update_refs_error: Option<git2::Error> = None;
                 for callback in callbacks {
-                    let mut cbs: git2::RemoteCallbacks = callback.into();
+                    let auth_method = callback.method();
+                    tried.push(auth_method.clone());
+                    let mut cbs: git2::RemoteCallbacks = callback.clone().into();
                     if self.legacy_project.omit_certificate_check.unwrap_or(false) {
                         cbs.certificate_check(|_, _| {
                             Ok(git2::CertificateCheckStatus::CertificateOk)
@@ -272,8 +301,10 @@ impl RepoActionsExt for Context {
                                 remote = %branch.remote(),
                                 %head,
                                 branch = branch.branch(),
+                                %auth_method,
                                 "pushed git branch"
                             );
+                            callback.approve(&git2_repo);
                             return Ok("".to_string());
                         }
                         Err(err) => match err.class() {
@@ -284,6 +315,7 @@ impl RepoActionsExt for Context {
                             _ => match err.code() {
                                 git2::ErrorCode::Auth => {
                                     tracing::warn!(project_id = %self.legacy_project.id, ?err, "push failed due to auth");
+                                    callback.reject(&git2_repo);
                                     continue;
                                 }
                                 _ => {
@@ -298,7 +330,92 @@ impl RepoActionsExt for Context {
                 }
             }
 
-            Err(anyhow!("authentication failed").context(Code::ProjectGitAuth))
+            Err(
+                anyhow::Error::from(credentials::AuthError::exhausted(tried)).context(
+                    ButError::Authentication {
+                        remote: branch.remote().to_string(),
+                    },
+                ),
+            )
+        }
+    }
+
+    fn push_atomic(
+        &self,
+        updates: &[(git2::Oid, RemoteRefname, Option<git2::Oid>)],
+        with_force: bool,
+        force_push_protection: bool,
+        askpass_broker: Option<Option<StackId>>,
+        push_opts: Vec<String>,
+    ) -> Result<Option<String>> {
+        if self.legacy_project.preferred_key != AuthKey::SystemExecutable {
+            return Ok(None);
+        }
+        let Some(remote) = updates
+            .first()
+            .map(|(_, branch, _)| branch.remote().to_string())
+        else {
+            return Ok(Some(String::new()));
+        };
+        if updates
+            .iter()
+            .any(|(_, branch, _)| branch.remote() != remote)
+        {
+            bail!("Atomic pushes can only update branches of a single remote");
+        }
+        let refspecs = updates
+            .iter()
+            .map(|(head, branch, _)| {
+                gitbutler_git::RefSpec::parse(format!("{head}:refs/heads/{}", branch.branch()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let leases: Vec<_> = updates
+            .iter()
+            .filter_map(|(_, branch, lease)| {
+                lease.map(|expected| gitbutler_git::Lease {
+                    refname: format!("refs/heads/{}", branch.branch()),
+                    expected: expected.to_string(),
+                })
+            })
+            .collect();
+
+        let remote_refs = updates
+            .iter()
+            .map(|(_, branch, _)| branch.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let repo_path = self.workdir_or_gitdir()?;
+        match std::thread::spawn(move || {
+            tokio::runtime::Runtime::new()
+                .unwrap()
+                .block_on(gitbutler_git::push_atomic(
+                    repo_path,
+                    gitbutler_git::tokio::TokioExecutor,
+                    &remote,
+                    &refspecs,
+                    with_force,
+                    force_push_protection,
+                    &leases,
+                    handle_git_prompt_push,
+                    askpass_broker,
+                    push_opts,
+                ))
+        })
+        .join()
+        .unwrap()
+        {
+            Ok(result) => Ok(Some(result)),
+            Err(gitbutler_git::Error::AtomicPushUnsupported(err)) => {
+                tracing::info!(?err, "remote doesn't support atomic pushes");
+                Ok(None)
+            }
+            Err(gitbutler_git::Error::ForcePushProtection(e)) => {
+                Err(e).context(ButError::ForcePushProtection {
+                    remote_ref: remote_refs,
+                })
+            }
+            Err(err) => Err(err.into()),
         }
     }
 
@@ -332,10 +449,13 @@ impl RepoActionsExt for Context {
 
         let git2_repo = self.git2_repo.get()?;
         let auth_flows = credentials::help(&git2_repo, &self.legacy_project, remote_name)?;
+        let mut tried = Vec::new();
         for (mut remote, callbacks) in auth_flows {
             for callback in callbacks {
+                let auth_method = callback.method();
+                tried.push(auth_method.clone());
                 let mut fetch_opts = git2::FetchOptions::new();
-                let mut cbs: git2::RemoteCallbacks = callback.into();
+                let mut cbs: git2::RemoteCallbacks = callback.clone().into();
                 if self.legacy_project.omit_certificate_check.unwrap_or(false) {
                     cbs.certificate_check(|_, _| Ok(git2::CertificateCheckStatus::CertificateOk));
                 }
@@ -344,7 +464,8 @@ impl RepoActionsExt for Context {
 
                 match remote.fetch(&[&refspec], Some(&mut fetch_opts), None) {
                     Ok(()) => {
-                        tracing::info!(project_id = %self.legacy_project.id, %refspec, "git fetched");
+                        tracing::info!(project_id = %self.legacy_project.id, %refspec, %auth_method, "git fetched");
+                        callback.approve(&git2_repo);
                         return Ok(());
                     }
                     Err(err) => match err.class() {
@@ -355,6 +476,7 @@ impl RepoActionsExt for Context {
                         _ => match err.code() {
                             git2::ErrorCode::Auth => {
                                 tracing::warn!(project_id = %self.legacy_project.id, ?err, "fetch failed due to auth");
+                                callback.reject(&git2_repo);
                                 continue;
                             }
                             _ => {
@@ -366,7 +488,12 @@ impl RepoActionsExt for Context {
             }
         }
 
-        Err(anyhow!("authentication failed")).context(Code::ProjectGitAuth)
+        Err(anyhow::Error::from(credentials::AuthError::exhausted(
+            tried,
+        )))
+        .context(ButError::Authentication {
+            remote: remote_name.to_string(),
+        })
     }
 }
 
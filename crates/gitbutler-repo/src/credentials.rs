@@ -1,11 +1,18 @@
-use std::{path::PathBuf, str::FromStr, vec};
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+    vec,
+};
 
 use anyhow::Context as _;
+use but_secret::git_credentials::{self, GitCredential};
 use gitbutler_project::AuthKey;
 use gitbutler_url::{ConvertError, Scheme, Url};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum SshCredential {
+    /// Use the keys held by the running `ssh-agent`.
+    Agent,
     Keyfile {
         key_path: PathBuf,
         passphrase: Option<String>,
@@ -14,7 +21,8 @@ pub enum SshCredential {
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum HttpsCredential {
-    CredentialHelper { username: String, password: String },
+    /// A credential provided by `git credential fill`.
+    CredentialHelper(GitCredential),
     GitHubToken(String),
 }
 
@@ -25,17 +33,115 @@ pub enum Credential {
     Https(HttpsCredential),
 }
 
+/// The way a [`Credential`] authenticates, to tell which ones were tried and which one succeeded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuthMethod {
+    /// No authentication was needed.
+    None,
+    SshAgent,
+    SshKey(PathBuf),
+    CredentialHelper,
+    GitHubToken,
+}
+
+impl std::fmt::Display for AuthMethod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AuthMethod::None => f.write_str("no authentication"),
+            AuthMethod::SshAgent => f.write_str("ssh-agent"),
+            AuthMethod::SshKey(path) => write!(f, "SSH key at '{}'", path.display()),
+            AuthMethod::CredentialHelper => f.write_str("Git credential helper"),
+            AuthMethod::GitHubToken => f.write_str("GitHub token"),
+        }
+    }
+}
+
+impl Credential {
+    /// Return how this credential authenticates.
+    pub fn method(&self) -> AuthMethod {
+        match self {
+            Credential::Noop => AuthMethod::None,
+            Credential::Ssh(SshCredential::Agent) => AuthMethod::SshAgent,
+            Credential::Ssh(SshCredential::Keyfile { key_path, .. }) => {
+                AuthMethod::SshKey(key_path.clone())
+            }
+            Credential::Https(HttpsCredential::CredentialHelper(_)) => AuthMethod::CredentialHelper,
+            Credential::Https(HttpsCredential::GitHubToken(_)) => AuthMethod::GitHubToken,
+        }
+    }
+}
+
+/// Feedback for credential helpers
+impl Credential {
+    /// Tell the credential helper that provided this credential that it was accepted by the remote at `repo`,
+    /// so it can be stored. Does nothing for other credentials.
+    pub fn approve(&self, repo: &git2::Repository) {
+        if let Credential::Https(HttpsCredential::CredentialHelper(credential)) = self
+            && let Err(err) =
+                git_credentials::approve(credential, Some(repo.workdir().unwrap_or(repo.path())))
+        {
+            tracing::warn!(?err, "failed to approve credential");
+        }
+    }
+
+    /// Tell the credential helper that provided this credential that it was refused by the remote at `repo`,
+    /// so it can be forgotten. Does nothing for other credentials.
+    pub fn reject(&self, repo: &git2::Repository) {
+        if let Credential::Https(HttpsCredential::CredentialHelper(credential)) = self
+            && let Err(err) =
+                git_credentials::reject(credential, Some(repo.workdir().unwrap_or(repo.path())))
+        {
+            tracing::warn!(?err, "failed to reject credential");
+        }
+    }
+}
+
+/// The error returned when no credential of the [authentication chain](help()) was accepted by the remote.
+#[derive(Debug, thiserror::Error)]
+pub enum AuthError {
+    #[error("authentication failed as there is no way to authenticate with the remote")]
+    NoAuthMethod,
+    #[error("authentication failed after trying {}", tried.iter().map(ToString::to_string).collect::<Vec<_>>().join(", "))]
+    AuthMethodExhausted { tried: Vec<AuthMethod> },
+}
+
+impl AuthError {
+    /// Create the error for having tried all of the methods in `tried` without success.
+    pub fn exhausted(tried: Vec<AuthMethod>) -> Self {
+        if tried.is_empty() {
+            AuthError::NoAuthMethod
+        } else {
+            AuthError::AuthMethodExhausted { tried }
+        }
+    }
+}
+
 impl From<Credential> for git2::RemoteCallbacks<'_> {
     fn from(value: Credential) -> Self {
         let mut remote_callbacks = git2::RemoteCallbacks::new();
         match value {
             Credential::Noop => {}
+            Credential::Ssh(SshCredential::Agent) => {
+                // libgit2 asks again for as long as the remote refuses the keys of the agent, so only offer them once.
+                let mut attempted = false;
+                remote_callbacks.credentials(move |url, username_from_url, _allowed_types| {
+                    if std::mem::replace(&mut attempted, true) {
+                        return Err(refused("the keys of ssh-agent were refused by the remote"));
+                    }
+                    tracing::info!("authenticating with {url} using ssh-agent");
+                    git2::Cred::ssh_key_from_agent(username_from_url.unwrap_or("git"))
+                });
+            }
             Credential::Ssh(SshCredential::Keyfile {
                 key_path,
                 passphrase,
             }) => {
+                let mut attempted = false;
                 remote_callbacks.credentials(move |url, username_from_url, _allowed_types| {
                     use resolve_path::PathResolveExt;
+                    if std::mem::replace(&mut attempted, true) {
+                        return Err(refused("the SSH key was refused by the remote"));
+                    }
                     let key_path = key_path.resolve();
                     tracing::info!(
                         "authenticating with {} using key {}",
@@ -50,7 +156,11 @@ impl From<Credential> for git2::RemoteCallbacks<'_> {
                     )
                 });
             }
-            Credential::Https(HttpsCredential::CredentialHelper { username, password }) => {
+            Credential::Https(HttpsCredential::CredentialHelper(GitCredential {
+                username,
+                password,
+                ..
+            })) => {
                 remote_callbacks.credentials(move |url, _username_from_url, _allowed_types| {
                     tracing::info!("authenticating with {url} as '{username}' with password using credential helper");
                     git2::Cred::userpass_plaintext(&username, &password)
@@ -67,6 +177,11 @@ impl From<Credential> for git2::RemoteCallbacks<'_> {
     }
 }
 
+/// Return an authentication error with `message`, so the next credential of the chain is tried.
+fn refused(message: &str) -> git2::Error {
+    git2::Error::new(git2::ErrorCode::Auth, git2::ErrorClass::Ssh, message)
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum HelpError {
     #[error("no url set for remote")]
@@ -79,6 +194,15 @@ pub enum HelpError {
     Other(#[from] anyhow::Error),
 }
 
+/// The SSH keys that are tried if no key file is configured for the project and they exist, like `ssh` does.
+const DEFAULT_KEY_FILES: &[&str] = &["~/.ssh/id_ed25519", "~/.ssh/id_ecdsa", "~/.ssh/id_rsa"];
+
+/// Return the remotes to try along with the credentials to try them with, in order.
+///
+/// All ways to authenticate form a single chain for the remote: first the keys of `ssh-agent`, then SSH key files,
+/// both with the SSH variant of the remote, and finally the credentials of the Git credential helpers with
+/// its HTTPS variant. The key file configured for `project` is used, or the default keys in `~/.ssh` otherwise.
+/// Variants of the remote that can't be expressed with the other scheme are skipped.
 pub fn help<'a>(
     repo: &'a git2::Repository,
     project: &gitbutler_project::Project,
@@ -93,57 +217,76 @@ pub fn help<'a>(
         return Ok(vec![(remote, vec![Credential::Noop])]);
     }
 
-    match &project.preferred_key {
-        AuthKey::Local { private_key_path } => {
-            let ssh_remote = if remote_url.scheme == Scheme::Ssh {
-                Ok(remote)
-            } else {
-                let ssh_url = remote_url.as_ssh()?;
-                repo.remote_anonymous(&ssh_url.to_string())
-            }?;
-
-            Ok(vec![(
-                ssh_remote,
-                vec![Credential::Ssh(SshCredential::Keyfile {
-                    key_path: private_key_path.clone(),
-                    passphrase: None,
-                })],
-            )])
-        }
-        AuthKey::GitCredentialsHelper => {
-            let https_remote = if remote_url.scheme == Scheme::Https {
-                Ok(remote)
-            } else {
-                let url = remote_url.as_https()?;
-                repo.remote_anonymous(&url.to_string())
-            }?;
-            let flow = https_flow(repo, &remote_url)?
-                .into_iter()
-                .map(Credential::Https)
-                .collect::<Vec<_>>();
-            Ok(vec![(https_remote, flow)])
-        }
+    let key_files = match &project.preferred_key {
+        AuthKey::Local { private_key_path } => vec![private_key_path.clone()],
+        AuthKey::GitCredentialsHelper => default_key_files(),
         AuthKey::SystemExecutable => {
             tracing::error!(
                 "WARNING: FIXME: this codepath should NEVER be hit. Something is seriously wrong."
             );
-            Ok(vec![])
+            return Ok(vec![]);
         }
+    };
+
+    let (mut ssh_remote, mut https_remote) = match remote_url.scheme {
+        Scheme::Ssh => (Some(remote), None),
+        Scheme::Https => (None, Some(remote)),
+        _ => (None, None),
+    };
+    if ssh_remote.is_none()
+        && let Ok(ssh_url) = remote_url.as_ssh()
+    {
+        ssh_remote = Some(repo.remote_anonymous(&ssh_url.to_string())?);
+    }
+    if https_remote.is_none()
+        && let Ok(https_url) = remote_url.as_https()
+    {
+        https_remote = Some(repo.remote_anonymous(&https_url.to_string())?);
+    }
+
+    let mut flows = Vec::new();
+    if let Some(ssh_remote) = ssh_remote {
+        let credentials = std::iter::once(SshCredential::Agent)
+            .chain(
+                key_files
+                    .into_iter()
+                    .map(|key_path| SshCredential::Keyfile {
+                        key_path,
+                        passphrase: None,
+                    }),
+            )
+            .map(Credential::Ssh)
+            .collect();
+        flows.push((ssh_remote, credentials));
+    }
+    if let Some(https_remote) = https_remote {
+        let credentials = https_flow(repo, &https_remote)?
+            .into_iter()
+            .map(Credential::Https)
+            .collect();
+        flows.push((https_remote, credentials));
     }
+    Ok(flows)
+}
+
+/// Return the [default key files](DEFAULT_KEY_FILES) that exist.
+fn default_key_files() -> Vec<PathBuf> {
+    use resolve_path::PathResolveExt;
+    DEFAULT_KEY_FILES
+        .iter()
+        .map(|path| Path::new(path).resolve().into_owned())
+        .filter(|path| path.is_file())
+        .collect()
 }
 
 fn https_flow(
     repo: &git2::Repository,
-    remote_url: &Url,
+    https_remote: &git2::Remote<'_>,
 ) -> Result<Vec<HttpsCredential>, HelpError> {
-    let mut flow = vec![];
-
-    let mut helper = git2::CredentialHelper::new(&remote_url.to_string());
-    let config = repo.config()?;
-    helper.config(&config);
-    if let Some((username, password)) = helper.execute() {
-        flow.push(HttpsCredential::CredentialHelper { username, password });
-    }
-
-    Ok(flow)
+    let url = https_remote.url().ok_or(HelpError::NoUrlSet)?;
+    let cwd = repo.workdir().unwrap_or(repo.path());
+    Ok(git_credentials::fill(url, Some(cwd))?
+        .map(HttpsCredential::CredentialHelper)
+        .into_iter()
+        .collect())
 }
@@ -0,0 +1,164 @@
+//! Discover and run the Git hooks of a repository the way Git would, so tools like `husky` or `lint-staged`
+//! see the same environment they would see when committing with Git.
+use std::{
+    ffi::OsStr,
+    io::Write as _,
+    path::{Path, PathBuf},
+    process::Stdio,
+};
+
+use anyhow::{Context as _, Result};
+use bstr::ByteSlice;
+
+/// The name of the directory `husky` keeps its hooks in, next to the `.git` directory.
+const HUSKY_DIR: &str = ".husky";
+
+/// The hooks of a repository, with all the directories they may be in.
+#[derive(Debug, Clone)]
+pub struct HookRunner {
+    /// The directories to find hooks in, in order of precedence.
+    dirs: Vec<PathBuf>,
+    /// The directory to run hooks in, the top of the worktree or the `.git` directory in bare repositories.
+    cwd: PathBuf,
+}
+
+/// The result of [running](HookRunner::run()) a hook.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HookOutcome {
+    /// There was no executable hook with the given name.
+    NotFound,
+    /// The hook ran and exited with status 0.
+    Success {
+        /// What the hook wrote to stdout.
+        stdout: String,
+        /// What the hook wrote to stderr.
+        stderr: String,
+    },
+    /// The hook ran and failed, which aborts the operation it was run for.
+    Failure {
+        /// What the hook wrote to stdout.
+        stdout: String,
+        /// What the hook wrote to stderr.
+        stderr: String,
+        /// The exit code, or `None` if the hook was terminated by a signal.
+        code: Option<i32>,
+    },
+}
+
+/// What to pass to a hook when running it.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct HookInput<'a> {
+    /// The arguments to pass.
+    pub args: &'a [&'a OsStr],
+    /// Additional environment variables to set.
+    pub env: &'a [(&'a str, &'a OsStr)],
+    /// The data to write to stdin, which is connected to `/dev/null` if `None`.
+    pub stdin: Option<&'a [u8]>,
+}
+
+/// Lifecycle
+impl HookRunner {
+    /// Find the hooks of `repo`, which are in `core.hooksPath` if set, or in `$GIT_COMMON_DIR/hooks`.
+    /// Like `git2-hooks` did before, hooks in a `.husky` directory next to the `.git` directory are
+    /// found as well if there is no hook with the same name in the configured directory.
+    pub fn discover(repo: &gix::Repository) -> Result<Self> {
+        let cwd = repo.workdir().unwrap_or_else(|| repo.git_dir()).to_owned();
+        let configured = repo
+            .config_snapshot()
+            .trusted_path("core.hooksPath")
+            .transpose()
+            .context("core.hooksPath could not be interpolated")?
+            .map(|path| {
+                // Like Git, relative paths are relative to the directory hooks run in.
+                if path.is_relative() {
+                    cwd.join(path)
+                } else {
+                    path.into_owned()
+                }
+            });
+        let mut dirs = vec![configured.unwrap_or_else(|| repo.common_dir().join("hooks"))];
+        if let Some(husky) = repo.workdir().map(|workdir| workdir.join(HUSKY_DIR)) {
+            dirs.push(husky);
+        }
+        Ok(HookRunner { dirs, cwd })
+    }
+}
+
+/// Execution
+impl HookRunner {
+    /// Return the path to the executable hook with `name`, like `pre-commit`, if there is one.
+    pub fn find(&self, name: &str) -> Option<PathBuf> {
+        self.dirs
+            .iter()
+            .map(|dir| dir.join(name))
+            .find(|path| is_executable(path))
+    }
+
+    /// Run the hook with `name` with `input`, in the top of the worktree.
+    pub fn run(&self, name: &str, input: HookInput<'_>) -> Result<HookOutcome> {
+        let Some(hook_path) = self.find(name) else {
+            return Ok(HookOutcome::NotFound);
+        };
+
+        // Like Git, run the hook through the shell so hooks without shebang or executable bit on Windows
+        // still run as shell scripts.
+        // Need unix separators for the unix bash to not swallow the backslash!
+        let hook_path_for_shell = gix::path::from_bstring(
+            gix::path::to_unix_separators_on_windows(gix::path::into_bstr(&hook_path)).into_owned(),
+        );
+        let mut cmd = std::process::Command::new(gix::path::env::shell());
+        cmd.arg("-c")
+            .arg(r#""$0" "$@""#)
+            .arg(hook_path_for_shell)
+            .args(input.args)
+            .current_dir(&self.cwd)
+            // Hooks that would open an editor must not block, there is nobody to edit.
+            .env("GIT_EDITOR", ":")
+            .envs(input.env.iter().copied())
+            .stdin(if input.stdin.is_some() {
+                Stdio::piped()
+            } else {
+                Stdio::null()
+            })
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+
+        let mut child = cmd
+            .spawn()
+            .with_context(|| format!("Failed to run hook at '{}'", hook_path.display()))?;
+        if let Some(stdin) = input.stdin {
+            let mut pipe = child.stdin.take().expect("configured");
+            // Hooks may exit without reading their input, which is fine.
+            if let Err(err) = pipe.write_all(stdin)
+                && err.kind() != std::io::ErrorKind::BrokenPipe
+            {
+                return Err(err.into());
+            }
+        }
+
+        let output = child.wait_with_output()?;
+        let stdout = output.stdout.to_str_lossy().into_owned();
+        let stderr = output.stderr.to_str_lossy().into_owned();
+        Ok(if output.status.success() {
+            HookOutcome::Success { stdout, stderr }
+        } else {
+            HookOutcome::Failure {
+                stdout,
+                stderr,
+                code: output.status.code(),
+            }
+        })
+    }
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt as _;
+    path.metadata()
+        .is_ok_and(|md| md.is_file() && md.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
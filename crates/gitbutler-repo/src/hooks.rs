@@ -1,12 +1,18 @@
-use std::{io::Write, process::Stdio};
+use std::ffi::OsStr;
 
 use anyhow::Result;
-use bstr::ByteSlice;
 use but_ctx::Context;
-use git2_hooks::{self, HookResult as H};
 use serde::Serialize;
 
-use crate::staging;
+use crate::{
+    hook_runner::{HookInput, HookOutcome, HookRunner},
+    staging,
+};
+
+const PRE_COMMIT: &str = "pre-commit";
+const COMMIT_MSG: &str = "commit-msg";
+const POST_COMMIT: &str = "post-commit";
+const PRE_PUSH: &str = "pre-push";
 
 #[derive(Serialize, PartialEq, Debug, Clone)]
 pub struct MessageData {
@@ -40,28 +46,54 @@ pub enum MessageHookResult {
     Failure(ErrorData),
 }
 
-pub fn commit_msg(ctx: &Context, mut message: String) -> Result<MessageHookResult> {
-    let original_message = message.clone();
-    match git2_hooks::hooks_commit_msg(&*ctx.git2_repo.get()?, Some(&["../.husky"]), &mut message)?
-    {
-        H::Ok { hook: _ } => match message == original_message {
-            true => Ok(MessageHookResult::Success),
-            false => Ok(MessageHookResult::Message(MessageData { message })),
+pub fn commit_msg(ctx: &Context, message: String) -> Result<MessageHookResult> {
+    let repo = ctx.repo.get()?;
+    let runner = HookRunner::discover(&repo)?;
+    if runner.find(COMMIT_MSG).is_none() {
+        return Ok(MessageHookResult::NotConfigured);
+    }
+
+    // The message is passed as file which the hook may rewrite, just like Git does.
+    let message_path = repo.git_dir().join("COMMIT_EDITMSG");
+    std::fs::write(&message_path, &message)?;
+    let outcome = runner.run(
+        COMMIT_MSG,
+        HookInput {
+            args: &[message_path.as_os_str()],
+            env: &[("GIT_INDEX_FILE", repo.index_path().as_os_str())],
+            stdin: None,
         },
-        H::NoHookFound => Ok(MessageHookResult::NotConfigured),
-        H::RunNotSuccessful {
+    )?;
+    Ok(match outcome {
+        HookOutcome::NotFound => MessageHookResult::NotConfigured,
+        HookOutcome::Success { .. } => {
+            let new_message = std::fs::read_to_string(&message_path)?;
+            if new_message == message {
+                MessageHookResult::Success
+            } else {
+                MessageHookResult::Message(MessageData {
+                    message: new_message,
+                })
+            }
+        }
+        HookOutcome::Failure {
             stdout,
             stderr,
             code,
-            ..
         } => {
             let error = join_output(stdout, stderr, code);
-            Ok(MessageHookResult::Failure(ErrorData { error }))
+            MessageHookResult::Failure(ErrorData { error })
         }
-    }
+    })
 }
 
 pub fn pre_commit_with_tree(ctx: &Context, tree_id: git2::Oid) -> Result<HookResult> {
+    let gix_repo = ctx.repo.get()?;
+    let runner = HookRunner::discover(&gix_repo)?;
+    if runner.find(PRE_COMMIT).is_none() {
+        return Ok(HookResult::NotConfigured);
+    }
+
     let repo = &*ctx.git2_repo.get()?;
     let original_tree = repo.index()?.write_tree()?;
 
@@ -77,48 +109,41 @@ pub fn pre_commit_with_tree(ctx: &Context, tree_id: git2::Oid) -> Result<HookRes
     index.read_tree(&repo.find_tree(tree_id)?)?;
     index.write()?;
 
-    Ok(
-        match git2_hooks::hooks_pre_commit(&*ctx.git2_repo.get()?, Some(&["../.husky"]))? {
-            H::Ok { hook: _ } => HookResult::Success,
-            H::NoHookFound => HookResult::NotConfigured,
-            H::RunNotSuccessful {
-                stdout,
-                stderr,
-                code,
-                ..
-            } => {
-                // If the output contains GITBUTLER_ERROR, it's our managed hook blocking
-                // commits on gitbutler/workspace - this is expected behavior, not a failure
-                if stdout.contains("GITBUTLER_ERROR") || stderr.contains("GITBUTLER_ERROR") {
-                    HookResult::Success
-                } else {
-                    let error = join_output(stdout, stderr, code);
-                    HookResult::Failure(ErrorData { error })
-                }
-            }
+    // Tools like `lint-staged` look at the index, which now has exactly what is going to be committed.
+    let outcome = runner.run(
+        PRE_COMMIT,
+        HookInput {
+            env: &[("GIT_INDEX_FILE", gix_repo.index_path().as_os_str())],
+            ..Default::default()
         },
-    )
-}
-
-pub fn post_commit(ctx: &Context) -> Result<HookResult> {
-    match git2_hooks::hooks_post_commit(&*ctx.git2_repo.get()?, Some(&["../.husky"]))? {
-        H::Ok { hook: _ } => Ok(HookResult::Success),
-        H::NoHookFound => Ok(HookResult::NotConfigured),
-        H::RunNotSuccessful {
+    )?;
+    Ok(match outcome {
+        HookOutcome::NotFound => HookResult::NotConfigured,
+        HookOutcome::Success { .. } => HookResult::Success,
+        HookOutcome::Failure {
             stdout,
             stderr,
             code,
-            ..
         } => {
-            let error = join_output(stdout, stderr, code);
-            Ok(HookResult::Failure(ErrorData { error }))
+            // If the output contains GITBUTLER_ERROR, it's our managed hook blocking
+            // commits on gitbutler/workspace - this is expected behavior, not a failure
+            if stdout.contains("GITBUTLER_ERROR") || stderr.contains("GITBUTLER_ERROR") {
+                HookResult::Success
+            } else {
+                let error = join_output(stdout, stderr, code);
+                HookResult::Failure(ErrorData { error })
+            }
         }
-    }
+    })
 }
 
-// TODO: double-check this with what should happen according to Git; contribute to `git2-hooks` possibly.
-/// Since git2-hooks doesn't support pre-push yet, we implement it ourselves
-/// following the same pattern as the existing hooks
+pub fn post_commit(ctx: &Context) -> Result<HookResult> {
+    let repo = ctx.repo.get()?;
+    let outcome = HookRunner::discover(&repo)?.run(POST_COMMIT, HookInput::default())?;
+    Ok(outcome.into())
+}
+
+/// Run the `pre-push` hook of `repo` for pushing `local_commit` to `remote_tracking_branch` on `remote_name`.
 /// Use `oid` and `remote_tracking_branch` to deduce the refspec information. Note that this isn't general, but should
 /// work for us.
 pub fn pre_push(
@@ -128,80 +153,47 @@ pub fn pre_push(
     local_commit: git2::Oid,
     remote_tracking_branch: &gitbutler_reference::RemoteRefname,
 ) -> Result<HookResult> {
-    let hooks_dir = repo
-        .config()
-        .and_then(|config| config.get_path("core.hooksPath"))
-        .unwrap_or_else(|_| repo.path().join("hooks"));
-    let hooks_path = hooks_dir.join("pre-push");
-    let husky_path = repo
-        .path()
-        .parent()
-        .and_then(|p| p.parent())
-        .map(|p| p.join(".husky").join("pre-push"));
-
-    // Check for hook in .git/hooks/pre-push first, then ../.husky/pre-push
-    let hook_path = hooks_path
-        .exists()
-        .then_some(hooks_path)
-        .or_else(|| husky_path.filter(|path| path.exists()));
-
-    let Some(hook_path) = hook_path else {
+    let gix_repo = gix::open(repo.path())?;
+    let runner = HookRunner::discover(&gix_repo)?;
+    if runner.find(PRE_PUSH).is_none() {
         return Ok(HookResult::NotConfigured);
-    };
-
-    // Execute the pre-push hook with remote name and URL as arguments
-    let mut child = std::process::Command::from({
-        let mut prep = gix::command::prepare(&hook_path);
-        if cfg!(windows) {
-            prep.use_shell = true;
-            prep.allow_manual_arg_splitting = false;
-            // Need unix separators for the unix bash to not swallow the backslash!
-            let with_slashes_for_bash = gix::path::to_unix_separators_on_windows(
-                gix::path::os_str_into_bstr(&prep.command)?,
-            );
-            prep.command = gix::path::from_bstring(with_slashes_for_bash.into_owned()).into();
-        }
-        prep.arg(remote_name).arg(remote_url)
-    })
-    .current_dir(repo.workdir().unwrap_or_else(|| repo.path()))
-    .stdin(Stdio::piped())
-    .spawn()?;
-
-    {
-        // Wait for the child process to be ready before writing to stdin.
-        // Check if the process has already exited unexpectedly.
-        if let Some(status) = child.try_wait()? {
-            // Process already exited, don't write to stdin.
-            let error = format!("pre-push hook exited early with status: {}", status);
-            return Ok(HookResult::Failure(ErrorData { error }));
-        }
-
-        let remote_commit = repo
-            .find_reference(&remote_tracking_branch.to_string())
-            .ok()
-            .and_then(|r| r.target())
-            .unwrap_or_else(git2::Oid::zero);
-        // THIS IS WRONG: but is correct in the common case. This also is an issue when the ref is actually pushed,
-        // but we can fix it when moving everything to `gix`.
-        let local_tracking_branch_deduced =
-            format!("refs/heads/{}", remote_tracking_branch.branch());
-        let stdin = child.stdin.as_mut().expect("configured");
-        stdin.write_all(
-            format!("{local_tracking_branch_deduced} {local_commit} {remote_tracking_branch} {remote_commit}\n")
-                .as_bytes(),
-        )?;
     }
 
-    let output = child.wait_with_output()?;
-    if output.status.success() {
-        Ok(HookResult::Success)
-    } else {
-        let error = join_output(
-            output.stdout.to_str_lossy().into_owned(),
-            output.stderr.to_str_lossy().into_owned(),
-            output.status.code(),
-        );
-        Ok(HookResult::Failure(ErrorData { error }))
+    let remote_commit = repo
+        .find_reference(&remote_tracking_branch.to_string())
+        .ok()
+        .and_then(|r| r.target())
+        .unwrap_or_else(git2::Oid::zero);
+    // THIS IS WRONG: but is correct in the common case. This also is an issue when the ref is actually pushed,
+    // but we can fix it when moving everything to `gix`.
+    let local_tracking_branch_deduced = format!("refs/heads/{}", remote_tracking_branch.branch());
+    let stdin = format!(
+        "{local_tracking_branch_deduced} {local_commit} {remote_tracking_branch} {remote_commit}\n"
+    );
+    let outcome = runner.run(
+        PRE_PUSH,
+        HookInput {
+            args: &[OsStr::new(remote_name), OsStr::new(remote_url)],
+            env: &[],
+            stdin: Some(stdin.as_bytes()),
+        },
+    )?;
+    Ok(outcome.into())
+}
+
+impl From<HookOutcome> for HookResult {
+    fn from(outcome: HookOutcome) -> Self {
+        match outcome {
+            HookOutcome::NotFound => HookResult::NotConfigured,
+            HookOutcome::Success { .. } => HookResult::Success,
+            HookOutcome::Failure {
+                stdout,
+                stderr,
+                code,
+            } => HookResult::Failure(ErrorData {
+                error: join_output(stdout, stderr, code),
+            }),
+        }
     }
 }
 
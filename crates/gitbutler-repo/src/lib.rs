@@ -14,6 +14,7 @@ pub mod credentials;
 
 mod config;
 use config::Config;
+pub mod hook_runner;
 pub mod hooks;
 pub mod managed_hooks;
 mod remote;
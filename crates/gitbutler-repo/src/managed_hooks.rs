@@ -172,10 +172,12 @@ impl ManagedHookType {
 }
 
 /// Get the hooks directory, respecting core.hooksPath configuration
+///
+/// Hooks are shared by all worktrees, so they are in the common dir, not the private git-dir of a linked worktree.
 fn get_hooks_dir(repo: &git2::Repository) -> PathBuf {
     repo.config()
         .and_then(|config| config.get_path("core.hooksPath"))
-        .unwrap_or_else(|_| repo.path().join("hooks"))
+        .unwrap_or_else(|_| repo.commondir().join("hooks"))
 }
 
 /// Check if a hook file contains our signature
@@ -4,12 +4,11 @@ use anyhow::{Context as _, Result};
 use but_core::{
     RepositoryExt,
     commit::{ConflictEntries, Headers},
+    ref_metadata::StackIdentity,
 };
-use but_oxidize::{ObjectIdExt as _, OidExt as _};
+use but_oxidize::{ObjectIdExt as _, OidExt as _, gix_to_git2_signature};
 use gitbutler_cherry_pick::{ConflictedTreeKey, RepositoryExt as _};
 
-use crate::RepositoryExt as _;
-
 fn extract_conflicted_files(
     merged_tree_id: gix::Id<'_>,
     merge_result: gix::merge::tree::Outcome<'_>,
@@ -152,7 +151,11 @@ pub fn merge_commits(
         Headers::new_with_random_change_id()
     };
 
-    let (author, committer) = repo.signatures()?;
+    // Take the signatures from `gix_repository` as it may be configured to commit as someone else.
+    let (author, committer) = gix_repository.commit_signatures()?;
+    let buf = &mut gix::date::parse::TimeBuf::default();
+    let author = gix_to_git2_signature(author.to_ref(buf))?;
+    let committer = gix_to_git2_signature(committer.to_ref(buf))?;
     let commit_oid = crate::RepositoryExt::commit_with_signature(
         &repo,
         None,
@@ -167,14 +170,20 @@ pub fn merge_commits(
 
     Ok(commit_oid.to_gix())
 }
+/// Like [`merge_commits()`], but names the merge after the branches, and authors and commits it as `identity`
+/// if set, or as configured in Git.
 pub fn gitbutler_merge_commits<'repo>(
     repo: &'repo git2::Repository,
     target_commit: git2::Commit<'repo>,
     incoming_commit: git2::Commit<'repo>,
     target_branch_name: &str,
     incoming_branch_name: &str,
+    identity: Option<&StackIdentity>,
 ) -> Result<git2::Commit<'repo>> {
-    let gix_repo = gix::open(repo.path())?;
+    let mut gix_repo = gix::open(repo.path())?;
+    if let Some(identity) = identity {
+        gix_repo = gix_repo.with_stack_identity(identity)?;
+    }
     let result_oid = merge_commits(
         &gix_repo,
         target_commit.id().to_gix(),
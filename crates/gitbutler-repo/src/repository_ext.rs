@@ -1,18 +1,16 @@
-use std::str;
+use std::{collections::BTreeSet, path::PathBuf, str};
 
 use anyhow::{Context as _, Result, anyhow, bail};
 use bstr::BString;
-use but_core::{GitConfigSettings, RepositoryExt as RepositoryExtGix, commit::Headers};
+use but_core::{RepositoryExt as RepositoryExtGix, commit::Headers};
 use but_error::Code;
 use but_oxidize::{
     ObjectIdExt as _, git2_signature_to_gix_signature, git2_to_gix_object_id, gix_to_git2_oid,
     gix_to_git2_signature,
 };
-use but_rebase::commit::sign_buffer;
 use but_status::create_wd_tree;
 use git2::Tree;
 use gitbutler_reference::{Refname, RemoteRefname};
-use gix::objs::WriteTo;
 use tracing::instrument;
 
 use crate::{Config, SignaturePurpose};
@@ -38,6 +36,12 @@ pub trait RepositoryExt {
     /// Returns the computed signature.
     fn sign_buffer(&self, buffer: &[u8]) -> Result<BString>;
     fn checkout_tree_builder<'a>(&'a self, tree: &'a git2::Tree<'a>) -> CheckoutTreeBuidler<'a>;
+    /// Return the paths that differ between the `old` and the `new` tree, with both the old and the new path of renames.
+    fn changed_paths(
+        &self,
+        old: &git2::Tree<'_>,
+        new: &git2::Tree<'_>,
+    ) -> Result<BTreeSet<PathBuf>>;
     fn maybe_find_branch_by_refname(&self, name: &Refname) -> Result<Option<git2::Branch<'_>>>;
     /// Add all untracked and modified files in the worktree to
     /// the object database, and create a tree from it.
@@ -75,19 +79,28 @@ impl RepositoryExt for git2::Repository {
             tree,
             repo: self,
             checkout_builder: git2::build::CheckoutBuilder::new(),
+            only_changes: false,
+            has_no_changes: false,
+        }
+    }
+
+    fn changed_paths(
+        &self,
+        old: &git2::Tree<'_>,
+        new: &git2::Tree<'_>,
+    ) -> Result<BTreeSet<PathBuf>> {
+        let diff = self.diff_tree_to_tree(Some(old), Some(new), None)?;
+        let mut paths = BTreeSet::new();
+        for delta in diff.deltas() {
+            paths.extend(delta.old_file().path().map(ToOwned::to_owned));
+            paths.extend(delta.new_file().path().map(ToOwned::to_owned));
         }
+        Ok(paths)
     }
 
     fn maybe_find_branch_by_refname(&self, name: &Refname) -> Result<Option<git2::Branch<'_>>> {
-        let branch = self.find_branch(
-            &name.simple_name(),
-            match name {
-                Refname::Virtual(_) | Refname::Local(_) | Refname::Other(_) => {
-                    git2::BranchType::Local
-                }
-                Refname::Remote(_) => git2::BranchType::Remote,
-            },
-        );
+        let (name, kind) = branch_name_and_type(name);
+        let branch = self.find_branch(&name, kind);
         match branch {
             Ok(branch) => Ok(Some(branch)),
             Err(e) if e.code() == git2::ErrorCode::NotFound => Ok(None),
@@ -96,15 +109,8 @@ impl RepositoryExt for git2::Repository {
     }
 
     fn find_branch_by_refname(&self, name: &Refname) -> Result<git2::Branch<'_>> {
-        let branch = self.find_branch(
-            &name.simple_name(),
-            match name {
-                Refname::Virtual(_) | Refname::Local(_) | Refname::Other(_) => {
-                    git2::BranchType::Local
-                }
-                Refname::Remote(_) => git2::BranchType::Remote,
-            },
-        )?;
+        let (name, kind) = branch_name_and_type(name);
+        let branch = self.find_branch(&name, kind)?;
 
         Ok(branch)
     }
@@ -164,42 +170,10 @@ impl RepositoryExt for git2::Repository {
             extra_headers: commit_headers.map(|h| (&h).into()).unwrap_or_default(),
         };
 
-        if repo.git_settings()?.gitbutler_sign_commits.unwrap_or(false) {
-            let mut buf = Vec::new();
-            commit.write_to(&mut buf)?;
-            let signature = sign_buffer(&repo, &buf);
-            match signature {
-                Ok(signature) => {
-                    commit.extra_headers.push(("gpgsig".into(), signature));
-                }
-                Err(err) => {
-                    // If signing fails, set the "gitbutler.signCommits" config to false before erroring out
-                    if repo
-                        .config_snapshot()
-                        .boolean_filter("gitbutler.signCommits", |md| {
-                            md.source != gix::config::Source::Local
-                        })
-                        .is_none()
-                    {
-                        repo.set_git_settings(&GitConfigSettings {
-                            gitbutler_sign_commits: Some(false),
-                            ..Default::default()
-                        })?;
-                        return Err(anyhow!("Failed to sign commit: {}", err)
-                            .context(Code::CommitSigningFailed));
-                    } else {
-                        tracing::warn!(
-                            "Commit signing failed but remains enabled as gitbutler.signCommits is explicitly enabled globally"
-                        );
-                        return Err(err);
-                    }
-                }
-            }
-        }
-
         if repo.git_settings()?.gitbutler_gerrit_mode.unwrap_or(false) {
             but_gerrit::set_trailers(&mut commit);
         }
+        but_core::signing::sign_commit(&repo, &mut commit)?;
 
         // TODO: extra-headers should be supported in `gix` directly.
         let oid = gix_to_git2_oid(repo.write_object(&commit)?);
@@ -212,7 +186,7 @@ impl RepositoryExt for git2::Repository {
     }
 
     fn sign_buffer(&self, buffer: &[u8]) -> Result<BString> {
-        but_rebase::commit::sign_buffer(&gix::open(self.path())?, buffer)
+        but_core::signing::sign_buffer(&gix::open(self.path())?, buffer)
     }
 
     fn remotes_as_string(&self) -> Result<Vec<String>> {
@@ -273,10 +247,28 @@ impl RepositoryExt for git2::Repository {
     }
 }
 
+/// Return the short name of the branch `name` refers to, along with its type, for looking it up with `git2`.
+fn branch_name_and_type(name: &Refname) -> (String, git2::BranchType) {
+    match name {
+        Refname::Virtual(_) | Refname::Local(_) | Refname::Other(_) => {
+            (name.simple_name(), git2::BranchType::Local)
+        }
+        // Targets on the local remote refer to local branches.
+        Refname::Remote(remote) if remote.is_local() => {
+            (remote.branch().to_owned(), git2::BranchType::Local)
+        }
+        Refname::Remote(_) => (name.simple_name(), git2::BranchType::Remote),
+    }
+}
+
 pub struct CheckoutTreeBuidler<'a> {
     repo: &'a git2::Repository,
     tree: &'a git2::Tree<'a>,
     checkout_builder: git2::build::CheckoutBuilder<'a>,
+    /// If `true`, the checkout is limited to changed paths.
+    only_changes: bool,
+    /// If `true`, the checkout was limited to changed paths, but there are none.
+    has_no_changes: bool,
 }
 
 impl CheckoutTreeBuidler<'_> {
@@ -290,9 +282,32 @@ impl CheckoutTreeBuidler<'_> {
         self
     }
 
+    /// Only update the paths that differ between `current_tree`, which the worktree is known to be at,
+    /// and the tree to check out, so all other files and their modification times stay untouched.
+    /// The index is still set to the tree to check out as a whole.
+    pub fn only_changes_from(&mut self, current_tree: &git2::Tree<'_>) -> Result<&mut Self> {
+        let paths = self.repo.changed_paths(current_tree, self.tree)?;
+        self.only_changes = true;
+        self.has_no_changes = paths.is_empty();
+        self.checkout_builder.disable_pathspec_match(true);
+        for path in paths {
+            self.checkout_builder.path(path);
+        }
+        Ok(self)
+    }
+
     pub fn checkout(&mut self) -> Result<()> {
-        self.repo
-            .checkout_tree(self.tree.as_object(), Some(&mut self.checkout_builder))
-            .map_err(Into::into)
+        if !self.has_no_changes {
+            self.repo
+                .checkout_tree(self.tree.as_object(), Some(&mut self.checkout_builder))?;
+        }
+        if self.only_changes {
+            // The checkout only updated the index entries of the changed paths, so reset all others as well.
+            // Entries that don't change keep their stat information, so files aren't considered modified.
+            let mut index = self.repo.index()?;
+            index.read_tree(self.tree)?;
+            index.write()?;
+        }
+        Ok(())
     }
 }
@@ -0,0 +1,51 @@
+use std::path::Path;
+
+use gitbutler_repo::RepositoryExt as _;
+use gitbutler_testsupport::testing_repository::TestingRepository;
+
+#[test]
+fn only_changes_from_updates_changed_paths_and_the_whole_index() -> anyhow::Result<()> {
+    let test = TestingRepository::open_with_initial_commit(&[
+        ("unchanged.txt", "unchanged"),
+        ("changed.txt", "old"),
+    ]);
+    let repo = &test.repository;
+    let workdir = test.tempdir.path();
+    let current_tree = repo.head()?.peel_to_tree()?;
+
+    let mut builder = repo.treebuilder(Some(&current_tree))?;
+    builder.insert("changed.txt", repo.blob(b"new")?, 0o100644)?;
+    builder.insert("added.txt", repo.blob(b"added")?, 0o100644)?;
+    let target_tree = repo.find_tree(builder.write()?)?;
+
+    // Stage a change only in the index, which isn't part of the trees.
+    let mut index = repo.index()?;
+    let mut entry = index
+        .get_path(Path::new("unchanged.txt"), 0)
+        .expect("tracked");
+    entry.id = repo.blob(b"staged")?;
+    index.add(&entry)?;
+    index.write()?;
+    let unchanged_mtime = std::fs::metadata(workdir.join("unchanged.txt"))?.modified()?;
+
+    repo.checkout_tree_builder(&target_tree)
+        .only_changes_from(&current_tree)?
+        .force()
+        .checkout()?;
+
+    assert_eq!(std::fs::read_to_string(workdir.join("changed.txt"))?, "new");
+    assert_eq!(std::fs::read_to_string(workdir.join("added.txt"))?, "added");
+    assert_eq!(
+        std::fs::metadata(workdir.join("unchanged.txt"))?.modified()?,
+        unchanged_mtime,
+        "files that didn't change aren't written"
+    );
+    let mut index = repo.index()?;
+    index.read(true)?;
+    assert_eq!(
+        index.write_tree()?,
+        target_tree.id(),
+        "index entries of paths that didn't change are reset as well"
+    );
+    Ok(())
+}
@@ -1,9 +1,10 @@
 use std::{path::PathBuf, str};
 
 use but_ctx::Context;
+use but_secret::{Sensitive, git_credentials::GitCredential};
 use but_settings::AppSettings;
 use gitbutler_project as projects;
-use gitbutler_repo::credentials::{Credential, SshCredential, help};
+use gitbutler_repo::credentials::{AuthMethod, Credential, HttpsCredential, SshCredential, help};
 use gitbutler_testsupport::test_repository;
 use gitbutler_user as users;
 
@@ -27,6 +28,16 @@ impl TestCase<'_> {
 
         let (repo, _tmp) = test_repository();
         repo.remote("origin", self.remote_url).unwrap();
+        let mut config = repo.config().unwrap();
+        // The empty value clears the helpers configured globally, so only this one provides a credential.
+        config.set_multivar("credential.helper", "^$", "").unwrap();
+        config
+            .set_multivar(
+                "credential.helper",
+                "^!",
+                "!f() { cat >/dev/null; printf 'username=user\\npassword=secret\\n'; }; f",
+            )
+            .unwrap();
         let project = projects::Project::new_for_gitbutler_repo(
             repo.workdir().unwrap().to_path_buf(),
             self.preferred_key.clone(),
@@ -39,6 +50,14 @@ impl TestCase<'_> {
             .map(|(remote, credentials)| (remote.url().as_ref().unwrap().to_string(), credentials))
             .collect::<Vec<_>>()
     }
+
+    /// Like [`run()`](Self::run), but only return the way each credential authenticates.
+    fn methods(&self) -> Vec<(String, Vec<AuthMethod>)> {
+        self.run()
+            .into_iter()
+            .map(|(url, credentials)| (url, credentials.iter().map(Credential::method).collect()))
+            .collect()
+    }
 }
 
 mod not_github {
@@ -56,18 +75,22 @@ mod not_github {
                     private_key_path: PathBuf::from("/tmp/id_rsa"),
                 },
             };
-            let flow = test_case.run();
-            assert_eq!(flow.len(), 1);
-            assert_eq!(
-                flow[0].0,
-                "git@gitlab.com:test-gitbutler/test.git".to_string(),
-            );
             assert_eq!(
-                flow[0].1,
-                vec![Credential::Ssh(SshCredential::Keyfile {
-                    key_path: PathBuf::from("/tmp/id_rsa"),
-                    passphrase: None,
-                })]
+                test_case.methods(),
+                [
+                    (
+                        "git@gitlab.com:test-gitbutler/test.git".to_string(),
+                        vec![
+                            AuthMethod::SshAgent,
+                            AuthMethod::SshKey(PathBuf::from("/tmp/id_rsa"))
+                        ]
+                    ),
+                    (
+                        "https://gitlab.com/test-gitbutler/test.git".to_string(),
+                        vec![AuthMethod::CredentialHelper]
+                    ),
+                ],
+                "ssh-agent, then the configured key, then the credential helper"
             );
         }
 
@@ -81,20 +104,163 @@ mod not_github {
                 },
             };
             let flow = test_case.run();
-            assert_eq!(flow.len(), 1);
+            assert_eq!(flow.len(), 2);
             assert_eq!(
                 flow[0].0,
                 "git@gitlab.com:test-gitbutler/test.git".to_string(),
             );
             assert_eq!(
                 flow[0].1,
-                vec![Credential::Ssh(SshCredential::Keyfile {
-                    key_path: PathBuf::from("/tmp/id_rsa"),
-                    passphrase: None,
-                })]
+                vec![
+                    Credential::Ssh(SshCredential::Agent),
+                    Credential::Ssh(SshCredential::Keyfile {
+                        key_path: PathBuf::from("/tmp/id_rsa"),
+                        passphrase: None,
+                    })
+                ]
+            );
+            assert_eq!(
+                flow[1].0,
+                "https://gitlab.com/test-gitbutler/test.git".to_string(),
+            );
+            assert_eq!(flow[1].1.len(), 1);
+            assert_eq!(flow[1].1[0].method(), AuthMethod::CredentialHelper);
+        }
+    }
+}
+
+mod with_system_executable {
+    use super::*;
+
+    #[test]
+    fn has_no_flow() {
+        let test_case = TestCase {
+            remote_url: "git@gitlab.com:test-gitbutler/test.git",
+            preferred_key: projects::AuthKey::SystemExecutable,
+            ..Default::default()
+        };
+        assert!(
+            test_case.run().is_empty(),
+            "the Git executable authenticates by itself"
+        );
+    }
+}
+
+mod with_credentials_helper {
+    use super::*;
+
+    #[test]
+    fn ssh_tries_agent_then_default_keys_then_the_credential_helper() {
+        for remote_url in [
+            "git@gitlab.com:test-gitbutler/test.git",
+            "https://gitlab.com/test-gitbutler/test.git",
+        ] {
+            let test_case = TestCase {
+                remote_url,
+                preferred_key: projects::AuthKey::GitCredentialsHelper,
+                ..Default::default()
+            };
+            let flow = test_case.methods();
+            assert_eq!(
+                flow.len(),
+                2,
+                "{remote_url}: the SSH remote, then the HTTPS one"
+            );
+            assert_eq!(
+                flow[0].0,
+                "git@gitlab.com:test-gitbutler/test.git".to_string(),
+            );
+            let (agent, keys) = flow[0].1.split_first().expect("ssh-agent is always tried");
+            assert_eq!(
+                *agent,
+                AuthMethod::SshAgent,
+                "{remote_url}: ssh-agent first"
+            );
+            assert!(
+                keys.iter()
+                    .all(|method| matches!(method, AuthMethod::SshKey(_))),
+                "{remote_url}: then the default keys that exist: {keys:?}"
+            );
+            assert_eq!(
+                flow[1],
+                (
+                    "https://gitlab.com/test-gitbutler/test.git".to_string(),
+                    vec![AuthMethod::CredentialHelper]
+                ),
+                "{remote_url}: the credential helper last"
             );
         }
     }
+
+    #[test]
+    fn https_uses_the_credential_of_the_helper_and_reports_back() -> anyhow::Result<()> {
+        let (repo, _tmp) = test_repository();
+        repo.remote("origin", "https://gitlab.com/test-gitbutler/test.git")?;
+        let workdir = repo.workdir().expect("non-bare").to_path_buf();
+        let log = workdir.join("helper.log");
+        let mut config = repo.config()?;
+        // The empty value clears the helpers configured globally.
+        config.set_multivar("credential.helper", "^$", "")?;
+        config.set_multivar(
+            "credential.helper",
+            "^!",
+            &format!(
+                "!f() {{ {{ echo \"action=$1\"; cat; }} >> '{log}'; if [ \"$1\" = get ]; then printf 'username=user\\npassword=secret\\n'; fi; }}; f",
+                log = log.display()
+            ),
+        )?;
+        let project = projects::Project::new_for_gitbutler_repo(
+            workdir,
+            projects::AuthKey::GitCredentialsHelper,
+        );
+        let ctx = Context::new_from_legacy_project_and_settings(&project, AppSettings::default());
+
+        let git2_repo = &*ctx.git2_repo.get()?;
+        let flow = help(git2_repo, &ctx.legacy_project, "origin")?;
+        assert_eq!(
+            flow.len(),
+            2,
+            "ssh-agent and keys first, then the credential helper"
+        );
+        let credential = Credential::Https(HttpsCredential::CredentialHelper(GitCredential {
+            url: "https://gitlab.com/test-gitbutler/test.git".into(),
+            username: "user".into(),
+            password: Sensitive("secret".into()),
+        }));
+        assert_eq!(flow[1].1, [credential.clone()]);
+
+        credential.approve(git2_repo);
+        credential.reject(git2_repo);
+        let actions: Vec<_> = std::fs::read_to_string(&log)?
+            .lines()
+            .filter_map(|line| line.strip_prefix("action="))
+            .map(ToOwned::to_owned)
+            .collect();
+        assert_eq!(actions, ["get", "store", "erase"]);
+        Ok(())
+    }
+}
+
+mod auth_error {
+    use std::path::PathBuf;
+
+    use gitbutler_repo::credentials::{AuthError, AuthMethod};
+
+    #[test]
+    fn lists_all_tried_methods() {
+        let err = AuthError::exhausted(vec![
+            AuthMethod::SshAgent,
+            AuthMethod::SshKey(PathBuf::from("/tmp/id_rsa")),
+        ]);
+        assert_eq!(
+            err.to_string(),
+            "authentication failed after trying ssh-agent, SSH key at '/tmp/id_rsa'"
+        );
+        assert!(matches!(
+            AuthError::exhausted(vec![]),
+            AuthError::NoAuthMethod
+        ));
+    }
 }
 
 mod github {
@@ -115,18 +281,21 @@ mod github {
                         private_key_path: PathBuf::from("/tmp/id_rsa"),
                     },
                 };
-                let flow = test_case.run();
-                assert_eq!(flow.len(), 1);
                 assert_eq!(
-                    flow[0].0,
-                    "git@github.com:gitbutlerapp/gitbutler.git".to_string(),
-                );
-                assert_eq!(
-                    flow[0].1,
-                    vec![Credential::Ssh(SshCredential::Keyfile {
-                        key_path: PathBuf::from("/tmp/id_rsa"),
-                        passphrase: None,
-                    })]
+                    test_case.methods(),
+                    [
+                        (
+                            "git@github.com:gitbutlerapp/gitbutler.git".to_string(),
+                            vec![
+                                AuthMethod::SshAgent,
+                                AuthMethod::SshKey(PathBuf::from("/tmp/id_rsa"))
+                            ]
+                        ),
+                        (
+                            "https://github.com/gitbutlerapp/gitbutler.git".to_string(),
+                            vec![AuthMethod::CredentialHelper]
+                        ),
+                    ]
                 );
             }
 
@@ -139,18 +308,21 @@ mod github {
                         private_key_path: PathBuf::from("/tmp/id_rsa"),
                     },
                 };
-                let flow = test_case.run();
-                assert_eq!(flow.len(), 1);
                 assert_eq!(
-                    flow[0].0,
-                    "git@github.com:gitbutlerapp/gitbutler.git".to_string(),
-                );
-                assert_eq!(
-                    flow[0].1,
-                    vec![Credential::Ssh(SshCredential::Keyfile {
-                        key_path: PathBuf::from("/tmp/id_rsa"),
-                        passphrase: None,
-                    })]
+                    test_case.methods(),
+                    [
+                        (
+                            "git@github.com:gitbutlerapp/gitbutler.git".to_string(),
+                            vec![
+                                AuthMethod::SshAgent,
+                                AuthMethod::SshKey(PathBuf::from("/tmp/id_rsa"))
+                            ]
+                        ),
+                        (
+                            "https://github.com/gitbutlerapp/gitbutler.git".to_string(),
+                            vec![AuthMethod::CredentialHelper]
+                        ),
+                    ]
                 );
             }
         }
@@ -91,3 +91,35 @@ fn pre_push_hook_failure() -> anyhow::Result<()> {
     }
     Ok(())
 }
+
+#[test]
+fn pre_push_hook_in_relative_core_hooks_path() -> anyhow::Result<()> {
+    let test_project = TestProject::default();
+
+    let repo = &test_project.local_repo;
+    repo.config()?.set_str("core.hooksPath", "custom-hooks")?;
+    let hooks_dir = repo.workdir().expect("non-bare").join("custom-hooks");
+    fs::create_dir_all(&hooks_dir)?;
+    let hook_path = hooks_dir.join("pre-push");
+
+    fs::write(&hook_path, "#!/bin/sh\necho from custom hooks\nexit 1\n")?;
+
+    #[cfg(unix)]
+    fs::set_permissions(&hook_path, fs::Permissions::from_mode(0o755))?;
+
+    let result = pre_push(
+        repo,
+        "origin",
+        "https://github.com/test/repo.git",
+        repo.head()?.target().expect("not detached"),
+        &gitbutler_reference::RemoteRefname::new("origin", "master"),
+    )?;
+    assert_eq!(
+        result,
+        HookResult::Failure(gitbutler_repo::hooks::ErrorData {
+            error: "from custom hooks\n".into()
+        }),
+        "relative hook paths are relative to the worktree, like in Git"
+    );
+    Ok(())
+}
@@ -1,3 +1,4 @@
+mod checkout_tree_builder;
 mod create_wd_tree;
 mod credentials;
 mod hooks;
@@ -14,7 +14,7 @@ mod gitbutler_merge_commits {
         let c = test_repository.commit_tree(Some(&a), &[("foo.txt", "a"), ("bar.txt", "a")]);
 
         let result =
-            gitbutler_merge_commits(&test_repository.repository, b, c, "master", "feature")
+            gitbutler_merge_commits(&test_repository.repository, b, c, "master", "feature", None)
                 .unwrap();
 
         assert_commit_tree_matches(
@@ -34,7 +34,7 @@ mod gitbutler_merge_commits {
         let c = test_repository.commit_tree(Some(&a), &[("foo.txt", "c")]);
 
         let result =
-            gitbutler_merge_commits(&test_repository.repository, b, c, "master", "feature")
+            gitbutler_merge_commits(&test_repository.repository, b, c, "master", "feature", None)
                 .unwrap();
 
         assert_commit_tree_matches(
@@ -60,7 +60,7 @@ mod gitbutler_merge_commits {
         let d = test_repository.commit_tree(Some(&a), &[("foo.txt", "a"), ("bar.txt", "a")]);
 
         let bc_result =
-            gitbutler_merge_commits(&test_repository.repository, b, c, "master", "feature")
+            gitbutler_merge_commits(&test_repository.repository, b, c, "master", "feature", None)
                 .unwrap();
 
         let result = gitbutler_merge_commits(
@@ -69,6 +69,7 @@ mod gitbutler_merge_commits {
             d,
             "master",
             "feature",
+            None,
         )
         .unwrap();
 
@@ -98,11 +99,11 @@ mod gitbutler_merge_commits {
         let e = test_repository.commit_tree(Some(&a), &[("foo.txt", "a"), ("bar.txt", "c")]);
 
         let bc_result =
-            gitbutler_merge_commits(&test_repository.repository, b, c, "master", "feature")
+            gitbutler_merge_commits(&test_repository.repository, b, c, "master", "feature", None)
                 .unwrap();
 
         let de_result =
-            gitbutler_merge_commits(&test_repository.repository, d, e, "master", "feature")
+            gitbutler_merge_commits(&test_repository.repository, d, e, "master", "feature", None)
                 .unwrap();
 
         let result = gitbutler_merge_commits(
@@ -111,6 +112,7 @@ mod gitbutler_merge_commits {
             de_result,
             "master",
             "feature",
+            None,
         )
         .unwrap();
 
@@ -145,11 +147,11 @@ mod gitbutler_merge_commits {
         let e = test_repository.commit_tree(Some(&a), &[("foo.txt", "f")]);
 
         let bc_result =
-            gitbutler_merge_commits(&test_repository.repository, b, c, "master", "feature")
+            gitbutler_merge_commits(&test_repository.repository, b, c, "master", "feature", None)
                 .unwrap();
 
         let de_result =
-            gitbutler_merge_commits(&test_repository.repository, d, e, "master", "feature")
+            gitbutler_merge_commits(&test_repository.repository, d, e, "master", "feature", None)
                 .unwrap();
 
         let result = gitbutler_merge_commits(
@@ -158,6 +160,7 @@ mod gitbutler_merge_commits {
             de_result,
             "master",
             "feature",
+            None,
         )
         .unwrap();
 
@@ -1,8 +1,10 @@
 #![warn(clippy::indexing_slicing)]
+mod rewritten;
 mod stack;
 mod state;
 mod target;
 
+pub use rewritten::{RewrittenCommits, RewrittenCommitsHandle};
 pub use stack::{Stack, StackId};
 pub use state::{VirtualBranches as VirtualBranchesState, VirtualBranchesHandle};
 pub use target::Target;
@@ -0,0 +1,122 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Result;
+use bstr::ByteSlice;
+
+/// The amount of rewrites that are remembered, the oldest ones are forgotten first.
+const MAX_ENTRIES: usize = 10_000;
+
+/// A persistent record of which commit was rewritten into which other commit by rebases and amends,
+/// similar to the `rewritten` list Git keeps during a rebase.
+///
+/// It's stored as `rewritten-list` next to `virtual_branches.toml`, with one `<old> <new>` pair per line,
+/// the most recent rewrite last.
+pub struct RewrittenCommitsHandle {
+    /// The path to the file containing the rewrites.
+    file_path: PathBuf,
+}
+
+impl RewrittenCommitsHandle {
+    /// Creates a new handle to the rewritten commits of the project whose data is stored in `base_path`.
+    pub fn new<P: AsRef<Path>>(base_path: P) -> Self {
+        let file_path = base_path.as_ref().join("rewritten-list");
+        Self { file_path }
+    }
+
+    /// Remember that each `old` commit in `rewrites` was rewritten into its `new` commit.
+    ///
+    /// Pairs where nothing was rewritten are ignored. This is best-effort as the rewrite already happened
+    /// by the time it's recorded, so failures are only logged.
+    pub fn record(&self, rewrites: impl IntoIterator<Item = (gix::ObjectId, gix::ObjectId)>) {
+        if let Err(err) = self.try_record(rewrites) {
+            tracing::warn!("Could not record rewritten commits: {err:#}");
+        }
+    }
+
+    fn try_record(
+        &self,
+        rewrites: impl IntoIterator<Item = (gix::ObjectId, gix::ObjectId)>,
+    ) -> Result<()> {
+        let mut rewrites = rewrites
+            .into_iter()
+            .filter(|(old, new)| old != new)
+            .peekable();
+        if rewrites.peek().is_none() {
+            return Ok(());
+        }
+        let mut entries = self.read_file()?;
+        entries.extend(rewrites);
+        if entries.len() > MAX_ENTRIES {
+            entries.drain(..entries.len() - MAX_ENTRIES);
+        }
+        let mut out = String::with_capacity(entries.len() * 82);
+        for (old, new) in &entries {
+            out.push_str(&format!("{old} {new}\n"));
+        }
+        but_fs::create_dirs_then_write(&self.file_path, out).map_err(Into::into)
+    }
+
+    /// Return the commit that `commit_id` was most recently rewritten into, following it through all recorded
+    /// rewrites, or `None` if it wasn't rewritten.
+    ///
+    /// Use [`Self::load()`] to resolve many commits.
+    pub fn resolve(&self, commit_id: gix::ObjectId) -> Result<Option<gix::ObjectId>> {
+        Ok(self.load()?.resolve(commit_id))
+    }
+
+    /// Read all recorded rewrites at once to resolve any amount of commits.
+    pub fn load(&self) -> Result<RewrittenCommits> {
+        Ok(RewrittenCommits(self.read_file()?.into_iter().collect()))
+    }
+
+    /// Return all recorded rewrites as `(old, new)` pairs, the oldest one first.
+    /// Lines that can't be parsed, like those of a partially written file, are skipped.
+    pub fn read_file(&self) -> Result<Vec<(gix::ObjectId, gix::ObjectId)>> {
+        let data = match std::fs::read(&self.file_path) {
+            Ok(data) => data,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err.into()),
+        };
+        Ok(data
+            .lines()
+            .filter(|line| !line.is_empty())
+            .filter_map(|line| {
+                let parsed = line.split_once_str(" ").and_then(|(old, new)| {
+                    Some((
+                        gix::ObjectId::from_hex(old).ok()?,
+                        gix::ObjectId::from_hex(new).ok()?,
+                    ))
+                });
+                if parsed.is_none() {
+                    tracing::warn!(
+                        "Skipping invalid line in rewritten-list: {}",
+                        line.as_bstr()
+                    );
+                }
+                parsed
+            })
+            .collect())
+    }
+}
+
+/// All recorded rewrites, as loaded by [`RewrittenCommitsHandle::load()`].
+pub struct RewrittenCommits(HashMap<gix::ObjectId, gix::ObjectId>);
+
+impl RewrittenCommits {
+    /// Return the commit that `commit_id` was most recently rewritten into, following it through all recorded
+    /// rewrites, or `None` if it wasn't rewritten.
+    pub fn resolve(&self, commit_id: gix::ObjectId) -> Option<gix::ObjectId> {
+        let mut current = commit_id;
+        // A commit may be rewritten back into a previous version of itself, so don't follow rewrites forever.
+        for _ in 0..self.0.len() {
+            match self.0.get(&current) {
+                Some(new) if *new != commit_id => current = *new,
+                _ => break,
+            }
+        }
+        (current != commit_id).then_some(current)
+    }
+}
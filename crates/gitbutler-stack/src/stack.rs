@@ -1,28 +1,30 @@
 use std::{
+    borrow::Cow,
     collections::{HashMap, HashSet},
     path::Path,
     str::FromStr,
 };
 
 use anyhow::{Context as _, Result, anyhow, bail};
-use but_core::Reference;
 pub use but_core::ref_metadata::StackId;
+use but_core::ref_metadata::{StackIdentity, StackNotes};
+use but_core::{Reference, RepositoryExt as _};
 use but_ctx::Context;
 use but_meta::virtual_branches_legacy_types;
-use but_oxidize::{ObjectIdExt, OidExt};
-use but_rebase::ReferenceSpec;
+use but_oxidize::{ObjectIdExt, OidExt, gix_to_git2_signature};
+use but_rebase::RebaseOutput;
 use git2::Commit;
 use gitbutler_reference::{Refname, RemoteRefname, VirtualRefname, normalize_branch_name};
 use gitbutler_repo::{
     RepositoryExt,
     logging::{LogUntil, RepositoryExt as _},
 };
-use gix::{utils::str::decompose, validate::reference::name_partial};
+use gix::{date::parse::TimeBuf, utils::str::decompose, validate::reference::name_partial};
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    StackBranch, VirtualBranchesHandle,
+    RewrittenCommitsHandle, StackBranch, VirtualBranchesHandle,
     heads::{add_head, get_head, remove_head},
     stack_branch::remote_reference,
 };
@@ -48,6 +50,10 @@ pub struct Stack {
     /// Represents the Stack state of pseudo-references ("heads").
     /// Do **NOT** edit this directly, instead use the `Stack` trait in gitbutler_stack.
     pub heads: Vec<StackBranch>,
+    /// Who to author and commit as in this stack, if not as configured in Git.
+    pub identity: Option<StackIdentity>,
+    /// The description of the stack and notes about its commits.
+    pub stack_notes: StackNotes,
 }
 
 impl From<virtual_branches_legacy_types::Stack> for Stack {
@@ -59,6 +65,8 @@ impl From<virtual_branches_legacy_types::Stack> for Stack {
             order,
             in_workspace,
             heads,
+            identity,
+            stack_notes,
             ..
         }: virtual_branches_legacy_types::Stack,
     ) -> Self {
@@ -69,6 +77,8 @@ impl From<virtual_branches_legacy_types::Stack> for Stack {
             order,
             in_workspace,
             heads: heads.into_iter().map(Into::into).collect(),
+            identity,
+            stack_notes,
         }
     }
 }
@@ -82,6 +92,8 @@ impl From<Stack> for virtual_branches_legacy_types::Stack {
             order,
             in_workspace,
             heads,
+            identity,
+            stack_notes,
         }: Stack,
     ) -> Self {
         virtual_branches_legacy_types::Stack {
@@ -91,6 +103,8 @@ impl From<Stack> for virtual_branches_legacy_types::Stack {
             order,
             in_workspace,
             heads: heads.into_iter().map(Into::into).collect(),
+            identity,
+            stack_notes,
             // Dummy values for backwards compatibility
             #[allow(deprecated)]
             notes: String::new(),
@@ -151,6 +165,8 @@ impl Stack {
             // Don't keep redundant information
             source_refname: None,
             upstream: None,
+            identity: None,
+            stack_notes: StackNotes::default(),
         }
     }
 
@@ -169,6 +185,33 @@ impl Stack {
         }
     }
 
+    /// Return `repo` configured to author and commit as set in the [identity](Self::identity) of this stack,
+    /// or `repo` itself if there is no such override.
+    pub fn repo_with_identity<'repo>(
+        &self,
+        repo: &'repo gix::Repository,
+    ) -> Result<Cow<'repo, gix::Repository>> {
+        Ok(match &self.identity {
+            Some(identity) if !identity.is_empty() => {
+                Cow::Owned(repo.clone().with_stack_identity(identity)?)
+            }
+            _ => Cow::Borrowed(repo),
+        })
+    }
+
+    /// Return the author and committer for commits created in this stack with `git2`, taking its
+    /// [identity](Self::identity) into account.
+    pub fn signatures(
+        &self,
+        repo: &gix::Repository,
+    ) -> Result<(git2::Signature<'static>, git2::Signature<'static>)> {
+        let (author, committer) = self.repo_with_identity(repo)?.commit_signatures()?;
+        Ok((
+            gix_to_git2_signature(author.to_ref(&mut TimeBuf::default()))?,
+            gix_to_git2_signature(committer.to_ref(&mut TimeBuf::default()))?,
+        ))
+    }
+
     pub fn tree(&self, ctx: &Context) -> Result<git2::Oid> {
         let repo = ctx.repo.get()?;
         repo.find_commit(self.head_oid(ctx)?)?
@@ -204,6 +247,8 @@ impl Stack {
             order,
             in_workspace: true,
             heads: vec![stack_branch],
+            identity: None,
+            stack_notes: StackNotes::default(),
         })
     }
 
@@ -219,6 +264,8 @@ impl Stack {
             order,
             in_workspace: true,
             heads: vec![stack_branch],
+            identity: None,
+            stack_notes: StackNotes::default(),
         })
     }
 
@@ -269,7 +316,13 @@ impl Stack {
         let virtual_branch_state = VirtualBranchesHandle::new(ctx.project_data_dir());
         let target = virtual_branch_state.get_default_target()?;
         let gix_repo = ctx.repo.get()?;
-        let merge_base = gix_repo.merge_base(self.head_oid(ctx)?, target.sha.to_gix())?;
+        let head = self.head_oid(ctx)?;
+        // Without a merge-base, the shallow boundary is likely to cut through the history of the stack.
+        let merge_base = gix_repo
+            .merge_base(head, target.sha.to_gix())
+            .map_err(|err| {
+                but_core::shallow::into_shallow_history_error(&gix_repo, head, err.into())
+            })?;
         Ok(merge_base.detach())
     }
 
@@ -622,15 +675,22 @@ impl Stack {
         Ok(())
     }
 
-    /// Sets the stack heads according to the output from the rebase of a `but-rebase` rebase operation
+    /// Sets the stack heads according to the output from the rebase of a `but-rebase` rebase operation,
+    /// records the rewritten commits and lets the notes about commits follow them.
     pub fn set_heads_from_rebase_output(
         &mut self,
         ctx: &Context,
-        references: Vec<ReferenceSpec>,
+        output: &RebaseOutput,
     ) -> anyhow::Result<()> {
+        let rewrites = output
+            .commit_mapping
+            .iter()
+            .map(|(_base, old, new)| (*old, *new));
+        RewrittenCommitsHandle::new(ctx.project_data_dir()).record(rewrites.clone());
+        self.stack_notes.rewrite_commits(rewrites);
         let git2_repo = ctx.git2_repo.get()?;
         let mut new_heads: HashMap<String, Commit<'_>> = HashMap::new();
-        for spec in &references {
+        for spec in &output.references {
             let commit = git2_repo.find_commit(spec.commit_id.to_git2())?;
             new_heads.insert(spec.reference.to_string(), commit);
         }
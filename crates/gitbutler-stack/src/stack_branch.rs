@@ -33,6 +33,12 @@ pub struct StackBranch {
     pub archived: bool,
 
     pub review_id: Option<String>,
+    /// The commit the remote branch pointed to after GitButler last pushed it, or `None` if it wasn't pushed yet.
+    /// Force-pushes are refused if the remote moved away from it, like with `git push --force-with-lease`.
+    pub remote_head: Option<gix::ObjectId>,
+    /// The reference of this branch was deleted outside of GitButler, like with `git branch -D`,
+    /// detaching the branch from its stack until the reference is recreated.
+    pub detached: bool,
 }
 
 impl From<virtual_branches_legacy_types::StackBranch> for StackBranch {
@@ -43,6 +49,8 @@ impl From<virtual_branches_legacy_types::StackBranch> for StackBranch {
             pr_number,
             archived,
             review_id,
+            remote_head,
+            detached,
         }: virtual_branches_legacy_types::StackBranch,
     ) -> Self {
         StackBranch {
@@ -51,6 +59,8 @@ impl From<virtual_branches_legacy_types::StackBranch> for StackBranch {
             pr_number,
             archived,
             review_id,
+            remote_head,
+            detached,
         }
     }
 }
@@ -63,6 +73,8 @@ impl From<StackBranch> for virtual_branches_legacy_types::StackBranch {
             pr_number,
             archived,
             review_id,
+            remote_head,
+            detached,
         }: StackBranch,
     ) -> Self {
         virtual_branches_legacy_types::StackBranch {
@@ -71,6 +83,8 @@ impl From<StackBranch> for virtual_branches_legacy_types::StackBranch {
             pr_number,
             archived,
             review_id,
+            remote_head,
+            detached,
         }
     }
 }
@@ -83,6 +97,8 @@ impl StackBranch {
             pr_number: None,
             archived: false,
             review_id: None,
+            remote_head: None,
+            detached: false,
         };
         branch.set_real_reference(repo, &branch.head)?;
         Ok(branch)
@@ -100,6 +116,8 @@ impl StackBranch {
             archived,
             review_id,
             head: gix::hash::Kind::Sha1.null(),
+            remote_head: None,
+            detached: false,
         }
     }
 
@@ -4,6 +4,7 @@ use std::{
 };
 
 use anyhow::{Result, anyhow};
+use but_core::sync::events::WorkspaceEvent;
 use but_error::Code;
 use but_fs::read_toml_file_or_default;
 use but_meta::virtual_branches_legacy_types;
@@ -128,8 +129,10 @@ impl VirtualBranchesHandle {
     /// Errors if the file cannot be read or written.
     pub fn set_default_target(&self, target: Target) -> Result<()> {
         let mut virtual_branches = self.read_file()?;
+        let target_sha = target.sha.to_gix();
         virtual_branches.default_target = Some(target);
         self.write_file(&virtual_branches)?;
+        self.publish(WorkspaceEvent::BaseMoved { target_sha });
         Ok(())
     }
 
@@ -156,8 +159,10 @@ impl VirtualBranchesHandle {
     /// Errors if the file cannot be read or written.
     pub fn set_stack(&self, stack: Stack) -> Result<()> {
         let mut virtual_branches = self.read_file()?;
-        virtual_branches.branches.insert(stack.id, stack);
+        let stack_id = stack.id;
+        virtual_branches.branches.insert(stack_id, stack);
         self.write_file(&virtual_branches)?;
+        self.publish(WorkspaceEvent::StackUpdated { stack_id });
         Ok(())
     }
 
@@ -311,6 +316,9 @@ impl VirtualBranchesHandle {
         let mut virtual_branches = self.read_file()?;
         virtual_branches.branches.remove(branch_id);
         self.write_file(&virtual_branches)?;
+        self.publish(WorkspaceEvent::StackUpdated {
+            stack_id: *branch_id,
+        });
         Ok(())
     }
 
@@ -423,6 +431,13 @@ impl VirtualBranchesHandle {
         let virtual_branches = self.read_file()?;
         Ok(virtual_branches.last_pushed_base)
     }
+
+    /// Let observers of the project know about `event`, as the state file lives in its application data directory.
+    fn publish(&self, event: WorkspaceEvent) {
+        if let Some(project_data_dir) = self.file_path.parent() {
+            but_core::sync::events::publish(project_data_dir, event);
+        }
+    }
 }
 
 fn write<P: AsRef<Path>>(file_path: P, virtual_branches: &VirtualBranches) -> Result<()> {
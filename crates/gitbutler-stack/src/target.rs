@@ -27,6 +27,12 @@ pub struct Target {
 }
 
 impl Target {
+    /// The name of the remote the target branch is fetched from, like `upstream` in fork-based workflows.
+    pub fn fetch_remote_name(&self) -> &str {
+        self.branch.remote()
+    }
+
+    /// The name of the remote branches are pushed to, which defaults to the [fetch remote](Self::fetch_remote_name()).
     pub fn push_remote_name(&self) -> String {
         match &self.push_remote_name {
             Some(remote) => remote.clone(),
@@ -34,6 +40,29 @@ impl Target {
         }
     }
 
+    /// Return `true` if the target is a local branch, which is the case in repositories without a remote
+    /// until one is configured.
+    pub fn is_local(&self) -> bool {
+        self.branch.is_local()
+    }
+
+    /// Return the names of the fetch remote and the push remote, without duplicates,
+    /// for fetching everything needed to integrate and push.
+    ///
+    /// There is nothing to fetch for [local targets](Self::is_local()).
+    pub fn remote_names(&self) -> Vec<String> {
+        if self.is_local() {
+            return self.push_remote_name.iter().cloned().collect();
+        }
+        let fetch_remote = self.fetch_remote_name().to_owned();
+        let push_remote = self.push_remote_name();
+        if push_remote == fetch_remote {
+            vec![fetch_remote]
+        } else {
+            vec![fetch_remote, push_remote]
+        }
+    }
+
     /// Returns the head sha of the remote branch this target is tracking.
     pub fn remote_head(&self, repo: &git2::Repository) -> Result<git2::Oid> {
         let branch = repo.find_branch_by_refname(&self.branch.clone().into())?;
@@ -5,7 +5,9 @@ use but_oxidize::{ObjectIdExt, OidExt};
 use git2::Commit;
 use gitbutler_repo::logging::{LogUntil, RepositoryExt as _};
 use gitbutler_repo_actions::RepoActionsExt;
-use gitbutler_stack::{PatchReferenceUpdate, StackBranch, VirtualBranchesHandle};
+use gitbutler_stack::{
+    PatchReferenceUpdate, RewrittenCommitsHandle, StackBranch, VirtualBranchesHandle,
+};
 use itertools::Itertools;
 use tempfile::TempDir;
 
@@ -422,6 +424,46 @@ fn push_series_success() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn remote_head_is_persisted() -> Result<()> {
+    let (ctx, _temp_dir) = command_ctx("multiple-commits")?;
+    let mut test_ctx = test_ctx(&ctx)?;
+    assert_eq!(test_ctx.stack.heads[0].remote_head, None, "never pushed");
+
+    let pushed = test_ctx.commits[0].id().to_gix();
+    test_ctx.stack.heads[0].remote_head = Some(pushed);
+    test_ctx.handle.set_stack(test_ctx.stack.clone())?;
+
+    let toml = std::fs::read_to_string(ctx.project_data_dir().join("virtual_branches.toml"))?;
+    assert!(toml.contains(&format!("remote_head = \"{pushed}\"")));
+    assert_eq!(
+        test_ctx.handle.get_stack(test_ctx.stack.id)?.heads[0].remote_head,
+        Some(pushed)
+    );
+    Ok(())
+}
+
+#[test]
+fn target_remote_names_with_separate_push_remote() -> Result<()> {
+    let (ctx, _temp_dir) = command_ctx("multiple-commits")?;
+    let state = VirtualBranchesHandle::new(ctx.project_data_dir());
+    let mut target = state.get_default_target()?;
+    assert_eq!(target.remote_names(), ["origin"]);
+
+    target.push_remote_name = Some("fork".into());
+    assert_eq!(target.fetch_remote_name(), "origin");
+    assert_eq!(target.push_remote_name(), "fork");
+    assert_eq!(
+        target.remote_names(),
+        ["origin", "fork"],
+        "the fetch remote comes first"
+    );
+
+    target.push_remote_name = Some("origin".into());
+    assert_eq!(target.remote_names(), ["origin"], "no duplicates");
+    Ok(())
+}
+
 #[test]
 fn update_name_after_push() -> Result<()> {
     let (ctx, _temp_dir) = command_ctx("multiple-commits")?;
@@ -439,6 +481,7 @@ fn update_name_after_push() -> Result<()> {
         false,
         false,
         None,
+        None,
         Some(Some(test_ctx.stack.id)),
         vec![],
     );
@@ -745,6 +788,55 @@ fn add_head_with_archived_bottom_head() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn rewritten_commits_are_followed_to_their_latest_version() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let rewritten = RewrittenCommitsHandle::new(tmp.path());
+    let id = |byte: u8| gix::ObjectId::from_bytes_or_panic(&[byte; 20]);
+    assert_eq!(rewritten.resolve(id(1))?, None);
+
+    rewritten.record([(id(1), id(2)), (id(3), id(3))]);
+    rewritten.record([(id(2), id(4))]);
+    assert_eq!(
+        rewritten.read_file()?.len(),
+        2,
+        "no-op rewrites aren't recorded"
+    );
+    assert_eq!(rewritten.resolve(id(1))?, Some(id(4)));
+    assert_eq!(rewritten.resolve(id(2))?, Some(id(4)));
+    assert_eq!(rewritten.resolve(id(4))?, None);
+
+    rewritten.record([(id(4), id(1))]);
+    assert_eq!(
+        rewritten.resolve(id(1))?,
+        Some(id(4)),
+        "cycles end before reaching the commit itself again"
+    );
+    Ok(())
+}
+
+#[test]
+fn invalid_rewritten_commits_are_skipped() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let rewritten = RewrittenCommitsHandle::new(tmp.path());
+    let id = |byte: u8| gix::ObjectId::from_bytes_or_panic(&[byte; 20]);
+    std::fs::write(
+        tmp.path().join("rewritten-list"),
+        format!("{} {}\nnot a rewrite\n{} 0123", id(1), id(2), id(2)),
+    )?;
+    assert_eq!(rewritten.read_file()?, [(id(1), id(2))]);
+
+    rewritten.record([(id(2), id(3))]);
+    let rewrites = rewritten.load()?;
+    assert_eq!(
+        rewrites.resolve(id(1)),
+        Some(id(3)),
+        "recording still works, and drops the invalid lines"
+    );
+    assert_eq!(rewritten.read_file()?.len(), 2);
+    Ok(())
+}
+
 fn command_ctx(name: &str) -> Result<(Context, TempDir)> {
     gitbutler_testsupport::writable::fixture("stacking.sh", name)
 }
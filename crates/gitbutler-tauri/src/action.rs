@@ -39,6 +39,29 @@ pub fn handle_changes(
     .map_err(|e| Error::from(anyhow::anyhow!(e)))
 }
 
+#[tauri::command(async)]
+#[instrument(err(Debug))]
+pub fn propose_stacks(
+    project_id: ProjectId,
+    use_ai: bool,
+) -> anyhow::Result<Vec<but_action::grouping::ProposedStack>, Error> {
+    let project = gitbutler_project::get(project_id)?;
+    let ctx = &mut Context::new_from_legacy_project(project.clone())?;
+    but_action::grouping::propose_stacks(ctx, use_ai).map_err(|e| Error::from(anyhow::anyhow!(e)))
+}
+
+#[tauri::command(async)]
+#[instrument(err(Debug))]
+pub fn accept_proposed_stacks(
+    project_id: ProjectId,
+    proposals: Vec<but_action::grouping::ProposedStack>,
+) -> anyhow::Result<but_action::grouping::AcceptOutcome, Error> {
+    let project = gitbutler_project::get(project_id)?;
+    let ctx = &mut Context::new_from_legacy_project(project.clone())?;
+    but_action::grouping::accept_proposed_stacks(ctx, proposals)
+        .map_err(|e| Error::from(anyhow::anyhow!(e)))
+}
+
 #[tauri::command(async)]
 #[instrument(err(Debug))]
 pub fn list_workflows(
@@ -14,7 +14,7 @@
 use std::sync::Arc;
 
 use anyhow::{Context, bail};
-use but_api::{commit, diff, github, legacy};
+use but_api::{bitbucket, commit, diff, github, gitlab, legacy, operations};
 use but_claude::{Broadcaster, Claude};
 use but_settings::AppSettingsWithDiskSync;
 use gitbutler_tauri::{
@@ -228,6 +228,12 @@ fn main() -> anyhow::Result<()> {
                 github::tauri_forget_github_account::forget_github_account,
                 github::tauri_list_known_github_accounts::list_known_github_accounts,
                 github::tauri_clear_all_github_tokens::clear_all_github_tokens,
+                gitlab::tauri_store_gitlab_pat::store_gitlab_pat,
+                gitlab::tauri_forget_gitlab_account::forget_gitlab_account,
+                gitlab::tauri_list_known_gitlab_accounts::list_known_gitlab_accounts,
+                bitbucket::tauri_store_bitbucket_api_token::store_bitbucket_api_token,
+                bitbucket::tauri_forget_bitbucket_account::forget_bitbucket_account,
+                bitbucket::tauri_list_known_bitbucket_accounts::list_known_bitbucket_accounts,
                 diff::tauri_commit_details::commit_details,
                 diff::tauri_commit_details_with_line_stats::commit_details_with_line_stats,
                 but_api::branch::tauri_branch_diff::branch_diff,
@@ -257,18 +263,30 @@ fn main() -> anyhow::Result<()> {
                 legacy::repo::tauri_pre_commit_hook_diffspecs::pre_commit_hook_diffspecs,
                 legacy::repo::tauri_post_commit_hook::post_commit_hook,
                 legacy::repo::tauri_message_hook::message_hook,
+                legacy::repo::tauri_validate_commit_message::validate_commit_message,
+                legacy::repo::tauri_fix_commit_message::fix_commit_message,
+                legacy::repo::tauri_list_recorded_resolutions::list_recorded_resolutions,
+                legacy::repo::tauri_forget_recorded_resolution::forget_recorded_resolution,
                 legacy::cherry_apply::tauri_cherry_apply_status::cherry_apply_status,
                 legacy::cherry_apply::tauri_cherry_apply::cherry_apply,
                 legacy::virtual_branches::tauri_create_virtual_branch::create_virtual_branch,
                 legacy::virtual_branches::tauri_delete_local_branch::delete_local_branch,
                 legacy::virtual_branches::tauri_get_base_branch_data::get_base_branch_data,
                 legacy::virtual_branches::tauri_set_base_branch::set_base_branch,
+                legacy::virtual_branches::tauri_switch_base_branch::switch_base_branch,
                 legacy::virtual_branches::tauri_switch_back_to_workspace::switch_back_to_workspace,
                 legacy::virtual_branches::tauri_push_base_branch::push_base_branch,
                 legacy::virtual_branches::tauri_integrate_upstream_commits::integrate_upstream_commits,
+                legacy::virtual_branches::tauri_integrate_upstream_for_stack::integrate_upstream_for_stack,
                 legacy::virtual_branches::tauri_get_initial_integration_steps_for_branch::get_initial_integration_steps_for_branch,
                 legacy::virtual_branches::tauri_update_stack_order::update_stack_order,
                 legacy::virtual_branches::tauri_unapply_stack::unapply_stack,
+                legacy::virtual_branches::tauri_archive_stack::archive_stack,
+                legacy::virtual_branches::tauri_branch_divergence::branch_divergence,
+                legacy::virtual_branches::tauri_conflict_details::conflict_details,
+                legacy::virtual_branches::tauri_export_patches::export_patches,
+                legacy::virtual_branches::tauri_export_stack_bundle::export_stack_bundle,
+                legacy::virtual_branches::tauri_import_stack_bundle::import_stack_bundle,
                 legacy::virtual_branches::tauri_create_virtual_branch_from_branch::create_virtual_branch_from_branch,
                 legacy::virtual_branches::tauri_amend_virtual_branch::amend_virtual_branch,
                 legacy::virtual_branches::tauri_undo_commit::undo_commit,
@@ -278,6 +296,13 @@ fn main() -> anyhow::Result<()> {
                 legacy::virtual_branches::tauri_get_branch_listing_details::get_branch_listing_details,
                 legacy::virtual_branches::tauri_integrate_branch_with_steps::integrate_branch_with_steps,
                 legacy::virtual_branches::tauri_squash_commits::squash_commits,
+                legacy::virtual_branches::tauri_autosquash_stack::autosquash_stack,
+                legacy::virtual_branches::tauri_resolve_rewritten::resolve_rewritten,
+                legacy::virtual_branches::tauri_external_commits::external_commits,
+                legacy::virtual_branches::tauri_adopt_external_commits::adopt_external_commits,
+                legacy::virtual_branches::tauri_cherry_pick_into_stack::cherry_pick_into_stack,
+                legacy::virtual_branches::tauri_revert_commit::revert_commit,
+                legacy::virtual_branches::tauri_apply_patch_series::apply_patch_series,
                 legacy::virtual_branches::tauri_fetch_from_remotes::fetch_from_remotes,
                 legacy::virtual_branches::tauri_move_commit::move_commit,
                 legacy::virtual_branches::tauri_move_branch::move_branch,
@@ -286,21 +311,41 @@ fn main() -> anyhow::Result<()> {
                 legacy::virtual_branches::tauri_upstream_integration_statuses::upstream_integration_statuses,
                 legacy::virtual_branches::tauri_integrate_upstream::integrate_upstream,
                 legacy::virtual_branches::tauri_resolve_upstream_integration::resolve_upstream_integration,
+                legacy::virtual_branches::tauri_detect_integrated_stacks::detect_integrated_stacks,
                 legacy::stack::tauri_create_reference::create_reference,
                 legacy::stack::tauri_create_branch::create_branch,
                 legacy::stack::tauri_remove_branch::remove_branch,
                 legacy::stack::tauri_update_branch_name::update_branch_name,
                 legacy::stack::tauri_update_branch_pr_number::update_branch_pr_number,
+                legacy::stack::tauri_stack_identity::stack_identity,
+                legacy::stack::tauri_set_stack_identity::set_stack_identity,
+                legacy::stack::tauri_stack_notes::stack_notes,
+                legacy::stack::tauri_set_stack_description::set_stack_description,
+                legacy::stack::tauri_set_commit_note::set_commit_note,
                 legacy::stack::tauri_push_stack::push_stack,
+                legacy::stack::tauri_push_stack_all::push_stack_all,
                 legacy::secret::tauri_secret_get_global::secret_get_global,
                 legacy::secret::tauri_secret_set_global::secret_set_global,
                 legacy::secret::tauri_secret_delete_global::secret_delete_global,
                 legacy::oplog::tauri_list_snapshots::list_snapshots,
                 legacy::oplog::tauri_create_snapshot::create_snapshot,
                 legacy::oplog::tauri_restore_snapshot::restore_snapshot,
+                legacy::oplog::tauri_create_named_snapshot::create_named_snapshot,
+                legacy::oplog::tauri_list_named_snapshots::list_named_snapshots,
+                legacy::oplog::tauri_restore_named_snapshot::restore_named_snapshot,
+                legacy::oplog::tauri_restore_stack::restore_stack,
                 legacy::oplog::tauri_snapshot_diff::snapshot_diff,
+                legacy::oplog::tauri_stack_diff::stack_diff,
+                legacy::oplog::tauri_snapshot_delta::snapshot_delta,
+                legacy::oplog::tauri_file_history::file_history,
+                legacy::oplog::tauri_prune_snapshots::prune_snapshots,
+                legacy::oplog::tauri_run_maintenance::run_maintenance,
                 legacy::config::tauri_get_gb_config::get_gb_config,
                 legacy::config::tauri_set_gb_config::set_gb_config,
+                legacy::config::tauri_get_commit_template::get_commit_template,
+                legacy::config::tauri_set_commit_template::set_commit_template,
+                legacy::config::tauri_get_workspace_merge_options::get_workspace_merge_options,
+                legacy::config::tauri_set_workspace_merge_options::set_workspace_merge_options,
                 legacy::config::tauri_store_author_globally_if_unset::store_author_globally_if_unset,
                 legacy::config::tauri_get_author_info::get_author_info,
                 legacy::remotes::tauri_list_remotes::list_remotes,
@@ -312,12 +357,19 @@ fn main() -> anyhow::Result<()> {
                 legacy::modes::tauri_abort_edit_and_return_to_workspace::abort_edit_and_return_to_workspace,
                 legacy::modes::tauri_edit_initial_index_state::edit_initial_index_state,
                 legacy::modes::tauri_edit_changes_from_initial::edit_changes_from_initial,
+                legacy::modes::tauri_resolve_with_mergetool::resolve_with_mergetool,
                 legacy::open::tauri_open_url::open_url,
                 legacy::open::tauri_show_in_finder::show_in_finder,
                 legacy::forge::tauri_pr_templates::pr_templates,
                 legacy::forge::tauri_pr_template::pr_template,
                 legacy::forge::tauri_list_reviews::list_reviews,
+                legacy::forge::tauri_checks_for_branch::checks_for_branch,
+                legacy::forge::tauri_review_comments_for_stack::review_comments_for_stack,
                 legacy::forge::tauri_publish_review::publish_review,
+                legacy::forge::tauri_import_review_suggestions::import_review_suggestions,
+                legacy::forge::tauri_create_pr_for_branch::create_pr_for_branch,
+                legacy::forge::tauri_update_review_bases::update_review_bases,
+                legacy::forge::tauri_update_stack_footers::update_stack_footers,
                 legacy::cli::tauri_install_cli::install_cli,
                 legacy::cli::tauri_cli_path::cli_path,
                 legacy::rules::tauri_create_workspace_rule::create_workspace_rule,
@@ -325,6 +377,10 @@ fn main() -> anyhow::Result<()> {
                 legacy::rules::tauri_update_workspace_rule::update_workspace_rule,
                 legacy::rules::tauri_list_workspace_rules::list_workspace_rules,
                 legacy::workspace::tauri_head_info::head_info,
+                legacy::workspace::tauri_workspace_graph::workspace_graph,
+                legacy::workspace::tauri_workspace_stats::workspace_stats,
+                legacy::workspace::tauri_fsck::fsck,
+                legacy::workspace::tauri_repair_workspace::repair_workspace,
                 legacy::workspace::tauri_stacks::stacks,
                 legacy::workspace::tauri_stack_details::stack_details,
                 legacy::workspace::tauri_branch_details::branch_details,
@@ -333,6 +389,7 @@ fn main() -> anyhow::Result<()> {
                 legacy::workspace::tauri_discard_worktree_changes::discard_worktree_changes,
                 legacy::workspace::tauri_stash_into_branch::stash_into_branch,
                 legacy::workspace::tauri_canned_branch_name::canned_branch_name,
+                legacy::workspace::tauri_suggest_branch_name::suggest_branch_name,
                 legacy::workspace::tauri_target_commits::target_commits,
                 legacy::workspace::tauri_move_changes_between_commits::move_changes_between_commits,
                 legacy::workspace::tauri_uncommit_changes::uncommit_changes,
@@ -342,6 +399,9 @@ fn main() -> anyhow::Result<()> {
                 legacy::absorb::tauri_absorption_plan::absorption_plan,
                 legacy::diff::tauri_changes_in_worktree::changes_in_worktree,
                 legacy::diff::tauri_tree_change_diffs::tree_change_diffs,
+                legacy::diff::tauri_blame::blame,
+                legacy::diff::tauri_explain_ignore::explain_ignore,
+                legacy::diff::tauri_set_skip_worktree::set_skip_worktree,
                 legacy::diff::tauri_assign_hunk::assign_hunk,
                 #[cfg(unix)]
                 legacy::workspace::tauri_show_graph_svg::show_graph_svg,
@@ -358,6 +418,8 @@ fn main() -> anyhow::Result<()> {
                 legacy::claude::tauri_claude_get_user_message::claude_get_user_message,
                 action::list_actions,
                 action::handle_changes,
+                action::propose_stacks,
+                action::accept_proposed_stacks,
                 action::list_workflows,
                 action::auto_commit,
                 action::auto_branch_changes,
@@ -377,6 +439,13 @@ fn main() -> anyhow::Result<()> {
                 settings::update_telemetry_distinct_id,
                 settings::update_claude,
                 settings::update_fetch,
+                settings::update_diff,
+                settings::update_whitespace_only_hunks,
+                settings::update_oplog,
+                settings::update_maintenance,
+                settings::update_operation_trace,
+                settings::update_ai,
+                settings::update_forge_hosts,
                 settings::update_reviews,
                 settings::update_ui,
                 bot::bot,
@@ -393,6 +462,9 @@ fn main() -> anyhow::Result<()> {
                 commit::tauri_commit_insert_blank::commit_insert_blank,
                 commit::tauri_commit_move_changes_between::commit_move_changes_between,
                 commit::tauri_commit_uncommit_changes::commit_uncommit_changes,
+                commit::tauri_commit_search::commit_search,
+                operations::tauri_list_operations::list_operations,
+                operations::tauri_cancel_operation::cancel_operation,
             ])
             .menu(move |handle| menu::build(handle, &app_settings_for_menu))
             .on_window_event(|window, event| match event {
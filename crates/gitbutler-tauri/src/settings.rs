@@ -3,8 +3,11 @@ use but_api::{json::Error, legacy::settings};
 use but_settings::{
     AppSettings, AppSettingsWithDiskSync,
     api::{
-        ClaudeUpdate, FeatureFlagsUpdate, FetchUpdate, ReviewsUpdate, TelemetryUpdate, UiUpdate,
+        AiUpdate, ClaudeUpdate, DiffUpdate, FeatureFlagsUpdate, FetchUpdate, MaintenanceUpdate,
+        OperationTraceUpdate, OplogUpdate, ReviewsUpdate, TelemetryUpdate, UiUpdate,
+        WhitespaceOnlyHunksUpdate,
     },
+    app_settings::ForgeHost,
 };
 use tauri::State;
 use tracing::instrument;
@@ -81,6 +84,87 @@ pub fn update_fetch(
         .map_err(Into::into)
 }
 
+#[tauri::command(async)]
+#[instrument(skip(app_settings_sync), err(Debug))]
+pub fn update_diff(
+    app_settings_sync: State<'_, AppSettingsWithDiskSync>,
+    update: DiffUpdate,
+) -> Result<(), Error> {
+    settings::update_diff(&app_settings_sync, settings::UpdateDiffParams { update })
+        .map_err(Into::into)
+}
+
+#[tauri::command(async)]
+#[instrument(skip(app_settings_sync), err(Debug))]
+pub fn update_whitespace_only_hunks(
+    app_settings_sync: State<'_, AppSettingsWithDiskSync>,
+    update: WhitespaceOnlyHunksUpdate,
+) -> Result<(), Error> {
+    settings::update_whitespace_only_hunks(
+        &app_settings_sync,
+        settings::UpdateWhitespaceOnlyHunksParams { update },
+    )
+    .map_err(Into::into)
+}
+
+#[tauri::command(async)]
+#[instrument(skip(app_settings_sync), err(Debug))]
+pub fn update_oplog(
+    app_settings_sync: State<'_, AppSettingsWithDiskSync>,
+    update: OplogUpdate,
+) -> Result<(), Error> {
+    settings::update_oplog(&app_settings_sync, settings::UpdateOplogParams { update })
+        .map_err(Into::into)
+}
+
+#[tauri::command(async)]
+#[instrument(skip(app_settings_sync), err(Debug))]
+pub fn update_maintenance(
+    app_settings_sync: State<'_, AppSettingsWithDiskSync>,
+    update: MaintenanceUpdate,
+) -> Result<(), Error> {
+    settings::update_maintenance(
+        &app_settings_sync,
+        settings::UpdateMaintenanceParams { update },
+    )
+    .map_err(Into::into)
+}
+
+#[tauri::command(async)]
+#[instrument(skip(app_settings_sync), err(Debug))]
+pub fn update_operation_trace(
+    app_settings_sync: State<'_, AppSettingsWithDiskSync>,
+    update: OperationTraceUpdate,
+) -> Result<(), Error> {
+    settings::update_operation_trace(
+        &app_settings_sync,
+        settings::UpdateOperationTraceParams { update },
+    )
+    .map_err(Into::into)
+}
+
+#[tauri::command(async)]
+#[instrument(skip(app_settings_sync), err(Debug))]
+pub fn update_ai(
+    app_settings_sync: State<'_, AppSettingsWithDiskSync>,
+    update: AiUpdate,
+) -> Result<(), Error> {
+    settings::update_ai(&app_settings_sync, settings::UpdateAiParams { update }).map_err(Into::into)
+}
+
+#[tauri::command(async)]
+#[instrument(skip(app_settings_sync), err(Debug))]
+pub fn update_forge_hosts(
+    app_settings_sync: State<'_, AppSettingsWithDiskSync>,
+    forge_hosts: Vec<ForgeHost>,
+) -> Result<(), Error> {
+    settings::update_forge_hosts(
+        &app_settings_sync,
+        settings::UpdateForgeHostsParams { forge_hosts },
+    )
+    .map_err(Into::into)
+}
+
 #[tauri::command(async)]
 #[instrument(skip(app_settings_sync), err(Debug))]
 pub fn update_reviews(
@@ -11,6 +11,7 @@ pub(crate) mod state {
 
     pub(crate) mod event {
         use anyhow::{Context as _, Result};
+        use but_ctx::access::OperationInfo;
         use but_db::poll::ItemKind;
         use but_settings::AppSettings;
         use gitbutler_project::ProjectId;
@@ -60,6 +61,37 @@ pub(crate) mod state {
                         payload: serde_json::json!(&changes),
                         project_id,
                     },
+                    Change::ExternalCommits {
+                        project_id,
+                        commit_ids,
+                    } => ChangeForFrontend {
+                        name: format!("project://{project_id}/git/external_commits"),
+                        payload: serde_json::json!({
+                            "commitIds": commit_ids.iter().map(ToString::to_string).collect::<Vec<_>>(),
+                        }),
+                        project_id,
+                    },
+                    Change::BaseBranchMoved {
+                        project_id,
+                        new_commits,
+                    } => ChangeForFrontend {
+                        name: format!("project://{project_id}/git/base_branch_moved"),
+                        payload: serde_json::json!({ "newCommits": new_commits }),
+                        project_id,
+                    },
+                    Change::ProjectSettings {
+                        project_id,
+                        settings,
+                    } => ChangeForFrontend {
+                        name: format!("project://{project_id}/settings"),
+                        payload: serde_json::json!(settings),
+                        project_id,
+                    },
+                    Change::WorkspaceEvent { project_id, event } => ChangeForFrontend {
+                        name: format!("project://{project_id}/workspace_event"),
+                        payload: serde_json::json!(event),
+                        project_id,
+                    },
                 }
             }
         }
@@ -75,6 +107,16 @@ pub(crate) mod state {
             }
         }
 
+        impl From<(ProjectId, &OperationInfo)> for ChangeForFrontend {
+            fn from((project_id, operation): (ProjectId, &OperationInfo)) -> Self {
+                ChangeForFrontend {
+                    name: format!("project://{project_id}/operation_progress"),
+                    payload: serde_json::json!(operation),
+                    project_id,
+                }
+            }
+        }
+
         impl From<(ProjectId, ItemKind)> for ChangeForFrontend {
             fn from(project_item: (ProjectId, ItemKind)) -> Self {
                 let (project_id, item) = project_item;
@@ -201,6 +243,17 @@ pub(crate) mod state {
                 move |item| ChangeForFrontend::from((project_id, item)).send(&app_handle)
             })?;
 
+            ctx.set_operation_progress_listener({
+                let app_handle = self.app_handle.clone();
+                move |operation| {
+                    if let Err(err) =
+                        ChangeForFrontend::from((project_id, operation)).send(&app_handle)
+                    {
+                        tracing::warn!(?err, "failed to send operation progress");
+                    }
+                }
+            });
+
             let has_exclusive_access = exclusive_access.is_some();
             state_by_label.insert(
                 window.to_owned(),
@@ -19,4 +19,25 @@ pub enum Change {
         project_id: ProjectId,
         changes: but_hunk_assignment::WorktreeChanges,
     },
+    /// Commits were made on top of the workspace commit with Git directly, like with `git commit`.
+    ExternalCommits {
+        project_id: ProjectId,
+        /// The commits, the oldest one first.
+        commit_ids: Vec<gix::ObjectId>,
+    },
+    /// The target branch gained `new_commits` commits when the remote was fetched in the background.
+    BaseBranchMoved {
+        project_id: ProjectId,
+        new_commits: usize,
+    },
+    /// The settings overrides of the project changed, making `settings` the settings in effect for it.
+    ProjectSettings {
+        project_id: ProjectId,
+        settings: but_settings::AppSettings,
+    },
+    /// A mutating operation changed the workspace as described by `event`.
+    WorkspaceEvent {
+        project_id: ProjectId,
+        event: but_core::sync::events::WorkspaceEvent,
+    },
 }
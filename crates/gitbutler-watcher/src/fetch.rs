@@ -0,0 +1,220 @@
+//! A scheduler that periodically fetches the remotes of a project in the background.
+use std::time::Duration;
+
+use anyhow::{Context as _, Result};
+use but_ctx::Context;
+use but_error::{AnyhowContextExt as _, Code};
+use but_settings::{AppSettings, AppSettingsWithDiskSync};
+use gitbutler_project::{FetchResult, ProjectId};
+use gitbutler_repo_actions::RepoActionsExt as _;
+use gitbutler_stack::VirtualBranchesHandle;
+use gix::bstr::ByteSlice as _;
+use tokio_util::sync::CancellationToken;
+
+use crate::Handler;
+
+/// How long to wait before looking at the settings again while auto-fetching is disabled.
+const DISABLED_POLL_INTERVAL: Duration = Duration::from_secs(60);
+/// The shortest time between fetches, so an interval of `0` doesn't fetch continuously.
+const MIN_INTERVAL: Duration = Duration::from_secs(60);
+/// The longest time to wait between fetches after failures, unless the configured interval is longer.
+const MAX_BACKOFF: Duration = Duration::from_secs(60 * 60);
+
+/// Keeps track of consecutive failures to back off exponentially when the remote can't be reached.
+#[derive(Debug, Default, Clone, Copy)]
+struct Backoff {
+    failures: u32,
+}
+
+impl Backoff {
+    /// Return how long to wait before the next fetch when fetching normally happens every `interval`.
+    fn delay(&self, interval: Duration) -> Duration {
+        let backed_off = interval.saturating_mul(2u32.saturating_pow(self.failures));
+        backed_off.min(MAX_BACKOFF.max(interval))
+    }
+}
+
+/// Return the time between fetches for `auto_fetch_interval_minutes` of the app settings,
+/// which is at least [`MIN_INTERVAL`], or `None` if auto-fetching is disabled.
+fn fetch_interval(auto_fetch_interval_minutes: isize) -> Option<Duration> {
+    let minutes = u64::try_from(auto_fetch_interval_minutes).ok()?;
+    Some(Duration::from_secs(minutes.saturating_mul(60)).max(MIN_INTERVAL))
+}
+
+/// Fetch all remotes of the project with `project_id` right away, and then every
+/// `fetch.autoFetchIntervalMinutes` of the app settings until `cancellation_token` is cancelled.
+///
+/// Authentication and network failures double the time until the next fetch, up to [`MAX_BACKOFF`].
+/// Whenever the target branch gains commits, [`Change::BaseBranchMoved`](crate::Change::BaseBranchMoved)
+/// is emitted through `handler`, which also does that for fetches made with Git directly.
+pub(crate) fn spawn(
+    handler: Handler,
+    project_id: ProjectId,
+    app_settings: AppSettingsWithDiskSync,
+    cancellation_token: CancellationToken,
+) {
+    tokio::spawn(async move {
+        let mut backoff = Backoff::default();
+        loop {
+            let settings = app_settings.get().map(|settings| settings.clone());
+            let delay = match settings {
+                Err(err) => {
+                    tracing::warn!(%project_id, ?err, "could not read settings for auto-fetch");
+                    DISABLED_POLL_INTERVAL
+                }
+                Ok(settings) => match fetch_interval(settings.fetch.auto_fetch_interval_minutes) {
+                    None => DISABLED_POLL_INTERVAL,
+                    Some(interval) => {
+                        fetch_and_report(&handler, project_id, settings, &mut backoff).await;
+                        backoff.delay(interval)
+                    }
+                },
+            };
+
+            tokio::select! {
+                () = tokio::time::sleep(delay) => {}
+                () = cancellation_token.cancelled() => {
+                    tracing::debug!(%project_id, "stopped auto-fetch");
+                    break;
+                }
+            }
+        }
+    });
+}
+
+/// Fetch the remotes of the project with `project_id`, emit an event if the target branch moved,
+/// and update `backoff` according to the outcome.
+async fn fetch_and_report(
+    handler: &Handler,
+    project_id: ProjectId,
+    settings: AppSettings,
+    backoff: &mut Backoff,
+) {
+    let handler = handler.clone();
+    match tokio::task::spawn_blocking(move || fetch_remotes(&handler, project_id, settings)).await {
+        Ok(Ok(())) => {
+            *backoff = Backoff::default();
+        }
+        Ok(Err(err)) => {
+            if is_auth_or_network_error(&err) {
+                backoff.failures = backoff.failures.saturating_add(1);
+            }
+            tracing::warn!(%project_id, ?err, failures = backoff.failures, "auto-fetch failed");
+        }
+        Err(err) => {
+            tracing::error!(%project_id, ?err, "auto-fetch task panicked");
+        }
+    }
+}
+
+/// Fetch all remotes of the project with `project_id`, like a manual fetch does, and let `handler` emit
+/// an event if the target branch moved.
+///
+/// A remote that can't be fetched doesn't prevent the others from being fetched, but its error is returned.
+fn fetch_remotes(handler: &Handler, project_id: ProjectId, settings: AppSettings) -> Result<()> {
+    let project = gitbutler_project::get(project_id).context("failed to get project")?;
+    let ctx = Context::new_from_legacy_project_and_settings(&project, settings.clone());
+    if VirtualBranchesHandle::new(ctx.project_data_dir())
+        .get_default_target()
+        .is_err()
+    {
+        // Projects without target have nothing to fetch yet.
+        return Ok(());
+    }
+
+    let remotes: Vec<String> = ctx
+        .repo
+        .get()?
+        .remote_names()
+        .iter()
+        .map(|name| name.to_str_lossy().into_owned())
+        .collect();
+    let mut errors = Vec::new();
+    for remote in &remotes {
+        if let Err(err) = ctx.fetch(remote, Some("auto".into())) {
+            errors.push(err);
+        }
+    }
+    let timestamp = std::time::SystemTime::now();
+    gitbutler_project::update(gitbutler_project::UpdateRequest {
+        project_data_last_fetched: Some(if errors.is_empty() {
+            FetchResult::Fetched { timestamp }
+        } else {
+            FetchResult::Error {
+                timestamp,
+                error: errors
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            }
+        }),
+        ..gitbutler_project::UpdateRequest::default_with_id(project_id)
+    })
+    .context("failed to update project with last fetched timestamp")?;
+
+    // Reopen to see the references as updated by the fetch.
+    let ctx = Context::new_from_legacy_project_and_settings(&project, settings);
+    handler.reconcile_target(&ctx)?;
+    match errors.into_iter().next() {
+        Some(err) => Err(err),
+        None => Ok(()),
+    }
+}
+
+/// Return `true` if `err` was caused by failing to authenticate with or to reach the remote.
+fn is_auth_or_network_error(err: &anyhow::Error) -> bool {
+    if let Some(ctx) = err.custom_context()
+        && matches!(ctx.code, Code::ProjectGitAuth | Code::NetworkError)
+    {
+        return true;
+    }
+    let message = format!("{err:#}").to_lowercase();
+    [
+        "authentication",
+        "authorization",
+        "permission denied",
+        "could not resolve host",
+        "could not read from remote",
+        "connection",
+        "timed out",
+        "network",
+    ]
+    .iter()
+    .any(|needle| message.contains(needle))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MINUTE: Duration = Duration::from_secs(60);
+
+    #[test]
+    fn intervals_are_at_least_a_minute() {
+        assert_eq!(
+            fetch_interval(-1),
+            None,
+            "negative intervals disable fetching"
+        );
+        assert_eq!(fetch_interval(0), Some(MIN_INTERVAL));
+        assert_eq!(fetch_interval(15), Some(15 * MINUTE));
+    }
+
+    #[test]
+    fn backoff_doubles_the_interval_up_to_a_maximum() {
+        let interval = 15 * MINUTE;
+        let backoff = |failures| Backoff { failures }.delay(interval);
+        assert_eq!(backoff(0), interval);
+        assert_eq!(backoff(1), 30 * MINUTE);
+        assert_eq!(backoff(2), MAX_BACKOFF);
+        assert_eq!(backoff(u32::MAX), MAX_BACKOFF, "no overflow");
+
+        let long_interval = 2 * MAX_BACKOFF;
+        assert_eq!(
+            Backoff { failures: 3 }.delay(long_interval),
+            long_interval,
+            "intervals longer than the maximum backoff are kept"
+        );
+    }
+}
@@ -1,4 +1,8 @@
-use std::{path::PathBuf, sync::Arc};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
 
 use anyhow::{Context as _, Result};
 use but_core::TreeChange;
@@ -7,9 +11,11 @@ use but_hunk_assignment::HunkAssignment;
 use but_hunk_dependency::ui::hunk_dependencies_for_workspace_changes_by_worktree_dir;
 use but_settings::{AppSettings, AppSettingsWithDiskSync};
 use gitbutler_filemonitor::{
-    FETCH_HEAD, HEAD, HEAD_ACTIVITY, INDEX, InternalEvent, LOCAL_REFS_DIR,
+    FETCH_HEAD, HEAD, HEAD_ACTIVITY, INDEX, InternalEvent, LOCAL_REFS_DIR, PACKED_REFS,
+    PROJECT_SETTINGS, REMOTE_REFS_DIR,
 };
-use gitbutler_operating_modes::operating_mode;
+use gitbutler_operating_modes::{OperatingMode, operating_mode};
+use gitbutler_oplog::OplogExt as _;
 use gitbutler_project::ProjectId;
 use tracing::instrument;
 
@@ -27,6 +33,10 @@ pub struct Handler {
     // need extra protection.
     /// A function to send events - decoupled from app-handle for testing purposes.
     send_event: Arc<dyn Fn(Change) -> Result<()> + Send + Sync + 'static>,
+    /// The worktree changes that were last emitted for each project, to be updated with the paths that changed since.
+    worktree_changes: Arc<Mutex<HashMap<ProjectId, but_core::WorktreeChanges>>>,
+    /// The commit the target branch of each project was last seen at.
+    pub(crate) target_tips: Arc<Mutex<HashMap<ProjectId, gix::ObjectId>>>,
 }
 
 impl Handler {
@@ -34,6 +44,8 @@ impl Handler {
     pub fn new(send_event: impl Fn(Change) -> Result<()> + Send + Sync + 'static) -> Self {
         Handler {
             send_event: Arc::new(send_event),
+            worktree_changes: Default::default(),
+            target_tips: Default::default(),
         }
     }
 
@@ -45,10 +57,13 @@ impl Handler {
         app_settings: AppSettingsWithDiskSync,
     ) -> Result<()> {
         match event {
-            InternalEvent::ProjectFilesChange(project_id, paths) => {
+            InternalEvent::ProjectFilesChange(project_id, paths, renames) => {
+                if !renames.is_empty() {
+                    tracing::debug!(%project_id, ?renames, "files were renamed");
+                }
                 let ctx =
                     &mut self.open_command_context(project_id, app_settings.get()?.clone())?;
-                let guard = ctx.exclusive_worktree_access();
+                let guard = ctx.shared_worktree_access();
                 let repo = ctx.repo.get()?.clone();
                 let (_, workspace) =
                     ctx.workspace_and_read_only_meta_from_head(guard.read_permission())?;
@@ -56,9 +71,22 @@ impl Handler {
             }
 
             InternalEvent::GitFilesChange(project_id, paths) => {
+                // The index or `HEAD` may have changed, so the worktree changes must be obtained from scratch.
+                self.forget_worktree_changes(project_id);
                 let ctx =
                     &mut self.open_command_context(project_id, app_settings.get()?.clone())?;
-                let guard = ctx.exclusive_worktree_access();
+                let mut guard = ctx.upgradable_worktree_access();
+                if paths.iter().any(|path| is_head_change(path)) {
+                    // Keep the state from before the change before anything reconciles with it.
+                    if let Err(err) = ctx.snapshot_external_change(guard.upgrade()) {
+                        tracing::warn!("Could not snapshot external change: {err:#}");
+                    }
+                }
+                if is_ref_change(&paths, LOCAL_REFS_DIR)
+                    && let Err(err) = self.reconcile_stack_branches(ctx, &mut guard)
+                {
+                    tracing::warn!("Could not reconcile stack branches: {err:#}");
+                }
                 let repo = ctx.repo.get()?.clone();
                 let (_, workspace) =
                     ctx.workspace_and_read_only_meta_from_head(guard.read_permission())?;
@@ -68,7 +96,7 @@ impl Handler {
         }
     }
 
-    fn emit_app_event(&self, event: Change) -> Result<()> {
+    pub(crate) fn emit_app_event(&self, event: Change) -> Result<()> {
         (self.send_event)(event).context("failed to send event")
     }
 
@@ -92,7 +120,7 @@ impl Handler {
         repo: &gix::Repository,
         workspace: &but_graph::projection::Workspace,
     ) -> Result<()> {
-        let _ = self.emit_worktree_changes(ctx, repo, workspace);
+        let _ = self.emit_worktree_changes(ctx, repo, workspace, Some(&paths));
 
         Ok(())
     }
@@ -102,8 +130,9 @@ impl Handler {
         ctx: &mut Context,
         repo: &gix::Repository,
         workspace: &but_graph::projection::Workspace,
+        dirty_paths: Option<&[PathBuf]>,
     ) -> Result<()> {
-        let wt_changes = but_core::diff::worktree_changes(repo)?;
+        let wt_changes = self.worktree_changes(ctx.legacy_project.id, repo, dirty_paths)?;
 
         let dependencies = hunk_dependencies_for_workspace_changes_by_worktree_dir(
             repo,
@@ -163,6 +192,69 @@ impl Handler {
         Ok(())
     }
 
+    /// Obtain the worktree changes of the project with `project_id`, only re-diffing the worktree-relative `dirty_paths`
+    /// if the changes are known from a previous call.
+    /// Without `dirty_paths`, or if one of them can affect all paths, like `.gitignore`, the whole worktree is diffed.
+    fn worktree_changes(
+        &self,
+        project_id: ProjectId,
+        repo: &gix::Repository,
+        dirty_paths: Option<&[PathBuf]>,
+    ) -> Result<but_core::WorktreeChanges> {
+        if dirty_paths.is_some_and(|paths| paths.iter().any(|path| affects_all_paths(path))) {
+            // Attributes affect how objects are diffed, so patches computed previously may not apply anymore.
+            but_core::UnifiedPatch::clear_cache();
+        }
+        let previous = self
+            .worktree_changes
+            .lock()
+            .ok()
+            .and_then(|mut cache| cache.remove(&project_id));
+        let changes = match (previous, dirty_paths) {
+            (Some(previous), Some(dirty_paths))
+                if !dirty_paths.iter().any(|path| affects_all_paths(path)) =>
+            {
+                let dirty_paths: Vec<_> = dirty_paths
+                    .iter()
+                    .map(|path| {
+                        gix::path::to_unix_separators_on_windows(gix::path::into_bstr(path))
+                            .into_owned()
+                    })
+                    .collect();
+                but_core::diff::worktree_changes_incremental(repo, previous, &dirty_paths)?
+            }
+            _ => but_core::diff::worktree_changes(repo)?,
+        };
+        if let Ok(mut cache) = self.worktree_changes.lock() {
+            cache.insert(project_id, changes.clone());
+        }
+        Ok(changes)
+    }
+
+    fn forget_worktree_changes(&self, project_id: ProjectId) {
+        if let Ok(mut cache) = self.worktree_changes.lock() {
+            cache.remove(&project_id);
+        }
+    }
+
+    /// Tell the frontend about commits that were made on top of the workspace commit with Git directly,
+    /// so the user can decide which stack they belong to.
+    fn emit_external_commits(&self, ctx: &Context) {
+        if operating_mode(ctx) != OperatingMode::OpenWorkspace {
+            return;
+        }
+        match gitbutler_branch_actions::external_commits(ctx) {
+            Ok(commit_ids) if !commit_ids.is_empty() => {
+                let _ = self.emit_app_event(Change::ExternalCommits {
+                    project_id: ctx.legacy_project.id,
+                    commit_ids,
+                });
+            }
+            Ok(_) => {}
+            Err(err) => tracing::warn!("Could not look for external commits: {err:#}"),
+        }
+    }
+
     pub fn git_files_change(
         &self,
         paths: Vec<PathBuf>,
@@ -172,6 +264,12 @@ impl Handler {
     ) -> Result<()> {
         let (head_ref_name, head_sha) = head_info(ctx)?;
 
+        if is_ref_change(&paths, REMOTE_REFS_DIR)
+            && let Err(err) = self.reconcile_target(ctx)
+        {
+            tracing::warn!("Could not reconcile the target branch: {err:#}");
+        }
+
         for path in paths {
             let Some(file_name) = path.to_str() else {
                 continue;
@@ -186,6 +284,7 @@ impl Handler {
                         project_id: ctx.legacy_project.id,
                         head_sha: head_sha.clone(),
                     })?;
+                    self.emit_external_commits(ctx);
                 }
                 HEAD_ACTIVITY => {
                     self.emit_app_event(Change::GitActivity {
@@ -194,7 +293,16 @@ impl Handler {
                     })?;
                 }
                 INDEX => {
-                    let _ = self.emit_worktree_changes(ctx, repo, workspace);
+                    let _ = self.emit_worktree_changes(ctx, repo, workspace, None);
+                }
+                // The context was opened with the changed overrides already, and diff options may have changed.
+                PROJECT_SETTINGS => {
+                    self.emit_app_event(Change::ProjectSettings {
+                        project_id: ctx.legacy_project.id,
+                        settings: ctx.settings.clone(),
+                    })?;
+                    self.forget_worktree_changes(ctx.legacy_project.id);
+                    let _ = self.emit_worktree_changes(ctx, repo, workspace, None);
                 }
                 HEAD => {
                     let git2_repo = ctx.git2_repo.get()?;
@@ -214,6 +322,28 @@ impl Handler {
     }
 }
 
+/// Return `true` if the worktree file at `path` can change how any other path is seen by Git, like `.gitignore`.
+fn affects_all_paths(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.starts_with(".git"))
+}
+
+/// Return `true` if any of `paths` is a reference in the directory `prefix`, like `refs/heads`.
+/// Branches may be packed, so `packed-refs` can stand for changes to local and remote branches alike.
+fn is_ref_change(paths: &[PathBuf], prefix: &str) -> bool {
+    paths.iter().any(|path| {
+        path.to_str()
+            .is_some_and(|path| path == PACKED_REFS || path.starts_with(prefix))
+    })
+}
+
+/// Return `true` if the git file at `path` could have changed what `HEAD` points to.
+fn is_head_change(path: &std::path::Path) -> bool {
+    path.to_str()
+        .is_some_and(|path| path == HEAD || path.starts_with(LOCAL_REFS_DIR))
+}
+
 fn head_info(ctx: &mut Context) -> Result<(String, String)> {
     let repo = &*ctx.git2_repo.get()?;
     let head_ref = repo.head().context("failed to get head")?;
@@ -15,6 +15,9 @@ use tokio::{
 use tokio_util::sync::CancellationToken;
 
 mod events;
+mod fetch;
+mod reconcile;
+mod workspace_events;
 
 pub use events::Change;
 use gitbutler_filemonitor::InternalEvent;
@@ -57,8 +60,8 @@ impl WatcherHandle {
 ///
 /// ### How it works
 ///
-/// The watcher is a processing loop that relies on filesystem events. These are aggregated so
-/// every ~100ms, the changed paths sorted by 'worktree' and 'git-repository' will be processed,
+/// The watcher is a processing loop that relies on filesystem events. These are aggregated until
+/// the filesystem was quiet for `featureFlags.watchDebounceMs`, and then the changed paths sorted by 'worktree' and 'git-repository' will be processed,
 /// each of these events is handled in its own thread, while being able to spawn additional processing
 /// tasks as well.
 ///
@@ -66,6 +69,12 @@ impl WatcherHandle {
 /// up if they take longer to process than the 100ms window between them, causing high-CPU and possibly
 /// high-memory. However, the likelihood for this is much lower than it was before the architecture
 /// was changed to what it is now, which should be much less wasteful.
+///
+/// Alongside, the target remote of the project is fetched periodically, see `fetch.autoFetchIntervalMinutes`.
+/// Whenever the target branch moves, be it by fetching here or with Git directly, [`Change::BaseBranchMoved`]
+/// is emitted, and stack branches deleted with Git are announced on the event bus as
+/// [`WorkspaceEvent::StackDetached`](but_core::sync::events::WorkspaceEvent::StackDetached).
+/// The [events](but_core::sync::events) published by mutating operations are forwarded as [`Change::WorkspaceEvent`].
 pub fn watch_in_background(
     handler: handler::Handler,
     worktree_path: impl AsRef<Path>,
@@ -76,11 +85,15 @@ pub fn watch_in_background(
     let (events_out, mut events_in) = unbounded_channel();
     let (flush_tx, mut flush_rx) = unbounded_channel();
 
+    let debounce = std::time::Duration::from_millis(u64::from(
+        app_settings.get()?.feature_flags.watch_debounce_ms,
+    ));
     let monitor = gitbutler_filemonitor::spawn(
         project_id,
         worktree_path.as_ref(),
         events_out.clone(),
         watch_mode,
+        debounce,
     )?;
 
     let cancellation_token = CancellationToken::new();
@@ -89,6 +102,13 @@ pub fn watch_in_background(
         signal_flush: flush_tx,
         cancellation_token: cancellation_token.clone(),
     };
+    fetch::spawn(
+        handler.clone(),
+        project_id,
+        app_settings.clone(),
+        cancellation_token.clone(),
+    );
+    workspace_events::spawn(handler.clone(), project_id, cancellation_token.clone())?;
     let handle_event =
         move |event: InternalEvent, app_settings: AppSettingsWithDiskSync| -> Result<()> {
             let handler = handler.clone();
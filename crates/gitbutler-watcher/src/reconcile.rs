@@ -0,0 +1,280 @@
+//! Reconcile the workspace with reference changes made outside of GitButler, like `git fetch`, `git pull`
+//! or `git branch -D`.
+use anyhow::Result;
+use but_core::sync::{
+    WorkspaceUpgradableGuard,
+    events::{self, WorkspaceEvent},
+};
+use but_ctx::Context;
+use gitbutler_operating_modes::{OperatingMode, operating_mode};
+use gitbutler_stack::VirtualBranchesHandle;
+
+use crate::{Change, Handler};
+
+impl Handler {
+    /// Emit [`Change::BaseBranchMoved`] with the number of commits the target branch gained since it was
+    /// last seen, or since the commit the workspace is based on if it wasn't seen yet.
+    ///
+    /// Nothing is emitted if the target branch didn't move, so calling this for every change to remote
+    /// references, no matter if they were fetched by GitButler or Git, emits each move only once.
+    pub(crate) fn reconcile_target(&self, ctx: &Context) -> Result<()> {
+        let project_id = ctx.legacy_project.id;
+        let Ok(target) = VirtualBranchesHandle::new(ctx.project_data_dir()).get_default_target()
+        else {
+            // Projects without target have nothing to compare with.
+            return Ok(());
+        };
+        let repo = ctx.repo.get()?;
+        let Some(tip) = repo
+            .try_find_reference(target.branch.to_string().as_str())?
+            .map(|mut r| r.peel_to_id())
+            .transpose()?
+            .map(|id| id.detach())
+        else {
+            return Ok(());
+        };
+        let previous_tip = {
+            let Ok(mut target_tips) = self.target_tips.lock() else {
+                return Ok(());
+            };
+            target_tips.insert(project_id, tip)
+        }
+        .unwrap_or_else(|| gix::ObjectId::from_bytes_or_panic(target.sha.as_bytes()));
+        if previous_tip == tip {
+            return Ok(());
+        }
+
+        let new_commits = repo
+            .rev_walk([tip])
+            .with_hidden([previous_tip])
+            .all()?
+            .filter_map(Result::ok)
+            .count();
+        if new_commits > 0 {
+            self.emit_app_event(Change::BaseBranchMoved {
+                project_id,
+                new_commits,
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Mark each branch of a stack in the workspace whose reference was deleted as
+    /// [detached](gitbutler_stack::StackBranch::detached), and publish [`WorkspaceEvent::StackDetached`] for it.
+    /// Branches whose references exist again aren't detached anymore.
+    ///
+    /// As the state is persisted with the stacks, each deletion is published only once.
+    /// `guard` is upgraded to exclusive access only if the state of a branch changed.
+    pub(crate) fn reconcile_stack_branches(
+        &self,
+        ctx: &Context,
+        guard: &mut WorkspaceUpgradableGuard,
+    ) -> Result<()> {
+        if operating_mode(ctx) != OperatingMode::OpenWorkspace {
+            return Ok(());
+        }
+        let project_data_dir = ctx.project_data_dir();
+        let vb_state = VirtualBranchesHandle::new(&project_data_dir);
+        let repo = ctx.repo.get()?;
+        for mut stack in vb_state.list_stacks_in_workspace()? {
+            let mut newly_detached = Vec::new();
+            let mut changed = false;
+            for branch in &mut stack.heads {
+                let detached = repo
+                    .try_find_reference(format!("refs/heads/{}", branch.name).as_str())?
+                    .is_none();
+                if detached == branch.detached {
+                    continue;
+                }
+                branch.detached = detached;
+                changed = true;
+                if detached {
+                    newly_detached.push(branch.name.clone());
+                }
+            }
+            if !changed {
+                continue;
+            }
+            let stack_id = stack.id;
+            let _exclusive_access = guard.upgrade();
+            vb_state.set_stack(stack)?;
+            for branch_name in newly_detached {
+                tracing::info!(%stack_id, branch_name, "stack branch was deleted outside of GitButler");
+                events::publish(
+                    &project_data_dir,
+                    WorkspaceEvent::StackDetached {
+                        stack_id,
+                        branch_name,
+                    },
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        path::Path,
+        process::Command,
+        sync::{Arc, Mutex},
+    };
+
+    use but_core::sync::events::{self, WorkspaceEvent};
+    use but_ctx::Context;
+    use but_settings::AppSettings;
+    use gitbutler_branch::BranchCreateRequest;
+    use gitbutler_filemonitor::PACKED_REFS;
+    use gitbutler_stack::VirtualBranchesHandle;
+    use gitbutler_testsupport::{TestProject, paths};
+    use tempfile::TempDir;
+
+    use crate::{Change, Handler};
+
+    #[test]
+    fn branches_deleted_with_git_detach_their_stack_until_they_are_recreated() -> anyhow::Result<()>
+    {
+        let (ctx, _project, _data_dir) = &workspace()?;
+        gitbutler_branch_actions::create_virtual_branch(
+            ctx,
+            &BranchCreateRequest::default(),
+            ctx.exclusive_worktree_access().write_permission(),
+        )?;
+        let vb_state = VirtualBranchesHandle::new(ctx.project_data_dir());
+        let stack = vb_state.list_stacks_in_workspace()?.remove(0);
+        let refname = format!("refs/heads/{}", stack.heads[0].name);
+        let (handler, _changes) = recording_handler();
+        let published = events::subscribe(ctx.project_data_dir());
+        let reconcile = || {
+            let mut guard = ctx.upgradable_worktree_access();
+            handler.reconcile_stack_branches(ctx, &mut guard)
+        };
+
+        let workdir = ctx.legacy_project.worktree_dir()?;
+        let tip = git(workdir, &["rev-parse", &refname]);
+        git(workdir, &["update-ref", "-d", &refname]);
+        reconcile()?;
+        assert_eq!(
+            published.try_iter().collect::<Vec<_>>(),
+            [WorkspaceEvent::StackDetached {
+                stack_id: stack.id,
+                branch_name: stack.heads[0].name.clone(),
+            }]
+        );
+        assert!(
+            vb_state.get_stack(stack.id)?.heads[0].detached,
+            "the detached state is persisted with the stack"
+        );
+
+        reconcile()?;
+        assert_eq!(
+            published.try_iter().count(),
+            0,
+            "the deletion was published already"
+        );
+
+        git(workdir, &["update-ref", &refname, &tip]);
+        reconcile()?;
+        assert!(!vb_state.get_stack(stack.id)?.heads[0].detached);
+        assert_eq!(published.try_iter().count(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn target_moved_and_packed_with_git_is_reported_once() -> anyhow::Result<()> {
+        let (mut ctx, _project, _data_dir) = workspace()?;
+        let (handler, changes) = recording_handler();
+
+        // Move the remote tracking branch by a commit like `git fetch` would, and pack it.
+        let workdir = ctx.legacy_project.worktree_dir()?.to_owned();
+        let remote_commit = git(
+            &workdir,
+            &[
+                "commit-tree",
+                "origin/master^{tree}",
+                "-p",
+                "origin/master",
+                "-m",
+                "remote",
+            ],
+        );
+        git(
+            &workdir,
+            &["update-ref", "refs/remotes/origin/master", &remote_commit],
+        );
+        git(&workdir, &["pack-refs", "--all"]);
+
+        let notify = |ctx: &mut Context| -> anyhow::Result<()> {
+            let repo = ctx.repo.get()?.clone();
+            let workspace = {
+                let guard = ctx.shared_worktree_access();
+                ctx.workspace_and_read_only_meta_from_head(guard.read_permission())?
+                    .1
+            };
+            handler.git_files_change(vec![PACKED_REFS.into()], ctx, &repo, &workspace)
+        };
+        notify(&mut ctx)?;
+        notify(&mut ctx)?;
+
+        let moves: Vec<_> = changes
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(|change| match change {
+                Change::BaseBranchMoved { new_commits, .. } => Some(*new_commits),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(moves, [1], "the move is reported only once");
+        Ok(())
+    }
+
+    /// Return the context of a project whose workspace is based on `origin/master`,
+    /// along with the repositories and the application data directory it uses.
+    fn workspace() -> anyhow::Result<(Context, TestProject, TempDir)> {
+        let data_dir = paths::data_dir();
+        let test_project = TestProject::default();
+        let project = gitbutler_project::add_at_app_data_dir(data_dir.path(), test_project.path())?
+            .unwrap_project();
+        let ctx = Context::new_from_legacy_project_and_settings(&project, AppSettings::default());
+        gitbutler_branch_actions::set_base_branch(
+            &ctx,
+            &"refs/remotes/origin/master".parse()?,
+            ctx.exclusive_worktree_access().write_permission(),
+        )?;
+        Ok((ctx, test_project, data_dir))
+    }
+
+    /// Return a handler that records the changes it emits.
+    fn recording_handler() -> (Handler, Arc<Mutex<Vec<Change>>>) {
+        let changes = Arc::new(Mutex::new(Vec::new()));
+        let handler = Handler::new({
+            let changes = changes.clone();
+            move |change| {
+                changes.lock().unwrap().push(change);
+                Ok(())
+            }
+        });
+        (handler, changes)
+    }
+
+    /// Run `git` with `args` in `cwd`, assert it succeeds and return its trimmed stdout.
+    fn git(cwd: &Path, args: &[&str]) -> String {
+        let out = Command::new("git")
+            .args(args)
+            .current_dir(cwd)
+            .env("GIT_AUTHOR_NAME", "author")
+            .env("GIT_AUTHOR_EMAIL", "author@example.com")
+            .env("GIT_COMMITTER_NAME", "committer")
+            .env("GIT_COMMITTER_EMAIL", "committer@example.com")
+            .output()
+            .unwrap();
+        assert!(
+            out.status.success(),
+            "git {args:?} failed: {}",
+            String::from_utf8_lossy(&out.stderr)
+        );
+        String::from_utf8(out.stdout).unwrap().trim().to_owned()
+    }
+}
@@ -0,0 +1,41 @@
+//! Forward the events that mutating operations publish about a workspace, so observers don't have to poll for them.
+use std::{sync::mpsc::RecvTimeoutError, time::Duration};
+
+use anyhow::{Context as _, Result};
+use gitbutler_project::ProjectId;
+use tokio_util::sync::CancellationToken;
+
+use crate::{Change, Handler};
+
+/// How long to wait for an event before checking if forwarding should stop.
+const CANCELLATION_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Subscribe to the workspace events of the project with `project_id` and emit each of them as
+/// [`Change::WorkspaceEvent`] through `handler`, until `cancellation_token` is cancelled.
+pub(crate) fn spawn(
+    handler: Handler,
+    project_id: ProjectId,
+    cancellation_token: CancellationToken,
+) -> Result<()> {
+    let project = gitbutler_project::get(project_id).context("failed to get project")?;
+    // Subscribe right away so no event is lost once the watcher is running.
+    let events = but_core::sync::events::subscribe(project.gb_dir());
+    std::thread::Builder::new()
+        .name(format!("workspace-events-{project_id}"))
+        .spawn(move || {
+            while !cancellation_token.is_cancelled() {
+                match events.recv_timeout(CANCELLATION_POLL_INTERVAL) {
+                    Ok(event) => {
+                        let change = Change::WorkspaceEvent { project_id, event };
+                        if let Err(err) = handler.emit_app_event(change) {
+                            tracing::warn!(%project_id, ?err, "could not emit workspace event");
+                        }
+                    }
+                    Err(RecvTimeoutError::Timeout) => {}
+                    Err(RecvTimeoutError::Disconnected) => break,
+                }
+            }
+            tracing::debug!(%project_id, "stopped forwarding workspace events");
+        })?;
+    Ok(())
+}
@@ -1,4 +1,7 @@
+use std::{collections::BTreeSet, path::PathBuf};
+
 use anyhow::Result;
+use but_core::RepositoryExt as _;
 use but_ctx::{
     Context,
     access::{WorktreeReadPermission, WorktreeWritePermission},
@@ -88,6 +91,10 @@ pub fn update_uncommitted_changes(
 }
 
 /// `old_uncommitted_changes` is `None` if the `safe_checkout` feature is toggled on in `ctx`
+///
+/// Unless `always_checkout` is set, the uncommitted changes are merged in memory first, so the worktree
+/// is only touched where files actually change. If they conflict, or if Git would convert the changed files when
+/// writing them to the worktree, they are checked out from a merged index instead.
 pub fn update_uncommitted_changes_with_tree(
     ctx: &Context,
     old: WorkspaceState,
@@ -98,7 +105,31 @@ pub fn update_uncommitted_changes_with_tree(
 ) -> Result<()> {
     let repo = &*ctx.git2_repo.get()?;
     if let Some(worktree_id) = old_uncommitted_changes {
-        let mut new_uncommitted_changes =
+        if !always_checkout.unwrap_or(false) {
+            let gix_repo = ctx.clone_repo_for_merging()?;
+            if let Some(new_worktree_id) =
+                move_tree_between_workspaces_fast(&gix_repo, worktree_id.to_gix(), &old, &new)?
+            {
+                let changed_paths = repo.changed_paths(
+                    &repo.find_tree(worktree_id)?,
+                    &repo.find_tree(new_worktree_id.to_git2())?,
+                )?;
+                if changed_paths.is_empty() {
+                    return Ok(());
+                }
+                if !has_worktree_conversions(&gix_repo, &changed_paths)? {
+                    but_core::worktree::safe_checkout(
+                        worktree_id.to_gix(),
+                        new_worktree_id,
+                        &gix_repo,
+                        but_core::worktree::checkout::Options::default(),
+                    )?;
+                    return Ok(());
+                }
+            }
+        }
+
+        let (mut new_uncommitted_changes, changed_paths) =
             move_tree_between_workspaces(repo, worktree_id, old, new)?;
 
         // If the new tree and old tree are the same, then we don't need to do anything
@@ -109,19 +140,32 @@ pub fn update_uncommitted_changes_with_tree(
             }
         }
 
-        repo.checkout_index(
-            Some(&mut new_uncommitted_changes),
-            Some(
-                git2::build::CheckoutBuilder::new()
-                    .force()
-                    .remove_untracked(true)
-                    .conflict_style_diff3(true),
-            ),
-        )?;
+        let mut checkout = git2::build::CheckoutBuilder::new();
+        checkout
+            .force()
+            .remove_untracked(true)
+            .conflict_style_diff3(true);
+        if !always_checkout.unwrap_or(false) {
+            // Files the workspaces agree on are unchanged in the worktree, so leave them alone.
+            if changed_paths.is_empty() {
+                return Ok(());
+            }
+            checkout.disable_pathspec_match(true);
+            for path in changed_paths {
+                checkout.path(path);
+            }
+        }
+        repo.checkout_index(Some(&mut new_uncommitted_changes), Some(&mut checkout))?;
     } else {
-        let old_tree_id = merge_workspace(repo, old)?.to_gix();
-        let new_tree_id = merge_workspace(repo, new)?.to_gix();
         let gix_repo = ctx.clone_repo_for_merging()?;
+        let old_tree_id = match merge_workspace_fast(&gix_repo, &old)? {
+            Some(tree_id) => tree_id,
+            None => merge_workspace(repo, old)?.to_gix(),
+        };
+        let new_tree_id = match merge_workspace_fast(&gix_repo, &new)? {
+            Some(tree_id) => tree_id,
+            None => merge_workspace(repo, new)?.to_gix(),
+        };
         but_core::worktree::safe_checkout(
             old_tree_id,
             new_tree_id,
@@ -133,16 +177,119 @@ pub fn update_uncommitted_changes_with_tree(
 }
 
 /// Take the changes on top of one workspace and return what they would look
-/// like if they were on top of the new workspace.
+/// like if they were on top of the new workspace, along with the paths that differ
+/// between both workspaces.
 fn move_tree_between_workspaces(
     repo: &git2::Repository,
     tree: git2::Oid,
     old: WorkspaceState,
     new: WorkspaceState,
-) -> Result<git2::Index> {
+) -> Result<(git2::Index, BTreeSet<PathBuf>)> {
     let old_workspace = merge_workspace(repo, old)?;
     let new_workspace = merge_workspace(repo, new)?;
-    move_tree(repo, tree, old_workspace, new_workspace)
+    let changed_paths = repo.changed_paths(
+        &repo.find_tree(old_workspace)?,
+        &repo.find_tree(new_workspace)?,
+    )?;
+    Ok((
+        move_tree(repo, tree, old_workspace, new_workspace)?,
+        changed_paths,
+    ))
+}
+
+/// Like [`move_tree_between_workspaces()`], but merge with `gix` so only objects are written, and return the tree
+/// the worktree should be changed to with a single checkout.
+///
+/// Return `None` if the worktree has to be updated from a merged index instead, which is when the uncommitted changes
+/// conflict and conflict markers need to be written.
+fn move_tree_between_workspaces_fast(
+    repo: &gix::Repository,
+    tree: gix::ObjectId,
+    old: &WorkspaceState,
+    new: &WorkspaceState,
+) -> Result<Option<gix::ObjectId>> {
+    let (Some(old_workspace), Some(new_workspace)) = (
+        merge_workspace_fast(repo, old)?,
+        merge_workspace_fast(repo, new)?,
+    ) else {
+        return Ok(None);
+    };
+
+    let (options, conflict_kind) = repo.merge_options_fail_fast()?;
+    let mut merge = repo.merge_trees(
+        old_workspace,
+        tree,
+        new_workspace,
+        repo.default_merge_labels(),
+        options,
+    )?;
+    if merge.has_unresolved_conflicts(conflict_kind) {
+        return Ok(None);
+    }
+    Ok(Some(merge.tree.write()?.detach()))
+}
+
+/// Return `true` if Git may convert any of the worktree-relative `paths` when writing them to the worktree of `repo`,
+/// which is when `core.autocrlf` is enabled, or when attributes like `text`, `eol`, `working-tree-encoding` or `filter`
+/// are set for them. These have to be checked out from a merged index so the worktree is materialized like Git would.
+fn has_worktree_conversions(repo: &gix::Repository, paths: &BTreeSet<PathBuf>) -> Result<bool> {
+    let config = repo.config_snapshot();
+    if config.string("core.autocrlf").is_some() && config.boolean("core.autocrlf") != Some(false) {
+        return Ok(true);
+    }
+
+    let index = repo.index_or_empty()?;
+    let mut stack = repo.attributes_only(
+        &index,
+        gix::worktree::stack::state::attributes::Source::WorktreeThenIdMapping,
+    )?;
+    let mut outcome = stack.selected_attribute_matches([
+        "text",
+        "crlf",
+        "eol",
+        "ident",
+        "working-tree-encoding",
+        "filter",
+    ]);
+    for path in paths {
+        let path = gix::path::into_bstr(path);
+        let platform = stack.at_entry(&*path, None)?;
+        if platform.matching_attributes(&mut outcome)
+            && outcome.iter().any(|m| {
+                matches!(
+                    m.assignment.state,
+                    gix::attrs::StateRef::Set | gix::attrs::StateRef::Value(_)
+                )
+            })
+        {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Like [`merge_workspace()`], but merge with `gix` and return `None` if any of the heads conflict.
+fn merge_workspace_fast(
+    repo: &gix::Repository,
+    workspace: &WorkspaceState,
+) -> Result<Option<gix::ObjectId>> {
+    let base = workspace.base.to_gix();
+    let (options, conflict_kind) = repo.merge_options_fail_fast()?;
+    let mut output = base;
+    for head in &workspace.heads {
+        let mut merge = repo.merge_trees(
+            base,
+            output,
+            head.to_gix(),
+            repo.default_merge_labels(),
+            options.clone(),
+        )?;
+        if merge.has_unresolved_conflicts(conflict_kind) {
+            return Ok(None);
+        }
+        output = merge.tree.write()?.detach();
+    }
+    Ok(Some(output))
 }
 
 /// Cherry pick a tree from one base tree on to another, favoring the contents of the tree when conflicts occur
@@ -80,6 +80,10 @@ export type FeatureFlags = {
 	 * "modern" uses ignore-aware non-recursive watching.
 	 */
 	watchMode: string;
+	/**
+	 * How long the filesystem has to be quiet, in milliseconds, before changes are processed as one batch.
+	 */
+	watchDebounceMs: number;
 };
 
 export type Fetch = {
@@ -89,6 +93,126 @@ export type Fetch = {
 	autoFetchIntervalMinutes: number;
 };
 
+export type Diff = {
+	/**
+	 * The diff algorithm, one of `myers`, `minimal`, `patience` or `histogram`.
+	 * If empty, `diff.algorithm` of the Git configuration is used.
+	 */
+	algorithm: string;
+	/**
+	 * How whitespace changes are ignored, one of `none`, `atEol`, `change` or `all`.
+	 */
+	ignoreWhitespace: string;
+};
+
+export type WhitespaceOnlyHunks = {
+	/**
+	 * One of `show` to list them like any other hunk, `hide` to leave them out of the uncommitted changes,
+	 * or `assign` to assign new ones to the stack of `formatting_branch`.
+	 */
+	handling: string;
+	/**
+	 * The short name of the branch whose stack receives whitespace-only hunks if `handling` is `assign`.
+	 */
+	formattingBranch: string;
+};
+
+export type Oplog = {
+	/**
+	 * Snapshots older than this many days are pruned. `0` keeps snapshots of any age.
+	 */
+	maxAgeDays: number;
+	/**
+	 * Only this many of the most recent snapshots are kept. `0` keeps any amount of snapshots.
+	 */
+	maxSnapshots: number;
+	/**
+	 * The most recent snapshots are kept as long as the data they add takes less than this many megabytes.
+	 * `0` doesn't limit the size.
+	 */
+	maxSizeMb: number;
+	/**
+	 * Whether snapshots are pruned automatically after a snapshot was created, at most once per hour.
+	 * This keeps the ids of the remaining snapshots, so pruned snapshots are hidden, but still take space
+	 * until they are pruned explicitly.
+	 */
+	autoPrune: boolean;
+};
+
+export type Maintenance = {
+	/**
+	 * How thoroughly objects are packed.
+	 */
+	mode: MaintenanceMode;
+	/**
+	 * Maintenance runs at most once in this many hours, and at least one hour apart.
+	 */
+	intervalHours: number;
+	/**
+	 * Unreachable objects, like those of pruned snapshots, are deleted once they are older than this many days,
+	 * and at least one day old.
+	 */
+	pruneExpiryDays: number;
+};
+
+/**
+ * How thoroughly objects are packed during [maintenance](Maintenance).
+ */
+export type MaintenanceMode = 'off' | 'incremental' | 'aggressive';
+
+export type OperationTrace = {
+	/**
+	 * Whether mutating operations are recorded in `operations.jsonl` in the GitButler directory of the project.
+	 * The trace never leaves the machine, and only records what was called, not the data it was called with.
+	 */
+	enabled: boolean;
+	/**
+	 * The trace file is rotated once it is larger than this many kilobytes.
+	 */
+	maxFileSizeKb: number;
+	/**
+	 * Only this many rotated trace files are kept besides the current one.
+	 */
+	maxRotatedFiles: number;
+};
+
+export type Ai = {
+	/**
+	 * The provider generating commit messages, one of `openai`, `anthropic` or `ollama`.
+	 * If empty, `gitbutler.aiModelProvider` of the Git configuration is used.
+	 */
+	provider: string;
+	/**
+	 * The model to ask, like `gpt-5-mini`. If empty, the model configured for the provider is used.
+	 */
+	model: string;
+	/**
+	 * The base URL of an OpenAI-compatible endpoint, or the `host:port` of an ollama server.
+	 * If empty, the default endpoint of the provider is used.
+	 */
+	endpoint: string;
+	/**
+	 * How many commit messages to suggest at once.
+	 */
+	commitMessageCandidates: number;
+};
+
+export type ForgeHost = {
+	/**
+	 * The host of remote URLs served by the forge, like `github.example.com`.
+	 */
+	host: string;
+	/**
+	 * The kind of forge, one of `github`, `gitlab`, `bitbucket` or `azure`.
+	 */
+	forge: string;
+	/**
+	 * The base URL of the API of the forge, like `https://github.example.com/api/v3`.
+	 * If unset, the default location of the API on `host` for the kind of forge is used.
+	 */
+	apiUrl: string | null;
+};
+
 export type GitHubOAuthAppSettings = {
 	/**
 	 * Client ID for the GitHub OAuth application. Set this to use custom (non-GitButler) OAuth application.
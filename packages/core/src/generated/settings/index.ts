@@ -1,13 +1,20 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 import type {
+	Ai,
 	Claude,
+	Diff,
 	ExtraCsp,
 	FeatureFlags,
 	Fetch,
+	ForgeHost,
 	GitHubOAuthAppSettings,
+	Maintenance,
+	OperationTrace,
+	Oplog,
 	Reviews,
 	TelemetrySettings,
-	UiSettings
+	UiSettings,
+	WhitespaceOnlyHunks
 } from './appSettings';
 
 export type AppSettings = {
@@ -15,6 +22,14 @@ export type AppSettings = {
 	 * The amount of context lines to show in unified diffs, above and below the hunk.
 	 */
 	contextLines: number;
+	/**
+	 * How unified diffs are computed.
+	 */
+	diff: Diff;
+	/**
+	 * What to do with hunks that only change whitespace.
+	 */
+	whitespaceOnlyHunks: WhitespaceOnlyHunks;
 	/**
 	 * Whether the user has passed the onboarding flow.
 	 */
@@ -39,6 +54,26 @@ export type AppSettings = {
 	 * Settings related to fetching.
 	 */
 	fetch: Fetch;
+	/**
+	 * Settings related to the retention of snapshots in the operations log.
+	 */
+	oplog: Oplog;
+	/**
+	 * Settings related to the maintenance of the object database.
+	 */
+	maintenance: Maintenance;
+	/**
+	 * Settings related to the local trace of operations for debugging.
+	 */
+	operationTrace: OperationTrace;
+	/**
+	 * Settings related to AI generated commit messages.
+	 */
+	ai: Ai;
+	/**
+	 * Self-hosted forges, like GitHub Enterprise or GitLab instances, which can't be recognized by their host.
+	 */
+	forgeHosts: Array<ForgeHost>;
 	/**
 	 * Settings related to Claude Code.
 	 */